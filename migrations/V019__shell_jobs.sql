@@ -0,0 +1,16 @@
+-- Detached shell jobs (ExecuteShell { detach: true })
+CREATE TABLE IF NOT EXISTS shell_jobs (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    command TEXT NOT NULL,
+    cwd TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'running',
+    exit_code INTEGER,
+    stdout TEXT NOT NULL DEFAULT '',
+    stderr TEXT NOT NULL DEFAULT '',
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    duration_ms INTEGER
+);
+CREATE INDEX IF NOT EXISTS idx_shell_jobs_session_id ON shell_jobs(session_id);
+CREATE INDEX IF NOT EXISTS idx_shell_jobs_status ON shell_jobs(status);
@@ -0,0 +1,12 @@
+-- State-changing client actions, attributed to the authenticated caller
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    connection_id INTEGER NOT NULL,
+    identity TEXT NOT NULL,
+    action TEXT NOT NULL,
+    session_id TEXT,
+    details TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_audit_log_session_id ON audit_log(session_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log(created_at);
@@ -0,0 +1,18 @@
+-- Per-turn latency: time-to-first-token, wall-clock duration, tool time.
+-- provider/model are captured at turn end so analytics survive model switches.
+CREATE TABLE IF NOT EXISTS turn_metrics (
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    turn_id TEXT NOT NULL,
+    turn_seq INTEGER NOT NULL DEFAULT 0,
+    provider TEXT NOT NULL,
+    model TEXT,
+    time_to_first_token_ms INTEGER,
+    duration_ms INTEGER NOT NULL,
+    tool_duration_ms INTEGER NOT NULL DEFAULT 0,
+    aborted INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (session_id, turn_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_turn_metrics_provider_model ON turn_metrics(provider, model);
+CREATE INDEX IF NOT EXISTS idx_turn_metrics_created_at ON turn_metrics(created_at DESC);
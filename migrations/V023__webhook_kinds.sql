@@ -0,0 +1,4 @@
+-- Chat senders share the webhook table. `kind` is generic, slack, or discord;
+-- `channel` is the Slack channel when posting with a bot token.
+ALTER TABLE webhooks ADD COLUMN kind TEXT NOT NULL DEFAULT 'generic';
+ALTER TABLE webhooks ADD COLUMN channel TEXT;
@@ -0,0 +1,8 @@
+-- APNs device tokens registered by clients for push notifications
+CREATE TABLE IF NOT EXISTS push_devices (
+    device_token TEXT PRIMARY KEY,
+    environment TEXT NOT NULL DEFAULT 'production',
+    identity TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
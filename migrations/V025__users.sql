@@ -0,0 +1,14 @@
+-- Named users that auth tokens, sessions, and audited actions are attributed to
+CREATE TABLE IF NOT EXISTS users (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    disabled_at TEXT
+);
+
+ALTER TABLE auth_tokens ADD COLUMN user_id TEXT;
+ALTER TABLE sessions ADD COLUMN created_by TEXT;
+ALTER TABLE audit_log ADD COLUMN user_name TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_auth_tokens_user_id ON auth_tokens(user_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_user_name ON audit_log(user_name);
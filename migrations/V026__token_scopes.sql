@@ -0,0 +1,2 @@
+-- What an auth token may do: read, approve, or admin (existing tokens keep full access)
+ALTER TABLE auth_tokens ADD COLUMN scope TEXT NOT NULL DEFAULT 'admin';
@@ -0,0 +1,7 @@
+-- Who besides its creator can access a session; sessions without a row are private
+CREATE TABLE IF NOT EXISTS session_sharing (
+    session_id TEXT PRIMARY KEY,
+    visibility TEXT NOT NULL DEFAULT 'private',
+    shared_with TEXT NOT NULL DEFAULT '[]',
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
@@ -0,0 +1,3 @@
+-- Estimated spend per session (NULL until a priced usage update arrives) and per turn
+ALTER TABLE usage_session_state ADD COLUMN estimated_cost_usd REAL;
+ALTER TABLE turn_metrics ADD COLUMN estimated_cost_usd REAL;
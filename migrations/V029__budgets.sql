@@ -0,0 +1,16 @@
+-- Estimated spend per calendar month (UTC) and project, for [budgets] limits
+CREATE TABLE IF NOT EXISTS budget_spend (
+    month TEXT NOT NULL,
+    project_path TEXT NOT NULL,
+    spend_usd REAL NOT NULL DEFAULT 0,
+    PRIMARY KEY (month, project_path)
+);
+
+-- Budget alert thresholds already crossed, so each fires once per month
+CREATE TABLE IF NOT EXISTS budget_alerts (
+    scope TEXT NOT NULL,
+    month TEXT NOT NULL,
+    threshold REAL NOT NULL,
+    fired_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (scope, month, threshold)
+);
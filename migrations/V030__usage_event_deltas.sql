@@ -0,0 +1,7 @@
+-- What each usage snapshot added, for usage analytics; rows from before this
+-- migration keep zero deltas and no cost
+ALTER TABLE usage_events ADD COLUMN model TEXT;
+ALTER TABLE usage_events ADD COLUMN input_delta_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE usage_events ADD COLUMN output_delta_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE usage_events ADD COLUMN cached_delta_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE usage_events ADD COLUMN cost_usd REAL;
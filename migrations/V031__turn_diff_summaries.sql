@@ -0,0 +1,2 @@
+-- One-line AI summary of what each turn changed, shown in the turn timeline.
+ALTER TABLE turn_diffs ADD COLUMN summary TEXT;
@@ -0,0 +1,14 @@
+-- Maps semantic search vectors back to what they embed. The vectors live in
+-- embedding_vectors, a sqlite-vec table created on first use and sized to
+-- the embedding model, keyed by embedding_items.id.
+CREATE TABLE IF NOT EXISTS embedding_items (
+    id INTEGER PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    -- '' for the session summary
+    message_id TEXT NOT NULL DEFAULT '',
+    -- The summary text embedded, to notice when it changes
+    summary TEXT,
+    model TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (session_id, message_id)
+);
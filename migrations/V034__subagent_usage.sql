@@ -0,0 +1,7 @@
+-- Token usage and estimated cost parsed from each subagent's transcript when it
+-- stops; the cost is also added to the parent session's usage_session_state
+ALTER TABLE subagents ADD COLUMN model TEXT;
+ALTER TABLE subagents ADD COLUMN input_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE subagents ADD COLUMN output_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE subagents ADD COLUMN cached_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE subagents ADD COLUMN estimated_cost_usd REAL;
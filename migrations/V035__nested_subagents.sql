@@ -0,0 +1,4 @@
+-- Subagents spawned by another subagent's Task call point at it; NULL for
+-- subagents of the session itself
+ALTER TABLE subagents ADD COLUMN parent_subagent_id TEXT;
+CREATE INDEX IF NOT EXISTS idx_subagents_parent ON subagents(parent_subagent_id);
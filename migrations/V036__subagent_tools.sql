@@ -0,0 +1,15 @@
+-- Tool calls parsed from subagent transcripts, filled in incrementally as the
+-- transcript grows so GetSubagentTools doesn't re-read the whole file
+CREATE TABLE IF NOT EXISTS subagent_tools (
+    subagent_id TEXT NOT NULL,
+    tool_use_id TEXT NOT NULL,
+    id TEXT NOT NULL,
+    tool_name TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    output TEXT,
+    spawned_subagent_id TEXT,
+    PRIMARY KEY (subagent_id, tool_use_id)
+);
+
+-- Bytes of the transcript already parsed into subagent_tools
+ALTER TABLE subagents ADD COLUMN tools_parsed_bytes INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,17 @@
+-- Indexes for the startup restore and cleanup queries
+
+-- Restore picks active sessions, sessions ended by a server shutdown, and
+-- sessions active in the last week; each term gets an index so the OR is
+-- answered from the indexes instead of a scan of every session
+CREATE INDEX IF NOT EXISTS idx_sessions_status_end_reason ON sessions(status, end_reason);
+CREATE INDEX IF NOT EXISTS idx_sessions_activity
+    ON sessions(datetime(COALESCE(last_activity_at, started_at)));
+
+-- Tool messages left in progress by a crash; only those rows are indexed
+CREATE INDEX IF NOT EXISTS idx_messages_in_progress
+    ON messages(session_id) WHERE is_in_progress = 1;
+
+-- Covered by the indexes above or by the table's primary key
+DROP INDEX IF EXISTS idx_sessions_status;
+DROP INDEX IF EXISTS idx_messages_session;
+DROP INDEX IF EXISTS idx_turn_diffs_session;
@@ -0,0 +1,15 @@
+-- MCP servers added to a session from OrbitDock, on top of the ones in the
+-- provider's own config
+CREATE TABLE IF NOT EXISTS session_mcp_servers (
+    session_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    command TEXT NOT NULL,
+    -- JSON array of arguments
+    args TEXT NOT NULL DEFAULT '[]',
+    -- JSON object of env vars, encrypted (`enc:` prefix); NULL when there are none
+    env TEXT,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (session_id, name)
+);
@@ -0,0 +1,9 @@
+-- Remote OrbitDock servers whose sessions this server lists and proxies.
+-- Tokens are stored encrypted.
+CREATE TABLE IF NOT EXISTS peers (
+    name TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    token TEXT,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
@@ -0,0 +1,12 @@
+-- GitHub issue a session was started from
+CREATE TABLE IF NOT EXISTS session_issues (
+    session_id TEXT PRIMARY KEY,
+    -- `owner/name`
+    repo TEXT NOT NULL,
+    number INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    url TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_issues_issue ON session_issues(repo, number);
@@ -0,0 +1,13 @@
+-- Linear or Jira ticket a session is linked to
+CREATE TABLE IF NOT EXISTS session_tickets (
+    session_id TEXT PRIMARY KEY,
+    -- `linear` or `jira`
+    tracker TEXT NOT NULL,
+    ticket_id TEXT NOT NULL,
+    url TEXT,
+    -- Comment on the ticket when the session ends
+    sync INTEGER NOT NULL DEFAULT 0,
+    -- When that comment was posted, so it is posted once
+    synced_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
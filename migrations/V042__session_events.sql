@@ -0,0 +1,13 @@
+-- Every message a session broadcast, in order, for `ReplaySession`
+CREATE TABLE IF NOT EXISTS session_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    -- The session's broadcast revision; restarts with the server
+    revision INTEGER NOT NULL,
+    -- Unix milliseconds when it was broadcast
+    recorded_at INTEGER NOT NULL,
+    -- The `ServerMessage` JSON as sent to subscribers
+    payload TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_events_session ON session_events(session_id, id);
@@ -0,0 +1,16 @@
+-- The session's structured plan (`SessionPlan` JSON), alongside `current_plan`
+ALTER TABLE sessions ADD COLUMN plan_steps TEXT;
+
+-- Every plan step that appeared or changed status, in order
+CREATE TABLE IF NOT EXISTS plan_step_transitions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    step_index INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    -- NULL when the step is new at this position
+    from_status TEXT,
+    to_status TEXT NOT NULL,
+    changed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_plan_step_transitions_session ON plan_step_transitions(session_id, id);
@@ -0,0 +1,21 @@
+-- One row per prompt-to-completion turn, for `GetTurns`
+CREATE TABLE IF NOT EXISTS turns (
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    turn_id TEXT NOT NULL,
+    turn_seq INTEGER NOT NULL DEFAULT 0,
+    started_at TEXT NOT NULL,
+    -- NULL while the turn is running
+    ended_at TEXT,
+    aborted INTEGER NOT NULL DEFAULT 0,
+    -- Message sequences the turn covers, inclusive: from just after the
+    -- previous turn (so its prompt is included) through the last at turn end
+    first_message_seq INTEGER NOT NULL DEFAULT 0,
+    last_message_seq INTEGER,
+    -- Token usage snapshot at turn end
+    input_tokens INTEGER,
+    output_tokens INTEGER,
+    cached_tokens INTEGER,
+    context_window INTEGER,
+    snapshot_kind TEXT,
+    PRIMARY KEY (session_id, turn_id)
+);
@@ -0,0 +1,10 @@
+-- Tokens each turn spent, summed from the deltas of its usage updates.
+-- Turns recorded before this migration keep NULL (unknown).
+ALTER TABLE turns ADD COLUMN input_delta_tokens INTEGER;
+ALTER TABLE turns ADD COLUMN output_delta_tokens INTEGER;
+ALTER TABLE turns ADD COLUMN cached_delta_tokens INTEGER;
+
+-- usage_turns.input_delta_tokens was the input growth since the previous
+-- diff snapshot; it now holds the turn's exact delta alongside these
+ALTER TABLE usage_turns ADD COLUMN output_delta_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE usage_turns ADD COLUMN cached_delta_tokens INTEGER NOT NULL DEFAULT 0;
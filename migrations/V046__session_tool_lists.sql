@@ -0,0 +1,4 @@
+-- Claude tool allow/deny lists, as JSON arrays of tool names, so they
+-- survive resume and can be edited while the session runs. NULL = none.
+ALTER TABLE sessions ADD COLUMN allowed_tools TEXT;
+ALTER TABLE sessions ADD COLUMN disallowed_tools TEXT;
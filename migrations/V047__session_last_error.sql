@@ -0,0 +1,3 @@
+-- A session's latest structured error (category, provider code, suggested
+-- recovery) as JSON, kept until its next turn starts. NULL = none.
+ALTER TABLE sessions ADD COLUMN last_error TEXT;
@@ -0,0 +1,12 @@
+-- Connector lifecycle milestones per session (spawned, resumed, timed out,
+-- thread id changed, ...) for `get_session_events` and bug reports
+CREATE TABLE IF NOT EXISTS session_diagnostics (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    kind TEXT NOT NULL,
+    -- Kind-specific JSON; NULL = none
+    details TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_diagnostics_session ON session_diagnostics(session_id, id);
@@ -1,4 +1,4 @@
-use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(
@@ -33,6 +33,7 @@ pub enum Command {
     Health,
 
     /// Manage sessions
+    #[command(visible_alias = "sessions")]
     Session {
         #[command(subcommand)]
         action: SessionAction,
@@ -98,6 +99,18 @@ pub enum Command {
         action: ShellAction,
     },
 
+    /// Send a prompt to a project's session, creating one if needed
+    Send(SendArgs),
+
+    /// Stream server events for all sessions or one session
+    Watch(WatchArgs),
+
+    /// Interactive terminal dashboard
+    Tui,
+
+    /// List pending approvals across sessions, or decide one
+    Approve(ApproveArgs),
+
     /// Generate shell completions
     Completions {
         /// Shell to generate completions for
@@ -105,6 +118,79 @@ pub enum Command {
     },
 }
 
+// ── Send ─────────────────────────────────────────────────────
+
+#[derive(Clone, Debug, Args)]
+pub struct SendArgs {
+    /// Prompt to send (use "-" to read from stdin)
+    #[arg(allow_hyphen_values = true)]
+    pub prompt: String,
+
+    /// Project directory (defaults to current directory)
+    #[arg(long)]
+    pub project: Option<String>,
+
+    /// Provider (claude or codex)
+    #[arg(long, short = 'p')]
+    pub provider: ProviderFilter,
+
+    /// Model to use
+    #[arg(long)]
+    pub model: Option<String>,
+
+    /// Reasoning effort
+    #[arg(long)]
+    pub effort: Option<Effort>,
+
+    /// Always start a new session instead of reusing an active one
+    #[arg(long)]
+    pub new: bool,
+
+    /// Start a session even if a budget that asks for confirmation is used up
+    #[arg(long)]
+    pub confirm_over_budget: bool,
+
+    /// Wait for the turn to finish and print the assistant's reply
+    #[arg(long, short = 'w')]
+    pub wait: bool,
+}
+
+// ── Approve ──────────────────────────────────────────────────
+
+#[derive(Clone, Debug, Args)]
+pub struct ApproveArgs {
+    /// Index from the listing, request ID, or session ID (omit to list)
+    pub target: Option<String>,
+
+    /// Decision
+    #[arg(long, short = 'd', default_value = "approved")]
+    pub decision: ApprovalDecision,
+
+    /// Message (for denied decisions)
+    #[arg(long)]
+    pub message: Option<String>,
+
+    /// Decide without showing the request and asking first
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+// ── Watch ────────────────────────────────────────────────────
+
+#[derive(Clone, Debug, Args)]
+pub struct WatchArgs {
+    /// Session ID (omit to watch the session list)
+    pub session_id: Option<String>,
+
+    /// Filter by event type (e.g. session_created, session_delta)
+    #[arg(long, short = 'f')]
+    pub filter: Vec<String>,
+
+    /// Timeout in seconds (omit for no timeout)
+    #[arg(long)]
+    pub timeout: Option<u64>,
+}
+
 // ── Session ──────────────────────────────────────────────────
 
 #[derive(Clone, Debug, Subcommand)]
@@ -122,9 +208,14 @@ pub enum SessionAction {
         /// Filter by project path
         #[arg(long)]
         project: Option<String>,
+
+        /// Filter by topic tag
+        #[arg(long)]
+        tag: Option<String>,
     },
 
     /// Show session details
+    #[command(visible_alias = "show")]
     Get {
         /// Session ID
         session_id: String,
@@ -159,6 +250,27 @@ pub enum SessionAction {
         /// System prompt
         #[arg(long)]
         system_prompt: Option<String>,
+
+        /// Start even if a budget that asks for confirmation is used up
+        #[arg(long)]
+        confirm_over_budget: bool,
+    },
+
+    /// Start a session on a GitHub issue, with the issue as its first prompt
+    FromIssue {
+        /// Repository as owner/name
+        repo: String,
+
+        /// Issue number
+        number: u64,
+
+        /// Working directory (defaults to the server's [github.repos] checkout)
+        #[arg(long)]
+        cwd: Option<String>,
+
+        /// Provider (defaults to the server's [github] provider)
+        #[arg(long, short = 'p')]
+        provider: Option<ProviderFilter>,
     },
 
     /// Send a message to a session (reads from stdin if content is "-")
@@ -297,6 +409,41 @@ pub enum SessionAction {
         name: String,
     },
 
+    /// Replace a session's topic tags (none clears them)
+    Tag {
+        /// Session ID
+        session_id: String,
+
+        /// Tags, e.g. auth tests
+        tags: Vec<String>,
+    },
+
+    /// Link a session to a Linear or Jira ticket
+    Ticket {
+        /// Session ID
+        session_id: String,
+
+        /// Ticket tracker
+        #[arg(required_unless_present = "clear")]
+        tracker: Option<TicketTrackerArg>,
+
+        /// Ticket ID, e.g. ENG-123
+        #[arg(required_unless_present = "clear")]
+        id: Option<String>,
+
+        /// Ticket URL (Jira URLs default from the server's jira_url)
+        #[arg(long)]
+        url: Option<String>,
+
+        /// Comment on the ticket when the session ends
+        #[arg(long)]
+        sync: bool,
+
+        /// Unlink the session's ticket
+        #[arg(long, conflicts_with_all = ["tracker", "id", "url", "sync"])]
+        clear: bool,
+    },
+
     /// Resume a session
     Resume {
         /// Session ID
@@ -310,6 +457,12 @@ pub enum ProviderFilter {
     Codex,
 }
 
+#[derive(Clone, Debug, ValueEnum)]
+pub enum TicketTrackerArg {
+    Linear,
+    Jira,
+}
+
 #[derive(Clone, Debug, ValueEnum)]
 pub enum StatusFilter {
     Active,
@@ -542,6 +695,12 @@ pub enum ServerAction {
         #[arg(long, conflicts_with = "primary")]
         secondary: bool,
     },
+
+    /// Gracefully shut down the server (requires an admin token)
+    Shutdown,
+
+    /// Re-read the server config file (requires an admin token)
+    ReloadConfig,
 }
 
 // ── Codex ────────────────────────────────────────────────────
@@ -625,6 +784,46 @@ pub enum McpAction {
         /// Session ID
         session_id: String,
     },
+
+    /// List the MCP servers added to a session
+    Servers {
+        /// Session ID
+        session_id: String,
+    },
+
+    /// Add an MCP server to a session, or replace the one with this name
+    Add {
+        /// Session ID
+        session_id: String,
+
+        /// Server name (letters, digits, '-' and '_')
+        name: String,
+
+        /// Command that starts the server
+        #[arg(long)]
+        command: String,
+
+        /// Argument to pass to the command (repeatable)
+        #[arg(long = "arg", allow_hyphen_values = true)]
+        args: Vec<String>,
+
+        /// Environment variable as KEY=VALUE (repeatable)
+        #[arg(long = "env")]
+        env: Vec<String>,
+
+        /// Save the server without starting it
+        #[arg(long)]
+        disabled: bool,
+    },
+
+    /// Remove an MCP server from a session
+    Remove {
+        /// Session ID
+        session_id: String,
+
+        /// Server name
+        name: String,
+    },
 }
 
 // ── Filesystem ───────────────────────────────────────────────
@@ -30,6 +30,13 @@ impl WsClient {
             .into_client_request()
             .context("Failed to build WebSocket request")?;
 
+        request.headers_mut().insert(
+            "X-OrbitDock-Client",
+            concat!("orbitdock-cli/", env!("CARGO_PKG_VERSION"))
+                .parse()
+                .context("Invalid client header")?,
+        );
+
         if let Some(token) = &config.token {
             request.headers_mut().insert(
                 "Authorization",
@@ -93,6 +100,7 @@ impl WsClient {
             session_id: session_id.to_string(),
             since_revision: None,
             include_snapshot: true,
+            snapshot: None,
         })
         .await?;
 
@@ -0,0 +1,256 @@
+use std::io::{IsTerminal, Write};
+
+use orbitdock_protocol::{ApprovalRequest, SessionState, SessionSummary};
+use serde::Deserialize;
+
+use super::session;
+use crate::cli::{ApprovalDecision, ApproveArgs};
+use crate::client::config::ClientConfig;
+use crate::client::rest::RestClient;
+use crate::error::{CliError, EXIT_CLIENT_ERROR, EXIT_SUCCESS};
+use crate::output::{human, Output};
+
+#[derive(Debug, Deserialize)]
+struct SessionsResponse {
+    sessions: Vec<SessionSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionResponse {
+    session: SessionState,
+}
+
+pub async fn run(
+    args: &ApproveArgs,
+    rest: &RestClient,
+    output: &Output,
+    config: &ClientConfig,
+) -> i32 {
+    let pending = match rest
+        .get::<SessionsResponse>("/api/sessions")
+        .await
+        .into_result()
+    {
+        Ok(resp) => pending_approvals(resp.sessions),
+        Err((code, err)) => {
+            output.print_error(&err);
+            return code;
+        }
+    };
+
+    let Some(target) = &args.target else {
+        if output.json {
+            let approvals: Vec<_> = pending
+                .iter()
+                .enumerate()
+                .map(|(index, s)| {
+                    serde_json::json!({
+                        "index": index + 1,
+                        "session_id": s.id,
+                        "request_id": s.pending_approval_id,
+                        "project_path": s.project_path,
+                        "tool_name": s.pending_tool_name,
+                        "tool_input": s.pending_tool_input,
+                        "question": s.pending_question,
+                    })
+                })
+                .collect();
+            output.print_json(&serde_json::json!({ "approvals": approvals }));
+        } else {
+            human::pending_approvals_table(&pending);
+        }
+        return EXIT_SUCCESS;
+    };
+
+    let session = match resolve_target(&pending, target) {
+        Ok(s) => s,
+        Err(err) => {
+            output.print_error(&err);
+            return EXIT_CLIENT_ERROR;
+        }
+    };
+    if session.pending_question.is_some() {
+        output.print_error(&CliError::new(
+            "question_pending",
+            format!(
+                "Session {} is asking a question. Reply with `orbitdock session answer`.",
+                session.id
+            ),
+        ));
+        return EXIT_CLIENT_ERROR;
+    }
+    let session_id = session.id.clone();
+    let request_id = session.pending_approval_id.clone().unwrap_or_default();
+
+    let interactive =
+        !output.json && std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    if interactive && !args.yes {
+        let path = format!("/api/sessions/{session_id}");
+        let request = rest
+            .get::<SessionResponse>(&path)
+            .await
+            .into_result()
+            .ok()
+            .and_then(|resp| resp.session.pending_approval);
+        page(&describe(session, request.as_ref()));
+
+        if !confirm(decision_prompt(&args.decision)) {
+            println!("Cancelled.");
+            return EXIT_SUCCESS;
+        }
+    }
+
+    session::approve_tool(
+        config,
+        output,
+        &session_id,
+        &args.decision,
+        args.message.as_deref(),
+        Some(&request_id),
+    )
+    .await
+}
+
+/// Sessions waiting on a decision, oldest first so the numbering is stable
+/// while they wait.
+fn pending_approvals(sessions: Vec<SessionSummary>) -> Vec<SessionSummary> {
+    let mut pending: Vec<SessionSummary> = sessions
+        .into_iter()
+        .filter(|s| s.has_pending_approval && s.pending_approval_id.is_some())
+        .collect();
+    pending.sort_by(|a, b| {
+        a.last_activity_at
+            .cmp(&b.last_activity_at)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    pending
+}
+
+/// A 1-based index, an exact request or session ID, or a unique session ID prefix.
+fn resolve_target<'a>(
+    pending: &'a [SessionSummary],
+    target: &str,
+) -> Result<&'a SessionSummary, CliError> {
+    if let Ok(index) = target.parse::<usize>() {
+        return index
+            .checked_sub(1)
+            .and_then(|i| pending.get(i))
+            .ok_or_else(|| {
+                CliError::new(
+                    "not_found",
+                    format!("No pending approval #{index} ({} pending)", pending.len()),
+                )
+            });
+    }
+
+    if let Some(exact) = pending
+        .iter()
+        .find(|s| s.id == target || s.pending_approval_id.as_deref() == Some(target))
+    {
+        return Ok(exact);
+    }
+
+    let mut matches = pending.iter().filter(|s| s.id.starts_with(target));
+    match (matches.next(), matches.next()) {
+        (Some(found), None) => Ok(found),
+        (Some(_), Some(_)) => Err(CliError::new(
+            "ambiguous",
+            format!("'{target}' matches more than one session"),
+        )),
+        (None, _) => Err(CliError::new(
+            "not_found",
+            format!("No pending approval matches '{target}'"),
+        )),
+    }
+}
+
+fn describe(session: &SessionSummary, request: Option<&ApprovalRequest>) -> String {
+    let mut text = String::new();
+    let tool = request
+        .and_then(|r| r.tool_name.as_deref())
+        .or(session.pending_tool_name.as_deref())
+        .unwrap_or("unknown");
+    text.push_str(&format!("Tool:     {tool}\n"));
+    text.push_str(&format!("Session:  {}\n", session.id));
+    text.push_str(&format!("Project:  {}\n", session.project_path));
+
+    match request {
+        Some(request) => {
+            if let Some(command) = &request.command {
+                text.push_str(&format!("\n$ {command}\n"));
+            }
+            if let Some(file_path) = &request.file_path {
+                text.push_str(&format!("\nFile: {file_path}\n"));
+            }
+            if let Some(diff) = &request.diff {
+                text.push_str(&format!("\n{diff}\n"));
+            }
+            if request.command.is_none() && request.diff.is_none() {
+                if let Some(input) = &request.tool_input {
+                    text.push_str(&format!("\n{}\n", pretty_json(input)));
+                }
+            }
+        }
+        None => {
+            if let Some(input) = &session.pending_tool_input {
+                text.push_str(&format!("\n{}\n", pretty_json(input)));
+            }
+        }
+    }
+    text
+}
+
+fn pretty_json(raw: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(raw)
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .unwrap_or_else(|_| raw.to_string())
+}
+
+/// Print directly when it fits on screen, otherwise hand it to `$PAGER`.
+fn page(text: &str) {
+    let (rows, _) = console::Term::stdout().size();
+    if text.lines().count() + 2 < rows as usize {
+        print!("{text}");
+        return;
+    }
+
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+    let mut parts = pager.split_whitespace();
+    let Some(program) = parts.next() else {
+        print!("{text}");
+        return;
+    };
+    let spawned = std::process::Command::new(program)
+        .args(parts)
+        .stdin(std::process::Stdio::piped())
+        .spawn();
+    match spawned {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            let _ = child.wait();
+        }
+        Err(_) => print!("{text}"),
+    }
+}
+
+fn decision_prompt(decision: &ApprovalDecision) -> &'static str {
+    match decision {
+        ApprovalDecision::Approved => "Approve",
+        ApprovalDecision::ApprovedForSession => "Approve for the rest of this session",
+        ApprovalDecision::ApprovedAlways => "Always approve",
+        ApprovalDecision::Denied => "Deny",
+        ApprovalDecision::Abort => "Deny and stop the turn",
+    }
+}
+
+fn confirm(prompt: &str) -> bool {
+    print!("\n{prompt}? [y/N] ");
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
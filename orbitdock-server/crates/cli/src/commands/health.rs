@@ -9,6 +9,9 @@ pub(super) struct HealthResponse {
     pub status: String,
     #[serde(default)]
     pub version: Option<String>,
+    /// Subsystem checks (database, persistence, watcher, ...) passed through to `--json`.
+    #[serde(flatten)]
+    pub checks: serde_json::Map<String, serde_json::Value>,
 }
 
 pub async fn run(rest: &RestClient, output: &Output) -> i32 {
@@ -20,9 +23,10 @@ pub async fn run(rest: &RestClient, output: &Output) -> i32 {
                 let version = health.version.as_deref().unwrap_or("unknown");
                 let style = console::Style::new().green().bold();
                 println!(
-                    "{} Server is running (version: {})",
+                    "{} Server is running (version: {}, status: {})",
                     style.apply_to("●"),
-                    version
+                    version,
+                    health.status
                 );
             }
             EXIT_SUCCESS
@@ -1,11 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use orbitdock_protocol::{McpAuthStatus, McpResource, McpResourceTemplate, McpTool};
+use orbitdock_protocol::{
+    McpAuthStatus, McpResource, McpResourceTemplate, McpTool, SessionMcpServer,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::cli::McpAction;
 use crate::client::rest::RestClient;
-use crate::error::EXIT_SUCCESS;
+use crate::error::{CliError, EXIT_CLIENT_ERROR, EXIT_SUCCESS};
 use crate::output::Output;
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -17,6 +19,12 @@ struct McpToolsResponse {
     auth_statuses: HashMap<String, McpAuthStatus>,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+struct SessionMcpServersResponse {
+    session_id: String,
+    servers: Vec<SessionMcpServer>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct AcceptedResponse {
     accepted: bool,
@@ -26,6 +34,32 @@ pub async fn run(action: &McpAction, rest: &RestClient, output: &Output) -> i32
     match action {
         McpAction::Tools { session_id } => tools(rest, output, session_id).await,
         McpAction::Refresh { session_id } => refresh(rest, output, session_id).await,
+        McpAction::Servers { session_id } => servers(rest, output, session_id).await,
+        McpAction::Add {
+            session_id,
+            name,
+            command,
+            args,
+            env,
+            disabled,
+        } => {
+            let env = match parse_env(env) {
+                Ok(env) => env,
+                Err(e) => {
+                    output.print_error(&e);
+                    return EXIT_CLIENT_ERROR;
+                }
+            };
+            let server = SessionMcpServer {
+                name: name.clone(),
+                command: command.clone(),
+                args: args.clone(),
+                env,
+                enabled: !disabled,
+            };
+            add(rest, output, session_id, &server).await
+        }
+        McpAction::Remove { session_id, name } => remove(rest, output, session_id, name).await,
     }
 }
 
@@ -81,3 +115,99 @@ async fn refresh(rest: &RestClient, output: &Output, session_id: &str) -> i32 {
         }
     }
 }
+
+async fn servers(rest: &RestClient, output: &Output, session_id: &str) -> i32 {
+    let path = format!("/api/sessions/{session_id}/mcp/servers");
+    match rest
+        .get::<SessionMcpServersResponse>(&path)
+        .await
+        .into_result()
+    {
+        Ok(resp) => {
+            print_servers(output, &resp);
+            EXIT_SUCCESS
+        }
+        Err((code, err)) => {
+            output.print_error(&err);
+            code
+        }
+    }
+}
+
+async fn add(
+    rest: &RestClient,
+    output: &Output,
+    session_id: &str,
+    server: &SessionMcpServer,
+) -> i32 {
+    let path = format!("/api/sessions/{session_id}/mcp/servers");
+    match rest
+        .put_json::<_, SessionMcpServersResponse>(&path, server)
+        .await
+        .into_result()
+    {
+        Ok(resp) => {
+            print_servers(output, &resp);
+            EXIT_SUCCESS
+        }
+        Err((code, err)) => {
+            output.print_error(&err);
+            code
+        }
+    }
+}
+
+async fn remove(rest: &RestClient, output: &Output, session_id: &str, name: &str) -> i32 {
+    let path = format!("/api/sessions/{session_id}/mcp/servers/{name}");
+    match rest
+        .delete::<SessionMcpServersResponse>(&path)
+        .await
+        .into_result()
+    {
+        Ok(resp) => {
+            print_servers(output, &resp);
+            EXIT_SUCCESS
+        }
+        Err((code, err)) => {
+            output.print_error(&err);
+            code
+        }
+    }
+}
+
+fn print_servers(output: &Output, resp: &SessionMcpServersResponse) {
+    if output.json {
+        output.print_json(resp);
+        return;
+    }
+    if resp.servers.is_empty() {
+        println!("No MCP servers added to this session.");
+        return;
+    }
+    for server in &resp.servers {
+        let state = if server.enabled { "" } else { " (disabled)" };
+        let args = if server.args.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", server.args.join(" "))
+        };
+        println!("  {}{state}: {}{args}", server.name, server.command);
+        for key in server.env.keys() {
+            println!("      {key}={}", SessionMcpServer::REDACTED);
+        }
+    }
+}
+
+/// `KEY=VALUE` pairs from `--env`.
+fn parse_env(pairs: &[String]) -> Result<BTreeMap<String, String>, CliError> {
+    pairs
+        .iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+            _ => Err(CliError::new(
+                "invalid_argument",
+                format!("Expected --env KEY=VALUE, got '{pair}'"),
+            )),
+        })
+        .collect()
+}
@@ -1,14 +1,17 @@
 mod approval;
+mod approve;
 mod codex;
 mod fs;
 mod health;
 mod mcp;
 mod model;
 mod review;
+mod send;
 mod server;
 mod session;
 mod shell;
 mod usage;
+mod watch;
 mod worktree;
 
 use crate::cli::Command;
@@ -29,12 +32,16 @@ pub async fn dispatch(command: &Command, config: &ClientConfig) -> i32 {
         Command::Review { action } => review::run(action, &rest, &output).await,
         Command::Model { action } => model::run(action, &rest, &output).await,
         Command::Usage { action } => usage::run(action, &rest, &output).await,
-        Command::Server { action } => server::run(action, &rest, &output).await,
+        Command::Server { action } => server::run(action, &rest, &output, config).await,
         Command::Codex { action } => codex::run(action, &rest, &output).await,
         Command::Worktree { action } => worktree::run(action, &rest, &output).await,
         Command::Mcp { action } => mcp::run(action, &rest, &output).await,
         Command::Fs { action } => fs::run(action, &rest, &output).await,
         Command::Shell { action } => shell::run(action, &output, config).await,
+        Command::Send(args) => send::run(args, &rest, &output, config).await,
+        Command::Watch(args) => watch::run(args, &output, config).await,
+        Command::Tui => crate::tui::run(&output, config).await,
+        Command::Approve(args) => approve::run(args, &rest, &output, config).await,
         Command::Completions { shell } => {
             crate::cli::generate_completions(*shell);
             crate::error::EXIT_SUCCESS
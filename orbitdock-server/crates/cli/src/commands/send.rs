@@ -0,0 +1,334 @@
+use std::time::Duration;
+
+use orbitdock_protocol::{
+    ClaudeIntegrationMode, ClientMessage, CodexIntegrationMode, MessageType, Provider,
+    ServerMessage, SessionStatus, SessionSummary, WorkStatus,
+};
+use serde::Deserialize;
+
+use super::session::work_status_str;
+use crate::cli::{resolve_stdin, ProviderFilter, SendArgs};
+use crate::client::config::ClientConfig;
+use crate::client::rest::RestClient;
+use crate::client::ws::WsClient;
+use crate::error::{
+    CliError, EXIT_CLIENT_ERROR, EXIT_CONNECTION_ERROR, EXIT_NEEDS_INPUT, EXIT_SERVER_ERROR,
+    EXIT_SUCCESS,
+};
+use crate::output::Output;
+
+#[derive(Debug, Deserialize)]
+struct SessionsResponse {
+    sessions: Vec<SessionSummary>,
+}
+
+pub async fn run(
+    args: &SendArgs,
+    rest: &RestClient,
+    output: &Output,
+    config: &ClientConfig,
+) -> i32 {
+    let prompt = match resolve_stdin(&args.prompt) {
+        Ok(p) => p,
+        Err(e) => {
+            output.print_error(&CliError::new("stdin_error", e.to_string()));
+            return EXIT_CLIENT_ERROR;
+        }
+    };
+    if prompt.trim().is_empty() {
+        output.print_error(&CliError::new("empty_prompt", "Prompt is empty"));
+        return EXIT_CLIENT_ERROR;
+    }
+
+    let project = resolve_project(args.project.as_deref());
+    let provider = match args.provider {
+        ProviderFilter::Claude => Provider::Claude,
+        ProviderFilter::Codex => Provider::Codex,
+    };
+
+    let reusable = if args.new {
+        None
+    } else {
+        match rest
+            .get::<SessionsResponse>("/api/sessions")
+            .await
+            .into_result()
+        {
+            Ok(resp) => find_reusable(&resp.sessions, &project, provider),
+            Err((code, err)) => {
+                output.print_error(&err);
+                return code;
+            }
+        }
+    };
+
+    let mut ws = match WsClient::connect(config).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            output.print_error(&CliError::connection(e.to_string()));
+            return EXIT_CONNECTION_ERROR;
+        }
+    };
+
+    let (session_id, created) = match reusable {
+        Some(session_id) => {
+            if let Err(e) = ws.subscribe_session(&session_id).await {
+                output.print_error(&CliError::new("subscribe_error", e.to_string()));
+                return EXIT_SERVER_ERROR;
+            }
+            (session_id, false)
+        }
+        None => match create_session(&mut ws, args, provider, &project).await {
+            Ok(session_id) => (session_id, true),
+            Err((code, err)) => {
+                output.print_error(&err);
+                return code;
+            }
+        },
+    };
+
+    if let Err(e) = ws
+        .send(&ClientMessage::SendMessage {
+            session_id: session_id.clone(),
+            content: prompt,
+            model: args.model.clone(),
+            effort: args.effort.as_ref().map(|e| e.as_str().to_string()),
+            skills: vec![],
+            images: vec![],
+            mentions: vec![],
+            attachment_ids: vec![],
+        })
+        .await
+    {
+        output.print_error(&CliError::connection(e.to_string()));
+        return EXIT_CONNECTION_ERROR;
+    }
+
+    if !args.wait {
+        if output.json {
+            output.print_json(&serde_json::json!({
+                "sent": true,
+                "session_id": session_id,
+                "created": created,
+            }));
+        } else {
+            println!("Sent to session {session_id}");
+        }
+        return EXIT_SUCCESS;
+    }
+
+    wait_for_turn(&mut ws, output, &session_id, created).await
+}
+
+fn resolve_project(project: Option<&str>) -> String {
+    let path = match project {
+        Some(p) => std::path::PathBuf::from(p),
+        None => std::env::current_dir().unwrap_or_else(|_| ".".into()),
+    };
+    std::fs::canonicalize(&path)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// The most recently active idle session OrbitDock drives for this project.
+fn find_reusable(sessions: &[SessionSummary], project: &str, provider: Provider) -> Option<String> {
+    sessions
+        .iter()
+        .filter(|s| {
+            s.provider == provider
+                && s.project_path == project
+                && s.status == SessionStatus::Active
+                && matches!(s.work_status, WorkStatus::Waiting | WorkStatus::Reply)
+                && match provider {
+                    Provider::Claude => {
+                        s.claude_integration_mode == Some(ClaudeIntegrationMode::Direct)
+                    }
+                    Provider::Codex => {
+                        s.codex_integration_mode == Some(CodexIntegrationMode::Direct)
+                    }
+                }
+        })
+        .max_by(|a, b| a.last_activity_at.cmp(&b.last_activity_at))
+        .map(|s| s.id.clone())
+}
+
+async fn create_session(
+    ws: &mut WsClient,
+    args: &SendArgs,
+    provider: Provider,
+    project: &str,
+) -> Result<String, (i32, CliError)> {
+    ws.send(&ClientMessage::CreateSession {
+        provider,
+        cwd: project.to_string(),
+        model: args.model.clone(),
+        approval_policy: None,
+        sandbox_mode: None,
+        permission_mode: None,
+        allowed_tools: vec![],
+        disallowed_tools: vec![],
+        effort: args.effort.as_ref().map(|e| e.as_str().to_string()),
+        system_prompt: None,
+        append_system_prompt: None,
+        confirm_over_budget: args.confirm_over_budget,
+    })
+    .await
+    .map_err(|e| (EXIT_CONNECTION_ERROR, CliError::connection(e.to_string())))?;
+
+    loop {
+        match ws.recv_timeout(Duration::from_secs(30)).await {
+            Ok(Some(ServerMessage::SessionSnapshot { session })) => return Ok(session.id),
+            Ok(Some(ServerMessage::Error { code, message, .. })) => {
+                return Err((EXIT_SERVER_ERROR, CliError::new(code, message)));
+            }
+            Ok(Some(_)) => continue,
+            Ok(None) => {
+                return Err((
+                    EXIT_CONNECTION_ERROR,
+                    CliError::connection("Timed out waiting for session creation"),
+                ));
+            }
+            Err(e) => return Err((EXIT_CONNECTION_ERROR, CliError::connection(e.to_string()))),
+        }
+    }
+}
+
+/// Whether a `work_status` change ends the wait, and with which exit code.
+/// A direct session goes back to `waiting` when its turn ends, so `waiting`
+/// only counts once the turn has been seen `working`.
+fn turn_outcome(saw_working: &mut bool, status: Option<WorkStatus>) -> Option<(WorkStatus, i32)> {
+    match status? {
+        WorkStatus::Working => {
+            *saw_working = true;
+            None
+        }
+        WorkStatus::Waiting if !*saw_working => None,
+        status @ (WorkStatus::Permission | WorkStatus::Question) => {
+            Some((status, EXIT_NEEDS_INPUT))
+        }
+        status => Some((status, EXIT_SUCCESS)),
+    }
+}
+
+/// Collect the assistant's messages until the turn settles, then print them.
+async fn wait_for_turn(ws: &mut WsClient, output: &Output, session_id: &str, created: bool) -> i32 {
+    let timeout = Duration::from_secs(300);
+    let mut replies: Vec<(String, String)> = Vec::new();
+    let mut saw_working = false;
+
+    let (status, exit_code) = loop {
+        match ws.recv_timeout(timeout).await {
+            Ok(Some(ServerMessage::MessageAppended { message, .. })) => {
+                if message.message_type == MessageType::Assistant {
+                    replies.push((message.id, message.content));
+                }
+            }
+            Ok(Some(ServerMessage::MessageUpdated {
+                message_id,
+                changes,
+                ..
+            })) => {
+                if let (Some(reply), Some(content)) = (
+                    replies.iter_mut().find(|(id, _)| *id == message_id),
+                    changes.content,
+                ) {
+                    reply.1 = content;
+                }
+            }
+            Ok(Some(ServerMessage::ApprovalRequested { .. })) => {
+                break (WorkStatus::Permission, EXIT_NEEDS_INPUT);
+            }
+            Ok(Some(ServerMessage::SessionDelta { changes, .. })) => {
+                if let Some(outcome) = turn_outcome(&mut saw_working, changes.work_status) {
+                    break outcome;
+                }
+            }
+            Ok(Some(ServerMessage::SessionEnded { .. })) => {
+                break (WorkStatus::Ended, EXIT_SUCCESS)
+            }
+            Ok(Some(ServerMessage::Error { code, message, .. })) => {
+                output.print_error(&CliError::new(code, message));
+                return EXIT_SERVER_ERROR;
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                output.print_error(&CliError::connection(
+                    "Connection closed or timed out before the turn finished",
+                ));
+                return EXIT_CONNECTION_ERROR;
+            }
+            Err(e) => {
+                output.print_error(&CliError::connection(e.to_string()));
+                return EXIT_CONNECTION_ERROR;
+            }
+        }
+    };
+
+    let reply = replies
+        .into_iter()
+        .map(|(_, content)| content)
+        .filter(|content| !content.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    if output.json {
+        output.print_json(&serde_json::json!({
+            "session_id": session_id,
+            "created": created,
+            "status": work_status_str(&status),
+            "output": reply,
+        }));
+    } else {
+        if !reply.is_empty() {
+            println!("{reply}");
+        }
+        if exit_code == EXIT_NEEDS_INPUT {
+            eprintln!(
+                "Session {session_id} is waiting for {}",
+                if status == WorkStatus::Question {
+                    "an answer"
+                } else {
+                    "approval"
+                }
+            );
+        }
+    }
+    exit_code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waiting_after_working_finishes_the_turn() {
+        let mut saw_working = false;
+        assert_eq!(
+            turn_outcome(&mut saw_working, Some(WorkStatus::Waiting)),
+            None
+        );
+        assert_eq!(turn_outcome(&mut saw_working, None), None);
+        assert_eq!(
+            turn_outcome(&mut saw_working, Some(WorkStatus::Working)),
+            None
+        );
+        assert_eq!(
+            turn_outcome(&mut saw_working, Some(WorkStatus::Waiting)),
+            Some((WorkStatus::Waiting, EXIT_SUCCESS))
+        );
+    }
+
+    #[test]
+    fn reply_and_prompts_end_the_wait_right_away() {
+        let mut saw_working = false;
+        assert_eq!(
+            turn_outcome(&mut saw_working, Some(WorkStatus::Reply)),
+            Some((WorkStatus::Reply, EXIT_SUCCESS))
+        );
+        assert_eq!(
+            turn_outcome(&mut saw_working, Some(WorkStatus::Permission)),
+            Some((WorkStatus::Permission, EXIT_NEEDS_INPUT))
+        );
+    }
+}
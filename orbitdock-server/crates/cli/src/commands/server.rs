@@ -1,9 +1,14 @@
+use std::time::Duration;
+
+use orbitdock_protocol::{ClientMessage, ServerMessage};
 use serde::{Deserialize, Serialize};
 
 use super::health::HealthResponse;
 use crate::cli::ServerAction;
+use crate::client::config::ClientConfig;
 use crate::client::rest::RestClient;
-use crate::error::EXIT_SUCCESS;
+use crate::client::ws::WsClient;
+use crate::error::{CliError, EXIT_CONNECTION_ERROR, EXIT_SERVER_ERROR, EXIT_SUCCESS};
 use crate::output::Output;
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -16,7 +21,12 @@ struct SetServerRoleRequest {
     is_primary: bool,
 }
 
-pub async fn run(action: &ServerAction, rest: &RestClient, output: &Output) -> i32 {
+pub async fn run(
+    action: &ServerAction,
+    rest: &RestClient,
+    output: &Output,
+    config: &ClientConfig,
+) -> i32 {
     match action {
         ServerAction::Status => status(rest, output).await,
         ServerAction::Role { primary, secondary } => {
@@ -29,6 +39,8 @@ pub async fn run(action: &ServerAction, rest: &RestClient, output: &Output) -> i
                 status(rest, output).await
             }
         }
+        ServerAction::Shutdown => shutdown(output, config).await,
+        ServerAction::ReloadConfig => reload_config(output, config).await,
     }
 }
 
@@ -82,3 +94,108 @@ async fn set_role(rest: &RestClient, output: &Output, is_primary: bool) -> i32 {
         }
     }
 }
+
+async fn shutdown(output: &Output, config: &ClientConfig) -> i32 {
+    let mut ws = match WsClient::connect(config).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            output.print_error(&CliError::connection(e.to_string()));
+            return EXIT_CONNECTION_ERROR;
+        }
+    };
+    if let Err(e) = ws.send(&ClientMessage::ShutdownServer).await {
+        output.print_error(&CliError::connection(e.to_string()));
+        return EXIT_CONNECTION_ERROR;
+    }
+
+    loop {
+        match ws.recv_timeout(Duration::from_secs(10)).await {
+            Ok(Some(ServerMessage::ServerShuttingDown)) => {
+                if output.json {
+                    output.print_json(&serde_json::json!({ "shutting_down": true }));
+                } else {
+                    println!("Server is shutting down");
+                }
+                return EXIT_SUCCESS;
+            }
+            Ok(Some(ServerMessage::Error { code, message, .. })) => {
+                output.print_error(&CliError::new(code, message));
+                return EXIT_SERVER_ERROR;
+            }
+            Ok(Some(_)) => continue,
+            Ok(None) => {
+                output.print_error(&CliError::connection(
+                    "Timed out waiting for the server to acknowledge shutdown",
+                ));
+                return EXIT_CONNECTION_ERROR;
+            }
+            Err(e) => {
+                output.print_error(&CliError::connection(e.to_string()));
+                return EXIT_CONNECTION_ERROR;
+            }
+        }
+    }
+}
+
+async fn reload_config(output: &Output, config: &ClientConfig) -> i32 {
+    let mut ws = match WsClient::connect(config).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            output.print_error(&CliError::connection(e.to_string()));
+            return EXIT_CONNECTION_ERROR;
+        }
+    };
+    if let Err(e) = ws.send(&ClientMessage::ReloadConfig).await {
+        output.print_error(&CliError::connection(e.to_string()));
+        return EXIT_CONNECTION_ERROR;
+    }
+
+    loop {
+        match ws.recv_timeout(Duration::from_secs(10)).await {
+            Ok(Some(ServerMessage::ConfigReloaded {
+                applied,
+                restart_required,
+                overridden,
+            })) => {
+                if output.json {
+                    output.print_json(&serde_json::json!({
+                        "applied": applied,
+                        "restart_required": restart_required,
+                        "overridden": overridden,
+                    }));
+                } else if applied.is_empty()
+                    && restart_required.is_empty()
+                    && overridden.is_empty()
+                {
+                    println!("Config reloaded, nothing changed");
+                } else {
+                    for (label, keys) in [
+                        ("Applied", &applied),
+                        ("Needs a restart", &restart_required),
+                        ("Overridden by the server's environment", &overridden),
+                    ] {
+                        if !keys.is_empty() {
+                            println!("{label}: {}", keys.join(", "));
+                        }
+                    }
+                }
+                return EXIT_SUCCESS;
+            }
+            Ok(Some(ServerMessage::Error { code, message, .. })) => {
+                output.print_error(&CliError::new(code, message));
+                return EXIT_SERVER_ERROR;
+            }
+            Ok(Some(_)) => continue,
+            Ok(None) => {
+                output.print_error(&CliError::connection(
+                    "Timed out waiting for the server to reload its config",
+                ));
+                return EXIT_CONNECTION_ERROR;
+            }
+            Err(e) => {
+                output.print_error(&CliError::connection(e.to_string()));
+                return EXIT_CONNECTION_ERROR;
+            }
+        }
+    }
+}
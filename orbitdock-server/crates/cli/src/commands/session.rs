@@ -1,13 +1,14 @@
 use std::time::Duration;
 
 use orbitdock_protocol::{
-    ClientMessage, MessageType, Provider, ServerMessage, SessionState, SessionStatus, WorkStatus,
+    ClientMessage, MessageType, Provider, ServerMessage, SessionState, SessionStatus, TicketLink,
+    TicketTracker, WorkStatus,
 };
 use serde::{Deserialize, Serialize};
 
 use crate::cli::{
     resolve_stdin, ApprovalDecision, Effort, PermissionMode, ProviderFilter, SessionAction,
-    StatusFilter,
+    StatusFilter, TicketTrackerArg,
 };
 use crate::client::config::ClientConfig;
 use crate::client::rest::RestClient;
@@ -39,6 +40,7 @@ pub async fn run(
             provider,
             status,
             project,
+            tag,
         } => {
             list(
                 rest,
@@ -46,6 +48,7 @@ pub async fn run(
                 provider.as_ref(),
                 status.as_ref(),
                 project.as_deref(),
+                tag.as_deref(),
             )
             .await
         }
@@ -53,6 +56,22 @@ pub async fn run(
             session_id,
             messages,
         } => get(rest, output, session_id, *messages).await,
+        SessionAction::FromIssue {
+            repo,
+            number,
+            cwd,
+            provider,
+        } => {
+            from_issue(
+                rest,
+                output,
+                repo,
+                *number,
+                cwd.as_deref(),
+                provider.as_ref(),
+            )
+            .await
+        }
 
         // WS commands
         SessionAction::Create {
@@ -62,6 +81,7 @@ pub async fn run(
             permission_mode,
             effort,
             system_prompt,
+            confirm_over_budget,
         } => {
             let resolved_cwd = match cwd {
                 Some(c) => c.clone(),
@@ -78,6 +98,7 @@ pub async fn run(
                 permission_mode.as_ref(),
                 effort.as_ref(),
                 system_prompt.as_deref(),
+                *confirm_over_budget,
             )
             .await
         }
@@ -178,6 +199,29 @@ pub async fn run(
         SessionAction::Rename { session_id, name } => {
             rename(config, output, session_id, name).await
         }
+        SessionAction::Tag { session_id, tags } => tag(config, output, session_id, tags).await,
+        SessionAction::Ticket {
+            session_id,
+            tracker,
+            id,
+            url,
+            sync,
+            clear: _,
+        } => {
+            let ticket = tracker
+                .as_ref()
+                .zip(id.as_ref())
+                .map(|(tracker, id)| TicketLink {
+                    tracker: match tracker {
+                        TicketTrackerArg::Linear => TicketTracker::Linear,
+                        TicketTrackerArg::Jira => TicketTracker::Jira,
+                    },
+                    id: id.clone(),
+                    url: url.clone(),
+                    sync: *sync,
+                });
+            ticket_link(config, output, session_id, ticket).await
+        }
         SessionAction::Resume { session_id } => resume(config, output, session_id).await,
     }
 }
@@ -212,7 +256,7 @@ fn session_status_str(s: &SessionStatus) -> &'static str {
     }
 }
 
-fn work_status_str(s: &WorkStatus) -> &'static str {
+pub(super) fn work_status_str(s: &WorkStatus) -> &'static str {
     match s {
         WorkStatus::Working => "working",
         WorkStatus::Waiting => "waiting",
@@ -231,6 +275,7 @@ async fn list(
     provider: Option<&ProviderFilter>,
     status: Option<&StatusFilter>,
     project: Option<&str>,
+    tag: Option<&str>,
 ) -> i32 {
     match rest
         .get::<SessionsResponse>("/api/sessions")
@@ -255,6 +300,10 @@ async fn list(
             if let Some(proj) = project {
                 resp.sessions.retain(|s| s.project_path.contains(proj));
             }
+            if let Some(tag) = tag {
+                let tag = tag.trim().to_lowercase();
+                resp.sessions.retain(|s| s.tags.contains(&tag));
+            }
 
             if output.json {
                 output.print_json(&resp);
@@ -291,6 +340,60 @@ async fn get(rest: &RestClient, output: &Output, session_id: &str, messages: boo
     }
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+struct IssueSessionResponse {
+    session_id: String,
+    issue: orbitdock_protocol::IssueLink,
+    existing: bool,
+}
+
+async fn from_issue(
+    rest: &RestClient,
+    output: &Output,
+    repo: &str,
+    number: u64,
+    cwd: Option<&str>,
+    provider: Option<&ProviderFilter>,
+) -> i32 {
+    let provider = provider.map(|provider| match provider {
+        ProviderFilter::Claude => Provider::Claude,
+        ProviderFilter::Codex => Provider::Codex,
+    });
+    let body = serde_json::json!({
+        "repo": repo,
+        "number": number,
+        "project_path": cwd,
+        "provider": provider,
+    });
+    match rest
+        .post_json::<_, IssueSessionResponse>("/api/github/issues", &body)
+        .await
+        .into_result()
+    {
+        Ok(resp) => {
+            if output.json {
+                output.print_json(&resp);
+            } else {
+                let verb = if resp.existing {
+                    "Already running"
+                } else {
+                    "Started"
+                };
+                println!(
+                    "{verb} session {} for {}#{}: {}",
+                    resp.session_id, resp.issue.repo, resp.issue.number, resp.issue.title
+                );
+                println!("  {}", resp.issue.url);
+            }
+            EXIT_SUCCESS
+        }
+        Err((code, err)) => {
+            output.print_error(&err);
+            code
+        }
+    }
+}
+
 // ── WS Commands ──────────────────────────────────────────────
 
 #[allow(clippy::too_many_arguments)]
@@ -303,6 +406,7 @@ async fn create(
     permission_mode: Option<&PermissionMode>,
     effort: Option<&Effort>,
     system_prompt: Option<&str>,
+    confirm_over_budget: bool,
 ) -> i32 {
     let Some(mut ws) = ws_connect(config, output).await else {
         return EXIT_CONNECTION_ERROR;
@@ -326,6 +430,7 @@ async fn create(
             effort: effort.map(|e| e.as_str().to_string()),
             system_prompt: system_prompt.map(str::to_string),
             append_system_prompt: None,
+            confirm_over_budget,
         })
         .await
     {
@@ -402,6 +507,7 @@ async fn send_message(
             skills: vec![],
             images: vec![],
             mentions: vec![],
+            attachment_ids: vec![],
         })
         .await
     {
@@ -421,7 +527,7 @@ async fn send_message(
     stream_turn_events(&mut ws, output).await
 }
 
-async fn approve_tool(
+pub(super) async fn approve_tool(
     config: &ClientConfig,
     output: &Output,
     session_id: &str,
@@ -804,6 +910,7 @@ async fn steer(config: &ClientConfig, output: &Output, session_id: &str, content
             content: content.to_string(),
             images: vec![],
             mentions: vec![],
+            attachment_ids: vec![],
         })
         .await
     {
@@ -841,11 +948,21 @@ async fn compact(config: &ClientConfig, output: &Output, session_id: &str) -> i3
 
     loop {
         match ws.recv_timeout(Duration::from_secs(60)).await {
-            Ok(Some(ServerMessage::ContextCompacted { .. })) => {
+            Ok(Some(ServerMessage::ContextCompactionCompleted {
+                tokens_before,
+                tokens_after,
+                ..
+            })) => {
                 if output.json {
-                    output.print_json(&serde_json::json!({"compacted": true}));
+                    output.print_json(&serde_json::json!({
+                        "compacted": true,
+                        "tokens_before": tokens_before,
+                        "tokens_after": tokens_after,
+                    }));
+                } else if let Some(tokens_after) = tokens_after {
+                    println!("Context compacted ({tokens_before} -> {tokens_after} tokens).");
                 } else {
-                    println!("Context compacted.");
+                    println!("Context compacted ({tokens_before} tokens before).");
                 }
                 return EXIT_SUCCESS;
             }
@@ -976,7 +1093,7 @@ async fn rollback(config: &ClientConfig, output: &Output, session_id: &str, turn
     }
 }
 
-async fn watch(
+pub(super) async fn watch(
     config: &ClientConfig,
     output: &Output,
     session_id: &str,
@@ -1050,6 +1167,63 @@ async fn watch(
     }
 }
 
+/// Stream the session list: the current sessions, then creations, status
+/// changes, and endings across every session this client can see.
+pub(super) async fn watch_list(
+    config: &ClientConfig,
+    output: &Output,
+    filter: &[String],
+    timeout_secs: Option<u64>,
+) -> i32 {
+    let Some(mut ws) = ws_connect(config, output).await else {
+        return EXIT_CONNECTION_ERROR;
+    };
+
+    if let Err(e) = ws.send(&ClientMessage::SubscribeList).await {
+        output.print_error(&CliError::connection(e.to_string()));
+        return EXIT_CONNECTION_ERROR;
+    }
+
+    if !output.json {
+        let bold = console::Style::new().bold();
+        println!("{} all sessions", bold.apply_to("Watching:"));
+        println!("Press Ctrl+C to stop.\n");
+    }
+
+    let timeout = timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(u64::MAX / 2));
+
+    loop {
+        match ws.recv_timeout(timeout).await {
+            Ok(Some(ref msg)) => {
+                if !filter.is_empty() {
+                    let event_type = event_type_name(msg);
+                    if !filter.iter().any(|f| event_type.contains(f.as_str())) {
+                        continue;
+                    }
+                }
+
+                if output.json {
+                    output.print_json(msg);
+                } else {
+                    print_list_event(msg);
+                }
+            }
+            Ok(None) => {
+                if !output.json {
+                    println!("\nConnection closed.");
+                }
+                return EXIT_SUCCESS;
+            }
+            Err(e) => {
+                output.print_error(&CliError::connection(e.to_string()));
+                return EXIT_CONNECTION_ERROR;
+            }
+        }
+    }
+}
+
 async fn rename(config: &ClientConfig, output: &Output, session_id: &str, name: &str) -> i32 {
     let Some(mut ws) = ws_connect(config, output).await else {
         return EXIT_CONNECTION_ERROR;
@@ -1086,6 +1260,130 @@ async fn rename(config: &ClientConfig, output: &Output, session_id: &str, name:
     EXIT_SUCCESS
 }
 
+async fn tag(config: &ClientConfig, output: &Output, session_id: &str, tags: &[String]) -> i32 {
+    let Some(mut ws) = ws_connect(config, output).await else {
+        return EXIT_CONNECTION_ERROR;
+    };
+
+    if let Err(e) = ws.send(&ClientMessage::SubscribeList).await {
+        output.print_error(&CliError::connection(e.to_string()));
+        return EXIT_CONNECTION_ERROR;
+    }
+    if let Err(e) = ws
+        .send(&ClientMessage::SetSessionTags {
+            session_id: session_id.to_string(),
+            tags: tags.to_vec(),
+        })
+        .await
+    {
+        output.print_error(&CliError::connection(e.to_string()));
+        return EXIT_CONNECTION_ERROR;
+    }
+
+    // The list subscription echoes the normalized tags back.
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    loop {
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        match ws.recv_timeout(remaining).await {
+            Ok(Some(ServerMessage::Error { code, message, .. })) => {
+                output.print_error(&CliError::new(code, message));
+                return EXIT_SERVER_ERROR;
+            }
+            Ok(Some(ServerMessage::SessionTagsChanged {
+                session_id: changed,
+                tags,
+            })) if changed == session_id => {
+                if output.json {
+                    output.print_json(&serde_json::json!({"session_id": changed, "tags": tags}));
+                } else if tags.is_empty() {
+                    println!("Session tags cleared");
+                } else {
+                    println!("Session tagged: {}", tags.join(", "));
+                }
+                return EXIT_SUCCESS;
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                output.print_error(&CliError::connection("Timed out waiting for tags"));
+                return EXIT_CONNECTION_ERROR;
+            }
+            Err(e) => {
+                output.print_error(&CliError::connection(e.to_string()));
+                return EXIT_CONNECTION_ERROR;
+            }
+        }
+    }
+}
+
+async fn ticket_link(
+    config: &ClientConfig,
+    output: &Output,
+    session_id: &str,
+    ticket: Option<TicketLink>,
+) -> i32 {
+    let Some(mut ws) = ws_connect(config, output).await else {
+        return EXIT_CONNECTION_ERROR;
+    };
+
+    if let Err(e) = ws.send(&ClientMessage::SubscribeList).await {
+        output.print_error(&CliError::connection(e.to_string()));
+        return EXIT_CONNECTION_ERROR;
+    }
+    if let Err(e) = ws
+        .send(&ClientMessage::SetSessionTicket {
+            session_id: session_id.to_string(),
+            ticket,
+        })
+        .await
+    {
+        output.print_error(&CliError::connection(e.to_string()));
+        return EXIT_CONNECTION_ERROR;
+    }
+
+    // The list subscription echoes the normalized link back.
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    loop {
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        match ws.recv_timeout(remaining).await {
+            Ok(Some(ServerMessage::Error { code, message, .. })) => {
+                output.print_error(&CliError::new(code, message));
+                return EXIT_SERVER_ERROR;
+            }
+            Ok(Some(ServerMessage::SessionTicketChanged {
+                session_id: changed,
+                ticket,
+            })) if changed == session_id => {
+                if output.json {
+                    output
+                        .print_json(&serde_json::json!({"session_id": changed, "ticket": ticket}));
+                } else if let Some(ticket) = ticket {
+                    let sync = if ticket.sync {
+                        " (comments when it ends)"
+                    } else {
+                        ""
+                    };
+                    match ticket.url {
+                        Some(url) => println!("Session linked to {} {url}{sync}", ticket.id),
+                        None => println!("Session linked to {}{sync}", ticket.id),
+                    }
+                } else {
+                    println!("Session ticket cleared");
+                }
+                return EXIT_SUCCESS;
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                output.print_error(&CliError::connection("Timed out waiting for the ticket"));
+                return EXIT_CONNECTION_ERROR;
+            }
+            Err(e) => {
+                output.print_error(&CliError::connection(e.to_string()));
+                return EXIT_CONNECTION_ERROR;
+            }
+        }
+    }
+}
+
 async fn resume(config: &ClientConfig, output: &Output, session_id: &str) -> i32 {
     let Some(mut ws) = ws_connect(config, output).await else {
         return EXIT_CONNECTION_ERROR;
@@ -1232,18 +1530,58 @@ fn event_type_name(msg: &ServerMessage) -> &'static str {
         ServerMessage::ApprovalRequested { .. } => "approval_requested",
         ServerMessage::ApprovalDecisionResult { .. } => "approval_decision_result",
         ServerMessage::ApprovalDeleted { .. } => "approval_deleted",
+        ServerMessage::AuditLog { .. } => "audit_log",
+        ServerMessage::UsageAnalytics { .. } => "usage_analytics",
+        ServerMessage::SemanticSearchResults { .. } => "semantic_search_results",
+        ServerMessage::SessionTagsChanged { .. } => "session_tags_changed",
+        ServerMessage::SessionTicketChanged { .. } => "session_ticket_changed",
+        ServerMessage::LogLevel { .. } => "log_level",
+        ServerMessage::ConnectorStats { .. } => "connector_stats",
+        ServerMessage::ConnectionsList { .. } => "connections_list",
+        ServerMessage::ServerShuttingDown => "server_shutting_down",
+        ServerMessage::ConfigReloaded { .. } => "config_reloaded",
+        ServerMessage::ServerConfig { .. } => "server_config",
+        ServerMessage::BudgetAlert { .. } => "budget_alert",
+        ServerMessage::ContextWarning { .. } => "context_warning",
+        ServerMessage::TurnSummarized { .. } => "turn_summarized",
+        ServerMessage::PlanDelta { .. } => "plan_delta",
+        ServerMessage::TurnsList { .. } => "turns_list",
+        ServerMessage::SessionEvents { .. } => "session_events",
+        ServerMessage::SessionResynced { .. } => "session_resynced",
+        ServerMessage::SessionsResynced { .. } => "sessions_resynced",
+        ServerMessage::PushDeviceRegistered { .. } => "push_device_registered",
         ServerMessage::ApprovalsList { .. } => "approvals_list",
         ServerMessage::TokensUpdated { .. } => "tokens_updated",
         ServerMessage::SessionCreated { .. } => "session_created",
         ServerMessage::SessionEnded { .. } => "session_ended",
+        ServerMessage::ConnectorReconnecting { .. } => "connector_reconnecting",
+        ServerMessage::ConnectorReconnected { .. } => "connector_reconnected",
         ServerMessage::SessionForked { .. } => "session_forked",
+        ServerMessage::SessionPresence { .. } => "session_presence",
+        ServerMessage::SessionSharing { .. } => "session_sharing",
+        ServerMessage::ContextCompactionStarted { .. } => "context_compaction_started",
+        ServerMessage::ContextCompactionCompleted { .. } => "context_compaction_completed",
         ServerMessage::ContextCompacted { .. } => "context_compacted",
         ServerMessage::UndoStarted { .. } => "undo_started",
         ServerMessage::UndoCompleted { .. } => "undo_completed",
         ServerMessage::ThreadRolledBack { .. } => "thread_rolled_back",
         ServerMessage::ShellStarted { .. } => "shell_started",
         ServerMessage::ShellOutput { .. } => "shell_output",
+        ServerMessage::JobsList { .. } => "jobs_list",
+        ServerMessage::JobOutput { .. } => "job_output",
+        ServerMessage::ShellJobCompleted { .. } => "shell_job_completed",
+        ServerMessage::TerminalOpened { .. } => "terminal_opened",
+        ServerMessage::TerminalOutput { .. } => "terminal_output",
+        ServerMessage::TerminalClosed { .. } => "terminal_closed",
+        ServerMessage::AttentionItems { .. } => "attention_items",
+        ServerMessage::AttentionChanged { .. } => "attention_changed",
+        ServerMessage::EditorsList { .. } => "editors_list",
+        ServerMessage::EditorOpened { .. } => "editor_opened",
+        ServerMessage::ReplayStarted { .. } => "replay_started",
+        ServerMessage::ReplayEvent { .. } => "replay_event",
+        ServerMessage::ReplayFinished { .. } => "replay_finished",
         ServerMessage::TurnDiffSnapshot { .. } => "turn_diff_snapshot",
+        ServerMessage::TurnMetricsRecorded { .. } => "turn_metrics_recorded",
         ServerMessage::RateLimitEvent { .. } => "rate_limit_event",
         ServerMessage::PromptSuggestion { .. } => "prompt_suggestion",
         ServerMessage::Error { .. } => "error",
@@ -1260,11 +1598,16 @@ fn event_type_name(msg: &ServerMessage) -> &'static str {
         ServerMessage::WorktreesList { .. } => "worktrees_list",
         ServerMessage::CodexAccountStatus { .. } => "codex_account_status",
         ServerMessage::CodexAccountUpdated { .. } => "codex_account_updated",
+        ServerMessage::CodexAccountUsage { .. } => "codex_account_usage",
+        ServerMessage::ClaudeAccountStatus { .. } => "claude_account_status",
+        ServerMessage::ClaudeLoginCompleted { .. } => "claude_login_completed",
         ServerMessage::CodexLoginChatgptStarted { .. } => "codex_login_started",
         ServerMessage::CodexLoginChatgptCompleted { .. } => "codex_login_completed",
         ServerMessage::CodexLoginChatgptCanceled { .. } => "codex_login_canceled",
         ServerMessage::ClaudeCapabilities { .. } => "claude_capabilities",
         ServerMessage::ClaudeModelsList { .. } => "claude_models_list",
+        ServerMessage::EffortLevelsList { .. } => "effort_levels_list",
+        ServerMessage::ProviderInfo { .. } => "provider_info",
         ServerMessage::ClaudeUsageResult { .. } => "claude_usage_result",
         ServerMessage::CodexUsageResult { .. } => "codex_usage_result",
         ServerMessage::FilesPersisted { .. } => "files_persisted",
@@ -1324,10 +1667,17 @@ fn print_watch_event(msg: &ServerMessage) {
         } => {
             println!("{} {outcome} ({request_id})", dim.apply_to("decision"));
         }
-        ServerMessage::TokensUpdated { usage, .. } => {
+        ServerMessage::TokensUpdated {
+            usage,
+            estimated_cost_usd,
+            ..
+        } => {
             let fill = usage.context_fill_percent();
+            let cost = estimated_cost_usd
+                .map(|cost| format!(" ~${cost:.2}"))
+                .unwrap_or_default();
             println!(
-                "{} {fill:.0}% ({} in / {} out)",
+                "{} {fill:.0}% ({} in / {} out){cost}",
                 dim.apply_to("tokens"),
                 usage.input_tokens,
                 usage.output_tokens
@@ -1336,6 +1686,12 @@ fn print_watch_event(msg: &ServerMessage) {
         ServerMessage::SessionEnded { reason, .. } => {
             println!("{} {reason}", bold.apply_to("ended"));
         }
+        ServerMessage::ContextCompactionStarted { tokens_before, .. } => {
+            println!(
+                "{} from {tokens_before} tokens",
+                dim.apply_to("compacting context")
+            );
+        }
         ServerMessage::ContextCompacted { .. } => {
             println!("{}", dim.apply_to("context compacted"));
         }
@@ -1371,6 +1727,66 @@ fn print_watch_event(msg: &ServerMessage) {
     }
 }
 
+fn print_list_event(msg: &ServerMessage) {
+    let dim = console::Style::new().dim();
+    let bold = console::Style::new().bold();
+
+    match msg {
+        ServerMessage::SessionsList { sessions } => {
+            let active = sessions
+                .iter()
+                .filter(|s| s.status == SessionStatus::Active)
+                .count();
+            println!(
+                "{} {} total, {active} active",
+                dim.apply_to("sessions"),
+                sessions.len()
+            );
+        }
+        ServerMessage::SessionCreated { session } => {
+            println!(
+                "{} {} ({}) {}",
+                bold.apply_to("+session"),
+                truncate(&session.id, 16),
+                provider_str(&session.provider),
+                session.project_path
+            );
+        }
+        ServerMessage::SessionForked {
+            source_session_id,
+            new_session_id,
+            ..
+        } => {
+            println!(
+                "{} {} from {}",
+                bold.apply_to("fork"),
+                truncate(new_session_id, 16),
+                truncate(source_session_id, 16)
+            );
+        }
+        ServerMessage::SessionDelta {
+            session_id,
+            changes,
+        } => {
+            let id = dim.apply_to(truncate(session_id, 16));
+            if let Some(status) = &changes.work_status {
+                println!("{id} {}", work_status_str(status));
+            }
+            if let Some(Some(name)) = &changes.custom_name {
+                println!("{id} name -> {name}");
+            }
+        }
+        ServerMessage::SessionEnded { session_id, reason } => {
+            println!(
+                "{} {} {reason}",
+                bold.apply_to("ended"),
+                truncate(session_id, 16)
+            );
+        }
+        _ => print_watch_event(msg),
+    }
+}
+
 // ── Human Output ─────────────────────────────────────────────
 
 fn print_session_detail(session: &SessionState, show_messages: bool) {
@@ -58,6 +58,7 @@ async fn exec(
             command: command.to_string(),
             cwd: cwd.map(str::to_string),
             timeout_secs,
+            detach: false,
         })
         .await
     {
@@ -0,0 +1,16 @@
+use crate::cli::WatchArgs;
+use crate::client::config::ClientConfig;
+use crate::output::Output;
+
+use super::session;
+
+/// Events print one JSON object per line in JSON mode (the default when
+/// piped), so the stream can go straight into `jq` or a log file.
+pub async fn run(args: &WatchArgs, output: &Output, config: &ClientConfig) -> i32 {
+    match &args.session_id {
+        Some(session_id) => {
+            session::watch(config, output, session_id, &args.filter, args.timeout).await
+        }
+        None => session::watch_list(config, output, &args.filter, args.timeout).await,
+    }
+}
@@ -5,6 +5,8 @@ pub const EXIT_SUCCESS: i32 = 0;
 pub const EXIT_CLIENT_ERROR: i32 = 1;
 pub const EXIT_SERVER_ERROR: i32 = 2;
 pub const EXIT_CONNECTION_ERROR: i32 = 3;
+/// The turn stopped to wait for an approval or an answer.
+pub const EXIT_NEEDS_INPUT: i32 = 4;
 
 /// Structured error from the server API.
 #[derive(Debug, Deserialize, Serialize)]
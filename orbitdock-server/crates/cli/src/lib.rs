@@ -3,5 +3,6 @@ pub mod client;
 pub mod commands;
 pub mod error;
 pub mod output;
+pub mod tui;
 
 pub use commands::dispatch;
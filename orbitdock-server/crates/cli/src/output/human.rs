@@ -98,6 +98,51 @@ pub fn approvals_table(approvals: &[ApprovalHistoryItem]) {
     println!("{table}");
 }
 
+/// Format pending approvals as a numbered table; the numbers are what
+/// `orbitdock approve <n>` accepts.
+pub fn pending_approvals_table(sessions: &[SessionSummary]) {
+    if sessions.is_empty() {
+        println!("No pending approvals.");
+        return;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("#").add_attribute(Attribute::Bold),
+            Cell::new("Session").add_attribute(Attribute::Bold),
+            Cell::new("Project").add_attribute(Attribute::Bold),
+            Cell::new("Tool").add_attribute(Attribute::Bold),
+            Cell::new("Request").add_attribute(Attribute::Bold),
+        ]);
+
+    for (index, s) in sessions.iter().enumerate() {
+        let project = s
+            .project_name
+            .as_deref()
+            .or(s.project_path.split('/').next_back())
+            .unwrap_or("-");
+        let tool = s.pending_tool_name.as_deref().unwrap_or("-");
+        let request = s
+            .pending_question
+            .as_deref()
+            .or(s.pending_tool_input.as_deref())
+            .unwrap_or("-");
+
+        table.add_row(vec![
+            Cell::new(index + 1),
+            Cell::new(truncate_id(&s.id)),
+            Cell::new(project),
+            Cell::new(tool).fg(Color::Red),
+            Cell::new(truncate(&request.replace('\n', " "), 60)),
+        ]);
+    }
+
+    println!("{table}");
+}
+
 fn status_cell(status: SessionStatus, work_status: WorkStatus) -> Cell {
     let (label, color) = match (status, work_status) {
         (SessionStatus::Ended, _) | (_, WorkStatus::Ended) => ("ended", Color::DarkGrey),
@@ -0,0 +1,232 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use orbitdock_protocol::{
+    ClientMessage, Message, ServerMessage, SessionStatus, SessionSummary, StateChanges, WorkStatus,
+};
+
+/// Everything the dashboard shows, rebuilt from the server's event stream.
+#[derive(Default)]
+pub struct App {
+    pub sessions: Vec<SessionSummary>,
+    pub selected: usize,
+    pub show_ended: bool,
+    /// Session whose conversation is shown in the message pane.
+    pub open_session: Option<String>,
+    pub messages: Vec<Message>,
+    pub status_line: Option<String>,
+    pub should_quit: bool,
+}
+
+impl App {
+    /// Sessions in display order: needs-attention first, then by recent activity.
+    pub fn visible_sessions(&self) -> Vec<&SessionSummary> {
+        let mut visible: Vec<&SessionSummary> = self
+            .sessions
+            .iter()
+            .filter(|s| self.show_ended || s.status == SessionStatus::Active)
+            .collect();
+        visible.sort_by(|a, b| {
+            attention_rank(a)
+                .cmp(&attention_rank(b))
+                .then_with(|| b.last_activity_at.cmp(&a.last_activity_at))
+        });
+        visible
+    }
+
+    pub fn selected_session(&self) -> Option<&SessionSummary> {
+        self.visible_sessions().get(self.selected).copied()
+    }
+
+    pub fn handle_server_message(&mut self, msg: ServerMessage) {
+        match msg {
+            ServerMessage::SessionsList { sessions } => self.sessions = sessions,
+            ServerMessage::SessionCreated { session } => {
+                self.sessions.retain(|s| s.id != session.id);
+                self.sessions.push(session);
+            }
+            ServerMessage::SessionDelta {
+                session_id,
+                changes,
+            } => {
+                if let Some(summary) = self.sessions.iter_mut().find(|s| s.id == session_id) {
+                    apply_changes(summary, &changes);
+                }
+            }
+            ServerMessage::SessionEnded { session_id, .. } => {
+                if let Some(summary) = self.sessions.iter_mut().find(|s| s.id == session_id) {
+                    summary.status = SessionStatus::Ended;
+                    summary.work_status = WorkStatus::Ended;
+                    summary.has_pending_approval = false;
+                }
+            }
+            ServerMessage::SessionSnapshot { session }
+                if self.open_session.as_deref() == Some(session.id.as_str()) =>
+            {
+                self.messages = session.messages;
+            }
+            ServerMessage::MessageAppended {
+                session_id,
+                message,
+            } if self.open_session.as_deref() == Some(session_id.as_str()) => {
+                self.messages.push(message);
+            }
+            ServerMessage::MessageUpdated {
+                session_id,
+                message_id,
+                changes,
+            } => {
+                if self.open_session.as_deref() != Some(session_id.as_str()) {
+                    return;
+                }
+                if let Some(message) = self.messages.iter_mut().find(|m| m.id == message_id) {
+                    if let Some(content) = changes.content {
+                        message.content = content;
+                    }
+                    if let Some(tool_output) = changes.tool_output {
+                        message.tool_output = Some(tool_output);
+                    }
+                }
+            }
+            ServerMessage::ApprovalDecisionResult {
+                request_id,
+                outcome,
+                ..
+            } => {
+                self.status_line = Some(format!("{request_id}: {outcome}"));
+            }
+            ServerMessage::Error { code, message, .. } => {
+                self.status_line = Some(format!("[{code}] {message}"));
+            }
+            _ => {}
+        }
+        self.clamp_selection();
+    }
+
+    /// Apply a key press, returning any messages to send to the server.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Vec<ClientMessage> {
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.should_quit = true;
+            }
+            KeyCode::Char('q') => self.should_quit = true,
+            KeyCode::Esc => {
+                if let Some(session_id) = self.open_session.take() {
+                    self.messages.clear();
+                    return vec![ClientMessage::UnsubscribeSession { session_id }];
+                }
+                self.should_quit = true;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = self.selected.saturating_add(1);
+                self.clamp_selection();
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('e') => {
+                self.show_ended = !self.show_ended;
+                self.clamp_selection();
+            }
+            KeyCode::Enter => return self.open_selected(),
+            KeyCode::Char('a') => return self.decide("approved"),
+            KeyCode::Char('A') => return self.decide("approved_for_session"),
+            KeyCode::Char('d') => return self.decide("denied"),
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn open_selected(&mut self) -> Vec<ClientMessage> {
+        let Some(session_id) = self.selected_session().map(|s| s.id.clone()) else {
+            return Vec::new();
+        };
+        if self.open_session.as_deref() == Some(session_id.as_str()) {
+            return Vec::new();
+        }
+
+        let mut outgoing = Vec::new();
+        if let Some(previous) = self.open_session.take() {
+            outgoing.push(ClientMessage::UnsubscribeSession {
+                session_id: previous,
+            });
+        }
+        self.messages.clear();
+        self.open_session = Some(session_id.clone());
+        outgoing.push(ClientMessage::SubscribeSession {
+            session_id,
+            since_revision: None,
+            include_snapshot: true,
+            snapshot: None,
+        });
+        outgoing
+    }
+
+    fn decide(&mut self, decision: &str) -> Vec<ClientMessage> {
+        let Some(session) = self.selected_session() else {
+            return Vec::new();
+        };
+        let Some(request_id) = session
+            .pending_approval_id
+            .clone()
+            .filter(|_| session.has_pending_approval)
+        else {
+            self.status_line = Some("No pending approval for this session".to_string());
+            return Vec::new();
+        };
+        let session_id = session.id.clone();
+        self.status_line = Some(format!("Sent {decision} for {request_id}"));
+        vec![ClientMessage::ApproveTool {
+            session_id,
+            request_id,
+            decision: decision.to_string(),
+            message: None,
+            interrupt: None,
+            updated_input: None,
+        }]
+    }
+
+    fn clamp_selection(&mut self) {
+        let count = self.visible_sessions().len();
+        self.selected = self.selected.min(count.saturating_sub(1));
+    }
+}
+
+fn attention_rank(session: &SessionSummary) -> u8 {
+    match session.work_status {
+        WorkStatus::Permission | WorkStatus::Question => 0,
+        WorkStatus::Working => 1,
+        WorkStatus::Reply | WorkStatus::Waiting => 2,
+        WorkStatus::Ended => 3,
+    }
+}
+
+fn apply_changes(summary: &mut SessionSummary, changes: &StateChanges) {
+    if let Some(status) = changes.status {
+        summary.status = status;
+    }
+    if let Some(work_status) = changes.work_status {
+        summary.work_status = work_status;
+    }
+    if let Some(pending) = &changes.pending_approval {
+        summary.has_pending_approval = pending.is_some();
+        summary.pending_approval_id = pending.as_ref().map(|req| req.id.clone());
+        summary.pending_tool_name = pending.as_ref().and_then(|req| req.tool_name.clone());
+        summary.pending_tool_input = pending
+            .as_ref()
+            .and_then(|req| req.command.clone().or_else(|| req.tool_input.clone()));
+        summary.pending_question = pending.as_ref().and_then(|req| req.question.clone());
+    }
+    if let Some(custom_name) = &changes.custom_name {
+        summary.custom_name = custom_name.clone();
+    }
+    if let Some(summary_text) = &changes.summary {
+        summary.summary = summary_text.clone();
+    }
+    if let Some(last_message) = &changes.last_message {
+        summary.last_message = last_message.clone();
+    }
+    if let Some(model) = &changes.model {
+        summary.model = model.clone();
+    }
+    if let Some(last_activity_at) = &changes.last_activity_at {
+        summary.last_activity_at = Some(last_activity_at.clone());
+    }
+}
@@ -0,0 +1,103 @@
+//! Terminal dashboard: live session list, pending approvals, and the
+//! conversation for the selected session, driven by the WebSocket protocol.
+
+mod app;
+mod ui;
+
+use std::io::IsTerminal;
+
+use anyhow::anyhow;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use tokio::sync::mpsc;
+
+use orbitdock_protocol::{ClientMessage, ServerMessage};
+
+use crate::client::config::ClientConfig;
+use crate::client::ws::WsClient;
+use crate::error::{CliError, EXIT_CLIENT_ERROR, EXIT_CONNECTION_ERROR, EXIT_SUCCESS};
+use crate::output::Output;
+
+use app::App;
+
+#[allow(clippy::large_enum_variant)]
+enum Input {
+    Server(anyhow::Result<Option<ServerMessage>>),
+    Terminal(Event),
+}
+
+pub async fn run(output: &Output, config: &ClientConfig) -> i32 {
+    if !std::io::stdout().is_terminal() {
+        output.print_error(&CliError::new(
+            "not_a_terminal",
+            "The dashboard needs an interactive terminal",
+        ));
+        return EXIT_CLIENT_ERROR;
+    }
+
+    let mut ws = match WsClient::connect(config).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            output.print_error(&CliError::connection(e.to_string()));
+            return EXIT_CONNECTION_ERROR;
+        }
+    };
+    if let Err(e) = ws.send(&ClientMessage::SubscribeList).await {
+        output.print_error(&CliError::connection(e.to_string()));
+        return EXIT_CONNECTION_ERROR;
+    }
+
+    // crossterm's reader blocks, so it gets its own thread.
+    let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if event_tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut terminal = ratatui::init();
+    let mut app = App::default();
+    let result = loop {
+        if let Err(e) = terminal.draw(|frame| ui::render(frame, &app)) {
+            break Err(e.into());
+        }
+
+        let input = tokio::select! {
+            msg = ws.recv() => Input::Server(msg),
+            Some(event) = event_rx.recv() => Input::Terminal(event),
+        };
+
+        match input {
+            Input::Server(Ok(Some(msg))) => app.handle_server_message(msg),
+            Input::Server(Ok(None)) => break Err(anyhow!("Server closed the connection")),
+            Input::Server(Err(e)) => break Err(e),
+            Input::Terminal(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                let mut sent = Ok(());
+                for msg in app.handle_key(key) {
+                    sent = ws.send(&msg).await;
+                    if sent.is_err() {
+                        break;
+                    }
+                }
+                if let Err(e) = sent {
+                    break Err(e);
+                }
+            }
+            Input::Terminal(_) => {}
+        }
+
+        if app.should_quit {
+            break Ok(());
+        }
+    };
+    ratatui::restore();
+
+    match result {
+        Ok(()) => EXIT_SUCCESS,
+        Err(e) => {
+            output.print_error(&CliError::connection(e.to_string()));
+            EXIT_CONNECTION_ERROR
+        }
+    }
+}
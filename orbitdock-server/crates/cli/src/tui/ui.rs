@@ -0,0 +1,193 @@
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+use orbitdock_protocol::{MessageType, Provider, SessionStatus, SessionSummary, WorkStatus};
+
+use super::app::App;
+use crate::output::truncate;
+
+/// Most lines of a single message shown in the conversation pane.
+const MESSAGE_LINES: usize = 6;
+
+pub fn render(frame: &mut Frame, app: &App) {
+    let [main, footer] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+    let [list_area, detail_area] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(main);
+
+    render_sessions(frame, app, list_area);
+
+    let selected = app.selected_session();
+    let approval_height = if selected.is_some_and(|s| s.has_pending_approval) {
+        6
+    } else {
+        0
+    };
+    let [approval_area, messages_area] =
+        Layout::vertical([Constraint::Length(approval_height), Constraint::Min(3)])
+            .areas(detail_area);
+
+    if let Some(session) = selected.filter(|s| s.has_pending_approval) {
+        render_approval(frame, session, approval_area);
+    }
+    render_messages(frame, app, messages_area);
+    render_footer(frame, app, footer);
+}
+
+fn render_sessions(frame: &mut Frame, app: &App, area: Rect) {
+    let sessions = app.visible_sessions();
+    let items: Vec<ListItem> = sessions
+        .iter()
+        .map(|s| {
+            let (label, color) = status_label(s);
+            let name = s
+                .custom_name
+                .as_deref()
+                .or(s.summary.as_deref())
+                .or(s.first_prompt.as_deref())
+                .unwrap_or("-");
+            let project = s
+                .project_name
+                .as_deref()
+                .or(s.project_path.split('/').next_back())
+                .unwrap_or("-");
+            ListItem::new(vec![
+                Line::from(vec![
+                    Span::styled(format!("{label:<10}"), Style::default().fg(color)),
+                    Span::raw(format!("{} ", provider_label(s.provider))),
+                    Span::styled(
+                        project.to_string(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                ]),
+                Line::from(Span::styled(
+                    format!("  {}", truncate(name, 60)),
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ])
+        })
+        .collect();
+
+    let title = if app.show_ended {
+        format!(" Sessions ({}) ", sessions.len())
+    } else {
+        format!(" Active sessions ({}) ", sessions.len())
+    };
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(Color::DarkGray))
+        .highlight_symbol("> ");
+
+    let mut state = ListState::default();
+    if !sessions.is_empty() {
+        state.select(Some(app.selected));
+    }
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn render_approval(frame: &mut Frame, session: &SessionSummary, area: Rect) {
+    let (title, body) = match session.work_status {
+        WorkStatus::Question => (
+            " Question ",
+            session
+                .pending_question
+                .as_deref()
+                .unwrap_or("(see session)"),
+        ),
+        _ => (
+            " Approval needed ",
+            session
+                .pending_tool_input
+                .as_deref()
+                .unwrap_or("(see session)"),
+        ),
+    };
+    let tool = session.pending_tool_name.as_deref().unwrap_or("unknown");
+    let text = vec![
+        Line::from(Span::styled(
+            tool.to_string(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(body.to_string()),
+    ];
+    let paragraph = Paragraph::new(text).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red))
+            .title(title),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn render_messages(frame: &mut Frame, app: &App, area: Rect) {
+    let title = match &app.open_session {
+        Some(session_id) => format!(" {} ", truncate(session_id, 40)),
+        None => " Messages (enter to open) ".to_string(),
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner_height = area.height.saturating_sub(2) as usize;
+    let inner_width = area.width.saturating_sub(2) as usize;
+
+    let mut lines: Vec<Line> = Vec::new();
+    for message in &app.messages {
+        let (role, color) = match message.message_type {
+            MessageType::User => ("you", Color::Cyan),
+            MessageType::Assistant => ("assistant", Color::Green),
+            MessageType::Tool | MessageType::ToolResult => ("tool", Color::Yellow),
+            _ => ("system", Color::DarkGray),
+        };
+        let content = match (message.message_type, message.tool_name.as_deref()) {
+            (MessageType::Tool, Some(tool)) => tool.to_string(),
+            _ => message.content.clone(),
+        };
+        lines.push(Line::from(Span::styled(
+            role,
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        )));
+        for line in content.lines().take(MESSAGE_LINES) {
+            lines.push(Line::from(truncate(line, inner_width)));
+        }
+        lines.push(Line::default());
+    }
+
+    // Keep the newest messages in view.
+    let skip = lines.len().saturating_sub(inner_height);
+    let visible: Vec<Line> = lines.into_iter().skip(skip).collect();
+    frame.render_widget(Paragraph::new(visible).block(block), area);
+}
+
+fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
+    let help =
+        "j/k move  enter open  a approve  A approve for session  d deny  e ended  esc back  q quit";
+    let text = match &app.status_line {
+        Some(status) => format!("{status}  |  {help}"),
+        None => help.to_string(),
+    };
+    frame.render_widget(
+        Paragraph::new(text).style(Style::default().fg(Color::DarkGray)),
+        area,
+    );
+}
+
+fn status_label(session: &SessionSummary) -> (&'static str, Color) {
+    match (session.status, session.work_status) {
+        (SessionStatus::Ended, _) | (_, WorkStatus::Ended) => ("ended", Color::DarkGray),
+        (_, WorkStatus::Working) => ("working", Color::Cyan),
+        (_, WorkStatus::Permission) => ("permission", Color::Red),
+        (_, WorkStatus::Question) => ("question", Color::Magenta),
+        (_, WorkStatus::Reply) => ("reply", Color::Blue),
+        (_, WorkStatus::Waiting) => ("waiting", Color::Yellow),
+    }
+}
+
+fn provider_label(provider: Provider) -> &'static str {
+    match provider {
+        Provider::Claude => "claude",
+        Provider::Codex => "codex",
+    }
+}
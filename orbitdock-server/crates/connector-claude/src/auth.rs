@@ -0,0 +1,451 @@
+//! Claude CLI account state and login.
+//!
+//! Reads who the CLI is logged in as from `claude auth status`, falling back
+//! to its credential files for builds without that command, and drives
+//! `claude auth login` so a remote client can re-authenticate the server:
+//! the login URL is handed back to open anywhere, and the code the browser
+//! shows can be pasted back through `submit_login_code`.
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use orbitdock_connector_core::ConnectorError;
+use orbitdock_protocol::ClaudeAccountStatus;
+use orbitdock_protocol::ServerMessage;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{ChildStdin, Command};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
+use tracing::warn;
+use uuid::Uuid;
+
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// How long `start_login` waits for the CLI to print its login URL.
+const LOGIN_URL_WAIT: Duration = Duration::from_secs(15);
+const STATUS_TIMEOUT: Duration = Duration::from_secs(10);
+
+const NOT_LOGGED_IN: &str =
+    "Claude CLI is not logged in. Start a login from OrbitDock, or run `claude auth login` on the server.";
+
+struct ActiveLogin {
+    login_id: String,
+    stdin: Option<ChildStdin>,
+    cancel: oneshot::Sender<()>,
+}
+
+pub struct ClaudeAuthService {
+    list_tx: broadcast::Sender<ServerMessage>,
+    active_login: Arc<Mutex<Option<ActiveLogin>>>,
+}
+
+impl ClaudeAuthService {
+    pub fn new(list_tx: broadcast::Sender<ServerMessage>) -> Self {
+        Self {
+            list_tx,
+            active_login: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub async fn read_account(&self) -> ClaudeAccountStatus {
+        Self::status_from_parts(&self.active_login).await
+    }
+
+    /// Run `claude auth login`. Returns the login ID and, when the CLI
+    /// printed one, the URL to open. Completion is broadcast as
+    /// `ClaudeLoginCompleted` followed by the new `ClaudeAccountStatus`.
+    pub async fn start_login(&self) -> Result<(String, Option<String>), String> {
+        let cli = cli_path()?;
+        let mut child = Command::new(&cli)
+            .args(["auth", "login"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| format!("failed to start `claude auth login`: {err}"))?;
+
+        let (lines_tx, mut lines_rx) = mpsc::unbounded_channel();
+        if let Some(stdout) = child.stdout.take() {
+            forward_lines(stdout, lines_tx.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward_lines(stderr, lines_tx);
+        }
+
+        let mut output = Vec::new();
+        let auth_url = tokio::time::timeout(LOGIN_URL_WAIT, async {
+            while let Some(line) = lines_rx.recv().await {
+                let url = login_url(&line);
+                output.push(line);
+                if url.is_some() {
+                    return url;
+                }
+            }
+            None
+        })
+        .await
+        .unwrap_or(None);
+
+        let login_id = Uuid::new_v4().to_string();
+        let (cancel_tx, mut cancel_rx) = oneshot::channel();
+        {
+            let mut guard = self.active_login.lock().await;
+            if let Some(existing) = guard.take() {
+                let _ = existing.cancel.send(());
+            }
+            *guard = Some(ActiveLogin {
+                login_id: login_id.clone(),
+                stdin: child.stdin.take(),
+                cancel: cancel_tx,
+            });
+        }
+
+        let active_login = self.active_login.clone();
+        let list_tx = self.list_tx.clone();
+        let login_id_for_task = login_id.clone();
+        tokio::spawn(async move {
+            enum Outcome {
+                Exited(std::io::Result<std::process::ExitStatus>),
+                Canceled,
+            }
+            let waited = tokio::time::timeout(LOGIN_TIMEOUT, async {
+                loop {
+                    tokio::select! {
+                        status = child.wait() => break Outcome::Exited(status),
+                        _ = &mut cancel_rx => break Outcome::Canceled,
+                        Some(line) = lines_rx.recv() => output.push(line),
+                    }
+                }
+            })
+            .await;
+
+            let (success, error) = match waited {
+                Ok(Outcome::Exited(Ok(status))) if status.success() => (true, None),
+                Ok(Outcome::Exited(Ok(status))) => {
+                    let detail = output
+                        .iter()
+                        .rev()
+                        .map(|line| line.trim())
+                        .find(|line| !line.is_empty())
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("`claude auth login` exited with {status}"));
+                    (false, Some(detail))
+                }
+                Ok(Outcome::Exited(Err(err))) => (false, Some(format!("Login failed: {err}"))),
+                Ok(Outcome::Canceled) => {
+                    let _ = child.start_kill();
+                    (false, Some("Login canceled".to_string()))
+                }
+                Err(_) => {
+                    let _ = child.start_kill();
+                    (false, Some("Login timed out".to_string()))
+                }
+            };
+
+            {
+                let mut guard = active_login.lock().await;
+                if guard.as_ref().map(|v| v.login_id.as_str()) == Some(login_id_for_task.as_str()) {
+                    *guard = None;
+                }
+            }
+
+            let _ = list_tx.send(ServerMessage::ClaudeLoginCompleted {
+                login_id: login_id_for_task,
+                success,
+                error,
+            });
+            let status = Self::status_from_parts(&active_login).await;
+            let _ = list_tx.send(ServerMessage::ClaudeAccountStatus { status });
+        });
+
+        Ok((login_id, auth_url))
+    }
+
+    /// Paste the code the browser showed into the running login.
+    pub async fn submit_login_code(&self, login_id: &str, code: &str) -> Result<(), String> {
+        let mut guard = self.active_login.lock().await;
+        let login = guard
+            .as_mut()
+            .filter(|login| login.login_id == login_id)
+            .ok_or_else(|| "No login in progress with that ID".to_string())?;
+        let stdin = login
+            .stdin
+            .as_mut()
+            .ok_or_else(|| "The login isn't accepting a code".to_string())?;
+        stdin
+            .write_all(format!("{}\n", code.trim()).as_bytes())
+            .await
+            .map_err(|err| format!("failed to send the code: {err}"))?;
+        stdin
+            .flush()
+            .await
+            .map_err(|err| format!("failed to send the code: {err}"))
+    }
+
+    /// Stop the running login. Returns false if `login_id` isn't it.
+    pub async fn cancel_login(&self, login_id: &str) -> bool {
+        let mut guard = self.active_login.lock().await;
+        if guard.as_ref().map(|v| v.login_id.as_str()) != Some(login_id) {
+            return false;
+        }
+        if let Some(active) = guard.take() {
+            let _ = active.cancel.send(());
+        }
+        true
+    }
+
+    pub async fn logout(&self) -> Result<ClaudeAccountStatus, String> {
+        let cli = cli_path()?;
+        let output = Command::new(&cli)
+            .args(["auth", "logout"])
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map_err(|err| format!("failed to run `claude auth logout`: {err}"))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("logout failed: {}", stderr.trim()));
+        }
+        Ok(self.read_account().await)
+    }
+
+    async fn status_from_parts(
+        active_login: &Arc<Mutex<Option<ActiveLogin>>>,
+    ) -> ClaudeAccountStatus {
+        let mut status = match cli_path() {
+            Ok(cli) => {
+                let mut status = match cli_auth_status(&cli).await {
+                    Some(status) => status,
+                    None => status_from_files(
+                        read_json(config_dir().map(|dir| dir.join(".credentials.json"))),
+                        read_json(claude_json_path()),
+                        api_key_in_env(),
+                    ),
+                };
+                if !status.logged_in {
+                    status.problem = Some(NOT_LOGGED_IN.to_string());
+                }
+                status.cli_path = Some(cli);
+                status
+            }
+            Err(problem) => ClaudeAccountStatus {
+                problem: Some(problem),
+                ..Default::default()
+            },
+        };
+        let active_login_id = active_login
+            .lock()
+            .await
+            .as_ref()
+            .map(|v| v.login_id.clone());
+        status.login_in_progress = active_login_id.is_some();
+        status.active_login_id = active_login_id;
+        status
+    }
+}
+
+fn cli_path() -> Result<String, String> {
+    crate::resolve_claude_binary().map_err(|err| match err {
+        ConnectorError::ProviderError(message) => message,
+        other => other.to_string(),
+    })
+}
+
+/// Ask the CLI. `None` when this build has no `auth status` command or its
+/// output can't be read.
+async fn cli_auth_status(cli: &str) -> Option<ClaudeAccountStatus> {
+    let output = tokio::time::timeout(
+        STATUS_TIMEOUT,
+        Command::new(cli)
+            .args(["auth", "status"])
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .map_err(|err| {
+        warn!(
+            component = "claude_auth",
+            event = "claude.auth.status_failed",
+            error = %err,
+            "Failed to run `claude auth status`"
+        );
+    })
+    .ok()?;
+    // Logged-out status is reported with a failing exit code, so read the
+    // output either way.
+    let value: Value = serde_json::from_slice(&output.stdout).ok()?;
+    status_from_cli_json(&value)
+}
+
+fn status_from_cli_json(value: &Value) -> Option<ClaudeAccountStatus> {
+    let string = |key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    Some(ClaudeAccountStatus {
+        logged_in: value.get("loggedIn")?.as_bool()?,
+        auth_method: string("authMethod"),
+        email: string("email"),
+        organization: string("orgName"),
+        subscription_type: string("subscriptionType"),
+        ..Default::default()
+    })
+}
+
+/// Work out the account from the files the CLI keeps: OAuth credentials in
+/// `.credentials.json` (the macOS CLI keeps them in the keychain instead)
+/// and the account profile in `~/.claude.json`.
+fn status_from_files(
+    credentials: Option<Value>,
+    claude_json: Option<Value>,
+    api_key: bool,
+) -> ClaudeAccountStatus {
+    let oauth = credentials
+        .as_ref()
+        .and_then(|v| v.get("claudeAiOauth"))
+        .filter(|oauth| oauth.get("accessToken").and_then(Value::as_str).is_some());
+    let account = claude_json.as_ref().and_then(|v| v.get("oauthAccount"));
+    let string = |value: Option<&Value>, key: &str| {
+        value
+            .and_then(|v| v.get(key))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+
+    if oauth.is_some() || account.is_some() {
+        ClaudeAccountStatus {
+            logged_in: true,
+            auth_method: Some("claude.ai".to_string()),
+            email: string(account, "emailAddress"),
+            organization: string(account, "organizationName"),
+            subscription_type: string(oauth, "subscriptionType"),
+            ..Default::default()
+        }
+    } else if api_key {
+        ClaudeAccountStatus {
+            logged_in: true,
+            auth_method: Some("api_key".to_string()),
+            ..Default::default()
+        }
+    } else {
+        ClaudeAccountStatus::default()
+    }
+}
+
+fn config_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("CLAUDE_CONFIG_DIR") {
+        if !dir.trim().is_empty() {
+            return Some(PathBuf::from(dir));
+        }
+    }
+    std::env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".claude"))
+}
+
+fn claude_json_path() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("CLAUDE_CONFIG_DIR") {
+        if !dir.trim().is_empty() {
+            return Some(PathBuf::from(dir).join(".claude.json"));
+        }
+    }
+    std::env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".claude.json"))
+}
+
+fn read_json(path: Option<PathBuf>) -> Option<Value> {
+    let contents = std::fs::read_to_string(path?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn api_key_in_env() -> bool {
+    std::env::var("ANTHROPIC_API_KEY").is_ok_and(|key| !key.trim().is_empty())
+}
+
+fn forward_lines(
+    reader: impl AsyncRead + Unpin + Send + 'static,
+    tx: mpsc::UnboundedSender<String>,
+) {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// The first `https://` URL on a line of login output.
+fn login_url(line: &str) -> Option<String> {
+    let start = line.find("https://")?;
+    let url = line[start..]
+        .split_whitespace()
+        .next()?
+        .trim_end_matches(['.', ',', ')', '"', '\'']);
+    Some(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn cli_status_json_is_read() {
+        let status = status_from_cli_json(&json!({
+            "loggedIn": true,
+            "authMethod": "claude.ai",
+            "email": "dev@example.com",
+            "orgName": "Example",
+            "subscriptionType": "max"
+        }))
+        .expect("status");
+        assert!(status.logged_in);
+        assert_eq!(status.email.as_deref(), Some("dev@example.com"));
+        assert_eq!(status.organization.as_deref(), Some("Example"));
+        assert_eq!(status.subscription_type.as_deref(), Some("max"));
+
+        let status = status_from_cli_json(&json!({ "loggedIn": false, "authMethod": "none" }))
+            .expect("status");
+        assert!(!status.logged_in);
+        assert!(status_from_cli_json(&json!({ "error": "unknown command" })).is_none());
+    }
+
+    #[test]
+    fn credential_files_fall_back_to_api_key_then_logged_out() {
+        let status = status_from_files(
+            Some(json!({ "claudeAiOauth": { "accessToken": "t", "subscriptionType": "pro" } })),
+            Some(json!({ "oauthAccount": { "emailAddress": "dev@example.com" } })),
+            true,
+        );
+        assert!(status.logged_in);
+        assert_eq!(status.auth_method.as_deref(), Some("claude.ai"));
+        assert_eq!(status.subscription_type.as_deref(), Some("pro"));
+        assert_eq!(status.email.as_deref(), Some("dev@example.com"));
+
+        let status = status_from_files(None, None, true);
+        assert_eq!(status.auth_method.as_deref(), Some("api_key"));
+
+        assert!(!status_from_files(Some(json!({})), None, false).logged_in);
+    }
+
+    #[test]
+    fn login_url_is_found_in_cli_output() {
+        assert_eq!(
+            login_url("Browser didn't open? Use the url below to sign in: https://claude.ai/oauth/authorize?code=true&client_id=x."),
+            Some("https://claude.ai/oauth/authorize?code=true&client_id=x".to_string())
+        );
+        assert_eq!(login_url("Paste code here if prompted >"), None);
+    }
+}
@@ -5,9 +5,11 @@
 //!
 //! Protocol reference: docs/claude-agent-sdk-protocol.md
 
+pub mod auth;
 pub mod session;
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -22,6 +24,7 @@ use tokio::sync::{mpsc, oneshot, Mutex};
 use tracing::{debug, error, info, warn};
 
 use orbitdock_connector_core::{ApprovalType, ConnectorError, ConnectorEvent};
+use orbitdock_protocol::{SessionError, SessionMcpServer};
 
 // ---------------------------------------------------------------------------
 // Stdin messages (Rust → CLI)
@@ -54,8 +57,16 @@ struct UserMessagePayload {
 #[derive(Debug, Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 enum UserContentBlock {
-    Text { text: String },
-    Image { source: ImageSource },
+    Text {
+        text: String,
+    },
+    Image {
+        source: ImageSource,
+    },
+    Document {
+        source: DocumentSource,
+        title: String,
+    },
 }
 
 #[derive(Debug, Serialize)]
@@ -71,6 +82,22 @@ enum ImageSource {
     },
 }
 
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum DocumentSource {
+    Base64 { media_type: String, data: String },
+    Text { media_type: String, data: String },
+}
+
+/// A file attached to a user message, stored on disk by the server.
+#[derive(Debug, Clone)]
+pub struct FileAttachment {
+    pub name: String,
+    /// `text/plain` or `application/pdf`
+    pub mime_type: String,
+    pub path: PathBuf,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "subtype", rename_all = "snake_case")]
 enum ControlRequestBody {
@@ -188,6 +215,33 @@ fn transform_image(image: &orbitdock_protocol::ImageInput) -> Result<UserContent
     }
 }
 
+/// Transform a file attachment to a document content block: PDFs as base64,
+/// everything else as plain text.
+fn transform_attachment(attachment: &FileAttachment) -> Result<UserContentBlock, String> {
+    let bytes = std::fs::read(&attachment.path).map_err(|e| {
+        format!(
+            "Failed to read attachment {}: {}",
+            attachment.path.display(),
+            e
+        )
+    })?;
+    let source = if attachment.mime_type == "application/pdf" {
+        DocumentSource::Base64 {
+            media_type: attachment.mime_type.clone(),
+            data: STANDARD.encode(&bytes),
+        }
+    } else {
+        DocumentSource::Text {
+            media_type: "text/plain".to_string(),
+            data: String::from_utf8_lossy(&bytes).into_owned(),
+        }
+    };
+    Ok(UserContentBlock::Document {
+        source,
+        title: attachment.name.clone(),
+    })
+}
+
 /// Parse a `data:*;base64,...` URI into `(media_type, base64_data)`.
 fn parse_data_uri_base64(uri: &str) -> Option<(String, String)> {
     let without_scheme = uri.strip_prefix("data:")?;
@@ -233,6 +287,38 @@ fn infer_media_type(path: &str) -> String {
     }
 }
 
+/// Extended-thinking budget for each `--effort` level, so a level picked
+/// mid-session can be applied with `SetMaxThinkingTokens` instead of a respawn.
+pub const EFFORT_THINKING_TOKENS: &[(&str, u64)] =
+    &[("low", 4_000), ("medium", 10_000), ("high", 31_999)];
+
+/// Thinking-token budget for an effort level, `None` if it isn't one.
+pub fn max_thinking_tokens(effort: &str) -> Option<u64> {
+    EFFORT_THINKING_TOKENS
+        .iter()
+        .find(|(level, _)| *level == effort)
+        .map(|(_, tokens)| *tokens)
+}
+
+/// A session's enabled MCP servers as `mcpServers` entries, the shape both
+/// `--mcp-config` and the `mcp_set_servers` control request take.
+pub fn mcp_servers_config(servers: &[SessionMcpServer]) -> Value {
+    let entries = servers
+        .iter()
+        .filter(|server| server.enabled)
+        .map(|server| {
+            let entry = serde_json::json!({
+                "type": "stdio",
+                "command": server.command,
+                "args": server.args,
+                "env": server.env,
+            });
+            (server.name.clone(), entry)
+        })
+        .collect();
+    Value::Object(entries)
+}
+
 // ---------------------------------------------------------------------------
 // ClaudeConnector
 // ---------------------------------------------------------------------------
@@ -256,10 +342,12 @@ pub struct ClaudeConnector {
     pending_controls: Arc<Mutex<HashMap<String, oneshot::Sender<Value>>>>,
     pending_approvals: Arc<Mutex<HashMap<String, PendingApproval>>>,
     models: Arc<Mutex<Vec<orbitdock_protocol::ClaudeModelOption>>>,
+    pid: Option<u32>,
 }
 
 impl ClaudeConnector {
     /// Spawn a new `claude` CLI subprocess.
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         cwd: &str,
         model: Option<&str>,
@@ -268,6 +356,8 @@ impl ClaudeConnector {
         allowed_tools: &[String],
         disallowed_tools: &[String],
         effort: Option<&str>,
+        append_system_prompt: Option<&str>,
+        mcp_servers: &[SessionMcpServer],
     ) -> Result<Self, ConnectorError> {
         let claude_bin = resolve_claude_binary()?;
 
@@ -302,8 +392,21 @@ impl ClaudeConnector {
         if let Some(e) = effort {
             args.extend(["--effort", e]);
         }
+        if let Some(prompt) = append_system_prompt {
+            args.extend(["--append-system-prompt", prompt]);
+        }
+
+        // Logged by name only: env values are often credentials.
+        let mut args_display = args.join(" ");
+        let mcp_config = mcp_servers_config(mcp_servers);
+        let mcp_config_json;
+        if let Some(entries) = mcp_config.as_object().filter(|entries| !entries.is_empty()) {
+            let names: Vec<&str> = entries.keys().map(String::as_str).collect();
+            args_display.push_str(&format!(" --mcp-config <{}>", names.join(",")));
+            mcp_config_json = serde_json::json!({ "mcpServers": mcp_config }).to_string();
+            args.extend(["--mcp-config", &mcp_config_json]);
+        }
 
-        let args_display = args.join(" ");
         info!(
             component = "claude_connector",
             event = "claude.spawn",
@@ -336,6 +439,7 @@ impl ClaudeConnector {
                 ConnectorError::ProviderError(format!("Failed to spawn claude CLI: {}", e))
             })?;
 
+        let pid = child.id();
         let stdin = child
             .stdin
             .take()
@@ -455,6 +559,7 @@ impl ClaudeConnector {
             pending_controls,
             pending_approvals,
             models: models.clone(),
+            pid,
         };
 
         // Send initialize control request — kill the child if it fails, and parse models from response
@@ -526,6 +631,11 @@ impl ClaudeConnector {
         Ok(connector)
     }
 
+    /// OS process id of the `claude` CLI, for resource sampling.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
     /// Take the event receiver (can only be called once).
     pub fn take_event_rx(&mut self) -> Option<mpsc::Receiver<ConnectorEvent>> {
         self.event_rx.take()
@@ -537,13 +647,22 @@ impl ClaudeConnector {
     }
 
     /// Send a user message to start or continue a turn.
+    ///
+    /// The CLI fixes its model at spawn, so a model override is applied by
+    /// restarting the process (see `ClaudeSession::model_switch`), not here.
+    /// An effort level becomes the thinking budget for this and later turns.
     pub async fn send_message(
         &self,
         content: &str,
         _model: Option<&str>,
-        _effort: Option<&str>,
+        effort: Option<&str>,
         images: &[orbitdock_protocol::ImageInput],
+        attachments: &[FileAttachment],
     ) -> Result<(), ConnectorError> {
+        if let Some(tokens) = effort.and_then(max_thinking_tokens) {
+            self.set_max_thinking(tokens).await?;
+        }
+
         let mut content_blocks = vec![UserContentBlock::Text {
             text: content.to_string(),
         }];
@@ -563,6 +682,20 @@ impl ClaudeConnector {
             }
         }
 
+        for attachment in attachments {
+            match transform_attachment(attachment) {
+                Ok(block) => content_blocks.push(block),
+                Err(e) => {
+                    warn!(
+                        event = "claude.attachment.transform_failed",
+                        error = %e,
+                        name = %attachment.name,
+                        "Failed to read attachment, skipping"
+                    );
+                }
+            }
+        }
+
         let msg = StdinMessage::User {
             session_id: String::new(),
             message: UserMessagePayload {
@@ -787,7 +920,8 @@ impl ClaudeConnector {
         Ok(())
     }
 
-    /// Set MCP servers configuration.
+    /// Replace the MCP servers the SDK manages for this session (those given
+    /// with `--mcp-config`). Takes [`mcp_servers_config`]'s shape.
     pub async fn mcp_set_servers(&self, servers: Value) -> Result<Value, ConnectorError> {
         self.send_control_request(ControlRequestBody::McpSetServers { servers })
             .await
@@ -1449,7 +1583,11 @@ impl ClaudeConnector {
                         duration_ms: None,
                     });
                 }
-                events.push(ConnectorEvent::ContextCompacted);
+                let tokens_before = raw
+                    .get("compact_metadata")
+                    .and_then(|metadata| metadata.get("pre_tokens"))
+                    .and_then(Value::as_u64);
+                events.push(ConnectorEvent::ContextCompacted { tokens_before });
                 events
             }
             "hook_started" => {
@@ -1628,6 +1766,7 @@ impl ClaudeConnector {
                             "Context compaction in progress — showing indicator"
                         );
 
+                        events.push(ConnectorEvent::ContextCompactionStarted);
                         events.push(ConnectorEvent::MessageCreated(
                             orbitdock_protocol::Message {
                                 id: msg_id,
@@ -1837,6 +1976,14 @@ impl ClaudeConnector {
                         },
                     ));
 
+                    if tool_name == "TodoWrite" {
+                        if let Some(plan) =
+                            input_value.and_then(orbitdock_connector_core::plan::from_todo_write)
+                        {
+                            events.push(ConnectorEvent::PlanStepsUpdated(plan));
+                        }
+                    }
+
                     // Build an aggregated per-turn patch diff stream from direct edit/write tools.
                     if let Some(payload) = input_value {
                         if let Some(diff) = Self::patch_diff_for_tool_use(Some(tool_name), payload)
@@ -2180,6 +2327,18 @@ impl ClaudeConnector {
                 subtype.to_string()
             };
             events.push(ConnectorEvent::TurnAborted { reason });
+            // API failures (auth, rate limits, overload) end a turn as a
+            // "success" result flagged `is_error`, with the error as its text.
+            // `error_*` subtypes are interrupts and turn limits, not failures.
+            if is_error && !subtype.starts_with("error") {
+                if let Some(message) = raw
+                    .get("result")
+                    .and_then(Value::as_str)
+                    .filter(|message| !message.trim().is_empty())
+                {
+                    events.push(ConnectorEvent::Error(SessionError::from_message(message)));
+                }
+            }
         } else {
             events.push(ConnectorEvent::TurnCompleted);
         }
@@ -2637,7 +2796,7 @@ fn extract_token_usage(
 /// 1. CLAUDE_BIN env var
 /// 2. ~/.claude/local/claude
 /// 3. Search PATH via `which`
-fn resolve_claude_binary() -> Result<String, ConnectorError> {
+pub fn resolve_claude_binary() -> Result<String, ConnectorError> {
     // 1. Env var override
     if let Ok(path) = std::env::var("CLAUDE_BIN") {
         if std::path::Path::new(&path).exists() {
@@ -2684,11 +2843,35 @@ mod tests {
     use tokio::sync::Mutex;
 
     use super::{
-        parse_data_uri_base64, transform_image, ClaudeConnector, ImageSource, PendingApproval,
+        mcp_servers_config, parse_data_uri_base64, transform_attachment, transform_image,
+        ClaudeConnector, DocumentSource, FileAttachment, ImageSource, PendingApproval,
         UserContentBlock,
     };
     use crate::ConnectorEvent;
 
+    #[test]
+    fn mcp_servers_config_skips_disabled_servers() {
+        let server = |name: &str, enabled: bool| orbitdock_protocol::SessionMcpServer {
+            name: name.to_string(),
+            command: "npx".to_string(),
+            args: vec!["-y".to_string(), "server-github".to_string()],
+            env: [("GITHUB_TOKEN".to_string(), "ghp_x".to_string())].into(),
+            enabled,
+        };
+        let config = mcp_servers_config(&[server("github", true), server("linear", false)]);
+        assert_eq!(
+            config,
+            json!({
+                "github": {
+                    "type": "stdio",
+                    "command": "npx",
+                    "args": ["-y", "server-github"],
+                    "env": { "GITHUB_TOKEN": "ghp_x" },
+                }
+            })
+        );
+    }
+
     #[test]
     fn parse_data_uri_base64_extracts_media_type_and_payload() {
         let uri = "data:image/png;base64,aGVs\nbG8=";
@@ -2730,6 +2913,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn transform_attachment_sends_text_inline_and_pdfs_as_base64() {
+        let dir = std::env::temp_dir().join(format!(
+            "orbitdock-claude-attachments-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).expect("create dir");
+        let log = dir.join("build.log");
+        std::fs::write(&log, "error: linker failed\n").expect("write log");
+        let pdf = dir.join("spec.pdf");
+        std::fs::write(&pdf, b"%PDF-1.7").expect("write pdf");
+
+        let block = transform_attachment(&FileAttachment {
+            name: "build.log".to_string(),
+            mime_type: "text/plain".to_string(),
+            path: log,
+        })
+        .expect("transform should succeed");
+        match block {
+            UserContentBlock::Document {
+                source: DocumentSource::Text { data, .. },
+                title,
+            } => {
+                assert_eq!(data, "error: linker failed\n");
+                assert_eq!(title, "build.log");
+            }
+            other => panic!("expected text document, got {:?}", other),
+        }
+
+        let block = transform_attachment(&FileAttachment {
+            name: "spec.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            path: pdf,
+        })
+        .expect("transform should succeed");
+        match block {
+            UserContentBlock::Document {
+                source: DocumentSource::Base64 { media_type, data },
+                ..
+            } => {
+                assert_eq!(media_type, "application/pdf");
+                assert_eq!(data, "JVBERi0xLjc=");
+            }
+            other => panic!("expected base64 document, got {:?}", other),
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[tokio::test]
     async fn handle_cli_control_request_accepts_camel_case_permission_fields() {
         let pending_approvals: Arc<Mutex<HashMap<String, PendingApproval>>> =
@@ -2940,6 +3171,71 @@ mod tests {
         assert!(has_diff, "expected DiffUpdated with patch-like content");
     }
 
+    #[test]
+    fn handle_assistant_message_emits_plan_steps_for_todo_write() {
+        let raw = json!({
+            "type": "assistant",
+            "message": {
+                "content": [
+                    {
+                        "type": "tool_use",
+                        "id": "toolu-todo-1",
+                        "name": "TodoWrite",
+                        "input": {
+                            "todos": [
+                                { "content": "Read the code", "status": "completed", "activeForm": "Reading" },
+                                { "content": "Fix the bug", "status": "in_progress", "activeForm": "Fixing" }
+                            ]
+                        }
+                    }
+                ]
+            }
+        });
+
+        let mut streaming_content = String::new();
+        let mut streaming_msg_id = None;
+        let mut turn_patch_diffs = Vec::new();
+        let mut last_turn_input = None;
+        let mut cumulative_output = 0;
+        let mut last_context_window = 200_000;
+        let msg_counter = Arc::new(AtomicU64::new(1));
+
+        let events = ClaudeConnector::handle_assistant_message(
+            &raw,
+            "sess-1",
+            &msg_counter,
+            &mut streaming_content,
+            &mut streaming_msg_id,
+            &mut turn_patch_diffs,
+            &mut last_turn_input,
+            &mut cumulative_output,
+            &mut last_context_window,
+        );
+
+        let plan = events
+            .iter()
+            .find_map(|event| match event {
+                ConnectorEvent::PlanStepsUpdated(plan) => Some(plan),
+                _ => None,
+            })
+            .expect("expected PlanStepsUpdated");
+        let steps: Vec<_> = plan
+            .steps
+            .iter()
+            .map(|step| (step.title.as_str(), step.status))
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                ("Read the code", orbitdock_protocol::PlanStepStatus::Done),
+                (
+                    "Fix the bug",
+                    orbitdock_protocol::PlanStepStatus::InProgress
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn handle_assistant_message_aggregates_patch_diffs_across_events() {
         let raw_edit = json!({
@@ -3023,4 +3319,44 @@ mod tests {
             "expected combined diff for both edits"
         );
     }
+
+    fn result_events(raw: &Value) -> Vec<ConnectorEvent> {
+        ClaudeConnector::handle_result_message(
+            raw,
+            &mut String::new(),
+            &mut None,
+            &mut None,
+            &mut 0,
+            &mut 200_000,
+        )
+    }
+
+    #[test]
+    fn handle_result_message_reports_api_errors_after_aborting_the_turn() {
+        let events = result_events(&json!({
+            "type": "result",
+            "subtype": "success",
+            "is_error": true,
+            "result": "Invalid API key · Please run /login"
+        }));
+        let aborted = events
+            .iter()
+            .position(|event| matches!(event, ConnectorEvent::TurnAborted { .. }))
+            .expect("expected turn aborted");
+        match events.get(aborted + 1) {
+            Some(ConnectorEvent::Error(error)) => {
+                assert_eq!(error.category, orbitdock_protocol::ErrorCategory::Auth);
+            }
+            other => panic!("expected error event after the abort, got {other:?}"),
+        }
+
+        let interrupted = result_events(&json!({
+            "type": "result",
+            "subtype": "error_during_execution",
+            "is_error": true
+        }));
+        assert!(!interrupted
+            .iter()
+            .any(|event| matches!(event, ConnectorEvent::Error(_))));
+    }
 }
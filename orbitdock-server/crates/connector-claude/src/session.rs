@@ -7,9 +7,9 @@
 use std::collections::HashMap;
 
 use orbitdock_connector_core::ConnectorError;
-use orbitdock_protocol::ProviderSessionId;
+use orbitdock_protocol::{ProviderSessionId, SessionMcpServer};
 
-use crate::ClaudeConnector;
+use crate::{ClaudeConnector, FileAttachment};
 
 /// Actions that can be sent to a Claude session
 #[allow(dead_code)]
@@ -19,6 +19,7 @@ pub enum ClaudeAction {
         model: Option<String>,
         effort: Option<String>,
         images: Vec<orbitdock_protocol::ImageInput>,
+        attachments: Vec<FileAttachment>,
     },
     Interrupt,
     ApproveTool {
@@ -53,6 +54,7 @@ pub enum ClaudeAction {
         content: String,
         message_id: String,
         images: Vec<orbitdock_protocol::ImageInput>,
+        attachments: Vec<FileAttachment>,
     },
     RewindFiles {
         user_message_id: String,
@@ -84,6 +86,11 @@ pub enum ClaudeAction {
         reply: tokio::sync::oneshot::Sender<Result<serde_json::Value, ConnectorError>>,
     },
     EndSession,
+    /// Replace the CLI process with a fresh `--resume` of the same session.
+    /// Handled by the owning event loop, which knows the spawn options.
+    RestartProcess {
+        reason: String,
+    },
 }
 
 impl std::fmt::Debug for ClaudeAction {
@@ -94,12 +101,14 @@ impl std::fmt::Debug for ClaudeAction {
                 model,
                 effort,
                 images,
+                attachments,
             } => f
                 .debug_struct("SendMessage")
                 .field("content_len", &content.len())
                 .field("model", model)
                 .field("effort", effort)
                 .field("images_count", &images.len())
+                .field("attachments_count", &attachments.len())
                 .finish(),
             Self::Interrupt => write!(f, "Interrupt"),
             Self::ApproveTool {
@@ -142,11 +151,13 @@ impl std::fmt::Debug for ClaudeAction {
                 content,
                 message_id,
                 images,
+                attachments,
             } => f
                 .debug_struct("SteerTurn")
                 .field("content_len", &content.len())
                 .field("message_id", message_id)
                 .field("images_count", &images.len())
+                .field("attachments_count", &attachments.len())
                 .finish(),
             Self::RewindFiles { user_message_id } => f
                 .debug_struct("RewindFiles")
@@ -181,6 +192,10 @@ impl std::fmt::Debug for ClaudeAction {
             Self::ApplyFlagSettings { .. } => write!(f, "ApplyFlagSettings"),
             Self::GetSettings { .. } => write!(f, "GetSettings"),
             Self::EndSession => write!(f, "EndSession"),
+            Self::RestartProcess { reason } => f
+                .debug_struct("RestartProcess")
+                .field("reason", reason)
+                .finish(),
         }
     }
 }
@@ -189,6 +204,8 @@ impl std::fmt::Debug for ClaudeAction {
 pub struct ClaudeSession {
     pub session_id: String,
     pub connector: ClaudeConnector,
+    /// Model the CLI process was spawned with (`None` = CLI default).
+    pub model: Option<String>,
 }
 
 impl ClaudeSession {
@@ -205,6 +222,8 @@ impl ClaudeSession {
         allowed_tools: &[String],
         disallowed_tools: &[String],
         effort: Option<&str>,
+        append_system_prompt: Option<&str>,
+        mcp_servers: &[SessionMcpServer],
     ) -> Result<Self, ConnectorError> {
         let connector = ClaudeConnector::new(
             cwd,
@@ -214,14 +233,24 @@ impl ClaudeSession {
             allowed_tools,
             disallowed_tools,
             effort,
+            append_system_prompt,
+            mcp_servers,
         )
         .await?;
         Ok(Self {
             session_id,
             connector,
+            model: model.map(str::to_string),
         })
     }
 
+    /// The model a `SendMessage` override asks to switch to, if it differs
+    /// from the one this process runs. The CLI fixes its model at spawn, so
+    /// the event loop restarts it with `--resume` before sending.
+    pub fn model_switch<'a>(&self, requested: Option<&'a str>) -> Option<&'a str> {
+        model_switch_target(self.model.as_deref(), requested)
+    }
+
     /// Handle an action from the WebSocket.
     pub async fn handle_action(
         connector: &ClaudeConnector,
@@ -233,9 +262,16 @@ impl ClaudeSession {
                 model,
                 effort,
                 images,
+                attachments,
             } => {
                 connector
-                    .send_message(&content, model.as_deref(), effort.as_deref(), &images)
+                    .send_message(
+                        &content,
+                        model.as_deref(),
+                        effort.as_deref(),
+                        &images,
+                        &attachments,
+                    )
                     .await?;
             }
             ClaudeAction::Interrupt => {
@@ -266,11 +302,15 @@ impl ClaudeSession {
             }
             ClaudeAction::Compact => {
                 // Send /compact as a user message — the CLI handles it as a slash command.
-                connector.send_message("/compact", None, None, &[]).await?;
+                connector
+                    .send_message("/compact", None, None, &[], &[])
+                    .await?;
             }
             ClaudeAction::Undo => {
                 // Send /undo as a slash command
-                connector.send_message("/undo", None, None, &[]).await?;
+                connector
+                    .send_message("/undo", None, None, &[], &[])
+                    .await?;
             }
             ClaudeAction::Resume { .. } => {
                 // Resume is handled at spawn time via --resume flag.
@@ -298,13 +338,16 @@ impl ClaudeSession {
                 connector.set_permission_mode(&mode).await?;
             }
             ClaudeAction::SteerTurn {
-                content, images, ..
+                content,
+                images,
+                attachments,
+                ..
             } => {
                 // Write directly to stdin — CLI queues mid-turn messages naturally.
                 // No interrupt needed: the SDK's streamInput just enqueues user
                 // messages and the CLI processes them when the current turn yields.
                 connector
-                    .send_message(&content, None, None, &images)
+                    .send_message(&content, None, None, &images, &attachments)
                     .await?;
             }
             ClaudeAction::RewindFiles { user_message_id } => {
@@ -344,11 +387,17 @@ impl ClaudeSession {
             ClaudeAction::EndSession => {
                 connector.shutdown().await?;
             }
+            // The connector can't respawn itself; the event loop swaps it out.
+            ClaudeAction::RestartProcess { .. } => {}
         }
         Ok(())
     }
 }
 
+fn model_switch_target<'a>(current: Option<&str>, requested: Option<&'a str>) -> Option<&'a str> {
+    requested.filter(|model| current != Some(*model))
+}
+
 /// Returns true if the shadow session should be cleaned up at runtime.
 /// Guards against accidentally deleting the owning direct session when
 /// the SDK session id is identical to the OrbitDock session id.
@@ -361,7 +410,7 @@ pub fn should_remove_shadow_runtime_session(
 
 #[cfg(test)]
 mod tests {
-    use super::should_remove_shadow_runtime_session;
+    use super::{model_switch_target, should_remove_shadow_runtime_session};
 
     #[test]
     fn shadow_cleanup_skips_owning_session_id() {
@@ -378,4 +427,15 @@ mod tests {
             "hook-shadow-session"
         ));
     }
+
+    #[test]
+    fn model_switch_only_when_override_differs() {
+        assert_eq!(model_switch_target(Some("sonnet"), None), None);
+        assert_eq!(model_switch_target(Some("sonnet"), Some("sonnet")), None);
+        assert_eq!(
+            model_switch_target(Some("sonnet"), Some("opus")),
+            Some("opus")
+        );
+        assert_eq!(model_switch_target(None, Some("opus")), Some("opus"));
+    }
 }
@@ -15,6 +15,9 @@ use orbitdock_protocol::CodexAccount;
 use orbitdock_protocol::CodexAccountStatus;
 use orbitdock_protocol::CodexAuthMode;
 use orbitdock_protocol::CodexLoginCancelStatus;
+use orbitdock_protocol::CodexRateLimitWindow;
+use orbitdock_protocol::CodexUsageSnapshot;
+use orbitdock_protocol::RateLimitInfo;
 use orbitdock_protocol::ServerMessage;
 use tokio::sync::broadcast;
 use tokio::sync::Mutex;
@@ -23,12 +26,24 @@ use uuid::Uuid;
 
 const LOGIN_CHATGPT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
 
+/// Percent of a rate-limit window at which sessions are warned.
+const USAGE_WARN_PERCENT: f64 = 90.0;
+
 #[derive(Clone)]
 struct ActiveLogin {
     login_id: String,
     shutdown_handle: ShutdownHandle,
 }
 
+/// The latest rate limits Codex reported, and which windows have already
+/// been warned about.
+#[derive(Default)]
+struct UsageState {
+    latest: Option<CodexUsageSnapshot>,
+    warned_primary: bool,
+    warned_secondary: bool,
+}
+
 enum ServiceState {
     Ready {
         auth_manager: Arc<AuthManager>,
@@ -44,6 +59,7 @@ pub struct CodexAuthService {
     state: ServiceState,
     list_tx: broadcast::Sender<ServerMessage>,
     active_login: Arc<Mutex<Option<ActiveLogin>>>,
+    usage: Arc<Mutex<UsageState>>,
 }
 
 impl CodexAuthService {
@@ -61,6 +77,7 @@ impl CodexAuthService {
                     },
                     list_tx,
                     active_login: Arc::new(Mutex::new(None)),
+                    usage: Arc::new(Mutex::new(UsageState::default())),
                 }
             }
             Err(err) => Self {
@@ -69,6 +86,7 @@ impl CodexAuthService {
                 },
                 list_tx,
                 active_login: Arc::new(Mutex::new(None)),
+                usage: Arc::new(Mutex::new(UsageState::default())),
             },
         }
     }
@@ -91,6 +109,37 @@ impl CodexAuthService {
         Ok(self.status_from_auth_manager(&auth_manager).await)
     }
 
+    /// Record rate limits a Codex session reported. Changed limits are
+    /// broadcast as `CodexAccountUsage`; a window that just reached
+    /// `USAGE_WARN_PERCENT` comes back as a warning for the session.
+    pub async fn record_usage(&self, mut usage: CodexUsageSnapshot) -> Option<RateLimitInfo> {
+        if usage.plan_type.is_none() {
+            if let Ok(auth_manager) = self.auth_manager() {
+                usage.plan_type = auth_manager
+                    .auth()
+                    .await
+                    .and_then(|auth| auth.account_plan_type())
+                    .map(|value| format!("{value:?}").to_lowercase());
+            }
+        }
+
+        let mut state = self.usage.lock().await;
+        let changed = state
+            .latest
+            .as_ref()
+            .is_none_or(|latest| latest.limits_changed(&usage));
+        let warning = usage_warning(&mut state, &usage);
+        state.latest = Some(usage.clone());
+        drop(state);
+
+        if changed {
+            let _ = self
+                .list_tx
+                .send(ServerMessage::CodexAccountUsage { usage });
+        }
+        warning
+    }
+
     pub async fn start_chatgpt_login(&self) -> Result<(String, String), String> {
         let (auth_manager, codex_home, credentials_store_mode) = self.ready_parts()?;
 
@@ -122,6 +171,7 @@ impl CodexAuthService {
         }
 
         let active_login = self.active_login.clone();
+        let usage = self.usage.clone();
         let list_tx = self.list_tx.clone();
         let login_id_for_task = login_id.clone();
         tokio::spawn(async move {
@@ -156,7 +206,7 @@ impl CodexAuthService {
                 error,
             });
 
-            let status = Self::status_from_parts(&auth_manager, &active_login).await;
+            let status = Self::status_from_parts(&auth_manager, &active_login, &usage).await;
             if success {
                 let _ = list_tx.send(ServerMessage::CodexAccountUpdated {
                     status: status.clone(),
@@ -227,12 +277,13 @@ impl CodexAuthService {
         &self,
         auth_manager: &Arc<AuthManager>,
     ) -> CodexAccountStatus {
-        Self::status_from_parts(auth_manager, &self.active_login).await
+        Self::status_from_parts(auth_manager, &self.active_login, &self.usage).await
     }
 
     async fn status_from_parts(
         auth_manager: &Arc<AuthManager>,
         active_login: &Arc<Mutex<Option<ActiveLogin>>>,
+        usage: &Arc<Mutex<UsageState>>,
     ) -> CodexAccountStatus {
         let auth = auth_manager.auth().await;
         let active_login_id = active_login
@@ -247,6 +298,7 @@ impl CodexAuthService {
             account,
             login_in_progress: active_login_id.is_some(),
             active_login_id,
+            usage: usage.lock().await.latest.clone(),
         }
     }
 
@@ -269,3 +321,77 @@ impl CodexAuthService {
         }
     }
 }
+
+/// A warning for the fullest window that just reached `USAGE_WARN_PERCENT`.
+/// A window re-arms once it drops back below, as it does when it resets.
+fn usage_warning(state: &mut UsageState, usage: &CodexUsageSnapshot) -> Option<RateLimitInfo> {
+    let mut crossed: Option<(&'static str, &CodexRateLimitWindow)> = None;
+    for (name, window, warned) in [
+        ("primary", &usage.primary, &mut state.warned_primary),
+        ("secondary", &usage.secondary, &mut state.warned_secondary),
+    ] {
+        let Some(window) = window else { continue };
+        if window.used_percent < USAGE_WARN_PERCENT {
+            *warned = false;
+        } else if !*warned {
+            *warned = true;
+            if crossed.is_none_or(|(_, fullest)| window.used_percent > fullest.used_percent) {
+                crossed = Some((name, window));
+            }
+        }
+    }
+
+    let (name, window) = crossed?;
+    Some(RateLimitInfo {
+        status: if window.used_percent >= 100.0 {
+            "rejected".to_string()
+        } else {
+            "allowed_warning".to_string()
+        },
+        resets_at: (window.resets_at_unix > 0.0)
+            .then(|| crate::iso_from_unix_secs(window.resets_at_unix as u64)),
+        rate_limit_type: Some(name.to_string()),
+        utilization: Some(window.used_percent / 100.0),
+        is_using_overage: None,
+        overage_status: None,
+        surpassed_threshold: Some(USAGE_WARN_PERCENT / 100.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(primary: f64, secondary: f64) -> CodexUsageSnapshot {
+        let window = |used_percent| CodexRateLimitWindow {
+            used_percent,
+            window_duration_mins: 300,
+            resets_at_unix: 1_760_000_000.0,
+        };
+        CodexUsageSnapshot {
+            primary: Some(window(primary)),
+            secondary: Some(window(secondary)),
+            plan_type: None,
+            fetched_at_unix: 0.0,
+        }
+    }
+
+    #[test]
+    fn usage_warns_once_per_window_until_it_resets() {
+        let mut state = UsageState::default();
+        assert!(usage_warning(&mut state, &usage(50.0, 20.0)).is_none());
+
+        let warning = usage_warning(&mut state, &usage(91.0, 20.0)).expect("warning");
+        assert_eq!(warning.status, "allowed_warning");
+        assert_eq!(warning.rate_limit_type.as_deref(), Some("primary"));
+        assert_eq!(warning.resets_at.as_deref(), Some("2025-10-09T08:53:20Z"));
+        assert!(usage_warning(&mut state, &usage(95.0, 20.0)).is_none());
+
+        let warning = usage_warning(&mut state, &usage(96.0, 100.0)).expect("warning");
+        assert_eq!(warning.status, "rejected");
+        assert_eq!(warning.rate_limit_type.as_deref(), Some("secondary"));
+
+        assert!(usage_warning(&mut state, &usage(3.0, 100.0)).is_none());
+        assert!(usage_warning(&mut state, &usage(92.0, 100.0)).is_some());
+    }
+}
@@ -4,6 +4,7 @@
 //! No subprocess, no JSON-RPC — just Rust function calls.
 
 pub mod auth;
+mod mcp_servers;
 pub mod rollout_parser;
 pub mod session;
 
@@ -35,6 +36,7 @@ use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
 use orbitdock_connector_core::{ApprovalType, ConnectorError, ConnectorEvent};
+use orbitdock_protocol::{ErrorCategory, SessionError, SessionMcpServer};
 
 /// Outcome of a steer_turn attempt
 pub enum SteerOutcome {
@@ -164,6 +166,8 @@ impl CodexConnector {
         model: Option<&str>,
         approval_policy: Option<&str>,
         sandbox_mode: Option<&str>,
+        developer_instructions: Option<&str>,
+        mcp_servers: &[SessionMcpServer],
     ) -> Result<Self, ConnectorError> {
         info!("Creating codex-core connector for {}", cwd);
 
@@ -193,6 +197,8 @@ impl CodexConnector {
             model,
             approval_policy,
             sandbox_mode,
+            developer_instructions,
+            mcp_servers,
             thread_manager.as_ref(),
         )
         .await?;
@@ -213,6 +219,8 @@ impl CodexConnector {
         model: Option<&str>,
         approval_policy: Option<&str>,
         sandbox_mode: Option<&str>,
+        developer_instructions: Option<&str>,
+        mcp_servers: &[SessionMcpServer],
     ) -> Result<Self, ConnectorError> {
         info!(
             "Resuming codex-core connector for {} with thread {}",
@@ -257,6 +265,8 @@ impl CodexConnector {
             model,
             approval_policy,
             sandbox_mode,
+            developer_instructions,
+            mcp_servers,
             thread_manager.as_ref(),
         )
         .await?;
@@ -277,6 +287,8 @@ impl CodexConnector {
         model: Option<&str>,
         approval_policy: Option<&str>,
         sandbox_mode: Option<&str>,
+        developer_instructions: Option<&str>,
+        mcp_servers: &[SessionMcpServer],
         thread_manager: &ThreadManager,
     ) -> Result<Config, ConnectorError> {
         let mut cli_overrides = Vec::new();
@@ -301,6 +313,18 @@ impl CodexConnector {
             ));
         }
 
+        // Extra instructions layered on top of Codex's own (e.g. from the
+        // repo's .orbitdock.toml)
+        if let Some(instructions) = developer_instructions {
+            cli_overrides.push((
+                "developer_instructions".to_string(),
+                toml::Value::String(instructions.to_string()),
+            ));
+        }
+
+        // MCP servers added to this session in OrbitDock
+        cli_overrides.extend(mcp_servers::config_overrides(mcp_servers));
+
         // Reasoning trace defaults for OrbitDock direct sessions. These can be
         // overridden via environment variables for troubleshooting.
         let show_raw_reasoning = parse_bool_env(ENV_CODEX_SHOW_RAW_REASONING)
@@ -433,6 +457,8 @@ impl CodexConnector {
             model,
             approval_policy,
             sandbox_mode,
+            None,
+            &[],
             self.thread_manager.as_ref(),
         )
         .await?;
@@ -498,7 +524,10 @@ impl CodexConnector {
                 Err(e) => {
                     error!("Error reading codex event: {}", e);
                     let _ = tx
-                        .send(ConnectorEvent::Error(format!("Event read error: {}", e)))
+                        .send(ConnectorEvent::Error(SessionError::new(
+                            ErrorCategory::Process,
+                            format!("Event read error: {}", e),
+                        )))
                         .await;
                     return;
                 }
@@ -1546,6 +1575,7 @@ impl CodexConnector {
             }
 
             EventMsg::TokenCount(e) => {
+                let mut events = Vec::new();
                 if let Some(info) = e.info {
                     let last = &info.last_token_usage;
                     let usage = orbitdock_protocol::TokenUsage {
@@ -1554,13 +1584,19 @@ impl CodexConnector {
                         cached_tokens: last.cached_input_tokens.max(0) as u64,
                         context_window: info.model_context_window.unwrap_or(200_000).max(0) as u64,
                     };
-                    vec![ConnectorEvent::TokensUpdated {
+                    events.push(ConnectorEvent::TokensUpdated {
                         usage,
                         snapshot_kind: orbitdock_protocol::TokenUsageSnapshotKind::ContextTurn,
-                    }]
-                } else {
-                    vec![]
+                    });
                 }
+                if let Some(usage) = e
+                    .rate_limits
+                    .and_then(|limits| serde_json::to_value(limits).ok())
+                    .and_then(|limits| codex_usage_snapshot(&limits))
+                {
+                    events.push(ConnectorEvent::AccountUsageUpdated(usage));
+                }
+                events
             }
 
             EventMsg::TurnDiff(e) => {
@@ -1592,10 +1628,11 @@ impl CodexConnector {
                     duration_ms: None,
                     images: vec![],
                 };
-                vec![
-                    ConnectorEvent::PlanUpdated(plan),
-                    ConnectorEvent::MessageCreated(message),
-                ]
+                let steps = orbitdock_connector_core::plan::from_codex_update(&plan);
+                let mut events = vec![ConnectorEvent::PlanUpdated(plan)];
+                events.extend(steps.map(ConnectorEvent::PlanStepsUpdated));
+                events.push(ConnectorEvent::MessageCreated(message));
+                events
             }
 
             EventMsg::PlanDelta(e) => {
@@ -1868,7 +1905,10 @@ impl CodexConnector {
             }
 
             EventMsg::Error(e) => {
-                vec![ConnectorEvent::Error(e.message)]
+                vec![ConnectorEvent::Error(codex_session_error(
+                    e.message,
+                    e.codex_error_info.as_ref(),
+                ))]
             }
 
             EventMsg::StreamError(e) => {
@@ -2209,7 +2249,10 @@ impl CodexConnector {
                         duration_ms: None,
                         images: vec![],
                     };
-                    vec![ConnectorEvent::MessageCreated(message)]
+                    vec![
+                        ConnectorEvent::ContextCompactionStarted,
+                        ConnectorEvent::MessageCreated(message),
+                    ]
                 }
                 _ => vec![],
             },
@@ -2494,7 +2537,9 @@ impl CodexConnector {
             }
 
             EventMsg::ContextCompacted(_) => {
-                vec![ConnectorEvent::ContextCompacted]
+                vec![ConnectorEvent::ContextCompacted {
+                    tokens_before: None,
+                }]
             }
 
             EventMsg::UndoStarted(e) => {
@@ -2952,10 +2997,14 @@ impl CodexConnector {
         Ok(())
     }
 
-    /// Refresh MCP servers (reinitialize and refresh cached tool lists)
-    pub async fn refresh_mcp_servers(&self) -> Result<(), ConnectorError> {
+    /// Refresh MCP servers (reinitialize and refresh cached tool lists), with
+    /// the session's own servers on top of Codex's config
+    pub async fn refresh_mcp_servers(
+        &self,
+        session_servers: &[SessionMcpServer],
+    ) -> Result<(), ConnectorError> {
         let config = McpServerRefreshConfig {
-            mcp_servers: serde_json::Value::Object(Default::default()),
+            mcp_servers: mcp_servers::refresh_servers(&self.codex_home, session_servers),
             mcp_oauth_credentials_store_mode: serde_json::Value::Null,
         };
         let op = Op::RefreshMcpServers { config };
@@ -3582,13 +3631,101 @@ fn stream_error_should_surface_to_timeline(event: &StreamErrorEvent) -> bool {
     )
 }
 
+/// Map a Codex error onto the session error model. Codex tags most failures
+/// with a `CodexErrorInfo`, whose wire name becomes the provider code; untagged
+/// or unmapped ones are classified from the message.
+fn codex_session_error(message: String, info: Option<&CodexErrorInfo>) -> SessionError {
+    let Some(info) = info else {
+        return SessionError::from_message(message);
+    };
+    let category = match info {
+        CodexErrorInfo::Unauthorized => Some(ErrorCategory::Auth),
+        CodexErrorInfo::UsageLimitExceeded => Some(ErrorCategory::RateLimit),
+        CodexErrorInfo::ContextWindowExceeded => Some(ErrorCategory::ContextWindow),
+        CodexErrorInfo::HttpConnectionFailed { .. }
+        | CodexErrorInfo::ResponseStreamConnectionFailed { .. }
+        | CodexErrorInfo::ResponseStreamDisconnected { .. } => Some(ErrorCategory::Network),
+        CodexErrorInfo::InternalServerError
+        | CodexErrorInfo::ResponseTooManyFailedAttempts { .. } => Some(ErrorCategory::Provider),
+        CodexErrorInfo::SandboxError => Some(ErrorCategory::Sandbox),
+        _ => None,
+    };
+    let error = match category {
+        Some(category) => SessionError::new(category, message),
+        None => SessionError::from_message(message),
+    };
+    match codex_error_code(info) {
+        Some(code) => error.with_provider_code(code),
+        None => error,
+    }
+}
+
+/// The serde name of a `CodexErrorInfo`, e.g. `usage_limit_exceeded`.
+fn codex_error_code(info: &CodexErrorInfo) -> Option<String> {
+    match serde_json::to_value(info).ok()? {
+        serde_json::Value::String(code) => Some(code),
+        serde_json::Value::Object(map) => map.keys().next().cloned(),
+        _ => None,
+    }
+}
+
+/// Read Codex's rate-limit snapshot (as JSON) into the protocol's usage
+/// type. Older Codex builds report `resets_in_seconds` instead of
+/// `resets_at`.
+fn codex_usage_snapshot(
+    limits: &serde_json::Value,
+) -> Option<orbitdock_protocol::CodexUsageSnapshot> {
+    let now = unix_now();
+    let window = |key: &str| -> Option<orbitdock_protocol::CodexRateLimitWindow> {
+        let window = limits.get(key)?;
+        let used_percent = window.get("used_percent")?.as_f64()?;
+        let resets_at_unix = match window.get("resets_at").and_then(|v| v.as_f64()) {
+            Some(at) => at,
+            None => now + window.get("resets_in_seconds").and_then(|v| v.as_f64())?,
+        };
+        Some(orbitdock_protocol::CodexRateLimitWindow {
+            used_percent,
+            window_duration_mins: window
+                .get("window_minutes")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0) as u32,
+            resets_at_unix,
+        })
+    };
+    let primary = window("primary");
+    let secondary = window("secondary");
+    if primary.is_none() && secondary.is_none() {
+        return None;
+    }
+    Some(orbitdock_protocol::CodexUsageSnapshot {
+        primary,
+        secondary,
+        plan_type: limits
+            .get("plan_type")
+            .and_then(|v| v.as_str())
+            .map(str::to_lowercase),
+        fetched_at_unix: now,
+    })
+}
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
 /// Get current time as ISO 8601 string
 fn iso_now() -> String {
     let secs = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs();
+    iso_from_unix_secs(secs)
+}
 
+/// Format seconds since the Unix epoch as an ISO 8601 string
+pub(crate) fn iso_from_unix_secs(secs: u64) -> String {
     let days_since_epoch = secs / 86400;
     let time_of_day = secs % 86400;
     let hours = time_of_day / 3600;
@@ -3640,13 +3777,14 @@ fn iso_now() -> String {
 #[cfg(test)]
 mod tests {
     use super::{
-        collaboration_mode_from_permission_mode, model_rejects_reasoning_summary,
-        parse_reasoning_summary, reasoning_summary_for_model, should_disable_reasoning_summary,
-        stream_error_should_surface_to_timeline,
+        codex_session_error, collaboration_mode_from_permission_mode,
+        model_rejects_reasoning_summary, parse_reasoning_summary, reasoning_summary_for_model,
+        should_disable_reasoning_summary, stream_error_should_surface_to_timeline,
     };
     use codex_protocol::config_types::{ModeKind, ReasoningSummary};
     use codex_protocol::openai_models::ReasoningEffort;
     use codex_protocol::protocol::{CodexErrorInfo, StreamErrorEvent};
+    use orbitdock_protocol::ErrorCategory;
 
     #[test]
     fn collaboration_mode_maps_plan() {
@@ -3786,4 +3924,50 @@ mod tests {
 
         assert!(stream_error_should_surface_to_timeline(&event));
     }
+
+    #[test]
+    fn codex_errors_map_to_categories_and_provider_codes() {
+        let error = codex_session_error(
+            "You've hit your usage limit".to_string(),
+            Some(&CodexErrorInfo::UsageLimitExceeded),
+        );
+        assert_eq!(error.category, ErrorCategory::RateLimit);
+        assert_eq!(error.provider_code.as_deref(), Some("usage_limit_exceeded"));
+
+        let error = codex_session_error(
+            "stream failed".to_string(),
+            Some(&CodexErrorInfo::HttpConnectionFailed {
+                http_status_code: Some(502),
+            }),
+        );
+        assert_eq!(error.category, ErrorCategory::Network);
+        assert!(error.retryable);
+        assert_eq!(
+            error.provider_code.as_deref(),
+            Some("http_connection_failed")
+        );
+
+        let error = codex_session_error("Unauthorized".to_string(), None);
+        assert_eq!(error.category, ErrorCategory::Auth);
+        assert!(error.provider_code.is_none());
+    }
+    #[test]
+    fn codex_rate_limits_become_a_usage_snapshot() {
+        let usage = codex_usage_snapshot(&json!({
+            "primary": { "used_percent": 91.0, "window_minutes": 300, "resets_at": 1_760_000_000 },
+            "secondary": { "used_percent": 40.0, "window_minutes": 10080, "resets_in_seconds": 60 },
+            "plan_type": "Plus"
+        }))
+        .expect("usage");
+
+        let primary = usage.primary.expect("primary");
+        assert_eq!(primary.used_percent, 91.0);
+        assert_eq!(primary.window_duration_mins, 300);
+        assert_eq!(primary.resets_at_unix, 1_760_000_000.0);
+        let secondary = usage.secondary.expect("secondary");
+        assert!(secondary.resets_at_unix > usage.fetched_at_unix);
+        assert_eq!(usage.plan_type.as_deref(), Some("plus"));
+
+        assert!(codex_usage_snapshot(&json!({ "primary": null, "credits": {} })).is_none());
+    }
 }
@@ -0,0 +1,122 @@
+//! A session's own MCP servers, as Codex config.
+//!
+//! They ride on the same `-c`-style overrides as the model and sandbox
+//! settings, so they sit on top of whatever `config.toml` declares.
+
+use std::path::Path;
+
+use orbitdock_protocol::SessionMcpServer;
+use tracing::warn;
+
+/// `mcp_servers.<name>` overrides for a session's enabled servers.
+pub(crate) fn config_overrides(servers: &[SessionMcpServer]) -> Vec<(String, toml::Value)> {
+    servers
+        .iter()
+        .filter(|server| server.enabled)
+        .map(|server| (format!("mcp_servers.{}", server.name), server_table(server)))
+        .collect()
+}
+
+fn server_table(server: &SessionMcpServer) -> toml::Value {
+    let mut table = toml::Table::new();
+    table.insert(
+        "command".to_string(),
+        toml::Value::String(server.command.clone()),
+    );
+    if !server.args.is_empty() {
+        let args = server.args.iter().cloned().map(toml::Value::String);
+        table.insert("args".to_string(), toml::Value::Array(args.collect()));
+    }
+    if !server.env.is_empty() {
+        let env = server
+            .env
+            .iter()
+            .map(|(key, value)| (key.clone(), toml::Value::String(value.clone())))
+            .collect();
+        table.insert("env".to_string(), toml::Value::Table(env));
+    }
+    toml::Value::Table(table)
+}
+
+/// Every server the thread should run after a refresh: those in
+/// `<codex_home>/config.toml`, then the session's. `RefreshMcpServers`
+/// takes the whole set, so sending only the session's would stop the rest.
+pub(crate) fn refresh_servers(
+    codex_home: &Path,
+    servers: &[SessionMcpServer],
+) -> serde_json::Value {
+    let mut merged = configured_servers(codex_home);
+    for server in servers.iter().filter(|server| server.enabled) {
+        merged.insert(server.name.clone(), server_table(server));
+    }
+    serde_json::to_value(merged).unwrap_or_else(|_| serde_json::Value::Object(Default::default()))
+}
+
+fn configured_servers(codex_home: &Path) -> toml::Table {
+    let path = codex_home.join("config.toml");
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return toml::Table::new();
+    };
+    match toml::from_str::<toml::Table>(&contents) {
+        Ok(mut config) => match config.remove("mcp_servers") {
+            Some(toml::Value::Table(servers)) => servers,
+            _ => toml::Table::new(),
+        },
+        Err(e) => {
+            warn!(
+                component = "codex_connector",
+                event = "codex.mcp_servers.config_unreadable",
+                path = %path.display(),
+                error = %e,
+                "Could not read MCP servers from Codex config"
+            );
+            toml::Table::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(name: &str, enabled: bool) -> SessionMcpServer {
+        SessionMcpServer {
+            name: name.to_string(),
+            command: "npx".to_string(),
+            args: vec!["-y".to_string(), "server-github".to_string()],
+            env: [("GITHUB_TOKEN".to_string(), "ghp_x".to_string())].into(),
+            enabled,
+        }
+    }
+
+    #[test]
+    fn overrides_cover_enabled_servers_only() {
+        let overrides = config_overrides(&[server("github", true), server("linear", false)]);
+        assert_eq!(overrides.len(), 1);
+        let (key, value) = &overrides[0];
+        assert_eq!(key, "mcp_servers.github");
+        assert_eq!(value["command"].as_str(), Some("npx"));
+        assert_eq!(value["args"].as_array().map(Vec::len), Some(2));
+        assert_eq!(value["env"]["GITHUB_TOKEN"].as_str(), Some("ghp_x"));
+    }
+
+    #[test]
+    fn refresh_keeps_configured_servers_and_lets_the_session_win() {
+        let codex_home =
+            std::env::temp_dir().join(format!("orbitdock-codex-mcp-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&codex_home).expect("create codex home");
+        std::fs::write(
+            codex_home.join("config.toml"),
+            "model = \"gpt-5\"\n\n\
+             [mcp_servers.docs]\ncommand = \"docs-mcp\"\n\n\
+             [mcp_servers.github]\ncommand = \"old-github\"\n",
+        )
+        .expect("write config");
+
+        let servers = refresh_servers(&codex_home, &[server("github", true)]);
+        let _ = std::fs::remove_dir_all(&codex_home);
+        assert_eq!(servers["docs"]["command"], "docs-mcp");
+        assert_eq!(servers["github"]["command"], "npx");
+        assert_eq!(servers["github"]["env"]["GITHUB_TOKEN"], "ghp_x");
+    }
+}
@@ -7,6 +7,7 @@
 use std::collections::HashMap;
 
 use orbitdock_connector_core::ConnectorError;
+use orbitdock_protocol::SessionMcpServer;
 use tokio::sync::oneshot;
 
 use crate::CodexConnector;
@@ -58,7 +59,10 @@ pub enum CodexAction {
         name: String,
     },
     ListMcpTools,
-    RefreshMcpServers,
+    /// Restart MCP servers with the session's own servers as they are now.
+    RefreshMcpServers {
+        servers: Vec<SessionMcpServer>,
+    },
     Compact,
     Undo,
     ThreadRollback {
@@ -153,7 +157,7 @@ impl std::fmt::Debug for CodexAction {
                 f.debug_struct("SetThreadName").field("name", name).finish()
             }
             Self::ListMcpTools => write!(f, "ListMcpTools"),
-            Self::RefreshMcpServers => write!(f, "RefreshMcpServers"),
+            Self::RefreshMcpServers { .. } => write!(f, "RefreshMcpServers"),
             Self::Compact => write!(f, "Compact"),
             Self::Undo => write!(f, "Undo"),
             Self::ThreadRollback { num_turns } => f
@@ -190,8 +194,18 @@ impl CodexSession {
         model: Option<&str>,
         approval_policy: Option<&str>,
         sandbox_mode: Option<&str>,
+        developer_instructions: Option<&str>,
+        mcp_servers: &[SessionMcpServer],
     ) -> Result<Self, ConnectorError> {
-        let connector = CodexConnector::new(cwd, model, approval_policy, sandbox_mode).await?;
+        let connector = CodexConnector::new(
+            cwd,
+            model,
+            approval_policy,
+            sandbox_mode,
+            developer_instructions,
+            mcp_servers,
+        )
+        .await?;
 
         Ok(Self {
             session_id,
@@ -200,6 +214,7 @@ impl CodexSession {
     }
 
     /// Resume an existing Codex session from its rollout file (preserves conversation history)
+    #[allow(clippy::too_many_arguments)]
     pub async fn resume(
         session_id: String,
         cwd: &str,
@@ -207,9 +222,19 @@ impl CodexSession {
         model: Option<&str>,
         approval_policy: Option<&str>,
         sandbox_mode: Option<&str>,
+        developer_instructions: Option<&str>,
+        mcp_servers: &[SessionMcpServer],
     ) -> Result<Self, ConnectorError> {
-        let connector =
-            CodexConnector::resume(cwd, thread_id, model, approval_policy, sandbox_mode).await?;
+        let connector = CodexConnector::resume(
+            cwd,
+            thread_id,
+            model,
+            approval_policy,
+            sandbox_mode,
+            developer_instructions,
+            mcp_servers,
+        )
+        .await?;
 
         Ok(Self {
             session_id,
@@ -302,8 +327,8 @@ impl CodexSession {
             CodexAction::ListMcpTools => {
                 connector.list_mcp_tools().await?;
             }
-            CodexAction::RefreshMcpServers => {
-                connector.refresh_mcp_servers().await?;
+            CodexAction::RefreshMcpServers { servers } => {
+                connector.refresh_mcp_servers(&servers).await?;
             }
             CodexAction::Compact => {
                 connector.compact().await?;
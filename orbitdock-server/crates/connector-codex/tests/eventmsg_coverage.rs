@@ -68,6 +68,10 @@ fn connector_plan_update_emits_tool_message_and_plan_state_update() {
         arm.contains("ConnectorEvent::PlanUpdated(plan)"),
         "PlanUpdate handler must update session plan side-state"
     );
+    assert!(
+        arm.contains("ConnectorEvent::PlanStepsUpdated"),
+        "PlanUpdate handler must emit structured plan steps"
+    );
     assert!(
         arm.contains("ConnectorEvent::MessageCreated(message)"),
         "PlanUpdate handler must emit a timeline tool message"
@@ -64,6 +64,9 @@ pub enum ConnectorEvent {
     /// Plan updated
     PlanUpdated(String),
 
+    /// Structured plan steps (Codex `update_plan`, Claude `TodoWrite`)
+    PlanStepsUpdated(orbitdock_protocol::SessionPlan),
+
     /// Thread name updated (auto-generated by codex-core or manually set)
     ThreadNameUpdated(String),
 
@@ -121,8 +124,12 @@ pub enum ConnectorEvent {
     /// Model name updated (e.g. from Claude init event)
     ModelUpdated(String),
 
-    /// Context was compacted (summarized)
-    ContextCompacted,
+    /// Context compaction began
+    ContextCompactionStarted,
+
+    /// Context was compacted (summarized). `tokens_before` is the provider's
+    /// own count of the context it compacted, when it reports one.
+    ContextCompacted { tokens_before: Option<u64> },
 
     /// Hook started with a (possibly new) session ID — used to register managed threads
     HookSessionId(String),
@@ -151,6 +158,10 @@ pub enum ConnectorEvent {
         info: orbitdock_protocol::RateLimitInfo,
     },
 
+    /// Account rate limits reported by Codex — handled by the event loop,
+    /// since they belong to the account rather than the session
+    AccountUsageUpdated(orbitdock_protocol::CodexUsageSnapshot),
+
     /// Prompt suggestion from the Claude SDK
     PromptSuggestion { suggestion: String },
 
@@ -158,5 +169,5 @@ pub enum ConnectorEvent {
     FilesPersisted { files: Vec<String> },
 
     /// Error occurred
-    Error(String),
+    Error(orbitdock_protocol::SessionError),
 }
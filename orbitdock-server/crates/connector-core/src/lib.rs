@@ -5,6 +5,7 @@
 
 mod error;
 mod event;
+pub mod plan;
 pub mod transition;
 
 pub use error::ConnectorError;
@@ -0,0 +1,201 @@
+//! Structured plans from provider payloads.
+//!
+//! Codex reports its plan through `update_plan` (`{explanation, plan: [{step,
+//! status}]}`); Claude keeps a checklist through the `TodoWrite` tool
+//! (`{todos: [{content, status, activeForm}]}`). Both become a `SessionPlan`,
+//! and `diff` says which steps a new plan changed.
+
+use orbitdock_protocol::{PlanStep, PlanStepChange, PlanStepStatus, SessionPlan};
+use serde_json::Value;
+
+/// Parse a Codex `update_plan` payload (as JSON text).
+pub fn from_codex_update(json: &str) -> Option<SessionPlan> {
+    let value: Value = serde_json::from_str(json).ok()?;
+    let steps = steps_from(value.get("plan")?, &["step", "title"])?;
+    Some(SessionPlan {
+        explanation: value
+            .get("explanation")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(str::to_string),
+        steps,
+    })
+}
+
+/// Parse the input of a Claude `TodoWrite` tool call.
+pub fn from_todo_write(input: &Value) -> Option<SessionPlan> {
+    Some(SessionPlan {
+        explanation: None,
+        steps: steps_from(input.get("todos")?, &["content", "activeForm"])?,
+    })
+}
+
+fn steps_from(items: &Value, title_keys: &[&str]) -> Option<Vec<PlanStep>> {
+    let steps = items
+        .as_array()?
+        .iter()
+        .filter_map(|item| {
+            let title = title_keys
+                .iter()
+                .filter_map(|key| item.get(key).and_then(Value::as_str))
+                .map(str::trim)
+                .find(|title| !title.is_empty())?;
+            let status = item
+                .get("status")
+                .and_then(Value::as_str)
+                .and_then(parse_status)
+                .unwrap_or(PlanStepStatus::Pending);
+            Some(PlanStep {
+                title: title.to_string(),
+                status,
+            })
+        })
+        .collect();
+    Some(steps)
+}
+
+/// Status names either provider uses, in any case or separator style.
+pub fn parse_status(status: &str) -> Option<PlanStepStatus> {
+    let normalized: String = status
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    match normalized.as_str() {
+        "pending" | "todo" | "notstarted" => Some(PlanStepStatus::Pending),
+        "inprogress" | "active" | "running" => Some(PlanStepStatus::InProgress),
+        "done" | "completed" | "complete" => Some(PlanStepStatus::Done),
+        "skipped" | "cancelled" | "canceled" => Some(PlanStepStatus::Skipped),
+        _ => None,
+    }
+}
+
+/// Steps of `new` that are not the same at their position in `old`.
+/// `previous_status` is set when the position held the same step before.
+pub fn diff(old: Option<&SessionPlan>, new: &SessionPlan) -> Vec<PlanStepChange> {
+    let old_steps = old.map(|plan| plan.steps.as_slice()).unwrap_or_default();
+    new.steps
+        .iter()
+        .enumerate()
+        .filter_map(|(index, step)| {
+            let before = old_steps.get(index);
+            if before == Some(step) {
+                return None;
+            }
+            Some(PlanStepChange {
+                index: index as u32,
+                title: step.title.clone(),
+                status: step.status,
+                previous_status: before
+                    .filter(|before| before.title == step.title)
+                    .map(|before| before.status),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn step(title: &str, status: PlanStepStatus) -> PlanStep {
+        PlanStep {
+            title: title.to_string(),
+            status,
+        }
+    }
+
+    #[test]
+    fn parses_codex_plan_updates() {
+        let plan = from_codex_update(
+            r#"{"explanation":" Refactor ","plan":[
+                {"step":"Read code","status":"completed"},
+                {"step":"Write code","status":"in_progress"},
+                {"step":"Ship","status":"inProgress"},
+                {"step":"  ","status":"pending"}
+            ]}"#,
+        )
+        .expect("plan");
+        assert_eq!(plan.explanation.as_deref(), Some("Refactor"));
+        assert_eq!(
+            plan.steps,
+            [
+                step("Read code", PlanStepStatus::Done),
+                step("Write code", PlanStepStatus::InProgress),
+                step("Ship", PlanStepStatus::InProgress),
+            ]
+        );
+        assert_eq!(from_codex_update("# just markdown"), None);
+    }
+
+    #[test]
+    fn parses_todo_write_input() {
+        let plan = from_todo_write(&json!({
+            "todos": [
+                {"content": "Add tests", "status": "pending", "activeForm": "Adding tests"},
+                {"content": "Drop old API", "status": "cancelled", "activeForm": "Dropping"},
+                {"activeForm": "Only active form", "status": "unknown"}
+            ]
+        }))
+        .expect("plan");
+        assert_eq!(plan.explanation, None);
+        assert_eq!(
+            plan.steps,
+            [
+                step("Add tests", PlanStepStatus::Pending),
+                step("Drop old API", PlanStepStatus::Skipped),
+                step("Only active form", PlanStepStatus::Pending),
+            ]
+        );
+        assert_eq!(from_todo_write(&json!({"plan": "text"})), None);
+    }
+
+    #[test]
+    fn diff_reports_new_and_changed_steps() {
+        let old = SessionPlan {
+            explanation: None,
+            steps: vec![
+                step("A", PlanStepStatus::Done),
+                step("B", PlanStepStatus::InProgress),
+                step("C", PlanStepStatus::Pending),
+            ],
+        };
+        let new = SessionPlan {
+            explanation: None,
+            steps: vec![
+                step("A", PlanStepStatus::Done),
+                step("B", PlanStepStatus::Done),
+                step("D", PlanStepStatus::Pending),
+                step("E", PlanStepStatus::Pending),
+            ],
+        };
+        let changes = diff(Some(&old), &new);
+        assert_eq!(
+            changes,
+            [
+                PlanStepChange {
+                    index: 1,
+                    title: "B".into(),
+                    status: PlanStepStatus::Done,
+                    previous_status: Some(PlanStepStatus::InProgress),
+                },
+                PlanStepChange {
+                    index: 2,
+                    title: "D".into(),
+                    status: PlanStepStatus::Pending,
+                    previous_status: None,
+                },
+                PlanStepChange {
+                    index: 3,
+                    title: "E".into(),
+                    status: PlanStepStatus::Pending,
+                    previous_status: None,
+                },
+            ]
+        );
+        assert_eq!(diff(None, &new).len(), 4);
+        assert!(diff(Some(&new), &new).is_empty());
+    }
+}
@@ -12,8 +12,9 @@ use orbitdock_protocol::{
     ApprovalPreview, ApprovalPreviewSegment, ApprovalPreviewType, ApprovalQuestionOption,
     ApprovalQuestionPrompt, ApprovalRequest, ApprovalRiskLevel, ApprovalType, McpAuthStatus,
     McpResource, McpResourceTemplate, McpStartupFailure, McpStartupStatus, McpTool, Message,
-    MessageChanges, MessageType, RemoteSkillSummary, ServerMessage, SessionStatus, SkillErrorInfo,
-    SkillsListEntry, StateChanges, TokenUsage, TokenUsageSnapshotKind, TurnDiff, WorkStatus,
+    MessageChanges, MessageType, PlanStepChange, Provider, RemoteSkillSummary, ServerMessage,
+    SessionError, SessionPlan, SessionStatus, SkillErrorInfo, SkillsListEntry, StateChanges,
+    TokenUsage, TokenUsageSnapshotKind, TurnDiff, WorkStatus,
 };
 use serde_json::{Map as JsonMap, Value as JsonValue};
 
@@ -57,6 +58,7 @@ impl WorkPhase {
 #[allow(dead_code)]
 pub struct TransitionState {
     pub id: String,
+    pub provider: Provider,
     pub revision: u64,
     pub phase: WorkPhase,
     pub messages: Vec<Message>,
@@ -64,6 +66,7 @@ pub struct TransitionState {
     pub token_usage_snapshot_kind: TokenUsageSnapshotKind,
     pub current_diff: Option<String>,
     pub current_plan: Option<String>,
+    pub plan: Option<SessionPlan>,
     pub custom_name: Option<String>,
     pub project_path: String,
     pub last_activity_at: Option<String>,
@@ -76,6 +79,21 @@ pub struct TransitionState {
     pub pending_approval: Option<ApprovalRequest>,
     pub repository_root: Option<String>,
     pub is_worktree: bool,
+    pub compaction: Option<CompactionProgress>,
+    /// Tokens spent by the running turn, summed from each usage update's
+    /// delta over the previous snapshot.
+    pub turn_usage: TokenUsage,
+    /// The latest error, cleared when the next turn starts.
+    pub last_error: Option<SessionError>,
+}
+
+/// A context compaction the provider is running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactionProgress {
+    pub tokens_before: u64,
+    /// Context fill from the last usage report during compaction, kept only
+    /// when it shrank below `tokens_before`.
+    pub tokens_after: Option<u64>,
 }
 
 // ---------------------------------------------------------------------------
@@ -117,6 +135,7 @@ pub enum Input {
     },
     DiffUpdated(String),
     PlanUpdated(String),
+    PlanStepsUpdated(SessionPlan),
     ThreadNameUpdated(String),
     SessionEnded {
         reason: String,
@@ -156,7 +175,10 @@ pub enum Input {
         models: Vec<orbitdock_protocol::ClaudeModelOption>,
     },
     ModelUpdated(String),
-    ContextCompacted,
+    ContextCompactionStarted,
+    ContextCompacted {
+        tokens_before: Option<u64>,
+    },
     UndoStarted {
         message: Option<String>,
     },
@@ -189,7 +211,7 @@ pub enum Input {
     FilesPersisted {
         files: Vec<String>,
     },
-    Error(String),
+    Error(SessionError),
 }
 
 impl From<ConnectorEvent> for Input {
@@ -246,6 +268,7 @@ impl From<ConnectorEvent> for Input {
             },
             ConnectorEvent::DiffUpdated(diff) => Input::DiffUpdated(diff),
             ConnectorEvent::PlanUpdated(plan) => Input::PlanUpdated(plan),
+            ConnectorEvent::PlanStepsUpdated(plan) => Input::PlanStepsUpdated(plan),
             ConnectorEvent::ThreadNameUpdated(name) => Input::ThreadNameUpdated(name),
             ConnectorEvent::SessionEnded { reason } => Input::SessionEnded { reason },
             ConnectorEvent::SkillsList { skills, errors } => Input::SkillsList { skills, errors },
@@ -289,7 +312,10 @@ impl From<ConnectorEvent> for Input {
                 models,
             },
             ConnectorEvent::ModelUpdated(model) => Input::ModelUpdated(model),
-            ConnectorEvent::ContextCompacted => Input::ContextCompacted,
+            ConnectorEvent::ContextCompactionStarted => Input::ContextCompactionStarted,
+            ConnectorEvent::ContextCompacted { tokens_before } => {
+                Input::ContextCompacted { tokens_before }
+            }
             ConnectorEvent::UndoStarted { message } => Input::UndoStarted { message },
             ConnectorEvent::UndoCompleted { success, message } => {
                 Input::UndoCompleted { success, message }
@@ -315,9 +341,11 @@ impl From<ConnectorEvent> for Input {
                 Input::PromptSuggestion { suggestion }
             }
             ConnectorEvent::FilesPersisted { files } => Input::FilesPersisted { files },
-            ConnectorEvent::Error(msg) => Input::Error(msg),
+            ConnectorEvent::Error(error) => Input::Error(error),
             // Handled in event loop before reaching transitions
-            ConnectorEvent::HookSessionId(_) => unreachable!(),
+            ConnectorEvent::HookSessionId(_) | ConnectorEvent::AccountUsageUpdated(_) => {
+                unreachable!()
+            }
         }
     }
 }
@@ -367,6 +395,26 @@ pub enum PersistOp {
         diff: Option<String>,
         plan: Option<String>,
     },
+    PlanStepsUpdate {
+        session_id: String,
+        plan: SessionPlan,
+        changes: Vec<PlanStepChange>,
+    },
+    TurnStart {
+        session_id: String,
+        turn_id: String,
+        turn_seq: u64,
+        started_at: String,
+    },
+    TurnEnd {
+        session_id: String,
+        turn_id: String,
+        ended_at: String,
+        aborted: bool,
+        token_usage: TokenUsage,
+        snapshot_kind: TokenUsageSnapshotKind,
+        turn_usage: TokenUsage,
+    },
     TurnDiffInsert {
         session_id: String,
         turn_id: String,
@@ -377,6 +425,7 @@ pub enum PersistOp {
         cached_tokens: u64,
         context_window: u64,
         snapshot_kind: TokenUsageSnapshotKind,
+        turn_usage: TokenUsage,
     },
     SetCustomName {
         session_id: String,
@@ -420,6 +469,10 @@ pub enum PersistOp {
         session_id: String,
         permission_mode: String,
     },
+    LastErrorUpdate {
+        session_id: String,
+        error: Option<SessionError>,
+    },
 }
 
 // ---------------------------------------------------------------------------
@@ -457,6 +510,28 @@ fn finalize_in_progress_messages(sid: &str, messages: &mut [Message]) -> Vec<Eff
     effects
 }
 
+/// Close the running turn's record, if a turn is running.
+fn end_turn(state: &TransitionState, now: &str, aborted: bool) -> Option<Effect> {
+    let turn_id = state.current_turn_id.clone()?;
+    Some(Effect::Persist(Box::new(PersistOp::TurnEnd {
+        session_id: state.id.clone(),
+        turn_id,
+        ended_at: now.to_string(),
+        aborted,
+        token_usage: state.token_usage.clone(),
+        snapshot_kind: state.token_usage_snapshot_kind,
+        turn_usage: state.turn_usage.clone(),
+    })))
+}
+
+/// The running turn's spend so far, or `None` between turns.
+fn running_turn_usage(state: &TransitionState) -> Option<TokenUsage> {
+    state
+        .current_turn_id
+        .as_ref()
+        .map(|_| state.turn_usage.clone())
+}
+
 // ---------------------------------------------------------------------------
 // transition() — the pure core
 // ---------------------------------------------------------------------------
@@ -481,6 +556,8 @@ pub fn transition(
             state.turn_count += 1;
             let turn_id = format!("turn-{}", state.turn_count);
             state.current_turn_id = Some(turn_id.clone());
+            state.turn_usage = TokenUsage::default();
+            let cleared_error = state.last_error.take().map(|_| None);
 
             effects.push(Effect::Persist(Box::new(PersistOp::SessionUpdate {
                 id: sid.clone(),
@@ -488,16 +565,29 @@ pub fn transition(
                 work_status: Some(WorkStatus::Working),
                 last_activity_at: Some(now.to_string()),
             })));
+            if cleared_error.is_some() {
+                effects.push(Effect::Persist(Box::new(PersistOp::LastErrorUpdate {
+                    session_id: sid.clone(),
+                    error: None,
+                })));
+            }
             effects.push(Effect::Emit(Box::new(ServerMessage::SessionDelta {
                 session_id: sid,
                 changes: StateChanges {
                     work_status: Some(WorkStatus::Working),
                     last_activity_at: Some(now.to_string()),
-                    current_turn_id: Some(Some(turn_id)),
+                    current_turn_id: Some(Some(turn_id.clone())),
                     turn_count: Some(state.turn_count),
+                    last_error: cleared_error,
                     ..Default::default()
                 },
             })));
+            effects.push(Effect::Persist(Box::new(PersistOp::TurnStart {
+                session_id: state.id.clone(),
+                turn_id,
+                turn_seq: state.turn_count,
+                started_at: now.to_string(),
+            })));
         }
 
         Input::TurnCompleted => {
@@ -511,6 +601,8 @@ pub fn transition(
                     diff: diff.clone(),
                     token_usage: Some(usage.clone()),
                     snapshot_kind: Some(state.token_usage_snapshot_kind),
+                    turn_usage: Some(state.turn_usage.clone()),
+                    summary: None,
                 };
                 state.turn_diffs.push(snapshot);
                 effects.push(Effect::Persist(Box::new(PersistOp::TurnDiffInsert {
@@ -523,6 +615,7 @@ pub fn transition(
                     cached_tokens: usage.cached_tokens,
                     context_window: usage.context_window,
                     snapshot_kind: state.token_usage_snapshot_kind,
+                    turn_usage: state.turn_usage.clone(),
                 })));
                 effects.push(Effect::Emit(Box::new(ServerMessage::TurnDiffSnapshot {
                     session_id: sid.clone(),
@@ -533,18 +626,23 @@ pub fn transition(
                     cached_tokens: Some(usage.cached_tokens),
                     context_window: Some(usage.context_window),
                     snapshot_kind: state.token_usage_snapshot_kind,
+                    turn_usage: Some(state.turn_usage.clone()),
                 })));
             }
 
             // Finalize any tool messages stuck at is_in_progress before status change
             effects.extend(finalize_in_progress_messages(&sid, &mut state.messages));
 
+            effects.extend(end_turn(&state, now, false));
+
             // Only transition if we're actually working
             if matches!(state.phase, WorkPhase::Working) {
                 state.phase = WorkPhase::Idle;
             }
             state.last_activity_at = Some(now.to_string());
             state.current_turn_id = None;
+            // A compaction still running at turn end failed without saying so
+            let compaction_abandoned = state.compaction.take().is_some();
 
             effects.push(Effect::Persist(Box::new(PersistOp::SessionUpdate {
                 id: sid.clone(),
@@ -558,6 +656,7 @@ pub fn transition(
                     work_status: Some(WorkStatus::Waiting),
                     last_activity_at: Some(now.to_string()),
                     current_turn_id: Some(None),
+                    compact_in_progress: compaction_abandoned.then_some(false),
                     ..Default::default()
                 },
             })));
@@ -569,10 +668,12 @@ pub fn transition(
             if !matches!(state.phase, WorkPhase::Idle | WorkPhase::Ended { .. }) {
                 // Finalize any tool messages stuck at is_in_progress before status change
                 effects.extend(finalize_in_progress_messages(&sid, &mut state.messages));
+                effects.extend(end_turn(&state, now, true));
 
                 state.phase = WorkPhase::Idle;
                 state.last_activity_at = Some(now.to_string());
                 state.current_turn_id = None;
+                let compaction_abandoned = state.compaction.take().is_some();
 
                 effects.push(Effect::Persist(Box::new(PersistOp::SessionUpdate {
                     id: sid.clone(),
@@ -586,53 +687,69 @@ pub fn transition(
                         work_status: Some(WorkStatus::Waiting),
                         last_activity_at: Some(now.to_string()),
                         current_turn_id: Some(None),
+                        compact_in_progress: compaction_abandoned.then_some(false),
                         ..Default::default()
                     },
                 })));
             }
         }
 
-        Input::Error(msg) => {
+        Input::Error(mut error) => {
             state.phase = WorkPhase::Idle;
             state.last_activity_at = Some(now.to_string());
+            error.occurred_at = Some(now.to_string());
+            state.last_error = Some(error.clone());
 
-            // Create an error message so the user sees what happened
-            let error_msg = Message {
-                id: format!("error-{}", uuid::Uuid::new_v4()),
-                session_id: sid.clone(),
-                sequence: None,
-                message_type: MessageType::Assistant,
-                content: msg,
-                tool_name: None,
-                tool_input: None,
-                tool_output: None,
-                is_error: true,
-                is_in_progress: false,
-                timestamp: now.to_string(),
-                duration_ms: None,
-                images: vec![],
-            };
-            state.messages.push(error_msg.clone());
+            // Create an error message so the user sees what happened, unless
+            // the provider already showed it as the latest message
+            let already_shown = state
+                .messages
+                .last()
+                .is_some_and(|message| message.content == error.message);
+            if !already_shown {
+                let error_msg = Message {
+                    id: format!("error-{}", uuid::Uuid::new_v4()),
+                    session_id: sid.clone(),
+                    sequence: None,
+                    message_type: MessageType::Assistant,
+                    content: error.message.clone(),
+                    tool_name: None,
+                    tool_input: None,
+                    tool_output: None,
+                    is_error: true,
+                    is_in_progress: false,
+                    timestamp: now.to_string(),
+                    duration_ms: None,
+                    images: vec![],
+                };
+                state.messages.push(error_msg.clone());
+
+                effects.push(Effect::Persist(Box::new(PersistOp::MessageAppend {
+                    session_id: sid.clone(),
+                    message: error_msg.clone(),
+                })));
+                effects.push(Effect::Emit(Box::new(ServerMessage::MessageAppended {
+                    session_id: sid.clone(),
+                    message: error_msg,
+                })));
+            }
 
-            effects.push(Effect::Persist(Box::new(PersistOp::MessageAppend {
-                session_id: sid.clone(),
-                message: error_msg.clone(),
-            })));
-            effects.push(Effect::Emit(Box::new(ServerMessage::MessageAppended {
-                session_id: sid.clone(),
-                message: error_msg,
-            })));
             effects.push(Effect::Persist(Box::new(PersistOp::SessionUpdate {
                 id: sid.clone(),
                 status: None,
                 work_status: Some(WorkStatus::Waiting),
                 last_activity_at: Some(now.to_string()),
             })));
+            effects.push(Effect::Persist(Box::new(PersistOp::LastErrorUpdate {
+                session_id: sid.clone(),
+                error: Some(error.clone()),
+            })));
             effects.push(Effect::Emit(Box::new(ServerMessage::SessionDelta {
                 session_id: sid,
                 changes: StateChanges {
                     work_status: Some(WorkStatus::Waiting),
                     last_activity_at: Some(now.to_string()),
+                    last_error: Some(Some(error)),
                     ..Default::default()
                 },
             })));
@@ -876,8 +993,26 @@ pub fn transition(
             usage,
             snapshot_kind,
         } => {
+            if state.current_turn_id.is_some() {
+                let delta = TokenUsage::delta(
+                    (&state.token_usage, state.token_usage_snapshot_kind),
+                    (&usage, snapshot_kind),
+                );
+                state.turn_usage.input_tokens += delta.input_tokens;
+                state.turn_usage.output_tokens += delta.output_tokens;
+                state.turn_usage.cached_tokens += delta.cached_tokens;
+                state.turn_usage.context_window = usage.context_window;
+            }
             state.token_usage = usage.clone();
             state.token_usage_snapshot_kind = snapshot_kind;
+            if let Some(compaction) = state.compaction.as_mut() {
+                let tokens = usage.context_tokens(state.provider);
+                if snapshot_kind != TokenUsageSnapshotKind::LifetimeTotals
+                    && tokens < compaction.tokens_before
+                {
+                    compaction.tokens_after = Some(tokens);
+                }
+            }
 
             effects.push(Effect::Persist(Box::new(PersistOp::TokensUpdate {
                 session_id: sid.clone(),
@@ -888,6 +1023,8 @@ pub fn transition(
                 session_id: sid,
                 usage,
                 snapshot_kind,
+                estimated_cost_usd: None,
+                turn_usage: running_turn_usage(&state),
             })));
         }
 
@@ -925,6 +1062,28 @@ pub fn transition(
             })));
         }
 
+        Input::PlanStepsUpdated(plan) => {
+            // Agents resend the whole plan; only broadcast what moved.
+            if state.plan.as_ref() != Some(&plan) {
+                let changes = crate::plan::diff(state.plan.as_ref(), &plan);
+                state.plan = Some(plan.clone());
+
+                let step_count = plan.steps.len() as u32;
+                let explanation = plan.explanation.clone();
+                effects.push(Effect::Persist(Box::new(PersistOp::PlanStepsUpdate {
+                    session_id: sid.clone(),
+                    plan,
+                    changes: changes.clone(),
+                })));
+                effects.push(Effect::Emit(Box::new(ServerMessage::PlanDelta {
+                    session_id: sid,
+                    explanation,
+                    step_count,
+                    changes,
+                })));
+            }
+        }
+
         Input::ThreadNameUpdated(name) => {
             state.custom_name = Some(name.clone());
             state.last_activity_at = Some(now.to_string());
@@ -946,11 +1105,13 @@ pub fn transition(
         Input::SessionEnded { reason } => {
             // Finalize any tool messages stuck at is_in_progress before ending
             effects.extend(finalize_in_progress_messages(&sid, &mut state.messages));
+            effects.extend(end_turn(&state, now, true));
 
             state.phase = WorkPhase::Ended {
                 reason: reason.clone(),
             };
             state.last_activity_at = Some(now.to_string());
+            state.compaction = None;
 
             effects.push(Effect::Persist(Box::new(PersistOp::SessionEnd {
                 id: sid.clone(),
@@ -1131,7 +1292,41 @@ pub fn transition(
         }
 
         // -- Context management -----------------------------------------------
-        Input::ContextCompacted => {
+        Input::ContextCompactionStarted => {
+            // Codex and Claude each announce a compaction once; ignore repeats
+            // so `tokens_before` stays the fill the compaction started from.
+            if state.compaction.is_none() {
+                let tokens_before = state.token_usage.context_tokens(state.provider);
+                state.compaction = Some(CompactionProgress {
+                    tokens_before,
+                    tokens_after: None,
+                });
+                state.last_activity_at = Some(now.to_string());
+
+                effects.push(Effect::Emit(Box::new(ServerMessage::SessionDelta {
+                    session_id: sid.clone(),
+                    changes: StateChanges {
+                        compact_in_progress: Some(true),
+                        last_activity_at: Some(now.to_string()),
+                        ..Default::default()
+                    },
+                })));
+                effects.push(Effect::Emit(Box::new(
+                    ServerMessage::ContextCompactionStarted {
+                        session_id: sid,
+                        tokens_before,
+                    },
+                )));
+            }
+        }
+
+        Input::ContextCompacted { tokens_before } => {
+            let progress = state.compaction.take();
+            let tokens_before = tokens_before
+                .or(progress.as_ref().map(|progress| progress.tokens_before))
+                .unwrap_or_else(|| state.token_usage.context_tokens(state.provider));
+            let tokens_after = progress.and_then(|progress| progress.tokens_after);
+
             state.last_activity_at = Some(now.to_string());
             let compacted_usage = TokenUsage {
                 input_tokens: 0,
@@ -1151,6 +1346,8 @@ pub fn transition(
                 session_id: sid.clone(),
                 usage: compacted_usage,
                 snapshot_kind: TokenUsageSnapshotKind::CompactionReset,
+                estimated_cost_usd: None,
+                turn_usage: running_turn_usage(&state),
             })));
 
             // Record compaction as a first-class transcript event so it is visible
@@ -1181,6 +1378,21 @@ pub fn transition(
                 session_id: sid.clone(),
                 message: compact_msg,
             })));
+            effects.push(Effect::Emit(Box::new(ServerMessage::SessionDelta {
+                session_id: sid.clone(),
+                changes: StateChanges {
+                    compact_in_progress: Some(false),
+                    last_activity_at: Some(now.to_string()),
+                    ..Default::default()
+                },
+            })));
+            effects.push(Effect::Emit(Box::new(
+                ServerMessage::ContextCompactionCompleted {
+                    session_id: sid.clone(),
+                    tokens_before,
+                    tokens_after,
+                },
+            )));
             effects.push(Effect::Emit(Box::new(ServerMessage::ContextCompacted {
                 session_id: sid,
             })));
@@ -2448,6 +2660,7 @@ mod tests {
     fn test_state() -> TransitionState {
         TransitionState {
             id: "test-session".to_string(),
+            provider: Provider::Codex,
             revision: 0,
             phase: WorkPhase::Idle,
             messages: Vec::new(),
@@ -2455,6 +2668,7 @@ mod tests {
             token_usage_snapshot_kind: TokenUsageSnapshotKind::Unknown,
             current_diff: None,
             current_plan: None,
+            plan: None,
             custom_name: None,
             project_path: "/tmp/project".to_string(),
             last_activity_at: None,
@@ -2467,6 +2681,9 @@ mod tests {
             pending_approval: None,
             repository_root: None,
             is_worktree: false,
+            compaction: None,
+            turn_usage: TokenUsage::default(),
+            last_error: None,
         }
     }
 
@@ -2496,7 +2713,7 @@ mod tests {
         let (new_state, effects) = transition(state, Input::TurnStarted, NOW);
 
         assert_eq!(new_state.phase, WorkPhase::Working);
-        assert_eq!(effects.len(), 2); // Persist + Emit
+        assert_eq!(effects.len(), 3); // Persist + Emit + turn record
         assert!(matches!(
             effects[0],
             Effect::Persist(ref op) if matches!(**op, PersistOp::SessionUpdate { .. })
@@ -2505,6 +2722,13 @@ mod tests {
             effects[1],
             Effect::Emit(ref msg) if matches!(**msg, ServerMessage::SessionDelta { .. })
         ));
+        assert!(matches!(
+            effects[2],
+            Effect::Persist(ref op) if matches!(
+                **op,
+                PersistOp::TurnStart { ref turn_id, turn_seq: 1, .. } if turn_id == "turn-1"
+            )
+        ));
     }
 
     #[test]
@@ -3038,18 +3262,26 @@ mod tests {
             context_window: 200_000,
         };
 
-        let (new_state, effects) = transition(state.clone(), Input::ContextCompacted, NOW);
+        let (new_state, effects) = transition(
+            state.clone(),
+            Input::ContextCompacted {
+                tokens_before: None,
+            },
+            NOW,
+        );
         assert_eq!(new_state.phase, state.phase);
         assert_eq!(new_state.token_usage.input_tokens, 0);
         assert_eq!(new_state.token_usage.cached_tokens, 0);
         assert_eq!(new_state.token_usage.output_tokens, 9_500);
         assert_eq!(new_state.token_usage.context_window, 200_000);
-        assert_eq!(effects.len(), 5);
+        assert_eq!(effects.len(), 7);
         assert!(matches!(effects[0], Effect::Persist(_)));
         assert!(matches!(effects[1], Effect::Emit(_)));
         assert!(matches!(effects[2], Effect::Persist(_)));
         assert!(matches!(effects[3], Effect::Emit(_)));
         assert!(matches!(effects[4], Effect::Emit(_)));
+        assert!(matches!(effects[5], Effect::Emit(_)));
+        assert!(matches!(effects[6], Effect::Emit(_)));
         if let Effect::Emit(message) = &effects[1] {
             match message.as_ref() {
                 ServerMessage::TokensUpdated { usage, .. } => {
@@ -3072,6 +3304,161 @@ mod tests {
         );
     }
 
+    #[test]
+    fn context_compaction_reports_tokens_before_and_after() {
+        let mut state = test_state();
+        state.phase = WorkPhase::Working;
+        state.token_usage = TokenUsage {
+            input_tokens: 150_000,
+            output_tokens: 4_000,
+            cached_tokens: 100_000,
+            context_window: 200_000,
+        };
+
+        let (state, effects) = transition(state, Input::ContextCompactionStarted, NOW);
+        assert_eq!(effects.len(), 2);
+        match &effects[0] {
+            Effect::Emit(message) => match message.as_ref() {
+                ServerMessage::SessionDelta { changes, .. } => {
+                    assert_eq!(changes.compact_in_progress, Some(true));
+                }
+                other => panic!("expected session_delta, got {:?}", other),
+            },
+            other => panic!("expected emit, got {:?}", other),
+        }
+        assert!(matches!(
+            &effects[1],
+            Effect::Emit(message) if matches!(
+                message.as_ref(),
+                ServerMessage::ContextCompactionStarted { tokens_before: 150_000, .. }
+            )
+        ));
+
+        // A repeated start keeps the original baseline
+        let (state, effects) = transition(state, Input::ContextCompactionStarted, NOW);
+        assert!(effects.is_empty());
+
+        let (state, _) = transition(
+            state,
+            Input::TokensUpdated {
+                usage: TokenUsage {
+                    input_tokens: 12_000,
+                    output_tokens: 4_000,
+                    cached_tokens: 0,
+                    context_window: 200_000,
+                },
+                snapshot_kind: TokenUsageSnapshotKind::ContextTurn,
+            },
+            NOW,
+        );
+        assert_eq!(
+            state.compaction.as_ref().and_then(|c| c.tokens_after),
+            Some(12_000)
+        );
+
+        let (state, effects) = transition(
+            state,
+            Input::ContextCompacted {
+                tokens_before: None,
+            },
+            NOW,
+        );
+        assert!(state.compaction.is_none());
+        let completed = effects.iter().find_map(|effect| match effect {
+            Effect::Emit(message) => match message.as_ref() {
+                ServerMessage::ContextCompactionCompleted {
+                    tokens_before,
+                    tokens_after,
+                    ..
+                } => Some((*tokens_before, *tokens_after)),
+                _ => None,
+            },
+            _ => None,
+        });
+        assert_eq!(completed, Some((150_000, Some(12_000))));
+        assert!(effects.iter().any(|effect| matches!(
+            effect,
+            Effect::Emit(message) if matches!(
+                message.as_ref(),
+                ServerMessage::SessionDelta { changes, .. }
+                    if changes.compact_in_progress == Some(false)
+            )
+        )));
+    }
+
+    #[test]
+    fn turn_usage_sums_deltas_within_the_turn() {
+        let mixed = |input_tokens, output_tokens| Input::TokensUpdated {
+            usage: TokenUsage {
+                input_tokens,
+                output_tokens,
+                cached_tokens: 0,
+                context_window: 200_000,
+            },
+            snapshot_kind: TokenUsageSnapshotKind::MixedLegacy,
+        };
+
+        // Usage from before the turn is not the turn's
+        let (state, _) = transition(test_state(), mixed(5_000, 400), NOW);
+        let (state, _) = transition(state, Input::TurnStarted, NOW);
+        let (state, _) = transition(state, mixed(6_000, 700), NOW);
+        let (state, effects) = transition(state, mixed(7_000, 900), NOW);
+        let reported = effects.iter().find_map(|effect| match effect {
+            Effect::Emit(message) => match message.as_ref() {
+                ServerMessage::TokensUpdated { turn_usage, .. } => turn_usage.clone(),
+                _ => None,
+            },
+            _ => None,
+        });
+        let reported = reported.expect("turn usage on tokens_updated");
+        assert_eq!(reported.input_tokens, 13_000);
+        assert_eq!(reported.output_tokens, 500);
+
+        let (state, effects) = transition(state, Input::TurnCompleted, NOW);
+        let recorded = effects.iter().find_map(|effect| match effect {
+            Effect::Persist(op) => match op.as_ref() {
+                PersistOp::TurnEnd { turn_usage, .. } => Some(turn_usage.clone()),
+                _ => None,
+            },
+            Effect::Emit(_) => None,
+        });
+        assert_eq!(
+            recorded.map(|usage| (usage.input_tokens, usage.output_tokens)),
+            Some((13_000, 500))
+        );
+
+        // Between turns, updates carry no turn usage
+        let (_, effects) = transition(state, mixed(8_000, 1_000), NOW);
+        assert!(effects.iter().all(|effect| !matches!(
+            effect,
+            Effect::Emit(message) if matches!(
+                message.as_ref(),
+                ServerMessage::TokensUpdated { turn_usage: Some(_), .. }
+            )
+        )));
+    }
+
+    #[test]
+    fn unfinished_compaction_clears_at_turn_end() {
+        let mut state = test_state();
+        state.phase = WorkPhase::Working;
+        state.compaction = Some(CompactionProgress {
+            tokens_before: 90_000,
+            tokens_after: None,
+        });
+
+        let (state, effects) = transition(state, Input::TurnCompleted, NOW);
+        assert!(state.compaction.is_none());
+        assert!(effects.iter().any(|effect| matches!(
+            effect,
+            Effect::Emit(message) if matches!(
+                message.as_ref(),
+                ServerMessage::SessionDelta { changes, .. }
+                    if changes.compact_in_progress == Some(false)
+            )
+        )));
+    }
+
     #[test]
     fn pass_through_events_only_emit() {
         let state = test_state();
@@ -3086,12 +3473,16 @@ mod tests {
         let mut state = test_state();
         state.phase = WorkPhase::Working;
 
-        let (new_state, effects) =
-            transition(state, Input::Error("something broke".to_string()), NOW);
+        let (new_state, effects) = transition(
+            state,
+            Input::Error(SessionError::from_message("something broke")),
+            NOW,
+        );
 
         assert_eq!(new_state.phase, WorkPhase::Idle);
-        // 4 effects: message persist, message emit, session update, session delta
-        assert_eq!(effects.len(), 4);
+        // 5 effects: message persist, message emit, session update, error
+        // persist, session delta
+        assert_eq!(effects.len(), 5);
         // Verify the error message was added to state
         let last_msg = new_state.messages.last().unwrap();
         assert!(last_msg.id.starts_with("error-"));
@@ -3100,6 +3491,59 @@ mod tests {
         assert_eq!(last_msg.message_type, MessageType::Assistant);
     }
 
+    #[test]
+    fn error_is_kept_until_the_next_turn_starts() {
+        let delta_error = |effects: &[Effect]| {
+            effects.iter().find_map(|effect| match effect {
+                Effect::Emit(msg) => match msg.as_ref() {
+                    ServerMessage::SessionDelta { changes, .. } => changes.last_error.clone(),
+                    _ => None,
+                },
+                _ => None,
+            })
+        };
+
+        let (state, effects) = transition(
+            test_state(),
+            Input::Error(SessionError::from_message(
+                "Invalid API key · Please run /login",
+            )),
+            NOW,
+        );
+        let error = state.last_error.clone().unwrap();
+        assert_eq!(error.category, orbitdock_protocol::ErrorCategory::Auth);
+        assert_eq!(error.occurred_at.as_deref(), Some(NOW));
+        assert_eq!(delta_error(&effects), Some(Some(error)));
+
+        let (state, effects) = transition(state, Input::TurnStarted, NOW);
+        assert!(state.last_error.is_none());
+        assert_eq!(delta_error(&effects), Some(None));
+
+        let (_, effects) = transition(state, Input::TurnStarted, NOW);
+        assert_eq!(delta_error(&effects), None);
+    }
+
+    #[test]
+    fn error_already_shown_by_the_provider_is_not_repeated() {
+        let mut state = test_state();
+        state.phase = WorkPhase::Working;
+        state.messages.push(test_message(
+            MessageType::Assistant,
+            "API Error: Overloaded",
+        ));
+
+        let (new_state, effects) = transition(
+            state,
+            Input::Error(SessionError::from_message("API Error: Overloaded")),
+            NOW,
+        );
+
+        assert_eq!(new_state.messages.len(), 1);
+        assert!(new_state.last_error.is_some());
+        // session update, error persist, session delta
+        assert_eq!(effects.len(), 3);
+    }
+
     #[test]
     fn tokens_updated_stores_usage() {
         let state = test_state();
@@ -3278,6 +3722,42 @@ mod tests {
         assert!(new_state.current_turn_id.is_none());
     }
 
+    #[test]
+    fn turn_end_is_recorded_once_per_running_turn() {
+        let turn_ends = |effects: &[Effect]| -> Vec<(String, bool)> {
+            effects
+                .iter()
+                .filter_map(|effect| match effect {
+                    Effect::Persist(op) => match op.as_ref() {
+                        PersistOp::TurnEnd {
+                            turn_id, aborted, ..
+                        } => Some((turn_id.clone(), *aborted)),
+                        _ => None,
+                    },
+                    Effect::Emit(_) => None,
+                })
+                .collect()
+        };
+
+        let (state, _) = transition(test_state(), Input::TurnStarted, NOW);
+        let (state, effects) = transition(state, Input::TurnCompleted, NOW);
+        assert_eq!(turn_ends(&effects), [("turn-1".to_string(), false)]);
+
+        // No turn is running now, so a stray completion records nothing.
+        let (state, effects) = transition(state, Input::TurnCompleted, NOW);
+        assert!(turn_ends(&effects).is_empty());
+
+        let (state, _) = transition(state, Input::TurnStarted, NOW);
+        let (_, effects) = transition(
+            state,
+            Input::SessionEnded {
+                reason: "user_requested".to_string(),
+            },
+            NOW,
+        );
+        assert_eq!(turn_ends(&effects), [("turn-2".to_string(), true)]);
+    }
+
     // -- finalize_in_progress_messages tests ---------------------------------
 
     fn tool_message(id: &str, in_progress: bool) -> Message {
@@ -3512,4 +3992,59 @@ mod tests {
             .collect();
         assert_eq!(msg_emits.len(), 3);
     }
+
+    #[test]
+    fn plan_steps_emit_only_what_changed() {
+        use orbitdock_protocol::{PlanStep, PlanStepStatus};
+
+        let plan = |second: PlanStepStatus| SessionPlan {
+            explanation: None,
+            steps: vec![
+                PlanStep {
+                    title: "Read".into(),
+                    status: PlanStepStatus::Done,
+                },
+                PlanStep {
+                    title: "Write".into(),
+                    status: second,
+                },
+            ],
+        };
+        let (state, effects) = transition(
+            test_state(),
+            Input::PlanStepsUpdated(plan(PlanStepStatus::InProgress)),
+            NOW,
+        );
+        assert_eq!(effects.len(), 2);
+
+        let (state, effects) = transition(
+            state,
+            Input::PlanStepsUpdated(plan(PlanStepStatus::Done)),
+            NOW,
+        );
+        assert_eq!(state.plan, Some(plan(PlanStepStatus::Done)));
+        match effects.last().map(|effect| match effect {
+            Effect::Emit(msg) => msg.as_ref().clone(),
+            Effect::Persist(_) => panic!("expected an emit last"),
+        }) {
+            Some(ServerMessage::PlanDelta {
+                step_count,
+                changes,
+                ..
+            }) => {
+                assert_eq!(step_count, 2);
+                assert_eq!(changes.len(), 1);
+                assert_eq!(changes[0].index, 1);
+                assert_eq!(changes[0].previous_status, Some(PlanStepStatus::InProgress));
+            }
+            other => panic!("expected PlanDelta, got {other:?}"),
+        }
+
+        let (_, effects) = transition(
+            state,
+            Input::PlanStepsUpdated(plan(PlanStepStatus::Done)),
+            NOW,
+        );
+        assert!(effects.is_empty(), "an unchanged plan is a no-op");
+    }
 }
@@ -6,7 +6,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::types::{
-    ImageInput, MentionInput, Provider, ReviewCommentStatus, ReviewCommentTag, SkillInput,
+    ImageInput, MentionInput, Provider, ReviewCommentStatus, ReviewCommentTag, SessionMcpServer,
+    SessionVisibility, SkillInput, SnapshotOptions, TicketLink, UsageGroupBy, UsageRange,
 };
 
 fn default_include_snapshot() -> bool {
@@ -17,6 +18,10 @@ fn is_true(value: &bool) -> bool {
     *value
 }
 
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
 /// Messages sent from client to server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -29,6 +34,8 @@ pub enum ClientMessage {
         since_revision: Option<u64>,
         #[serde(default = "default_include_snapshot", skip_serializing_if = "is_true")]
         include_snapshot: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        snapshot: Option<SnapshotOptions>,
     },
     UnsubscribeSession {
         session_id: String,
@@ -49,6 +56,9 @@ pub enum ClientMessage {
         images: Vec<ImageInput>,
         #[serde(default, skip_serializing_if = "Vec::is_empty")]
         mentions: Vec<MentionInput>,
+        /// Ids of uploaded attachments to send with the message
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        attachment_ids: Vec<String>,
     },
     ApproveTool {
         session_id: String,
@@ -84,6 +94,16 @@ pub enum ClientMessage {
         sandbox_mode: Option<String>,
         permission_mode: Option<String>,
     },
+    /// Replace a running Claude session's tool allow/deny lists; empty
+    /// clears one. Codex has no per-tool lists (its approval policy and
+    /// sandbox go through `UpdateSessionConfig`), so it is refused.
+    UpdateSessionTools {
+        session_id: String,
+        #[serde(default)]
+        allowed_tools: Vec<String>,
+        #[serde(default)]
+        disallowed_tools: Vec<String>,
+    },
 
     // Session naming
     RenameSession {
@@ -91,6 +111,44 @@ pub enum ClientMessage {
         name: Option<String>,
     },
 
+    // Session tags
+    /// Replace a session's topic tags; an empty list clears them. Tags are
+    /// lowercased and deduplicated, and automatic tagging leaves them alone.
+    SetSessionTags {
+        session_id: String,
+        tags: Vec<String>,
+    },
+
+    // Ticket linking
+    /// Link a session to a Linear or Jira ticket, replacing any earlier link;
+    /// `None` unlinks it.
+    SetSessionTicket {
+        session_id: String,
+        ticket: Option<TicketLink>,
+    },
+
+    // Presence
+    /// Tell the session's other viewers whether this connection is writing a
+    /// prompt. Cleared when it sends one, unsubscribes, or disconnects.
+    SetComposing {
+        session_id: String,
+        composing: bool,
+    },
+
+    // Session sharing
+    /// Replace who can access a session. Only its creator (or a credential
+    /// without a user) may change this.
+    ShareSession {
+        session_id: String,
+        visibility: SessionVisibility,
+        /// Users who can access a private session besides its creator.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        users: Vec<String>,
+    },
+    GetSessionSharing {
+        session_id: String,
+    },
+
     // Session management
     CreateSession {
         provider: Provider,
@@ -110,6 +168,9 @@ pub enum ClientMessage {
         system_prompt: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         append_system_prompt: Option<String>,
+        /// Start anyway when a `[budgets]` limit set to `confirm` is exhausted.
+        #[serde(default, skip_serializing_if = "is_false")]
+        confirm_over_budget: bool,
     },
     ResumeSession {
         session_id: String,
@@ -172,10 +233,103 @@ pub enum ClientMessage {
         approval_id: i64,
     },
 
+    // Audit log
+    GetAuditLog {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+        /// Return entries older than this id (for paging backwards).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        before_id: Option<i64>,
+    },
+
+    // Usage analytics
+    /// Token, spend, and turn totals grouped by `group_by`; answered with
+    /// `UsageAnalytics`.
+    GetUsageAnalytics {
+        group_by: UsageGroupBy,
+        #[serde(default)]
+        range: UsageRange,
+    },
+
+    // Semantic search
+    /// Sessions and messages closest in meaning to a natural-language
+    /// `query`; answered with `SemanticSearchResults`.
+    SemanticSearch {
+        query: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+    },
+
+    // Server logging (admin)
+    /// Set the level for one tracing target, or the default level when
+    /// `target` is omitted.
+    SetLogLevel {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        target: Option<String>,
+        level: String,
+    },
+    GetLogLevel,
+    /// Re-read the server config file and apply what can change live
+    /// (admin). Same as sending the server SIGHUP.
+    ReloadConfig,
+
+    // Server settings (admin)
+    GetServerConfig {
+        request_id: String,
+    },
+    /// Store one setting; a `null` value clears it.
+    SetServerConfig {
+        request_id: String,
+        key: String,
+        #[serde(default)]
+        value: Option<String>,
+    },
+
+    // Connector process resources
+    GetConnectorStats,
+
+    // Server lifecycle (admin)
+    /// Shut down the same way as SIGINT: flush persistence, keep direct
+    /// sessions resumable, and remove the PID file.
+    ShutdownServer,
+
+    // Connected clients (admin)
+    ListConnections,
+    DisconnectConnection {
+        connection_id: u64,
+    },
+
+    // Push notifications
+    /// Register an APNs device token for attention-needed notifications.
+    RegisterPushDevice {
+        device_token: String,
+        /// `production` (default) or `sandbox` for development builds.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        environment: Option<String>,
+    },
+    UnregisterPushDevice {
+        device_token: String,
+    },
+
     // Codex models
     ListModels,
     // Claude models (cached from DB, populated when sessions are created)
     ListClaudeModels,
+    /// Valid `effort` values for a provider; answered with `effort_levels_list`.
+    ListEffortLevels {
+        request_id: String,
+        provider: Provider,
+    },
+    /// Located provider CLIs and their versions; answered with
+    /// `provider_info`. `refresh` re-probes instead of using the startup
+    /// result.
+    GetProviderInfo {
+        request_id: String,
+        #[serde(default)]
+        refresh: bool,
+    },
     // Codex account/auth state
     CodexAccountRead {
         #[serde(default)]
@@ -210,8 +364,22 @@ pub enum ClientMessage {
     RefreshMcpServers {
         session_id: String,
     },
+    /// MCP servers OrbitDock adds to this session, with env values redacted.
+    ListSessionMcpServers {
+        session_id: String,
+    },
+    /// Add a session MCP server, or replace the one with the same name.
+    SetSessionMcpServer {
+        session_id: String,
+        server: SessionMcpServer,
+    },
+    RemoveSessionMcpServer {
+        session_id: String,
+        name: String,
+    },
 
     // Server config
+    /// Superseded by `SetServerConfig` with key `openai_api_key`.
     SetOpenAiKey {
         key: String,
     },
@@ -223,6 +391,7 @@ pub enum ClientMessage {
         device_name: String,
         is_primary: bool,
     },
+    /// Superseded by `GetServerConfig`.
     CheckOpenAiKey {
         request_id: String,
     },
@@ -241,6 +410,8 @@ pub enum ClientMessage {
         images: Vec<ImageInput>,
         #[serde(default, skip_serializing_if = "Vec::is_empty")]
         mentions: Vec<MentionInput>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        attachment_ids: Vec<String>,
     },
 
     // Context management
@@ -294,6 +465,12 @@ pub enum ClientMessage {
         turn_id: Option<String>,
     },
 
+    // Full message text (broadcasts cap long content and tool output)
+    GetFullMessage {
+        session_id: String,
+        message_id: String,
+    },
+
     // Claude hook transport (server-owned write path)
     ClaudeSessionStart {
         session_id: String,
@@ -402,6 +579,24 @@ pub enum ClientMessage {
         last_assistant_message: Option<String>,
     },
 
+    /// A Codex CLI `notify` payload, forwarded by
+    /// `orbitdock hook-forward codex_notify` with its keys snake_cased.
+    CodexNotifyEvent {
+        /// Codex's notification type, e.g. `agent-turn-complete`.
+        event: String,
+        /// Absent from older Codex versions, which are matched by `cwd`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        thread_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        turn_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cwd: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        input_messages: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        last_assistant_message: Option<String>,
+    },
+
     // Shell execution (provider-independent, user-initiated)
     ExecuteShell {
         session_id: String,
@@ -410,11 +605,115 @@ pub enum ClientMessage {
         cwd: Option<String>,
         #[serde(default = "default_shell_timeout")]
         timeout_secs: u64,
+        /// Run as a background job: persisted, survives the session ending,
+        /// runs until it exits or is canceled (`timeout_secs` doesn't apply),
+        /// and queryable via `ListJobs` / `GetJobOutput`.
+        #[serde(default)]
+        detach: bool,
     },
     CancelShell {
         session_id: String,
         request_id: String,
     },
+    ListJobs {
+        session_id: String,
+    },
+    GetJobOutput {
+        session_id: String,
+        job_id: String,
+    },
+
+    // Interactive terminals (PTY in the session's working directory)
+    OpenTerminal {
+        session_id: String,
+        request_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cwd: Option<String>,
+        #[serde(default = "default_terminal_cols")]
+        cols: u16,
+        #[serde(default = "default_terminal_rows")]
+        rows: u16,
+    },
+    /// Keystrokes for an open terminal (UTF-8 text, written verbatim).
+    TerminalInput {
+        terminal_id: String,
+        data: String,
+    },
+    ResizeTerminal {
+        terminal_id: String,
+        cols: u16,
+        rows: u16,
+    },
+    CloseTerminal {
+        terminal_id: String,
+    },
+
+    // Attention inbox
+    /// Answered with `attention_items`.
+    GetAttentionItems {
+        request_id: String,
+    },
+    /// Hide an item until its session needs attention for a new reason.
+    AcknowledgeAttention {
+        item_id: String,
+    },
+    /// Hide an item for `minutes`.
+    SnoozeAttention {
+        item_id: String,
+        minutes: u32,
+    },
+
+    // Editors on the server's machine
+    /// Open a file in the session's project, relative to its working
+    /// directory unless absolute. `editor` names one from `ListEditors`;
+    /// the server's default otherwise.
+    OpenInEditor {
+        session_id: String,
+        file_path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        editor: Option<String>,
+    },
+    ListEditors {
+        request_id: String,
+    },
+
+    // Event journal
+    /// Stream the session's recorded broadcasts back with their original
+    /// pacing; `speed` 2.0 plays twice as fast.
+    ReplaySession {
+        session_id: String,
+        #[serde(default = "default_replay_speed")]
+        speed: f64,
+    },
+
+    // Turns
+    /// Answered with `turns_list`.
+    GetTurns {
+        session_id: String,
+    },
+
+    // Diagnostics
+    /// The session's connector lifecycle log, for bug reports; answered
+    /// with `session_events`.
+    GetSessionEvents {
+        session_id: String,
+    },
+    /// Re-read a passive session's transcript or rollout and correct the
+    /// messages and token usage that drifted from it; answered with
+    /// `session_resynced`.
+    ResyncSession {
+        session_id: String,
+    },
+    /// `resync_session` for several sessions, or every passive session with
+    /// a transcript when `session_ids` is empty; answered with
+    /// `sessions_resynced`.
+    ResyncSessions {
+        request_id: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        session_ids: Vec<String>,
+    },
 
     // Remote filesystem browsing (for iOS project picker)
     BrowseDirectory {
@@ -455,9 +754,22 @@ fn default_shell_timeout() -> u64 {
     30
 }
 
+fn default_replay_speed() -> f64 {
+    1.0
+}
+
+fn default_terminal_cols() -> u16 {
+    80
+}
+
+fn default_terminal_rows() -> u16 {
+    24
+}
+
 #[cfg(test)]
 mod tests {
     use super::ClientMessage;
+    use crate::types::{SessionVisibility, SnapshotTruncation, UsageGroupBy, UsageRange};
 
     #[test]
     fn deserializes_claude_status_event() {
@@ -712,6 +1024,119 @@ mod tests {
         let _: ClientMessage = serde_json::from_str(&serialized).expect("roundtrip");
     }
 
+    #[test]
+    fn roundtrip_set_session_mcp_server() {
+        let json = r#"{"type":"set_session_mcp_server","session_id":"sess-m3","server":{"name":"github","command":"npx","args":["-y","@modelcontextprotocol/server-github"],"env":{"GITHUB_TOKEN":"ghp_x"}}}"#;
+        let parsed: ClientMessage =
+            serde_json::from_str(json).expect("parse set_session_mcp_server");
+        match &parsed {
+            ClientMessage::SetSessionMcpServer { session_id, server } => {
+                assert_eq!(session_id, "sess-m3");
+                assert_eq!(server.name, "github");
+                assert_eq!(server.args.len(), 2);
+                assert_eq!(
+                    server.env.get("GITHUB_TOKEN").map(String::as_str),
+                    Some("ghp_x")
+                );
+                assert!(server.enabled);
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+        let serialized = serde_json::to_string(&parsed).expect("serialize");
+        let _: ClientMessage = serde_json::from_str(&serialized).expect("roundtrip");
+    }
+
+    #[test]
+    fn parses_open_in_editor_with_optional_fields() {
+        let json = r#"{"type":"open_in_editor","session_id":"sess-e1","file_path":"src/main.rs","line":42}"#;
+        match serde_json::from_str::<ClientMessage>(json).expect("parse open_in_editor") {
+            ClientMessage::OpenInEditor {
+                session_id,
+                file_path,
+                line,
+                editor,
+            } => {
+                assert_eq!(session_id, "sess-e1");
+                assert_eq!(file_path, "src/main.rs");
+                assert_eq!(line, Some(42));
+                assert_eq!(editor, None);
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn roundtrip_get_turns() {
+        let json = r#"{"type":"get_turns","session_id":"sess-t2"}"#;
+        match serde_json::from_str::<ClientMessage>(json).expect("parse get_turns") {
+            ClientMessage::GetTurns { session_id } => assert_eq!(session_id, "sess-t2"),
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn roundtrip_get_session_events() {
+        let json = r#"{"type":"get_session_events","session_id":"sess-d1"}"#;
+        match serde_json::from_str::<ClientMessage>(json).expect("parse get_session_events") {
+            ClientMessage::GetSessionEvents { session_id } => assert_eq!(session_id, "sess-d1"),
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resync_sessions_defaults_to_every_passive_session() {
+        let json = r#"{"type":"resync_sessions","request_id":"req-r1"}"#;
+        match serde_json::from_str::<ClientMessage>(json).expect("parse resync_sessions") {
+            ClientMessage::ResyncSessions {
+                request_id,
+                session_ids,
+            } => {
+                assert_eq!(request_id, "req-r1");
+                assert!(session_ids.is_empty());
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn replay_session_defaults_to_real_time() {
+        let json = r#"{"type":"replay_session","session_id":"sess-r1"}"#;
+        match serde_json::from_str::<ClientMessage>(json).expect("parse replay_session") {
+            ClientMessage::ReplaySession { session_id, speed } => {
+                assert_eq!(session_id, "sess-r1");
+                assert_eq!(speed, 1.0);
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn roundtrip_set_session_ticket() {
+        let json = r#"{"type":"set_session_ticket","session_id":"sess-t1","ticket":{"tracker":"jira","id":"OPS-42","sync":true}}"#;
+        let parsed: ClientMessage = serde_json::from_str(json).expect("parse set_session_ticket");
+        match &parsed {
+            ClientMessage::SetSessionTicket { session_id, ticket } => {
+                assert_eq!(session_id, "sess-t1");
+                let ticket = ticket.as_ref().expect("ticket");
+                assert_eq!(ticket.tracker, crate::types::TicketTracker::Jira);
+                assert_eq!(ticket.id, "OPS-42");
+                assert!(ticket.url.is_none());
+                assert!(ticket.sync);
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+        let serialized = serde_json::to_string(&parsed).expect("serialize");
+        let _: ClientMessage = serde_json::from_str(&serialized).expect("roundtrip");
+
+        let unlink: ClientMessage =
+            serde_json::from_str(r#"{"type":"set_session_ticket","session_id":"s","ticket":null}"#)
+                .expect("parse unlink");
+        assert!(matches!(
+            unlink,
+            ClientMessage::SetSessionTicket { ticket: None, .. }
+        ));
+    }
+
     #[test]
     fn roundtrip_codex_account_read() {
         let json = r#"{"type":"codex_account_read","refresh_token":true}"#;
@@ -784,6 +1209,46 @@ mod tests {
         let _: ClientMessage = serde_json::from_str(&serialized).expect("roundtrip");
     }
 
+    #[test]
+    fn open_terminal_defaults_size() {
+        let json = r#"{"type":"open_terminal","session_id":"sess-t","request_id":"req-t"}"#;
+        let parsed: ClientMessage = serde_json::from_str(json).expect("parse open_terminal");
+        match &parsed {
+            ClientMessage::OpenTerminal {
+                session_id,
+                request_id,
+                cwd,
+                cols,
+                rows,
+            } => {
+                assert_eq!(session_id, "sess-t");
+                assert_eq!(request_id, "req-t");
+                assert!(cwd.is_none());
+                assert_eq!((*cols, *rows), (80, 24));
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+        let serialized = serde_json::to_string(&parsed).expect("serialize");
+        let _: ClientMessage = serde_json::from_str(&serialized).expect("roundtrip");
+    }
+
+    #[test]
+    fn execute_shell_detach_defaults_to_false() {
+        let json = r#"{"type":"execute_shell","session_id":"sess-j","command":"make"}"#;
+        let parsed: ClientMessage = serde_json::from_str(json).expect("parse execute_shell");
+        match parsed {
+            ClientMessage::ExecuteShell {
+                timeout_secs,
+                detach,
+                ..
+            } => {
+                assert_eq!(timeout_secs, 30);
+                assert!(!detach);
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
+
     #[test]
     fn roundtrip_steer_turn() {
         let json =
@@ -795,11 +1260,13 @@ mod tests {
                 content,
                 images,
                 mentions,
+                attachment_ids,
             } => {
                 assert_eq!(session_id, "sess-s1");
                 assert_eq!(content, "use postgres instead");
                 assert!(images.is_empty());
                 assert!(mentions.is_empty());
+                assert!(attachment_ids.is_empty());
             }
             other => panic!("unexpected variant: {:?}", other),
         }
@@ -814,7 +1281,8 @@ mod tests {
           "session_id":"sess-s2",
           "content":"take this into account",
           "images":[{"input_type":"url","value":"data:image/png;base64,iVBOR"}],
-          "mentions":[{"name":"main.rs","path":"/project/src/main.rs"}]
+          "mentions":[{"name":"main.rs","path":"/project/src/main.rs"}],
+          "attachment_ids":["6f1c2d"]
         }"#;
         let parsed: ClientMessage =
             serde_json::from_str(json).expect("parse steer_turn with mixed inputs");
@@ -824,6 +1292,7 @@ mod tests {
                 content,
                 images,
                 mentions,
+                attachment_ids,
             } => {
                 assert_eq!(session_id, "sess-s2");
                 assert_eq!(content, "take this into account");
@@ -831,6 +1300,7 @@ mod tests {
                 assert_eq!(mentions.len(), 1);
                 assert_eq!(images[0].input_type, "url");
                 assert_eq!(mentions[0].name, "main.rs");
+                assert_eq!(attachment_ids, &["6f1c2d".to_string()]);
             }
             other => panic!("unexpected variant: {:?}", other),
         }
@@ -1142,6 +1612,7 @@ mod tests {
                 session_id,
                 since_revision,
                 include_snapshot,
+                ..
             } => {
                 assert_eq!(session_id, "sess-r1");
                 assert_eq!(*since_revision, Some(42));
@@ -1156,6 +1627,7 @@ mod tests {
                 session_id,
                 since_revision,
                 include_snapshot,
+                ..
             } => {
                 assert_eq!(session_id, "sess-r1");
                 assert_eq!(since_revision, Some(42));
@@ -1175,6 +1647,7 @@ mod tests {
                 session_id,
                 since_revision,
                 include_snapshot,
+                ..
             } => {
                 assert_eq!(session_id, "sess-r2");
                 assert_eq!(*since_revision, None);
@@ -1208,6 +1681,7 @@ mod tests {
             session_id: "sess-r3".to_string(),
             since_revision: Some(7),
             include_snapshot: false,
+            snapshot: None,
         };
         let serialized = serde_json::to_string(&parsed).expect("serialize subscribe_session");
         assert!(
@@ -1220,6 +1694,7 @@ mod tests {
                 session_id,
                 since_revision,
                 include_snapshot,
+                ..
             } => {
                 assert_eq!(session_id, "sess-r3");
                 assert_eq!(since_revision, Some(7));
@@ -1229,6 +1704,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_subscribe_session_snapshot_options() {
+        let json = r#"{"type":"subscribe_session","session_id":"sess-r4","snapshot":{"max_messages":500,"truncation":"head_tail"}}"#;
+        let parsed: ClientMessage =
+            serde_json::from_str(json).expect("parse subscribe_session with snapshot options");
+        match parsed {
+            ClientMessage::SubscribeSession { snapshot, .. } => {
+                let snapshot = snapshot.expect("snapshot options");
+                assert_eq!(snapshot.max_messages, Some(500));
+                assert_eq!(snapshot.max_content_chars, None);
+                assert_eq!(snapshot.truncation, Some(SnapshotTruncation::HeadTail));
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
+
     #[test]
     fn roundtrip_send_message_mixed_inputs() {
         let json = r#"{
@@ -1358,4 +1849,156 @@ mod tests {
             assert!(result.is_err(), "payload should fail: {payload}");
         }
     }
+
+    #[test]
+    fn get_audit_log_filters_are_optional() {
+        let msg: ClientMessage = serde_json::from_str(r#"{"type":"get_audit_log"}"#).unwrap();
+        match msg {
+            ClientMessage::GetAuditLog {
+                session_id,
+                limit,
+                before_id,
+            } => {
+                assert!(session_id.is_none());
+                assert!(limit.is_none());
+                assert!(before_id.is_none());
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+
+        let json = serde_json::to_string(&ClientMessage::GetAuditLog {
+            session_id: Some("sess-1".to_string()),
+            limit: Some(50),
+            before_id: None,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"get_audit_log","session_id":"sess-1","limit":50}"#
+        );
+    }
+
+    #[test]
+    fn usage_analytics_range_is_optional() {
+        let msg: ClientMessage =
+            serde_json::from_str(r#"{"type":"get_usage_analytics","group_by":"model"}"#).unwrap();
+        match msg {
+            ClientMessage::GetUsageAnalytics { group_by, range } => {
+                assert_eq!(group_by, UsageGroupBy::Model);
+                assert_eq!(range, UsageRange::default());
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+
+        let json = serde_json::to_string(&ClientMessage::GetUsageAnalytics {
+            group_by: UsageGroupBy::Day,
+            range: UsageRange {
+                since: Some("2026-10-01".to_string()),
+                until: None,
+            },
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"get_usage_analytics","group_by":"day","range":{"since":"2026-10-01"}}"#
+        );
+    }
+
+    #[test]
+    fn share_session_users_are_optional() {
+        let msg: ClientMessage = serde_json::from_str(
+            r#"{"type":"share_session","session_id":"sess-1","visibility":"team"}"#,
+        )
+        .unwrap();
+        match msg {
+            ClientMessage::ShareSession {
+                session_id,
+                visibility,
+                users,
+            } => {
+                assert_eq!(session_id, "sess-1");
+                assert_eq!(visibility, SessionVisibility::Team);
+                assert!(users.is_empty());
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+
+        let json = serde_json::to_string(&ClientMessage::ShareSession {
+            session_id: "sess-1".to_string(),
+            visibility: SessionVisibility::Private,
+            users: vec!["sam".to_string()],
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"share_session","session_id":"sess-1","visibility":"private","users":["sam"]}"#
+        );
+    }
+
+    #[test]
+    fn set_log_level_target_is_optional() {
+        let msg: ClientMessage =
+            serde_json::from_str(r#"{"type":"set_log_level","level":"debug"}"#).unwrap();
+        match msg {
+            ClientMessage::SetLogLevel { target, level } => {
+                assert!(target.is_none());
+                assert_eq!(level, "debug");
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+
+        let json = serde_json::to_string(&ClientMessage::SetLogLevel {
+            target: Some("orbitdock::websocket".to_string()),
+            level: "trace".to_string(),
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"set_log_level","target":"orbitdock::websocket","level":"trace"}"#
+        );
+    }
+
+    #[test]
+    fn register_push_device_environment_defaults_to_none() {
+        let msg: ClientMessage =
+            serde_json::from_str(r#"{"type":"register_push_device","device_token":"a1b2c3"}"#)
+                .unwrap();
+        match msg {
+            ClientMessage::RegisterPushDevice {
+                device_token,
+                environment,
+            } => {
+                assert_eq!(device_token, "a1b2c3");
+                assert!(environment.is_none());
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn shutdown_server_is_a_bare_type() {
+        let json = serde_json::to_string(&ClientMessage::ShutdownServer).unwrap();
+        assert_eq!(json, r#"{"type":"shutdown_server"}"#);
+        let msg: ClientMessage = serde_json::from_str(&json).unwrap();
+        assert!(matches!(msg, ClientMessage::ShutdownServer));
+    }
+
+    #[test]
+    fn reload_config_is_a_bare_type() {
+        let json = serde_json::to_string(&ClientMessage::ReloadConfig).unwrap();
+        assert_eq!(json, r#"{"type":"reload_config"}"#);
+    }
+
+    #[test]
+    fn set_server_config_null_value_clears() {
+        let json =
+            r#"{"type":"set_server_config","request_id":"r1","key":"openai_api_key","value":null}"#;
+        match serde_json::from_str::<ClientMessage>(json).unwrap() {
+            ClientMessage::SetServerConfig { key, value, .. } => {
+                assert_eq!(key, "openai_api_key");
+                assert_eq!(value, None);
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
 }
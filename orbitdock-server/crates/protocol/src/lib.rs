@@ -123,4 +123,76 @@ mod tests {
         let id = ProviderSessionId::new("abc-123").unwrap();
         assert_eq!(format!("{id}"), "abc-123");
     }
+
+    fn subagent(id: &str, parent: Option<&str>) -> SubagentInfo {
+        SubagentInfo {
+            id: id.to_string(),
+            agent_type: "general-purpose".to_string(),
+            started_at: "2026-10-17T09:00:00Z".to_string(),
+            ended_at: None,
+            parent_subagent_id: parent.map(str::to_string),
+            model: None,
+            token_usage: None,
+            estimated_cost_usd: None,
+        }
+    }
+
+    #[test]
+    fn subagent_tree_nests_children_under_their_parents() {
+        let tree = SubagentNode::tree(vec![
+            subagent("a", None),
+            subagent("a1", Some("a")),
+            subagent("a1x", Some("a1")),
+            subagent("b", None),
+            subagent("a2", Some("a")),
+            subagent("orphan", Some("gone")),
+        ]);
+
+        let ids: Vec<&str> = tree.iter().map(|node| node.subagent.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "orphan"]);
+        let a_children: Vec<&str> = tree[0]
+            .children
+            .iter()
+            .map(|node| node.subagent.id.as_str())
+            .collect();
+        assert_eq!(a_children, ["a1", "a2"]);
+        assert_eq!(tree[0].children[0].children[0].subagent.id, "a1x");
+
+        let json = serde_json::to_value(&tree[0]).unwrap();
+        assert_eq!(json["id"], "a");
+        assert_eq!(json["children"][0]["parent_subagent_id"], "a");
+        assert!(json["children"][1].get("children").is_none());
+    }
+
+    #[test]
+    fn session_errors_are_classified_from_provider_messages() {
+        let auth = SessionError::from_message("Invalid API key · Please run /login");
+        assert_eq!(auth.category, ErrorCategory::Auth);
+        assert_eq!(auth.suggested_action, Some(RecoveryAction::Relogin));
+        assert!(!auth.retryable);
+
+        let overloaded = SessionError::from_message("API Error: Overloaded");
+        assert_eq!(overloaded.category, ErrorCategory::Provider);
+        assert!(overloaded.retryable);
+
+        assert_eq!(
+            SessionError::from_message("Prompt is too long").category,
+            ErrorCategory::ContextWindow
+        );
+        assert_eq!(
+            SessionError::from_message("something odd").suggested_action,
+            None
+        );
+    }
+
+    #[test]
+    fn session_error_serializes_without_empty_fields() {
+        let error = SessionError::new(ErrorCategory::RateLimit, "slow down")
+            .with_provider_code("usage_limit_exceeded");
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["category"], "rate_limit");
+        assert_eq!(json["suggested_action"], "wait_for_rate_limit");
+        assert_eq!(json["retryable"], true);
+        assert!(json.get("occurred_at").is_none());
+    }
 }
@@ -43,6 +43,12 @@ pub enum ServerMessage {
         session_id: String,
         usage: TokenUsage,
         snapshot_kind: TokenUsageSnapshotKind,
+        /// The session's estimated spend after this update.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        estimated_cost_usd: Option<f64>,
+        /// Tokens the running turn has spent so far; absent between turns.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        turn_usage: Option<TokenUsage>,
     },
 
     // Lifecycle
@@ -53,6 +59,21 @@ pub enum ServerMessage {
         session_id: String,
         reason: String,
     },
+    /// A direct session's connector died on its own and the server will
+    /// resume it in `retry_in_ms`. Sent to session subscribers before each
+    /// attempt.
+    ConnectorReconnecting {
+        session_id: String,
+        attempt: u32,
+        max_attempts: u32,
+        retry_in_ms: u64,
+        reason: String,
+    },
+    /// The connector is running again after `attempts` tries.
+    ConnectorReconnected {
+        session_id: String,
+        attempts: u32,
+    },
     SessionForked {
         source_session_id: String,
         new_session_id: String,
@@ -60,6 +81,38 @@ pub enum ServerMessage {
         forked_from_thread_id: Option<String>,
     },
 
+    /// A session's topic tags changed, by `SetSessionTags` or automatic
+    /// tagging. Sent to list subscribers.
+    SessionTagsChanged {
+        session_id: String,
+        tags: Vec<String>,
+    },
+
+    /// A session was linked to a ticket (`None`: unlinked). Sent to list
+    /// subscribers.
+    SessionTicketChanged {
+        session_id: String,
+        ticket: Option<TicketLink>,
+    },
+
+    /// Who is subscribed to a session and who is writing a prompt. Sent to
+    /// session subscribers whenever either changes.
+    SessionPresence {
+        session_id: String,
+        viewers: Vec<SessionViewer>,
+    },
+
+    /// Who can access a session, sent after `GetSessionSharing` or
+    /// `ShareSession`.
+    SessionSharing {
+        session_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        visibility: SessionVisibility,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        shared_with: Vec<String>,
+    },
+
     // Approval history
     ApprovalsList {
         session_id: Option<String>,
@@ -69,6 +122,83 @@ pub enum ServerMessage {
         approval_id: i64,
     },
 
+    // Audit log
+    AuditLog {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+        entries: Vec<AuditLogEntry>,
+    },
+
+    // Usage analytics
+    UsageAnalytics {
+        group_by: UsageGroupBy,
+        range: UsageRange,
+        rows: Vec<UsageAnalyticsRow>,
+    },
+
+    // Semantic search
+    /// Closest hits first.
+    SemanticSearchResults {
+        query: String,
+        hits: Vec<SemanticSearchHit>,
+    },
+
+    // Server logging
+    /// Active tracing filter directives, sent after `GetLogLevel` or a
+    /// successful `SetLogLevel`.
+    LogLevel {
+        filter: String,
+    },
+    /// Result of `ReloadConfig`, by config key (`logging.filter`,
+    /// `limits.ws_rate_limit`, ...). Keys set in the server's environment
+    /// keep their env value and are listed under `overridden`.
+    ConfigReloaded {
+        applied: Vec<String>,
+        restart_required: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        overridden: Vec<String>,
+    },
+
+    // Server settings
+    /// Reply to `GetServerConfig`, and to `SetServerConfig` with the one
+    /// setting it changed.
+    ServerConfig {
+        request_id: String,
+        settings: Vec<ServerSetting>,
+    },
+
+    // Budgets
+    /// Estimated spend for the month crossed an alert threshold of a
+    /// `[budgets]` limit. `project_path` is set for per-project budgets.
+    BudgetAlert {
+        session_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        project_path: Option<String>,
+        month: String,
+        spent_usd: f64,
+        budget_usd: f64,
+        threshold: f64,
+    },
+
+    // Connector process resources
+    ConnectorStats {
+        processes: Vec<ConnectorProcessStats>,
+    },
+
+    // Server lifecycle
+    /// Sent to every connected client just before a requested shutdown.
+    ServerShuttingDown,
+
+    // Connected clients
+    ConnectionsList {
+        connections: Vec<ConnectionInfo>,
+    },
+
+    // Push notifications
+    PushDeviceRegistered {
+        device_token: String,
+    },
+
     // Codex models
     ModelsList {
         models: Vec<CodexModelOption>,
@@ -94,6 +224,20 @@ pub enum ServerMessage {
     CodexAccountUpdated {
         status: CodexAccountStatus,
     },
+    /// Codex rate limits changed, as reported by a running session.
+    CodexAccountUsage {
+        usage: CodexUsageSnapshot,
+    },
+    // Claude account/auth status
+    ClaudeAccountStatus {
+        status: ClaudeAccountStatus,
+    },
+    ClaudeLoginCompleted {
+        login_id: String,
+        success: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
 
     // Skills
     SkillsList {
@@ -139,6 +283,15 @@ pub enum ServerMessage {
     ClaudeModelsList {
         models: Vec<crate::ClaudeModelOption>,
     },
+    EffortLevelsList {
+        request_id: String,
+        provider: crate::Provider,
+        levels: Vec<crate::EffortLevel>,
+    },
+    ProviderInfo {
+        request_id: String,
+        providers: Vec<ProviderBinaryInfo>,
+    },
 
     // Claude capabilities (from init system message)
     ClaudeCapabilities {
@@ -150,9 +303,31 @@ pub enum ServerMessage {
     },
 
     // Context management
+    /// Compaction began; `tokens_before` is the context fill it starts from.
+    ContextCompactionStarted {
+        session_id: String,
+        tokens_before: u64,
+    },
+    /// Compaction finished. `tokens_after` is present when the provider
+    /// reported the shrunken context; otherwise the next `tokens_updated` has it.
+    ContextCompactionCompleted {
+        session_id: String,
+        tokens_before: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tokens_after: Option<u64>,
+    },
     ContextCompacted {
         session_id: String,
     },
+    /// The conversation now fills `percent` of the context window, crossing
+    /// the `threshold` warning level; a good time to `compact_context`.
+    ContextWarning {
+        session_id: String,
+        used_tokens: u64,
+        context_window: u64,
+        percent: f64,
+        threshold: f64,
+    },
     UndoStarted {
         session_id: String,
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -183,6 +358,56 @@ pub enum ServerMessage {
         #[serde(skip_serializing_if = "Option::is_none")]
         context_window: Option<u64>,
         snapshot_kind: TokenUsageSnapshotKind,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        turn_usage: Option<TokenUsage>,
+    },
+    /// A one-line summary for a turn in `turn_diffs`.
+    TurnSummarized {
+        session_id: String,
+        turn_id: String,
+        summary: String,
+    },
+
+    // Turns
+    /// Reply to `get_turns`: the session's turns, oldest first.
+    TurnsList {
+        session_id: String,
+        turns: Vec<SessionTurn>,
+    },
+
+    // Diagnostics
+    /// Reply to `get_session_events`: the newest diagnostic events, oldest
+    /// first.
+    SessionEvents {
+        session_id: String,
+        events: Vec<SessionDiagnosticEvent>,
+    },
+    /// Reply to `resync_session`. Corrections were also sent to the
+    /// session's subscribers as ordinary message and token updates.
+    SessionResynced {
+        resync: SessionResync,
+    },
+    /// Reply to `resync_sessions`, one entry per session attempted.
+    SessionsResynced {
+        request_id: String,
+        results: Vec<SessionResync>,
+    },
+
+    // Plans
+    /// The plan changed. Resize the steps to `step_count`, then replace the
+    /// step at each change's `index`.
+    PlanDelta {
+        session_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        explanation: Option<String>,
+        step_count: u32,
+        changes: Vec<PlanStepChange>,
+    },
+
+    // Turn latency
+    TurnMetricsRecorded {
+        session_id: String,
+        metrics: TurnMetrics,
     },
 
     // Review comments
@@ -226,6 +451,86 @@ pub enum ServerMessage {
         outcome: ShellExecutionOutcome,
     },
 
+    // Background shell jobs
+    JobsList {
+        session_id: String,
+        jobs: Vec<crate::ShellJob>,
+    },
+    JobOutput {
+        session_id: String,
+        job: crate::ShellJob,
+        stdout: String,
+        stderr: String,
+    },
+    ShellJobCompleted {
+        session_id: String,
+        job: crate::ShellJob,
+    },
+
+    // Interactive terminals
+    TerminalOpened {
+        session_id: String,
+        request_id: String,
+        terminal_id: String,
+        cwd: String,
+    },
+    /// Raw PTY output, base64-encoded (chunks may split UTF-8 sequences).
+    TerminalOutput {
+        terminal_id: String,
+        data: String,
+    },
+    TerminalClosed {
+        terminal_id: String,
+        exit_code: Option<i32>,
+    },
+
+    // Attention inbox
+    AttentionItems {
+        request_id: String,
+        items: Vec<AttentionItem>,
+    },
+    /// The inbox changed; `items` is all of it, most recent first.
+    AttentionChanged {
+        items: Vec<AttentionItem>,
+    },
+
+    // Editors on the server's machine
+    EditorsList {
+        request_id: String,
+        editors: Vec<EditorInfo>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        default_editor: Option<String>,
+    },
+    EditorOpened {
+        session_id: String,
+        editor: String,
+        /// Absolute path that was opened.
+        file_path: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        line: Option<u32>,
+    },
+
+    // Event journal replay
+    ReplayStarted {
+        session_id: String,
+        replay_id: String,
+        event_count: u64,
+        /// Recorded span from first to last event, before `speed`.
+        duration_ms: u64,
+    },
+    /// One recorded broadcast. `event` is the original `ServerMessage`,
+    /// wrapped so it is never applied as live state.
+    ReplayEvent {
+        session_id: String,
+        replay_id: String,
+        recorded_at_ms: u64,
+        event: serde_json::Value,
+    },
+    ReplayFinished {
+        session_id: String,
+        replay_id: String,
+    },
+
     // Remote filesystem browsing
     DirectoryListing {
         request_id: String,
@@ -327,6 +632,10 @@ pub enum ServerMessage {
         code: String,
         message: String,
         session_id: Option<String>,
+        /// Structured detail when the failure came from a provider or
+        /// connector, so clients can offer a recovery.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<SessionError>,
     },
 }
 
@@ -461,6 +770,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn roundtrip_shell_job_completed() {
+        let msg = ServerMessage::ShellJobCompleted {
+            session_id: "sess-1".to_string(),
+            job: crate::ShellJob {
+                id: "job-1".to_string(),
+                session_id: "sess-1".to_string(),
+                command: "cargo build".to_string(),
+                cwd: "/tmp".to_string(),
+                status: crate::ShellJobStatus::TimedOut,
+                exit_code: None,
+                started_at: "2026-01-01T00:00:00Z".to_string(),
+                finished_at: Some("2026-01-01T00:20:00Z".to_string()),
+                duration_ms: Some(1_200_000),
+            },
+        };
+
+        let json = serde_json::to_string(&msg).expect("serialize");
+        assert!(json.contains(r#""status":"timed_out""#));
+        assert!(!json.contains("exit_code"));
+        let reparsed: ServerMessage = serde_json::from_str(&json).expect("deserialize");
+        match reparsed {
+            ServerMessage::ShellJobCompleted { session_id, job } => {
+                assert_eq!(session_id, "sess-1");
+                assert_eq!(job.id, "job-1");
+                assert_eq!(job.status, crate::ShellJobStatus::TimedOut);
+                assert_eq!(job.duration_ms, Some(1_200_000));
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
+
     #[test]
     fn roundtrip_mcp_startup_complete() {
         let msg = ServerMessage::McpStartupComplete {
@@ -545,6 +886,7 @@ mod tests {
                 }),
                 login_in_progress: false,
                 active_login_id: None,
+                usage: None,
             },
         };
 
@@ -560,6 +902,94 @@ mod tests {
         }
     }
 
+    #[test]
+    fn roundtrip_codex_account_usage() {
+        let msg = ServerMessage::CodexAccountUsage {
+            usage: CodexUsageSnapshot {
+                primary: Some(CodexRateLimitWindow {
+                    used_percent: 92.5,
+                    window_duration_mins: 300,
+                    resets_at_unix: 1_760_000_000.0,
+                }),
+                secondary: None,
+                plan_type: Some("plus".to_string()),
+                fetched_at_unix: 1_759_990_000.0,
+            },
+        };
+
+        let json = serde_json::to_value(&msg).expect("serialize");
+        assert_eq!(json["type"], "codex_account_usage");
+        assert_eq!(json["usage"]["primary"]["used_percent"], 92.5);
+        assert!(json["usage"].get("secondary").is_none());
+
+        let reparsed: ServerMessage = serde_json::from_value(json).expect("deserialize");
+        match reparsed {
+            ServerMessage::CodexAccountUsage { usage } => {
+                assert_eq!(usage.plan_type.as_deref(), Some("plus"));
+                assert_eq!(usage.primary.map(|w| w.window_duration_mins), Some(300));
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn roundtrip_claude_account_status() {
+        let msg = ServerMessage::ClaudeAccountStatus {
+            status: ClaudeAccountStatus {
+                logged_in: false,
+                cli_path: Some("/usr/local/bin/claude".to_string()),
+                problem: Some("Claude CLI is not logged in.".to_string()),
+                ..Default::default()
+            },
+        };
+
+        let json = serde_json::to_value(&msg).expect("serialize");
+        assert_eq!(json["type"], "claude_account_status");
+        assert!(json["status"].get("email").is_none());
+
+        let reparsed: ServerMessage = serde_json::from_value(json).expect("deserialize");
+        match reparsed {
+            ServerMessage::ClaudeAccountStatus { status } => {
+                assert!(!status.logged_in);
+                assert_eq!(
+                    status.problem.as_deref(),
+                    Some("Claude CLI is not logged in.")
+                );
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn roundtrip_provider_info() {
+        let msg = ServerMessage::ProviderInfo {
+            request_id: "req-1".to_string(),
+            providers: vec![ProviderBinaryInfo {
+                provider: Provider::Codex,
+                status: ProviderBinaryStatus::NotFound,
+                path: None,
+                version: None,
+                min_version: "0.50.0".to_string(),
+                detail: Some("codex not found".to_string()),
+                checked_at: 1_700_000_000,
+            }],
+        };
+
+        let json = serde_json::to_value(&msg).expect("serialize");
+        assert_eq!(json["type"], "provider_info");
+        assert_eq!(json["providers"][0]["status"], "not_found");
+        assert!(json["providers"][0].get("path").is_none());
+
+        let reparsed: ServerMessage = serde_json::from_value(json).expect("deserialize");
+        match reparsed {
+            ServerMessage::ProviderInfo { providers, .. } => {
+                assert_eq!(providers[0].provider, Provider::Codex);
+                assert_eq!(providers[0].status, ProviderBinaryStatus::NotFound);
+            }
+            other => panic!("unexpected variant: {:?}", other),
+        }
+    }
+
     #[test]
     fn roundtrip_codex_login_chatgpt_started() {
         let msg = ServerMessage::CodexLoginChatgptStarted {
@@ -698,6 +1128,159 @@ mod tests {
         }
     }
 
+    #[test]
+    fn turns_list_omits_running_turn_fields() {
+        let msg = ServerMessage::TurnsList {
+            session_id: "sess-1".to_string(),
+            turns: vec![SessionTurn {
+                turn_id: "turn-1".to_string(),
+                started_at: "2026-01-01T00:00:00Z".to_string(),
+                ended_at: None,
+                aborted: false,
+                prompt: Some("fix the build".to_string()),
+                first_message_sequence: 0,
+                last_message_sequence: None,
+                message_count: 3,
+                diff: None,
+                summary: None,
+                token_usage: None,
+                snapshot_kind: None,
+                turn_usage: None,
+                duration_ms: None,
+            }],
+        };
+
+        let json = serde_json::to_string(&msg).expect("serialize");
+        assert!(json.contains(r#""type":"turns_list""#));
+        assert!(!json.contains("ended_at"));
+        assert!(!json.contains("last_message_sequence"));
+        match serde_json::from_str::<ServerMessage>(&json).expect("deserialize") {
+            ServerMessage::TurnsList { turns, .. } => {
+                assert_eq!(turns[0].prompt.as_deref(), Some("fix the build"));
+                assert_eq!(turns[0].message_count, 3);
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn context_compaction_completed_omits_unknown_tokens_after() {
+        let msg = ServerMessage::ContextCompactionCompleted {
+            session_id: "sess-1".to_string(),
+            tokens_before: 150_000,
+            tokens_after: None,
+        };
+
+        let json = serde_json::to_string(&msg).expect("serialize");
+        assert!(json.contains(r#""type":"context_compaction_completed""#));
+        assert!(!json.contains("tokens_after"));
+        match serde_json::from_str::<ServerMessage>(&json).expect("deserialize") {
+            ServerMessage::ContextCompactionCompleted {
+                tokens_before,
+                tokens_after,
+                ..
+            } => {
+                assert_eq!(tokens_before, 150_000);
+                assert_eq!(tokens_after, None);
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_turn_metrics_recorded() {
+        let msg = ServerMessage::TurnMetricsRecorded {
+            session_id: "sess-1".to_string(),
+            metrics: TurnMetrics {
+                turn_id: "turn-2".to_string(),
+                time_to_first_token_ms: Some(850),
+                duration_ms: 12_400,
+                tool_duration_ms: 3_100,
+                aborted: false,
+                estimated_cost_usd: Some(0.042),
+            },
+        };
+
+        let json = serde_json::to_string(&msg).expect("serialize");
+        assert!(json.contains(r#""type":"turn_metrics_recorded""#));
+        let reparsed: ServerMessage = serde_json::from_str(&json).expect("deserialize");
+        match reparsed {
+            ServerMessage::TurnMetricsRecorded {
+                session_id,
+                metrics,
+            } => {
+                assert_eq!(session_id, "sess-1");
+                assert_eq!(metrics.time_to_first_token_ms, Some(850));
+                assert_eq!(metrics.duration_ms, 12_400);
+                assert_eq!(metrics.tool_duration_ms, 3_100);
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_connector_stats() {
+        let server = ConnectorProcessStats {
+            session_id: None,
+            provider: None,
+            pid: 100,
+            cpu_percent: 3.5,
+            rss_bytes: 80 * 1024 * 1024,
+            sampled_at: "1700000000Z".to_string(),
+        };
+        let claude = ConnectorProcessStats {
+            session_id: Some("sess-1".to_string()),
+            provider: Some(Provider::Claude),
+            pid: 4242,
+            cpu_percent: 97.0,
+            rss_bytes: 12 * 1024 * 1024 * 1024,
+            sampled_at: "1700000000Z".to_string(),
+        };
+        let msg = ServerMessage::ConnectorStats {
+            processes: vec![server.clone(), claude.clone()],
+        };
+
+        let json = serde_json::to_string(&msg).expect("serialize");
+        assert!(json.contains(r#""type":"connector_stats""#));
+        let reparsed: ServerMessage = serde_json::from_str(&json).expect("deserialize");
+        match reparsed {
+            ServerMessage::ConnectorStats { processes } => {
+                assert_eq!(processes, vec![server, claude]);
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn roundtrip_connections_list() {
+        let connection = ConnectionInfo {
+            connection_id: 7,
+            identity: "token:abc".to_string(),
+            user: Some("alice".to_string()),
+            remote_addr: Some("100.64.0.2:51234".to_string()),
+            client_name: Some("OrbitDock-iOS".to_string()),
+            client_version: Some("1.4.0".to_string()),
+            device_name: None,
+            connected_at: "1700000000Z".to_string(),
+            subscribed_to_list: true,
+            subscriptions: vec!["sess-1".to_string()],
+        };
+        let msg = ServerMessage::ConnectionsList {
+            connections: vec![connection.clone()],
+        };
+
+        let json = serde_json::to_string(&msg).expect("serialize");
+        assert!(json.contains(r#""type":"connections_list""#));
+        assert!(!json.contains("device_name"));
+        let reparsed: ServerMessage = serde_json::from_str(&json).expect("deserialize");
+        match reparsed {
+            ServerMessage::ConnectionsList { connections } => {
+                assert_eq!(connections, vec![connection]);
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+    }
+
     #[test]
     fn roundtrip_turn_diff_snapshot() {
         let msg = ServerMessage::TurnDiffSnapshot {
@@ -709,6 +1292,12 @@ mod tests {
             cached_tokens: Some(3000),
             context_window: Some(200000),
             snapshot_kind: TokenUsageSnapshotKind::ContextTurn,
+            turn_usage: Some(TokenUsage {
+                input_tokens: 1800,
+                output_tokens: 1200,
+                cached_tokens: 900,
+                context_window: 200000,
+            }),
         };
 
         let json = serde_json::to_string(&msg).expect("serialize");
@@ -723,6 +1312,7 @@ mod tests {
                 cached_tokens,
                 context_window,
                 snapshot_kind,
+                turn_usage,
             } => {
                 assert_eq!(session_id, "sess-1");
                 assert_eq!(turn_id, "turn-3");
@@ -732,6 +1322,7 @@ mod tests {
                 assert_eq!(cached_tokens, Some(3000));
                 assert_eq!(context_window, Some(200000));
                 assert_eq!(snapshot_kind, TokenUsageSnapshotKind::ContextTurn);
+                assert_eq!(turn_usage.map(|usage| usage.input_tokens), Some(1800));
             }
             other => panic!("unexpected variant: {:?}", other),
         }
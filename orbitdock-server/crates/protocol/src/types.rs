@@ -1,5 +1,7 @@
 //! Core types shared across the protocol
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -79,6 +81,73 @@ pub enum ShellExecutionOutcome {
     Canceled,
 }
 
+/// Lifecycle status of a detached (background) shell job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellJobStatus {
+    Running,
+    Completed,
+    Failed,
+    TimedOut,
+    Canceled,
+    /// The server stopped while the job was still running.
+    Interrupted,
+}
+
+impl ShellJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::TimedOut => "timed_out",
+            Self::Canceled => "canceled",
+            Self::Interrupted => "interrupted",
+        }
+    }
+
+    pub fn from_str_opt(s: &str) -> Option<Self> {
+        match s {
+            "running" => Some(Self::Running),
+            "completed" => Some(Self::Completed),
+            "failed" => Some(Self::Failed),
+            "timed_out" => Some(Self::TimedOut),
+            "canceled" => Some(Self::Canceled),
+            "interrupted" => Some(Self::Interrupted),
+            _ => None,
+        }
+    }
+}
+
+impl From<ShellExecutionOutcome> for ShellJobStatus {
+    fn from(outcome: ShellExecutionOutcome) -> Self {
+        match outcome {
+            ShellExecutionOutcome::Completed => Self::Completed,
+            ShellExecutionOutcome::Failed => Self::Failed,
+            ShellExecutionOutcome::TimedOut => Self::TimedOut,
+            ShellExecutionOutcome::Canceled => Self::Canceled,
+        }
+    }
+}
+
+/// A detached shell job started with `ExecuteShell { detach: true }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShellJob {
+    /// Same as the `request_id` reported in `ShellStarted`.
+    pub id: String,
+    pub session_id: String,
+    pub command: String,
+    pub cwd: String,
+    pub status: ShellJobStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub started_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
 /// A message in the conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
@@ -156,6 +225,69 @@ impl TokenUsage {
         (self.input_tokens as f64 / self.context_window as f64) * 100.0
     }
 
+    /// Tokens occupying the context window. Claude reports cache reads and
+    /// writes apart from input; Codex counts them within it.
+    pub fn context_tokens(&self, provider: Provider) -> u64 {
+        match provider {
+            Provider::Claude => self.input_tokens + self.cached_tokens,
+            Provider::Codex => self.input_tokens,
+        }
+    }
+
+    /// Tokens `next` adds on top of the previous snapshot.
+    ///
+    /// - `ContextTurn` is one request's usage, so all of it is new.
+    /// - `MixedLegacy` is one request's input with cumulative output.
+    /// - `LifetimeTotals` is cumulative, so only the increase counts.
+    /// - Compaction resets and unknown snapshots add nothing.
+    ///
+    /// A snapshot identical to the previous one is a repeat and adds nothing.
+    pub fn delta(
+        previous: (&TokenUsage, TokenUsageSnapshotKind),
+        next: (&TokenUsage, TokenUsageSnapshotKind),
+    ) -> Self {
+        let (prev, prev_kind) = previous;
+        let (usage, kind) = next;
+        let repeated = prev_kind == kind
+            && prev.input_tokens == usage.input_tokens
+            && prev.output_tokens == usage.output_tokens
+            && prev.cached_tokens == usage.cached_tokens;
+        if repeated {
+            return Self::default();
+        }
+
+        let tokens = |input_tokens, output_tokens, cached_tokens| Self {
+            input_tokens,
+            output_tokens,
+            cached_tokens,
+            context_window: 0,
+        };
+        let output_delta = if prev_kind == kind && usage.output_tokens >= prev.output_tokens {
+            usage.output_tokens - prev.output_tokens
+        } else {
+            usage.output_tokens
+        };
+        match kind {
+            TokenUsageSnapshotKind::ContextTurn => {
+                tokens(usage.input_tokens, usage.output_tokens, usage.cached_tokens)
+            }
+            TokenUsageSnapshotKind::MixedLegacy => {
+                tokens(usage.input_tokens, output_delta, usage.cached_tokens)
+            }
+            TokenUsageSnapshotKind::LifetimeTotals if prev_kind == kind => tokens(
+                usage.input_tokens.saturating_sub(prev.input_tokens),
+                output_delta,
+                usage.cached_tokens.saturating_sub(prev.cached_tokens),
+            ),
+            TokenUsageSnapshotKind::LifetimeTotals => {
+                tokens(usage.input_tokens, usage.output_tokens, usage.cached_tokens)
+            }
+            TokenUsageSnapshotKind::CompactionReset | TokenUsageSnapshotKind::Unknown => {
+                Self::default()
+            }
+        }
+    }
+
     /// Calculate cache hit percentage
     pub fn cache_hit_percent(&self) -> f64 {
         if self.input_tokens == 0 {
@@ -226,6 +358,17 @@ pub enum ApprovalType {
     Question,
 }
 
+/// Who besides its creator can see and steer a session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionVisibility {
+    /// The creator and any users it is shared with.
+    #[default]
+    Private,
+    /// Every user on the server.
+    Team,
+}
+
 fn bool_is_false(value: &bool) -> bool {
     !*value
 }
@@ -311,6 +454,26 @@ pub struct ApprovalHistoryItem {
     pub decided_at: Option<String>,
 }
 
+/// A recorded state-changing client action.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub created_at: String,
+    pub connection_id: u64,
+    /// Authenticated caller: `anonymous`, `static_token`, or `token:<id>`.
+    pub identity: String,
+    /// Name of the user the token belongs to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// The `ClientMessage` type, e.g. `approve_tool`.
+    pub action: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Action-specific fields (never message bodies or secrets).
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+}
+
 /// Summary of a session for list views
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionSummary {
@@ -335,6 +498,9 @@ pub struct SessionSummary {
     pub token_usage: TokenUsage,
     #[serde(default)]
     pub token_usage_snapshot_kind: TokenUsageSnapshotKind,
+    /// Estimated spend at the server's pricing table; absent for unpriced models.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
     pub has_pending_approval: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub codex_integration_mode: Option<CodexIntegrationMode>,
@@ -359,6 +525,9 @@ pub struct SessionSummary {
     pub pending_approval_id: Option<String>,
     pub started_at: Option<String>,
     pub last_activity_at: Option<String>,
+    /// Name of the user whose token created the session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub git_branch: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -382,6 +551,19 @@ pub struct SessionSummary {
     /// Number of unread messages in this session.
     #[serde(default)]
     pub unread_count: u64,
+    /// Topic tags, set by hand or picked by the naming provider.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Peer server the session runs on, for sessions relayed from another
+    /// server. Their ids are namespaced as `<peer>:<id>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// GitHub issue the session was started from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<IssueLink>,
+    /// External ticket the session is linked to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<TicketLink>,
 }
 
 /// A diff snapshot from a completed turn
@@ -393,6 +575,303 @@ pub struct TurnDiff {
     pub token_usage: Option<TokenUsage>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub snapshot_kind: Option<TokenUsageSnapshotKind>,
+    /// Tokens the turn itself spent, as opposed to the `token_usage` snapshot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn_usage: Option<TokenUsage>,
+    /// One line on what the turn changed, filled in after it completes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+/// Where a plan step stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanStepStatus {
+    Pending,
+    InProgress,
+    Done,
+    Skipped,
+}
+
+impl PlanStepStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::InProgress => "in_progress",
+            Self::Done => "done",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// One step of a session's plan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub title: String,
+    pub status: PlanStepStatus,
+}
+
+/// The agent's current plan, from Codex `update_plan` or Claude `TodoWrite`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionPlan {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<PlanStep>,
+}
+
+/// A step that is new or changed in a `plan_delta`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanStepChange {
+    /// Position in the plan, from 0.
+    pub index: u32,
+    pub title: String,
+    pub status: PlanStepStatus,
+    /// The step's status before, if this position held the same step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_status: Option<PlanStepStatus>,
+}
+
+/// Latency and duration measurements for a single turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnMetrics {
+    pub turn_id: String,
+    /// Time from turn start to the first assistant, reasoning, or tool output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_to_first_token_ms: Option<u64>,
+    pub duration_ms: u64,
+    /// Sum of reported tool execution durations within the turn.
+    #[serde(default)]
+    pub tool_duration_ms: u64,
+    /// True if the turn was interrupted or the session ended mid-turn.
+    #[serde(default)]
+    pub aborted: bool,
+    /// Estimated spend during this turn; absent for unpriced models.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
+}
+
+/// One prompt-to-completion turn of a session, from `get_turns`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTurn {
+    pub turn_id: String,
+    pub started_at: String,
+    /// Absent while the turn is running.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<String>,
+    /// True if the turn was interrupted or the session ended mid-turn.
+    #[serde(default)]
+    pub aborted: bool,
+    /// The first user message of the turn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Sequence of the turn's first message; later turns start after it ends.
+    pub first_message_sequence: u64,
+    /// Sequence of the turn's last message; absent while the turn is running.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_message_sequence: Option<u64>,
+    pub message_count: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Token usage snapshot when the turn ended.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_kind: Option<TokenUsageSnapshotKind>,
+    /// Tokens the turn itself spent, summed over its usage updates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn_usage: Option<TokenUsage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+/// A connector lifecycle milestone from a session's diagnostic log, from
+/// `get_session_events`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDiagnosticEvent {
+    pub id: i64,
+    pub created_at: String,
+    /// e.g. `connector_spawned`, `connector_timed_out`, `thread_id_changed`.
+    pub kind: String,
+    /// Kind-specific fields: provider, trigger, error, thread ids.
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+}
+
+/// What a transcript resync corrected, from `resync_session` or
+/// `resync_sessions`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionResync {
+    pub session_id: String,
+    /// Transcript messages the session was missing, now appended.
+    pub messages_added: u32,
+    /// Messages whose content, tool output, or status disagreed with the
+    /// transcript.
+    pub messages_updated: u32,
+    /// Messages the server had but the database had lost, written back.
+    pub messages_restored: u32,
+    pub token_usage_corrected: bool,
+    /// Why the session was skipped; only set in `sessions_resynced`, since a
+    /// single `resync_session` answers failures with `error`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Aggregated turn latency for one provider/model pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnLatencyStats {
+    pub provider: Provider,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub turns: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_time_to_first_token_ms: Option<f64>,
+    pub avg_duration_ms: f64,
+    pub avg_tool_duration_ms: f64,
+}
+
+/// How `GetUsageAnalytics` buckets usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageGroupBy {
+    /// UTC calendar day.
+    Day,
+    Project,
+    Provider,
+    Model,
+}
+
+/// Time window for usage analytics. Each end is a date or ISO-8601
+/// timestamp; `since` is inclusive, `until` exclusive, and either may be open.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageRange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
+}
+
+/// Usage totals for one day, project, provider, or model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageAnalyticsRow {
+    /// `YYYY-MM-DD`, project path, `claude`/`codex`, or model name.
+    pub key: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cached_tokens: u64,
+    /// Absent when none of the usage was on a priced model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
+    pub turns: u64,
+    pub sessions: u64,
+}
+
+/// A message or session summary matched by `SemanticSearch`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SemanticSearchHit {
+    pub session_id: String,
+    /// Absent when the hit is the session summary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_name: Option<String>,
+    pub project_path: String,
+    pub snippet: String,
+    /// Cosine distance from the query; lower is closer.
+    pub distance: f64,
+}
+
+/// Latest resource sample for a connector process.
+///
+/// Codex runs inside the server process, so its usage appears on the entry
+/// with no `session_id` (the server itself).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectorProcessStats {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<Provider>,
+    pub pid: u32,
+    /// CPU usage since the previous sample; 100.0 is one full core.
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub sampled_at: String,
+}
+
+/// A WebSocket client attached to the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionInfo {
+    pub connection_id: u64,
+    /// Auth identity label (`anonymous`, `static_token`, `token:<id>`).
+    pub identity: String,
+    /// Name of the user the token belongs to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_addr: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_version: Option<String>,
+    /// Device name from the client's primary claim, once it has sent one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_name: Option<String>,
+    pub connected_at: String,
+    pub subscribed_to_list: bool,
+    #[serde(default)]
+    pub subscriptions: Vec<String>,
+}
+
+/// A connection subscribed to a session, as shown to the session's other
+/// viewers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionViewer {
+    pub connection_id: u64,
+    /// Name of the user the connection's token belongs to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    /// Device name from the client's primary claim, once it has sent one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_name: Option<String>,
+    /// Whether the viewer is writing a prompt for the session.
+    #[serde(default)]
+    pub composing: bool,
+}
+
+/// GitHub issue a session was started from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssueLink {
+    /// `owner/name`
+    pub repo: String,
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+}
+
+/// Issue tracker a ticket lives in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TicketTracker {
+    Linear,
+    Jira,
+}
+
+/// External ticket a session's work belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TicketLink {
+    pub tracker: TicketTracker,
+    /// Ticket key, e.g. `ENG-123`.
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Comment on the ticket with the session's summary and pull request
+    /// when the session ends.
+    #[serde(default)]
+    pub sync: bool,
 }
 
 /// Subagent metadata
@@ -403,6 +882,74 @@ pub struct SubagentInfo {
     pub started_at: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ended_at: Option<String>,
+    /// Subagent that spawned this one; `None` for subagents of the session itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_subagent_id: Option<String>,
+    /// Model the subagent ran on, once it has stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Tokens the subagent used, once it has stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+    /// Estimated spend, included in the parent session's `estimated_cost_usd`;
+    /// `None` for an unpriced model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
+}
+
+/// A subagent and the subagents it spawned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubagentNode {
+    #[serde(flatten)]
+    pub subagent: SubagentInfo,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SubagentNode>,
+}
+
+impl SubagentNode {
+    /// Nest a session's flat subagent list under each `parent_subagent_id`,
+    /// keeping the list's order. Subagents whose parent isn't in the list are
+    /// roots.
+    pub fn tree(subagents: Vec<SubagentInfo>) -> Vec<SubagentNode> {
+        let ids: std::collections::HashSet<String> =
+            subagents.iter().map(|subagent| subagent.id.clone()).collect();
+        let mut children: std::collections::HashMap<String, Vec<SubagentInfo>> =
+            std::collections::HashMap::new();
+        let mut roots = Vec::new();
+        for subagent in subagents {
+            match subagent.parent_subagent_id.clone() {
+                Some(parent) if parent != subagent.id && ids.contains(&parent) => {
+                    children.entry(parent).or_default().push(subagent)
+                }
+                _ => roots.push(subagent),
+            }
+        }
+
+        fn build(
+            subagent: SubagentInfo,
+            children: &mut std::collections::HashMap<String, Vec<SubagentInfo>>,
+        ) -> SubagentNode {
+            let nested = children.remove(&subagent.id).unwrap_or_default();
+            SubagentNode {
+                children: nested
+                    .into_iter()
+                    .map(|child| build(child, children))
+                    .collect(),
+                subagent,
+            }
+        }
+        let mut tree: Vec<SubagentNode> = roots
+            .into_iter()
+            .map(|root| build(root, &mut children))
+            .collect();
+        // Whatever is left sits in a parent cycle; show it rather than drop it.
+        let stranded: Vec<SubagentInfo> = children.into_values().flatten().collect();
+        tree.extend(stranded.into_iter().map(|subagent| SubagentNode {
+            subagent,
+            children: Vec::new(),
+        }));
+        tree
+    }
 }
 
 /// A tool call from a subagent transcript
@@ -414,6 +961,33 @@ pub struct SubagentTool {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<String>,
     pub is_in_progress: bool,
+    /// Subagent a Task call spawned, once its result names it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subagent_id: Option<String>,
+}
+
+/// Which messages a size-limited snapshot keeps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotTruncation {
+    /// The most recent messages.
+    #[default]
+    Tail,
+    /// The session's first user prompts, then the most recent messages.
+    HeadTail,
+}
+
+/// Per-subscription snapshot limits. Unset fields use the server's defaults;
+/// the server still caps both numbers and shrinks snapshots that would not
+/// fit in one WebSocket frame.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_messages: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_content_chars: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncation: Option<SnapshotTruncation>,
 }
 
 /// Full session state
@@ -448,6 +1022,15 @@ pub struct SessionState {
     pub pending_approval: Option<ApprovalRequest>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub permission_mode: Option<String>,
+    /// Tools a Claude session may use without asking (`--allowedTools`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_tools: Vec<String>,
+    /// Tools a Claude session may not use (`--disallowedTools`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disallowed_tools: Vec<String>,
+    /// The session's latest error, until a turn starts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<SessionError>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pending_tool_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -459,8 +1042,21 @@ pub struct SessionState {
     pub token_usage: TokenUsage,
     #[serde(default)]
     pub token_usage_snapshot_kind: TokenUsageSnapshotKind,
+    /// Estimated spend at the server's pricing table; absent for unpriced models.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
     pub current_diff: Option<String>,
     pub current_plan: Option<String>,
+    /// Structured form of the plan; kept current by `plan_delta`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<SessionPlan>,
+    /// True while `steer_turn` reaches the agent: an active session OrbitDock
+    /// drives directly, Codex or Claude.
+    #[serde(default)]
+    pub steerable: bool,
+    /// True between `context_compaction_started` and `..._completed`.
+    #[serde(default)]
+    pub compact_in_progress: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub codex_integration_mode: Option<CodexIntegrationMode>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -473,6 +1069,9 @@ pub struct SessionState {
     pub last_activity_at: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub forked_from_session_id: Option<String>,
+    /// Name of the user whose token created the session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub revision: Option<u64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -481,6 +1080,9 @@ pub struct SessionState {
     pub turn_count: u64,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub turn_diffs: Vec<TurnDiff>,
+    /// The most recent turns only; `/api/analytics/turn-latency` has the rest.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub turn_metrics: Vec<TurnMetrics>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub git_branch: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -510,6 +1112,9 @@ pub struct SessionState {
     /// Number of unread messages in this session.
     #[serde(default)]
     pub unread_count: u64,
+    /// Connections subscribed to this session.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub viewers: Vec<SessionViewer>,
 }
 
 /// Changes to apply to a session state (delta updates)
@@ -548,6 +1153,12 @@ pub struct StateChanges {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub permission_mode: Option<Option<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disallowed_tools: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<Option<SessionError>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub last_activity_at: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub current_turn_id: Option<Option<String>>,
@@ -573,6 +1184,150 @@ pub struct StateChanges {
     /// Updated unread message count.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub unread_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub steerable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compact_in_progress: Option<bool>,
+}
+
+impl StateChanges {
+    /// Fold `later` into these changes; fields it sets replace earlier values.
+    pub fn merge(&mut self, later: StateChanges) {
+        let StateChanges {
+            status,
+            work_status,
+            pending_approval,
+            token_usage,
+            token_usage_snapshot_kind,
+            current_diff,
+            current_plan,
+            custom_name,
+            summary,
+            first_prompt,
+            last_message,
+            codex_integration_mode,
+            claude_integration_mode,
+            approval_policy,
+            sandbox_mode,
+            permission_mode,
+            allowed_tools,
+            disallowed_tools,
+            last_error,
+            last_activity_at,
+            current_turn_id,
+            turn_count,
+            git_branch,
+            git_sha,
+            current_cwd,
+            model,
+            effort,
+            approval_version,
+            repository_root,
+            is_worktree,
+            unread_count,
+            steerable,
+            compact_in_progress,
+        } = later;
+        if status.is_some() {
+            self.status = status;
+        }
+        if work_status.is_some() {
+            self.work_status = work_status;
+        }
+        if pending_approval.is_some() {
+            self.pending_approval = pending_approval;
+        }
+        if token_usage.is_some() {
+            self.token_usage = token_usage;
+        }
+        if token_usage_snapshot_kind.is_some() {
+            self.token_usage_snapshot_kind = token_usage_snapshot_kind;
+        }
+        if current_diff.is_some() {
+            self.current_diff = current_diff;
+        }
+        if current_plan.is_some() {
+            self.current_plan = current_plan;
+        }
+        if custom_name.is_some() {
+            self.custom_name = custom_name;
+        }
+        if summary.is_some() {
+            self.summary = summary;
+        }
+        if first_prompt.is_some() {
+            self.first_prompt = first_prompt;
+        }
+        if last_message.is_some() {
+            self.last_message = last_message;
+        }
+        if codex_integration_mode.is_some() {
+            self.codex_integration_mode = codex_integration_mode;
+        }
+        if claude_integration_mode.is_some() {
+            self.claude_integration_mode = claude_integration_mode;
+        }
+        if approval_policy.is_some() {
+            self.approval_policy = approval_policy;
+        }
+        if sandbox_mode.is_some() {
+            self.sandbox_mode = sandbox_mode;
+        }
+        if permission_mode.is_some() {
+            self.permission_mode = permission_mode;
+        }
+        if allowed_tools.is_some() {
+            self.allowed_tools = allowed_tools;
+        }
+        if disallowed_tools.is_some() {
+            self.disallowed_tools = disallowed_tools;
+        }
+        if last_error.is_some() {
+            self.last_error = last_error;
+        }
+        if last_activity_at.is_some() {
+            self.last_activity_at = last_activity_at;
+        }
+        if current_turn_id.is_some() {
+            self.current_turn_id = current_turn_id;
+        }
+        if turn_count.is_some() {
+            self.turn_count = turn_count;
+        }
+        if git_branch.is_some() {
+            self.git_branch = git_branch;
+        }
+        if git_sha.is_some() {
+            self.git_sha = git_sha;
+        }
+        if current_cwd.is_some() {
+            self.current_cwd = current_cwd;
+        }
+        if model.is_some() {
+            self.model = model;
+        }
+        if effort.is_some() {
+            self.effort = effort;
+        }
+        if approval_version.is_some() {
+            self.approval_version = approval_version;
+        }
+        if repository_root.is_some() {
+            self.repository_root = repository_root;
+        }
+        if is_worktree.is_some() {
+            self.is_worktree = is_worktree;
+        }
+        if unread_count.is_some() {
+            self.unread_count = unread_count;
+        }
+        if steerable.is_some() {
+            self.steerable = steerable;
+        }
+        if compact_in_progress.is_some() {
+            self.compact_in_progress = compact_in_progress;
+        }
+    }
 }
 
 /// Changes to apply to a message (delta updates)
@@ -611,6 +1366,17 @@ pub struct ClaudeModelOption {
     pub description: String,
 }
 
+/// A value the provider accepts for `effort` on create or send.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffortLevel {
+    pub value: String,
+    pub description: String,
+    pub is_default: bool,
+    /// Claude's extended-thinking budget for the level.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_thinking_tokens: Option<u64>,
+}
+
 /// Skill attached to a message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkillInput {
@@ -621,9 +1387,11 @@ pub struct SkillInput {
 /// Image attached to a message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageInput {
-    /// "url" for data URI, "path" for local file
+    /// "url" for data URI, "path" for local file, "server" for an image the
+    /// server stores (sent only by the server)
     pub input_type: String,
-    /// Data URI string or local file path
+    /// Data URI string, local file path, or `/api/images/...` URL to fetch
+    /// from the server with the usual auth
     pub value: String,
 }
 
@@ -634,6 +1402,26 @@ pub struct MentionInput {
     pub path: String,
 }
 
+/// File attached to a message (text file, log, PDF, or voice recording),
+/// uploaded with `POST /api/sessions/{session_id}/attachments`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    pub session_id: String,
+    /// File name as uploaded
+    pub name: String,
+    /// "text/plain", "application/pdf", or an "audio/*" type
+    pub mime_type: String,
+    pub size_bytes: u64,
+    pub created_at: String,
+    /// Message the attachment was sent with, once sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    /// What was said in an audio attachment, once sent and transcribed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcript: Option<String>,
+}
+
 /// Scope of a skill
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -681,6 +1469,32 @@ pub struct RemoteSkillSummary {
 
 // MARK: - MCP Types
 
+/// A stdio MCP server OrbitDock starts for one session, alongside the ones
+/// in the provider's own config. A session's server replaces a configured
+/// server with the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionMcpServer {
+    pub name: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Values are redacted when listed; see `SessionMcpServer::REDACTED`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl SessionMcpServer {
+    /// Stands in for env values in listings. Sending it back keeps the
+    /// stored value.
+    pub const REDACTED: &'static str = "<redacted>";
+}
+
 /// MCP tool definition (mirrors codex-core mcp::Tool)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -802,6 +1616,65 @@ pub struct CodexAccountStatus {
     pub login_in_progress: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub active_login_id: Option<String>,
+    /// Rate limits Codex last reported for this account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<CodexUsageSnapshot>,
+}
+
+/// Snapshot of the Claude CLI's auth state, for telling users why direct
+/// sessions can't start and walking them through a login.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClaudeAccountStatus {
+    pub logged_in: bool,
+    /// How the CLI authenticates, e.g. `claude.ai` or `api_key`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    /// Claude plan, e.g. `pro` or `max`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subscription_type: Option<String>,
+    /// The CLI binary the server runs; absent when none was found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cli_path: Option<String>,
+    /// Why direct sessions can't start right now, and what to do about it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub problem: Option<String>,
+    #[serde(default)]
+    pub login_in_progress: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_login_id: Option<String>,
+}
+
+/// Whether a provider CLI the server runs is usable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderBinaryStatus {
+    Ok,
+    /// Older than the minimum version OrbitDock parses events from.
+    Outdated,
+    NotFound,
+    /// Found, but `--version` failed or printed nothing recognisable.
+    Unknown,
+}
+
+/// A provider CLI located on the server host, with its version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderBinaryInfo {
+    pub provider: Provider,
+    pub status: ProviderBinaryStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub min_version: String,
+    /// What went wrong, or what to upgrade; absent when `status` is `ok`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Unix seconds when the binary was last probed.
+    pub checked_at: u64,
 }
 
 // MARK: - Provider Usage Types
@@ -813,6 +1686,23 @@ pub struct UsageErrorInfo {
     pub message: String,
 }
 
+/// A server setting stored in the database, as listed by `GetServerConfig`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerSetting {
+    pub key: String,
+    pub description: String,
+    /// Current value; always omitted for secrets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secret: bool,
+    /// Whether the setting has a value, from the database or the environment.
+    pub configured: bool,
+    /// Allowed values, when the setting is an enumeration.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+}
+
 /// A client device that currently claims this server as its primary control plane.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ClientPrimaryClaim {
@@ -821,7 +1711,7 @@ pub struct ClientPrimaryClaim {
 }
 
 /// Codex rate-limit window.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CodexRateLimitWindow {
     pub used_percent: f64,
     pub window_duration_mins: u32,
@@ -829,15 +1719,27 @@ pub struct CodexRateLimitWindow {
 }
 
 /// Endpoint-scoped Codex usage snapshot.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CodexUsageSnapshot {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub primary: Option<CodexRateLimitWindow>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub secondary: Option<CodexRateLimitWindow>,
+    /// ChatGPT plan the limits belong to, e.g. `plus` or `pro`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_type: Option<String>,
     pub fetched_at_unix: f64,
 }
 
+impl CodexUsageSnapshot {
+    /// Whether the limits differ from `other`, ignoring when each was read.
+    pub fn limits_changed(&self, other: &Self) -> bool {
+        self.primary != other.primary
+            || self.secondary != other.secondary
+            || self.plan_type != other.plan_type
+    }
+}
+
 /// Claude subscription usage window.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaudeUsageWindow {
@@ -901,6 +1803,172 @@ pub struct ReviewComment {
     pub updated_at: Option<String>,
 }
 
+// Session errors
+
+/// What kind of failure a session error is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The provider rejected the account's credentials.
+    Auth,
+    /// A rate limit or usage cap was hit.
+    RateLimit,
+    /// The conversation no longer fits the model's context window.
+    ContextWindow,
+    /// The provider couldn't be reached or the stream dropped.
+    Network,
+    /// The provider's API failed or rejected the request.
+    Provider,
+    /// The provider CLI or app-server process failed.
+    Process,
+    /// The sandbox blocked the operation.
+    Sandbox,
+    Unknown,
+}
+
+/// What a client can offer to recover from a session error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+    /// Send the last message again.
+    Retry,
+    /// Wait for the rate limit to reset, then retry.
+    WaitForRateLimit,
+    /// Log in to the provider again.
+    Relogin,
+    /// Compact the conversation to free context.
+    CompactContext,
+    /// Resume the session, restarting its connector.
+    ResumeSession,
+    /// Continue in a fresh session.
+    StartNewSession,
+}
+
+/// A failure reported by a session's provider or connector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionError {
+    pub category: ErrorCategory,
+    pub message: String,
+    /// The provider's own error code, e.g. Codex's `usage_limit_exceeded`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_code: Option<String>,
+    /// Whether sending the same request again may succeed.
+    #[serde(default)]
+    pub retryable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggested_action: Option<RecoveryAction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub occurred_at: Option<String>,
+}
+
+impl SessionError {
+    /// An error of `category` with that category's usual retryability and
+    /// recovery action.
+    pub fn new(category: ErrorCategory, message: impl Into<String>) -> Self {
+        let (retryable, suggested_action) = match category {
+            ErrorCategory::Auth => (false, Some(RecoveryAction::Relogin)),
+            ErrorCategory::RateLimit => (true, Some(RecoveryAction::WaitForRateLimit)),
+            ErrorCategory::ContextWindow => (false, Some(RecoveryAction::CompactContext)),
+            ErrorCategory::Network | ErrorCategory::Provider => (true, Some(RecoveryAction::Retry)),
+            ErrorCategory::Process => (true, Some(RecoveryAction::ResumeSession)),
+            ErrorCategory::Sandbox | ErrorCategory::Unknown => (false, None),
+        };
+        Self {
+            category,
+            message: message.into(),
+            provider_code: None,
+            retryable,
+            suggested_action,
+            occurred_at: None,
+        }
+    }
+
+    pub fn with_provider_code(mut self, code: impl Into<String>) -> Self {
+        self.provider_code = Some(code.into());
+        self
+    }
+
+    /// Classify a bare message by the phrases providers use for each kind
+    /// of failure. Anything unrecognised is `Unknown`.
+    pub fn from_message(message: impl Into<String>) -> Self {
+        let message = message.into();
+        let lower = message.to_ascii_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|needle| lower.contains(needle));
+        let category = if has(&[
+            "invalid api key",
+            "/login",
+            "unauthorized",
+            "authentication",
+            "oauth token",
+        ]) {
+            ErrorCategory::Auth
+        } else if has(&["rate limit", "rate_limit", "usage limit", "quota"]) {
+            ErrorCategory::RateLimit
+        } else if has(&[
+            "prompt is too long",
+            "context window",
+            "context_length",
+            "maximum context",
+        ]) {
+            ErrorCategory::ContextWindow
+        } else if has(&[
+            "connection",
+            "network",
+            "timed out",
+            "timeout",
+            "stream disconnected",
+        ]) {
+            ErrorCategory::Network
+        } else if has(&["overloaded", "api error", "internal server error"]) {
+            ErrorCategory::Provider
+        } else {
+            ErrorCategory::Unknown
+        };
+        Self::new(category, message)
+    }
+}
+
+// Attention inbox
+
+/// Why a session is in the attention inbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttentionKind {
+    Approval,
+    Question,
+    /// The last turn ended in an error and no turn has started since.
+    Error,
+    /// A turn has shown no activity for a while.
+    StalledTurn,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttentionItem {
+    /// Stable while the cause is unchanged; a new approval, error, or stall
+    /// gets a new id, so acknowledging one doesn't hide the next.
+    pub id: String,
+    pub session_id: String,
+    pub kind: AttentionKind,
+    /// Tool name, question, or error message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_name: Option<String>,
+    pub project_path: String,
+    /// When the session last changed, for ordering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+}
+
+// Editors on the server's machine
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorInfo {
+    pub name: String,
+    /// Whether its command was found on the server's `PATH`.
+    pub available: bool,
+}
+
 // Remote filesystem browsing
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,503 @@
+// OrbitDock web dashboard.
+//
+// Speaks the same WebSocket protocol as the macOS app. Everything from the
+// server is rendered with textContent, never as HTML.
+"use strict";
+
+(() => {
+  if (document.body.dataset.page === "sign-in") {
+    showSignInError();
+    return;
+  }
+
+  const TRUNCATED_MARKER = "[truncated]";
+  const LIST_REFRESH_MS = 30000;
+  const MAX_RECONNECT_MS = 30000;
+
+  const state = {
+    socket: null,
+    reconnectDelay: 1000,
+    sessions: new Map(),
+    selectedId: null,
+    session: null,
+    tab: "messages",
+    composing: false,
+  };
+
+  const $ = (id) => document.getElementById(id);
+
+  function el(tag, className, text) {
+    const node = document.createElement(tag);
+    if (className) node.className = className;
+    if (text !== undefined && text !== null) node.textContent = text;
+    return node;
+  }
+
+  // Connection
+
+  function connect() {
+    const scheme = location.protocol === "https:" ? "wss:" : "ws:";
+    const socket = new WebSocket(`${scheme}//${location.host}/ws`);
+    state.socket = socket;
+    setConnection("Connecting…", false);
+
+    socket.addEventListener("open", () => {
+      state.reconnectDelay = 1000;
+      setConnection("Live", true);
+      send({ type: "subscribe_list" });
+      if (state.selectedId) subscribe(state.selectedId);
+    });
+    socket.addEventListener("message", (event) => {
+      let message;
+      try {
+        message = JSON.parse(event.data);
+      } catch (_) {
+        return;
+      }
+      handle(message);
+    });
+    socket.addEventListener("close", () => {
+      if (state.socket !== socket) return;
+      state.composing = false;
+      setConnection("Reconnecting…", false);
+      checkSignedIn().then(() => {
+        setTimeout(connect, state.reconnectDelay);
+        state.reconnectDelay = Math.min(state.reconnectDelay * 2, MAX_RECONNECT_MS);
+      });
+    });
+  }
+
+  function send(message) {
+    if (state.socket && state.socket.readyState === WebSocket.OPEN) {
+      state.socket.send(JSON.stringify(message));
+      return true;
+    }
+    return false;
+  }
+
+  function setConnection(label, live) {
+    const node = $("connection");
+    node.textContent = label;
+    node.classList.toggle("live", live);
+  }
+
+  // A browser can't see why a WebSocket handshake failed, so ask over HTTP
+  // and go back to sign in if the token no longer works.
+  async function checkSignedIn() {
+    try {
+      const response = await fetch("/api/sessions", { credentials: "same-origin" });
+      if (response.status === 401) {
+        location.href = "/dashboard/sign-out?error=rejected";
+        return new Promise(() => {});
+      }
+      if (response.ok) mergeSessions((await response.json()).sessions || []);
+    } catch (_) {
+      // Server unreachable; the reconnect loop keeps trying.
+    }
+  }
+
+  // Server messages
+
+  function handle(message) {
+    switch (message.type) {
+      case "sessions_list":
+        state.sessions = new Map(message.sessions.map((s) => [s.id, s]));
+        renderSessions();
+        break;
+      case "session_created":
+        state.sessions.set(message.session.id, message.session);
+        renderSessions();
+        break;
+      case "session_ended": {
+        const summary = state.sessions.get(message.session_id);
+        if (summary) {
+          summary.status = "ended";
+          summary.work_status = "ended";
+          summary.has_pending_approval = false;
+          renderSessions();
+        }
+        if (isSelected(message.session_id) && state.session) {
+          state.session.status = "ended";
+          state.session.work_status = "ended";
+          state.session.pending_approval = null;
+          renderSession();
+        }
+        break;
+      }
+      case "session_snapshot":
+        if (!isSelected(message.session.id)) break;
+        state.session = message.session;
+        state.session.turn_diffs = state.session.turn_diffs || [];
+        renderSession();
+        break;
+      case "session_delta":
+        applyDelta(message.session_id, message.changes);
+        break;
+      case "message_appended":
+        if (!isSelected(message.session_id) || !state.session) break;
+        upsertMessage(message.message);
+        renderMessages();
+        break;
+      case "message_updated":
+        if (!isSelected(message.session_id) || !state.session) break;
+        updateMessage(message.message_id, message.changes);
+        renderMessages();
+        break;
+      case "approval_requested":
+        if (isSelected(message.session_id) && state.session) {
+          state.session.pending_approval = message.request;
+          renderApproval();
+        }
+        markSummary(message.session_id, { has_pending_approval: true });
+        break;
+      case "session_presence":
+        if (!isSelected(message.session_id) || !state.session) break;
+        state.session.viewers = message.viewers;
+        renderViewers();
+        break;
+      case "turn_diff_snapshot":
+        if (!isSelected(message.session_id) || !state.session) break;
+        upsertTurnDiff(message);
+        if (state.tab === "diff") renderDiff();
+        break;
+      case "error":
+        if (!message.session_id || isSelected(message.session_id)) {
+          setConnection(`Error: ${message.message}`, false);
+        }
+        break;
+      default:
+        break;
+    }
+  }
+
+  function isSelected(sessionId) {
+    return sessionId === state.selectedId;
+  }
+
+  // Nullable fields are sent as null when cleared, so check for the key.
+  function applyDelta(sessionId, changes) {
+    const summaryChanges = {};
+    for (const key of ["status", "work_status", "custom_name", "summary", "first_prompt", "last_message", "last_activity_at"]) {
+      if (key in changes) summaryChanges[key] = changes[key];
+    }
+    if ("pending_approval" in changes) {
+      summaryChanges.has_pending_approval = changes.pending_approval !== null;
+    }
+    markSummary(sessionId, summaryChanges);
+
+    if (!isSelected(sessionId) || !state.session) return;
+    Object.assign(state.session, changes);
+    renderHeader();
+    if ("pending_approval" in changes) renderApproval();
+    if ("current_diff" in changes && state.tab === "diff") renderDiff();
+  }
+
+  function markSummary(sessionId, changes) {
+    const summary = state.sessions.get(sessionId);
+    if (!summary || Object.keys(changes).length === 0) return;
+    Object.assign(summary, changes);
+    renderSessions();
+  }
+
+  function mergeSessions(sessions) {
+    for (const summary of sessions) state.sessions.set(summary.id, summary);
+    renderSessions();
+  }
+
+  function upsertMessage(message) {
+    const messages = state.session.messages;
+    const index = messages.findIndex((m) => m.id === message.id);
+    if (index >= 0) messages[index] = message;
+    else messages.push(message);
+  }
+
+  function updateMessage(messageId, changes) {
+    const message = state.session.messages.find((m) => m.id === messageId);
+    if (!message) return;
+    for (const [key, value] of Object.entries(changes)) {
+      if (value !== null && value !== undefined) message[key] = value;
+    }
+  }
+
+  function upsertTurnDiff(snapshot) {
+    const diffs = state.session.turn_diffs;
+    const entry = { turn_id: snapshot.turn_id, diff: snapshot.diff };
+    const index = diffs.findIndex((d) => d.turn_id === snapshot.turn_id);
+    if (index >= 0) diffs[index] = Object.assign(diffs[index], entry);
+    else diffs.push(entry);
+  }
+
+  // Session list
+
+  function sessionName(session) {
+    return session.custom_name || session.summary || session.first_prompt || session.project_name || session.id;
+  }
+
+  function renderSessions() {
+    const list = $("sessions");
+    const onlyWaiting = $("needs-attention").checked;
+    const sessions = [...state.sessions.values()]
+      .filter((s) => !onlyWaiting || s.has_pending_approval)
+      .sort((a, b) => {
+        const active = Number(b.status === "active") - Number(a.status === "active");
+        if (active !== 0) return active;
+        return (b.last_activity_at || "").localeCompare(a.last_activity_at || "");
+      });
+
+    list.replaceChildren(
+      ...sessions.map((session) => {
+        const item = el("li");
+        item.classList.toggle("selected", isSelected(session.id));
+        const name = el("div", "name", sessionName(session));
+        const detail = el("div", "detail");
+        detail.append(statusBadge(session), " ", session.project_name || session.project_path || "");
+        item.append(name, detail);
+        if (session.last_message) item.append(el("div", "detail", session.last_message));
+        item.addEventListener("click", () => select(session.id));
+        return item;
+      }),
+    );
+  }
+
+  function statusBadge(session) {
+    if (session.has_pending_approval) return el("span", "badge attention", "needs approval");
+    if (session.status === "ended") return el("span", "badge", "ended");
+    if (session.work_status === "working") return el("span", "badge working", "working");
+    return el("span", "badge", session.work_status || session.status);
+  }
+
+  function select(sessionId) {
+    if (state.selectedId === sessionId) return;
+    if (state.selectedId) send({ type: "unsubscribe_session", session_id: state.selectedId });
+    state.selectedId = sessionId;
+    state.session = null;
+    state.composing = false;
+    $("empty").hidden = true;
+    $("session").hidden = false;
+    $("messages").replaceChildren(el("div", "meta", "Loading…"));
+    $("diff").replaceChildren();
+    $("approval").hidden = true;
+    renderSessions();
+    subscribe(sessionId);
+  }
+
+  function subscribe(sessionId) {
+    send({ type: "subscribe_session", session_id: sessionId });
+  }
+
+  // Session view
+
+  function renderSession() {
+    renderHeader();
+    renderMessages();
+    renderApproval();
+    if (state.tab === "diff") renderDiff();
+  }
+
+  function renderHeader() {
+    const session = state.session;
+    if (!session) return;
+    $("session-title").textContent = sessionName(session);
+    const parts = [session.provider, session.model, session.project_name, session.work_status || session.status];
+    $("session-meta").textContent = parts.filter(Boolean).join(" · ");
+    const open = session.status !== "ended";
+    $("interrupt").hidden = !open || session.work_status !== "working";
+    $("composer").hidden = !open;
+    renderViewers();
+  }
+
+  // Includes this dashboard, which can't tell which viewer it is.
+  function renderViewers() {
+    const viewers = state.session.viewers || [];
+    const name = (v) => v.user || v.device_name || v.client_name || `connection ${v.connection_id}`;
+    const parts = [];
+    if (viewers.length > 1) parts.push(`Viewing: ${viewers.map(name).join(", ")}`);
+    const composing = viewers.filter((v) => v.composing).map(name);
+    if (composing.length > 0) parts.push(`Typing: ${composing.join(", ")}`);
+    $("session-viewers").textContent = parts.join(" · ");
+  }
+
+  function renderMessages() {
+    const container = $("messages");
+    const pinned = container.scrollHeight - container.scrollTop - container.clientHeight < 40;
+    container.replaceChildren(...state.session.messages.map(renderMessage));
+    if (pinned) container.scrollTop = container.scrollHeight;
+  }
+
+  function renderMessage(message) {
+    const kind = message.message_type;
+    const card = el("div", `message ${kind}`);
+    if (message.is_error) card.classList.add("error");
+
+    let role = kind.replace("_", " ");
+    if (message.tool_name) role += ` · ${message.tool_name}`;
+    if (message.is_in_progress) role += " · running";
+    card.append(el("div", "role", role));
+
+    const body = kind === "tool" && !message.content ? message.tool_input : message.content;
+    if (body) card.append(el("pre", "content", body));
+    if (message.tool_output) card.append(el("pre", "output", message.tool_output));
+
+    const truncated = [message.content, message.tool_output].some((text) => text && text.endsWith(TRUNCATED_MARKER));
+    if (truncated) {
+      const button = el("button", "show-full", "Show full message");
+      button.type = "button";
+      button.addEventListener("click", () => loadFullMessage(message.id));
+      card.append(button);
+    }
+    return card;
+  }
+
+  async function loadFullMessage(messageId) {
+    const sessionId = state.selectedId;
+    const path = `/api/sessions/${encodeURIComponent(sessionId)}/messages/${encodeURIComponent(messageId)}`;
+    const response = await fetch(path, { credentials: "same-origin" });
+    if (!response.ok || !isSelected(sessionId) || !state.session) return;
+    const payload = await response.json();
+    upsertMessage(payload.message);
+    renderMessages();
+  }
+
+  function renderDiff() {
+    const container = $("diff");
+    const session = state.session;
+    if (!session) return;
+    const sections = [];
+    if (session.current_diff) sections.push(diffSection("Current turn", session.current_diff));
+    for (const turn of [...(session.turn_diffs || [])].reverse()) {
+      sections.push(diffSection(turn.summary || `Turn ${turn.turn_id}`, turn.diff));
+    }
+    if (sections.length === 0) sections.push(el("div", "meta", "No changes yet."));
+    container.replaceChildren(...sections);
+  }
+
+  function diffSection(title, diff) {
+    const section = el("div", "turn-diff");
+    section.append(el("h2", null, title));
+    const pre = el("pre");
+    for (const line of diff.split("\n")) {
+      let kind = "";
+      if (line.startsWith("+++") || line.startsWith("---")) kind = "";
+      else if (line.startsWith("+")) kind = "added";
+      else if (line.startsWith("-")) kind = "removed";
+      else if (line.startsWith("@@")) kind = "hunk";
+      pre.append(el("div", `diff-line ${kind}`.trim(), line || " "));
+    }
+    section.append(pre);
+    return section;
+  }
+
+  function renderApproval() {
+    const box = $("approval");
+    const request = state.session && state.session.pending_approval;
+    if (!request) {
+      box.hidden = true;
+      box.replaceChildren();
+      return;
+    }
+    box.hidden = false;
+    const sessionId = state.selectedId;
+    const children = [];
+
+    if (request.type === "question") {
+      const prompts = request.question_prompts || [];
+      const question = request.question || (prompts[0] && prompts[0].question) || "The agent has a question.";
+      children.push(el("strong", null, question));
+      const actions = el("form", "actions");
+      const input = el("input");
+      input.placeholder = "Answer";
+      input.required = true;
+      const submit = el("button", "primary", "Answer");
+      submit.type = "submit";
+      actions.append(input, submit);
+      actions.addEventListener("submit", (event) => {
+        event.preventDefault();
+        send({ type: "answer_question", session_id: sessionId, request_id: request.id, answer: input.value });
+      });
+      children.push(actions);
+    } else {
+      const label = request.type === "patch" ? "Apply changes" : `Run ${request.tool_name || "tool"}`;
+      children.push(el("strong", null, label));
+      if (request.file_path) children.push(el("div", "meta", request.file_path));
+      const detail = request.command || request.diff || request.tool_input;
+      if (detail) children.push(el("pre", null, detail));
+      const actions = el("div", "actions");
+      for (const [decision, text, className] of [
+        ["approved", "Approve", "primary"],
+        ["approved_for_session", "Approve for session", null],
+        ["denied", "Deny", null],
+      ]) {
+        const button = el("button", className, text);
+        button.type = "button";
+        button.addEventListener("click", () => {
+          send({ type: "approve_tool", session_id: sessionId, request_id: request.id, decision });
+        });
+        actions.append(button);
+      }
+      children.push(actions);
+    }
+    box.replaceChildren(...children);
+  }
+
+  // Controls
+
+  $("needs-attention").addEventListener("change", renderSessions);
+
+  for (const button of document.querySelectorAll(".tabs button")) {
+    button.addEventListener("click", () => {
+      state.tab = button.dataset.tab;
+      for (const other of document.querySelectorAll(".tabs button")) {
+        other.classList.toggle("active", other === button);
+      }
+      $("messages").hidden = state.tab !== "messages";
+      $("diff").hidden = state.tab !== "diff";
+      if (state.tab === "diff") renderDiff();
+    });
+  }
+
+  $("composer").addEventListener("submit", (event) => {
+    event.preventDefault();
+    const input = $("composer-input");
+    const content = input.value.trim();
+    if (!content || !state.selectedId) return;
+    if (send({ type: "send_message", session_id: state.selectedId, content })) {
+      input.value = "";
+      state.composing = false;
+    }
+  });
+
+  // The server clears this when the message is sent or the view changes.
+  $("composer-input").addEventListener("input", (event) => {
+    const composing = event.target.value.trim() !== "";
+    if (composing === state.composing || !state.selectedId) return;
+    if (send({ type: "set_composing", session_id: state.selectedId, composing })) {
+      state.composing = composing;
+    }
+  });
+
+  $("composer-input").addEventListener("keydown", (event) => {
+    if (event.key === "Enter" && (event.metaKey || event.ctrlKey)) {
+      $("composer").requestSubmit();
+    }
+  });
+
+  $("interrupt").addEventListener("click", () => {
+    if (state.selectedId) send({ type: "interrupt_session", session_id: state.selectedId });
+  });
+
+  setInterval(checkSignedIn, LIST_REFRESH_MS);
+  connect();
+
+  function showSignInError() {
+    const error = new URLSearchParams(location.search).get("error");
+    const messages = {
+      invalid: "Enter a valid token.",
+      rejected: "That token was not accepted.",
+    };
+    const node = document.getElementById("sign-in-error");
+    if (node && messages[error]) {
+      node.textContent = messages[error];
+      node.hidden = false;
+    }
+  }
+})();
@@ -1,12 +1,22 @@
-//! AI-powered session naming via OpenAI API.
+//! AI-powered session naming and turn summaries.
 //!
-//! Generates concise 3-7 word names from first user prompts.
+//! Generates concise 3-7 word names from first user prompts, and one-line
+//! summaries of completed turns for notifications.
 //! Fire-and-forget: failures silently fall back to first_prompt display.
+//!
+//! The model behind both is a [`NamingProvider`] picked by the `[naming]`
+//! config section: OpenAI, Anthropic, an OpenAI-compatible local endpoint
+//! (Ollama, LM Studio), or `first_prompt`, which names sessions from the
+//! prompt itself without calling a model. Unset, OpenAI is used when its key
+//! is configured, then Anthropic.
 
 use std::collections::HashSet;
 use std::sync::Mutex;
 
+use futures::future::BoxFuture;
 use orbitdock_protocol::{ServerMessage, StateChanges};
+use serde::Deserialize;
+use serde_json::{json, Value};
 use tokio::sync::{broadcast, mpsc};
 use tracing::{info, warn};
 
@@ -32,17 +42,162 @@ impl NamingGuard {
     }
 }
 
+const OPENAI_MODEL: &str = "gpt-5-mini-2025-08-07";
+const ANTHROPIC_MODEL: &str = "claude-haiku-4-5";
+/// Longest first prompt sent to a model for naming, in characters.
+const MAX_NAMING_INPUT_CHARS: usize = 500;
+
+/// `[naming]` in the config file.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NamingSection {
+    pub provider: Option<ProviderKind>,
+    /// Overrides the provider's default model; required for `openai_compatible`.
+    pub model: Option<String>,
+    /// `openai_compatible` only, e.g. `http://localhost:11434/v1`.
+    pub base_url: Option<String>,
+    /// Completed turns between rolling summary refreshes; 0 turns them off.
+    pub summary_every_turns: Option<u32>,
+    /// Completed turns before a session is tagged automatically; 0 turns
+    /// automatic tags off.
+    pub tag_after_turns: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    #[serde(rename = "openai")]
+    OpenAi,
+    Anthropic,
+    #[serde(rename = "openai_compatible")]
+    OpenAiCompatible,
+    FirstPrompt,
+}
+
+impl NamingSection {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.provider == Some(ProviderKind::OpenAiCompatible) {
+            if self.base_url.is_none() {
+                problems.push("naming.base_url: required for openai_compatible".into());
+            }
+            if self.model.is_none() {
+                problems.push("naming.model: required for openai_compatible".into());
+            }
+        } else if self.base_url.is_some() {
+            problems.push("naming.base_url: only applies to openai_compatible".into());
+        }
+        problems
+    }
+}
+
+/// What a provider is asked to produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Task {
+    /// A 3-7 word name from a session's first prompt.
+    SessionName,
+    /// One sentence on what a completed turn did.
+    TurnSummary,
+    /// 2-3 sentences on a whole session, refreshed as it goes.
+    SessionSummary,
+    /// 1-3 comma-separated topic tags for a session.
+    SessionTags,
+}
+
+impl Task {
+    fn instructions(self) -> &'static str {
+        match self {
+            Task::SessionName => "You name coding sessions. Given a user's first message to an AI coding assistant, produce a concise 3-7 word name. Reply with only the name.",
+            Task::TurnSummary => "You summarize turns of an AI coding assistant for a notification. Given the user's request, the assistant's actions, and diff stats, write one plain sentence (under 20 words) saying what the assistant did. No preamble, no markdown.",
+            Task::SessionSummary => "You keep a running summary of a coding session with an AI coding assistant. Given the current summary, if any, and the latest turns, write 2-3 plain sentences on what the session is working on and where it stands now. No preamble, no markdown.",
+            Task::SessionTags => "You tag coding sessions with an AI coding assistant by topic. Given the session's first turns, reply with 1-3 short lowercase topic tags separated by commas, such as: auth, migration, tests. Prefer broad areas of the codebase or kinds of work over specifics. No other text.",
+        }
+    }
+
+    /// Schema name and string field of the structured reply.
+    fn schema(self) -> (&'static str, &'static str) {
+        match self {
+            Task::SessionName => ("session_name", "name"),
+            Task::TurnSummary => ("turn_summary", "summary"),
+            Task::SessionSummary => ("session_summary", "summary"),
+            Task::SessionTags => ("session_tags", "tags"),
+        }
+    }
+
+    fn field(self) -> &'static str {
+        self.schema().1
+    }
+}
+
+/// A backend for session names and turn summaries.
+pub trait NamingProvider: Send + Sync {
+    /// Provider name for logs.
+    fn label(&self) -> &'static str;
+
+    /// Produce `task` from `input`; `Ok(None)` if this provider can't.
+    fn generate<'a>(
+        &'a self,
+        task: Task,
+        input: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<Option<String>>>;
+}
+
 /// Resolve the OpenAI API key from env var or database.
 pub fn resolve_api_key() -> Option<String> {
+    resolve_key("OPENAI_API_KEY", "openai_api_key")
+}
+
+/// Resolve the Anthropic API key from env var or database.
+pub fn resolve_anthropic_key() -> Option<String> {
+    resolve_key("ANTHROPIC_API_KEY", "anthropic_api_key")
+}
+
+fn resolve_key(env: &str, config_key: &str) -> Option<String> {
     // Check env var first
-    if let Ok(key) = std::env::var("OPENAI_API_KEY") {
+    if let Ok(key) = std::env::var(env) {
         if !key.is_empty() {
             return Some(key);
         }
     }
 
     // Fall back to config table in SQLite
-    crate::persistence::load_config_value("openai_api_key")
+    crate::persistence::load_config_value(config_key)
+}
+
+/// The provider `[naming]` selects, or `None` when it has no credentials.
+/// Reads the database for keys, like [`resolve_api_key`].
+pub fn provider() -> Option<Box<dyn NamingProvider>> {
+    let config = crate::config_file::current();
+    let naming = &config.naming;
+    let model = |default: &str| naming.model.clone().unwrap_or_else(|| default.to_string());
+    let openai = || {
+        resolve_api_key().map(|api_key| {
+            Box::new(OpenAi {
+                api_key,
+                model: model(OPENAI_MODEL),
+            }) as Box<dyn NamingProvider>
+        })
+    };
+    let anthropic = || {
+        resolve_anthropic_key().map(|api_key| {
+            Box::new(Anthropic {
+                api_key,
+                model: model(ANTHROPIC_MODEL),
+            }) as Box<dyn NamingProvider>
+        })
+    };
+    match naming.provider {
+        Some(ProviderKind::OpenAi) => openai(),
+        Some(ProviderKind::Anthropic) => anthropic(),
+        Some(ProviderKind::OpenAiCompatible) => Some(Box::new(OpenAiCompatible {
+            base_url: naming.base_url.clone()?,
+            // Local servers usually ignore the key; hosted ones need it.
+            api_key: resolve_api_key(),
+            model: naming.model.clone()?,
+        })),
+        Some(ProviderKind::FirstPrompt) => Some(Box::new(FirstPrompt)),
+        None => openai().or_else(anthropic),
+    }
 }
 
 /// Returns true if the prompt is a bootstrap/system prompt that shouldn't be named.
@@ -70,22 +225,25 @@ pub fn spawn_naming_task(
             return;
         }
 
-        let api_key = match resolve_api_key() {
-            Some(key) => key,
-            None => {
-                warn!(
-                    session_id = %session_id,
-                    "No OpenAI API key found for AI naming (set OPENAI_API_KEY or add to Keychain)"
-                );
-                return;
-            }
+        let Some(provider) = provider() else {
+            warn!(
+                session_id = %session_id,
+                "No AI naming provider configured (set OPENAI_API_KEY, ANTHROPIC_API_KEY, or [naming] in config.toml)"
+            );
+            return;
         };
 
-        match generate_name(&api_key, &first_prompt).await {
-            Ok(name) => {
+        let input = match first_prompt.char_indices().nth(MAX_NAMING_INPUT_CHARS) {
+            Some((end, _)) => &first_prompt[..end],
+            None => first_prompt.as_str(),
+        };
+        match provider.generate(Task::SessionName, input).await {
+            Ok(None) => {}
+            Ok(Some(name)) => {
                 info!(
                     session_id = %session_id,
                     name = %name,
+                    provider = provider.label(),
                     "AI-generated session name"
                 );
 
@@ -121,6 +279,7 @@ pub fn spawn_naming_task(
             Err(e) => {
                 warn!(
                     session_id = %session_id,
+                    provider = provider.label(),
                     error = %e,
                     "Failed to generate AI session name"
                 );
@@ -129,113 +288,274 @@ pub fn spawn_naming_task(
     });
 }
 
-/// Call OpenAI API to generate a session name.
-async fn generate_name(api_key: &str, prompt: &str) -> Result<String, anyhow::Error> {
-    let truncated = if prompt.len() > 500 {
-        &prompt[..500]
-    } else {
-        prompt
-    };
+/// OpenAI Responses API with structured output.
+struct OpenAi {
+    api_key: String,
+    model: String,
+}
 
-    let body = serde_json::json!({
-        "model": "gpt-5-mini-2025-08-07",
-        "max_output_tokens": 4096,
-        "instructions": "You name coding sessions. Given a user's first message to an AI coding assistant, produce a concise 3-7 word name.",
-        "input": truncated,
-        "text": {
-            "format": {
-                "type": "json_schema",
-                "name": "session_name",
-                "strict": true,
-                "schema": {
-                    "type": "object",
-                    "properties": {
-                        "name": { "type": "string" }
-                    },
-                    "required": ["name"],
-                    "additionalProperties": false
+impl NamingProvider for OpenAi {
+    fn label(&self) -> &'static str {
+        "openai"
+    }
+
+    fn generate<'a>(
+        &'a self,
+        task: Task,
+        input: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<Option<String>>> {
+        Box::pin(async move {
+            let (schema_name, field) = task.schema();
+            let body = json!({
+                "model": self.model,
+                "max_output_tokens": 4096,
+                "instructions": task.instructions(),
+                "input": input,
+                "text": {
+                    "format": {
+                        "type": "json_schema",
+                        "name": schema_name,
+                        "strict": true,
+                        "schema": {
+                            "type": "object",
+                            "properties": {
+                                field: { "type": "string" }
+                            },
+                            "required": [field],
+                            "additionalProperties": false
+                        }
+                    }
                 }
-            }
-        }
-    });
+            });
+            let request = reqwest::Client::new()
+                .post("https://api.openai.com/v1/responses")
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .json(&body);
+            let json = post_json(request, "OpenAI").await?;
+
+            // With structured output, output_text is the JSON string `{"<field>": "..."}`
+            let text = json["output_text"].as_str().or_else(|| {
+                // Fallback: walk the output array for message content
+                json["output"]
+                    .as_array()?
+                    .iter()
+                    .filter(|item| item["type"].as_str() == Some("message"))
+                    .find_map(|item| {
+                        item["content"]
+                            .as_array()?
+                            .iter()
+                            .find(|c| c["type"].as_str() == Some("output_text"))?["text"]
+                            .as_str()
+                    })
+            });
+            reply_field(text, field, &json).map(Some)
+        })
+    }
+}
+
+/// Anthropic Messages API.
+struct Anthropic {
+    api_key: String,
+    model: String,
+}
+
+impl NamingProvider for Anthropic {
+    fn label(&self) -> &'static str {
+        "anthropic"
+    }
+
+    fn generate<'a>(
+        &'a self,
+        task: Task,
+        input: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<Option<String>>> {
+        Box::pin(async move {
+            let body = json!({
+                "model": self.model,
+                "max_tokens": 256,
+                "system": task.instructions(),
+                "messages": [{ "role": "user", "content": input }],
+            });
+            let request = reqwest::Client::new()
+                .post("https://api.anthropic.com/v1/messages")
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", "2023-06-01")
+                .json(&body);
+            let json = post_json(request, "Anthropic").await?;
+
+            let text = json["content"].as_array().and_then(|content| {
+                content
+                    .iter()
+                    .find(|block| block["type"].as_str() == Some("text"))?["text"]
+                    .as_str()
+            });
+            reply_field(text, task.field(), &json).map(Some)
+        })
+    }
+}
+
+/// `/chat/completions` on an OpenAI-compatible server such as Ollama or LM
+/// Studio. Replies are plain text; local models follow JSON schemas unevenly.
+struct OpenAiCompatible {
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+}
 
-    let client = reqwest::Client::new();
-
-    // First attempt
-    let result = call_openai(&client, api_key, &body).await;
-    match result {
-        Ok(name) => Ok(name),
-        Err(e) => {
-            // Retry once on 429 (rate limit)
-            if e.to_string().contains("429") {
-                tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-                call_openai(&client, api_key, &body).await
-            } else {
-                Err(e)
+impl NamingProvider for OpenAiCompatible {
+    fn label(&self) -> &'static str {
+        "openai_compatible"
+    }
+
+    fn generate<'a>(
+        &'a self,
+        task: Task,
+        input: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<Option<String>>> {
+        Box::pin(async move {
+            let body = json!({
+                "model": self.model,
+                "messages": [
+                    { "role": "system", "content": task.instructions() },
+                    { "role": "user", "content": input },
+                ],
+            });
+            let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
+            let mut request = reqwest::Client::new().post(url).json(&body);
+            if let Some(api_key) = &self.api_key {
+                request = request.header("Authorization", format!("Bearer {api_key}"));
             }
-        }
+            let json = post_json(request, "OpenAI-compatible").await?;
+
+            let text = json["choices"][0]["message"]["content"].as_str();
+            reply_field(text, task.field(), &json).map(Some)
+        })
     }
 }
 
-async fn call_openai(
-    client: &reqwest::Client,
-    api_key: &str,
-    body: &serde_json::Value,
-) -> Result<String, anyhow::Error> {
-    let resp = client
-        .post("https://api.openai.com/v1/responses")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .json(body)
-        .send()
-        .await?;
+/// Names sessions from the first prompt alone; never summarizes.
+struct FirstPrompt;
+
+impl NamingProvider for FirstPrompt {
+    fn label(&self) -> &'static str {
+        "first_prompt"
+    }
+
+    fn generate<'a>(
+        &'a self,
+        task: Task,
+        input: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<Option<String>>> {
+        Box::pin(async move {
+            Ok(match task {
+                Task::SessionName => crate::session_naming::name_from_first_prompt(input),
+                Task::TurnSummary | Task::SessionSummary | Task::SessionTags => None,
+            })
+        })
+    }
+}
+
+/// Send a request and return its JSON reply, retrying once on a rate limit.
+pub(crate) async fn post_json(
+    request: reqwest::RequestBuilder,
+    api: &str,
+) -> anyhow::Result<Value> {
+    let retry = request.try_clone();
+    let mut resp = request.send().await?;
+    if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        if let Some(retry) = retry {
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            resp = retry.send().await?;
+        }
+    }
 
     let status = resp.status();
     if !status.is_success() {
         let text = resp.text().await.unwrap_or_default();
-        anyhow::bail!("OpenAI API error {}: {}", status, text);
+        anyhow::bail!("{api} API error {}: {}", status, text);
     }
 
-    let json: serde_json::Value = resp.json().await?;
+    Ok(resp.json().await?)
+}
 
-    // With structured output, output_text is the JSON string `{"name": "..."}`
-    let name = json["output_text"]
-        .as_str()
-        .and_then(|text| {
-            let parsed: serde_json::Value = serde_json::from_str(text).ok()?;
-            parsed["name"].as_str().map(|s| s.to_string())
-        })
-        // Fallback: walk the output array for message content
-        .or_else(|| {
-            json["output"]
-                .as_array()?
-                .iter()
-                .filter(|item| item["type"].as_str() == Some("message"))
-                .find_map(|item| {
-                    item["content"].as_array()?.iter().find_map(|c| {
-                        if c["type"].as_str() == Some("output_text") {
-                            let text = c["text"].as_str()?;
-                            // Try parsing as structured JSON first
-                            if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(text) {
-                                parsed["name"].as_str().map(|s| s.to_string())
-                            } else {
-                                Some(text.to_string())
-                            }
-                        } else {
-                            None
-                        }
-                    })
-                })
+/// The reply's `field` when it is structured JSON, else the reply itself.
+fn reply_field(text: Option<&str>, field: &str, response: &Value) -> anyhow::Result<String> {
+    let value = text
+        .map(|text| match serde_json::from_str::<Value>(text) {
+            Ok(parsed) if parsed[field].is_string() => {
+                parsed[field].as_str().unwrap_or_default().to_string()
+            }
+            _ => text.to_string(),
         })
-        .map(|s| s.trim().trim_matches('"').to_string())
+        .map(|s| s.trim().trim_matches('"').trim().to_string())
         .unwrap_or_default();
 
-    if name.is_empty() {
+    if value.is_empty() {
         warn!(
-            response = %json,
-            "OpenAI API returned empty name — check response format"
+            response = %response,
+            field,
+            "AI provider returned empty field — check response format"
         );
-        anyhow::bail!("Empty name from OpenAI API");
+        anyhow::bail!("Empty {field} from AI provider");
     }
 
-    Ok(name)
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reply_field_accepts_structured_and_plain_replies() {
+        let response = json!({});
+        assert_eq!(
+            reply_field(Some(r#"{"name": "Fix login redirect"}"#), "name", &response).unwrap(),
+            "Fix login redirect"
+        );
+        assert_eq!(
+            reply_field(Some(" \"Refactor parser\"\n"), "name", &response).unwrap(),
+            "Refactor parser"
+        );
+        assert!(reply_field(Some("  "), "name", &response).is_err());
+        assert!(reply_field(None, "name", &response).is_err());
+    }
+
+    #[tokio::test]
+    async fn first_prompt_provider_names_without_a_model() {
+        let name = FirstPrompt
+            .generate(Task::SessionName, "Fix the flaky login test")
+            .await
+            .unwrap();
+        assert_eq!(
+            name,
+            crate::session_naming::name_from_first_prompt("Fix the flaky login test")
+        );
+        assert!(name.is_some());
+        assert_eq!(
+            FirstPrompt
+                .generate(Task::TurnSummary, "User: hi")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn openai_compatible_needs_an_endpoint_and_model() {
+        let naming = NamingSection {
+            provider: Some(ProviderKind::OpenAiCompatible),
+            ..Default::default()
+        };
+        assert_eq!(naming.problems().len(), 2);
+        let naming = NamingSection {
+            provider: Some(ProviderKind::Anthropic),
+            base_url: Some("http://localhost:11434/v1".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            naming.problems(),
+            vec!["naming.base_url: only applies to openai_compatible"]
+        );
+    }
 }
@@ -0,0 +1,398 @@
+//! Files attached to messages: text files, logs, PDFs, and voice recordings.
+//!
+//! `POST /api/sessions/{session_id}/attachments?name=<file name>` stores the
+//! request body as `<data dir>/attachments/<session>/<id>/content`, next to an
+//! `attachment.json` describing it, and returns the id. `send_message` and
+//! `steer_turn` reference uploads by id in `attachment_ids`; the server records
+//! which message carried each one and hands the files to the connector.
+//!
+//! Audio is never handed over: it is transcribed into the prompt text (see
+//! `transcription`), so voice prompts work with every provider. Only Claude
+//! takes other files (as document blocks); Codex sessions refuse them rather
+//! than silently dropping them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    body::Bytes,
+    extract::{Path as UrlPath, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use orbitdock_connector_claude::FileAttachment;
+use orbitdock_protocol::Attachment;
+
+use crate::paths;
+use crate::session_utils::iso_timestamp;
+
+/// Largest attachment accepted, in bytes.
+pub const MAX_BYTES: usize = 10 * 1024 * 1024;
+const METADATA_FILE: &str = "attachment.json";
+const CONTENT_FILE: &str = "content";
+const PDF_MAGIC: &[u8] = b"%PDF-";
+/// ISO media brands that are still images (HEIC, AVIF), not recordings.
+const IMAGE_BRANDS: &[&[u8]] = &[b"heic", b"heix", b"mif1", b"msf1", b"avif", b"avis"];
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum AttachmentError {
+    #[error("attachment name is required")]
+    MissingName,
+    #[error("attachment is empty")]
+    Empty,
+    #[error("attachment is larger than {} MB", MAX_BYTES / (1024 * 1024))]
+    TooLarge,
+    #[error("only text files, PDFs, and audio can be attached")]
+    Unsupported,
+    #[error("attachment {0} not found")]
+    NotFound(String),
+    #[error("{0}")]
+    Io(String),
+}
+
+impl AttachmentError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AttachmentError::MissingName => "invalid_attachment_name",
+            AttachmentError::Empty => "empty_attachment",
+            AttachmentError::TooLarge => "attachment_too_large",
+            AttachmentError::Unsupported => "unsupported_attachment",
+            AttachmentError::NotFound(_) => "attachment_not_found",
+            AttachmentError::Io(_) => "attachment_io_error",
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            AttachmentError::MissingName | AttachmentError::Empty => StatusCode::BAD_REQUEST,
+            AttachmentError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            AttachmentError::Unsupported => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AttachmentError::NotFound(_) => StatusCode::NOT_FOUND,
+            AttachmentError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for AttachmentError {
+    fn into_response(self) -> Response {
+        let body = AttachmentErrorResponse {
+            code: self.code(),
+            error: self.to_string(),
+        };
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AttachmentErrorResponse {
+    code: &'static str,
+    error: String,
+}
+
+/// What an upload is stored as, from its content rather than its name.
+fn sniff_mime_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(PDF_MAGIC) {
+        return Some("application/pdf");
+    }
+    if let Some(audio) = sniff_audio(bytes) {
+        return Some(audio);
+    }
+    let text = std::str::from_utf8(bytes).ok()?;
+    (!text.contains('\0')).then_some("text/plain")
+}
+
+/// Container formats voice memo and recorder apps produce.
+fn sniff_audio(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WAVE") {
+        Some("audio/wav")
+    } else if bytes.get(4..8) == Some(b"ftyp")
+        && !bytes
+            .get(8..12)
+            .is_some_and(|brand| IMAGE_BRANDS.contains(&brand))
+    {
+        // m4a voice memos; MP4 video carries an audio track too.
+        Some("audio/mp4")
+    } else if bytes.starts_with(b"OggS") {
+        Some("audio/ogg")
+    } else if bytes.starts_with(b"fLaC") {
+        Some("audio/flac")
+    } else if bytes.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        // Browser MediaRecorder output.
+        Some("audio/webm")
+    } else if bytes.starts_with(b"ID3")
+        || (bytes.len() > 1 && bytes[0] == 0xFF && bytes[1] & 0xE0 == 0xE0)
+    {
+        Some("audio/mpeg")
+    } else {
+        None
+    }
+}
+
+pub fn is_audio(attachment: &Attachment) -> bool {
+    attachment.mime_type.starts_with("audio/")
+}
+
+/// Keep a file name readable but free of paths and control characters.
+fn safe_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default().trim();
+    let cleaned: String = base
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let cleaned = cleaned.trim_start_matches('.');
+    if cleaned.is_empty() {
+        "attachment".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+fn session_dir(session_id: &str) -> PathBuf {
+    let safe: String = session_id
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    paths::attachments_dir().join(safe)
+}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+}
+
+fn read_metadata(dir: &Path) -> Option<Attachment> {
+    let raw = fs::read_to_string(dir.join(METADATA_FILE)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn write_metadata(dir: &Path, attachment: &Attachment) -> Result<(), AttachmentError> {
+    let raw =
+        serde_json::to_vec_pretty(attachment).map_err(|e| AttachmentError::Io(e.to_string()))?;
+    fs::write(dir.join(METADATA_FILE), raw).map_err(|e| AttachmentError::Io(e.to_string()))
+}
+
+/// Store an upload for `session_id`.
+pub fn store(session_id: &str, name: &str, bytes: &[u8]) -> Result<Attachment, AttachmentError> {
+    if name.trim().is_empty() {
+        return Err(AttachmentError::MissingName);
+    }
+    if bytes.is_empty() {
+        return Err(AttachmentError::Empty);
+    }
+    if bytes.len() > MAX_BYTES {
+        return Err(AttachmentError::TooLarge);
+    }
+    let mime_type = sniff_mime_type(bytes).ok_or(AttachmentError::Unsupported)?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let dir = session_dir(session_id).join(&id);
+    fs::create_dir_all(&dir).map_err(|e| AttachmentError::Io(format!("create dir: {e}")))?;
+    fs::write(dir.join(CONTENT_FILE), bytes)
+        .map_err(|e| AttachmentError::Io(format!("write file: {e}")))?;
+
+    let attachment = Attachment {
+        id,
+        session_id: session_id.to_string(),
+        name: safe_file_name(name),
+        mime_type: mime_type.to_string(),
+        size_bytes: bytes.len() as u64,
+        created_at: iso_timestamp(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis(),
+        ),
+        message_id: None,
+        transcript: None,
+    };
+    write_metadata(&dir, &attachment)?;
+    Ok(attachment)
+}
+
+/// An attachment and the path of its file.
+pub fn load(session_id: &str, id: &str) -> Result<(Attachment, PathBuf), AttachmentError> {
+    let not_found = || AttachmentError::NotFound(id.to_string());
+    if !is_valid_id(id) {
+        return Err(not_found());
+    }
+    let dir = session_dir(session_id).join(id);
+    let attachment = read_metadata(&dir).ok_or_else(not_found)?;
+    let path = dir.join(CONTENT_FILE);
+    if !path.is_file() {
+        return Err(not_found());
+    }
+    Ok((attachment, path))
+}
+
+/// Every attachment uploaded to `session_id`, oldest first.
+pub fn list(session_id: &str) -> Vec<Attachment> {
+    let Ok(entries) = fs::read_dir(session_dir(session_id)) else {
+        return Vec::new();
+    };
+    let mut attachments: Vec<Attachment> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| read_metadata(&entry.path()))
+        .collect();
+    attachments.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+    attachments
+}
+
+/// Load every attachment in `ids`, failing if any is unknown.
+pub fn load_all(
+    session_id: &str,
+    ids: &[String],
+) -> Result<Vec<(Attachment, PathBuf)>, AttachmentError> {
+    ids.iter().map(|id| load(session_id, id)).collect()
+}
+
+/// Record that `loaded` went out with `message_id` (along with any transcript
+/// set on them) and return the files to hand to the connector: everything but
+/// audio, which reaches it as prompt text.
+pub fn mark_sent(
+    session_id: &str,
+    loaded: Vec<(Attachment, PathBuf)>,
+    message_id: &str,
+) -> Vec<FileAttachment> {
+    let mut files = Vec::with_capacity(loaded.len());
+    for (mut attachment, path) in loaded {
+        attachment.message_id = Some(message_id.to_string());
+        if let Some(dir) = path.parent() {
+            if let Err(e) = write_metadata(dir, &attachment) {
+                warn!(
+                    event = "attachment.mark_sent_failed",
+                    session_id = session_id,
+                    attachment_id = %attachment.id,
+                    error = %e,
+                    "Failed to record the message an attachment was sent with"
+                );
+            }
+        }
+        if is_audio(&attachment) {
+            continue;
+        }
+        files.push(FileAttachment {
+            name: attachment.name,
+            mime_type: attachment.mime_type,
+            path,
+        });
+    }
+    files
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttachmentsResponse {
+    pub session_id: String,
+    pub attachments: Vec<Attachment>,
+}
+
+/// `POST /api/sessions/{session_id}/attachments?name=<file name>`
+pub async fn upload_handler(
+    UrlPath(session_id): UrlPath<String>,
+    Query(query): Query<UploadQuery>,
+    body: Bytes,
+) -> Result<Json<Attachment>, AttachmentError> {
+    tokio::task::spawn_blocking(move || store(&session_id, &query.name, &body))
+        .await
+        .map_err(|e| AttachmentError::Io(e.to_string()))?
+        .map(Json)
+}
+
+/// `GET /api/sessions/{session_id}/attachments`
+pub async fn list_handler(UrlPath(session_id): UrlPath<String>) -> Json<AttachmentsResponse> {
+    let lookup = session_id.clone();
+    let attachments = tokio::task::spawn_blocking(move || list(&lookup))
+        .await
+        .unwrap_or_default();
+    Json(AttachmentsResponse {
+        session_id,
+        attachments,
+    })
+}
+
+/// `GET /api/sessions/{session_id}/attachments/{attachment_id}`: the file itself.
+pub async fn download_handler(
+    UrlPath((session_id, attachment_id)): UrlPath<(String, String)>,
+) -> Result<Response, AttachmentError> {
+    let (attachment, path) = load(&session_id, &attachment_id)?;
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| AttachmentError::Io(e.to_string()))?;
+    let content_type = if attachment.mime_type == "text/plain" {
+        "text/plain; charset=utf-8".to_string()
+    } else {
+        attachment.mime_type.clone()
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"{}\"",
+                    attachment.name.replace('"', "_")
+                ),
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uploads_are_sniffed_not_trusted_by_name() {
+        assert_eq!(sniff_mime_type(b"%PDF-1.7\n..."), Some("application/pdf"));
+        assert_eq!(
+            sniff_mime_type(b"error: build failed\n"),
+            Some("text/plain")
+        );
+        assert_eq!(sniff_mime_type(&[0x89, b'P', b'N', b'G', 0, 1]), None);
+        assert_eq!(
+            sniff_mime_type(b"RIFF\x24\0\0\0WAVEfmt "),
+            Some("audio/wav")
+        );
+        assert_eq!(
+            sniff_mime_type(b"\0\0\0\x1cftypM4A \0\0\0\0"),
+            Some("audio/mp4")
+        );
+        assert_eq!(sniff_mime_type(b"\0\0\0\x18ftypheic\0\0\0\0"), None);
+        assert_eq!(sniff_mime_type(b"ID3\x04\0\0"), Some("audio/mpeg"));
+        assert_eq!(
+            sniff_mime_type(&[0x1A, 0x45, 0xDF, 0xA3, 0x9F]),
+            Some("audio/webm")
+        );
+        assert_eq!(sniff_mime_type(b"text\0with nul"), None);
+
+        assert_eq!(safe_file_name("../../etc/passwd"), "passwd");
+        assert_eq!(safe_file_name("C:\\logs\\build.log"), "build.log");
+        assert_eq!(safe_file_name(".env"), "env");
+        assert_eq!(safe_file_name("a$b.txt"), "a_b.txt");
+        assert_eq!(safe_file_name("/"), "attachment");
+    }
+}
@@ -0,0 +1,295 @@
+//! The "needs attention" inbox.
+//!
+//! Every few seconds the server derives attention items from the live session
+//! snapshots: a pending approval or question, an error the session hasn't
+//! started a turn since, or a turn with no activity for `STALL_AFTER`. When
+//! the set changes it is broadcast as `attention_changed` on the list channel,
+//! so clients no longer scan every session summary. Acknowledging an item
+//! hides it until its session needs attention for a new reason (item ids
+//! change with the cause); snoozing hides it for a while. Both are in memory
+//! and shared by every client; each client only sees items for sessions it
+//! can access.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use orbitdock_protocol::{AttentionItem, AttentionKind, ServerMessage, SessionStatus, WorkStatus};
+
+use crate::session::SessionSnapshot;
+use crate::state::SessionRegistry;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a running turn may go without any session change before it is
+/// reported as stalled.
+const STALL_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// The inbox, held by the registry.
+#[derive(Default)]
+pub struct AttentionInbox {
+    inner: Mutex<Inbox>,
+}
+
+impl AttentionInbox {
+    fn lock(&self) -> MutexGuard<'_, Inbox> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Default)]
+struct Inbox {
+    /// Visible items, most recent first.
+    items: Vec<AttentionItem>,
+    /// Session of every current item, including hidden ones, so acks and
+    /// snoozes can be checked against it.
+    all_ids: HashMap<String, String>,
+    acknowledged: Vec<String>,
+    snoozed: HashMap<String, Instant>,
+    /// Each working session's revision and when it was first seen at it.
+    activity: HashMap<String, (u64, Instant)>,
+}
+
+impl Inbox {
+    /// Replace the derived items, dropping acks and snoozes for causes that
+    /// are gone. Returns whether the visible set changed.
+    fn update(&mut self, mut derived: Vec<AttentionItem>, now: Instant) -> bool {
+        self.all_ids = derived
+            .iter()
+            .map(|item| (item.id.clone(), item.session_id.clone()))
+            .collect();
+        self.acknowledged.retain(|id| self.all_ids.contains_key(id));
+        self.snoozed
+            .retain(|id, until| self.all_ids.contains_key(id) && *until > now);
+        derived.retain(|item| {
+            !self.acknowledged.contains(&item.id) && !self.snoozed.contains_key(&item.id)
+        });
+        derived.sort_by(|a, b| b.since.cmp(&a.since));
+        if derived == self.items {
+            return false;
+        }
+        self.items = derived;
+        true
+    }
+
+    /// Whether the session's running turn has gone `STALL_AFTER` without its
+    /// revision moving.
+    fn stalled(&mut self, session_id: &str, revision: u64, now: Instant) -> bool {
+        let (seen, since) = self
+            .activity
+            .entry(session_id.to_string())
+            .or_insert((revision, now));
+        if *seen != revision {
+            *seen = revision;
+            *since = now;
+        }
+        now.duration_since(*since) >= STALL_AFTER
+    }
+}
+
+pub async fn start_attention_loop(state: Arc<SessionRegistry>) {
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        refresh(&state);
+    }
+}
+
+/// Re-derive the inbox and broadcast it if it changed.
+pub fn refresh(state: &SessionRegistry) {
+    let snapshots: Vec<Arc<SessionSnapshot>> = state
+        .iter_sessions()
+        .map(|entry| entry.value().snapshot())
+        .collect();
+    let now = Instant::now();
+    let changed = {
+        let mut inbox = state.attention().lock();
+        inbox
+            .activity
+            .retain(|id, _| snapshots.iter().any(|snap| snap.id == *id));
+        let mut derived = Vec::new();
+        for snap in &snapshots {
+            let stalled = snap.status == SessionStatus::Active
+                && snap.work_status == WorkStatus::Working
+                && inbox.stalled(&snap.id, snap.revision, now);
+            if snap.work_status != WorkStatus::Working {
+                inbox.activity.remove(&snap.id);
+            }
+            derived.extend(items_for(snap, stalled));
+        }
+        inbox.update(derived, now).then(|| inbox.items.clone())
+    };
+    if let Some(items) = changed {
+        state.broadcast_to_list(ServerMessage::AttentionChanged { items });
+    }
+}
+
+/// The visible inbox, most recent first.
+pub fn items(state: &SessionRegistry) -> Vec<AttentionItem> {
+    state.attention().lock().items.clone()
+}
+
+/// The session `item_id` belongs to, if it is a current item.
+pub fn item_session(state: &SessionRegistry, item_id: &str) -> Option<String> {
+    state.attention().lock().all_ids.get(item_id).cloned()
+}
+
+/// Hide `item_id` until its cause changes. False if there is no such item.
+pub fn acknowledge(state: &SessionRegistry, item_id: &str) -> bool {
+    {
+        let mut inbox = state.attention().lock();
+        if !inbox.all_ids.contains_key(item_id) {
+            return false;
+        }
+        if !inbox.acknowledged.iter().any(|id| id == item_id) {
+            inbox.acknowledged.push(item_id.to_string());
+        }
+    }
+    refresh(state);
+    true
+}
+
+/// Hide `item_id` for `minutes`. False if there is no such item.
+pub fn snooze(state: &SessionRegistry, item_id: &str, minutes: u32) -> bool {
+    {
+        let mut inbox = state.attention().lock();
+        if !inbox.all_ids.contains_key(item_id) {
+            return false;
+        }
+        let until = Instant::now() + Duration::from_secs(u64::from(minutes) * 60);
+        inbox.snoozed.insert(item_id.to_string(), until);
+    }
+    refresh(state);
+    true
+}
+
+/// What in `snap` needs attention. `stalled` is whether its running turn
+/// has gone quiet.
+fn items_for(snap: &SessionSnapshot, stalled: bool) -> Vec<AttentionItem> {
+    if snap.status != SessionStatus::Active {
+        return Vec::new();
+    }
+    let item = |kind: AttentionKind, marker: &str, detail: Option<String>, since: Option<&str>| {
+        AttentionItem {
+            id: format!("{}:{}:{marker}", snap.id, kind_name(kind)),
+            session_id: snap.id.clone(),
+            kind,
+            detail,
+            session_name: snap.custom_name.clone().or_else(|| snap.summary.clone()),
+            project_path: snap.project_path.clone(),
+            since: since
+                .map(str::to_string)
+                .or_else(|| snap.last_activity_at.clone()),
+        }
+    };
+    let pending_marker = snap
+        .pending_approval_id
+        .clone()
+        .unwrap_or_else(|| snap.approval_version.to_string());
+
+    let mut items = Vec::new();
+    match snap.work_status {
+        WorkStatus::Permission => items.push(item(
+            AttentionKind::Approval,
+            &pending_marker,
+            snap.pending_tool_name.clone(),
+            None,
+        )),
+        WorkStatus::Question => items.push(item(
+            AttentionKind::Question,
+            &pending_marker,
+            snap.pending_question.clone(),
+            None,
+        )),
+        WorkStatus::Working if stalled => items.push(item(
+            AttentionKind::StalledTurn,
+            &snap.revision.to_string(),
+            None,
+            None,
+        )),
+        _ => {}
+    }
+    if let Some(error) = &snap.last_error {
+        let at = error.occurred_at.as_deref();
+        items.push(item(
+            AttentionKind::Error,
+            at.unwrap_or_default(),
+            Some(error.message.clone()),
+            at,
+        ));
+    }
+    items
+}
+
+fn kind_name(kind: AttentionKind) -> &'static str {
+    match kind {
+        AttentionKind::Approval => "approval",
+        AttentionKind::Question => "question",
+        AttentionKind::Error => "error",
+        AttentionKind::StalledTurn => "stalled_turn",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, since: &str) -> AttentionItem {
+        AttentionItem {
+            id: id.to_string(),
+            session_id: "s".to_string(),
+            kind: AttentionKind::Approval,
+            detail: None,
+            session_name: None,
+            project_path: "/repo".to_string(),
+            since: Some(since.to_string()),
+        }
+    }
+
+    #[test]
+    fn update_reports_changes_and_orders_newest_first() {
+        let mut inbox = Inbox::default();
+        let now = Instant::now();
+        assert!(inbox.update(vec![item("a", "1"), item("b", "2")], now));
+        assert_eq!(inbox.items[0].id, "b");
+        assert!(!inbox.update(vec![item("a", "1"), item("b", "2")], now));
+    }
+
+    #[test]
+    fn acknowledged_items_stay_hidden_until_their_cause_goes() {
+        let mut inbox = Inbox::default();
+        let now = Instant::now();
+        inbox.update(vec![item("a", "1")], now);
+        inbox.acknowledged.push("a".to_string());
+        assert!(inbox.update(vec![item("a", "1")], now));
+        assert!(inbox.items.is_empty());
+
+        inbox.update(Vec::new(), now);
+        assert!(inbox.acknowledged.is_empty());
+        inbox.update(vec![item("a", "1")], now);
+        assert_eq!(inbox.items.len(), 1);
+    }
+
+    #[test]
+    fn snoozed_items_return_when_the_snooze_ends() {
+        let mut inbox = Inbox::default();
+        let now = Instant::now();
+        inbox
+            .snoozed
+            .insert("a".to_string(), now + Duration::from_secs(60));
+        inbox.update(vec![item("a", "1")], now);
+        assert!(inbox.items.is_empty());
+
+        inbox.update(vec![item("a", "1")], now + Duration::from_secs(61));
+        assert_eq!(inbox.items.len(), 1);
+    }
+
+    #[test]
+    fn turns_stall_only_after_the_revision_stops_moving() {
+        let mut inbox = Inbox::default();
+        let start = Instant::now();
+        assert!(!inbox.stalled("s", 1, start));
+        assert!(!inbox.stalled("s", 2, start + STALL_AFTER));
+        assert!(!inbox.stalled("s", 2, start + STALL_AFTER + Duration::from_secs(1)));
+        assert!(inbox.stalled("s", 2, start + STALL_AFTER * 2));
+    }
+}
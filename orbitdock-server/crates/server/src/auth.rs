@@ -2,19 +2,83 @@
 //!
 //! All authenticated requests must include `Authorization: Bearer <token>`.
 //! The `/health` endpoint remains unauthenticated for simple liveness probes.
+//!
+//! Browsers can't add that header to page loads or WebSocket handshakes, so
+//! the web dashboard keeps its token in a cookie instead (see `dashboard`).
+//! The cookie is only honored on same-origin requests.
+//!
+//! Tokens are checked when a request arrives, so a WebSocket would outlive a
+//! revoked token. `start_token_sweep_loop` closes those connections.
 
 use axum::{
     body::Body,
     extract::State,
-    http::{Request, StatusCode},
+    http::{header, Method, Request, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Redirect, Response},
 };
-use tracing::warn;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{info, warn};
 
 use crate::auth_tokens;
+use crate::dashboard;
+use crate::scopes::{self, TokenScope};
+use crate::state::SessionRegistry;
 
 const MAX_BEARER_TOKEN_LEN: usize = 1024;
+const TOKEN_SWEEP_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Who authenticated a request. Inserted as a request extension by
+/// [`auth_middleware`] so handlers can attribute actions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuthIdentity {
+    /// Auth is disabled (loopback or explicitly insecure).
+    Anonymous,
+    /// The `--auth-token` / `ORBITDOCK_AUTH_TOKEN` shared secret.
+    StaticToken,
+    /// A database-issued token, by id, with the user it was issued to and
+    /// its scope.
+    Token {
+        id: String,
+        user: Option<String>,
+        scope: TokenScope,
+    },
+}
+
+impl AuthIdentity {
+    pub fn label(&self) -> String {
+        match self {
+            AuthIdentity::Anonymous => "anonymous".to_string(),
+            AuthIdentity::StaticToken => "static_token".to_string(),
+            AuthIdentity::Token { id, .. } => format!("token:{id}"),
+        }
+    }
+
+    /// The named user behind this identity, if its token belongs to one.
+    pub fn user(&self) -> Option<&str> {
+        match self {
+            AuthIdentity::Token { user, .. } => user.as_deref(),
+            AuthIdentity::Anonymous | AuthIdentity::StaticToken => None,
+        }
+    }
+
+    /// What this identity may do. Only database tokens can be narrowed; open
+    /// servers and the static token carry full access.
+    pub fn scope(&self) -> TokenScope {
+        match self {
+            AuthIdentity::Token { scope, .. } => *scope,
+            AuthIdentity::Anonymous | AuthIdentity::StaticToken => TokenScope::Admin,
+        }
+    }
+
+    /// Whether this identity may change server-wide settings.
+    pub fn is_admin(&self) -> bool {
+        self.scope() == TokenScope::Admin
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct AuthState {
@@ -43,36 +107,73 @@ impl AuthState {
 }
 
 /// Axum middleware that checks for a valid auth token.
-/// Skips authentication for the `/health` endpoint.
+/// Skips authentication for the `/health` endpoint and the GitHub webhook,
+/// which checks its own signature.
 pub async fn auth_middleware(
     State(auth): State<AuthState>,
-    req: Request<Body>,
+    mut req: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
     let path = req.uri().path();
 
-    // /health is always unauthenticated
-    if path == "/health" {
+    // /health is always unauthenticated, and so is the dashboard's sign-in
+    // page, which checks the token it is given by setting it and redirecting.
+    // GitHub can't send a bearer token, so its deliveries are signed instead.
+    if path == "/health"
+        || dashboard::is_public_path(path)
+        || path == crate::github_issues::WEBHOOK_PATH
+    {
         return Ok(next.run(req).await);
     }
 
+    // Dashboard pages send a signed-out browser to sign in rather than
+    // showing it a bare 401.
+    let unauthorized = if req.method() == Method::GET && dashboard::is_page_path(path) {
+        Ok(Redirect::to(dashboard::SIGN_IN_PATH).into_response())
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    };
+
     if !auth.requires_auth()? {
+        req.extensions_mut().insert(AuthIdentity::Anonymous);
         return Ok(next.run(req).await);
     }
 
-    let Some(token) = bearer_token(&req) else {
-        return Err(StatusCode::UNAUTHORIZED);
+    let Some(token) = bearer_token(&req).or_else(|| dashboard_cookie_token(&req)) else {
+        return unauthorized;
     };
 
     if let Some(expected) = auth.static_token.as_deref() {
         if constant_time_eq(expected.as_bytes(), token.as_bytes()) {
+            req.extensions_mut().insert(AuthIdentity::StaticToken);
             return Ok(next.run(req).await);
         }
     }
 
     match auth_tokens::verify_bearer_token(token) {
-        Ok(true) => return Ok(next.run(req).await),
-        Ok(false) => {}
+        Ok(Some(verified)) => {
+            let required = scopes::required_scope_for_request(req.method(), req.uri().path());
+            if !verified.scope.allows(required) {
+                warn!(
+                    component = "auth",
+                    event = "auth.scope_denied",
+                    token_id = %verified.id,
+                    scope = verified.scope.as_str(),
+                    required = required.as_str(),
+                    method = %req.method(),
+                    path = %req.uri().path(),
+                    "Token scope does not allow this request"
+                );
+                return Err(StatusCode::FORBIDDEN);
+            }
+            req.extensions_mut().insert(AuthIdentity::Token {
+                id: verified.id,
+                user: verified.user,
+                scope: verified.scope,
+            });
+            return Ok(next.run(req).await);
+        }
+        Ok(None) => {}
         Err(e) => {
             warn!(
                 component = "auth",
@@ -84,7 +185,55 @@ pub async fn auth_middleware(
         }
     }
 
-    Err(StatusCode::UNAUTHORIZED)
+    unauthorized
+}
+
+/// Close WebSocket connections whose token was revoked, expired, or whose
+/// user was disabled since they connected.
+pub async fn start_token_sweep_loop(state: Arc<SessionRegistry>) {
+    let mut interval = tokio::time::interval(TOKEN_SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let connections = state.token_connections();
+        if connections.is_empty() {
+            continue;
+        }
+        let ids: Vec<String> = connections
+            .iter()
+            .map(|(_, id)| id.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let dead = match tokio::task::spawn_blocking(move || auth_tokens::dead_token_ids(&ids))
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|dead| dead)
+        {
+            Ok(dead) => dead,
+            Err(e) => {
+                warn!(
+                    component = "auth",
+                    event = "auth.token_sweep_error",
+                    error = %e,
+                    "Failed to check live connection tokens"
+                );
+                continue;
+            }
+        };
+
+        for (conn_id, token_id) in connections {
+            if dead.contains(&token_id) && state.disconnect_connection(conn_id) {
+                info!(
+                    component = "auth",
+                    event = "auth.token_connection_closed",
+                    connection_id = conn_id,
+                    token_id = %token_id,
+                    "Closed connection using a revoked or expired token"
+                );
+            }
+        }
+    }
 }
 
 fn bearer_token(req: &Request<Body>) -> Option<&str> {
@@ -97,6 +246,44 @@ fn bearer_token(req: &Request<Body>) -> Option<&str> {
     Some(token)
 }
 
+/// The dashboard's token cookie, if this is a same-origin request carrying one.
+/// Requests from other origins are refused it so another site can't act with
+/// the dashboard's credentials.
+fn dashboard_cookie_token(req: &Request<Body>) -> Option<&str> {
+    if !is_same_origin(req) {
+        return None;
+    }
+    req.headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| {
+            pair.trim()
+                .strip_prefix(dashboard::TOKEN_COOKIE)?
+                .strip_prefix('=')
+        })
+        .filter(|token| !token.is_empty() && token.len() <= MAX_BEARER_TOKEN_LEN)
+}
+
+/// No `Origin` (a plain navigation), or one naming the host the request was
+/// sent to.
+fn is_same_origin(req: &Request<Body>) -> bool {
+    let Some(origin) = req.headers().get(header::ORIGIN) else {
+        return true;
+    };
+    let origin_host = origin
+        .to_str()
+        .ok()
+        .and_then(|origin| origin.split_once("://"))
+        .map(|(_, host)| host);
+    let host = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok());
+    matches!((origin_host, host), (Some(origin), Some(host)) if origin.eq_ignore_ascii_case(host))
+}
+
 fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
     let max_len = left.len().max(right.len());
     let mut diff = left.len() ^ right.len();
@@ -107,3 +294,51 @@ fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
     }
     diff == 0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(headers: &[(&str, &str)]) -> Request<Body> {
+        let mut builder = Request::builder().uri("/ws");
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).expect("request")
+    }
+
+    #[test]
+    fn dashboard_cookie_is_only_read_on_same_origin_requests() {
+        let cookie = ("cookie", "theme=dark; orbitdock_token=odtk_abc");
+        let host = ("host", "dock.example:4000");
+
+        assert_eq!(
+            dashboard_cookie_token(&request(&[cookie, host])),
+            Some("odtk_abc")
+        );
+        assert_eq!(
+            dashboard_cookie_token(&request(&[
+                cookie,
+                host,
+                ("origin", "https://dock.example:4000")
+            ])),
+            Some("odtk_abc")
+        );
+        assert_eq!(
+            dashboard_cookie_token(&request(&[
+                cookie,
+                host,
+                ("origin", "https://evil.example")
+            ])),
+            None
+        );
+        assert_eq!(
+            dashboard_cookie_token(&request(&[cookie, host, ("origin", "null")])),
+            None
+        );
+        assert_eq!(
+            dashboard_cookie_token(&request(&[("cookie", "orbitdock_token="), host])),
+            None
+        );
+    }
+}
@@ -2,6 +2,13 @@
 //!
 //! Tokens are issued as `odtk_<id>_<secret>`.
 //! Only salted hashes are stored in SQLite.
+//!
+//! A token may belong to a named user so that sessions and audited actions
+//! are attributed to a person rather than the shared server. Disabling a user
+//! revokes their tokens. Each token also carries a scope (see `scopes`) that
+//! limits what it may do.
+
+use std::time::Duration;
 
 use anyhow::Context;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
@@ -10,6 +17,7 @@ use ring::digest::{Context as DigestContext, SHA256};
 use ring::rand::{SecureRandom, SystemRandom};
 use rusqlite::{params, Connection};
 
+use crate::scopes::TokenScope;
 use crate::{migration_runner, paths};
 
 const TOKEN_PREFIX: &str = "odtk";
@@ -23,25 +31,138 @@ const MAX_TOKEN_DELIMITERS_TO_TRY: usize = 64;
 pub struct IssuedToken {
     pub id: String,
     pub token: String,
+    pub expires_at: Option<String>,
+}
+
+/// A verified bearer token and the user it belongs to, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedToken {
+    pub id: String,
+    pub user: Option<String>,
+    pub scope: TokenScope,
 }
 
 #[derive(Debug, Clone)]
 pub struct TokenRecord {
     pub id: String,
     pub label: Option<String>,
+    pub user: Option<String>,
+    pub scope: TokenScope,
     pub created_at: String,
     pub last_used_at: Option<String>,
     pub expires_at: Option<String>,
     pub revoked_at: Option<String>,
+    pub expired: bool,
 }
 
-pub fn issue_token(label: Option<&str>) -> anyhow::Result<IssuedToken> {
+#[derive(Debug, Clone)]
+pub struct UserRecord {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+    pub disabled_at: Option<String>,
+    pub active_tokens: i64,
+}
+
+/// Issue a new token. `expires_in` of `None` never expires.
+pub fn issue_token(
+    label: Option<&str>,
+    user: Option<&str>,
+    scope: TokenScope,
+    expires_in: Option<Duration>,
+) -> anyhow::Result<IssuedToken> {
     let conn = open_admin_connection()?;
-    let rng = SystemRandom::new();
     let label = label
         .map(str::trim)
         .filter(|v| !v.is_empty())
         .map(ToString::to_string);
+    let user_id = match user {
+        Some(name) => Some(
+            active_user_id(&conn, name)?
+                .with_context(|| format!("no active user named {:?}", name.trim()))?,
+        ),
+        None => None,
+    };
+
+    insert_token(
+        &conn,
+        label.as_deref(),
+        user_id.as_deref(),
+        scope,
+        expires_in,
+    )
+}
+
+/// Replace a token with a new one carrying the same label, user, and scope.
+///
+/// The old token is revoked right away, or after `grace` so clients can move
+/// over. Returns `None` if the token doesn't exist or is already dead.
+pub fn rotate_token(
+    id: &str,
+    expires_in: Option<Duration>,
+    grace: Option<Duration>,
+) -> anyhow::Result<Option<IssuedToken>> {
+    let mut conn = open_admin_connection()?;
+    let tx = conn.transaction()?;
+    let existing = tx.query_row(
+        "SELECT label, user_id, scope
+         FROM auth_tokens
+         WHERE id = ?1
+           AND revoked_at IS NULL
+           AND (expires_at IS NULL OR expires_at > strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))",
+        params![id.trim()],
+        |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        },
+    );
+    let (label, user_id, scope) = match existing {
+        Ok(v) => v,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+        Err(e) => return Err(anyhow::Error::new(e).context("query auth token")),
+    };
+
+    let issued = insert_token(
+        &tx,
+        label.as_deref(),
+        user_id.as_deref(),
+        parse_scope(scope),
+        expires_in,
+    )?;
+    match grace {
+        // Only ever shortens the old token's life.
+        Some(grace) => tx.execute(
+            "UPDATE auth_tokens
+             SET expires_at = MIN(COALESCE(expires_at, ?2), ?2)
+             WHERE id = ?1",
+            params![id.trim(), expires_at_from_now(&tx, grace)?],
+        )?,
+        None => tx.execute(
+            "UPDATE auth_tokens
+             SET revoked_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+             WHERE id = ?1",
+            params![id.trim()],
+        )?,
+    };
+    tx.commit()?;
+    Ok(Some(issued))
+}
+
+fn insert_token(
+    conn: &Connection,
+    label: Option<&str>,
+    user_id: Option<&str>,
+    scope: TokenScope,
+    expires_in: Option<Duration>,
+) -> anyhow::Result<IssuedToken> {
+    let rng = SystemRandom::new();
+    let expires_at = match expires_in {
+        Some(lifetime) => Some(expires_at_from_now(conn, lifetime)?),
+        None => None,
+    };
 
     for _ in 0..8 {
         let id = random_string(&rng, TOKEN_ID_BYTES)?;
@@ -51,9 +172,18 @@ pub fn issue_token(label: Option<&str>) -> anyhow::Result<IssuedToken> {
 
         let inserted = conn
             .execute(
-                "INSERT INTO auth_tokens (id, token_hash, token_salt, label)
-                 VALUES (?1, ?2, ?3, ?4)",
-                params![id, hash.to_vec(), salt.to_vec(), label.as_deref()],
+                "INSERT INTO auth_tokens
+                     (id, token_hash, token_salt, label, user_id, scope, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    id,
+                    hash.to_vec(),
+                    salt.to_vec(),
+                    label,
+                    user_id,
+                    scope.as_str(),
+                    expires_at
+                ],
             )
             .with_context(|| "insert auth token")?;
 
@@ -61,6 +191,7 @@ pub fn issue_token(label: Option<&str>) -> anyhow::Result<IssuedToken> {
             return Ok(IssuedToken {
                 id: id.clone(),
                 token: format!("{}_{}_{}", TOKEN_PREFIX, id, secret),
+                expires_at,
             });
         }
     }
@@ -68,12 +199,48 @@ pub fn issue_token(label: Option<&str>) -> anyhow::Result<IssuedToken> {
     anyhow::bail!("failed to generate a unique auth token id")
 }
 
+/// A timestamp `lifetime` from now, in the format the table stores.
+fn expires_at_from_now(conn: &Connection, lifetime: Duration) -> anyhow::Result<String> {
+    let modifier = format!("+{} seconds", lifetime.as_secs());
+    conn.query_row(
+        "SELECT strftime('%Y-%m-%dT%H:%M:%fZ', 'now', ?1)",
+        params![modifier],
+        |row| row.get(0),
+    )
+    .context("compute token expiry")
+}
+
+/// Parse a token lifetime like `90m`, `12h`, `30d`, or `2w`.
+pub fn parse_lifetime(value: &str) -> anyhow::Result<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .with_context(|| format!("invalid lifetime {value:?} (e.g. 90m, 12h, 30d)"))?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => anyhow::bail!("invalid lifetime {value:?} (e.g. 90m, 12h, 30d)"),
+    };
+    if amount == 0 {
+        anyhow::bail!("lifetime must be greater than zero");
+    }
+    Ok(Duration::from_secs(amount.saturating_mul(unit_secs)))
+}
+
 pub fn active_token_count() -> anyhow::Result<i64> {
     let conn = open_runtime_connection()?;
     let count = conn.query_row(
         "SELECT COUNT(*) FROM auth_tokens
          WHERE revoked_at IS NULL
-           AND (expires_at IS NULL OR expires_at > strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))",
+           AND (expires_at IS NULL OR expires_at > strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+           AND (user_id IS NULL OR user_id IN (SELECT id FROM users WHERE disabled_at IS NULL))",
         [],
         |row| row.get::<_, i64>(0),
     )?;
@@ -83,18 +250,25 @@ pub fn active_token_count() -> anyhow::Result<i64> {
 pub fn list_tokens() -> anyhow::Result<Vec<TokenRecord>> {
     let conn = open_admin_connection()?;
     let mut stmt = conn.prepare(
-        "SELECT id, label, created_at, last_used_at, expires_at, revoked_at
-         FROM auth_tokens
-         ORDER BY datetime(created_at) DESC",
+        "SELECT t.id, t.label, u.name, t.scope, t.created_at, t.last_used_at, t.expires_at,
+                t.revoked_at,
+                t.expires_at IS NOT NULL
+                    AND t.expires_at <= strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+         FROM auth_tokens t
+         LEFT JOIN users u ON u.id = t.user_id
+         ORDER BY datetime(t.created_at) DESC",
     )?;
     let rows = stmt.query_map([], |row| {
         Ok(TokenRecord {
             id: row.get(0)?,
             label: row.get(1)?,
-            created_at: row.get(2)?,
-            last_used_at: row.get(3)?,
-            expires_at: row.get(4)?,
-            revoked_at: row.get(5)?,
+            user: row.get(2)?,
+            scope: parse_scope(row.get(3)?),
+            created_at: row.get(4)?,
+            last_used_at: row.get(5)?,
+            expires_at: row.get(6)?,
+            revoked_at: row.get(7)?,
+            expired: row.get(8)?,
         })
     })?;
 
@@ -116,19 +290,49 @@ pub fn revoke_token(id: &str) -> anyhow::Result<bool> {
     Ok(updated > 0)
 }
 
-pub fn verify_bearer_token(token: &str) -> anyhow::Result<bool> {
+/// Of the given token ids, those that no longer verify: revoked, expired,
+/// deleted, or belonging to a disabled user.
+pub fn dead_token_ids(ids: &[String]) -> anyhow::Result<Vec<String>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let conn = open_runtime_connection()?;
+    let mut stmt = conn.prepare(
+        "SELECT 1
+         FROM auth_tokens t
+         LEFT JOIN users u ON u.id = t.user_id
+         WHERE t.id = ?1
+           AND t.revoked_at IS NULL
+           AND (t.expires_at IS NULL OR t.expires_at > strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+           AND (t.user_id IS NULL OR (u.id IS NOT NULL AND u.disabled_at IS NULL))",
+    )?;
+
+    let mut dead = Vec::new();
+    for id in ids {
+        if !stmt.exists(params![id])? {
+            dead.push(id.clone());
+        }
+    }
+    Ok(dead)
+}
+
+/// Verify a bearer token, returning the matching token id and its user.
+///
+/// Tokens of disabled users no longer verify.
+pub fn verify_bearer_token(token: &str) -> anyhow::Result<Option<VerifiedToken>> {
     let token_candidates = parse_token_candidates(token);
     if token_candidates.is_empty() {
-        return Ok(false);
+        return Ok(None);
     }
 
     let conn = open_runtime_connection()?;
     let mut stmt = conn.prepare(
-        "SELECT token_hash, token_salt
-         FROM auth_tokens
-         WHERE id = ?1
-           AND revoked_at IS NULL
-           AND (expires_at IS NULL OR expires_at > strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+        "SELECT t.token_hash, t.token_salt, t.user_id, u.name, u.disabled_at, t.scope
+         FROM auth_tokens t
+         LEFT JOIN users u ON u.id = t.user_id
+         WHERE t.id = ?1
+           AND t.revoked_at IS NULL
+           AND (t.expires_at IS NULL OR t.expires_at > strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
          LIMIT 1",
     )?;
 
@@ -136,10 +340,14 @@ pub fn verify_bearer_token(token: &str) -> anyhow::Result<bool> {
         let row = stmt.query_row(params![id], |row| {
             let hash: Vec<u8> = row.get(0)?;
             let salt: Vec<u8> = row.get(1)?;
-            Ok((hash, salt))
+            let user_id: Option<String> = row.get(2)?;
+            let user: Option<String> = row.get(3)?;
+            let disabled_at: Option<String> = row.get(4)?;
+            let scope: String = row.get(5)?;
+            Ok((hash, salt, user_id, user, disabled_at, scope))
         });
 
-        let (expected_hash, salt) = match row {
+        let (expected_hash, salt, user_id, user, disabled_at, scope) = match row {
             Ok(v) => v,
             Err(rusqlite::Error::QueryReturnedNoRows) => continue,
             Err(e) => return Err(anyhow::Error::new(e).context("query auth token")),
@@ -148,6 +356,10 @@ pub fn verify_bearer_token(token: &str) -> anyhow::Result<bool> {
         if expected_hash.len() != HASH_BYTES || salt.len() != TOKEN_SALT_BYTES {
             continue;
         }
+        // A token whose user was disabled or deleted is dead.
+        if user_id.is_some() && (user.is_none() || disabled_at.is_some()) {
+            continue;
+        }
 
         let candidate_hash = hash_secret(&salt, secret);
         let is_match = constant_time_eq(&expected_hash, &candidate_hash);
@@ -159,11 +371,121 @@ pub fn verify_bearer_token(token: &str) -> anyhow::Result<bool> {
                  WHERE id = ?1",
                 params![id],
             );
-            return Ok(true);
+            return Ok(Some(VerifiedToken {
+                id: id.to_string(),
+                user,
+                scope: parse_scope(scope),
+            }));
         }
     }
 
-    Ok(false)
+    Ok(None)
+}
+
+pub fn add_user(name: &str) -> anyhow::Result<UserRecord> {
+    let name = validate_user_name(name)?;
+    let conn = open_admin_connection()?;
+    let rng = SystemRandom::new();
+    let id = format!("usr_{}", random_string(&rng, TOKEN_ID_BYTES)?);
+
+    let inserted = conn.execute(
+        "INSERT INTO users (id, name) VALUES (?1, ?2) ON CONFLICT(name) DO NOTHING",
+        params![id, name],
+    )?;
+    if inserted == 0 {
+        anyhow::bail!("a user named {name:?} already exists");
+    }
+
+    list_users_with(&conn)?
+        .into_iter()
+        .find(|user| user.id == id)
+        .context("read back new user")
+}
+
+pub fn list_users() -> anyhow::Result<Vec<UserRecord>> {
+    let conn = open_admin_connection()?;
+    list_users_with(&conn)
+}
+
+/// Disable a user and revoke their tokens. Their sessions and audit entries
+/// keep the attribution.
+pub fn disable_user(name: &str) -> anyhow::Result<bool> {
+    let mut conn = open_admin_connection()?;
+    let tx = conn.transaction()?;
+    let Some(user_id) = active_user_id(&tx, name)? else {
+        return Ok(false);
+    };
+    tx.execute(
+        "UPDATE users SET disabled_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = ?1",
+        params![user_id],
+    )?;
+    tx.execute(
+        "UPDATE auth_tokens
+         SET revoked_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+         WHERE user_id = ?1 AND revoked_at IS NULL",
+        params![user_id],
+    )?;
+    tx.commit()?;
+    Ok(true)
+}
+
+fn list_users_with(conn: &Connection) -> anyhow::Result<Vec<UserRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT u.id, u.name, u.created_at, u.disabled_at,
+                (SELECT COUNT(*) FROM auth_tokens t
+                 WHERE t.user_id = u.id
+                   AND t.revoked_at IS NULL
+                   AND (t.expires_at IS NULL OR t.expires_at > strftime('%Y-%m-%dT%H:%M:%fZ', 'now')))
+         FROM users u
+         ORDER BY u.name",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(UserRecord {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            created_at: row.get(2)?,
+            disabled_at: row.get(3)?,
+            active_tokens: row.get(4)?,
+        })
+    })?;
+
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+fn active_user_id(conn: &Connection, name: &str) -> anyhow::Result<Option<String>> {
+    let row = conn.query_row(
+        "SELECT id FROM users WHERE name = ?1 AND disabled_at IS NULL",
+        params![name.trim()],
+        |row| row.get(0),
+    );
+    match row {
+        Ok(id) => Ok(Some(id)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(anyhow::Error::new(e).context("query user")),
+    }
+}
+
+/// User names appear in logs and the audit trail, so keep them plain.
+fn validate_user_name(name: &str) -> anyhow::Result<&str> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '@'));
+    if !valid {
+        anyhow::bail!("user names may use letters, digits, and . _ - @ (up to 64 characters)");
+    }
+    Ok(name)
+}
+
+/// Unknown scopes fail closed to `read`.
+fn parse_scope(value: String) -> TokenScope {
+    TokenScope::parse(&value).unwrap_or(TokenScope::Read)
 }
 
 fn open_admin_connection() -> anyhow::Result<Connection> {
@@ -249,7 +571,9 @@ fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::parse_token_candidates;
+    use std::time::Duration;
+
+    use super::{parse_lifetime, parse_token_candidates, validate_user_name};
 
     #[test]
     fn parse_token_candidates_rejects_invalid_prefix() {
@@ -265,4 +589,27 @@ mod tests {
             .iter()
             .any(|(id, secret)| *id == "abc_def" && *secret == "ghi_jkl"));
     }
+
+    #[test]
+    fn validate_user_name_accepts_plain_names_only() {
+        assert_eq!(
+            validate_user_name("  alice@example.com ").unwrap(),
+            "alice@example.com"
+        );
+        assert!(validate_user_name("").is_err());
+        assert!(validate_user_name("alice smith").is_err());
+        assert!(validate_user_name(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn parse_lifetime_accepts_unit_suffixes() {
+        assert_eq!(parse_lifetime("90m").unwrap(), Duration::from_secs(90 * 60));
+        assert_eq!(
+            parse_lifetime(" 30d ").unwrap(),
+            Duration::from_secs(30 * 86_400)
+        );
+        assert!(parse_lifetime("30").is_err());
+        assert!(parse_lifetime("0h").is_err());
+        assert!(parse_lifetime("d").is_err());
+    }
 }
@@ -0,0 +1,471 @@
+//! Monthly spend budgets from `[budgets]` in the config file.
+//!
+//! As the persistence writer records a session's estimated spend (see
+//! `pricing`), the increase is added to `budget_spend` for the current UTC
+//! month and the session's project. Each budget that covers the project (the
+//! global one, and any `[budgets.projects]` entry at or above its path) is
+//! checked against its alert thresholds; a newly crossed threshold fires a
+//! `budget_threshold` webhook event, which also reaches push, Slack, Discord,
+//! and list subscribers as `BudgetAlert`. `budget_alerts` records what fired so
+//! each threshold alerts once per month.
+//!
+//! An exhausted budget set to `confirm` or `block` gates `CreateSession`.
+//! Forks and takeovers continue existing work and are never gated.
+//!
+//! The config is re-read on every check, so edits apply on reload.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use orbitdock_protocol::Provider;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+
+use crate::config_file;
+use crate::webhooks::{self, WebhookEvent, WebhookEventKind, WebhookSession};
+
+/// Fractions of the budget that alert when the section doesn't list its own.
+const DEFAULT_THRESHOLDS: [f64; 2] = [0.8, 1.0];
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BudgetsSection {
+    /// Limit across every project.
+    pub monthly_usd: Option<f64>,
+    /// Fractions of a limit that alert, e.g. `[0.5, 0.8, 1.0]`.
+    pub alert_thresholds: Option<Vec<f64>>,
+    pub over_budget: Option<OverBudget>,
+    /// Limits keyed by project path; a path also covers the projects below it.
+    pub projects: BTreeMap<String, ProjectBudget>,
+}
+
+/// One `[budgets.projects."<path>"]` entry; unset keys inherit from `[budgets]`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectBudget {
+    pub monthly_usd: f64,
+    #[serde(default)]
+    pub alert_thresholds: Option<Vec<f64>>,
+    #[serde(default)]
+    pub over_budget: Option<OverBudget>,
+}
+
+/// What `CreateSession` does once a budget is used up.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverBudget {
+    /// Alert only.
+    #[default]
+    Notify,
+    /// Refuse unless the request sets `confirm_over_budget`.
+    Confirm,
+    /// Refuse.
+    Block,
+}
+
+/// A budget that covers some project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Budget {
+    /// `None` for the global budget, else the configured project path.
+    pub project: Option<String>,
+    pub monthly_usd: f64,
+    pub thresholds: Vec<f64>,
+    pub over_budget: OverBudget,
+}
+
+impl Budget {
+    /// Key for `budget_alerts`.
+    fn scope(&self) -> String {
+        match &self.project {
+            Some(path) => format!("project:{path}"),
+            None => "global".to_string(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match &self.project {
+            Some(path) => format!("the monthly budget for {path}"),
+            None => "the global monthly budget".to_string(),
+        }
+    }
+}
+
+impl BudgetsSection {
+    /// Budgets covering `project_path`: the global one, then project entries.
+    pub fn applicable(&self, project_path: &str) -> Vec<Budget> {
+        let thresholds = |own: &Option<Vec<f64>>| {
+            own.clone()
+                .or_else(|| self.alert_thresholds.clone())
+                .unwrap_or_else(|| DEFAULT_THRESHOLDS.to_vec())
+        };
+        let global = self.monthly_usd.map(|monthly_usd| Budget {
+            project: None,
+            monthly_usd,
+            thresholds: thresholds(&None),
+            over_budget: self.over_budget.unwrap_or_default(),
+        });
+        let projects = self
+            .projects
+            .iter()
+            .filter(|(path, _)| covers(path, project_path))
+            .map(|(path, budget)| Budget {
+                project: Some(trim_path(path).to_string()),
+                monthly_usd: budget.monthly_usd,
+                thresholds: thresholds(&budget.alert_thresholds),
+                over_budget: budget.over_budget.or(self.over_budget).unwrap_or_default(),
+            });
+        global.into_iter().chain(projects).collect()
+    }
+
+    /// Problems that parsing alone doesn't catch.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut check = |key: String, limit: Option<f64>, thresholds: &Option<Vec<f64>>| {
+            if limit.is_some_and(|v| v < 0.0 || !v.is_finite()) {
+                problems.push(format!("{key}.monthly_usd: must be a non-negative number"));
+            }
+            let thresholds = thresholds.as_deref().unwrap_or_default();
+            if thresholds.iter().any(|t| *t <= 0.0 || !t.is_finite()) {
+                problems.push(format!(
+                    "{key}.alert_thresholds: must be positive fractions of the budget"
+                ));
+            }
+        };
+        check("budgets".into(), self.monthly_usd, &self.alert_thresholds);
+        for (path, budget) in &self.projects {
+            check(
+                format!("budgets.projects.\"{path}\""),
+                Some(budget.monthly_usd),
+                &budget.alert_thresholds,
+            );
+        }
+        problems
+    }
+}
+
+fn trim_path(path: &str) -> &str {
+    match path.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    }
+}
+
+/// Whether a budget keyed by `key` covers `project_path`.
+fn covers(key: &str, project_path: &str) -> bool {
+    let key = key.trim_end_matches('/');
+    project_path == key
+        || project_path
+            .strip_prefix(key)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn current_month(conn: &Connection) -> rusqlite::Result<String> {
+    conn.query_row("SELECT strftime('%Y-%m', 'now')", [], |row| row.get(0))
+}
+
+/// Spend recorded this month against `budget`.
+fn spent(conn: &Connection, month: &str, budget: &Budget) -> rusqlite::Result<f64> {
+    let prefix = budget
+        .project
+        .as_deref()
+        .map(|path| path.trim_end_matches('/'));
+    conn.query_row(
+        "SELECT COALESCE(SUM(spend_usd), 0) FROM budget_spend
+         WHERE month = ?1
+           AND (?2 IS NULL
+                OR project_path = ?2
+                OR substr(project_path, 1, length(?2) + 1) = ?2 || '/')",
+        params![month, prefix],
+        |row| row.get(0),
+    )
+}
+
+/// Add `delta` USD of `session_id`'s spend to this month's total and fire an
+/// alert for each budget that newly crossed a threshold.
+///
+/// Runs on the persistence writer's connection.
+pub fn record_spend(conn: &Connection, session_id: &str, delta: f64) -> rusqlite::Result<()> {
+    record_spend_against(conn, session_id, delta, &config_file::current().budgets)
+}
+
+fn record_spend_against(
+    conn: &Connection,
+    session_id: &str,
+    delta: f64,
+    budgets: &BudgetsSection,
+) -> rusqlite::Result<()> {
+    if delta <= 0.0 {
+        return Ok(());
+    }
+    #[allow(clippy::type_complexity)]
+    let session: Option<(String, Option<String>, Option<String>, Option<String>)> = conn
+        .query_row(
+            "SELECT project_path, provider, model, COALESCE(custom_name, summary)
+             FROM sessions WHERE id = ?1",
+            params![session_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()?;
+    let Some((project_path, provider, model, name)) = session else {
+        return Ok(());
+    };
+
+    let month = current_month(conn)?;
+    conn.execute(
+        "INSERT INTO budget_spend (month, project_path, spend_usd) VALUES (?1, ?2, ?3)
+         ON CONFLICT(month, project_path) DO UPDATE SET spend_usd = spend_usd + excluded.spend_usd",
+        params![month, project_path, delta],
+    )?;
+
+    for budget in budgets.applicable(&project_path) {
+        let spent = spent(conn, &month, &budget)?;
+        // Record every crossed threshold, but alert only on the highest new one.
+        let mut crossed = None;
+        for &threshold in &budget.thresholds {
+            if spent < threshold * budget.monthly_usd {
+                continue;
+            }
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO budget_alerts (scope, month, threshold) VALUES (?1, ?2, ?3)",
+                params![budget.scope(), month, threshold],
+            )?;
+            if inserted > 0 && crossed.is_none_or(|highest| threshold > highest) {
+                crossed = Some(threshold);
+            }
+        }
+        let Some(threshold) = crossed else {
+            continue;
+        };
+
+        info!(
+            component = "budgets",
+            event = "budgets.threshold_crossed",
+            session_id = %session_id,
+            scope = %budget.scope(),
+            month = %month,
+            spent_usd = spent,
+            budget_usd = budget.monthly_usd,
+            threshold,
+            "Budget alert threshold crossed"
+        );
+        webhooks::emit(WebhookEvent::new(
+            WebhookEventKind::BudgetThreshold,
+            WebhookSession {
+                id: session_id.to_string(),
+                provider: provider.as_deref().map(|provider| match provider {
+                    "codex" => Provider::Codex,
+                    _ => Provider::Claude,
+                }),
+                project_path: Some(project_path.clone()),
+                name: name.clone(),
+                model: model.clone(),
+            },
+            json!({
+                "scope": budget.project.as_ref().map_or("global", |_| "project"),
+                "project_path": budget.project,
+                "month": month,
+                "spent_usd": spent,
+                "budget_usd": budget.monthly_usd,
+                "threshold": threshold,
+            }),
+        ));
+    }
+    Ok(())
+}
+
+/// A used-up budget that restricts new sessions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exhausted {
+    pub budget: Budget,
+    pub spent_usd: f64,
+}
+
+impl Exhausted {
+    pub fn message(&self) -> String {
+        format!(
+            "Estimated spend of ${:.2} has reached {} (${:.2})",
+            self.spent_usd,
+            self.budget.describe(),
+            self.budget.monthly_usd
+        )
+    }
+}
+
+/// The strictest used-up budget covering `project_path` that is set to
+/// `confirm` or `block`. Reads the database, so call it off the async runtime.
+pub fn exhausted(db_path: &Path, project_path: &str) -> Option<Exhausted> {
+    let budgets = config_file::current().budgets.applicable(project_path);
+    if budgets
+        .iter()
+        .all(|budget| budget.over_budget == OverBudget::Notify)
+    {
+        return None;
+    }
+    let conn = Connection::open(db_path).ok()?;
+    exhausted_in(&conn, budgets).ok().flatten()
+}
+
+fn exhausted_in(conn: &Connection, budgets: Vec<Budget>) -> rusqlite::Result<Option<Exhausted>> {
+    let month = current_month(conn)?;
+    let mut strictest: Option<Exhausted> = None;
+    for budget in budgets {
+        if budget.over_budget == OverBudget::Notify {
+            continue;
+        }
+        let spent_usd = spent(conn, &month, &budget)?;
+        if spent_usd < budget.monthly_usd {
+            continue;
+        }
+        if strictest
+            .as_ref()
+            .is_none_or(|current| budget.over_budget > current.budget.over_budget)
+        {
+            strictest = Some(Exhausted { budget, spent_usd });
+        }
+    }
+    Ok(strictest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration_runner;
+
+    fn section(toml: &str) -> BudgetsSection {
+        config_file::ServerConfig::parse(toml).unwrap().budgets
+    }
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migration_runner::run_migrations(&mut conn).unwrap();
+        conn
+    }
+
+    fn add_spend(conn: &Connection, project_path: &str, usd: f64) {
+        let month = current_month(conn).unwrap();
+        conn.execute(
+            "INSERT INTO budget_spend (month, project_path, spend_usd) VALUES (?1, ?2, ?3)",
+            params![month, project_path, usd],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn project_budgets_cover_nested_paths_and_inherit_defaults() {
+        let budgets = section(
+            r#"
+            [budgets]
+            monthly_usd = 100.0
+            alert_thresholds = [0.5, 1.0]
+            over_budget = "confirm"
+
+            [budgets.projects."/work/api/"]
+            monthly_usd = 20.0
+            over_budget = "block"
+            "#,
+        );
+
+        let applicable = budgets.applicable("/work/api/crates/server");
+        assert_eq!(applicable.len(), 2);
+        assert_eq!(applicable[0].project, None);
+        assert_eq!(applicable[0].over_budget, OverBudget::Confirm);
+        assert_eq!(applicable[1].project.as_deref(), Some("/work/api"));
+        assert_eq!(applicable[1].thresholds, vec![0.5, 1.0]);
+        assert_eq!(applicable[1].over_budget, OverBudget::Block);
+        assert_eq!(budgets.applicable("/work/api-client").len(), 1);
+        assert!(section("").applicable("/anything").is_empty());
+    }
+
+    #[test]
+    fn exhausted_picks_the_strictest_used_up_budget() {
+        let conn = test_db();
+        let budgets = section(
+            r#"
+            [budgets]
+            monthly_usd = 10.0
+            over_budget = "confirm"
+
+            [budgets.projects."/work/api"]
+            monthly_usd = 5.0
+            over_budget = "block"
+            "#,
+        );
+
+        add_spend(&conn, "/work/api/sub", 4.0);
+        add_spend(&conn, "/work/web", 6.5);
+        let status = exhausted_in(&conn, budgets.applicable("/work/api")).unwrap();
+        assert_eq!(status.unwrap().budget.over_budget, OverBudget::Confirm);
+
+        add_spend(&conn, "/work/api", 1.0);
+        let status = exhausted_in(&conn, budgets.applicable("/work/api"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.budget.project.as_deref(), Some("/work/api"));
+        assert!((status.spent_usd - 5.0).abs() < 1e-9);
+        assert!(status.message().contains("/work/api"));
+    }
+
+    #[test]
+    fn each_threshold_is_recorded_once_per_month() {
+        let conn = test_db();
+        conn.execute(
+            "INSERT INTO sessions (id, provider, project_path, model)
+             VALUES ('s1', 'claude', '/work/api', 'claude-sonnet-4-5')",
+            [],
+        )
+        .unwrap();
+        let budgets = section(
+            r#"
+            [budgets.projects."/work"]
+            monthly_usd = 10.0
+            alert_thresholds = [0.5, 0.8, 1.0]
+            "#,
+        );
+        let crossed = |conn: &Connection| -> Vec<f64> {
+            let mut stmt = conn
+                .prepare("SELECT threshold FROM budget_alerts ORDER BY threshold")
+                .unwrap();
+            stmt.query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+
+        record_spend_against(&conn, "s1", 4.0, &budgets).unwrap();
+        assert!(crossed(&conn).is_empty());
+        record_spend_against(&conn, "s1", 4.5, &budgets).unwrap();
+        assert_eq!(crossed(&conn), vec![0.5, 0.8]);
+        record_spend_against(&conn, "s1", 0.5, &budgets).unwrap();
+        assert_eq!(crossed(&conn), vec![0.5, 0.8]);
+        record_spend_against(&conn, "unknown-session", 100.0, &budgets).unwrap();
+        record_spend_against(&conn, "s1", 1.0, &budgets).unwrap();
+        assert_eq!(crossed(&conn), vec![0.5, 0.8, 1.0]);
+
+        let spent = spent(
+            &conn,
+            &current_month(&conn).unwrap(),
+            &budgets.applicable("/work")[0],
+        );
+        assert!((spent.unwrap() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reports_invalid_limits() {
+        let budgets = section(
+            r#"
+            [budgets]
+            monthly_usd = -1.0
+            alert_thresholds = [0.0]
+
+            [budgets.projects."/work"]
+            monthly_usd = 5.0
+            "#,
+        );
+        let problems = budgets.problems();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("budgets.monthly_usd"));
+        assert!(problems[1].starts_with("budgets.alert_thresholds"));
+    }
+}
@@ -0,0 +1,440 @@
+//! Slack and Discord senders for webhook events.
+//!
+//! Messages carry the session name, project, what happened, and an
+//! `orbitdock://session/<id>` deep link. Later events for the same session
+//! reply in a thread: Slack threads need a bot token (`chat.postMessage`
+//! returns the parent `ts`; incoming webhooks cannot thread), and Discord
+//! threads need a forum channel. Other Discord channels get one message per
+//! event. Thread ids live in memory, so a restart starts fresh threads.
+
+use std::sync::Arc;
+
+use dashmap::{DashMap, DashSet};
+use orbitdock_protocol::Provider;
+use serde_json::{json, Value};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+use crate::webhooks::{AttemptOutcome, WebhookEvent, WebhookEventKind, WebhookRecord};
+
+const SESSION_LINK_PREFIX: &str = "orbitdock://session/";
+/// Cap on quoted message text so a long reply doesn't flood the channel.
+const MAX_DETAIL_CHARS: usize = 300;
+
+/// Thread per (webhook, session), guarded so one event at a time posts to it.
+#[derive(Default)]
+pub struct ThreadRegistry {
+    threads: DashMap<(String, String), Arc<Mutex<Option<String>>>>,
+    /// Discord webhooks whose channel turned out not to support threads.
+    unthreaded: DashSet<String>,
+}
+
+impl ThreadRegistry {
+    pub async fn lock(
+        &self,
+        webhook_id: &str,
+        session_id: &str,
+    ) -> OwnedMutexGuard<Option<String>> {
+        let slot = self
+            .threads
+            .entry((webhook_id.to_string(), session_id.to_string()))
+            .or_default()
+            .clone();
+        slot.lock_owned().await
+    }
+
+    pub fn forget(&self, webhook_id: &str, session_id: &str) {
+        self.threads
+            .remove(&(webhook_id.to_string(), session_id.to_string()));
+    }
+}
+
+pub fn session_link(session_id: &str) -> String {
+    format!("{SESSION_LINK_PREFIX}{session_id}")
+}
+
+/// Session name, falling back to the project directory, then the id.
+pub(crate) fn title(event: &WebhookEvent) -> String {
+    let session = &event.session;
+    session
+        .name
+        .clone()
+        .or_else(|| {
+            session
+                .project_path
+                .as_deref()
+                .and_then(|path| path.rsplit('/').find(|part| !part.is_empty()))
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| session.id.clone())
+}
+
+/// One line saying what happened.
+pub(crate) fn headline(event: &WebhookEvent) -> String {
+    let data = &event.data;
+    let text = |key: &str| data[key].as_str().filter(|value| !value.is_empty());
+    match event.kind {
+        WebhookEventKind::TurnCompleted => {
+            let mut headline = match text("summary") {
+                Some(summary) => truncate(summary, 200),
+                None => "Turn finished".to_string(),
+            };
+            if let Some(stats) = diff_stats(data) {
+                headline.push_str(&format!(" ({stats})"));
+            }
+            headline
+        }
+        WebhookEventKind::ApprovalPending => match text("command").or_else(|| text("tool_name")) {
+            Some(subject) => format!("Needs approval: `{}`", truncate(subject, 120)),
+            None if text("question").is_some() => "Has a question".to_string(),
+            None => "Needs approval".to_string(),
+        },
+        WebhookEventKind::SessionErrored => format!(
+            "Error: {}",
+            truncate(text("message").unwrap_or("unknown error"), 200)
+        ),
+        WebhookEventKind::SessionEnded => match text("reason") {
+            Some(reason) => format!("Session ended ({reason})"),
+            None => "Session ended".to_string(),
+        },
+        WebhookEventKind::BudgetThreshold => {
+            let usd = |key: &str| data[key].as_f64().unwrap_or_default();
+            let budget = match text("project_path") {
+                Some(path) => format!("monthly budget for {path}"),
+                None => "global monthly budget".to_string(),
+            };
+            format!(
+                "Estimated spend ${:.2} reached {:.0}% of the ${:.2} {budget}",
+                usd("spent_usd"),
+                usd("threshold") * 100.0,
+                usd("budget_usd"),
+            )
+        }
+        WebhookEventKind::ContextWarning => format!(
+            "Context window {:.0}% full; consider compacting",
+            data["percent"].as_f64().unwrap_or_default()
+        ),
+    }
+}
+
+/// Longer text worth quoting under the headline, if any.
+pub(crate) fn detail(event: &WebhookEvent) -> Option<String> {
+    let key = match event.kind {
+        WebhookEventKind::TurnCompleted => "last_message",
+        WebhookEventKind::ApprovalPending => "question",
+        _ => return None,
+    };
+    event.data[key]
+        .as_str()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| truncate(value, MAX_DETAIL_CHARS))
+}
+
+/// `3 files, +40 -12` from a turn's `diff_stats`, if it changed anything.
+fn diff_stats(data: &Value) -> Option<String> {
+    let stats: crate::turn_summary::DiffStats =
+        serde_json::from_value(data["diff_stats"].clone()).ok()?;
+    (!stats.is_empty()).then(|| stats.describe())
+}
+
+pub(crate) fn truncate(value: &str, max_chars: usize) -> String {
+    match value.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &value[..end]),
+        None => value.to_string(),
+    }
+}
+
+fn context_line(event: &WebhookEvent) -> String {
+    let mut parts = Vec::new();
+    if let Some(path) = event.session.project_path.as_deref() {
+        parts.push(path.to_string());
+    }
+    if let Some(provider) = event.session.provider {
+        parts.push(
+            match provider {
+                Provider::Claude => "claude",
+                Provider::Codex => "codex",
+            }
+            .to_string(),
+        );
+    }
+    if let Some(model) = event.session.model.as_deref() {
+        parts.push(model.to_string());
+    }
+    parts.join(" · ")
+}
+
+fn slack_payload(event: &WebhookEvent, channel: Option<&str>, thread_ts: Option<&str>) -> Value {
+    let title = title(event);
+    let headline = headline(event);
+    let mut text = format!(
+        "*<{}|{}>*\n{}",
+        session_link(&event.session.id),
+        title,
+        headline
+    );
+    if let Some(detail) = detail(event) {
+        for line in detail.lines() {
+            text.push_str("\n>");
+            text.push_str(line);
+        }
+    }
+
+    let mut payload = json!({
+        "text": format!("{title}: {headline}"),
+        "blocks": [
+            { "type": "section", "text": { "type": "mrkdwn", "text": text } },
+            {
+                "type": "context",
+                "elements": [{ "type": "mrkdwn", "text": context_line(event) }]
+            }
+        ],
+    });
+    if let Some(channel) = channel {
+        payload["channel"] = json!(channel);
+    }
+    if let Some(thread_ts) = thread_ts {
+        payload["thread_ts"] = json!(thread_ts);
+    }
+    payload
+}
+
+fn discord_color(kind: WebhookEventKind) -> u32 {
+    match kind {
+        WebhookEventKind::TurnCompleted => 0x2EB67D,
+        WebhookEventKind::ApprovalPending => 0xECB22E,
+        WebhookEventKind::SessionErrored => 0xE01E5A,
+        WebhookEventKind::SessionEnded => 0x808080,
+        WebhookEventKind::BudgetThreshold => 0xE8912D,
+        WebhookEventKind::ContextWarning => 0xECB22E,
+    }
+}
+
+fn discord_payload(event: &WebhookEvent, thread_name: Option<&str>) -> Value {
+    let mut description = headline(event);
+    if let Some(detail) = detail(event) {
+        description.push_str("\n\n");
+        description.push_str(&detail);
+    }
+    let mut payload = json!({
+        "embeds": [{
+            "title": title(event),
+            "description": description,
+            "color": discord_color(event.kind),
+            "fields": [
+                { "name": "Project", "value": context_line(event), "inline": false },
+                { "name": "Open in OrbitDock", "value": session_link(&event.session.id), "inline": false }
+            ],
+        }],
+        "allowed_mentions": { "parse": [] },
+    });
+    if let Some(thread_name) = thread_name {
+        payload["thread_name"] = json!(truncate(thread_name, 100));
+    }
+    payload
+}
+
+/// Post to Slack: `chat.postMessage` with a bot token, or an incoming webhook.
+pub(crate) async fn send_slack(
+    client: &reqwest::Client,
+    webhook: &WebhookRecord,
+    event: &WebhookEvent,
+    thread: &mut Option<String>,
+) -> AttemptOutcome {
+    let bot_token = webhook.secret.as_deref();
+    let payload = slack_payload(event, webhook.channel.as_deref(), thread.as_deref());
+    let mut request = client.post(&webhook.url).json(&payload);
+    if let Some(token) = bot_token {
+        request = request.bearer_auth(token);
+    }
+    let response = match request.send().await {
+        Ok(response) if response.status().is_success() && bot_token.is_some() => response,
+        result => return AttemptOutcome::from_send(result),
+    };
+
+    // The Web API reports failures in the body of a 200.
+    let status = response.status().as_u16();
+    let body: Value = response.json().await.unwrap_or_default();
+    if body["ok"].as_bool() == Some(true) {
+        if thread.is_none() {
+            *thread = body["ts"].as_str().map(str::to_string);
+        }
+        return AttemptOutcome::ok(status);
+    }
+    let error = body["error"].as_str().unwrap_or("unknown_error");
+    AttemptOutcome {
+        status_code: Some(status),
+        error: Some(format!("slack: {error}")),
+        retryable: error == "ratelimited",
+    }
+}
+
+/// Post to a Discord webhook, opening a forum thread on the first event.
+pub(crate) async fn send_discord(
+    client: &reqwest::Client,
+    threads: &ThreadRegistry,
+    webhook: &WebhookRecord,
+    event: &WebhookEvent,
+    thread: &mut Option<String>,
+) -> AttemptOutcome {
+    let Ok(mut url) = reqwest::Url::parse(&webhook.url) else {
+        return AttemptOutcome {
+            status_code: None,
+            error: Some("invalid Discord webhook URL".to_string()),
+            retryable: false,
+        };
+    };
+    url.query_pairs_mut().append_pair("wait", "true");
+    if let Some(thread_id) = thread.as_deref() {
+        url.query_pairs_mut().append_pair("thread_id", thread_id);
+    }
+
+    let open_thread = thread.is_none() && !threads.unthreaded.contains(&webhook.id);
+    let thread_name = open_thread.then(|| title(event));
+    let result = client
+        .post(url.clone())
+        .json(&discord_payload(event, thread_name.as_deref()))
+        .send()
+        .await;
+
+    let response = match result {
+        Ok(response) if response.status() == reqwest::StatusCode::BAD_REQUEST && open_thread => {
+            // Only forum channels accept `thread_name`; post plainly from now on.
+            threads.unthreaded.insert(webhook.id.clone());
+            let result = client
+                .post(url)
+                .json(&discord_payload(event, None))
+                .send()
+                .await;
+            return AttemptOutcome::from_send(result);
+        }
+        Ok(response) if response.status().is_success() => response,
+        result => return AttemptOutcome::from_send(result),
+    };
+
+    let status = response.status().as_u16();
+    if open_thread {
+        let body: Value = response.json().await.unwrap_or_default();
+        *thread = body["channel_id"].as_str().map(str::to_string);
+    }
+    AttemptOutcome::ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webhooks::WebhookSession;
+
+    fn event(kind: WebhookEventKind, data: Value) -> WebhookEvent {
+        WebhookEvent {
+            kind,
+            session: WebhookSession {
+                id: "sess-1".into(),
+                provider: Some(Provider::Claude),
+                project_path: Some("/work/orbitdock/".into()),
+                name: None,
+                model: Some("claude-opus".into()),
+            },
+            data,
+            occurred_at: "1760700000Z".into(),
+            turn_transcript: None,
+            turn_diff_id: None,
+        }
+    }
+
+    #[test]
+    fn slack_message_links_session_and_threads_replies() {
+        let event = event(
+            WebhookEventKind::TurnCompleted,
+            json!({ "last_message": "Migration finished.\nAll tests pass." }),
+        );
+
+        let first = slack_payload(&event, Some("C123"), None);
+        assert_eq!(first["channel"], "C123");
+        assert!(first.get("thread_ts").is_none());
+        assert_eq!(first["text"], "orbitdock: Turn finished");
+        assert_eq!(
+            first["blocks"][0]["text"]["text"],
+            "*<orbitdock://session/sess-1|orbitdock>*\nTurn finished\n>Migration finished.\n>All tests pass."
+        );
+        assert_eq!(
+            first["blocks"][1]["elements"][0]["text"],
+            "/work/orbitdock/ · claude · claude-opus"
+        );
+
+        let reply = slack_payload(&event, None, Some("1700000000.000100"));
+        assert_eq!(reply["thread_ts"], "1700000000.000100");
+        assert!(reply.get("channel").is_none());
+    }
+
+    #[test]
+    fn headlines_describe_each_event() {
+        let approval = event(
+            WebhookEventKind::ApprovalPending,
+            json!({ "tool_name": "Bash", "command": "rm -rf target" }),
+        );
+        assert_eq!(headline(&approval), "Needs approval: `rm -rf target`");
+
+        let question = event(
+            WebhookEventKind::ApprovalPending,
+            json!({ "question": "Which database?" }),
+        );
+        assert_eq!(headline(&question), "Has a question");
+        assert_eq!(detail(&question).as_deref(), Some("Which database?"));
+
+        let summarized = event(
+            WebhookEventKind::TurnCompleted,
+            json!({
+                "summary": "Fixed the login redirect",
+                "diff_stats": { "files": 2, "additions": 14, "deletions": 3 },
+            }),
+        );
+        assert_eq!(
+            headline(&summarized),
+            "Fixed the login redirect (2 files, +14 -3)"
+        );
+
+        let ended = event(
+            WebhookEventKind::SessionEnded,
+            json!({ "reason": "user_requested" }),
+        );
+        assert_eq!(headline(&ended), "Session ended (user_requested)");
+
+        let budget = event(
+            WebhookEventKind::BudgetThreshold,
+            json!({
+                "project_path": "/work",
+                "spent_usd": 8.004,
+                "budget_usd": 10.0,
+                "threshold": 0.8,
+            }),
+        );
+        assert_eq!(
+            headline(&budget),
+            "Estimated spend $8.00 reached 80% of the $10.00 monthly budget for /work"
+        );
+
+        let context = event(
+            WebhookEventKind::ContextWarning,
+            json!({ "percent": 91.6, "threshold": 90.0 }),
+        );
+        assert_eq!(
+            headline(&context),
+            "Context window 92% full; consider compacting"
+        );
+
+        let payload = discord_payload(&ended, Some("orbitdock"));
+        assert_eq!(payload["thread_name"], "orbitdock");
+        assert_eq!(payload["embeds"][0]["color"], 0x808080);
+        assert_eq!(
+            payload["embeds"][0]["fields"][1]["value"],
+            "orbitdock://session/sess-1"
+        );
+    }
+
+    #[test]
+    fn truncate_respects_char_boundaries() {
+        assert_eq!(truncate("héllo", 2), "hé…");
+        assert_eq!(truncate("hi", 5), "hi");
+    }
+}
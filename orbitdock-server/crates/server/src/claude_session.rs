@@ -7,18 +7,23 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use orbitdock_connector_core::ConnectorEvent;
-use orbitdock_protocol::{McpAuthStatus, McpResource, McpResourceTemplate, McpTool, ServerMessage};
+use orbitdock_protocol::{
+    ErrorCategory, McpAuthStatus, McpResource, McpResourceTemplate, McpTool, Provider,
+    ServerMessage, SessionError, WorkStatus,
+};
 use serde_json::Value;
 use tokio::sync::{broadcast, mpsc};
 use tokio::task::JoinHandle;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::persistence::PersistCommand;
+use crate::reconnect::{self, Reconnect};
 use crate::session::SessionHandle;
 use crate::session_actor::SessionActorHandle;
 use crate::session_command::SessionCommand;
 use crate::session_command_handler::{
-    dispatch_connector_event, handle_session_command, is_turn_ending, spawn_interrupt_watchdog,
+    coalesced_delta_due, dispatch_connector_event, handle_session_command, is_turn_ending,
+    spawn_interrupt_watchdog,
 };
 use crate::state::SessionRegistry;
 
@@ -40,6 +45,7 @@ pub fn start_event_loop(
 ) -> (SessionActorHandle, mpsc::Sender<ClaudeAction>) {
     let (action_tx, mut action_rx) = mpsc::channel::<ClaudeAction>(100);
     let (command_tx, mut command_rx) = mpsc::channel::<SessionCommand>(256);
+    handle.set_command_tx(&command_tx);
 
     let snapshot = handle.snapshot_arc();
     let id = handle.id().to_string();
@@ -49,6 +55,9 @@ pub fn start_event_loop(
 
     let mut event_rx = session.connector.take_event_rx().unwrap();
     let session_id = session.session_id.clone();
+    if let Some(pid) = session.connector.pid() {
+        state.register_connector_pid(&session_id, pid);
+    }
 
     let mut session_handle = handle;
     let persist = persist_tx.clone();
@@ -60,14 +69,53 @@ pub fn start_event_loop(
         // Watchdog channel for synthetic events (interrupt timeout)
         let (watchdog_tx, mut watchdog_rx) = mpsc::channel(4);
         let mut interrupt_watchdog: Option<JoinHandle<()>> = None;
+        let mut reconnect = Reconnect::default();
+        // Set once the session is being ended on purpose, so the CLI exiting
+        // isn't mistaken for a crash.
+        let mut ending = false;
 
         loop {
+            let delta_deadline = session_handle.coalesced_delta_deadline();
             tokio::select! {
                 Some(event) = event_rx.recv() => {
                     if is_turn_ending(&event) {
                         if let Some(h) = interrupt_watchdog.take() { h.abort(); }
                     }
 
+                    // A CLI that died on its own is resumed in place; the
+                    // session only ends once reconnecting gives up.
+                    if let ConnectorEvent::SessionEnded { ref reason } = event {
+                        if !ending && reconnect::is_unexpected_exit(reason) {
+                            if let Some(msg) = reconnect.schedule(&session_id, reason) {
+                                warn!(
+                                    component = "claude_connector",
+                                    event = "claude.reconnect.scheduled",
+                                    session_id = %session_id,
+                                    reason = %reason,
+                                    "Claude CLI exited unexpectedly, scheduling reconnect"
+                                );
+                                session_handle.broadcast(msg);
+                                continue;
+                            }
+                            crate::session_diagnostics::record(
+                                &persist,
+                                &session_id,
+                                "connector_gave_up",
+                                crate::session_diagnostics::connector_error(
+                                    Provider::Claude, "reconnect", reason,
+                                ),
+                            ).await;
+                            dispatch_connector_event(
+                                &session_id,
+                                ConnectorEvent::Error(reconnect::gave_up_error("Claude", reason)),
+                                &mut session_handle,
+                                &persist,
+                            ).await;
+                        }
+                    } else {
+                        reconnect.connector_alive();
+                    }
+
                     // Register hook session IDs as managed threads so the hook
                     // handler doesn't create duplicate passive sessions. On --resume
                     // the CLI creates a new session_id for hooks.
@@ -179,6 +227,10 @@ pub fn start_event_loop(
                         }
                     }
 
+                    if matches!(action, ClaudeAction::EndSession) {
+                        ending = true;
+                    }
+
                     match &action {
                         ClaudeAction::Interrupt => {
                             match session.connector.interrupt().await {
@@ -200,13 +252,25 @@ pub fn start_event_loop(
                                     );
                                     dispatch_connector_event(
                                         &session_id,
-                                        ConnectorEvent::Error(format!("Interrupt failed: {e}")),
+                                        ConnectorEvent::Error(SessionError::new(
+                                            ErrorCategory::Process,
+                                            format!("Interrupt failed: {e}"),
+                                        )),
                                         &mut session_handle,
                                         &persist,
                                     ).await;
                                 }
                             }
                         }
+                        ClaudeAction::RestartProcess { reason } => {
+                            let model = session_handle.snapshot_arc().load_full().model.clone();
+                            if let Some(rx) = restart_process(
+                                &mut session, &mut session_handle, &persist, &state,
+                                model.as_deref(), reason,
+                            ).await {
+                                event_rx = rx;
+                            }
+                        }
                         ClaudeAction::ListMcpTools => {
                             match session.connector.mcp_status().await {
                                 Ok(response) => {
@@ -266,6 +330,28 @@ pub fn start_event_loop(
                                 }
                             }
                         }
+                        ClaudeAction::SendMessage { model, .. } => {
+                            // The CLI can't change model in place; resume the
+                            // conversation in a process running the new one.
+                            if let Some(next) = session.model_switch(model.as_deref()) {
+                                let next = next.to_string();
+                                if let Some(rx) = restart_process(
+                                    &mut session, &mut session_handle, &persist, &state,
+                                    Some(&next), "model_change",
+                                ).await {
+                                    event_rx = rx;
+                                }
+                            }
+                            if let Err(e) = ClaudeSession::handle_action(&session.connector, action).await {
+                                error!(
+                                    component = "claude_connector",
+                                    event = "claude.action.failed",
+                                    session_id = %session_id,
+                                    error = %e,
+                                    "Failed to handle Claude action"
+                                );
+                            }
+                        }
                         _ => {
                             if let Err(e) = ClaudeSession::handle_action(&session.connector, action).await {
                                 error!(
@@ -284,6 +370,53 @@ pub fn start_event_loop(
                     handle_session_command(cmd, &mut session_handle, &persist).await;
                 }
 
+                _ = coalesced_delta_due(delta_deadline), if delta_deadline.is_some() => {
+                    session_handle.flush_coalesced_delta();
+                }
+
+                _ = reconnect::reconnect_due(reconnect.due()), if reconnect.is_pending() && !ending => {
+                    let reason = reconnect.begin();
+                    let model = session_handle.snapshot_arc().load_full().model.clone();
+                    if let Some(rx) = restart_process(
+                        &mut session, &mut session_handle, &persist, &state,
+                        model.as_deref(), "reconnect",
+                    ).await {
+                        event_rx = rx;
+                        session_handle.broadcast(reconnect.succeeded(&session_id));
+                        crate::session_diagnostics::record(
+                            &persist,
+                            &session_id,
+                            "connector_reconnected",
+                            crate::session_diagnostics::connector_error(
+                                Provider::Claude, "reconnect", &reason,
+                            ),
+                        ).await;
+                    } else if let Some(msg) = reconnect.schedule(&session_id, &reason) {
+                        session_handle.broadcast(msg);
+                    } else {
+                        crate::session_diagnostics::record(
+                            &persist,
+                            &session_id,
+                            "connector_gave_up",
+                            crate::session_diagnostics::connector_error(
+                                Provider::Claude, "reconnect", &reason,
+                            ),
+                        ).await;
+                        dispatch_connector_event(
+                            &session_id,
+                            ConnectorEvent::Error(reconnect::gave_up_error("Claude", &reason)),
+                            &mut session_handle,
+                            &persist,
+                        ).await;
+                        dispatch_connector_event(
+                            &session_id,
+                            ConnectorEvent::SessionEnded { reason },
+                            &mut session_handle,
+                            &persist,
+                        ).await;
+                    }
+                }
+
                 else => break,
             }
         }
@@ -292,6 +425,9 @@ pub fn start_event_loop(
             h.abort();
         }
         state.remove_claude_action_tx(&session_id);
+        if let Some(pid) = session.connector.pid() {
+            state.remove_connector_pid(&session_id, pid);
+        }
 
         info!(
             component = "claude_connector",
@@ -304,6 +440,84 @@ pub fn start_event_loop(
     (actor_handle, action_tx)
 }
 
+/// Replace the CLI process with a `--resume` of the same conversation on
+/// `model`, keeping the other spawn options from the session snapshot.
+/// Returns the new process's event receiver, or `None` if the spawn failed
+/// and the old process is still in place.
+async fn restart_process(
+    session: &mut ClaudeSession,
+    session_handle: &mut SessionHandle,
+    persist: &mpsc::Sender<PersistCommand>,
+    state: &Arc<SessionRegistry>,
+    model: Option<&str>,
+    reason: &str,
+) -> Option<mpsc::Receiver<ConnectorEvent>> {
+    let session_id = session.session_id.clone();
+    let snap = session_handle.snapshot_arc().load_full();
+    let resume_id = session.connector.claude_session_id().await;
+    let provider_id = resume_id
+        .as_deref()
+        .and_then(orbitdock_protocol::ProviderSessionId::new);
+    info!(
+        component = "claude_connector",
+        event = "claude.process.restarting",
+        session_id = %session_id,
+        reason = %reason,
+        model = ?model,
+        "Restarting Claude CLI process"
+    );
+    match ClaudeSession::new(
+        session_id.clone(),
+        &snap.project_path,
+        model,
+        provider_id.as_ref(),
+        snap.permission_mode.as_deref(),
+        &snap.allowed_tools,
+        &snap.disallowed_tools,
+        snap.effort.as_deref(),
+        crate::project_config::instructions(&snap.project_path).as_deref(),
+        &state.session_mcp_servers(&session_id),
+    )
+    .await
+    {
+        Ok(mut replacement) => {
+            // Take the receiver first so the old process's EOF doesn't end
+            // the session.
+            let event_rx = replacement.connector.take_event_rx().unwrap();
+            let old = std::mem::replace(session, replacement);
+            let _ = old.connector.shutdown().await;
+            if let Some(pid) = old.connector.pid() {
+                state.remove_connector_pid(&session_id, pid);
+            }
+            if let Some(pid) = session.connector.pid() {
+                state.register_connector_pid(&session_id, pid);
+            }
+            if snap.work_status == WorkStatus::Working {
+                dispatch_connector_event(
+                    &session_id,
+                    ConnectorEvent::TurnAborted {
+                        reason: format!("Claude CLI restarted: {reason}"),
+                    },
+                    session_handle,
+                    persist,
+                )
+                .await;
+            }
+            Some(event_rx)
+        }
+        Err(e) => {
+            error!(
+                component = "claude_connector",
+                event = "claude.process.restart_failed",
+                session_id = %session_id,
+                error = %e,
+                "Failed to restart Claude CLI process"
+            );
+            None
+        }
+    }
+}
+
 /// Parse the mcp_status control response into a McpToolsList event.
 ///
 /// The response from the CLI contains `mcpServers` — an array of objects with
@@ -0,0 +1,470 @@
+//! Passive Claude sessions from transcripts, for machines without hooks.
+//!
+//! Claude Code appends every session to
+//! `<claude home>/projects/<project>/<session_id>.jsonl`, for every home in
+//! [`crate::provider_homes`]. This watches those files
+//! and replays what it reads as the hooks Claude would have sent: a user prompt
+//! becomes `UserPromptSubmit`, an assistant reply that ends the turn (or an
+//! interrupt) becomes `Stop`, and anything else just syncs messages. Sessions
+//! that delivered a real hook in the last few minutes are skipped, so installed
+//! hooks stay the source of truth. Files are read from where they stood at
+//! startup; history is not replayed.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use orbitdock_protocol::ClientMessage;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::hook_handler::handle_transcript_message;
+use crate::state::SessionRegistry;
+
+/// How long after a real hook the watcher keeps its hands off a session.
+const HOOK_QUIET: Duration = Duration::from_secs(300);
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// User "prompts" Claude writes for slash commands and shell escapes.
+const COMMAND_PREFIXES: &[&str] = &["<command-", "<local-command-", "<bash-"];
+const INTERRUPT_PREFIX: &str = "[Request interrupted by user";
+
+#[derive(Debug, PartialEq, Eq)]
+enum EntryKind {
+    Prompt(String),
+    TurnEnd,
+    Activity,
+}
+
+#[derive(Debug)]
+struct Entry {
+    cwd: Option<String>,
+    model: Option<String>,
+    kind: EntryKind,
+}
+
+pub async fn start_claude_transcript_watcher(state: Arc<SessionRegistry>) -> anyhow::Result<()> {
+    if std::env::var("ORBITDOCK_DISABLE_CLAUDE_WATCHER").as_deref() == Ok("1") {
+        info!(
+            component = "claude_transcript_watcher",
+            event = "claude_transcript_watcher.disabled",
+            "Claude transcript watcher disabled by ORBITDOCK_DISABLE_CLAUDE_WATCHER"
+        );
+        return Ok(());
+    }
+
+    let (projects_dirs, missing): (Vec<PathBuf>, Vec<PathBuf>) =
+        crate::provider_homes::claude_project_dirs()
+            .into_iter()
+            .partition(|dir| dir.exists());
+    for dir in &missing {
+        info!(
+            component = "claude_transcript_watcher",
+            event = "claude_transcript_watcher.projects_dir_missing",
+            path = %dir.display(),
+            "Claude projects directory missing"
+        );
+    }
+    if projects_dirs.is_empty() {
+        return Ok(());
+    }
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<PathBuf>();
+    let watched_dirs = projects_dirs.clone();
+    let mut watcher = RecommendedWatcher::new(
+        move |res: Result<notify::Event, notify::Error>| match res {
+            Ok(event) => {
+                if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    return;
+                }
+                for path in event.paths {
+                    if watched_dirs
+                        .iter()
+                        .any(|dir| is_session_transcript(dir, &path))
+                    {
+                        let _ = tx.send(path);
+                    }
+                }
+            }
+            Err(err) => {
+                warn!(
+                    component = "claude_transcript_watcher",
+                    event = "claude_transcript_watcher.fs_event_error",
+                    error = %err,
+                    "Claude transcript watcher event error"
+                );
+            }
+        },
+        notify::Config::default(),
+    )?;
+    let mut offsets = HashMap::new();
+    for dir in &projects_dirs {
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+        let existing = existing_offsets(dir);
+        info!(
+            component = "claude_transcript_watcher",
+            event = "claude_transcript_watcher.started",
+            path = %dir.display(),
+            transcripts = existing.len(),
+            "Claude transcript watcher started"
+        );
+        offsets.extend(existing);
+    }
+
+    while let Some(path) = rx.recv().await {
+        tokio::time::sleep(DEBOUNCE).await;
+        let mut dirty = HashSet::from([path]);
+        while let Ok(path) = rx.try_recv() {
+            dirty.insert(path);
+        }
+        for path in dirty {
+            let offset = offsets.entry(path.clone()).or_insert(0);
+            let lines = match read_new_lines(&path, offset) {
+                Ok(lines) => lines,
+                Err(err) => {
+                    warn!(
+                        component = "claude_transcript_watcher",
+                        event = "claude_transcript_watcher.read_failed",
+                        path = %path.display(),
+                        error = %err,
+                        "Failed reading Claude transcript"
+                    );
+                    continue;
+                }
+            };
+            replay(&state, &path, &lines).await;
+        }
+    }
+
+    drop(watcher);
+    Ok(())
+}
+
+/// `<projects>/<project>/<session_id>.jsonl`, but not subagent transcripts
+/// nested deeper.
+fn is_session_transcript(projects_dir: &Path, path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("jsonl")
+        && path.parent().and_then(Path::parent) == Some(projects_dir)
+}
+
+fn existing_offsets(projects_dir: &Path) -> HashMap<PathBuf, u64> {
+    let mut offsets = HashMap::new();
+    let Ok(projects) = std::fs::read_dir(projects_dir) else {
+        return offsets;
+    };
+    for project in projects.flatten() {
+        let Ok(files) = std::fs::read_dir(project.path()) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let path = file.path();
+            if !is_session_transcript(projects_dir, &path) {
+                continue;
+            }
+            if let Ok(metadata) = file.metadata() {
+                offsets.insert(path, metadata.len());
+            }
+        }
+    }
+    offsets
+}
+
+/// Complete lines appended since `offset`, advancing it past them. A partial
+/// last line is left for the next read.
+fn read_new_lines(path: &Path, offset: &mut u64) -> std::io::Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    if file.metadata()?.len() < *offset {
+        // Truncated or replaced; start over.
+        *offset = 0;
+    }
+    file.seek(SeekFrom::Start(*offset))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+
+    let Some(end) = buf.iter().rposition(|&b| b == b'\n') else {
+        return Ok(Vec::new());
+    };
+    *offset += end as u64 + 1;
+    Ok(String::from_utf8_lossy(&buf[..end])
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+fn parse_entry(line: &str) -> Option<Entry> {
+    let value: Value = serde_json::from_str(line).ok()?;
+    let entry_type = value.get("type").and_then(Value::as_str)?;
+    if !matches!(entry_type, "user" | "assistant" | "system") {
+        return None;
+    }
+    if value.get("isSidechain").and_then(Value::as_bool) == Some(true) {
+        return None;
+    }
+
+    let message = value.get("message");
+    let kind = match entry_type {
+        "user" if value.get("isMeta").and_then(Value::as_bool) != Some(true) => {
+            match message.and_then(|m| m.get("content")).and_then(prompt_text) {
+                Some(text) if text.starts_with(INTERRUPT_PREFIX) => EntryKind::TurnEnd,
+                Some(text) if !COMMAND_PREFIXES.iter().any(|p| text.starts_with(p)) => {
+                    EntryKind::Prompt(text)
+                }
+                _ => EntryKind::Activity,
+            }
+        }
+        "assistant"
+            if message
+                .and_then(|m| m.get("stop_reason"))
+                .and_then(Value::as_str)
+                == Some("end_turn") =>
+        {
+            EntryKind::TurnEnd
+        }
+        _ => EntryKind::Activity,
+    };
+
+    Some(Entry {
+        cwd: value.get("cwd").and_then(Value::as_str).map(str::to_string),
+        model: message
+            .and_then(|m| m.get("model"))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        kind,
+    })
+}
+
+/// The text of a user message, or `None` for tool results.
+fn prompt_text(content: &Value) -> Option<String> {
+    let text = match content {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => {
+            let mut parts = Vec::new();
+            for item in items {
+                match item.get("type").and_then(Value::as_str) {
+                    Some("tool_result") => return None,
+                    Some("text") => {
+                        if let Some(text) = item.get("text").and_then(Value::as_str) {
+                            parts.push(text);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            parts.join("\n")
+        }
+        _ => return None,
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Hook event names to replay for a batch of entries. Every event syncs
+/// messages, so bare activity only needs an event of its own when nothing
+/// else happened.
+fn hook_events(entries: &[Entry]) -> Vec<(&'static str, Option<String>)> {
+    let mut events: Vec<(&'static str, Option<String>)> = entries
+        .iter()
+        .filter_map(|entry| match &entry.kind {
+            EntryKind::Prompt(text) => Some(("UserPromptSubmit", Some(text.clone()))),
+            EntryKind::TurnEnd => Some(("Stop", None)),
+            EntryKind::Activity => None,
+        })
+        .collect();
+    if events.is_empty() && !entries.is_empty() {
+        events.push(("TranscriptActivity", None));
+    }
+    events
+}
+
+async fn replay(state: &Arc<SessionRegistry>, path: &Path, lines: &[String]) {
+    let Some(session_id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        return;
+    };
+    if state.claude_hook_seen_within(session_id, HOOK_QUIET)
+        || state.is_managed_claude_thread(session_id)
+    {
+        return;
+    }
+    let entries: Vec<Entry> = lines.iter().filter_map(|line| parse_entry(line)).collect();
+    let Some(cwd) = entries.iter().rev().find_map(|entry| entry.cwd.clone()) else {
+        return;
+    };
+    let transcript_path = path.to_string_lossy().to_string();
+
+    if state.get_session(session_id).is_none() {
+        debug!(
+            component = "claude_transcript_watcher",
+            event = "claude_transcript_watcher.session_found",
+            session_id = %session_id,
+            "Picked up Claude session from its transcript"
+        );
+        let model = entries.iter().find_map(|entry| entry.model.clone());
+        handle_transcript_message(
+            ClientMessage::ClaudeSessionStart {
+                session_id: session_id.to_string(),
+                cwd: cwd.clone(),
+                model,
+                source: None,
+                context_label: None,
+                transcript_path: Some(transcript_path.clone()),
+                permission_mode: None,
+                agent_type: None,
+                terminal_session_id: None,
+                terminal_app: None,
+            },
+            state,
+        )
+        .await;
+    }
+
+    for (hook_event_name, prompt) in hook_events(&entries) {
+        handle_transcript_message(
+            ClientMessage::ClaudeStatusEvent {
+                session_id: session_id.to_string(),
+                cwd: Some(cwd.clone()),
+                transcript_path: Some(transcript_path.clone()),
+                hook_event_name: hook_event_name.to_string(),
+                notification_type: None,
+                tool_name: None,
+                stop_hook_active: None,
+                prompt,
+                message: None,
+                title: None,
+                trigger: None,
+                custom_instructions: None,
+                permission_mode: None,
+                last_assistant_message: None,
+                teammate_name: None,
+                team_name: None,
+                task_id: None,
+                task_subject: None,
+                task_description: None,
+                config_source: None,
+                config_file_path: None,
+            },
+            state,
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn kind(line: &str) -> Option<EntryKind> {
+        parse_entry(line).map(|entry| entry.kind)
+    }
+
+    #[test]
+    fn classifies_transcript_lines() {
+        assert_eq!(
+            kind(
+                r#"{"type":"user","cwd":"/repo","message":{"role":"user","content":"fix the build"}}"#
+            ),
+            Some(EntryKind::Prompt("fix the build".into()))
+        );
+        assert_eq!(
+            kind(
+                r#"{"type":"user","message":{"content":[{"type":"tool_result","content":"ok"}]}}"#
+            ),
+            Some(EntryKind::Activity)
+        );
+        assert_eq!(
+            kind(r#"{"type":"user","message":{"content":"<command-name>/clear</command-name>"}}"#),
+            Some(EntryKind::Activity)
+        );
+        assert_eq!(
+            kind(
+                r#"{"type":"user","message":{"content":[{"type":"text","text":"[Request interrupted by user]"}]}}"#
+            ),
+            Some(EntryKind::TurnEnd)
+        );
+        assert_eq!(
+            kind(
+                r#"{"type":"assistant","message":{"model":"claude-opus-4","stop_reason":"end_turn"}}"#
+            ),
+            Some(EntryKind::TurnEnd)
+        );
+        assert_eq!(
+            kind(r#"{"type":"assistant","message":{"stop_reason":"tool_use"}}"#),
+            Some(EntryKind::Activity)
+        );
+        assert!(kind(r#"{"type":"user","isSidechain":true,"message":{"content":"hi"}}"#).is_none());
+        assert!(kind(r#"{"type":"summary","summary":"Fixed build"}"#).is_none());
+        assert!(kind("not json").is_none());
+    }
+
+    #[test]
+    fn replays_turns_and_collapses_bare_activity() {
+        let entry = |kind| Entry {
+            cwd: None,
+            model: None,
+            kind,
+        };
+        let turn = [
+            entry(EntryKind::Prompt("hi".into())),
+            entry(EntryKind::Activity),
+            entry(EntryKind::TurnEnd),
+        ];
+        assert_eq!(
+            hook_events(&turn),
+            vec![("UserPromptSubmit", Some("hi".into())), ("Stop", None)]
+        );
+        let busy = [entry(EntryKind::Activity), entry(EntryKind::Activity)];
+        assert_eq!(hook_events(&busy), vec![("TranscriptActivity", None)]);
+        assert!(hook_events(&[]).is_empty());
+    }
+
+    #[test]
+    fn reads_only_complete_new_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        std::fs::write(&path, "{\"a\":1}\n{\"b\":").unwrap();
+
+        let mut offset = 0;
+        assert_eq!(
+            read_new_lines(&path, &mut offset).unwrap(),
+            vec!["{\"a\":1}"]
+        );
+        assert_eq!(offset, 8);
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        writeln!(file, "2}}").unwrap();
+        assert_eq!(
+            read_new_lines(&path, &mut offset).unwrap(),
+            vec!["{\"b\":2}"]
+        );
+        assert!(read_new_lines(&path, &mut offset).unwrap().is_empty());
+
+        std::fs::write(&path, "{}\n").unwrap();
+        assert_eq!(read_new_lines(&path, &mut offset).unwrap(), vec!["{}"]);
+    }
+
+    #[test]
+    fn only_top_level_session_transcripts_are_watched() {
+        let root = Path::new("/home/me/.claude/projects");
+        assert!(is_session_transcript(
+            root,
+            Path::new("/home/me/.claude/projects/-repo/abc.jsonl")
+        ));
+        assert!(!is_session_transcript(
+            root,
+            Path::new("/home/me/.claude/projects/-repo/abc/subagents/agent-1.jsonl")
+        ));
+        assert!(!is_session_transcript(
+            root,
+            Path::new("/home/me/.claude/projects/-repo/notes.txt")
+        ));
+    }
+}
@@ -0,0 +1,53 @@
+//! `orbitdock config path` / `config validate` — inspect the server config file.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+
+use crate::config_file::{self, ServerConfig};
+
+pub fn path() -> anyhow::Result<()> {
+    let Some(path) = config_file::path() else {
+        bail!("could not determine the config file location");
+    };
+    let state = if path.exists() { "" } else { " (not created)" };
+    println!("{}{state}", path.display());
+    Ok(())
+}
+
+pub fn validate(file: Option<&Path>) -> anyhow::Result<()> {
+    let path = match file {
+        Some(file) => file.to_path_buf(),
+        None => config_file::path().unwrap_or_else(|| PathBuf::from(config_file::FILE_NAME)),
+    };
+
+    println!();
+    let Some(config) = ServerConfig::load(&path)? else {
+        println!("  No config file at {}", path.display());
+        println!();
+        return Ok(());
+    };
+
+    let problems = config.problems();
+    if !problems.is_empty() {
+        println!("  {}", path.display());
+        for problem in &problems {
+            println!("    ✗ {problem}");
+        }
+        println!();
+        bail!("{} problem(s) in {}", problems.len(), path.display());
+    }
+
+    println!("  ✓ {} is valid", path.display());
+    let overridden: Vec<&str> = config
+        .env_vars()
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| std::env::var_os(name).is_some() && !config_file::exported_from_file(name))
+        .collect();
+    if !overridden.is_empty() {
+        println!("  Overridden by the environment: {}", overridden.join(", "));
+    }
+    println!();
+    Ok(())
+}
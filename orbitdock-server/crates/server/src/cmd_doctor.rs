@@ -4,7 +4,7 @@
 
 use std::path::Path;
 
-use crate::{auth_tokens, crypto, paths};
+use crate::{auth_tokens, crypto, paths, spool};
 
 enum Status {
     Pass,
@@ -43,6 +43,7 @@ pub fn run(data_dir: &Path) -> anyhow::Result<()> {
         check_hook_transport_config(),
         check_hooks_in_settings(),
         check_spool_queue(),
+        check_spool_dead_letters(),
         check_wal_size(),
         check_port(),
         check_health(),
@@ -360,9 +361,7 @@ fn check_hook_transport_config() -> Check {
 }
 
 fn check_hooks_in_settings() -> Check {
-    let settings_path = dirs::home_dir()
-        .map(|h| h.join(".claude/settings.json"))
-        .unwrap_or_default();
+    let settings_path = crate::provider_homes::default_claude_home().join("settings.json");
 
     if !settings_path.exists() {
         return Check {
@@ -438,50 +437,58 @@ fn check_hooks_in_settings() -> Check {
 }
 
 fn check_spool_queue() -> Check {
-    let spool_dir = paths::spool_dir();
-    if !spool_dir.exists() {
+    let stats = spool::stats();
+    if stats.depth == 0 {
         return Check {
             name: "Spool queue",
             status: Status::Pass,
-            detail: "empty (no spool dir)".to_string(),
+            detail: "empty".to_string(),
         };
     }
 
-    match std::fs::read_dir(&spool_dir) {
-        Ok(entries) => {
-            let count = entries
-                .filter_map(|e| e.ok())
-                .filter(|e| {
-                    e.path()
-                        .extension()
-                        .and_then(|ext| ext.to_str())
-                        .map(|ext| ext == "json")
-                        .unwrap_or(false)
-                })
-                .count();
-
-            if count == 0 {
-                Check {
-                    name: "Spool queue",
-                    status: Status::Pass,
-                    detail: "empty".to_string(),
-                }
-            } else {
-                Check {
-                    name: "Spool queue",
-                    status: Status::Warn,
-                    detail: format!(
-                        "{} queued events (retried by hook-forward and drained on server start)",
-                        count
-                    ),
-                }
-            }
+    let oldest = stats
+        .oldest_age_secs
+        .map(|secs| format!(", oldest {}h old", secs / 3600))
+        .unwrap_or_default();
+    let detail = format!(
+        "{} queued events, {} KB{}",
+        stats.depth,
+        stats.bytes / 1024,
+        oldest
+    );
+    if stats.depth as usize >= spool::MAX_FILES || stats.bytes >= spool::MAX_BYTES {
+        Check {
+            name: "Spool queue",
+            status: Status::Fail,
+            detail: format!("{detail} (at the spool limit; oldest events are being dropped)"),
         }
-        Err(e) => Check {
+    } else {
+        Check {
             name: "Spool queue",
             status: Status::Warn,
-            detail: format!("cannot read: {}", e),
-        },
+            detail: format!("{detail} (retried by hook-forward and drained on server start)"),
+        }
+    }
+}
+
+fn check_spool_dead_letters() -> Check {
+    let count = spool::stats().dead_letters;
+    if count == 0 {
+        Check {
+            name: "Spool dead letters",
+            status: Status::Pass,
+            detail: "none".to_string(),
+        }
+    } else {
+        Check {
+            name: "Spool dead letters",
+            status: Status::Warn,
+            detail: format!(
+                "{} events could not be delivered (see {})",
+                count,
+                paths::spool_dead_letter_dir().display()
+            ),
+        }
     }
 }
 
@@ -566,53 +573,32 @@ fn check_health() -> Check {
 }
 
 fn check_disk_space(data_dir: &Path) -> Check {
-    #[cfg(unix)]
-    {
-        use std::ffi::CString;
-
-        let c_path = match CString::new(data_dir.to_string_lossy().as_bytes()) {
-            Ok(p) => p,
-            Err(_) => {
-                return Check {
-                    name: "Disk space",
-                    status: Status::Warn,
-                    detail: "cannot check".to_string(),
-                };
-            }
+    let Some(free_bytes) = crate::health::available_disk_bytes(data_dir) else {
+        return Check {
+            name: "Disk space",
+            status: Status::Warn,
+            detail: "cannot determine".to_string(),
         };
+    };
 
-        unsafe {
-            let mut stat: libc::statvfs = std::mem::zeroed();
-            if libc::statvfs(c_path.as_ptr(), &mut stat) == 0 {
-                let free_bytes = stat.f_bavail as u64 * stat.f_frsize;
-                let free_gb = free_bytes / (1024 * 1024 * 1024);
-
-                if free_gb < 1 {
-                    return Check {
-                        name: "Disk space",
-                        status: Status::Fail,
-                        detail: format!("{} GB free (critically low)", free_gb),
-                    };
-                } else if free_gb < 5 {
-                    return Check {
-                        name: "Disk space",
-                        status: Status::Warn,
-                        detail: format!("{} GB free (low)", free_gb),
-                    };
-                } else {
-                    return Check {
-                        name: "Disk space",
-                        status: Status::Pass,
-                        detail: format!("{} GB free", free_gb),
-                    };
-                }
-            }
+    let free_gb = free_bytes / (1024 * 1024 * 1024);
+    if free_gb < 1 {
+        Check {
+            name: "Disk space",
+            status: Status::Fail,
+            detail: format!("{} GB free (critically low)", free_gb),
+        }
+    } else if free_gb < 5 {
+        Check {
+            name: "Disk space",
+            status: Status::Warn,
+            detail: format!("{} GB free (low)", free_gb),
+        }
+    } else {
+        Check {
+            name: "Disk space",
+            status: Status::Pass,
+            detail: format!("{} GB free", free_gb),
         }
-    }
-
-    Check {
-        name: "Disk space",
-        status: Status::Warn,
-        detail: "cannot determine".to_string(),
     }
 }
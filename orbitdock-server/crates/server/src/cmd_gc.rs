@@ -0,0 +1,43 @@
+//! `orbitdock gc` — remove images and attachments left behind by deleted
+//! sessions, and by long-ended ones under `[retention] media_max_age_days`.
+
+use anyhow::Context;
+use rusqlite::Connection;
+
+use crate::{media_gc, migration_runner, paths};
+
+pub fn run(dry_run: bool, json: bool) -> anyhow::Result<()> {
+    let db_path = paths::db_path();
+    let mut conn = Connection::open(&db_path)
+        .with_context(|| format!("open database at {}", db_path.display()))?;
+    migration_runner::run_migrations(&mut conn).context("run migrations")?;
+    drop(conn);
+
+    let report = media_gc::collect(&db_path, dry_run)?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "dry_run": dry_run,
+                "directories": report.directories,
+                "files": report.files,
+                "reclaimed_bytes": report.bytes,
+            }))?
+        );
+        return Ok(());
+    }
+
+    if report.directories == 0 {
+        println!("Nothing to collect");
+        return Ok(());
+    }
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    println!(
+        "{verb} {} session media director{} ({} files, {:.1} MB)",
+        report.directories,
+        if report.directories == 1 { "y" } else { "ies" },
+        report.files,
+        report.bytes as f64 / (1024.0 * 1024.0)
+    );
+    Ok(())
+}
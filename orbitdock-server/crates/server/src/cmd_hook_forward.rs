@@ -2,7 +2,20 @@
 //!
 //! Reads a Claude hook JSON payload from stdin, wraps it into an OrbitDock
 //! client message (`type` field), POSTs it to `/api/hook`, and spools on
-//! transient failures. This replaces shell-script transport.
+//! transient failures. This replaces shell-script transport: connection
+//! errors and 5xx responses are retried a few times with backoff before the
+//! event is spooled (as are auth failures), while other events the server
+//! rejects (4xx) are dropped rather than replayed forever. The spooled backlog
+//! is replayed in batches through `/api/hook/batch`; events the server rejects
+//! on replay go to the dead-letter folder, and the spool itself is pruned to
+//! its limits before each write (see `spool`).
+//!
+//! When install-hooks stored a hook secret, each POST carries the timestamp
+//! and HMAC signature headers `/api/hook` checks (see `hook_signature`).
+//!
+//! Codex CLI's `notify` program gets its payload as the last argument
+//! instead, with kebab-case keys and its own `type`; `codex_notify` moves
+//! that to `event` and snake_cases the keys.
 
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -16,7 +29,7 @@ use serde_json::{Map, Value};
 #[cfg(unix)]
 use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
 
-use crate::{crypto, paths};
+use crate::{crypto, hook_signature, paths, spool};
 
 const DEFAULT_SERVER_URL: &str = "http://127.0.0.1:4000";
 
@@ -33,6 +46,8 @@ pub enum HookForwardType {
     ToolEvent,
     #[value(name = "claude_subagent_event")]
     SubagentEvent,
+    #[value(name = "codex_notify")]
+    CodexNotify,
 }
 
 impl HookForwardType {
@@ -43,6 +58,7 @@ impl HookForwardType {
             HookForwardType::StatusEvent => "claude_status_event",
             HookForwardType::ToolEvent => "claude_tool_event",
             HookForwardType::SubagentEvent => "claude_subagent_event",
+            HookForwardType::CodexNotify => "codex_notify_event",
         }
     }
 }
@@ -54,6 +70,8 @@ struct HookTransportConfig {
     auth_token_enc: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     auth_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hook_secret_enc: Option<String>,
 }
 
 impl HookTransportConfig {
@@ -71,6 +89,14 @@ impl HookTransportConfig {
             )
         })
     }
+
+    fn hook_secret(&self) -> Option<String> {
+        normalized_non_empty(
+            self.hook_secret_enc
+                .as_ref()
+                .and_then(|value| crypto::decrypt(value)),
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,23 +104,33 @@ struct PersistedHookTransportConfig {
     server_url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     auth_token_enc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hook_secret_enc: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 struct HookTarget {
     server_url: String,
     auth_token: Option<String>,
+    hook_secret: Option<String>,
 }
 
 pub fn run(
     hook_type: HookForwardType,
+    payload: Option<&str>,
     server_url: Option<&str>,
     auth_token: Option<&str>,
 ) -> anyhow::Result<()> {
-    let mut payload = String::new();
-    std::io::stdin()
-        .read_to_string(&mut payload)
-        .context("read hook payload from stdin")?;
+    let payload = match payload {
+        Some(payload) => payload.to_string(),
+        None => {
+            let mut payload = String::new();
+            std::io::stdin()
+                .read_to_string(&mut payload)
+                .context("read hook payload from stdin")?;
+            payload
+        }
+    };
 
     if payload.trim().is_empty() {
         return Ok(());
@@ -120,6 +156,7 @@ pub fn run(
 pub fn write_transport_config(
     server_url: &str,
     auth_token: Option<&str>,
+    hook_secret: Option<&str>,
 ) -> anyhow::Result<PathBuf> {
     paths::ensure_dirs().context("ensure data directories for hook transport config")?;
     crypto::ensure_key();
@@ -128,9 +165,13 @@ pub fn write_transport_config(
     let encrypted_auth_token = normalized_non_empty(auth_token.map(ToString::to_string))
         .map(|token| encrypt_token_for_storage(&token))
         .transpose()?;
+    let encrypted_hook_secret = normalized_non_empty(hook_secret.map(ToString::to_string))
+        .map(|secret| encrypt_token_for_storage(&secret))
+        .transpose()?;
     let config = PersistedHookTransportConfig {
         server_url: normalized_url,
         auth_token_enc: encrypted_auth_token,
+        hook_secret_enc: encrypted_hook_secret,
     };
     let body = serde_json::to_string_pretty(&config)?;
     write_secure_config(&config_path, &body)?;
@@ -153,11 +194,13 @@ fn resolve_target(
         .unwrap_or_else(|| DEFAULT_SERVER_URL.to_string());
 
     let resolved_token = normalized_non_empty(auth_token.map(ToString::to_string))
-        .or_else(|| persisted.and_then(|cfg| cfg.auth_token()));
+        .or_else(|| persisted.as_ref().and_then(|cfg| cfg.auth_token()));
+    let hook_secret = persisted.and_then(|cfg| cfg.hook_secret());
 
     Ok(HookTarget {
         server_url: resolved_url,
         auth_token: resolved_token,
+        hook_secret,
     })
 }
 
@@ -230,6 +273,10 @@ fn build_hook_body(hook_type: HookForwardType, payload: &str) -> anyhow::Result<
         anyhow::bail!("hook payload must be a JSON object");
     };
 
+    if hook_type == HookForwardType::CodexNotify {
+        normalize_codex_notify_fields(obj);
+    }
+
     obj.insert(
         "type".to_string(),
         Value::String(hook_type.as_wire_type().to_string()),
@@ -242,6 +289,17 @@ fn build_hook_body(hook_type: HookForwardType, payload: &str) -> anyhow::Result<
     Ok(serde_json::to_string(&value)?)
 }
 
+fn normalize_codex_notify_fields(obj: &mut Map<String, Value>) {
+    let fields = std::mem::take(obj);
+    for (key, value) in fields {
+        let key = match key.as_str() {
+            "type" => "event".to_string(),
+            _ => key.replace('-', "_"),
+        };
+        obj.insert(key, value);
+    }
+}
+
 fn inject_session_start_terminal_fields(obj: &mut Map<String, Value>) {
     if !obj.contains_key("terminal_session_id") {
         obj.insert(
@@ -266,6 +324,23 @@ fn inject_session_start_terminal_fields(obj: &mut Map<String, Value>) {
     }
 }
 
+/// Attempts at delivering the current event before it is spooled.
+const POST_ATTEMPTS: u32 = 3;
+const RETRY_BACKOFF: Duration = Duration::from_millis(250);
+/// Spooled events replayed per `/api/hook/batch` request.
+const REPLAY_BATCH: usize = 100;
+const HOOK_PATH: &str = "/api/hook";
+const BATCH_PATH: &str = "/api/hook/batch";
+
+#[derive(Debug)]
+enum PostError {
+    /// Worth retrying: the server is unreachable, timed out, failed, or
+    /// refused the token.
+    Transient,
+    /// The server refused the event; resending it cannot succeed.
+    Rejected,
+}
+
 async fn forward_with_spool(target: &HookTarget, current_body: &str) -> anyhow::Result<()> {
     paths::ensure_dirs().context("ensure hook spool directory")?;
     let spool_dir = paths::spool_dir();
@@ -274,43 +349,84 @@ async fn forward_with_spool(target: &HookTarget, current_body: &str) -> anyhow::
         .timeout(Duration::from_secs(5))
         .build()?;
 
-    let mut queued = load_spool_files(&spool_dir);
-    queued.sort_by(|a, b| a.0.cmp(&b.0));
-
-    for (path, body) in queued {
-        if post_hook(&client, target, &body).await.is_err() {
-            spool_event(&spool_dir, current_body)?;
-            return Ok(());
-        }
-        let _ = std::fs::remove_file(path);
-    }
-
-    if post_hook(&client, target, current_body).await.is_err() {
+    if replay_spool(&client, target, &spool_dir).await.is_err() {
+        // Still down: queue behind the backlog rather than retrying.
         spool_event(&spool_dir, current_body)?;
+        return Ok(());
     }
 
-    Ok(())
+    let mut attempt = 1;
+    loop {
+        match post_hook(&client, target, HOOK_PATH, current_body).await {
+            Err(PostError::Transient) if attempt < POST_ATTEMPTS => {
+                tokio::time::sleep(RETRY_BACKOFF * attempt).await;
+                attempt += 1;
+            }
+            Err(PostError::Transient) => {
+                spool_event(&spool_dir, current_body)?;
+                return Ok(());
+            }
+            Ok(()) | Err(PostError::Rejected) => return Ok(()),
+        }
+    }
 }
 
-fn load_spool_files(spool_dir: &Path) -> Vec<(PathBuf, String)> {
-    let entries = match std::fs::read_dir(spool_dir) {
-        Ok(entries) => entries,
-        Err(_) => return Vec::new(),
-    };
+/// Send the backlog oldest first, `REPLAY_BATCH` events per request. Fails
+/// only on a transient error, leaving the rest queued.
+async fn replay_spool(
+    client: &reqwest::Client,
+    target: &HookTarget,
+    spool_dir: &Path,
+) -> Result<(), PostError> {
+    let queued = spool::queued(spool_dir);
+    for chunk in queued.chunks(REPLAY_BATCH) {
+        let events: Vec<(&Path, String)> = chunk
+            .iter()
+            .filter_map(|entry| {
+                let body = std::fs::read_to_string(&entry.path).ok()?;
+                Some((entry.path.as_path(), body))
+            })
+            .collect();
+        let batch = format!(
+            "[{}]",
+            events
+                .iter()
+                .map(|(_, body)| body.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        );
 
-    entries
-        .filter_map(|entry| entry.ok().map(|e| e.path()))
-        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
-        .filter_map(|path| std::fs::read_to_string(&path).ok().map(|body| (path, body)))
-        .collect()
+        match post_hook(client, target, BATCH_PATH, &batch).await {
+            Ok(()) => {
+                for (path, _) in &events {
+                    let _ = std::fs::remove_file(path);
+                }
+            }
+            Err(PostError::Transient) => return Err(PostError::Transient),
+            // One bad event rejects the whole batch, as does a server without
+            // the batch endpoint; sort it out one event at a time.
+            Err(PostError::Rejected) => {
+                for (path, body) in &events {
+                    match post_hook(client, target, HOOK_PATH, body).await {
+                        Ok(()) => {
+                            let _ = std::fs::remove_file(path);
+                        }
+                        Err(PostError::Rejected) => {
+                            let _ = spool::dead_letter(path);
+                        }
+                        Err(PostError::Transient) => return Err(PostError::Transient),
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
 }
 
 fn spool_event(spool_dir: &Path, body: &str) -> anyhow::Result<()> {
     std::fs::create_dir_all(spool_dir)?;
-    let ts = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis();
+    let ts = spool::now_ms();
+    spool::prune(spool_dir, ts);
     let pid = std::process::id();
     let filename = format!("{ts}-{pid}.json");
 
@@ -339,9 +455,10 @@ fn spool_event(spool_dir: &Path, body: &str) -> anyhow::Result<()> {
 async fn post_hook(
     client: &reqwest::Client,
     target: &HookTarget,
+    path: &str,
     body: &str,
-) -> anyhow::Result<()> {
-    let url = format!("{}/api/hook", target.server_url.trim_end_matches('/'));
+) -> Result<(), PostError> {
+    let url = format!("{}{path}", target.server_url.trim_end_matches('/'));
     let mut request = client
         .post(url)
         .header("Content-Type", "application/json")
@@ -350,17 +467,43 @@ async fn post_hook(
     if let Some(token) = target.auth_token.as_deref() {
         request = request.bearer_auth(token);
     }
+    if let Some(secret) = target.hook_secret.as_deref() {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        request = request
+            .header(hook_signature::TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                hook_signature::SIGNATURE_HEADER,
+                hook_signature::sign(secret, timestamp, body),
+            );
+    }
 
-    let response = request.send().await?;
-    if !response.status().is_success() {
-        anyhow::bail!("hook request failed with status {}", response.status());
+    let response = request.send().await.map_err(|_| PostError::Transient)?;
+    classify_status(response.status())
+}
+
+fn classify_status(status: reqwest::StatusCode) -> Result<(), PostError> {
+    // Spooling would replay the flood the server is shedding; drop instead.
+    if status.is_success() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Ok(());
     }
-    Ok(())
+    // A bad token gets fixed; keep those events until it is.
+    if status.is_server_error()
+        || matches!(
+            status,
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+        )
+    {
+        return Err(PostError::Transient);
+    }
+    Err(PostError::Rejected)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{build_hook_body, HookForwardType};
+    use super::{build_hook_body, classify_status, HookForwardType, PostError};
 
     #[test]
     fn build_hook_body_injects_type() {
@@ -373,6 +516,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn build_hook_body_normalizes_codex_notify_payload() {
+        let payload = r#"{
+          "type":"agent-turn-complete",
+          "thread-id":"019a-thread",
+          "turn-id":"12345",
+          "cwd":"/tmp/project",
+          "input-messages":["Rename foo to bar"],
+          "last-assistant-message":"Renamed foo to bar."
+        }"#;
+        let body = build_hook_body(HookForwardType::CodexNotify, payload).expect("build hook body");
+        let msg: orbitdock_protocol::ClientMessage =
+            serde_json::from_str(&body).expect("parse client message");
+        match msg {
+            orbitdock_protocol::ClientMessage::CodexNotifyEvent {
+                event,
+                thread_id,
+                input_messages,
+                last_assistant_message,
+                ..
+            } => {
+                assert_eq!(event, "agent-turn-complete");
+                assert_eq!(thread_id.as_deref(), Some("019a-thread"));
+                assert_eq!(input_messages, vec!["Rename foo to bar"]);
+                assert_eq!(
+                    last_assistant_message.as_deref(),
+                    Some("Renamed foo to bar.")
+                );
+            }
+            other => panic!("unexpected message variant: {other:?}"),
+        }
+    }
+
     #[test]
     fn build_hook_body_keeps_existing_terminal_fields() {
         let payload = r#"{
@@ -393,4 +569,28 @@ mod tests {
             Some("my-term")
         );
     }
+
+    #[test]
+    fn retries_transient_failures_and_drops_rejections() {
+        use reqwest::StatusCode;
+
+        assert!(classify_status(StatusCode::NO_CONTENT).is_ok());
+        assert!(classify_status(StatusCode::TOO_MANY_REQUESTS).is_ok());
+        assert!(matches!(
+            classify_status(StatusCode::BAD_GATEWAY),
+            Err(PostError::Transient)
+        ));
+        assert!(matches!(
+            classify_status(StatusCode::BAD_REQUEST),
+            Err(PostError::Rejected)
+        ));
+        assert!(matches!(
+            classify_status(StatusCode::UNAUTHORIZED),
+            Err(PostError::Transient)
+        ));
+        assert!(matches!(
+            classify_status(StatusCode::NOT_FOUND),
+            Err(PostError::Rejected)
+        ));
+    }
 }
@@ -5,6 +5,7 @@
 
 use std::path::Path;
 
+use crate::listen;
 use crate::migration_runner;
 use crate::paths;
 
@@ -30,7 +31,7 @@ pub fn run(data_dir: &Path, _server_url: &str) -> anyhow::Result<()> {
     println!("  Database initialized at {}", db_path.display());
 
     // 3. Detect Tailscale
-    let ts_ip = detect_tailscale_ip();
+    let ts_ip = listen::tailscale_ip();
 
     if !installer_mode {
         println!();
@@ -38,7 +39,7 @@ pub fn run(data_dir: &Path, _server_url: &str) -> anyhow::Result<()> {
             println!("  Tailscale detected! Your IP: {}", ip);
             println!("  For remote access (secure by default):");
             println!("    orbitdock generate-token");
-            println!("    orbitdock start --bind 0.0.0.0:4000");
+            println!("    orbitdock start --tailscale");
             println!();
         }
 
@@ -55,25 +56,3 @@ pub fn run(data_dir: &Path, _server_url: &str) -> anyhow::Result<()> {
 fn installer_mode() -> bool {
     std::env::var_os("ORBITDOCK_INSTALLER_MODE").is_some()
 }
-
-fn detect_tailscale_ip() -> Option<String> {
-    let output = std::process::Command::new("tailscale")
-        .args(["status", "--json"])
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
-
-    let json: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
-    let self_node = json.get("Self")?;
-    let addrs = self_node.get("TailscaleIPs")?.as_array()?;
-    // Prefer IPv4
-    addrs
-        .iter()
-        .find(|a| a.as_str().map(|s| !s.contains(':')).unwrap_or(false))
-        .or_else(|| addrs.first())
-        .and_then(|a| a.as_str())
-        .map(|s| s.to_string())
-}
@@ -1,8 +1,13 @@
-//! `orbitdock install-hooks` — configure Claude Code hooks.
+//! `orbitdock install-hooks` — configure Claude Code hooks and Codex notify.
 //!
-//! Safely merges OrbitDock hook entries into `~/.claude/settings.json`.
-//! Hooks invoke `orbitdock hook-forward ...` directly; no shell script
-//! install is required.
+//! Safely merges OrbitDock hook entries into `~/.claude/settings.json` and
+//! sets the top-level `notify` program in `~/.codex/config.toml` (or under
+//! `$CLAUDE_CONFIG_DIR` / `$CODEX_HOME` when set). Both invoke
+//! `orbitdock hook-forward ...` directly; no shell script install is required.
+//! A `notify` program that isn't OrbitDock's is left alone.
+//!
+//! For a local server the hook secret defaults to the server's own (generated
+//! on first install), so hook requests are signed without any setup.
 
 use std::io::{Read, Write};
 #[cfg(unix)]
@@ -11,7 +16,7 @@ use std::path::{Path, PathBuf};
 #[cfg(unix)]
 use std::{fs::OpenOptions, os::fd::AsRawFd};
 
-use crate::{cmd_hook_forward, paths};
+use crate::{cmd_hook_forward, hook_signature, paths, provider_homes};
 
 /// All Claude Code hook types we register for.
 const HOOK_TYPES: &[(&str, &str)] = &[
@@ -39,19 +44,23 @@ pub fn run(
     settings_path: Option<&Path>,
     server_url: Option<&str>,
     auth_token: Option<&str>,
+    hook_secret: Option<&str>,
 ) -> anyhow::Result<()> {
     let installer_mode = installer_mode();
-    let settings_file = settings_path.map(PathBuf::from).unwrap_or_else(|| {
-        dirs::home_dir()
-            .expect("HOME not found")
-            .join(".claude/settings.json")
-    });
+    let settings_file = settings_path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| provider_homes::default_claude_home().join("settings.json"));
 
     let target_url = server_url.unwrap_or("http://127.0.0.1:4000");
     let resolved_auth_token = resolve_auth_token(target_url, auth_token)?;
-    let transport_config_path =
-        cmd_hook_forward::write_transport_config(target_url, resolved_auth_token.as_deref())?;
-    let hook_binary = quote_for_shell(&resolve_hook_binary_path());
+    let resolved_hook_secret = resolve_hook_secret(target_url, hook_secret)?;
+    let transport_config_path = cmd_hook_forward::write_transport_config(
+        target_url,
+        resolved_auth_token.as_deref(),
+        resolved_hook_secret.as_deref(),
+    )?;
+    let hook_binary_path = resolve_hook_binary_path();
+    let hook_binary = quote_for_shell(&hook_binary_path);
 
     // Read existing settings or start with empty object
     let existing = if settings_file.exists() {
@@ -198,7 +207,14 @@ pub fn run(
         None => {}
     }
     if !installer_mode {
-        println!("  Hook forward binary: {}", resolve_hook_binary_path());
+        match resolved_hook_secret {
+            Some(_) => println!("  Hook signing: enabled"),
+            None => println!("  Hook signing: off (pass --hook-secret to sign requests)"),
+        }
+    }
+    install_codex_notify(&hook_binary_path, installer_mode)?;
+    if !installer_mode {
+        println!("  Hook forward binary: {}", hook_binary_path);
         println!("  Spool directory: {}", paths::spool_dir().display());
     }
     println!();
@@ -206,6 +222,104 @@ pub fn run(
     Ok(())
 }
 
+/// Point Codex's `notify` program at `hook-forward codex_notify`.
+fn install_codex_notify(hook_binary_path: &str, installer_mode: bool) -> anyhow::Result<()> {
+    let config_file = provider_homes::default_codex_home().join("config.toml");
+    let existing = if config_file.exists() {
+        std::fs::read_to_string(&config_file)?
+    } else {
+        String::new()
+    };
+
+    let notify = [
+        hook_binary_path.to_string(),
+        "hook-forward".to_string(),
+        "codex_notify".to_string(),
+    ];
+    let updated = match merge_codex_notify(&existing, &notify) {
+        Ok(updated) => updated,
+        Err(reason) => {
+            println!("  Codex notify: skipped ({reason})");
+            return Ok(());
+        }
+    };
+    if updated == existing {
+        if !installer_mode {
+            println!("  Codex notify: already configured");
+        }
+        return Ok(());
+    }
+
+    if config_file.exists() {
+        let backup = config_file.with_extension("toml.bak");
+        std::fs::copy(&config_file, &backup)?;
+        if !installer_mode {
+            println!(
+                "  Backed up {} → {}",
+                config_file.display(),
+                backup.display()
+            );
+        }
+    }
+    if let Some(parent) = config_file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&config_file, updated)?;
+    println!("  Codex notify ready in {}", config_file.display());
+    Ok(())
+}
+
+/// Set the top-level `notify` key in Codex `config.toml` text, keeping the
+/// rest of the file (comments included) as written. Errors when the file
+/// doesn't parse or already notifies some other program.
+fn merge_codex_notify(content: &str, notify: &[String]) -> Result<String, String> {
+    let table: toml::Table = content
+        .parse()
+        .map_err(|_| "config.toml does not parse".to_string())?;
+    let line = format!(
+        "notify = {}",
+        toml::Value::Array(notify.iter().cloned().map(toml::Value::String).collect())
+    );
+
+    let Some(current) = table.get("notify") else {
+        return Ok(format!("{line}\n{content}"));
+    };
+    let ours = current.as_array().is_some_and(|argv| {
+        argv.iter()
+            .filter_map(|arg| arg.as_str())
+            .any(|arg| arg == "hook-forward" || arg.contains("orbitdock"))
+    });
+    if !ours {
+        return Err("config.toml already sets another notify program".to_string());
+    }
+
+    // Replace the existing assignment, which may span several lines.
+    let mut lines: Vec<&str> = content.lines().collect();
+    let start = lines
+        .iter()
+        .position(|l| {
+            l.trim_start()
+                .strip_prefix("notify")
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        })
+        .ok_or_else(|| "could not locate notify in config.toml".to_string())?;
+    let mut depth = 0i32;
+    let mut end = start;
+    for (idx, l) in lines.iter().enumerate().skip(start) {
+        depth += l.matches('[').count() as i32 - l.matches(']').count() as i32;
+        end = idx;
+        if depth <= 0 {
+            break;
+        }
+    }
+    lines.splice(start..=end, [line.as_str()]);
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    Ok(updated)
+}
+
 fn installer_mode() -> bool {
     std::env::var_os("ORBITDOCK_INSTALLER_MODE").is_some()
 }
@@ -223,6 +337,24 @@ fn resolve_auth_token(
     Ok(prompted_token)
 }
 
+/// The explicit secret, else the local server's own (created if missing).
+/// Remote servers' secrets can't be discovered, so those stay unsigned.
+fn resolve_hook_secret(
+    server_url: &str,
+    hook_secret: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    if let Some(secret) = normalized_non_empty(hook_secret) {
+        return Ok(Some(secret));
+    }
+    if !is_local_server_url(server_url) {
+        return Ok(None);
+    }
+    match hook_signature::server_secret() {
+        Some(secret) => Ok(Some(secret)),
+        None => hook_signature::ensure_local_secret().map(Some),
+    }
+}
+
 fn should_prompt_for_auth_token(server_url: &str) -> bool {
     !is_local_server_url(server_url)
 }
@@ -346,3 +478,39 @@ fn resolve_hook_binary_path() -> String {
 fn quote_for_shell(path: &str) -> String {
     format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notify() -> Vec<String> {
+        ["/usr/local/bin/orbitdock", "hook-forward", "codex_notify"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    #[test]
+    fn codex_notify_is_added_above_tables_and_replaced_in_place() {
+        let content = "# my config\nmodel = \"o3\"\n\n[profiles.fast]\nmodel = \"o4-mini\"\n";
+        let added = merge_codex_notify(content, &notify()).unwrap();
+        assert!(added.starts_with(
+            "notify = [\"/usr/local/bin/orbitdock\", \"hook-forward\", \"codex_notify\"]\n# my config"
+        ));
+        assert!(added.parse::<toml::Table>().is_ok());
+
+        let stale = "model = \"o3\"\nnotify = [\n  \"/old/orbitdock\",\n  \"hook-forward\",\n  \"codex_notify\",\n]\n[tui]\n";
+        let replaced = merge_codex_notify(stale, &notify()).unwrap();
+        assert_eq!(
+            replaced,
+            "model = \"o3\"\nnotify = [\"/usr/local/bin/orbitdock\", \"hook-forward\", \"codex_notify\"]\n[tui]\n"
+        );
+        assert_eq!(merge_codex_notify(&replaced, &notify()).unwrap(), replaced);
+    }
+
+    #[test]
+    fn codex_notify_leaves_other_programs_alone() {
+        let content = "notify = [\"notify-send\", \"codex\"]\n";
+        assert!(merge_codex_notify(content, &notify()).is_err());
+        assert!(merge_codex_notify("not = [toml", &notify()).is_err());
+    }
+}
@@ -509,6 +509,43 @@ fn install_systemd(
     Ok(())
 }
 
+/// Restart the installed service so it picks up a new binary. Returns
+/// `Ok(false)` when no service is installed.
+pub fn restart_installed_service() -> anyhow::Result<bool> {
+    let Some(home) = dirs::home_dir() else {
+        return Ok(false);
+    };
+
+    let output = if cfg!(target_os = "macos") {
+        if !home
+            .join("Library/LaunchAgents/com.orbitdock.server.plist")
+            .exists()
+        {
+            return Ok(false);
+        }
+        let target = format!("gui/{}/com.orbitdock.server", unsafe { libc::getuid() });
+        std::process::Command::new("launchctl")
+            .args(["kickstart", "-k", &target])
+            .output()?
+    } else {
+        if !home
+            .join(".config/systemd/user/orbitdock-server.service")
+            .exists()
+        {
+            return Ok(false);
+        }
+        std::process::Command::new("systemctl")
+            .args(["--user", "restart", "orbitdock-server.service"])
+            .output()?
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("service restart failed: {}", stderr.trim());
+    }
+    Ok(true)
+}
+
 fn escape_systemd_env(value: &str) -> String {
     value.replace('\\', "\\\\").replace('"', "\\\"")
 }
@@ -0,0 +1,192 @@
+//! `orbitdock logs` — print recent server log lines.
+//!
+//! With `--tail`, keeps following `server.log`. The open handle is held across
+//! polls, so when the server rotates the file out from under us we drain what
+//! was written to the old file before switching to the new one.
+
+use std::fs::{File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::log_rotation::ACTIVE_LOG_NAME;
+use crate::paths;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const READ_CHUNK: u64 = 64 * 1024;
+
+pub fn run(lines: usize, tail: bool) -> anyhow::Result<()> {
+    let path = paths::log_dir().join(ACTIVE_LOG_NAME);
+    if !path.exists() && !tail {
+        anyhow::bail!("no server log at {}", path.display());
+    }
+
+    let mut follower = Follower {
+        path,
+        file: None,
+        offset: 0,
+    };
+    if let Ok(mut file) = File::open(&follower.path) {
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        out.write_all(&last_lines(&mut file, lines)?)?;
+        follower.offset = file.metadata()?.len();
+        follower.file = Some(file);
+    }
+
+    if tail {
+        let stdout = io::stdout();
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let mut out = stdout.lock();
+            follower.poll(&mut out)?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// The final `count` lines of `file`, read backwards in chunks.
+fn last_lines(file: &mut File, count: usize) -> io::Result<Vec<u8>> {
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    let len = file.metadata()?.len();
+    let mut start = len;
+    let mut buf = Vec::new();
+
+    while start > 0 {
+        let chunk = READ_CHUNK.min(start);
+        start -= chunk;
+        file.seek(SeekFrom::Start(start))?;
+        let mut piece = vec![0; chunk as usize];
+        file.read_exact(&mut piece)?;
+        piece.extend_from_slice(&buf);
+        buf = piece;
+
+        // One extra newline: the file normally ends with one.
+        if buf.iter().filter(|b| **b == b'\n').count() > count {
+            break;
+        }
+    }
+
+    let body = buf.strip_suffix(b"\n").unwrap_or(&buf);
+    let start = body
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, b)| **b == b'\n')
+        .nth(count - 1)
+        .map(|(i, _)| i + 1)
+        .unwrap_or(0);
+    Ok(buf[start..].to_vec())
+}
+
+struct Follower {
+    path: PathBuf,
+    /// The file being followed; still the old one for a poll after rotation.
+    file: Option<File>,
+    offset: u64,
+}
+
+impl Follower {
+    /// Copy anything new to `out`, moving to a fresh `server.log` once the
+    /// one we hold has been rotated away and fully read.
+    fn poll(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            if file.metadata()?.len() < self.offset {
+                // Truncated in place: start over from the top.
+                self.offset = 0;
+            }
+            self.offset += copy_from(file, self.offset, out)?;
+        }
+
+        let Ok(current) = File::open(&self.path) else {
+            return Ok(());
+        };
+        let rotated = match &self.file {
+            Some(held) => !same_file(&held.metadata()?, &current.metadata()?),
+            None => true,
+        };
+        if rotated {
+            let mut current = current;
+            self.offset = copy_from(&mut current, 0, out)?;
+            self.file = Some(current);
+        }
+        Ok(())
+    }
+}
+
+/// Copy `file` from `offset` to its current end, returning the bytes copied.
+fn copy_from(file: &mut File, offset: u64, out: &mut impl Write) -> io::Result<u64> {
+    file.seek(SeekFrom::Start(offset))?;
+    io::copy(file, out)
+}
+
+#[cfg(unix)]
+fn same_file(held: &Metadata, current: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    held.dev() == current.dev() && held.ino() == current.ino()
+}
+
+/// Without inodes, a path that got shorter than what we hold was replaced.
+#[cfg(not(unix))]
+fn same_file(held: &Metadata, current: &Metadata) -> bool {
+    current.len() >= held.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tail_of(contents: &str, count: usize) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("server.log");
+        std::fs::write(&path, contents).unwrap();
+        let mut file = File::open(&path).unwrap();
+        String::from_utf8(last_lines(&mut file, count).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn last_lines_returns_trailing_lines() {
+        assert_eq!(tail_of("a\nb\nc\n", 2), "b\nc\n");
+        assert_eq!(tail_of("a\nb\nc", 2), "b\nc");
+        assert_eq!(tail_of("a\nb\n", 10), "a\nb\n");
+        assert_eq!(tail_of("a\nb\n", 0), "");
+        assert_eq!(tail_of("", 5), "");
+    }
+
+    #[test]
+    fn follower_keeps_lines_written_just_before_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("server.log");
+        std::fs::write(&path, "old\n").unwrap();
+        let mut follower = Follower {
+            path: path.clone(),
+            file: Some(File::open(&path).unwrap()),
+            offset: 4,
+        };
+
+        let mut append = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        append.write_all(b"late\n").unwrap();
+        std::fs::rename(&path, dir.path().join("server.2026-10-17.1.log")).unwrap();
+        std::fs::write(&path, "new\n").unwrap();
+
+        let mut out = Vec::new();
+        follower.poll(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "late\nnew\n");
+
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"next\n")
+            .unwrap();
+        let mut out = Vec::new();
+        follower.poll(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "next\n");
+    }
+}
@@ -0,0 +1,61 @@
+//! `orbitdock add-peer` / `list-peers` / `remove-peer` — manage the peer
+//! servers whose sessions this one relays.
+
+use crate::federation;
+
+pub fn add(name: &str, url: &str, token: Option<&str>) -> anyhow::Result<()> {
+    let peer = federation::add_peer(name, url, token)?;
+
+    println!();
+    println!("  Saved peer {}", peer.name);
+    println!("    url: {}", peer.url);
+    if peer.token.is_none() {
+        println!("    note: no token; the peer must allow unauthenticated access");
+    }
+    println!(
+        "    sessions appear as {} on the next reload (within 30s)",
+        federation::namespaced(&peer.name, "<id>")
+    );
+    println!();
+    Ok(())
+}
+
+pub fn list() -> anyhow::Result<()> {
+    let peers = federation::list_peers()?;
+
+    println!();
+    println!("  Peers");
+    println!("  ─────");
+    println!();
+
+    if peers.is_empty() {
+        println!("  No peers configured.");
+        println!();
+        return Ok(());
+    }
+
+    for peer in peers {
+        let status = if peer.enabled { "enabled" } else { "disabled" };
+        println!("  {}  [{}]  {}", peer.name, status, peer.url);
+        println!(
+            "    token: {}",
+            if peer.token.is_some() { "yes" } else { "no" }
+        );
+        println!("    created: {}", peer.created_at);
+        println!();
+    }
+
+    Ok(())
+}
+
+pub fn remove(name: &str) -> anyhow::Result<()> {
+    let removed = federation::remove_peer(name)?;
+    println!();
+    if removed {
+        println!("  Removed peer {}", name.trim());
+    } else {
+        println!("  Peer {} was not found", name.trim());
+    }
+    println!();
+    Ok(())
+}
@@ -5,7 +5,7 @@ use std::io::{self, BufRead, Write};
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 
-use crate::{auth_tokens, cmd_install_hooks, cmd_install_service, cmd_status};
+use crate::{auth_tokens, cmd_install_hooks, cmd_install_service, cmd_status, listen};
 
 const LOCAL_BIND_ADDR: &str = "127.0.0.1:4000";
 const REMOTE_BIND_ADDR: &str = "0.0.0.0:4000";
@@ -127,8 +127,12 @@ pub fn run(data_dir: &Path) -> anyhow::Result<()> {
         println!();
         println!("  Configuring local Claude Code hooks for http://127.0.0.1:4000...");
         std::env::set_var("ORBITDOCK_INSTALLER_MODE", "1");
-        let hook_result =
-            cmd_install_hooks::run(None, Some("http://127.0.0.1:4000"), Some(token.as_str()));
+        let hook_result = cmd_install_hooks::run(
+            None,
+            Some("http://127.0.0.1:4000"),
+            Some(token.as_str()),
+            None,
+        );
         std::env::remove_var("ORBITDOCK_INSTALLER_MODE");
         hook_result?;
     } else {
@@ -316,25 +320,7 @@ fn strip_string_tag(line: &str) -> Option<&str> {
 }
 
 fn detect_tailscale_url() -> Option<String> {
-    let output = std::process::Command::new("tailscale")
-        .args(["status", "--json"])
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
-
-    let json: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
-    let self_node = json.get("Self")?;
-    let addrs = self_node.get("TailscaleIPs")?.as_array()?;
-    let ip = addrs
-        .iter()
-        .find(|a| a.as_str().map(|s| !s.contains(':')).unwrap_or(false))
-        .or_else(|| addrs.first())?
-        .as_str()?;
-
-    Some(format!("http://{}:4000", ip))
+    listen::tailscale_ip().map(|ip| format!("http://{}", SocketAddr::new(ip, 4000)))
 }
 
 fn print_summary(
@@ -84,7 +84,7 @@ pub fn run(data_dir: &Path, opts: SetupOptions) -> anyhow::Result<()> {
         } else {
             None
         };
-        cmd_install_hooks::run(None, hook_url, hook_auth)?;
+        cmd_install_hooks::run(None, hook_url, hook_auth, None)?;
     } else {
         println!("  Skipping hook installation.");
     }
@@ -0,0 +1,507 @@
+//! `orbitdock simulate` — load-test the session pipeline without a real agent.
+//!
+//! Runs headless in this process against a throwaway data directory, so it
+//! never touches the real database or a running server. Each synthetic
+//! session is a real session actor fed by a mock connector: turns of a user
+//! prompt, a streamed assistant reply, and a token update, sent as connector
+//! events at `--rate` events per second. Everything downstream is the real
+//! thing: transitions, subscriber fan-out, the event journal, and the
+//! persistence writer.
+//!
+//! Reported: broadcast latency (connector event to subscriber), persistence
+//! queue depth and the time to drain it at the end, and resident memory.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use orbitdock_connector_core::ConnectorEvent;
+use orbitdock_protocol::{
+    Message, MessageType, Provider, ServerMessage, TokenUsage, TokenUsageSnapshotKind,
+};
+use rusqlite::Connection;
+use serde::Serialize;
+use tokio::sync::{mpsc, Notify};
+
+use crate::persistence::{create_persistence_channel, PersistCommand, PersistenceWriter};
+use crate::session::SessionHandle;
+use crate::session_command::SessionCommand;
+use crate::state::SessionRegistry;
+use crate::{event_journal, migration_runner, paths, resource_monitor};
+
+/// Streamed updates per assistant reply.
+const STREAM_UPDATES: usize = 8;
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+/// Prefix the mock connector stamps on message content: microseconds since
+/// the run started, read back by the subscriber to time the broadcast.
+const STAMP_PREFIX: &str = "sim@";
+
+pub struct SimulateOptions {
+    pub sessions: usize,
+    /// Connector events per second, per session.
+    pub rate: f64,
+    pub duration: Duration,
+    /// Bytes of assistant text per streamed update.
+    pub message_bytes: usize,
+    pub keep_data: bool,
+    pub json: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct SimulateReport {
+    sessions: usize,
+    rate_per_session: f64,
+    duration_secs: f64,
+    events_sent: u64,
+    broadcasts_received: u64,
+    latency_ms: LatencyStats,
+    persist_queue_max: usize,
+    persist_queue_avg: f64,
+    persist_queue_capacity: usize,
+    /// Time to write what was still queued when the load stopped.
+    persist_drain_ms: u64,
+    rss_start_bytes: Option<u64>,
+    rss_peak_bytes: Option<u64>,
+    rss_end_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+struct LatencyStats {
+    samples: usize,
+    p50: f64,
+    p95: f64,
+    p99: f64,
+    max: f64,
+}
+
+impl LatencyStats {
+    fn from_micros(mut samples: Vec<u64>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let at = |quantile: f64| {
+            let index = ((samples.len() - 1) as f64 * quantile).round() as usize;
+            samples[index] as f64 / 1000.0
+        };
+        Self {
+            samples: samples.len(),
+            p50: at(0.50),
+            p95: at(0.95),
+            p99: at(0.99),
+            max: at(1.0),
+        }
+    }
+}
+
+pub fn run(options: SimulateOptions) -> anyhow::Result<()> {
+    anyhow::ensure!(options.sessions > 0, "--sessions must be at least 1");
+    anyhow::ensure!(
+        options.rate.is_finite() && options.rate > 0.0,
+        "--rate must be above 0"
+    );
+    anyhow::ensure!(!options.duration.is_zero(), "--duration must be above 0");
+
+    let data_dir = std::env::temp_dir().join(format!("orbitdock-simulate-{}", std::process::id()));
+    paths::init_data_dir(Some(&data_dir));
+    paths::ensure_dirs().context("create simulation data directory")?;
+    let mut conn = Connection::open(paths::db_path()).context("open simulation database")?;
+    migration_runner::run_migrations(&mut conn)?;
+    drop(conn);
+
+    if !options.json {
+        println!();
+        println!(
+            "  Simulating {} sessions at {} events/s each for {}s...",
+            options.sessions,
+            options.rate,
+            options.duration.as_secs_f64()
+        );
+    }
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let mut report = runtime.block_on(simulate(&options));
+    drop(runtime);
+
+    if options.keep_data {
+        report.data_dir = Some(data_dir);
+    } else {
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+    Ok(())
+}
+
+async fn simulate(options: &SimulateOptions) -> SimulateReport {
+    let (persist_tx, persist_rx) = create_persistence_channel();
+    tokio::spawn(PersistenceWriter::new(persist_rx).run());
+    let state = Arc::new(SessionRegistry::new_with_primary(persist_tx.clone(), true));
+    tokio::spawn(event_journal::start_journal(state.clone()));
+
+    let started = Instant::now();
+    let received = Arc::new(AtomicU64::new(0));
+    let latencies = Arc::new(Mutex::new(Vec::new()));
+    let mut generators = Vec::with_capacity(options.sessions);
+
+    for index in 0..options.sessions {
+        let session_id = format!("sim-{index:04}");
+        let project_path = format!("/simulate/project-{}", index % 10);
+        let _ = persist_tx
+            .send(PersistCommand::SessionCreate {
+                id: session_id.clone(),
+                provider: Provider::Claude,
+                project_path: project_path.clone(),
+                project_name: None,
+                branch: Some("main".to_string()),
+                model: Some("simulated".to_string()),
+                approval_policy: None,
+                sandbox_mode: None,
+                permission_mode: None,
+                forked_from_session_id: None,
+            })
+            .await;
+
+        let mut handle = SessionHandle::new(session_id.clone(), Provider::Claude, project_path);
+        let mut rx = handle.subscribe();
+        let actor = state.add_session(handle);
+
+        let received = received.clone();
+        let latencies = latencies.clone();
+        tokio::spawn(async move {
+            while let Some(shared) = rx.recv().await {
+                received.fetch_add(1, Ordering::Relaxed);
+                if let Some(stamp) = stamp_of(&shared.message) {
+                    let elapsed = started.elapsed().as_micros() as u64;
+                    if let Ok(mut latencies) = latencies.lock() {
+                        latencies.push(elapsed.saturating_sub(stamp));
+                    }
+                }
+            }
+        });
+
+        let connector = MockConnector::new(session_id, options.message_bytes, started);
+        let interval = Duration::from_secs_f64(1.0 / options.rate);
+        let deadline = started + options.duration;
+        generators.push(tokio::spawn(connector.drive(actor, interval, deadline)));
+    }
+
+    let sampler = tokio::spawn(sample(persist_tx.clone(), started + options.duration));
+    let mut events_sent = 0;
+    for generator in generators {
+        events_sent += generator.await.unwrap_or(0);
+    }
+    let samples = sampler.await.unwrap_or_default();
+
+    let drain_started = Instant::now();
+    let done = Arc::new(Notify::new());
+    if persist_tx
+        .send(PersistCommand::Flush { done: done.clone() })
+        .await
+        .is_ok()
+    {
+        done.notified().await;
+    }
+    let persist_drain_ms = drain_started.elapsed().as_millis() as u64;
+
+    let latency_ms = LatencyStats::from_micros(
+        latencies
+            .lock()
+            .map(|latencies| latencies.clone())
+            .unwrap_or_default(),
+    );
+    SimulateReport {
+        sessions: options.sessions,
+        rate_per_session: options.rate,
+        duration_secs: options.duration.as_secs_f64(),
+        events_sent,
+        broadcasts_received: received.load(Ordering::Relaxed),
+        latency_ms,
+        persist_queue_max: samples.queue_max,
+        persist_queue_avg: samples.queue_avg(),
+        persist_queue_capacity: persist_tx.max_capacity(),
+        persist_drain_ms,
+        rss_start_bytes: samples.rss_start,
+        rss_peak_bytes: samples.rss_peak,
+        rss_end_bytes: resource_monitor::resident_memory(std::process::id()),
+        data_dir: None,
+    }
+}
+
+/// A stand-in for an agent connector: endless turns of synthetic events.
+struct MockConnector {
+    session_id: String,
+    message_bytes: usize,
+    started: Instant,
+    turn: u64,
+}
+
+impl MockConnector {
+    fn new(session_id: String, message_bytes: usize, started: Instant) -> Self {
+        Self {
+            session_id,
+            message_bytes,
+            started,
+            turn: 0,
+        }
+    }
+
+    /// Send events every `interval` until `deadline`; returns how many.
+    async fn drive(
+        mut self,
+        actor: crate::session_actor::SessionActorHandle,
+        interval: Duration,
+        deadline: Instant,
+    ) -> u64 {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut sent = 0;
+        'turns: loop {
+            for event in self.next_turn() {
+                ticker.tick().await;
+                if Instant::now() >= deadline {
+                    break 'turns;
+                }
+                // Stamped as it is handed over, so queueing in the actor
+                // counts toward latency.
+                let event = self.stamp(event);
+                actor
+                    .send(SessionCommand::ProcessEvent {
+                        event: event.into(),
+                    })
+                    .await;
+                sent += 1;
+            }
+        }
+        sent
+    }
+
+    /// One turn: prompt, streamed reply, token update, completion.
+    fn next_turn(&mut self) -> Vec<ConnectorEvent> {
+        self.turn += 1;
+        let turn = self.turn;
+        let reply_id = format!("{}-assistant-{turn}", self.session_id);
+        let mut events = vec![
+            ConnectorEvent::TurnStarted,
+            ConnectorEvent::MessageCreated(self.message(
+                format!("{}-user-{turn}", self.session_id),
+                MessageType::User,
+                false,
+            )),
+            ConnectorEvent::MessageCreated(self.message(
+                reply_id.clone(),
+                MessageType::Assistant,
+                true,
+            )),
+        ];
+        for update in 1..=STREAM_UPDATES {
+            events.push(ConnectorEvent::MessageUpdated {
+                message_id: reply_id.clone(),
+                content: Some("x".repeat(self.message_bytes * update)),
+                tool_output: None,
+                is_error: None,
+                is_in_progress: Some(update < STREAM_UPDATES),
+                duration_ms: None,
+            });
+        }
+        events.push(ConnectorEvent::TokensUpdated {
+            usage: TokenUsage {
+                input_tokens: 1_000 * turn,
+                output_tokens: 200 * turn,
+                cached_tokens: 500 * turn,
+                context_window: 200_000,
+            },
+            snapshot_kind: TokenUsageSnapshotKind::LifetimeTotals,
+        });
+        events.push(ConnectorEvent::TurnCompleted);
+        events
+    }
+
+    fn message(&self, id: String, message_type: MessageType, in_progress: bool) -> Message {
+        Message {
+            id,
+            session_id: self.session_id.clone(),
+            sequence: None,
+            message_type,
+            content: "x".repeat(self.message_bytes),
+            tool_name: None,
+            tool_input: None,
+            tool_output: None,
+            is_error: false,
+            is_in_progress: in_progress,
+            timestamp: crate::session_utils::chrono_now(),
+            duration_ms: None,
+            images: vec![],
+        }
+    }
+
+    fn stamp(&self, mut event: ConnectorEvent) -> ConnectorEvent {
+        let stamp = format!("{STAMP_PREFIX}{};", self.started.elapsed().as_micros());
+        match &mut event {
+            ConnectorEvent::MessageCreated(message) => message.content.insert_str(0, &stamp),
+            ConnectorEvent::MessageUpdated {
+                content: Some(content),
+                ..
+            } => content.insert_str(0, &stamp),
+            _ => {}
+        }
+        event
+    }
+}
+
+/// When the mock connector sent the event behind this broadcast, in
+/// microseconds since the run started.
+fn stamp_of(message: &ServerMessage) -> Option<u64> {
+    let content = match message {
+        ServerMessage::MessageAppended { message, .. } => &message.content,
+        ServerMessage::MessageUpdated { changes, .. } => changes.content.as_ref()?,
+        _ => return None,
+    };
+    content
+        .strip_prefix(STAMP_PREFIX)?
+        .split_once(';')?
+        .0
+        .parse()
+        .ok()
+}
+
+#[derive(Debug, Default)]
+struct Samples {
+    queue_max: usize,
+    queue_total: usize,
+    count: usize,
+    rss_start: Option<u64>,
+    rss_peak: Option<u64>,
+}
+
+impl Samples {
+    fn queue_avg(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.queue_total as f64 / self.count as f64
+        }
+    }
+}
+
+/// Persistence queue depth and resident memory, sampled until `until`.
+async fn sample(persist_tx: mpsc::Sender<PersistCommand>, until: Instant) -> Samples {
+    let pid = std::process::id();
+    let mut samples = Samples {
+        rss_start: resource_monitor::resident_memory(pid),
+        ..Default::default()
+    };
+    samples.rss_peak = samples.rss_start;
+    let mut ticker = tokio::time::interval(SAMPLE_INTERVAL);
+    let mut rss_ticks = 0u32;
+    while Instant::now() < until {
+        ticker.tick().await;
+        let depth = persist_tx.max_capacity() - persist_tx.capacity();
+        samples.queue_max = samples.queue_max.max(depth);
+        samples.queue_total += depth;
+        samples.count += 1;
+        // `ps` is comparatively slow; once a second is plenty.
+        rss_ticks += 1;
+        if rss_ticks.is_multiple_of(10) {
+            let rss = tokio::task::spawn_blocking(move || resource_monitor::resident_memory(pid))
+                .await
+                .ok()
+                .flatten();
+            samples.rss_peak = samples.rss_peak.max(rss);
+        }
+    }
+    samples
+}
+
+fn print_report(report: &SimulateReport) {
+    let mb = |bytes: Option<u64>| {
+        bytes
+            .map(|bytes| format!("{:.1} MB", bytes as f64 / 1_048_576.0))
+            .unwrap_or_else(|| "n/a".to_string())
+    };
+    let latency = &report.latency_ms;
+
+    println!();
+    println!("  Simulation");
+    println!("  ──────────");
+    println!();
+    println!("  Sessions:        {}", report.sessions);
+    println!(
+        "  Events sent:     {} ({:.0}/s overall)",
+        report.events_sent,
+        report.events_sent as f64 / report.duration_secs
+    );
+    println!("  Broadcasts:      {}", report.broadcasts_received);
+    println!();
+    println!(
+        "  Broadcast latency ({} samples): p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms, max {:.2} ms",
+        latency.samples, latency.p50, latency.p95, latency.p99, latency.max
+    );
+    println!(
+        "  Persistence queue: max {} / {}, avg {:.1}, drained in {} ms",
+        report.persist_queue_max,
+        report.persist_queue_capacity,
+        report.persist_queue_avg,
+        report.persist_drain_ms
+    );
+    println!(
+        "  Memory (RSS):      start {}, peak {}, end {}",
+        mb(report.rss_start_bytes),
+        mb(report.rss_peak_bytes),
+        mb(report.rss_end_bytes)
+    );
+    if let Some(dir) = &report.data_dir {
+        println!();
+        println!("  Data kept in {}", dir.display());
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_percentiles_come_from_sorted_samples() {
+        let stats = LatencyStats::from_micros((1..=100).rev().map(|ms| ms * 1000).collect());
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.p50, 51.0);
+        assert_eq!(stats.p95, 95.0);
+        assert_eq!(stats.p99, 99.0);
+        assert_eq!(stats.max, 100.0);
+        assert_eq!(
+            LatencyStats::from_micros(Vec::new()),
+            LatencyStats::default()
+        );
+    }
+
+    #[test]
+    fn stamps_survive_the_trip_through_a_broadcast() {
+        let mut connector = MockConnector::new("sim-0".into(), 16, Instant::now());
+        let turn = connector.next_turn();
+        assert_eq!(turn.len(), 3 + STREAM_UPDATES + 2);
+
+        let ConnectorEvent::MessageCreated(message) = connector.stamp(turn[1].clone()) else {
+            panic!("expected the user prompt");
+        };
+        let broadcast = ServerMessage::MessageAppended {
+            session_id: "sim-0".into(),
+            message,
+        };
+        assert!(stamp_of(&broadcast).is_some());
+
+        let unstamped = ServerMessage::MessageAppended {
+            session_id: "sim-0".into(),
+            message: connector.message("m".into(), MessageType::User, false),
+        };
+        assert_eq!(stamp_of(&unstamped), None);
+    }
+}
@@ -0,0 +1,408 @@
+//! `orbitdock stats` — summarize agent activity over the last N days.
+//!
+//! Reads the database directly, so it works whether or not the server is
+//! running. Token counts are each session's latest rollup, attributed to the
+//! window the session was last active in. Cost is the spend the server tracked
+//! per usage update, or for older sessions an estimate from those totals, both
+//! at the prices in `crate::pricing`.
+
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::pricing::estimate_cost;
+use crate::{migration_runner, paths};
+
+#[derive(Debug, Default, Serialize)]
+struct StatsReport {
+    days: u32,
+    sessions_started: u64,
+    sessions_active: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    cached_tokens: u64,
+    estimated_cost_usd: f64,
+    /// Active sessions whose model has no known price (excluded from cost).
+    unpriced_sessions: u64,
+    turns: u64,
+    avg_turn_duration_ms: Option<u64>,
+    providers: Vec<ProviderStats>,
+    projects: Vec<ProjectStats>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ProviderStats {
+    provider: String,
+    sessions: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    cached_tokens: u64,
+    estimated_cost_usd: f64,
+    turns: u64,
+    avg_turn_duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ProjectStats {
+    project_path: String,
+    sessions: u64,
+    turns: u64,
+    total_tokens: u64,
+    estimated_cost_usd: f64,
+}
+
+pub fn run(days: u32, top: usize, json: bool) -> anyhow::Result<()> {
+    let db_path = paths::db_path();
+    let mut conn = Connection::open(&db_path)
+        .with_context(|| format!("open database at {}", db_path.display()))?;
+    migration_runner::run_migrations(&mut conn).context("run migrations")?;
+
+    let report = collect(&conn, days, top)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+    Ok(())
+}
+
+fn collect(conn: &Connection, days: u32, top: usize) -> anyhow::Result<StatsReport> {
+    let cutoff = format!("-{days} days");
+    let mut report = StatsReport {
+        days,
+        ..Default::default()
+    };
+
+    report.sessions_started = conn.query_row(
+        "SELECT COUNT(*) FROM sessions
+         WHERE julianday(started_at) >= julianday('now', ?1)",
+        params![cutoff],
+        |row| row.get::<_, i64>(0),
+    )? as u64;
+
+    let mut providers: BTreeMap<String, ProviderStats> = BTreeMap::new();
+    let mut projects: BTreeMap<String, ProjectStats> = BTreeMap::new();
+
+    let mut stmt = conn.prepare(
+        "SELECT
+            COALESCE(s.provider, 'claude'),
+            s.project_path,
+            s.model,
+            COALESCE(u.lifetime_input_tokens, s.input_tokens, 0) + COALESCE(a.input_tokens, 0),
+            COALESCE(u.lifetime_output_tokens, s.output_tokens, 0) + COALESCE(a.output_tokens, 0),
+            COALESCE(u.lifetime_cached_tokens, s.cached_tokens, 0) + COALESCE(a.cached_tokens, 0),
+            u.estimated_cost_usd
+         FROM sessions s
+         LEFT JOIN usage_session_state u ON u.session_id = s.id
+         LEFT JOIN (
+            SELECT session_id,
+                   SUM(input_tokens) AS input_tokens,
+                   SUM(output_tokens) AS output_tokens,
+                   SUM(cached_tokens) AS cached_tokens
+            FROM subagents
+            GROUP BY session_id
+         ) a ON a.session_id = s.id
+         WHERE julianday(COALESCE(s.last_activity_at, s.started_at)) >= julianday('now', ?1)",
+    )?;
+    let rows = stmt.query_map(params![cutoff], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, i64>(3)?.max(0) as u64,
+            row.get::<_, i64>(4)?.max(0) as u64,
+            row.get::<_, i64>(5)?.max(0) as u64,
+            row.get::<_, Option<f64>>(6)?,
+        ))
+    })?;
+    for row in rows {
+        let (provider, project_path, model, input, output, cached, tracked_cost) = row?;
+        let cost = tracked_cost.or_else(|| {
+            model
+                .as_deref()
+                .and_then(|model| estimate_cost(&provider, model, input, output, cached))
+        });
+        if cost.is_none() && input + output > 0 {
+            report.unpriced_sessions += 1;
+        }
+        let cost = cost.unwrap_or(0.0);
+
+        report.sessions_active += 1;
+        report.input_tokens += input;
+        report.output_tokens += output;
+        report.cached_tokens += cached;
+        report.estimated_cost_usd += cost;
+
+        let entry = providers
+            .entry(provider.clone())
+            .or_insert_with(|| ProviderStats {
+                provider,
+                ..Default::default()
+            });
+        entry.sessions += 1;
+        entry.input_tokens += input;
+        entry.output_tokens += output;
+        entry.cached_tokens += cached;
+        entry.estimated_cost_usd += cost;
+
+        let entry = projects
+            .entry(project_path.clone())
+            .or_insert_with(|| ProjectStats {
+                project_path,
+                ..Default::default()
+            });
+        entry.sessions += 1;
+        entry.total_tokens += input + output;
+        entry.estimated_cost_usd += cost;
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT t.provider, s.project_path, COUNT(*), SUM(t.duration_ms)
+         FROM turn_metrics t
+         JOIN sessions s ON s.id = t.session_id
+         WHERE julianday(t.created_at) >= julianday('now', ?1)
+         GROUP BY t.provider, s.project_path",
+    )?;
+    let rows = stmt.query_map(params![cutoff], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)? as u64,
+            row.get::<_, i64>(3)?.max(0) as u64,
+        ))
+    })?;
+    let mut turn_ms: BTreeMap<String, u64> = BTreeMap::new();
+    for row in rows {
+        let (provider, project_path, turns, duration_ms) = row?;
+        report.turns += turns;
+        *turn_ms.entry(provider.clone()).or_default() += duration_ms;
+        providers
+            .entry(provider.clone())
+            .or_insert_with(|| ProviderStats {
+                provider,
+                ..Default::default()
+            })
+            .turns += turns;
+        if let Some(project) = projects.get_mut(&project_path) {
+            project.turns += turns;
+        }
+    }
+
+    let total_ms: u64 = turn_ms.values().sum();
+    report.avg_turn_duration_ms = average(total_ms, report.turns);
+    for stats in providers.values_mut() {
+        let ms = turn_ms.get(&stats.provider).copied().unwrap_or(0);
+        stats.avg_turn_duration_ms = average(ms, stats.turns);
+    }
+
+    report.providers = providers.into_values().collect();
+    let mut projects: Vec<ProjectStats> = projects.into_values().collect();
+    projects.sort_by(|a, b| {
+        b.turns
+            .cmp(&a.turns)
+            .then(b.total_tokens.cmp(&a.total_tokens))
+            .then_with(|| a.project_path.cmp(&b.project_path))
+    });
+    projects.truncate(top);
+    report.projects = projects;
+
+    Ok(report)
+}
+
+fn average(total: u64, count: u64) -> Option<u64> {
+    (count > 0).then(|| total / count)
+}
+
+fn print_report(report: &StatsReport) {
+    println!();
+    println!("  OrbitDock usage — last {} days", report.days);
+    println!("  ───────────────────────────");
+    println!();
+    println!(
+        "  Sessions: {} active, {} started",
+        report.sessions_active, report.sessions_started
+    );
+    println!(
+        "  Tokens:   {} in, {} out, {} cached",
+        format_tokens(report.input_tokens),
+        format_tokens(report.output_tokens),
+        format_tokens(report.cached_tokens)
+    );
+    println!("  Cost:     ~${:.2} (estimated)", report.estimated_cost_usd);
+    if report.unpriced_sessions > 0 {
+        println!(
+            "            {} session(s) with unknown model pricing not included",
+            report.unpriced_sessions
+        );
+    }
+    println!(
+        "  Turns:    {} (avg {})",
+        report.turns,
+        format_duration(report.avg_turn_duration_ms)
+    );
+
+    if report.providers.is_empty() {
+        println!();
+        println!("  No activity in this window.");
+        println!();
+        return;
+    }
+
+    println!();
+    println!(
+        "  {:<10} {:>8} {:>10} {:>10} {:>10} {:>7} {:>9}",
+        "PROVIDER", "SESSIONS", "INPUT", "OUTPUT", "COST", "TURNS", "AVG TURN"
+    );
+    for p in &report.providers {
+        println!(
+            "  {:<10} {:>8} {:>10} {:>10} {:>10} {:>7} {:>9}",
+            p.provider,
+            p.sessions,
+            format_tokens(p.input_tokens),
+            format_tokens(p.output_tokens),
+            format!("${:.2}", p.estimated_cost_usd),
+            p.turns,
+            format_duration(p.avg_turn_duration_ms)
+        );
+    }
+
+    if !report.projects.is_empty() {
+        println!();
+        println!("  Busiest projects");
+        for project in &report.projects {
+            println!(
+                "    {:<40} {:>4} sessions {:>6} turns {:>8} tokens  ${:.2}",
+                project.project_path,
+                project.sessions,
+                project.turns,
+                format_tokens(project.total_tokens),
+                project.estimated_cost_usd
+            );
+        }
+    }
+    println!();
+}
+
+fn format_tokens(tokens: u64) -> String {
+    match tokens {
+        t if t >= 1_000_000 => format!("{:.1}M", t as f64 / 1_000_000.0),
+        t if t >= 1_000 => format!("{:.1}k", t as f64 / 1_000.0),
+        t => t.to_string(),
+    }
+}
+
+fn format_duration(ms: Option<u64>) -> String {
+    match ms {
+        None => "-".to_string(),
+        Some(ms) if ms >= 60_000 => format!("{}m{:02}s", ms / 60_000, (ms % 60_000) / 1000),
+        Some(ms) => format!("{:.1}s", ms as f64 / 1000.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migration_runner::run_migrations(&mut conn).unwrap();
+        conn
+    }
+
+    fn insert_session(conn: &Connection, id: &str, provider: &str, project: &str, model: &str) {
+        conn.execute(
+            "INSERT INTO sessions (id, provider, project_path, model, started_at, last_activity_at,
+                                   input_tokens, output_tokens, cached_tokens)
+             VALUES (?1, ?2, ?3, ?4,
+                     strftime('%Y-%m-%dT%H:%M:%SZ', 'now', '-1 day'),
+                     strftime('%Y-%m-%dT%H:%M:%SZ', 'now'),
+                     1000000, 100000, 0)",
+            params![id, provider, project, model],
+        )
+        .unwrap();
+    }
+
+    fn insert_turn(conn: &Connection, session_id: &str, turn_id: &str, provider: &str, ms: i64) {
+        conn.execute(
+            "INSERT INTO turn_metrics (session_id, turn_id, provider, duration_ms)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![session_id, turn_id, provider, ms],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn collect_groups_by_provider_and_project() {
+        let conn = test_db();
+        insert_session(&conn, "a", "claude", "/repo/app", "claude-sonnet-4-5");
+        insert_session(&conn, "b", "codex", "/repo/api", "gpt-5-codex");
+        insert_session(&conn, "c", "codex", "/repo/api", "mystery-model");
+        conn.execute(
+            "INSERT INTO sessions (id, provider, project_path, started_at, last_activity_at)
+             VALUES ('old', 'claude', '/repo/app', '2020-01-01T00:00:00Z', '2020-01-01T00:00:00Z')",
+            [],
+        )
+        .unwrap();
+        insert_turn(&conn, "a", "t1", "claude", 2_000);
+        insert_turn(&conn, "b", "t1", "codex", 4_000);
+        insert_turn(&conn, "c", "t1", "codex", 6_000);
+
+        let report = collect(&conn, 7, 5).unwrap();
+
+        assert_eq!(report.sessions_active, 3);
+        assert_eq!(report.sessions_started, 3);
+        assert_eq!(report.unpriced_sessions, 1);
+        assert_eq!(report.turns, 3);
+        assert_eq!(report.avg_turn_duration_ms, Some(4_000));
+
+        let codex = report
+            .providers
+            .iter()
+            .find(|p| p.provider == "codex")
+            .unwrap();
+        assert_eq!(codex.sessions, 2);
+        assert_eq!(codex.avg_turn_duration_ms, Some(5_000));
+
+        // Sonnet: 1M in at $3 + 100k out at $15; gpt-5: 1M in at $1.25 + 100k out at $10.
+        assert!((report.estimated_cost_usd - (4.5 + 2.25)).abs() < 1e-9);
+        assert_eq!(report.projects[0].project_path, "/repo/api");
+        assert_eq!(report.projects[0].turns, 2);
+    }
+
+    #[test]
+    fn subagent_tokens_count_toward_their_session() {
+        let conn = test_db();
+        insert_session(&conn, "a", "claude", "/repo/app", "claude-sonnet-4-5");
+        conn.execute(
+            "INSERT INTO subagents (id, session_id, agent_type, started_at, input_tokens, output_tokens, cached_tokens)
+             VALUES ('agent-1', 'a', 'Explore', '2026-10-17T09:00:00Z', 500, 200, 3000)",
+            [],
+        )
+        .unwrap();
+
+        let report = collect(&conn, 7, 5).unwrap();
+
+        assert_eq!(report.input_tokens, 1_000_500);
+        assert_eq!(report.output_tokens, 100_200);
+        assert_eq!(report.cached_tokens, 3000);
+    }
+
+    #[test]
+    fn tracked_cost_wins_over_token_estimate() {
+        let conn = test_db();
+        insert_session(&conn, "a", "claude", "/repo/app", "mystery-model");
+        conn.execute(
+            "INSERT INTO usage_session_state (session_id, provider, estimated_cost_usd)
+             VALUES ('a', 'claude', 1.5)",
+            [],
+        )
+        .unwrap();
+
+        let report = collect(&conn, 7, 5).unwrap();
+
+        assert_eq!(report.unpriced_sessions, 0);
+        assert!((report.estimated_cost_usd - 1.5).abs() < 1e-9);
+    }
+}
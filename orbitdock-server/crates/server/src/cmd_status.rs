@@ -1,10 +1,13 @@
 //! `orbitdock status` — check if the server is running.
-//! `orbitdock generate-token` — create a secure auth token.
+//! `orbitdock token create/list/revoke/rotate` — manage auth tokens.
 
 use std::path::Path;
 
+use crate::scopes::TokenScope;
 use crate::VERSION;
-use crate::{auth_tokens, paths};
+use crate::{auth_tokens, cmd_tunnel, listen, paths};
+
+const DEFAULT_URL: &str = "http://127.0.0.1:4000";
 
 pub fn run(data_dir: &Path) -> anyhow::Result<()> {
     println!();
@@ -28,10 +31,25 @@ pub fn run(data_dir: &Path) -> anyhow::Result<()> {
         false
     };
 
-    // Try HTTP health check
-    let health_ok = check_health();
-    if health_ok {
-        println!("  Health: OK (http://127.0.0.1:4000/health)");
+    // Try HTTP health check where the server last said it listens
+    let listen_url = pid_alive
+        .then(listen::read_listen_file)
+        .flatten()
+        .unwrap_or_else(|| DEFAULT_URL.to_string());
+    println!("  Listening: {}", listen_url);
+    match cmd_tunnel::current() {
+        Some(tunnel) => println!(
+            "  Tunnel: {} {} (pid {})",
+            tunnel.provider.as_str(),
+            tunnel.url.as_deref().unwrap_or("(waiting for URL)"),
+            tunnel.pid
+        ),
+        None => println!("  Tunnel: not running (orbitdock tunnel)"),
+    }
+    let health = check_health(&listen_url);
+    let health_ok = health.is_some();
+    if let Some(report) = &health {
+        print_health_report(report);
     } else if pid_alive {
         println!("  Health: unreachable (server may be binding to a different address)");
     } else {
@@ -73,19 +91,37 @@ pub fn run(data_dir: &Path) -> anyhow::Result<()> {
 /// Create a new auth token and store its hash in the database. Returns the token string.
 pub fn create_token(data_dir: &Path) -> anyhow::Result<String> {
     let _ = data_dir;
-    let issued = auth_tokens::issue_token(None)?;
+    let issued = auth_tokens::issue_token(None, None, TokenScope::Admin, None)?;
     Ok(issued.token)
 }
 
-pub fn generate_token(data_dir: &Path) -> anyhow::Result<()> {
+pub fn generate_token(
+    data_dir: &Path,
+    user: Option<&str>,
+    label: Option<&str>,
+    scope: &str,
+    expires_in: Option<&str>,
+) -> anyhow::Result<()> {
     let _ = data_dir;
-    let issued = auth_tokens::issue_token(None)?;
+    let Some(scope) = TokenScope::parse(scope) else {
+        let valid: Vec<&str> = TokenScope::ALL.iter().map(|s| s.as_str()).collect();
+        anyhow::bail!("Unknown scope '{}' (expected {})", scope, valid.join(", "));
+    };
+    let expires_in = expires_in.map(auth_tokens::parse_lifetime).transpose()?;
+    let issued = auth_tokens::issue_token(label, user, scope, expires_in)?;
 
     println!();
     println!("  Secure auth token generated and stored (hashed) in the database.");
     println!("  Copy it now and store it somewhere secure.");
     println!();
     println!("  Token ID: {}", issued.id);
+    if let Some(user) = user {
+        println!("  User: {}", user.trim());
+    }
+    println!("  Scope: {}", scope.as_str());
+    if let Some(ref expires) = issued.expires_at {
+        println!("  Expires: {}", expires);
+    }
     println!("  Token: {}", issued.token);
     println!();
     println!("  Usage:");
@@ -116,11 +152,17 @@ pub fn list_tokens() -> anyhow::Result<()> {
     for token in tokens {
         let status = if token.revoked_at.is_some() {
             "revoked"
+        } else if token.expired {
+            "expired"
         } else {
             "active"
         };
         let label = token.label.as_deref().unwrap_or("(no label)");
         println!("  {}  [{}]  {}", token.id, status, label);
+        if let Some(ref user) = token.user {
+            println!("    user: {}", user);
+        }
+        println!("    scope: {}", token.scope.as_str());
         println!("    created: {}", token.created_at);
         if let Some(ref used) = token.last_used_at {
             println!("    last used: {}", used);
@@ -142,6 +184,7 @@ pub fn revoke_token(token_id: &str) -> anyhow::Result<()> {
     println!();
     if revoked {
         println!("  Revoked token {}", token_id.trim());
+        println!("  A running server closes connections using it within a few seconds.");
     } else {
         println!(
             "  Token {} was not found or already revoked",
@@ -152,23 +195,109 @@ pub fn revoke_token(token_id: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn process_alive(pid: u32) -> bool {
+/// Issue a replacement for a token and retire the old one, immediately or
+/// after a grace period.
+pub fn rotate_token(
+    token_id: &str,
+    expires_in: Option<&str>,
+    grace: Option<&str>,
+) -> anyhow::Result<()> {
+    let expires_in = expires_in.map(auth_tokens::parse_lifetime).transpose()?;
+    let grace = grace.map(auth_tokens::parse_lifetime).transpose()?;
+    let Some(issued) = auth_tokens::rotate_token(token_id, expires_in, grace)? else {
+        anyhow::bail!(
+            "token {} was not found, or is revoked or expired",
+            token_id.trim()
+        );
+    };
+
+    println!();
+    println!("  Rotated token {}", token_id.trim());
+    if grace.is_some() {
+        println!("  The old token keeps working for the grace period, then expires.");
+    } else {
+        println!("  The old token is revoked; connections using it will be closed.");
+    }
+    println!();
+    println!("  New Token ID: {}", issued.id);
+    if let Some(ref expires) = issued.expires_at {
+        println!("  Expires: {}", expires);
+    }
+    println!("  Token: {}", issued.token);
+    println!();
+    Ok(())
+}
+
+pub(crate) fn process_alive(pid: u32) -> bool {
     // kill -0 checks if process exists without sending a signal
     unsafe { libc::kill(pid as i32, 0) == 0 }
 }
 
-fn check_health() -> bool {
+/// Fetch the `/health` report. Returns `None` if the server is unreachable.
+fn check_health(listen_url: &str) -> Option<serde_json::Value> {
     // Use a quick blocking HTTP check (this runs outside tokio)
-    std::process::Command::new("curl")
-        .args([
-            "-s",
-            "--connect-timeout",
-            "1",
-            "--max-time",
-            "2",
-            "http://127.0.0.1:4000/health",
-        ])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+    let mut command = std::process::Command::new("curl");
+    command.args(["-s", "--connect-timeout", "1", "--max-time", "2"]);
+    match listen_url.strip_prefix("unix:") {
+        Some(socket) => {
+            command.args(["--unix-socket", socket, "http://localhost/health"]);
+        }
+        // Liveness only: the certificate is for a hostname, not this address.
+        None if listen_url.starts_with("https://") => {
+            command.args(["-k", &format!("{listen_url}/health")]);
+        }
+        None => {
+            command.arg(format!("{listen_url}/health"));
+        }
+    }
+    let output = command.output().ok().filter(|o| o.status.success())?;
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+fn print_health_report(report: &serde_json::Value) {
+    let status = report["status"].as_str().unwrap_or("unknown");
+    println!("  Health: {}", status);
+
+    // Older servers only report status + version.
+    if report.get("database").is_none() {
+        return;
+    }
+
+    let db = &report["database"];
+    if db["reachable"].as_bool().unwrap_or(false) {
+        let version = db["migration_version"].as_i64().unwrap_or(0);
+        println!("    Database: reachable (migration v{})", version);
+    } else {
+        let error = db["error"].as_str().unwrap_or("unknown error");
+        println!("    Database: unreachable ({})", error);
+    }
+    println!(
+        "    Persistence queue: {}/{}",
+        report["persistence"]["queue_depth"].as_u64().unwrap_or(0),
+        report["persistence"]["queue_capacity"]
+            .as_u64()
+            .unwrap_or(0)
+    );
+    println!(
+        "    Spool backlog: {}",
+        report["spool_backlog"].as_u64().unwrap_or(0)
+    );
+    let watcher = if report["rollout_watcher_running"].as_bool().unwrap_or(false) {
+        "running"
+    } else {
+        "not running"
+    };
+    println!("    Rollout watcher: {}", watcher);
+    println!(
+        "    Connectors: {} codex, {} claude",
+        report["connectors"]["codex"].as_u64().unwrap_or(0),
+        report["connectors"]["claude"].as_u64().unwrap_or(0)
+    );
+    if let Some(bytes) = report["disk"]["available_bytes"].as_u64() {
+        println!("    Disk free: {} GB", bytes / (1024 * 1024 * 1024));
+    }
+    println!(
+        "    Uptime: {}s",
+        report["uptime_seconds"].as_u64().unwrap_or(0)
+    );
 }
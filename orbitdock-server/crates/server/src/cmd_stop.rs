@@ -0,0 +1,75 @@
+//! `orbitdock stop` — gracefully stop the server recorded in the PID file.
+//!
+//! Sends SIGTERM, which runs the same shutdown path as Ctrl-C: pending writes
+//! are flushed, direct sessions stay resumable, and the PID file is removed.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+
+use crate::cmd_status::process_alive;
+use crate::paths;
+
+pub fn run(timeout_secs: u64, force: bool) -> anyhow::Result<()> {
+    let pid_path = paths::pid_file_path();
+    let Ok(pid_str) = std::fs::read_to_string(&pid_path) else {
+        println!();
+        println!("  Server is not running (no PID file).");
+        println!();
+        return Ok(());
+    };
+    let pid: u32 = pid_str
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid PID file {}", pid_path.display()))?;
+
+    println!();
+    if !process_alive(pid) {
+        let _ = std::fs::remove_file(&pid_path);
+        println!("  Server is not running (removed stale PID file for {pid}).");
+        println!();
+        return Ok(());
+    }
+
+    println!("  Stopping server (PID {pid})...");
+    signal(pid, libc::SIGTERM)?;
+    if wait_for_exit(pid, Duration::from_secs(timeout_secs)) {
+        println!("  Stopped.");
+        println!();
+        return Ok(());
+    }
+
+    if !force {
+        bail!("server did not exit within {timeout_secs}s; rerun with --force to kill it");
+    }
+    println!("  Still running after {timeout_secs}s — killing it.");
+    signal(pid, libc::SIGKILL)?;
+    if !wait_for_exit(pid, Duration::from_secs(2)) {
+        bail!("server (PID {pid}) is still running");
+    }
+    let _ = std::fs::remove_file(&pid_path);
+    println!("  Killed.");
+    println!();
+    Ok(())
+}
+
+fn signal(pid: u32, signal: i32) -> anyhow::Result<()> {
+    if unsafe { libc::kill(pid as i32, signal) } != 0 {
+        bail!(
+            "could not signal PID {pid}: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    Ok(())
+}
+
+fn wait_for_exit(pid: u32, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if !process_alive(pid) {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    !process_alive(pid)
+}
@@ -1,30 +1,139 @@
-//! `orbitdock tunnel` — expose the server via Cloudflare Tunnel.
+//! `orbitdock tunnel` — expose the server outside this machine.
 //!
-//! Quick tunnel (no account): spins up a temporary `trycloudflare.com` URL.
-//! Named tunnel (account required): uses an existing tunnel name.
+//! - Cloudflare (default): a quick `trycloudflare.com` URL, no account
+//!   needed, or an existing named tunnel with `--name`.
+//! - Tailscale Funnel: `https://<machine>.<tailnet>.ts.net`, served by
+//!   `tailscale funnel`.
+//! - SSH: a reverse tunnel (`ssh -R`) to a host you control.
+//!
+//! A public URL in front of a server without auth would let anyone in, so a
+//! token is issued first when none exists, and printed with the URL. While the
+//! tunnel runs, its provider and URL are written next to the PID file so
+//! `/health` and `orbitdock status` can report it.
 
 use std::io::{BufRead, BufReader};
 use std::process::{Child, Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::cmd_status::process_alive;
+use crate::scopes::TokenScope;
+use crate::{auth_tokens, paths};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    /// Cloudflare Tunnel via `cloudflared`
+    Cloudflare,
+    /// Tailscale Funnel via `tailscale funnel`
+    Tailscale,
+    /// SSH reverse tunnel to a host you control
+    Ssh,
+}
 
-use crate::paths;
+impl Provider {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Provider::Cloudflare => "cloudflare",
+            Provider::Tailscale => "tailscale",
+            Provider::Ssh => "ssh",
+        }
+    }
+}
 
-pub fn run(port: u16, name: Option<&str>) -> anyhow::Result<()> {
-    let cloudflared = find_cloudflared()?;
+pub struct TunnelOptions {
+    pub provider: Provider,
+    pub port: u16,
+    /// Cloudflare named tunnel
+    pub name: Option<String>,
+    /// SSH destination, e.g. `me@relay.example.com`
+    pub ssh: Option<String>,
+    /// Port to open on the SSH host (defaults to `port`)
+    pub remote_port: Option<u16>,
+    /// URL to print instead of the one the provider gives
+    pub public_url: Option<String>,
+    /// Issue a new token even when some already exist
+    pub new_token: bool,
+}
+
+/// A running tunnel, as recorded in the status file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TunnelStatus {
+    pub provider: Provider,
+    /// `None` until a Cloudflare quick tunnel reports its URL.
+    pub url: Option<String>,
+    pub pid: u32,
+    /// Unix seconds
+    pub started_at: u64,
+}
+
+/// How clients authenticate through the tunnel.
+enum Access {
+    /// `ORBITDOCK_AUTH_TOKEN` is set.
+    StaticToken(String),
+    /// A token issued for this tunnel.
+    Issued(String),
+    /// Tokens already exist; clients use one of them.
+    Existing(i64),
+}
+
+pub fn run(opts: TunnelOptions) -> anyhow::Result<()> {
+    let access = ensure_access(opts.new_token)?;
 
     println!();
-    println!("  Starting Cloudflare Tunnel...");
-    println!("  Binary: {}", cloudflared);
+    let (mut child, provider_url) = match opts.provider {
+        Provider::Cloudflare => {
+            let cloudflared = find_cloudflared()?;
+            println!("  Starting Cloudflare Tunnel...");
+            println!("  Binary: {}", cloudflared);
+            let child = if let Some(tunnel_name) = opts.name.as_deref() {
+                println!("  Named tunnel: {}", tunnel_name);
+                start_named_tunnel(&cloudflared, opts.port, tunnel_name)?
+            } else {
+                println!("  Quick tunnel (temporary URL, no account needed)");
+                start_quick_tunnel(&cloudflared, opts.port)?
+            };
+            (child, None)
+        }
+        Provider::Tailscale => {
+            let url = tailscale_funnel_url()?;
+            println!("  Starting Tailscale Funnel...");
+            (start_funnel(opts.port)?, Some(url))
+        }
+        Provider::Ssh => {
+            let Some(destination) = opts.ssh.as_deref() else {
+                anyhow::bail!("--via ssh needs --ssh <user@host>");
+            };
+            let remote_port = opts.remote_port.unwrap_or(opts.port);
+            println!("  Starting SSH reverse tunnel to {}...", destination);
+            let child = start_ssh_tunnel(destination, opts.port, remote_port)?;
+            (child, Some(ssh_public_url(destination, remote_port)))
+        }
+    };
     println!();
 
-    let mut child = if let Some(tunnel_name) = name {
-        println!("  Named tunnel: {}", tunnel_name);
-        start_named_tunnel(&cloudflared, port, tunnel_name)?
-    } else {
-        println!("  Quick tunnel (temporary URL, no account needed)");
-        start_quick_tunnel(&cloudflared, port)?
+    // Ctrl+C reaches the tunnel process too. Outlive it so the status file is
+    // cleared once it exits.
+    ignore_interrupts();
+
+    let mut status = TunnelStatus {
+        provider: opts.provider,
+        url: opts.public_url.clone().or(provider_url),
+        pid: std::process::id(),
+        started_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
     };
+    write_status(&status);
+    if let Some(url) = status.url.as_deref() {
+        announce(url, &access);
+    }
 
-    // Parse stderr for the tunnel URL
+    // cloudflared prints the quick tunnel URL to stderr; the other providers
+    // write straight to the terminal.
     if let Some(stderr) = child.stderr.take() {
         let reader = BufReader::new(stderr);
         for line in reader.lines() {
@@ -33,20 +142,14 @@ pub fn run(port: u16, name: Option<&str>) -> anyhow::Result<()> {
                 Err(_) => continue,
             };
 
-            // cloudflared prints the URL to stderr in a line like:
             // "... https://xxx-yyy.trycloudflare.com ..."
-            if line.contains("trycloudflare.com") || line.contains(".cloudflare") {
+            if status.url.is_none()
+                && (line.contains("trycloudflare.com") || line.contains(".cloudflare"))
+            {
                 if let Some(url) = extract_url(&line) {
-                    println!();
-                    println!("  ══════════════════════════════════════════");
-                    println!("  Tunnel URL: {}", url);
-                    println!("  ══════════════════════════════════════════");
-                    println!();
-                    println!("  Connect a remote machine:");
-                    println!("    orbitdock install-hooks --server-url {}", url);
-                    println!();
-                    println!("  Press Ctrl+C to stop the tunnel.");
-                    println!();
+                    announce(&url, &access);
+                    status.url = Some(url);
+                    write_status(&status);
                 }
             }
 
@@ -55,15 +158,102 @@ pub fn run(port: u16, name: Option<&str>) -> anyhow::Result<()> {
         }
     }
 
-    // Wait for the child process
-    let status = child.wait()?;
-    if !status.success() {
-        anyhow::bail!("cloudflared exited with status {}", status);
+    let exit = child.wait();
+    remove_status_file();
+    let exit = exit?;
+    if !exit.success() {
+        anyhow::bail!(
+            "{} tunnel exited with status {}",
+            opts.provider.as_str(),
+            exit
+        );
     }
 
     Ok(())
 }
 
+/// Make sure the server will ask for a token before anything is exposed.
+fn ensure_access(new_token: bool) -> anyhow::Result<Access> {
+    let env_token = std::env::var("ORBITDOCK_AUTH_TOKEN")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    if let Some(token) = env_token {
+        return Ok(Access::StaticToken(token));
+    }
+
+    let active = auth_tokens::active_token_count()?;
+    if active == 0 || new_token {
+        // The server checks for tokens on every request, so this turns auth on
+        // for one that is already running.
+        let issued = auth_tokens::issue_token(Some("tunnel"), None, TokenScope::Admin, None)?;
+        return Ok(Access::Issued(issued.token));
+    }
+    Ok(Access::Existing(active))
+}
+
+fn announce(url: &str, access: &Access) {
+    println!();
+    println!("  ══════════════════════════════════════════");
+    println!("  Tunnel URL: {}", url);
+    match access {
+        Access::StaticToken(token) => {
+            let prefix: String = token.chars().take(8).collect();
+            println!("  Token:      {}... (ORBITDOCK_AUTH_TOKEN)", prefix);
+        }
+        Access::Issued(token) => println!("  Token:      {}", token),
+        Access::Existing(count) => {
+            println!("  Token:      required ({} active token(s))", count);
+        }
+    }
+    println!("  ══════════════════════════════════════════");
+    println!();
+    match access {
+        Access::Issued(_) => {
+            println!("  The token was just issued and is only shown once. Copy it now.")
+        }
+        Access::Existing(_) => {
+            println!("  Use a token you already have, or rerun with --new-token.")
+        }
+        Access::StaticToken(_) => {}
+    }
+    if url.starts_with("http://") {
+        println!("  Warning: this URL is plain HTTP, so the token crosses the network");
+        println!("  unencrypted. Put TLS in front of it and pass --public-url.");
+    }
+    println!();
+    println!("  Pair the iOS app:");
+    println!("    orbitdock pair --tunnel-url {}", url);
+    println!("  Connect a remote machine:");
+    println!("    orbitdock install-hooks --server-url {}", url);
+    println!();
+    println!("  Press Ctrl+C to stop the tunnel.");
+    println!();
+}
+
+/// The running tunnel, if there is one.
+pub fn current() -> Option<TunnelStatus> {
+    let contents = std::fs::read_to_string(paths::tunnel_status_path()).ok()?;
+    let status: TunnelStatus = serde_json::from_str(&contents).ok()?;
+    process_alive(status.pid).then_some(status)
+}
+
+fn write_status(status: &TunnelStatus) {
+    if let Ok(json) = serde_json::to_string(status) {
+        let _ = std::fs::write(paths::tunnel_status_path(), json);
+    }
+}
+
+fn remove_status_file() {
+    let _ = std::fs::remove_file(paths::tunnel_status_path());
+}
+
+fn ignore_interrupts() {
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_IGN);
+    }
+}
+
 fn find_cloudflared() -> anyhow::Result<String> {
     // 1. Check PATH
     if let Ok(output) = Command::new("which").arg("cloudflared").output() {
@@ -119,6 +309,61 @@ fn start_named_tunnel(cloudflared: &str, port: u16, name: &str) -> anyhow::Resul
     Ok(child)
 }
 
+/// The Funnel URL for this machine: its MagicDNS name over HTTPS.
+fn tailscale_funnel_url() -> anyhow::Result<String> {
+    let output = Command::new("tailscale")
+        .args(["status", "--json"])
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to run tailscale: {} (is Tailscale installed?)", e))?;
+    if !output.status.success() {
+        anyhow::bail!("Tailscale isn't running. Start it and log in, then try again.");
+    }
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    funnel_url_from_status(&json).ok_or_else(|| {
+        anyhow::anyhow!(
+            "This machine has no tailnet DNS name. Enable MagicDNS and HTTPS \
+             certificates for your tailnet, then try again."
+        )
+    })
+}
+
+fn funnel_url_from_status(status: &serde_json::Value) -> Option<String> {
+    let dns_name = status.get("Self")?.get("DNSName")?.as_str()?;
+    let host = dns_name.trim_end_matches('.');
+    (!host.is_empty()).then(|| format!("https://{host}"))
+}
+
+fn start_funnel(port: u16) -> anyhow::Result<Child> {
+    Command::new("tailscale")
+        .args(["funnel", &port.to_string()])
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to start tailscale funnel: {}", e))
+}
+
+/// Forward `remote_port` on the SSH host to the local server. The port is
+/// opened on all of the host's interfaces, which its sshd only allows with
+/// `GatewayPorts clientspecified` (or `yes`).
+fn start_ssh_tunnel(destination: &str, port: u16, remote_port: u16) -> anyhow::Result<Child> {
+    Command::new("ssh")
+        .args([
+            "-N",
+            "-o",
+            "ExitOnForwardFailure=yes",
+            "-o",
+            "ServerAliveInterval=30",
+            "-R",
+            &format!("0.0.0.0:{remote_port}:127.0.0.1:{port}"),
+            destination,
+        ])
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to start ssh: {}", e))
+}
+
+fn ssh_public_url(destination: &str, remote_port: u16) -> String {
+    let host = destination.rsplit('@').next().unwrap_or(destination);
+    format!("http://{host}:{remote_port}")
+}
+
 fn extract_url(line: &str) -> Option<String> {
     // Find HTTPS URLs in the line
     for word in line.split_whitespace() {
@@ -131,3 +376,41 @@ fn extract_url(line: &str) -> Option<String> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn funnel_url_uses_the_magic_dns_name() {
+        let status = serde_json::json!({
+            "Self": { "DNSName": "my-mac.tail1234.ts.net.", "TailscaleIPs": ["100.64.1.2"] }
+        });
+        assert_eq!(
+            funnel_url_from_status(&status).as_deref(),
+            Some("https://my-mac.tail1234.ts.net")
+        );
+        assert_eq!(
+            funnel_url_from_status(&serde_json::json!({ "Self": { "DNSName": "" } })),
+            None
+        );
+    }
+
+    #[test]
+    fn ssh_url_points_at_the_remote_port() {
+        assert_eq!(
+            ssh_public_url("me@relay.example.com", 8443),
+            "http://relay.example.com:8443"
+        );
+        assert_eq!(ssh_public_url("relay", 4000), "http://relay:4000");
+    }
+
+    #[test]
+    fn extracts_quick_tunnel_url() {
+        let line = "2024-01-01T00:00:00Z INF |  https://calm-river-1234.trycloudflare.com  |";
+        assert_eq!(
+            extract_url(line).as_deref(),
+            Some("https://calm-river-1234.trycloudflare.com")
+        );
+    }
+}
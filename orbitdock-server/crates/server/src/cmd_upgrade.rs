@@ -0,0 +1,299 @@
+//! `orbitdock upgrade` — replace this binary with a published release.
+//!
+//! Downloads the same release asset `install.sh` would, refuses it unless the
+//! published SHA-256 matches, swaps it over the running executable with an
+//! atomic rename (keeping the old one as `orbitdock.previous`), and restarts
+//! the installed launchd/systemd service.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+
+use crate::cmd_install_service;
+use crate::VERSION;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+const DEFAULT_REPO: &str = "Robdel12/OrbitDock";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Channel {
+    /// Latest full release
+    Stable,
+    /// Latest release including prereleases
+    Beta,
+}
+
+pub struct UpgradeOptions {
+    pub channel: Channel,
+    pub version: Option<String>,
+    pub check: bool,
+    pub force: bool,
+    pub restart: bool,
+}
+
+pub fn run(opts: UpgradeOptions) -> anyhow::Result<()> {
+    let repo = std::env::var("ORBITDOCK_SERVER_REPO").unwrap_or_else(|_| DEFAULT_REPO.into());
+
+    let tag = match &opts.version {
+        Some(version) => normalize_tag(version),
+        None => latest_tag(&repo, opts.channel)?,
+    };
+
+    println!();
+    println!("  Current: v{VERSION}");
+    println!("  Latest:  {tag} ({:?})", opts.channel);
+
+    let up_to_date = tag.trim_start_matches('v') == VERSION;
+    if opts.check {
+        if up_to_date {
+            println!("  Up to date.");
+        } else {
+            println!("  Run `orbitdock upgrade` to install {tag}.");
+        }
+        println!();
+        return Ok(());
+    }
+    if up_to_date && !opts.force {
+        println!("  Already up to date.");
+        println!();
+        return Ok(());
+    }
+
+    let target = std::env::current_exe()
+        .and_then(std::fs::canonicalize)
+        .context("could not locate the running binary")?;
+    // A fresh, randomly named 0700 directory: another local user can't
+    // pre-create it or swap the extracted binary before it's installed.
+    let work_dir = tempfile::Builder::new()
+        .prefix("orbitdock-upgrade-")
+        .tempdir()
+        .context("could not create a private upgrade directory")?;
+    download_and_install(&repo, &tag, work_dir.path(), &target)?;
+
+    println!("  Installed {tag} at {}", target.display());
+
+    if !opts.restart {
+        println!("  Restart the server to finish upgrading.");
+    } else {
+        match cmd_install_service::restart_installed_service() {
+            Ok(true) => println!("  Service restarted."),
+            Ok(false) => {
+                println!("  No service installed — restart the server to finish upgrading.")
+            }
+            Err(e) => println!("  Warning: {e}. Restart the server to finish upgrading."),
+        }
+    }
+    println!();
+    Ok(())
+}
+
+fn download_and_install(
+    repo: &str,
+    tag: &str,
+    work_dir: &Path,
+    target: &Path,
+) -> anyhow::Result<()> {
+    let assets = asset_names_for_platform().context(
+        "no prebuilt release for this platform; reinstall with install.sh --force-source",
+    )?;
+
+    let mut downloaded = None;
+    for asset in assets {
+        let url = format!("https://github.com/{repo}/releases/download/{tag}/{asset}");
+        let zip_path = work_dir.join(asset);
+        println!("  Downloading {asset}...");
+        if curl_to_file(&url, &zip_path).is_ok() {
+            downloaded = Some((asset, url, zip_path));
+            break;
+        }
+    }
+    let Some((asset, url, zip_path)) = downloaded else {
+        bail!("no release asset for this platform in {tag}");
+    };
+
+    let checksum_path = work_dir.join(format!("{asset}.sha256"));
+    curl_to_file(&format!("{url}.sha256"), &checksum_path)
+        .context("release has no published checksum; refusing to install it")?;
+    let expected = parse_checksum(&std::fs::read_to_string(&checksum_path)?, asset)
+        .context("could not read the published checksum")?;
+    let actual = sha256_hex(&std::fs::read(&zip_path)?);
+    if !actual.eq_ignore_ascii_case(&expected) {
+        bail!("checksum mismatch for {asset}: expected {expected}, got {actual}");
+    }
+    println!("  Checksum verified.");
+
+    let extract_dir = work_dir.join("extract");
+    let status = Command::new("unzip")
+        .arg("-qo")
+        .arg(&zip_path)
+        .arg("-d")
+        .arg(&extract_dir)
+        .status()
+        .context("unzip not found")?;
+    if !status.success() {
+        bail!("could not extract {asset}");
+    }
+    let new_binary = extract_dir.join("orbitdock");
+    if !new_binary.is_file() {
+        bail!("binary not found in {asset}");
+    }
+
+    let runs = Command::new(&new_binary)
+        .arg("--version")
+        .output()
+        .map(|out| out.status.success())
+        .unwrap_or(false);
+    if !runs {
+        bail!("downloaded binary does not run on this machine");
+    }
+
+    replace_binary(&new_binary, target)
+}
+
+/// Stage next to the target so the final rename stays on one filesystem.
+fn replace_binary(new_binary: &Path, target: &Path) -> anyhow::Result<()> {
+    let staged = sibling(target, ".orbitdock.upgrade");
+    std::fs::copy(new_binary, &staged)
+        .with_context(|| format!("could not write to {}", staged.display()))?;
+    #[cfg(unix)]
+    std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+
+    let _ = std::fs::copy(target, sibling(target, "orbitdock.previous"));
+    std::fs::rename(&staged, target).inspect_err(|_| {
+        let _ = std::fs::remove_file(&staged);
+    })?;
+    Ok(())
+}
+
+fn sibling(path: &Path, name: &str) -> PathBuf {
+    path.with_file_name(name)
+}
+
+fn latest_tag(repo: &str, channel: Channel) -> anyhow::Result<String> {
+    let url = match channel {
+        Channel::Stable => format!("https://api.github.com/repos/{repo}/releases/latest"),
+        Channel::Beta => format!("https://api.github.com/repos/{repo}/releases?per_page=10"),
+    };
+    let output = Command::new("curl")
+        .args(["-fsSL", "-H", "Accept: application/vnd.github+json", &url])
+        .output()
+        .context("curl not found")?;
+    if !output.status.success() {
+        bail!("could not reach GitHub releases for {repo}");
+    }
+    let body: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let release = match channel {
+        Channel::Stable => Some(&body),
+        Channel::Beta => body
+            .as_array()
+            .and_then(|releases| releases.iter().find(|r| r["draft"] != true)),
+    };
+    release
+        .and_then(|r| r["tag_name"].as_str())
+        .map(str::to_string)
+        .context("no published release found")
+}
+
+fn curl_to_file(url: &str, path: &Path) -> anyhow::Result<()> {
+    let status = Command::new("curl")
+        .args(["-fsSL", "-o"])
+        .arg(path)
+        .arg(url)
+        .status()
+        .context("curl not found")?;
+    if !status.success() {
+        bail!("download failed: {url}");
+    }
+    Ok(())
+}
+
+fn asset_names_for_platform() -> Option<&'static [&'static str]> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("macos", _) => Some(&[
+            "orbitdock-darwin-arm64.zip",
+            "orbitdock-darwin-universal.zip",
+        ]),
+        ("linux", "x86_64") => Some(&["orbitdock-linux-x86_64.zip"]),
+        ("linux", "aarch64") => Some(&["orbitdock-linux-aarch64.zip"]),
+        _ => None,
+    }
+}
+
+fn normalize_tag(version: &str) -> String {
+    if version.starts_with('v') {
+        version.to_string()
+    } else {
+        format!("v{version}")
+    }
+}
+
+/// The hash for `asset` from `sha256sum` output, or a bare hash.
+fn parse_checksum(contents: &str, asset: &str) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let hash = parts.next()?;
+        let valid = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+        match parts.next() {
+            Some(name) if name.trim_start_matches('*') != asset => None,
+            _ if valid => Some(hash.to_string()),
+            _ => None,
+        }
+    })
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, bytes)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_checksum_matches_asset_name() {
+        let hash = "a".repeat(64);
+        let other = "b".repeat(64);
+        let contents =
+            format!("{other}  orbitdock-linux-aarch64.zip\n{hash} *orbitdock-linux-x86_64.zip\n");
+        assert_eq!(
+            parse_checksum(&contents, "orbitdock-linux-x86_64.zip"),
+            Some(hash.clone())
+        );
+        assert_eq!(parse_checksum(&hash, "anything.zip"), Some(hash));
+        assert_eq!(parse_checksum("not-a-hash  x.zip", "x.zip"), None);
+    }
+
+    #[test]
+    fn sha256_hex_matches_known_digest() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn replace_binary_swaps_and_keeps_previous() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("orbitdock");
+        let new_binary = dir.path().join("new");
+        std::fs::write(&target, b"old").unwrap();
+        std::fs::write(&new_binary, b"new").unwrap();
+
+        replace_binary(&new_binary, &target).unwrap();
+
+        assert_eq!(std::fs::read(&target).unwrap(), b"new");
+        assert_eq!(
+            std::fs::read(dir.path().join("orbitdock.previous")).unwrap(),
+            b"old"
+        );
+        assert!(!dir.path().join(".orbitdock.upgrade").exists());
+    }
+}
@@ -0,0 +1,60 @@
+//! `orbitdock add-user` / `list-users` / `remove-user` — manage the users
+//! that auth tokens are issued to.
+
+use crate::auth_tokens;
+
+pub fn add(name: &str) -> anyhow::Result<()> {
+    let user = auth_tokens::add_user(name)?;
+
+    println!();
+    println!("  Added user {} ({})", user.name, user.id);
+    println!();
+    println!("  Issue them a token with:");
+    println!("    orbitdock generate-token --user {}", user.name);
+    println!();
+    Ok(())
+}
+
+pub fn list() -> anyhow::Result<()> {
+    let users = auth_tokens::list_users()?;
+
+    println!();
+    println!("  Users");
+    println!("  ─────");
+    println!();
+
+    if users.is_empty() {
+        println!("  No users found.");
+        println!();
+        return Ok(());
+    }
+
+    for user in users {
+        let status = if user.disabled_at.is_some() {
+            "disabled"
+        } else {
+            "active"
+        };
+        println!("  {}  [{}]  {}", user.name, status, user.id);
+        println!("    active tokens: {}", user.active_tokens);
+        println!("    created: {}", user.created_at);
+        if let Some(ref disabled) = user.disabled_at {
+            println!("    disabled: {}", disabled);
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+pub fn remove(name: &str) -> anyhow::Result<()> {
+    let disabled = auth_tokens::disable_user(name)?;
+    println!();
+    if disabled {
+        println!("  Disabled user {} and revoked their tokens", name.trim());
+    } else {
+        println!("  User {} was not found or already disabled", name.trim());
+    }
+    println!();
+    Ok(())
+}
@@ -0,0 +1,207 @@
+//! `orbitdock add-webhook` / `add-slack` / `add-discord` / `list-webhooks` /
+//! `remove-webhook` / `webhook-deliveries` — manage outbound webhooks.
+
+use crate::webhooks::{self, NewWebhook, WebhookEventKind, WebhookKind, WebhookRecord};
+
+const SLACK_POST_MESSAGE_URL: &str = "https://slack.com/api/chat.postMessage";
+
+pub fn add(url: &str, secret: Option<&str>, events: &[String]) -> anyhow::Result<()> {
+    let webhook = webhooks::add_webhook(NewWebhook {
+        kind: WebhookKind::Generic,
+        url: url.to_string(),
+        secret: secret.map(str::to_string),
+        channel: None,
+        events: parse_events(events)?,
+    })?;
+
+    print_added(&webhook);
+    if webhook.secret.is_some() {
+        println!(
+            "    signed: {} header (HMAC-SHA256 of the body)",
+            webhooks::SIGNATURE_HEADER
+        );
+    }
+    println!();
+    Ok(())
+}
+
+/// Slack via an incoming webhook URL, or a bot token + channel for threads.
+pub fn add_slack(
+    webhook_url: Option<&str>,
+    bot_token: Option<&str>,
+    channel: Option<&str>,
+    events: &[String],
+) -> anyhow::Result<()> {
+    let (url, secret, channel) = match (webhook_url, bot_token, channel) {
+        (Some(url), None, None) => (url, None, None),
+        (None, Some(token), Some(channel)) => (SLACK_POST_MESSAGE_URL, Some(token), Some(channel)),
+        _ => anyhow::bail!("pass either --webhook-url, or --bot-token with --channel"),
+    };
+    let webhook = webhooks::add_webhook(NewWebhook {
+        kind: WebhookKind::Slack,
+        url: url.to_string(),
+        secret: secret.map(str::to_string),
+        channel: channel.map(str::to_string),
+        events: parse_events(events)?,
+    })?;
+
+    print_added(&webhook);
+    if webhook.channel.is_none() {
+        println!("    note: incoming webhooks can't thread; use a bot token for threaded updates");
+    }
+    println!();
+    Ok(())
+}
+
+pub fn add_discord(url: &str, events: &[String]) -> anyhow::Result<()> {
+    let webhook = webhooks::add_webhook(NewWebhook {
+        kind: WebhookKind::Discord,
+        url: url.to_string(),
+        secret: None,
+        channel: None,
+        events: parse_events(events)?,
+    })?;
+
+    print_added(&webhook);
+    println!();
+    Ok(())
+}
+
+fn parse_events(events: &[String]) -> anyhow::Result<Option<Vec<WebhookEventKind>>> {
+    if events.is_empty() {
+        return Ok(None);
+    }
+    let mut kinds = Vec::with_capacity(events.len());
+    for name in events {
+        let Some(kind) = WebhookEventKind::parse(name) else {
+            anyhow::bail!(
+                "unknown webhook event {name:?} (expected one of: {})",
+                event_names(&WebhookEventKind::ALL)
+            );
+        };
+        kinds.push(kind);
+    }
+    Ok(Some(kinds))
+}
+
+fn print_added(webhook: &WebhookRecord) {
+    println!();
+    println!("  Added {} webhook {}", webhook.kind.as_str(), webhook.id);
+    match webhook.channel.as_deref() {
+        Some(channel) => println!("    channel: {channel}"),
+        None => println!("    url: {}", webhook.url),
+    }
+    println!("    events: {}", describe_events(webhook.events.as_deref()));
+}
+
+pub fn list() -> anyhow::Result<()> {
+    let webhooks = webhooks::list_webhooks()?;
+
+    println!();
+    println!("  Webhooks");
+    println!("  ────────");
+    println!();
+
+    if webhooks.is_empty() {
+        println!("  No webhooks configured.");
+        println!();
+        return Ok(());
+    }
+
+    for webhook in webhooks {
+        let status = if webhook.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        };
+        let target = webhook.channel.as_deref().unwrap_or(&webhook.url);
+        println!(
+            "  {}  [{}, {}]  {}",
+            webhook.id,
+            webhook.kind.as_str(),
+            status,
+            target
+        );
+        println!("    events: {}", describe_events(webhook.events.as_deref()));
+        if webhook.kind == WebhookKind::Generic {
+            println!(
+                "    signed: {}",
+                if webhook.secret.is_some() {
+                    "yes"
+                } else {
+                    "no"
+                }
+            );
+        }
+        println!("    created: {}", webhook.created_at);
+        println!();
+    }
+
+    Ok(())
+}
+
+pub fn remove(webhook_id: &str) -> anyhow::Result<()> {
+    let removed = webhooks::remove_webhook(webhook_id)?;
+    println!();
+    if removed {
+        println!("  Removed webhook {}", webhook_id.trim());
+    } else {
+        println!("  Webhook {} was not found", webhook_id.trim());
+    }
+    println!();
+    Ok(())
+}
+
+pub fn deliveries(webhook_id: Option<&str>, limit: usize) -> anyhow::Result<()> {
+    let deliveries = webhooks::list_deliveries(webhook_id, limit)?;
+
+    println!();
+    if deliveries.is_empty() {
+        println!("  No webhook deliveries recorded.");
+        println!();
+        return Ok(());
+    }
+
+    for delivery in deliveries {
+        let outcome = match (delivery.success, delivery.status_code) {
+            (true, Some(code)) => format!("ok {code}"),
+            (true, None) => "ok".to_string(),
+            (false, _) => format!(
+                "failed: {}",
+                delivery.error.as_deref().unwrap_or("unknown error")
+            ),
+        };
+        println!(
+            "  {}  {}  {} #{}  {}  ({} ms)",
+            delivery.created_at,
+            delivery.webhook_id,
+            delivery.event,
+            delivery.attempt,
+            outcome,
+            delivery.duration_ms
+        );
+        if let Some(session_id) = delivery.session_id.as_deref() {
+            println!(
+                "    delivery: {}  session: {}",
+                delivery.delivery_id, session_id
+            );
+        }
+    }
+    println!();
+    Ok(())
+}
+
+fn describe_events(events: Option<&[WebhookEventKind]>) -> String {
+    match events {
+        None => "all".to_string(),
+        Some(events) => event_names(events),
+    }
+}
+
+fn event_names(events: &[WebhookEventKind]) -> String {
+    events
+        .iter()
+        .map(|kind| kind.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -4,18 +4,21 @@
 
 use std::sync::Arc;
 
-use orbitdock_protocol::ServerMessage;
+use orbitdock_protocol::{
+    ErrorCategory, Provider, ServerMessage, SessionError, SessionStatus, WorkStatus,
+};
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::persistence::PersistCommand;
+use crate::reconnect::{self, Reconnect};
 use crate::session::SessionHandle;
 use crate::session_actor::SessionActorHandle;
 use crate::session_command::SessionCommand;
 use crate::session_command_handler::{
-    dispatch_connector_event, dispatch_transition_input, handle_session_command, is_turn_ending,
-    spawn_interrupt_watchdog,
+    coalesced_delta_due, dispatch_connector_event, dispatch_transition_input,
+    handle_session_command, is_turn_ending, spawn_interrupt_watchdog,
 };
 use crate::state::SessionRegistry;
 
@@ -34,6 +37,7 @@ pub fn start_event_loop(
 ) -> (SessionActorHandle, mpsc::Sender<CodexAction>) {
     let (action_tx, mut action_rx) = mpsc::channel::<CodexAction>(100);
     let (command_tx, mut command_rx) = mpsc::channel::<SessionCommand>(256);
+    handle.set_command_tx(&command_tx);
 
     let snapshot = handle.snapshot_arc();
     let id = handle.id().to_string();
@@ -51,10 +55,55 @@ pub fn start_event_loop(
         // Watchdog channel for synthetic events (interrupt timeout)
         let (watchdog_tx, mut watchdog_rx) = mpsc::channel(4);
         let mut interrupt_watchdog: Option<JoinHandle<()>> = None;
+        let mut reconnect = Reconnect::default();
+        // Set once the session is being ended on purpose, so the event stream
+        // closing isn't mistaken for a crash.
+        let mut ending = false;
+        let mut connector_alive = true;
 
         loop {
+            let delta_deadline = session_handle.coalesced_delta_deadline();
             tokio::select! {
-                Some(event) = event_rx.recv() => {
+                event = event_rx.recv(), if connector_alive => {
+                    let Some(event) = event else {
+                        // The connector stopped sending events. Unless the
+                        // session is being ended, resume the thread in place.
+                        connector_alive = false;
+                        let live = session_handle.snapshot_arc().load_full().status
+                            == SessionStatus::Active;
+                        if !ending && live {
+                            if let Some(msg) = reconnect.schedule(&session_id, "event_stream_closed") {
+                                warn!(
+                                    component = "codex_connector",
+                                    event = "codex.reconnect.scheduled",
+                                    session_id = %session_id,
+                                    "Codex event stream closed unexpectedly, scheduling reconnect"
+                                );
+                                session_handle.broadcast(msg);
+                            } else {
+                                end_after_reconnect_gave_up(
+                                    &session_id, "event_stream_closed", &mut session_handle, &persist,
+                                ).await;
+                            }
+                        }
+                        continue;
+                    };
+                    reconnect.connector_alive();
+
+                    // Rate limits are account-wide; the auth service keeps
+                    // them and says when this session should be warned.
+                    if let orbitdock_connector_core::ConnectorEvent::AccountUsageUpdated(usage) = event {
+                        if let Some(info) = state.codex_auth().record_usage(usage).await {
+                            dispatch_connector_event(
+                                &session_id,
+                                orbitdock_connector_core::ConnectorEvent::RateLimitEvent { info },
+                                &mut session_handle,
+                                &persist,
+                            ).await;
+                        }
+                        continue;
+                    }
+
                     if is_turn_ending(&event) {
                         if let Some(h) = interrupt_watchdog.take() { h.abort(); }
                     }
@@ -99,6 +148,10 @@ pub fn start_event_loop(
                 }
 
                 Some(action) = action_rx.recv() => {
+                    if matches!(action, CodexAction::EndSession) {
+                        ending = true;
+                    }
+
                     match action {
                         CodexAction::SteerTurn {
                             content,
@@ -171,7 +224,10 @@ pub fn start_event_loop(
                                     dispatch_connector_event(
                                         &session_id,
                                         orbitdock_connector_core::ConnectorEvent::Error(
-                                            format!("Interrupt failed: {e}"),
+                                            SessionError::new(
+                                                ErrorCategory::Process,
+                                                format!("Interrupt failed: {e}"),
+                                            ),
                                         ),
                                         &mut session_handle,
                                         &persist,
@@ -197,6 +253,35 @@ pub fn start_event_loop(
                     handle_session_command(cmd, &mut session_handle, &persist).await;
                 }
 
+                _ = coalesced_delta_due(delta_deadline), if delta_deadline.is_some() => {
+                    session_handle.flush_coalesced_delta();
+                }
+
+                _ = reconnect::reconnect_due(reconnect.due()), if reconnect.is_pending() && !ending => {
+                    let reason = reconnect.begin();
+                    if let Some(rx) = reconnect_connector(
+                        &mut session, &mut session_handle, &persist, &state,
+                    ).await {
+                        event_rx = rx;
+                        connector_alive = true;
+                        session_handle.broadcast(reconnect.succeeded(&session_id));
+                        crate::session_diagnostics::record(
+                            &persist,
+                            &session_id,
+                            "connector_reconnected",
+                            crate::session_diagnostics::connector_error(
+                                Provider::Codex, "reconnect", &reason,
+                            ),
+                        ).await;
+                    } else if let Some(msg) = reconnect.schedule(&session_id, &reason) {
+                        session_handle.broadcast(msg);
+                    } else {
+                        end_after_reconnect_gave_up(
+                            &session_id, &reason, &mut session_handle, &persist,
+                        ).await;
+                    }
+                }
+
                 else => break,
             }
         }
@@ -216,3 +301,95 @@ pub fn start_event_loop(
 
     (actor_handle, action_tx)
 }
+
+/// Replace the connector with a resume of the same Codex thread, keeping the
+/// session's model, approval policy, and sandbox. Returns the new event
+/// receiver, or `None` if the resume failed.
+async fn reconnect_connector(
+    session: &mut CodexSession,
+    session_handle: &mut SessionHandle,
+    persist: &mpsc::Sender<PersistCommand>,
+    state: &Arc<SessionRegistry>,
+) -> Option<mpsc::Receiver<orbitdock_connector_core::ConnectorEvent>> {
+    let session_id = session.session_id.clone();
+    let snap = session_handle.snapshot_arc().load_full();
+    let thread_id = session.thread_id().to_string();
+    info!(
+        component = "codex_connector",
+        event = "codex.reconnect.attempt",
+        session_id = %session_id,
+        thread_id = %thread_id,
+        "Resuming Codex thread"
+    );
+    match CodexSession::resume(
+        session_id.clone(),
+        &snap.project_path,
+        &thread_id,
+        snap.model.as_deref(),
+        snap.approval_policy.as_deref(),
+        snap.sandbox_mode.as_deref(),
+        crate::project_config::instructions(&snap.project_path).as_deref(),
+        &state.session_mcp_servers(&session_id),
+    )
+    .await
+    {
+        Ok(mut replacement) => {
+            let event_rx = replacement.connector.take_event_rx().unwrap();
+            *session = replacement;
+            if snap.work_status == WorkStatus::Working {
+                dispatch_connector_event(
+                    &session_id,
+                    orbitdock_connector_core::ConnectorEvent::TurnAborted {
+                        reason: "Codex connector reconnected".to_string(),
+                    },
+                    session_handle,
+                    persist,
+                )
+                .await;
+            }
+            Some(event_rx)
+        }
+        Err(e) => {
+            error!(
+                component = "codex_connector",
+                event = "codex.reconnect.failed",
+                session_id = %session_id,
+                error = %e,
+                "Failed to resume Codex thread"
+            );
+            None
+        }
+    }
+}
+
+/// Surface the give-up error and end the session.
+async fn end_after_reconnect_gave_up(
+    session_id: &str,
+    reason: &str,
+    session_handle: &mut SessionHandle,
+    persist: &mpsc::Sender<PersistCommand>,
+) {
+    crate::session_diagnostics::record(
+        persist,
+        session_id,
+        "connector_gave_up",
+        crate::session_diagnostics::connector_error(Provider::Codex, "reconnect", reason),
+    )
+    .await;
+    dispatch_connector_event(
+        session_id,
+        orbitdock_connector_core::ConnectorEvent::Error(reconnect::gave_up_error("Codex", reason)),
+        session_handle,
+        persist,
+    )
+    .await;
+    dispatch_connector_event(
+        session_id,
+        orbitdock_connector_core::ConnectorEvent::SessionEnded {
+            reason: reason.to_string(),
+        },
+        session_handle,
+        persist,
+    )
+    .await;
+}
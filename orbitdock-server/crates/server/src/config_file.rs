@@ -0,0 +1,1012 @@
+//! Server configuration file (`<data dir>/config.toml`).
+//!
+//! Most keys mirror an `ORBITDOCK_*` environment variable. [`load_into_env`]
+//! runs before flags are parsed and copies each key into the environment
+//! unless that variable is already set, so precedence is flag > env > file.
+//! Connector defaults have no env equivalent: they fill in whatever a
+//! `CreateSession` request leaves out.
+//!
+//! `ORBITDOCK_SERVER_CONFIG` points at a different file.
+//!
+//! [`reload`] (SIGHUP or an admin `ReloadConfig`) re-reads the file without
+//! touching the environment. Settings read through [`var`] pick up the new
+//! values; the log filter, log rotation, and hook rate limit are re-applied;
+//! everything read once at startup is reported as needing a restart.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use orbitdock_protocol::{Provider, SnapshotTruncation};
+use serde::Deserialize;
+
+use crate::ai_naming::NamingSection;
+use crate::budgets::BudgetsSection;
+use crate::editors::EditorSection;
+use crate::github_issues::GithubSection;
+use crate::listen::BindTarget;
+use crate::pricing::ModelPrice;
+use crate::semantic_search::EmbeddingsSection;
+use crate::tickets::TicketsSection;
+use crate::transcription::TranscriptionSection;
+use crate::user_hooks::UserHook;
+
+pub const FILE_NAME: &str = "config.toml";
+
+static PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+static LOADED: RwLock<Option<Arc<ServerConfig>>> = RwLock::new(None);
+/// Variables `load_into_env` set from the file (as opposed to the real environment).
+static EXPORTED: RwLock<Vec<&'static str>> = RwLock::new(Vec::new());
+
+/// Config key for each environment variable the file can set.
+const KEYS: &[(&str, &str)] = &[
+    ("ORBITDOCK_DATA_DIR", "data_dir"),
+    ("ORBITDOCK_BIND_ADDR", "server.bind"),
+    ("ORBITDOCK_TAILSCALE", "server.tailscale"),
+    ("ORBITDOCK_SERVER_SECONDARY", "server.secondary"),
+    ("ORBITDOCK_TLS_CERT", "server.tls_cert"),
+    ("ORBITDOCK_TLS_KEY", "server.tls_key"),
+    ("ORBITDOCK_CORS_ALLOWED_ORIGINS", "server.cors_allowed_origins"),
+    ("ORBITDOCK_AUTH_TOKEN", "auth.token"),
+    ("ORBITDOCK_HOOK_SECRET", "auth.hook_secret"),
+    (
+        "ORBITDOCK_ALLOW_INSECURE_NO_AUTH",
+        "auth.allow_insecure_no_auth",
+    ),
+    ("ORBITDOCK_SERVER_LOG_FILTER", "logging.filter"),
+    ("ORBITDOCK_SERVER_LOG_FORMAT", "logging.format"),
+    ("ORBITDOCK_SERVER_LOG_MAX_BYTES", "retention.log_max_bytes"),
+    ("ORBITDOCK_SERVER_LOG_MAX_FILES", "retention.log_max_files"),
+    ("ORBITDOCK_SERVER_LOG_COMPRESS", "retention.log_compress"),
+    ("ORBITDOCK_MEDIA_MAX_AGE_DAYS", "retention.media_max_age_days"),
+    (
+        "ORBITDOCK_JOURNAL_MAX_EVENTS",
+        "retention.journal_max_events",
+    ),
+    (
+        "ORBITDOCK_WAL_CHECKPOINT_SECS",
+        "database.wal_checkpoint_secs",
+    ),
+    ("ORBITDOCK_WAL_CHECKPOINT_MB", "database.wal_checkpoint_mb"),
+    ("ORBITDOCK_TURN_SUMMARIES", "notifications.turn_summaries"),
+    ("ORBITDOCK_PUSH_RELAY_URL", "notifications.push_relay_url"),
+    ("ORBITDOCK_PUSH_RELAY_TOKEN", "notifications.push_relay_token"),
+    ("ORBITDOCK_APNS_KEY_PATH", "notifications.apns_key_path"),
+    ("ORBITDOCK_APNS_KEY_ID", "notifications.apns_key_id"),
+    ("ORBITDOCK_APNS_TEAM_ID", "notifications.apns_team_id"),
+    ("ORBITDOCK_APNS_TOPIC", "notifications.apns_topic"),
+    ("ORBITDOCK_CODEX_PATH", "connectors.codex_path"),
+    ("ORBITDOCK_DISABLE_CODEX_WATCHER", "connectors.codex_watcher"),
+    ("ORBITDOCK_CONNECTOR_AUTO_RESTART", "connectors.auto_restart"),
+    ("ORBITDOCK_HOOK_RATE_LIMIT", "limits.hook_rate_limit"),
+    ("ORBITDOCK_WS_RATE_LIMIT", "limits.ws_rate_limit"),
+    ("ORBITDOCK_CONNECTOR_MAX_RSS_MB", "limits.connector_max_rss_mb"),
+    (
+        "ORBITDOCK_CONNECTOR_MAX_CPU_PERCENT",
+        "limits.connector_max_cpu_percent",
+    ),
+    ("ORBITDOCK_SHELL_ALLOWLIST", "limits.shell_allowlist"),
+    (
+        "ORBITDOCK_SHELL_ALLOWLIST_AGENTS",
+        "limits.shell_allowlist_agents",
+    ),
+    ("ORBITDOCK_SNAPSHOT_MAX_MESSAGES", "snapshots.max_messages"),
+    (
+        "ORBITDOCK_SNAPSHOT_MAX_CONTENT_CHARS",
+        "snapshots.max_content_chars",
+    ),
+    ("ORBITDOCK_SNAPSHOT_TRUNCATION", "snapshots.truncation"),
+];
+
+/// Variables that take effect on [`reload`] without a restart.
+const LIVE: &[&str] = &[
+    "ORBITDOCK_SERVER_LOG_FILTER",
+    "ORBITDOCK_SERVER_LOG_MAX_BYTES",
+    "ORBITDOCK_SERVER_LOG_MAX_FILES",
+    "ORBITDOCK_SERVER_LOG_COMPRESS",
+    "ORBITDOCK_MEDIA_MAX_AGE_DAYS",
+    "ORBITDOCK_JOURNAL_MAX_EVENTS",
+    "ORBITDOCK_WAL_CHECKPOINT_SECS",
+    "ORBITDOCK_WAL_CHECKPOINT_MB",
+    "ORBITDOCK_TURN_SUMMARIES",
+    "ORBITDOCK_HOOK_RATE_LIMIT",
+    "ORBITDOCK_HOOK_SECRET",
+    "ORBITDOCK_WS_RATE_LIMIT",
+    "ORBITDOCK_SNAPSHOT_MAX_MESSAGES",
+    "ORBITDOCK_SNAPSHOT_MAX_CONTENT_CHARS",
+    "ORBITDOCK_SNAPSHOT_TRUNCATION",
+];
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Only honored from the default `~/.orbitdock/config.toml`.
+    pub data_dir: Option<PathBuf>,
+    pub server: ServerSection,
+    pub auth: AuthSection,
+    pub logging: LoggingSection,
+    pub retention: RetentionSection,
+    pub database: DatabaseSection,
+    pub notifications: NotificationsSection,
+    pub connectors: ConnectorsSection,
+    pub limits: LimitsSection,
+    pub snapshots: SnapshotsSection,
+    pub context: ContextSection,
+    pub naming: NamingSection,
+    pub embeddings: EmbeddingsSection,
+    pub transcription: TranscriptionSection,
+    /// Per-model token rates keyed by model-name substring.
+    pub pricing: BTreeMap<String, ModelPrice>,
+    pub budgets: BudgetsSection,
+    pub github: GithubSection,
+    pub tickets: TicketsSection,
+    pub editor: EditorSection,
+    /// `[[hooks]]`: scripts run on session events; see `user_hooks`.
+    pub hooks: Vec<UserHook>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSection {
+    pub bind: Option<String>,
+    pub tailscale: Option<bool>,
+    pub secondary: Option<bool>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    pub cors_allowed_origins: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthSection {
+    /// Prefer `orbitdock generate-token`; a token here sits in plain text.
+    pub token: Option<String>,
+    pub allow_insecure_no_auth: Option<bool>,
+    /// Shared secret `/api/hook` requests must be signed with.
+    pub hook_secret: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSection {
+    pub filter: Option<String>,
+    pub format: Option<String>,
+}
+
+/// How many rotated server logs are kept and how large each may grow.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionSection {
+    pub log_max_bytes: Option<u64>,
+    pub log_max_files: Option<usize>,
+    pub log_compress: Option<bool>,
+    /// Delete images and attachments of sessions ended this many days ago.
+    pub media_max_age_days: Option<u64>,
+    /// Broadcasts kept per session for replay; 0 stops recording them.
+    pub journal_max_events: Option<u64>,
+}
+
+/// WAL checkpoints run by the persistence writer; see `wal_checkpoint`.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseSection {
+    /// Checkpoint at least this often; 0 turns the timer off.
+    pub wal_checkpoint_secs: Option<u64>,
+    /// Checkpoint once the WAL is this large; 0 turns the size check off.
+    pub wal_checkpoint_mb: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsSection {
+    pub turn_summaries: Option<bool>,
+    pub push_relay_url: Option<String>,
+    pub push_relay_token: Option<String>,
+    pub apns_key_path: Option<PathBuf>,
+    pub apns_key_id: Option<String>,
+    pub apns_team_id: Option<String>,
+    pub apns_topic: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConnectorsSection {
+    pub codex_path: Option<PathBuf>,
+    pub codex_watcher: Option<bool>,
+    /// Codex homes to watch besides `$CODEX_HOME` / `~/.codex`.
+    pub codex_homes: Option<Vec<PathBuf>>,
+    /// Claude homes to watch besides `$CLAUDE_CONFIG_DIR` / `~/.claude`.
+    pub claude_homes: Option<Vec<PathBuf>>,
+    pub auto_restart: Option<bool>,
+    /// Repos whose `.orbitdock.toml` may loosen the safety defaults below
+    /// and pre-approve tools.
+    pub trusted_projects: Option<Vec<PathBuf>>,
+    pub claude: ConnectorDefaults,
+    pub codex: ConnectorDefaults,
+}
+
+/// Used when `CreateSession` doesn't specify a value.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConnectorDefaults {
+    pub model: Option<String>,
+    pub effort: Option<String>,
+    /// Codex only.
+    pub approval_policy: Option<String>,
+    /// Codex only.
+    pub sandbox_mode: Option<String>,
+    /// Claude only.
+    pub permission_mode: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsSection {
+    pub hook_rate_limit: Option<f64>,
+    pub ws_rate_limit: Option<f64>,
+    pub connector_max_rss_mb: Option<u64>,
+    pub connector_max_cpu_percent: Option<f64>,
+    pub shell_allowlist: Option<Vec<String>>,
+    pub shell_allowlist_agents: Option<bool>,
+}
+
+/// Session snapshots sent on subscribe; clients can override these per subscription.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnapshotsSection {
+    pub max_messages: Option<usize>,
+    pub max_content_chars: Option<usize>,
+    pub truncation: Option<SnapshotTruncation>,
+}
+
+/// Warnings as a session's context window fills; see `context_warnings`.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContextSection {
+    /// Percentages of the context window that warn; empty turns warnings off.
+    pub warn_at_percent: Option<Vec<f64>>,
+    /// Also send warnings as `context_warning` webhook events.
+    pub notify: Option<bool>,
+}
+
+impl ServerConfig {
+    pub fn parse(contents: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents)
+                .with_context(|| format!("invalid {}", path.display()))
+                .map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("could not read {}", path.display())),
+        }
+    }
+
+    pub fn connector_defaults(&self, provider: Provider) -> &ConnectorDefaults {
+        match provider {
+            Provider::Claude => &self.connectors.claude,
+            Provider::Codex => &self.connectors.codex,
+        }
+    }
+
+    /// Whether the project config at `path` is in a trusted project.
+    pub fn trusts_project(&self, path: &Path) -> bool {
+        self.connectors
+            .trusted_projects
+            .iter()
+            .flatten()
+            .any(|root| path.starts_with(crate::provider_homes::expand_home(root)))
+    }
+
+    /// Environment variables this file sets, in the format each one expects.
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        fn flag(value: bool) -> String {
+            if value { "1" } else { "0" }.to_string()
+        }
+        fn path(value: &Path) -> String {
+            value.display().to_string()
+        }
+
+        let mut vars = Vec::new();
+        let mut set = |name: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                vars.push((name, value));
+            }
+        };
+
+        set("ORBITDOCK_DATA_DIR", self.data_dir.as_deref().map(path));
+
+        let server = &self.server;
+        set("ORBITDOCK_BIND_ADDR", server.bind.clone());
+        set(
+            "ORBITDOCK_TAILSCALE",
+            server.tailscale.map(|v| v.to_string()),
+        );
+        set(
+            "ORBITDOCK_SERVER_SECONDARY",
+            server.secondary.map(|v| v.to_string()),
+        );
+        set("ORBITDOCK_TLS_CERT", server.tls_cert.as_deref().map(path));
+        set("ORBITDOCK_TLS_KEY", server.tls_key.as_deref().map(path));
+        set(
+            "ORBITDOCK_CORS_ALLOWED_ORIGINS",
+            server.cors_allowed_origins.as_ref().map(|o| o.join(",")),
+        );
+
+        set("ORBITDOCK_AUTH_TOKEN", self.auth.token.clone());
+        set("ORBITDOCK_HOOK_SECRET", self.auth.hook_secret.clone());
+        set(
+            "ORBITDOCK_ALLOW_INSECURE_NO_AUTH",
+            self.auth.allow_insecure_no_auth.map(|v| v.to_string()),
+        );
+
+        set("ORBITDOCK_SERVER_LOG_FILTER", self.logging.filter.clone());
+        set("ORBITDOCK_SERVER_LOG_FORMAT", self.logging.format.clone());
+
+        let retention = &self.retention;
+        set(
+            "ORBITDOCK_SERVER_LOG_MAX_BYTES",
+            retention.log_max_bytes.map(|v| v.to_string()),
+        );
+        set(
+            "ORBITDOCK_SERVER_LOG_MAX_FILES",
+            retention.log_max_files.map(|v| v.to_string()),
+        );
+        set(
+            "ORBITDOCK_SERVER_LOG_COMPRESS",
+            retention.log_compress.map(flag),
+        );
+        set(
+            "ORBITDOCK_MEDIA_MAX_AGE_DAYS",
+            retention.media_max_age_days.map(|v| v.to_string()),
+        );
+        set(
+            "ORBITDOCK_JOURNAL_MAX_EVENTS",
+            retention.journal_max_events.map(|v| v.to_string()),
+        );
+
+        let database = &self.database;
+        set(
+            "ORBITDOCK_WAL_CHECKPOINT_SECS",
+            database.wal_checkpoint_secs.map(|v| v.to_string()),
+        );
+        set(
+            "ORBITDOCK_WAL_CHECKPOINT_MB",
+            database.wal_checkpoint_mb.map(|v| v.to_string()),
+        );
+
+        let notifications = &self.notifications;
+        set(
+            "ORBITDOCK_TURN_SUMMARIES",
+            notifications.turn_summaries.map(flag),
+        );
+        set(
+            "ORBITDOCK_PUSH_RELAY_URL",
+            notifications.push_relay_url.clone(),
+        );
+        set(
+            "ORBITDOCK_PUSH_RELAY_TOKEN",
+            notifications.push_relay_token.clone(),
+        );
+        set(
+            "ORBITDOCK_APNS_KEY_PATH",
+            notifications.apns_key_path.as_deref().map(path),
+        );
+        set("ORBITDOCK_APNS_KEY_ID", notifications.apns_key_id.clone());
+        set("ORBITDOCK_APNS_TEAM_ID", notifications.apns_team_id.clone());
+        set("ORBITDOCK_APNS_TOPIC", notifications.apns_topic.clone());
+
+        let connectors = &self.connectors;
+        set(
+            "ORBITDOCK_CODEX_PATH",
+            connectors.codex_path.as_deref().map(path),
+        );
+        set(
+            "ORBITDOCK_DISABLE_CODEX_WATCHER",
+            connectors.codex_watcher.map(|on| flag(!on)),
+        );
+        set(
+            "ORBITDOCK_CONNECTOR_AUTO_RESTART",
+            connectors.auto_restart.map(flag),
+        );
+
+        let limits = &self.limits;
+        set(
+            "ORBITDOCK_HOOK_RATE_LIMIT",
+            limits.hook_rate_limit.map(|v| v.to_string()),
+        );
+        set(
+            "ORBITDOCK_WS_RATE_LIMIT",
+            limits.ws_rate_limit.map(|v| v.to_string()),
+        );
+        set(
+            "ORBITDOCK_CONNECTOR_MAX_RSS_MB",
+            limits.connector_max_rss_mb.map(|v| v.to_string()),
+        );
+        set(
+            "ORBITDOCK_CONNECTOR_MAX_CPU_PERCENT",
+            limits.connector_max_cpu_percent.map(|v| v.to_string()),
+        );
+        set(
+            "ORBITDOCK_SHELL_ALLOWLIST",
+            limits.shell_allowlist.as_ref().map(|l| l.join(",")),
+        );
+        set(
+            "ORBITDOCK_SHELL_ALLOWLIST_AGENTS",
+            limits.shell_allowlist_agents.map(flag),
+        );
+
+        let snapshots = &self.snapshots;
+        set(
+            "ORBITDOCK_SNAPSHOT_MAX_MESSAGES",
+            snapshots.max_messages.map(|v| v.to_string()),
+        );
+        set(
+            "ORBITDOCK_SNAPSHOT_MAX_CONTENT_CHARS",
+            snapshots.max_content_chars.map(|v| v.to_string()),
+        );
+        set(
+            "ORBITDOCK_SNAPSHOT_TRUNCATION",
+            snapshots
+                .truncation
+                .map(|t| crate::snapshot_compaction::truncation_name(t).to_string()),
+        );
+
+        vars
+    }
+
+    /// Problems that parsing alone doesn't catch.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if let Some(bind) = &self.server.bind {
+            if let Err(e) = bind.parse::<BindTarget>() {
+                problems.push(format!("server.bind: {e}"));
+            }
+        }
+        match (&self.server.tls_cert, &self.server.tls_key) {
+            (Some(_), None) => problems.push("server.tls_cert needs server.tls_key".into()),
+            (None, Some(_)) => problems.push("server.tls_key needs server.tls_cert".into()),
+            _ => {}
+        }
+        for (key, file) in [
+            ("server.tls_cert", &self.server.tls_cert),
+            ("server.tls_key", &self.server.tls_key),
+            (
+                "notifications.apns_key_path",
+                &self.notifications.apns_key_path,
+            ),
+            ("connectors.codex_path", &self.connectors.codex_path),
+        ] {
+            if let Some(file) = file {
+                if !file.exists() {
+                    problems.push(format!("{key}: {} does not exist", file.display()));
+                }
+            }
+        }
+        if let Some(format) = &self.logging.format {
+            if !matches!(format.to_ascii_lowercase().as_str(), "json" | "pretty") {
+                problems.push(format!(
+                    "logging.format: expected \"json\" or \"pretty\", got \"{format}\""
+                ));
+            }
+        }
+        for (key, value) in [
+            ("limits.hook_rate_limit", self.limits.hook_rate_limit),
+            ("limits.ws_rate_limit", self.limits.ws_rate_limit),
+            (
+                "limits.connector_max_cpu_percent",
+                self.limits.connector_max_cpu_percent,
+            ),
+        ] {
+            if value.is_some_and(|v| v < 0.0 || !v.is_finite()) {
+                problems.push(format!("{key}: must be a non-negative number"));
+            }
+        }
+        for (model, price) in &self.pricing {
+            let rates = [Some(price.input), Some(price.output), price.cached];
+            if rates
+                .into_iter()
+                .flatten()
+                .any(|rate| rate < 0.0 || !rate.is_finite())
+            {
+                problems.push(format!(
+                    "pricing.{model}: rates must be non-negative numbers"
+                ));
+            }
+        }
+        if let Some(thresholds) = &self.context.warn_at_percent {
+            if thresholds
+                .iter()
+                .any(|percent| !(*percent > 0.0 && *percent <= 100.0))
+            {
+                problems.push("context.warn_at_percent: must be between 0 and 100".into());
+            }
+        }
+        problems.extend(self.budgets.problems());
+        problems.extend(self.naming.problems());
+        problems.extend(self.embeddings.problems());
+        problems.extend(self.transcription.problems());
+        problems.extend(self.github.problems());
+        problems.extend(self.tickets.problems());
+        problems.extend(self.editor.problems());
+        problems.extend(crate::user_hooks::problems(&self.hooks));
+        if self.snapshots.max_messages == Some(0) {
+            problems.push("snapshots.max_messages: must be at least 1".into());
+        }
+        if self.connectors.claude.approval_policy.is_some()
+            || self.connectors.claude.sandbox_mode.is_some()
+        {
+            problems.push(
+                "connectors.claude: approval_policy and sandbox_mode only apply to Codex".into(),
+            );
+        }
+        if self.connectors.codex.permission_mode.is_some() {
+            problems.push("connectors.codex: permission_mode only applies to Claude".into());
+        }
+
+        problems
+    }
+}
+
+/// `ORBITDOCK_SERVER_CONFIG`, else `config.toml` in the data directory named
+/// by `--data-dir`, `ORBITDOCK_DATA_DIR`, or `~/.orbitdock`.
+fn config_path(args: &[String]) -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("ORBITDOCK_SERVER_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let data_dir = data_dir_arg(args)
+        .or_else(|| std::env::var("ORBITDOCK_DATA_DIR").ok().map(PathBuf::from))
+        .or_else(|| dirs::home_dir().map(|home| home.join(".orbitdock")))?;
+    Some(data_dir.join(FILE_NAME))
+}
+
+fn data_dir_arg(args: &[String]) -> Option<PathBuf> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--data-dir" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(value) = arg.strip_prefix("--data-dir=") {
+            return Some(PathBuf::from(value));
+        }
+    }
+    None
+}
+
+/// Load the config file and export its settings to unset env vars.
+///
+/// Must run before flags are parsed and before any threads start. A broken
+/// file is reported and ignored so client commands keep working.
+pub fn load_into_env() {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(path) = config_path(&args) else {
+        return;
+    };
+    *PATH.write().expect("config lock poisoned") = Some(path.clone());
+    let config = match ServerConfig::load(&path) {
+        Ok(Some(config)) => config,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("Warning: ignoring {}: {e:#}", path.display());
+            eprintln!("Run `orbitdock config validate` for details.");
+            return;
+        }
+    };
+
+    let mut exported = Vec::new();
+    for (name, value) in config.env_vars() {
+        if std::env::var_os(name).is_none() {
+            std::env::set_var(name, value);
+            exported.push(name);
+        }
+    }
+    *EXPORTED.write().expect("config lock poisoned") = exported;
+    *LOADED.write().expect("config lock poisoned") = Some(Arc::new(config));
+}
+
+pub fn exported_from_file(name: &str) -> bool {
+    EXPORTED
+        .read()
+        .expect("config lock poisoned")
+        .contains(&name)
+}
+
+/// Where `load_into_env` looked for the config file.
+pub fn path() -> Option<PathBuf> {
+    PATH.read().expect("config lock poisoned").clone()
+}
+
+/// The config as last loaded or reloaded, or defaults if there was none.
+pub fn current() -> Arc<ServerConfig> {
+    LOADED
+        .read()
+        .expect("config lock poisoned")
+        .clone()
+        .unwrap_or_default()
+}
+
+/// A setting's current value: the real environment wins, then the config
+/// file as of the last reload.
+pub fn var(name: &str) -> Option<String> {
+    if !exported_from_file(name) {
+        if let Ok(value) = std::env::var(name) {
+            return Some(value);
+        }
+    }
+    current()
+        .env_vars()
+        .into_iter()
+        .find(|(var, _)| *var == name)
+        .map(|(_, value)| value)
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// Changed keys now in effect.
+    pub applied: Vec<String>,
+    /// Changed keys that are only read at startup.
+    pub restart_required: Vec<String>,
+    /// Changed keys ignored because the environment sets them.
+    pub overridden: Vec<String>,
+}
+
+impl ReloadReport {
+    fn changed(&self, key: &str) -> bool {
+        self.applied.iter().any(|k| k.starts_with(key))
+    }
+}
+
+/// Re-read the config file and apply the settings that can change live.
+///
+/// A file that fails to parse or validate leaves the running config alone.
+pub fn reload() -> anyhow::Result<ReloadReport> {
+    let path = path().context("could not determine the config file location")?;
+    let config = ServerConfig::load(&path)?.unwrap_or_default();
+    let problems = config.problems();
+    if !problems.is_empty() {
+        anyhow::bail!("{}: {}", path.display(), problems.join("; "));
+    }
+
+    let report = diff(&current(), &config, |name| {
+        std::env::var_os(name).is_some() && !exported_from_file(name)
+    });
+    *LOADED.write().expect("config lock poisoned") = Some(Arc::new(config));
+
+    if report.changed("logging.filter") {
+        crate::logging::reload_filter()?;
+    }
+    if report.changed("retention.") {
+        crate::log_rotation::update_policy(crate::log_rotation::RotationPolicy::from_env());
+    }
+    if report.changed("limits.hook_rate_limit") {
+        crate::rate_limit::reload_hook_limit();
+    }
+    if report.changed("limits.ws_rate_limit") {
+        crate::rate_limit::reload_ws_limit();
+    }
+
+    tracing::info!(
+        component = "config",
+        event = "config.reloaded",
+        path = %path.display(),
+        applied = ?report.applied,
+        restart_required = ?report.restart_required,
+        overridden = ?report.overridden,
+        "Config file reloaded"
+    );
+    Ok(report)
+}
+
+/// Reload the config file on every SIGHUP.
+#[cfg(unix)]
+pub async fn start_sighup_loop() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let Ok(mut hangup) = signal(SignalKind::hangup()) else {
+        return;
+    };
+    while hangup.recv().await.is_some() {
+        if let Err(e) = tokio::task::spawn_blocking(reload)
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result)
+        {
+            tracing::warn!(
+                component = "config",
+                event = "config.reload_failed",
+                error = %format!("{e:#}"),
+                "Config reload failed; keeping the current config"
+            );
+        }
+    }
+}
+
+/// Sort the keys that differ between two configs by how they take effect.
+fn diff(old: &ServerConfig, new: &ServerConfig, in_env: impl Fn(&str) -> bool) -> ReloadReport {
+    let old_vars = old.env_vars();
+    let new_vars = new.env_vars();
+    let value = |vars: &[(&str, String)], name: &str| {
+        vars.iter()
+            .find(|(var, _)| *var == name)
+            .map(|(_, value)| value.clone())
+    };
+
+    let mut report = ReloadReport::default();
+    for (name, key) in KEYS {
+        if value(&old_vars, name) == value(&new_vars, name) {
+            continue;
+        }
+        let key = key.to_string();
+        if in_env(name) {
+            report.overridden.push(key);
+        } else if LIVE.contains(name) {
+            report.applied.push(key);
+        } else {
+            report.restart_required.push(key);
+        }
+    }
+    // Read from the config on every `CreateSession`, so always live.
+    for (provider, key) in [
+        (Provider::Claude, "connectors.claude"),
+        (Provider::Codex, "connectors.codex"),
+    ] {
+        if old.connector_defaults(provider) != new.connector_defaults(provider) {
+            report.applied.push(key.to_string());
+        }
+    }
+    // Checked on every `CreateSession` and takeover.
+    if old.connectors.trusted_projects != new.connectors.trusted_projects {
+        report
+            .applied
+            .push("connectors.trusted_projects".to_string());
+    }
+    // Looked up on every cost calculation.
+    if old.pricing != new.pricing {
+        report.applied.push("pricing".to_string());
+    }
+    // Read on every token update.
+    if old.context != new.context {
+        report.applied.push("context".to_string());
+    }
+    // Resolved for every session name and turn summary.
+    if old.naming != new.naming {
+        report.applied.push("naming".to_string());
+    }
+    // Resolved on every indexing pass and search.
+    if old.embeddings != new.embeddings {
+        report.applied.push("embeddings".to_string());
+    }
+    // Resolved for every voice prompt.
+    if old.transcription != new.transcription {
+        report.applied.push("transcription".to_string());
+    }
+    // Checked on every spend update and `CreateSession`.
+    if old.budgets != new.budgets {
+        report.applied.push("budgets".to_string());
+    }
+    // Read for every issue session and webhook delivery.
+    if old.github != new.github {
+        report.applied.push("github".to_string());
+    }
+    // Read when a linked session ends.
+    if old.tickets != new.tickets {
+        report.applied.push("tickets".to_string());
+    }
+    // Read on every `OpenInEditor` and `ListEditors`.
+    if old.editor != new.editor {
+        report.applied.push("editor".to_string());
+    }
+    // Matched against every event.
+    if old.hooks != new.hooks {
+        report.applied.push("hooks".to_string());
+    }
+    // The watchers pick their directories once at startup.
+    if old.connectors.codex_homes != new.connectors.codex_homes {
+        report
+            .restart_required
+            .push("connectors.codex_homes".to_string());
+    }
+    if old.connectors.claude_homes != new.connectors.claude_homes {
+        report
+            .restart_required
+            .push("connectors.claude_homes".to_string());
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sections_into_env_vars() {
+        let config = ServerConfig::parse(
+            r#"
+            [server]
+            bind = "0.0.0.0:4000"
+            cors_allowed_origins = ["https://a.example", "https://b.example"]
+
+            [retention]
+            log_max_files = 3
+            log_compress = false
+
+            [connectors]
+            codex_watcher = false
+
+            [connectors.codex]
+            model = "gpt-5-codex"
+
+            [limits]
+            ws_rate_limit = 0
+
+            [snapshots]
+            truncation = "head_tail"
+            "#,
+        )
+        .unwrap();
+
+        let vars = config.env_vars();
+        let get = |name: &str| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(get("ORBITDOCK_BIND_ADDR"), Some("0.0.0.0:4000"));
+        assert_eq!(
+            get("ORBITDOCK_CORS_ALLOWED_ORIGINS"),
+            Some("https://a.example,https://b.example")
+        );
+        assert_eq!(get("ORBITDOCK_SERVER_LOG_MAX_FILES"), Some("3"));
+        assert_eq!(get("ORBITDOCK_SERVER_LOG_COMPRESS"), Some("0"));
+        assert_eq!(get("ORBITDOCK_DISABLE_CODEX_WATCHER"), Some("1"));
+        assert_eq!(get("ORBITDOCK_WS_RATE_LIMIT"), Some("0"));
+        assert_eq!(get("ORBITDOCK_SNAPSHOT_TRUNCATION"), Some("head_tail"));
+        assert_eq!(get("ORBITDOCK_AUTH_TOKEN"), None);
+        assert_eq!(
+            config.connector_defaults(Provider::Codex).model.as_deref(),
+            Some("gpt-5-codex")
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let err = ServerConfig::parse("[server]\nbnid = \"127.0.0.1:4000\"\n").unwrap_err();
+        assert!(err.to_string().contains("bnid"));
+    }
+
+    #[test]
+    fn reports_semantic_problems() {
+        let config = ServerConfig::parse(
+            r#"
+            [server]
+            bind = "not an address"
+            tls_cert = "/nonexistent/cert.pem"
+
+            [logging]
+            format = "xml"
+
+            [connectors.codex]
+            permission_mode = "plan"
+
+            [pricing.my-model]
+            input = -1.0
+            output = 2.0
+
+            [budgets]
+            monthly_usd = -5.0
+
+            [context]
+            warn_at_percent = [80.0, 120.0]
+
+            [naming]
+            provider = "openai_compatible"
+            model = "llama3.2"
+
+            [embeddings]
+            provider = "openai_compatible"
+            base_url = "http://localhost:11434/v1"
+
+            [transcription]
+            provider = "whisper_cpp"
+            "#,
+        )
+        .unwrap();
+
+        let problems = config.problems();
+        assert!(problems.iter().any(|p| p.starts_with("server.bind")));
+        assert!(problems.iter().any(|p| p.contains("tls_cert needs")));
+        assert!(problems.iter().any(|p| p.contains("does not exist")));
+        assert!(problems.iter().any(|p| p.starts_with("logging.format")));
+        assert!(problems.iter().any(|p| p.starts_with("connectors.codex")));
+        assert!(problems.iter().any(|p| p.starts_with("pricing.my-model")));
+        assert!(problems
+            .iter()
+            .any(|p| p.starts_with("budgets.monthly_usd")));
+        assert!(problems
+            .iter()
+            .any(|p| p.starts_with("context.warn_at_percent")));
+        assert!(problems.iter().any(|p| p.starts_with("naming.base_url")));
+        assert!(problems.iter().any(|p| p.starts_with("embeddings.model")));
+        assert!(problems
+            .iter()
+            .any(|p| p.starts_with("transcription.model_path")));
+    }
+
+    #[test]
+    fn data_dir_flag_locates_config() {
+        let args = vec![
+            "orbitdock".to_string(),
+            "--data-dir=/srv/orbitdock".to_string(),
+            "start".to_string(),
+        ];
+        assert_eq!(data_dir_arg(&args), Some(PathBuf::from("/srv/orbitdock")));
+        let args = vec![
+            "orbitdock".to_string(),
+            "--data-dir".to_string(),
+            "/tmp/od".to_string(),
+        ];
+        assert_eq!(data_dir_arg(&args), Some(PathBuf::from("/tmp/od")));
+    }
+
+    #[test]
+    fn reload_diff_sorts_changed_keys() {
+        let old = ServerConfig::parse(
+            r#"
+            [server]
+            bind = "127.0.0.1:4000"
+
+            [logging]
+            filter = "info"
+
+            [limits]
+            ws_rate_limit = 50.0
+            hook_rate_limit = 100.0
+            "#,
+        )
+        .unwrap();
+        let new = ServerConfig::parse(
+            r#"
+            [server]
+            bind = "0.0.0.0:4000"
+
+            [logging]
+            filter = "debug"
+
+            [limits]
+            ws_rate_limit = 10.0
+            hook_rate_limit = 100.0
+
+            [connectors.codex]
+            model = "gpt-5-codex"
+
+            [pricing.gpt-5-codex]
+            input = 1.0
+            output = 8.0
+
+            [budgets]
+            monthly_usd = 50.0
+            "#,
+        )
+        .unwrap();
+
+        let report = diff(&old, &new, |name| name == "ORBITDOCK_WS_RATE_LIMIT");
+        assert_eq!(
+            report,
+            ReloadReport {
+                applied: vec![
+                    "logging.filter".into(),
+                    "connectors.codex".into(),
+                    "pricing".into(),
+                    "budgets".into()
+                ],
+                restart_required: vec!["server.bind".into()],
+                overridden: vec!["limits.ws_rate_limit".into()],
+            }
+        );
+        assert_eq!(diff(&new, &new, |_| false), ReloadReport::default());
+    }
+}
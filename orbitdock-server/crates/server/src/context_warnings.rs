@@ -0,0 +1,129 @@
+//! Warnings as a session's context window fills up.
+//!
+//! Each token update is turned into how much of the context window the
+//! conversation occupies. When that crosses one of the `[context]`
+//! `warn_at_percent` thresholds (80/90/95 by default) the session broadcasts a
+//! `ContextWarning` so remote viewers can compact before the agent runs out
+//! of room. Each threshold warns once, and re-arms when usage falls back below
+//! it, as it does after a compaction. With `notify = true` the warning also
+//! goes out as a `context_warning` webhook event, reaching push, Slack, and
+//! Discord.
+
+use orbitdock_protocol::{Provider, TokenUsage, TokenUsageSnapshotKind};
+
+use crate::config_file;
+
+pub const DEFAULT_THRESHOLDS: [f64; 3] = [80.0, 90.0, 95.0];
+
+/// A threshold the context fill just crossed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextWarning {
+    pub used_tokens: u64,
+    pub context_window: u64,
+    pub percent: f64,
+    pub threshold: f64,
+}
+
+/// Tokens occupying the context window, or `None` when the snapshot doesn't
+/// describe the context (lifetime totals).
+pub fn used_tokens(
+    provider: Provider,
+    usage: &TokenUsage,
+    kind: TokenUsageSnapshotKind,
+) -> Option<u64> {
+    match kind {
+        TokenUsageSnapshotKind::LifetimeTotals => None,
+        TokenUsageSnapshotKind::CompactionReset => Some(0),
+        _ => Some(usage.context_tokens(provider)),
+    }
+}
+
+/// The highest threshold at or below `percent`.
+pub fn level(thresholds: &[f64], percent: f64) -> Option<f64> {
+    thresholds
+        .iter()
+        .copied()
+        .filter(|threshold| percent >= *threshold)
+        .max_by(f64::total_cmp)
+}
+
+/// Update `warned` (the level last warned about) from a new snapshot and
+/// return a warning if it rose.
+pub fn observe(
+    warned: &mut Option<f64>,
+    provider: Provider,
+    usage: &TokenUsage,
+    kind: TokenUsageSnapshotKind,
+) -> Option<ContextWarning> {
+    let used_tokens = used_tokens(provider, usage, kind)?;
+    if usage.context_window == 0 {
+        return None;
+    }
+    let percent = used_tokens as f64 / usage.context_window as f64 * 100.0;
+    let config = config_file::current();
+    let thresholds = config
+        .context
+        .warn_at_percent
+        .as_deref()
+        .unwrap_or(&DEFAULT_THRESHOLDS);
+    let current = level(thresholds, percent);
+    let previous = std::mem::replace(warned, current);
+    let threshold =
+        current.filter(|current| previous.is_none_or(|previous| *current > previous))?;
+    Some(ContextWarning {
+        used_tokens,
+        context_window: usage.context_window,
+        percent,
+        threshold,
+    })
+}
+
+/// Whether warnings also go out as webhook events.
+pub fn notify() -> bool {
+    config_file::current().context.notify.unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input: u64, cached: u64) -> TokenUsage {
+        TokenUsage {
+            input_tokens: input,
+            output_tokens: 0,
+            cached_tokens: cached,
+            context_window: 100_000,
+        }
+    }
+
+    #[test]
+    fn each_threshold_warns_once_and_rearms_after_compaction() {
+        use TokenUsageSnapshotKind::*;
+        let mut warned = None;
+        let mut observe = |input, cached, kind| {
+            observe(&mut warned, Provider::Claude, &usage(input, cached), kind)
+                .map(|warning| warning.threshold)
+        };
+
+        assert_eq!(observe(50_000, 10_000, MixedLegacy), None);
+        // Claude's cached tokens count toward the context.
+        assert_eq!(observe(40_000, 41_000, MixedLegacy), Some(80.0));
+        assert_eq!(observe(40_000, 42_000, MixedLegacy), None);
+        // Jumping past two thresholds warns about the higher one.
+        assert_eq!(observe(50_000, 46_000, MixedLegacy), Some(95.0));
+        assert_eq!(observe(0, 0, CompactionReset), None);
+        assert_eq!(observe(60_000, 25_000, MixedLegacy), Some(80.0));
+        assert_eq!(observe(9_000_000, 0, LifetimeTotals), None);
+    }
+
+    #[test]
+    fn codex_input_already_includes_cached_tokens() {
+        let usage = usage(70_000, 60_000);
+        assert_eq!(
+            used_tokens(Provider::Codex, &usage, TokenUsageSnapshotKind::ContextTurn),
+            Some(70_000)
+        );
+        assert_eq!(level(&DEFAULT_THRESHOLDS, 70.0), None);
+        assert_eq!(level(&DEFAULT_THRESHOLDS, 93.5), Some(90.0));
+    }
+}
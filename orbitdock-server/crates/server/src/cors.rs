@@ -0,0 +1,225 @@
+//! Which browser origins may call the API.
+//!
+//! Origins come from `ORBITDOCK_CORS_ALLOWED_ORIGINS` (comma-separated) or,
+//! failing that, `cors-origins.txt` in the data dir (one per line, `#`
+//! comments). Each entry is an exact origin like `https://dock.example.com`,
+//! `localhost` for any loopback origin on any port, or `*` for everyone.
+//!
+//! With nothing configured, a server that requires auth allows loopback
+//! origins so local browser tools work, and an open server sends no CORS
+//! headers at all.
+
+use axum::http::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    request::Parts,
+    HeaderValue, Method,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::info;
+
+use crate::paths;
+
+const LOCALHOST_ENTRY: &str = "localhost";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CorsPolicy {
+    Disabled,
+    Any,
+    Allow {
+        localhost: bool,
+        origins: Vec<HeaderValue>,
+    },
+}
+
+impl CorsPolicy {
+    fn from_entries<'a>(entries: impl Iterator<Item = &'a str>) -> anyhow::Result<Option<Self>> {
+        let mut localhost = false;
+        let mut origins = Vec::new();
+        for entry in entries.map(str::trim) {
+            match entry {
+                "" => continue,
+                "*" => return Ok(Some(CorsPolicy::Any)),
+                LOCALHOST_ENTRY => localhost = true,
+                origin => origins.push(
+                    HeaderValue::from_str(origin.trim_end_matches('/'))
+                        .map_err(|e| anyhow::anyhow!("invalid CORS origin '{origin}': {e}"))?,
+                ),
+            }
+        }
+        if !localhost && origins.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(CorsPolicy::Allow { localhost, origins }))
+    }
+
+    /// Env wins over the file; with neither, fall back on whether auth is on.
+    pub fn resolve(
+        env: Option<&str>,
+        file: Option<&str>,
+        auth_required: bool,
+    ) -> anyhow::Result<Self> {
+        if let Some(policy) = env
+            .map(|raw| Self::from_entries(raw.split(',')))
+            .transpose()?
+            .flatten()
+        {
+            return Ok(policy);
+        }
+        let file_entries = file.map(|raw| {
+            raw.lines()
+                .filter(|line| !line.trim_start().starts_with('#'))
+        });
+        if let Some(policy) = file_entries.map(Self::from_entries).transpose()?.flatten() {
+            return Ok(policy);
+        }
+        Ok(if auth_required {
+            CorsPolicy::Allow {
+                localhost: true,
+                origins: Vec::new(),
+            }
+        } else {
+            CorsPolicy::Disabled
+        })
+    }
+
+    pub fn allows(&self, origin: &HeaderValue) -> bool {
+        match self {
+            CorsPolicy::Disabled => false,
+            CorsPolicy::Any => true,
+            CorsPolicy::Allow { localhost, origins } => {
+                origins.contains(origin) || (*localhost && is_loopback_origin(origin))
+            }
+        }
+    }
+
+    pub fn layer(&self) -> Option<CorsLayer> {
+        let allow_origin = match self {
+            CorsPolicy::Disabled => return None,
+            CorsPolicy::Any => AllowOrigin::any(),
+            CorsPolicy::Allow {
+                localhost: false,
+                origins,
+            } => AllowOrigin::list(origins.clone()),
+            policy => {
+                let policy = policy.clone();
+                AllowOrigin::predicate(move |origin: &HeaderValue, _: &Parts| policy.allows(origin))
+            }
+        };
+        Some(
+            CorsLayer::new()
+                .allow_origin(allow_origin)
+                .allow_methods([
+                    Method::GET,
+                    Method::POST,
+                    Method::PUT,
+                    Method::PATCH,
+                    Method::DELETE,
+                    Method::OPTIONS,
+                ])
+                .allow_headers([AUTHORIZATION, CONTENT_TYPE]),
+        )
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            CorsPolicy::Disabled => "disabled".to_string(),
+            CorsPolicy::Any => "any origin".to_string(),
+            CorsPolicy::Allow { localhost, origins } => {
+                let mut parts: Vec<String> = origins
+                    .iter()
+                    .filter_map(|origin| origin.to_str().ok().map(str::to_string))
+                    .collect();
+                if *localhost {
+                    parts.insert(0, LOCALHOST_ENTRY.to_string());
+                }
+                parts.join(", ")
+            }
+        }
+    }
+}
+
+/// Build the CORS layer for this server, or `None` to send no CORS headers.
+pub fn configured_layer(auth_required: bool) -> anyhow::Result<Option<CorsLayer>> {
+    let env = std::env::var("ORBITDOCK_CORS_ALLOWED_ORIGINS").ok();
+    let file = std::fs::read_to_string(paths::cors_origins_path()).ok();
+    let policy = CorsPolicy::resolve(env.as_deref(), file.as_deref(), auth_required)?;
+
+    info!(
+        component = "server",
+        event = "cors.configured",
+        allowed_origins = %policy.describe(),
+        "Configured CORS"
+    );
+
+    Ok(policy.layer())
+}
+
+/// `http(s)://localhost`, `127.0.0.1`, or `[::1]`, on any port.
+fn is_loopback_origin(origin: &HeaderValue) -> bool {
+    let Ok(origin) = origin.to_str() else {
+        return false;
+    };
+    let Some(authority) = origin
+        .strip_prefix("http://")
+        .or_else(|| origin.strip_prefix("https://"))
+    else {
+        return false;
+    };
+    let host = match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origin(value: &str) -> HeaderValue {
+        HeaderValue::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn defaults_depend_on_auth() {
+        let open = CorsPolicy::resolve(None, None, false).unwrap();
+        assert_eq!(open, CorsPolicy::Disabled);
+        assert!(open.layer().is_none());
+
+        let authed = CorsPolicy::resolve(None, None, true).unwrap();
+        assert!(authed.allows(&origin("http://localhost:5173")));
+        assert!(authed.allows(&origin("http://127.0.0.1:4000")));
+        assert!(authed.allows(&origin("http://[::1]:8080")));
+        assert!(!authed.allows(&origin("http://localhost.evil.com")));
+        assert!(!authed.allows(&origin("https://example.com")));
+    }
+
+    #[test]
+    fn env_takes_precedence_over_file() {
+        let policy = CorsPolicy::resolve(
+            Some("https://dock.example.com/, localhost"),
+            Some("https://other.example.com"),
+            false,
+        )
+        .unwrap();
+        assert!(policy.allows(&origin("https://dock.example.com")));
+        assert!(policy.allows(&origin("http://localhost:3000")));
+        assert!(!policy.allows(&origin("https://other.example.com")));
+
+        let from_file = CorsPolicy::resolve(
+            Some(" "),
+            Some("# team dashboard\nhttps://other.example.com\n"),
+            true,
+        )
+        .unwrap();
+        assert!(from_file.allows(&origin("https://other.example.com")));
+        assert!(!from_file.allows(&origin("http://localhost:3000")));
+    }
+
+    #[test]
+    fn wildcard_allows_everyone_and_bad_origins_fail() {
+        let any = CorsPolicy::resolve(Some("*"), None, true).unwrap();
+        assert_eq!(any, CorsPolicy::Any);
+        assert!(CorsPolicy::resolve(Some("https://bad\norigin"), None, true).is_err());
+    }
+}
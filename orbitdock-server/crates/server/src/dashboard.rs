@@ -0,0 +1,156 @@
+//! Built-in web dashboard.
+//!
+//! A single page served from `/dashboard` that talks to the server over the
+//! same `/ws` protocol as the macOS app: the session list, a live view of a
+//! session's conversation and diffs, approvals, and a send box. The assets are
+//! plain HTML, CSS and JavaScript compiled into the binary; there is no build
+//! step.
+//!
+//! When the server requires a token, the browser signs in at
+//! `/dashboard/sign-in`, which stores the token in an HttpOnly, SameSite=Strict
+//! cookie that `auth` accepts on same-origin requests. A token that doesn't
+//! verify sends the browser back to sign in.
+
+use axum::{
+    extract::Query,
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use serde::Deserialize;
+
+pub const TOKEN_COOKIE: &str = "orbitdock_token";
+pub const SIGN_IN_PATH: &str = "/dashboard/sign-in";
+const SIGN_OUT_PATH: &str = "/dashboard/sign-out";
+const TOKEN_MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+const INDEX_HTML: &str = include_str!("../dashboard/index.html");
+const SIGN_IN_HTML: &str = include_str!("../dashboard/sign-in.html");
+const APP_JS: &str = include_str!("../dashboard/app.js");
+const APP_CSS: &str = include_str!("../dashboard/app.css");
+
+const CONTENT_SECURITY_POLICY: &str = "default-src 'self'; connect-src 'self'; \
+    img-src 'self' data:; style-src 'self'; script-src 'self'; frame-ancestors 'none'";
+
+/// Paths `auth` lets through without a token: signing in and out, and the
+/// static assets the sign-in page needs, which hold nothing private.
+pub fn is_public_path(path: &str) -> bool {
+    matches!(
+        path,
+        SIGN_IN_PATH | SIGN_OUT_PATH | "/dashboard/app.js" | "/dashboard/app.css"
+    )
+}
+
+/// Paths a signed-out browser is redirected away from rather than refused.
+pub fn is_page_path(path: &str) -> bool {
+    path == "/dashboard" || path.starts_with("/dashboard/")
+}
+
+fn page(content_type: &'static str, body: &'static str) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+pub async fn index() -> Response {
+    page("text/html; charset=utf-8", INDEX_HTML)
+}
+
+pub async fn app_js() -> Response {
+    page("text/javascript; charset=utf-8", APP_JS)
+}
+
+pub async fn app_css() -> Response {
+    page("text/css; charset=utf-8", APP_CSS)
+}
+
+pub async fn sign_in_page() -> Response {
+    page("text/html; charset=utf-8", SIGN_IN_HTML)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignInForm {
+    token: String,
+}
+
+/// Store the token and go to the dashboard, which `auth` checks it against.
+pub async fn sign_in(headers: HeaderMap, Form(form): Form<SignInForm>) -> Response {
+    let token = form.token.trim();
+    if token.is_empty() || token.contains([';', ',', ' ', '"', '\\']) || !token.is_ascii() {
+        return Redirect::to(&format!("{SIGN_IN_PATH}?error=invalid")).into_response();
+    }
+    let cookie = token_cookie(token, TOKEN_MAX_AGE_SECS, is_https(&headers));
+    with_cookie(Redirect::to("/dashboard"), &cookie)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SignOutQuery {
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Forget the token. `?error=` is kept on the way back to the sign-in page.
+pub async fn sign_out(headers: HeaderMap, Query(query): Query<SignOutQuery>) -> Response {
+    let cookie = token_cookie("", 0, is_https(&headers));
+    let target = match query.error.as_deref() {
+        Some("rejected") => format!("{SIGN_IN_PATH}?error=rejected"),
+        _ => SIGN_IN_PATH.to_string(),
+    };
+    with_cookie(Redirect::to(&target), &cookie)
+}
+
+fn token_cookie(token: &str, max_age_secs: u64, secure: bool) -> String {
+    let secure = if secure { "; Secure" } else { "" };
+    format!(
+        "{TOKEN_COOKIE}={token}; Path=/; Max-Age={max_age_secs}; HttpOnly; SameSite=Strict{secure}"
+    )
+}
+
+/// Whether the browser reached the server over HTTPS, as far as its `Origin`
+/// says. The sign-in form always sends one.
+fn is_https(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ORIGIN)
+        .and_then(|origin| origin.to_str().ok())
+        .is_some_and(|origin| origin.starts_with("https://"))
+}
+
+fn with_cookie(redirect: Redirect, cookie: &str) -> Response {
+    let mut response = redirect.into_response();
+    if let Ok(value) = HeaderValue::from_str(cookie) {
+        response.headers_mut().insert(header::SET_COOKIE, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_cookie_is_http_only_and_same_site() {
+        assert_eq!(
+            token_cookie("odtk_abc", 60, true),
+            "orbitdock_token=odtk_abc; Path=/; Max-Age=60; HttpOnly; SameSite=Strict; Secure"
+        );
+        assert!(!token_cookie("", 0, false).contains("Secure"));
+    }
+
+    #[test]
+    fn only_sign_in_and_assets_skip_auth() {
+        assert!(is_public_path("/dashboard/sign-in"));
+        assert!(is_public_path("/dashboard/sign-out"));
+        assert!(is_public_path("/dashboard/app.js"));
+        assert!(!is_public_path("/dashboard"));
+        assert!(!is_public_path("/dashboard/sign-in/"));
+        assert!(is_page_path("/dashboard"));
+        assert!(!is_page_path("/dashboards"));
+    }
+}
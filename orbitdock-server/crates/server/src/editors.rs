@@ -0,0 +1,293 @@
+//! Opening session files in an editor on the server's machine.
+//!
+//! `OpenInEditor` resolves a path against the session's working directory,
+//! refuses anything outside the session's project, and launches the editor's
+//! command with the file and line filled in. Commands run directly, never
+//! through a shell, so a path can't smuggle in extra arguments. `ListEditors`
+//! reports the editors this server knows and which of them are installed.
+//!
+//! VS Code, Cursor, and Zed are built in; `[editor.commands]` adds more or
+//! overrides them.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use orbitdock_protocol::EditorInfo;
+use serde::Deserialize;
+
+/// Editors every server knows, in the order a default is picked from.
+const BUILT_IN: [(&str, &str); 3] = [
+    ("code", "code --goto {file}:{line}"),
+    ("cursor", "cursor --goto {file}:{line}"),
+    ("zed", "zed {file}:{line}"),
+];
+
+/// Where editor CLIs land outside a login shell's `PATH`, as when the server
+/// runs under launchd or systemd.
+const EXTRA_BIN_DIRS: [&str; 2] = ["/usr/local/bin", "/opt/homebrew/bin"];
+
+/// `[editor]` in the config file.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EditorSection {
+    /// Editor used when a request names none; the first installed built-in
+    /// otherwise.
+    pub default: Option<String>,
+    /// Extra editors by name: a command line where `{file}` and `{line}` are
+    /// filled in, e.g. `subl {file}:{line}`.
+    pub commands: BTreeMap<String, String>,
+}
+
+impl EditorSection {
+    /// Built-ins with `commands` layered on top.
+    fn editors(&self) -> BTreeMap<String, String> {
+        let mut editors: BTreeMap<String, String> = BUILT_IN
+            .iter()
+            .map(|(name, command)| (name.to_string(), command.to_string()))
+            .collect();
+        editors.extend(self.commands.clone());
+        editors
+    }
+
+    /// Problems that parsing alone doesn't catch.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, command) in &self.commands {
+            if command.split_whitespace().next().is_none() {
+                problems.push(format!("editor.commands.{name}: must not be empty"));
+            } else if !command.contains("{file}") {
+                problems.push(format!("editor.commands.{name}: must include {{file}}"));
+            }
+        }
+        if let Some(default) = &self.default {
+            if !self.editors().contains_key(default) {
+                problems.push(format!(
+                    "editor.default: {default} is not a built-in editor or in editor.commands"
+                ));
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EditorError {
+    #[error("No editor named {0}")]
+    UnknownEditor(String),
+    #[error("No editor is installed; set [editor] default or add one to [editor.commands]")]
+    NoEditor,
+    #[error("{0} is not a file in the session's project")]
+    OutsideProject(String),
+    #[error("Could not launch {editor}: {source}")]
+    Launch {
+        editor: String,
+        source: std::io::Error,
+    },
+}
+
+impl EditorError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            EditorError::UnknownEditor(_) => "unknown_editor",
+            EditorError::NoEditor => "no_editor",
+            EditorError::OutsideProject(_) => "path_outside_project",
+            EditorError::Launch { .. } => "editor_launch_failed",
+        }
+    }
+}
+
+/// Every known editor and whether its command is installed.
+pub fn list(config: &EditorSection) -> (Vec<EditorInfo>, Option<String>) {
+    let editors = config
+        .editors()
+        .into_iter()
+        .map(|(name, command)| EditorInfo {
+            available: program(&command).is_some_and(|program| find_program(program).is_some()),
+            name,
+        })
+        .collect::<Vec<_>>();
+    let default = default_editor(config, &editors);
+    (editors, default)
+}
+
+fn default_editor(config: &EditorSection, editors: &[EditorInfo]) -> Option<String> {
+    config.default.clone().or_else(|| {
+        BUILT_IN
+            .iter()
+            .find(|(name, _)| {
+                editors
+                    .iter()
+                    .any(|editor| editor.name == *name && editor.available)
+            })
+            .map(|(name, _)| name.to_string())
+    })
+}
+
+/// The file `file_path` names, if it's inside one of `roots`.
+///
+/// Relative paths resolve against `cwd`. Symlinks are followed before the
+/// check, so a link out of the project is refused too.
+pub fn resolve_file(file_path: &str, cwd: &Path, roots: &[&Path]) -> Result<PathBuf, EditorError> {
+    let outside = || EditorError::OutsideProject(file_path.to_string());
+    let requested = Path::new(file_path.trim());
+    let path = if requested.is_absolute() {
+        requested.to_path_buf()
+    } else {
+        cwd.join(requested)
+    };
+    let path = path.canonicalize().map_err(|_| outside())?;
+    let inside = roots
+        .iter()
+        .any(|root| root.canonicalize().is_ok_and(|root| path.starts_with(root)));
+    if !inside || !path.is_file() {
+        return Err(outside());
+    }
+    Ok(path)
+}
+
+/// The editor's command line for `file` at `line`, split into arguments.
+pub fn command_line(template: &str, file: &Path, line: Option<u32>) -> Vec<String> {
+    let file = file.to_string_lossy();
+    let line = line.unwrap_or(1).max(1).to_string();
+    template
+        .split_whitespace()
+        .map(|arg| arg.replace("{file}", &file).replace("{line}", &line))
+        .collect()
+}
+
+/// Launch `editor` (or the default) on `file`, returning the editor's name.
+pub fn open(
+    config: &EditorSection,
+    editor: Option<&str>,
+    file: &Path,
+    line: Option<u32>,
+) -> Result<String, EditorError> {
+    let editors = config.editors();
+    let name = match editor.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) => name.to_string(),
+        None => default_editor(config, &list(config).0).ok_or(EditorError::NoEditor)?,
+    };
+    let template = editors
+        .get(&name)
+        .ok_or_else(|| EditorError::UnknownEditor(name.clone()))?;
+    let args = command_line(template, file, line);
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| EditorError::UnknownEditor(name.clone()))?;
+    let program = find_program(program).unwrap_or_else(|| PathBuf::from(program));
+
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|source| EditorError::Launch {
+            editor: name.clone(),
+            source,
+        })?;
+    // Editor CLIs hand off to the running app and exit; reap them.
+    tokio::spawn(async move {
+        let _ = child.wait().await;
+    });
+    Ok(name)
+}
+
+fn program(template: &str) -> Option<&str> {
+    template.split_whitespace().next()
+}
+
+fn find_program(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .chain(EXTRA_BIN_DIRS.iter().map(PathBuf::from))
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_file_and_line_without_a_shell() {
+        let file = Path::new("/work/api/src/my file.rs");
+        assert_eq!(
+            command_line("code --goto {file}:{line}", file, Some(42)),
+            vec!["code", "--goto", "/work/api/src/my file.rs:42"]
+        );
+        assert_eq!(
+            command_line("zed {file}:{line}", file, None),
+            vec!["zed", "/work/api/src/my file.rs:1"]
+        );
+    }
+
+    #[test]
+    fn refuses_files_outside_the_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("api");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::write(project.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "").unwrap();
+
+        let resolved = resolve_file("src/main.rs", &project, &[&project]).unwrap();
+        assert!(resolved.ends_with("api/src/main.rs"));
+        let absolute = project.join("src/main.rs");
+        assert!(resolve_file(absolute.to_str().unwrap(), &project, &[&project]).is_ok());
+
+        for path in ["../secret.txt", "src", "src/missing.rs"] {
+            assert!(
+                matches!(
+                    resolve_file(path, &project, &[&project]),
+                    Err(EditorError::OutsideProject(_))
+                ),
+                "{path}"
+            );
+        }
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.path().join("secret.txt"), project.join("link"))
+                .unwrap();
+            assert!(resolve_file("link", &project, &[&project]).is_err());
+        }
+    }
+
+    #[test]
+    fn custom_editors_need_a_file_placeholder() {
+        let config = EditorSection {
+            default: Some("nano".into()),
+            commands: BTreeMap::from([
+                ("subl".to_string(), "subl {file}:{line}".to_string()),
+                ("broken".to_string(), "idea".to_string()),
+            ]),
+        };
+        let problems = config.problems();
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].contains("editor.commands.broken"));
+        assert!(problems[1].contains("editor.default"));
+
+        let (editors, _) = list(&config);
+        let names: Vec<_> = editors.iter().map(|editor| editor.name.as_str()).collect();
+        assert_eq!(names, ["broken", "code", "cursor", "subl", "zed"]);
+    }
+
+    #[test]
+    fn unknown_editors_are_refused() {
+        let error = open(
+            &EditorSection::default(),
+            Some("notepad"),
+            Path::new("/tmp/x"),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(error.code(), "unknown_editor");
+    }
+}
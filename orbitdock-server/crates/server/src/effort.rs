@@ -0,0 +1,116 @@
+//! Effort levels per provider.
+//!
+//! Codex takes `effort` as its reasoning effort. Claude takes it as `--effort`
+//! at spawn and, mid-session, as the matching extended-thinking budget. Both
+//! accept a fixed set of values, so `send_message` and `create_session`
+//! check against it instead of letting an unknown level fall back silently.
+
+use orbitdock_connector_claude::EFFORT_THINKING_TOKENS;
+use orbitdock_protocol::{EffortLevel, Provider};
+
+const CODEX_LEVELS: &[(&str, &str)] = &[
+    ("none", "No reasoning"),
+    ("minimal", "Fewest reasoning tokens"),
+    ("low", "Light reasoning, faster replies"),
+    ("medium", "Balanced reasoning"),
+    ("high", "Thorough reasoning"),
+    ("xhigh", "Maximum reasoning, slowest"),
+];
+
+const CLAUDE_DESCRIPTIONS: &[(&str, &str)] = &[
+    ("low", "Short thinking budget"),
+    ("medium", "Moderate thinking budget"),
+    ("high", "Largest thinking budget"),
+];
+
+const DEFAULT_LEVEL: &str = "medium";
+
+/// The levels `provider` accepts, lowest first.
+pub(crate) fn levels(provider: Provider) -> Vec<EffortLevel> {
+    match provider {
+        Provider::Codex => CODEX_LEVELS
+            .iter()
+            .map(|(value, description)| level(value, description, None))
+            .collect(),
+        Provider::Claude => EFFORT_THINKING_TOKENS
+            .iter()
+            .map(|(value, tokens)| {
+                let description = CLAUDE_DESCRIPTIONS
+                    .iter()
+                    .find(|(v, _)| v == value)
+                    .map_or("", |(_, d)| d);
+                level(value, description, Some(*tokens))
+            })
+            .collect(),
+    }
+}
+
+fn level(value: &str, description: &str, max_thinking_tokens: Option<u64>) -> EffortLevel {
+    EffortLevel {
+        value: value.to_string(),
+        description: description.to_string(),
+        is_default: value == DEFAULT_LEVEL,
+        max_thinking_tokens,
+    }
+}
+
+/// Canonical form of `effort` for `provider`: trimmed and lowercased, or an
+/// error message listing what is accepted.
+pub(crate) fn validate(provider: Provider, effort: &str) -> Result<String, String> {
+    let value = effort.trim().to_ascii_lowercase();
+    let levels = levels(provider);
+    if levels.iter().any(|level| level.value == value) {
+        return Ok(value);
+    }
+    let accepted: Vec<&str> = levels.iter().map(|level| level.value.as_str()).collect();
+    Err(format!(
+        "Unsupported effort '{effort}' for {}; expected one of: {}",
+        provider_name(provider),
+        accepted.join(", ")
+    ))
+}
+
+fn provider_name(provider: Provider) -> &'static str {
+    match provider {
+        Provider::Claude => "claude",
+        Provider::Codex => "codex",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_provider_has_one_default_level() {
+        for provider in [Provider::Claude, Provider::Codex] {
+            let defaults: Vec<_> = levels(provider)
+                .into_iter()
+                .filter(|level| level.is_default)
+                .collect();
+            assert_eq!(defaults.len(), 1);
+            assert_eq!(defaults[0].value, "medium");
+        }
+    }
+
+    #[test]
+    fn claude_levels_carry_thinking_budgets() {
+        assert!(levels(Provider::Claude)
+            .iter()
+            .all(|level| level.max_thinking_tokens.is_some() && !level.description.is_empty()));
+        assert!(levels(Provider::Codex)
+            .iter()
+            .all(|level| level.max_thinking_tokens.is_none()));
+    }
+
+    #[test]
+    fn validate_normalizes_and_rejects_per_provider() {
+        assert_eq!(validate(Provider::Codex, " High ").as_deref(), Ok("high"));
+        assert_eq!(validate(Provider::Codex, "xhigh").as_deref(), Ok("xhigh"));
+        assert_eq!(validate(Provider::Claude, "low").as_deref(), Ok("low"));
+
+        let err = validate(Provider::Claude, "xhigh").unwrap_err();
+        assert!(err.contains("low, medium, high"), "{err}");
+        assert!(validate(Provider::Codex, "extreme").is_err());
+    }
+}
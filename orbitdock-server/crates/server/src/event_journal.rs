@@ -0,0 +1,218 @@
+//! Session event journal: every message a session broadcasts, in order.
+//!
+//! `SessionHandle` hands each broadcast (with its revision) to `record`, and
+//! one background task batches them into `session_events` through the
+//! persistence writer. Each session keeps its newest
+//! `[retention] journal_max_events`; 0 stops recording.
+//!
+//! `ReplaySession` streams a session's journal back to the client that asked,
+//! each message wrapped in `replay_event` so it is never mistaken for live
+//! state, spaced as they were originally sent (divided by `speed`).
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use rusqlite::{params, Connection};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::persistence::PersistCommand;
+use crate::state::SessionRegistry;
+
+pub const DEFAULT_MAX_EVENTS: u64 = 10_000;
+/// Most events one replay streams: the newest ones.
+pub const MAX_REPLAY_EVENTS: usize = 50_000;
+/// Longest wait between two replayed events, so idle stretches don't stall
+/// a replay.
+pub const MAX_REPLAY_GAP: Duration = Duration::from_secs(10);
+pub const MAX_REPLAY_SPEED: f64 = 100.0;
+const QUEUE_CAPACITY: usize = 4_096;
+const BATCH_SIZE: usize = 256;
+
+static EVENTS: OnceLock<mpsc::Sender<JournalEntry>> = OnceLock::new();
+/// Events dropped because the queue was full, logged with the next batch.
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub session_id: String,
+    pub revision: u64,
+    pub recorded_at_ms: u64,
+    /// The broadcast's JSON, as sent to subscribers.
+    pub payload: String,
+}
+
+/// Queue a broadcast for the journal. A no-op until the journal is running.
+pub fn record(session_id: &str, revision: u64, payload: &str) {
+    let Some(tx) = EVENTS.get() else {
+        return;
+    };
+    let entry = JournalEntry {
+        session_id: session_id.to_string(),
+        revision,
+        recorded_at_ms: crate::spool::now_ms(),
+        payload: payload.to_string(),
+    };
+    if tx.try_send(entry).is_err() {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// `journal_max_events` from the config; 0 turns the journal off.
+pub fn max_events() -> u64 {
+    crate::config_file::var("ORBITDOCK_JOURNAL_MAX_EVENTS")
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_MAX_EVENTS)
+}
+
+pub async fn start_journal(state: Arc<SessionRegistry>) {
+    let (tx, mut rx) = mpsc::channel(QUEUE_CAPACITY);
+    if EVENTS.set(tx).is_err() {
+        return;
+    }
+
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    while rx.recv_many(&mut batch, BATCH_SIZE).await > 0 {
+        let dropped = DROPPED.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            warn!(
+                component = "event_journal",
+                event = "event_journal.dropped",
+                dropped,
+                "Event journal queue was full; dropped events"
+            );
+        }
+        let max_per_session = max_events();
+        if max_per_session == 0 {
+            batch.clear();
+            continue;
+        }
+        let _ = state
+            .persist()
+            .send(PersistCommand::SessionEventsRecorded {
+                events: std::mem::take(&mut batch),
+                max_per_session,
+            })
+            .await;
+    }
+}
+
+/// A journal row: when it was recorded and the broadcast JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    pub recorded_at_ms: u64,
+    pub payload: String,
+}
+
+/// The session's newest `limit` events, oldest first.
+pub fn load(
+    db_path: &Path,
+    session_id: &str,
+    limit: usize,
+) -> rusqlite::Result<Vec<RecordedEvent>> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare(
+        "SELECT recorded_at, payload FROM (
+            SELECT id, recorded_at, payload FROM session_events
+            WHERE session_id = ?1
+            ORDER BY id DESC
+            LIMIT ?2
+         ) ORDER BY id ASC",
+    )?;
+    let events = stmt
+        .query_map(params![session_id, limit as i64], |row| {
+            Ok(RecordedEvent {
+                recorded_at_ms: row.get::<_, i64>(0)?.max(0) as u64,
+                payload: row.get(1)?,
+            })
+        })?
+        .collect();
+    events
+}
+
+/// How long to wait before each event: the original gap divided by `speed`,
+/// at most `MAX_REPLAY_GAP`. The first event plays at once.
+pub fn delays(events: &[RecordedEvent], speed: f64) -> Vec<Duration> {
+    let mut previous = events.first().map(|event| event.recorded_at_ms);
+    events
+        .iter()
+        .map(|event| {
+            let gap_ms = previous
+                .map(|previous| event.recorded_at_ms.saturating_sub(previous))
+                .unwrap_or(0);
+            previous = Some(event.recorded_at_ms);
+            Duration::from_secs_f64(gap_ms as f64 / 1000.0 / speed).min(MAX_REPLAY_GAP)
+        })
+        .collect()
+}
+
+/// `speed` if it is usable: positive and at most `MAX_REPLAY_SPEED`.
+pub fn valid_speed(speed: f64) -> bool {
+    speed.is_finite() && speed > 0.0 && speed <= MAX_REPLAY_SPEED
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(recorded_at_ms: u64) -> RecordedEvent {
+        RecordedEvent {
+            recorded_at_ms,
+            payload: "{}".to_string(),
+        }
+    }
+
+    #[test]
+    fn delays_follow_the_original_gaps() {
+        let events = [event(1_000), event(1_500), event(3_500), event(63_500)];
+        assert_eq!(
+            delays(&events, 1.0),
+            [
+                Duration::ZERO,
+                Duration::from_millis(500),
+                Duration::from_secs(2),
+                MAX_REPLAY_GAP,
+            ]
+        );
+        assert_eq!(
+            delays(&events, 4.0)[1..3],
+            [Duration::from_millis(125), Duration::from_millis(500)]
+        );
+        assert!(delays(&[], 1.0).is_empty());
+    }
+
+    #[test]
+    fn speed_must_be_positive_and_bounded() {
+        assert!(valid_speed(1.0));
+        assert!(valid_speed(0.25));
+        assert!(!valid_speed(0.0));
+        assert!(!valid_speed(-2.0));
+        assert!(!valid_speed(f64::NAN));
+        assert!(!valid_speed(1_000.0));
+    }
+
+    #[test]
+    fn loads_the_newest_events_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("orbitdock.db");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(include_str!(
+            "../../../../migrations/V042__session_events.sql"
+        ))
+        .unwrap();
+        for (session_id, at) in [("s1", 10), ("s2", 15), ("s1", 20), ("s1", 30)] {
+            conn.execute(
+                "INSERT INTO session_events (session_id, revision, recorded_at, payload)
+                 VALUES (?1, 0, ?2, '{}')",
+                params![session_id, at],
+            )
+            .unwrap();
+        }
+
+        let events = load(&db_path, "s1", 2).unwrap();
+        assert_eq!(events, [event(20), event(30)]);
+        assert!(load(&db_path, "missing", 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,715 @@
+//! Sessions from other OrbitDock servers.
+//!
+//! Peers (a work laptop, a home desktop, a build box) are added with
+//! `orbitdock add-peer` and stored in SQLite; the server re-reads them every
+//! 30 seconds, so changes apply without a restart. For each enabled peer a
+//! task keeps a list subscription open and relays the peer's sessions to local
+//! list subscribers, with ids namespaced as `<peer>:<id>` and `origin` set.
+//! Subscribing to one of those ids opens a connection to the peer for that
+//! session and forwards its messages with ids rewritten the same way.
+//!
+//! Peer sessions are read-only here; actions go to the server that runs them.
+//! Only operator credentials see them, since a peer's users aren't this
+//! server's users. Sessions a peer relays from its own peers are not passed
+//! on, so peers that add each other don't loop.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use dashmap::DashMap;
+use orbitdock_cli::client::config::ClientConfig;
+use orbitdock_cli::client::ws::WsClient;
+use orbitdock_protocol::{
+    ClientMessage, ServerMessage, SessionSummary, SnapshotOptions, StateChanges,
+};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::task::{AbortHandle, JoinHandle};
+use tracing::{info, warn};
+
+use crate::session_access;
+use crate::state::SessionRegistry;
+use crate::websocket::{send_json, OutboundMessage};
+use crate::ws_handlers::HandlerContext;
+use crate::{migration_runner, paths};
+
+const RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const MAX_NAME_CHARS: usize = 32;
+
+/// A peer as stored in `peers`, with the token decrypted.
+#[derive(Debug, Clone)]
+pub struct PeerRecord {
+    pub name: String,
+    pub url: String,
+    pub token: Option<String>,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+/// A peer as `GET /api/peers` reports it. Never includes the token.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerStatus {
+    pub name: String,
+    pub url: String,
+    pub connected: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub session_count: usize,
+}
+
+#[derive(Debug)]
+struct PeerState {
+    url: String,
+    token: Option<String>,
+    connected: bool,
+    error: Option<String>,
+    /// The peer's sessions, by namespaced id.
+    sessions: BTreeMap<String, SessionSummary>,
+}
+
+/// Running peers and session proxies, held by the registry.
+#[derive(Default)]
+pub struct Peers {
+    peers: DashMap<String, PeerState>,
+    /// Open session proxies, by connection and namespaced session id.
+    subscriptions: DashMap<(u64, String), AbortHandle>,
+}
+
+impl Peers {
+    fn insert(&self, name: &str, url: String, token: Option<String>) {
+        self.peers.insert(
+            name.to_string(),
+            PeerState {
+                url,
+                token,
+                connected: false,
+                error: None,
+                sessions: BTreeMap::new(),
+            },
+        );
+    }
+
+    fn remove(&self, name: &str) {
+        self.peers.remove(name);
+    }
+
+    fn update(&self, name: &str, f: impl FnOnce(&mut PeerState)) {
+        if let Some(mut peer) = self.peers.get_mut(name) {
+            f(&mut peer);
+        }
+    }
+
+    fn has_session(&self, name: &str, session_id: &str) -> bool {
+        self.peers
+            .get(name)
+            .is_some_and(|peer| peer.sessions.contains_key(session_id))
+    }
+
+    /// Forget a peer's sessions, returning their ids.
+    fn clear_sessions(&self, name: &str) -> Vec<String> {
+        self.peers
+            .get_mut(name)
+            .map(|mut peer| std::mem::take(&mut peer.sessions).into_keys().collect())
+            .unwrap_or_default()
+    }
+
+    fn client_config(&self, name: &str) -> Option<ClientConfig> {
+        self.peers.get(name).map(|peer| ClientConfig {
+            server_url: peer.url.clone(),
+            token: peer.token.clone(),
+            json: false,
+        })
+    }
+
+    /// Sessions of every connected peer.
+    pub fn summaries(&self) -> Vec<SessionSummary> {
+        self.peers
+            .iter()
+            .flat_map(|peer| peer.sessions.values().cloned().collect::<Vec<_>>())
+            .collect()
+    }
+
+    pub fn statuses(&self) -> Vec<PeerStatus> {
+        let mut statuses: Vec<PeerStatus> = self
+            .peers
+            .iter()
+            .map(|entry| PeerStatus {
+                name: entry.key().clone(),
+                url: entry.url.clone(),
+                connected: entry.connected,
+                error: entry.error.clone(),
+                session_count: entry.sessions.len(),
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    fn track_subscription(&self, conn_id: u64, session_id: String, task: AbortHandle) {
+        if let Some(previous) = self.subscriptions.insert((conn_id, session_id), task) {
+            previous.abort();
+        }
+    }
+
+    pub fn end_subscription(&self, conn_id: u64, session_id: &str) {
+        if let Some((_, task)) = self
+            .subscriptions
+            .remove(&(conn_id, session_id.to_string()))
+        {
+            task.abort();
+        }
+    }
+
+    /// Stop every proxy a closed connection had open.
+    pub fn end_connection(&self, conn_id: u64) {
+        self.subscriptions.retain(|(conn, _), task| {
+            if *conn == conn_id {
+                task.abort();
+            }
+            *conn != conn_id
+        });
+    }
+}
+
+/// Peer names prefix session ids, so they can't contain the separator.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_NAME_CHARS
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+}
+
+pub fn namespaced(peer: &str, session_id: &str) -> String {
+    format!("{peer}:{session_id}")
+}
+
+/// The peer and the peer's own id for a namespaced session id. Local ids
+/// never contain `:`.
+pub fn split_id(session_id: &str) -> Option<(&str, &str)> {
+    session_id
+        .split_once(':')
+        .filter(|(peer, id)| valid_name(peer) && !id.is_empty())
+}
+
+pub fn is_peer_session(session_id: &str) -> bool {
+    split_id(session_id).is_some()
+}
+
+fn namespace_summary(peer: &str, mut session: SessionSummary) -> SessionSummary {
+    session.id = namespaced(peer, &session.id);
+    session.origin = Some(peer.to_string());
+    session
+}
+
+/// Namespace every session id in a serialized peer message: the
+/// `session_id`-style fields at any depth, and the `id` of `session` and
+/// `sessions[]` objects.
+fn rewrite_ids(value: &mut Value, peer: &str) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                match (key.as_str(), child) {
+                    ("session_id" | "source_session_id" | "new_session_id", Value::String(id)) => {
+                        *id = namespaced(peer, id)
+                    }
+                    ("session", Value::Object(session)) => rewrite_session(session, peer),
+                    ("sessions", Value::Array(sessions)) => {
+                        for session in sessions {
+                            match session {
+                                Value::Object(session) => rewrite_session(session, peer),
+                                other => rewrite_ids(other, peer),
+                            }
+                        }
+                    }
+                    (_, child) => rewrite_ids(child, peer),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                rewrite_ids(item, peer);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn rewrite_session(session: &mut serde_json::Map<String, Value>, peer: &str) {
+    for (key, child) in session.iter_mut() {
+        match (key.as_str(), child) {
+            ("id", Value::String(id)) => *id = namespaced(peer, id),
+            (_, child) => rewrite_ids(child, peer),
+        }
+    }
+}
+
+fn namespace_message(peer: &str, msg: &ServerMessage) -> Option<ServerMessage> {
+    let mut value = serde_json::to_value(msg).ok()?;
+    rewrite_ids(&mut value, peer);
+    serde_json::from_value(value).ok()
+}
+
+/// Keep a task per enabled peer running, picking up peers added, changed,
+/// or removed since the last pass.
+pub async fn start_federation(state: Arc<SessionRegistry>) {
+    let mut running: HashMap<String, (PeerRecord, JoinHandle<()>)> = HashMap::new();
+    let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+    loop {
+        interval.tick().await;
+        let mut peers: HashMap<String, PeerRecord> = load_enabled_peers(state.db_path())
+            .into_iter()
+            .map(|peer| (peer.name.clone(), peer))
+            .collect();
+
+        running.retain(|name, (record, task)| {
+            let unchanged = peers
+                .get(name)
+                .is_some_and(|peer| peer.url == record.url && peer.token == record.token);
+            if !unchanged {
+                task.abort();
+                end_peer_sessions(&state, name, "peer_removed");
+                state.peers().remove(name);
+                info!(
+                    component = "federation",
+                    event = "federation.peer_stopped",
+                    peer = %name,
+                    "Stopped relaying peer"
+                );
+            }
+            unchanged
+        });
+
+        for (name, peer) in peers.drain() {
+            if running.contains_key(&name) {
+                continue;
+            }
+            state
+                .peers()
+                .insert(&name, peer.url.clone(), peer.token.clone());
+            let task = tokio::spawn(run_peer(state.clone(), name.clone()));
+            running.insert(name, (peer, task));
+        }
+    }
+}
+
+/// Relay one peer's session list, reconnecting with backoff.
+async fn run_peer(state: Arc<SessionRegistry>, name: String) {
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        let Some(config) = state.peers().client_config(&name) else {
+            return;
+        };
+        let error = match WsClient::connect(&config).await {
+            Ok(mut client) => {
+                backoff = INITIAL_BACKOFF;
+                state.peers().update(&name, |peer| {
+                    peer.connected = true;
+                    peer.error = None;
+                });
+                info!(
+                    component = "federation",
+                    event = "federation.peer_connected",
+                    peer = %name,
+                    url = %config.server_url,
+                    "Connected to peer"
+                );
+                let error = match relay_list(&state, &name, &mut client).await {
+                    Ok(()) => "connection closed".to_string(),
+                    Err(e) => format!("{e:#}"),
+                };
+                end_peer_sessions(&state, &name, "peer_disconnected");
+                error
+            }
+            Err(e) => format!("{e:#}"),
+        };
+        warn!(
+            component = "federation",
+            event = "federation.peer_unavailable",
+            peer = %name,
+            error = %error,
+            retry_in_secs = backoff.as_secs(),
+            "Peer unavailable"
+        );
+        state.peers().update(&name, |peer| {
+            peer.connected = false;
+            peer.error = Some(error);
+        });
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+async fn relay_list(
+    state: &SessionRegistry,
+    peer: &str,
+    client: &mut WsClient,
+) -> anyhow::Result<()> {
+    client.send(&ClientMessage::SubscribeList).await?;
+    while let Some(msg) = client.recv().await? {
+        relay(state, peer, msg);
+    }
+    Ok(())
+}
+
+/// Pass a peer's list message on to local list subscribers.
+fn relay(state: &SessionRegistry, peer: &str, msg: ServerMessage) {
+    match msg {
+        ServerMessage::SessionsList { sessions } => {
+            let sessions: Vec<SessionSummary> = sessions
+                .into_iter()
+                .filter(|session| session.origin.is_none())
+                .map(|session| namespace_summary(peer, session))
+                .collect();
+            state.peers().update(peer, |state| {
+                state.sessions = sessions
+                    .iter()
+                    .map(|session| (session.id.clone(), session.clone()))
+                    .collect();
+            });
+            for session in sessions {
+                state.broadcast_to_list(ServerMessage::SessionCreated { session });
+            }
+        }
+        ServerMessage::SessionCreated { session } => {
+            if session.origin.is_some() {
+                return;
+            }
+            let session = namespace_summary(peer, session);
+            state.peers().update(peer, |state| {
+                state.sessions.insert(session.id.clone(), session.clone());
+            });
+            state.broadcast_to_list(ServerMessage::SessionCreated { session });
+        }
+        msg => {
+            // Anything else about a session must be about one already relayed.
+            let Some(session_id) = session_access::list_message_session(&msg) else {
+                return;
+            };
+            if !state
+                .peers()
+                .has_session(peer, &namespaced(peer, session_id))
+            {
+                return;
+            }
+            let Some(msg) = namespace_message(peer, &msg) else {
+                return;
+            };
+            state.peers().update(peer, |state| match &msg {
+                ServerMessage::SessionDelta {
+                    session_id,
+                    changes,
+                } => {
+                    if let Some(session) = state.sessions.get_mut(session_id) {
+                        apply_changes(session, changes);
+                    }
+                }
+                ServerMessage::SessionTagsChanged { session_id, tags } => {
+                    if let Some(session) = state.sessions.get_mut(session_id) {
+                        session.tags = tags.clone();
+                    }
+                }
+                ServerMessage::SessionTicketChanged { session_id, ticket } => {
+                    if let Some(session) = state.sessions.get_mut(session_id) {
+                        session.ticket = ticket.clone();
+                    }
+                }
+                ServerMessage::SessionEnded { session_id, .. } => {
+                    state.sessions.remove(session_id);
+                }
+                _ => {}
+            });
+            state.broadcast_to_list(msg);
+        }
+    }
+}
+
+/// Keep a relayed summary current with the peer's deltas.
+fn apply_changes(session: &mut SessionSummary, changes: &StateChanges) {
+    if let Some(status) = changes.status {
+        session.status = status;
+    }
+    if let Some(work_status) = changes.work_status {
+        session.work_status = work_status;
+    }
+    if let Some(pending) = &changes.pending_approval {
+        session.has_pending_approval = pending.is_some();
+        session.pending_approval_id = pending.as_ref().map(|req| req.id.clone());
+        session.pending_tool_name = pending.as_ref().and_then(|req| req.tool_name.clone());
+        session.pending_tool_input = pending
+            .as_ref()
+            .and_then(|req| req.command.clone().or_else(|| req.tool_input.clone()));
+        session.pending_question = pending.as_ref().and_then(|req| req.question.clone());
+    }
+    if let Some(token_usage) = &changes.token_usage {
+        session.token_usage = token_usage.clone();
+    }
+    if let Some(custom_name) = &changes.custom_name {
+        session.custom_name = custom_name.clone();
+    }
+    if let Some(summary) = &changes.summary {
+        session.summary = summary.clone();
+    }
+    if let Some(last_message) = &changes.last_message {
+        session.last_message = last_message.clone();
+    }
+    if let Some(model) = &changes.model {
+        session.model = model.clone();
+    }
+    if let Some(git_branch) = &changes.git_branch {
+        session.git_branch = git_branch.clone();
+    }
+    if let Some(last_activity_at) = &changes.last_activity_at {
+        session.last_activity_at = Some(last_activity_at.clone());
+    }
+    if let Some(unread_count) = changes.unread_count {
+        session.unread_count = unread_count;
+    }
+}
+
+fn end_peer_sessions(state: &SessionRegistry, peer: &str, reason: &str) {
+    for session_id in state.peers().clear_sessions(peer) {
+        state.broadcast_to_list(ServerMessage::SessionEnded {
+            session_id,
+            reason: reason.to_string(),
+        });
+    }
+}
+
+/// Subscribe a local connection to a peer session. Messages are forwarded
+/// until the client unsubscribes or disconnects, or the peer goes away.
+pub(crate) async fn subscribe_session(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    since_revision: Option<u64>,
+    include_snapshot: bool,
+    snapshot: Option<SnapshotOptions>,
+) {
+    let Some((peer, remote_id)) = split_id(&session_id) else {
+        return;
+    };
+    let Some(config) = ctx.state.peers().client_config(peer) else {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "not_found".into(),
+                message: format!("No peer named {peer}"),
+                session_id: Some(session_id.clone()),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    };
+    let request = ClientMessage::SubscribeSession {
+        session_id: remote_id.to_string(),
+        since_revision,
+        include_snapshot,
+        snapshot,
+    };
+    let peer = peer.to_string();
+    let client_tx = ctx.client_tx.clone();
+    let task_session_id = session_id.clone();
+    let task = tokio::spawn(async move {
+        if let Err(e) = forward_session(&config, &peer, &request, &client_tx).await {
+            warn!(
+                component = "federation",
+                event = "federation.session_proxy_failed",
+                peer = %peer,
+                session_id = %task_session_id,
+                error = %format!("{e:#}"),
+                "Peer session subscription ended"
+            );
+            send_json(
+                &client_tx,
+                ServerMessage::Error {
+                    code: "peer_unavailable".into(),
+                    message: format!("Lost the connection to peer {peer}: {e:#}"),
+                    session_id: Some(task_session_id),
+                    error: None,
+                },
+            )
+            .await;
+        }
+    });
+    ctx.state
+        .peers()
+        .track_subscription(ctx.conn_id, session_id, task.abort_handle());
+}
+
+async fn forward_session(
+    config: &ClientConfig,
+    peer: &str,
+    request: &ClientMessage,
+    client_tx: &mpsc::Sender<OutboundMessage>,
+) -> anyhow::Result<()> {
+    let mut client = WsClient::connect(config).await?;
+    client.send(request).await?;
+    loop {
+        let msg = tokio::select! {
+            msg = client.recv() => msg?,
+            _ = client_tx.closed() => return Ok(()),
+        };
+        let Some(msg) = msg else {
+            anyhow::bail!("connection closed");
+        };
+        let mut value = serde_json::to_value(&msg)?;
+        rewrite_ids(&mut value, peer);
+        if client_tx
+            .send(OutboundMessage::Raw(value.to_string()))
+            .await
+            .is_err()
+        {
+            return Ok(());
+        }
+    }
+}
+
+/// Add a peer, or replace the URL and token of the one with its name.
+pub fn add_peer(name: &str, url: &str, token: Option<&str>) -> anyhow::Result<PeerRecord> {
+    let name = name.trim();
+    if !valid_name(name) {
+        anyhow::bail!(
+            "peer names may only use letters, digits, '-' and '_' (at most {MAX_NAME_CHARS})"
+        );
+    }
+    let url = url.trim().trim_end_matches('/');
+    let parsed = reqwest::Url::parse(url).with_context(|| format!("invalid peer URL {url}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        anyhow::bail!("peer URL must be http or https");
+    }
+    let token = token
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(ToString::to_string);
+    let stored_token = token
+        .as_deref()
+        .map(crate::crypto::encrypt)
+        .transpose()
+        .context("encrypt peer token")?;
+
+    let conn = open_admin_connection()?;
+    conn.execute(
+        "INSERT INTO peers (name, url, token) VALUES (?1, ?2, ?3)
+         ON CONFLICT(name) DO UPDATE SET url = excluded.url, token = excluded.token",
+        params![name, url, stored_token],
+    )?;
+    query_peers(&conn, false)?
+        .into_iter()
+        .find(|peer| peer.name == name)
+        .context("peer missing after insert")
+}
+
+pub fn list_peers() -> anyhow::Result<Vec<PeerRecord>> {
+    let conn = open_admin_connection()?;
+    query_peers(&conn, false)
+}
+
+pub fn remove_peer(name: &str) -> anyhow::Result<bool> {
+    let conn = open_admin_connection()?;
+    let deleted = conn.execute("DELETE FROM peers WHERE name = ?1", params![name.trim()])?;
+    Ok(deleted > 0)
+}
+
+fn load_enabled_peers(db_path: &Path) -> Vec<PeerRecord> {
+    let result = Connection::open(db_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|conn| query_peers(&conn, true));
+    match result {
+        Ok(peers) => peers,
+        Err(e) => {
+            warn!(
+                component = "federation",
+                event = "federation.load_failed",
+                error = %e,
+                "Could not load peers"
+            );
+            Vec::new()
+        }
+    }
+}
+
+fn query_peers(conn: &Connection, enabled_only: bool) -> anyhow::Result<Vec<PeerRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, url, token, enabled, created_at
+         FROM peers
+         WHERE ?1 = 0 OR enabled = 1
+         ORDER BY name",
+    )?;
+    let rows = stmt.query_map(params![enabled_only], |row| {
+        let token: Option<String> = row.get(2)?;
+        Ok(PeerRecord {
+            name: row.get(0)?,
+            url: row.get(1)?,
+            token: token.as_deref().and_then(crate::crypto::decrypt),
+            enabled: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+fn open_admin_connection() -> anyhow::Result<Connection> {
+    paths::ensure_dirs().context("ensure data dirs for peer db")?;
+    let db_path = paths::db_path();
+    let mut conn = Connection::open(&db_path)
+        .with_context(|| format!("open peer db at {}", db_path.display()))?;
+    migration_runner::run_migrations(&mut conn).context("run peer migrations")?;
+    Ok(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn namespaced_ids_split_back_into_peer_and_id() {
+        let id = namespaced("build-box", "od-123");
+        assert_eq!(id, "build-box:od-123");
+        assert_eq!(split_id(&id), Some(("build-box", "od-123")));
+        assert_eq!(split_id("od-123"), None);
+        assert_eq!(split_id("build-box:"), None);
+        assert_eq!(split_id("not a peer:od-123"), None);
+    }
+
+    #[test]
+    fn peer_names_cannot_hold_the_separator() {
+        assert!(valid_name("laptop"));
+        assert!(valid_name("home_desktop-2"));
+        assert!(!valid_name(""));
+        assert!(!valid_name("a:b"));
+        assert!(!valid_name("has space"));
+        assert!(!valid_name(&"x".repeat(33)));
+    }
+
+    #[test]
+    fn rewrite_namespaces_session_ids_only() {
+        let mut value = json!({
+            "type": "sessions_list",
+            "sessions": [{ "id": "s1", "summary": "fix tests" }],
+            "session": {
+                "id": "s2",
+                "messages": [{ "id": "m1", "session_id": "s2" }],
+            },
+            "source_session_id": "s3",
+            "new_session_id": "s4",
+            "approval": { "id": "a1" },
+        });
+        rewrite_ids(&mut value, "laptop");
+        assert_eq!(value["sessions"][0]["id"], "laptop:s1");
+        assert_eq!(value["session"]["id"], "laptop:s2");
+        assert_eq!(value["session"]["messages"][0]["id"], "m1");
+        assert_eq!(value["session"]["messages"][0]["session_id"], "laptop:s2");
+        assert_eq!(value["source_session_id"], "laptop:s3");
+        assert_eq!(value["new_session_id"], "laptop:s4");
+        assert_eq!(value["approval"]["id"], "a1");
+    }
+}
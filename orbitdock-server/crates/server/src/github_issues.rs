@@ -0,0 +1,811 @@
+//! Sessions started from GitHub issues.
+//!
+//! `POST /api/github/issues` fetches an issue and creates a session for it:
+//! the issue is rendered through `[github] prompt_template` and sent as the
+//! first prompt, and the session's summary links back to the issue. The
+//! session runs in the checkout the request names, else the one listed for
+//! the repo under `[github.repos]`. An issue that already has a live session
+//! gets that session back instead of a second one.
+//!
+//! GitHub can start sessions too: point an `issues` webhook at
+//! `/api/github/webhook`. Deliveries must be signed with `[github]
+//! webhook_secret` (`X-Hub-Signature-256`) and are refused without one, since
+//! GitHub can't send a bearer token. Anyone can open an issue on a public
+//! repo, so the webhook is also refused until `[github] label` or
+//! `author_associations` limits which issues start sessions: issues given
+//! that label, and issues whose author has one of those associations
+//! (`OWNER`, `MEMBER`, `COLLABORATOR`, ...). With both set, both must match.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use orbitdock_protocol::{IssueLink, Provider, ServerMessage};
+use ring::hmac;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::state::SessionRegistry;
+use crate::websocket::OutboundMessage;
+use crate::ws_handlers::{messaging, session_crud, HandlerContext};
+
+pub const WEBHOOK_PATH: &str = "/api/github/webhook";
+pub const SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+pub const EVENT_HEADER: &str = "X-GitHub-Event";
+const DEFAULT_API_URL: &str = "https://api.github.com";
+const DEFAULT_TEMPLATE: &str = "Work on GitHub issue {repo}#{number}: {title}\n\n{url}\n\n{body}";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// No WebSocket connection has id 0, so sessions created here have no
+/// creator and their replies go nowhere.
+const CONNECTION_ID: u64 = 0;
+
+/// `[github]` in the config file.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GithubSection {
+    /// Token for private repos and a higher rate limit; `GITHUB_TOKEN` otherwise.
+    pub token: Option<String>,
+    /// `https://api.github.com` unless set, e.g. for GitHub Enterprise.
+    pub api_url: Option<String>,
+    /// Secret webhook deliveries are signed with; unset refuses them.
+    pub webhook_secret: Option<String>,
+    /// First prompt; `{repo}`, `{number}`, `{title}`, `{body}`, and `{url}`
+    /// are filled in from the issue.
+    pub prompt_template: Option<String>,
+    /// Provider for issue sessions; `claude` unless set.
+    pub provider: Option<Provider>,
+    /// Only issues given this label start sessions from the webhook.
+    pub label: Option<String>,
+    /// Only issues whose author has one of these `author_association`s
+    /// start sessions from the webhook.
+    pub author_associations: Vec<String>,
+    /// Checkout each issue session runs in, keyed by `owner/name`.
+    pub repos: BTreeMap<String, String>,
+}
+
+impl GithubSection {
+    /// The checkout listed for `repo`, matched without regard to case.
+    fn checkout(&self, repo: &str) -> Option<String> {
+        self.repos
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(repo))
+            .map(|(_, path)| path.clone())
+    }
+
+    fn token(&self) -> Option<String> {
+        self.token
+            .clone()
+            .or_else(|| std::env::var("GITHUB_TOKEN").ok())
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty())
+    }
+
+    /// Problems that parsing alone doesn't catch.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some(url) = &self.api_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                problems.push("github.api_url: must be an http(s) URL".into());
+            }
+        }
+        if self
+            .prompt_template
+            .as_deref()
+            .is_some_and(|template| template.trim().is_empty())
+        {
+            problems.push("github.prompt_template: must not be empty".into());
+        }
+        if self
+            .label
+            .as_deref()
+            .is_some_and(|label| label.trim().is_empty())
+        {
+            problems.push("github.label: must not be empty".into());
+        }
+        if self.webhook_secret.is_some() && !self.webhook_restricted() {
+            problems.push(
+                "github.webhook_secret: also set label or author_associations, or anyone who can open an issue starts a session".into(),
+            );
+        }
+        for (repo, path) in &self.repos {
+            if !valid_repo(repo) {
+                problems.push(format!("github.repos.{repo}: expected owner/name"));
+            } else if !Path::new(path).is_dir() {
+                problems.push(format!("github.repos.{repo}: {path} is not a directory"));
+            }
+        }
+        problems
+    }
+
+    /// Whether `label` or `author_associations` limits which issues the
+    /// webhook starts sessions for.
+    fn webhook_restricted(&self) -> bool {
+        self.label.is_some() || !self.author_associations.is_empty()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IssueSessionError {
+    #[error("Repository must be written as owner/name")]
+    InvalidRepo,
+    #[error("No checkout for {0}; pass project_path or add it to [github.repos]")]
+    NoCheckout(String),
+    #[error("Issue {0} was not found")]
+    NotFound(String),
+    #[error("{0} is a pull request, not an issue")]
+    PullRequest(String),
+    #[error("Could not fetch the issue from GitHub: {0}")]
+    Fetch(String),
+    #[error("{message}")]
+    Create { code: String, message: String },
+}
+
+impl IssueSessionError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            IssueSessionError::InvalidRepo | IssueSessionError::NoCheckout(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            IssueSessionError::NotFound(_) => StatusCode::NOT_FOUND,
+            IssueSessionError::PullRequest(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            IssueSessionError::Fetch(_) => StatusCode::BAD_GATEWAY,
+            IssueSessionError::Create { code, .. } if code.starts_with("budget_") => {
+                StatusCode::CONFLICT
+            }
+            IssueSessionError::Create { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            IssueSessionError::InvalidRepo => "invalid_repo",
+            IssueSessionError::NoCheckout(_) => "no_checkout",
+            IssueSessionError::NotFound(_) => "not_found",
+            IssueSessionError::PullRequest(_) => "pull_request",
+            IssueSessionError::Fetch(_) => "github_error",
+            IssueSessionError::Create { code, .. } => match code.as_str() {
+                "budget_exceeded" => "budget_exceeded",
+                "budget_confirmation_required" => "budget_confirmation_required",
+                _ => "session_create_failed",
+            },
+        }
+    }
+}
+
+/// The parts of a GitHub issue a session needs.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Issue {
+    pub number: u64,
+    pub title: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    pub html_url: String,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    /// The author's relationship to the repo, e.g. `OWNER` or `NONE`.
+    #[serde(default)]
+    pub author_association: Option<String>,
+    /// Present when the "issue" is a pull request.
+    #[serde(default)]
+    pub pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Label {
+    pub name: String,
+}
+
+/// What to start an issue session with, beyond the issue itself.
+#[derive(Debug, Default, Clone)]
+pub struct SessionOptions {
+    /// Overrides the `[github.repos]` checkout.
+    pub project_path: Option<String>,
+    /// Overrides `[github] provider`.
+    pub provider: Option<Provider>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IssueSession {
+    pub session_id: String,
+    pub issue: IssueLink,
+    /// True when the issue already had a live session, which is returned
+    /// instead of starting another.
+    pub existing: bool,
+}
+
+/// `owner/name`, each part made of GitHub's allowed characters.
+pub fn valid_repo(repo: &str) -> bool {
+    let part = |part: &str| {
+        !part.is_empty()
+            && part != "."
+            && part != ".."
+            && part.len() <= 100
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    matches!(repo.split_once('/'), Some((owner, name)) if part(owner) && part(name))
+}
+
+/// Fill `template`'s placeholders from the issue. Unknown placeholders, and
+/// braces in the issue text itself, are left alone.
+pub fn render_prompt(template: &str, repo: &str, issue: &Issue) -> String {
+    let value = |name: &str| match name {
+        "repo" => Some(repo.to_string()),
+        "number" => Some(issue.number.to_string()),
+        "title" => Some(issue.title.clone()),
+        "body" => Some(issue.body.clone().unwrap_or_default()),
+        "url" => Some(issue.html_url.clone()),
+        _ => None,
+    };
+
+    let mut prompt = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        prompt.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after
+            .find('}')
+            .and_then(|end| Some((value(&after[..end])?, end)))
+        {
+            Some((text, end)) => {
+                prompt.push_str(&text);
+                rest = &after[end + 1..];
+            }
+            None => {
+                prompt.push('{');
+                rest = after;
+            }
+        }
+    }
+    prompt.push_str(rest);
+    prompt.trim_end().to_string()
+}
+
+/// Fetch `repo`#`number` and start a session for it.
+pub async fn start_session(
+    state: &Arc<SessionRegistry>,
+    repo: &str,
+    number: u64,
+    options: SessionOptions,
+) -> Result<IssueSession, IssueSessionError> {
+    let repo = repo.trim();
+    if !valid_repo(repo) {
+        return Err(IssueSessionError::InvalidRepo);
+    }
+    if let Some(existing) = existing_session(state, repo, number) {
+        return Ok(existing);
+    }
+    let config = crate::config_file::current();
+    let project_path = options
+        .project_path
+        .clone()
+        .or_else(|| config.github.checkout(repo))
+        .ok_or_else(|| IssueSessionError::NoCheckout(repo.to_string()))?;
+    let issue = fetch_issue(&config.github, repo, number).await?;
+    create(state, repo, issue, project_path, options.provider).await
+}
+
+fn existing_session(state: &SessionRegistry, repo: &str, number: u64) -> Option<IssueSession> {
+    let session_id = state.sessions_for_issue(repo, number).into_iter().next()?;
+    let issue = state.session_issue(&session_id)?;
+    Some(IssueSession {
+        session_id,
+        issue,
+        existing: true,
+    })
+}
+
+async fn fetch_issue(
+    github: &GithubSection,
+    repo: &str,
+    number: u64,
+) -> Result<Issue, IssueSessionError> {
+    let api_url = github.api_url.as_deref().unwrap_or(DEFAULT_API_URL);
+    let url = format!(
+        "{}/repos/{repo}/issues/{number}",
+        api_url.trim_end_matches('/')
+    );
+    let mut request = reqwest::Client::new()
+        .get(url)
+        .timeout(REQUEST_TIMEOUT)
+        .header(reqwest::header::USER_AGENT, "orbitdock")
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json");
+    if let Some(token) = github.token() {
+        request = request.bearer_auth(token);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| IssueSessionError::Fetch(e.to_string()))?;
+    match response.status() {
+        status if status.is_success() => {}
+        reqwest::StatusCode::NOT_FOUND => {
+            return Err(IssueSessionError::NotFound(format!("{repo}#{number}")))
+        }
+        status => {
+            return Err(IssueSessionError::Fetch(format!(
+                "GitHub returned {status}"
+            )))
+        }
+    }
+    let issue: Issue = response
+        .json()
+        .await
+        .map_err(|e| IssueSessionError::Fetch(e.to_string()))?;
+    if issue.pull_request.is_some() {
+        return Err(IssueSessionError::PullRequest(format!("{repo}#{number}")));
+    }
+    Ok(issue)
+}
+
+/// Create the session through the same path as `CreateSession`, then send
+/// the rendered issue as its first prompt.
+async fn create(
+    state: &Arc<SessionRegistry>,
+    repo: &str,
+    issue: Issue,
+    project_path: String,
+    provider: Option<Provider>,
+) -> Result<IssueSession, IssueSessionError> {
+    let config = crate::config_file::current();
+    let provider = provider
+        .or(config.github.provider)
+        .unwrap_or(Provider::Claude);
+    let template = config
+        .github
+        .prompt_template
+        .as_deref()
+        .unwrap_or(DEFAULT_TEMPLATE);
+    let prompt = render_prompt(template, repo, &issue);
+    let link = IssueLink {
+        repo: repo.to_string(),
+        number: issue.number,
+        title: issue.title.clone(),
+        url: issue.html_url.clone(),
+    };
+
+    // Large enough that the handlers never wait on a reader; whatever the
+    // session forwards after the first prompt is dropped with the receiver.
+    let (client_tx, mut client_rx) = mpsc::channel(256);
+    let ctx = HandlerContext {
+        client_tx: &client_tx,
+        state,
+        conn_id: CONNECTION_ID,
+    };
+    Box::pin(session_crud::create_session(
+        &ctx,
+        provider,
+        project_path.clone(),
+        None,
+        None,
+        None,
+        None,
+        Vec::new(),
+        Vec::new(),
+        None,
+        None,
+        None,
+        false,
+        Some(link.clone()),
+    ))
+    .await;
+
+    let mut session_id = None;
+    while let Ok(outbound) = client_rx.try_recv() {
+        let OutboundMessage::Json(message) = outbound else {
+            continue;
+        };
+        match message {
+            ServerMessage::SessionSnapshot { session } if session_id.is_none() => {
+                session_id = Some(session.id);
+            }
+            ServerMessage::Error { code, message, .. } => {
+                return Err(IssueSessionError::Create { code, message });
+            }
+            _ => {}
+        }
+    }
+    let Some(session_id) = session_id else {
+        return Err(IssueSessionError::Create {
+            code: "session_create_failed".into(),
+            message: "The session was not created".into(),
+        });
+    };
+
+    messaging::send_message(
+        &ctx,
+        session_id.clone(),
+        prompt,
+        None,
+        None,
+        Vec::new(),
+        Vec::new(),
+        Vec::new(),
+        Vec::new(),
+    )
+    .await;
+
+    info!(
+        component = "github_issues",
+        event = "github_issues.session_started",
+        session_id = %session_id,
+        repo = %repo,
+        issue = issue.number,
+        project_path = %project_path,
+        "Started a session for a GitHub issue"
+    );
+    Ok(IssueSession {
+        session_id,
+        issue: link,
+        existing: false,
+    })
+}
+
+/// Check `X-Hub-Signature-256` (`sha256=<hex HMAC-SHA256 of the body>`).
+pub fn verify_signature(secret: &str, signature: Option<&str>, body: &[u8]) -> bool {
+    let Some(tag) = signature
+        .and_then(|signature| signature.strip_prefix("sha256="))
+        .and_then(crate::hook_signature::from_hex)
+    else {
+        return false;
+    };
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::verify(&key, body, &tag).is_ok()
+}
+
+/// The parts of an `issues` webhook delivery we look at.
+#[derive(Debug, Deserialize)]
+pub struct IssuesEvent {
+    pub action: String,
+    pub issue: Issue,
+    /// The label just added, for `labeled`.
+    #[serde(default)]
+    pub label: Option<Label>,
+    pub repository: Repository,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Repository {
+    pub full_name: String,
+}
+
+impl IssuesEvent {
+    /// Whether this delivery should start a session, given `[github] label`.
+    pub fn starts_session(&self, label: Option<&str>) -> bool {
+        if self.issue.pull_request.is_some() {
+            return false;
+        }
+        let Some(label) = label.map(str::trim) else {
+            return self.action == "opened";
+        };
+        let matches = |name: &str| name.eq_ignore_ascii_case(label);
+        match self.action.as_str() {
+            "opened" => self.issue.labels.iter().any(|l| matches(&l.name)),
+            "labeled" => self.label.as_ref().is_some_and(|l| matches(&l.name)),
+            _ => false,
+        }
+    }
+
+    /// Whether the issue's author is allowed by `[github]
+    /// author_associations`; any author when it's empty.
+    pub fn author_allowed(&self, associations: &[String]) -> bool {
+        associations.is_empty()
+            || self
+                .issue
+                .author_association
+                .as_deref()
+                .is_some_and(|author| {
+                    associations
+                        .iter()
+                        .any(|allowed| allowed.trim().eq_ignore_ascii_case(author))
+                })
+    }
+}
+
+/// What became of a webhook delivery GitHub's signature checked out for.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Delivery {
+    /// A session is starting in the background.
+    Accepted,
+    Ignored(String),
+    Pong,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeliveryError {
+    #[error(
+        "Set [github] webhook_secret, and label or author_associations, to accept GitHub webhooks"
+    )]
+    Disabled,
+    #[error("Webhook signature does not match")]
+    InvalidSignature,
+    #[error("Could not parse the issues event: {0}")]
+    InvalidPayload(String),
+}
+
+/// Handle a delivery to `/api/github/webhook`.
+///
+/// Sessions start in the background so GitHub gets its answer well within
+/// its ten-second delivery timeout.
+pub fn handle_delivery(
+    state: &Arc<SessionRegistry>,
+    event: Option<&str>,
+    signature: Option<&str>,
+    body: &[u8],
+) -> Result<Delivery, DeliveryError> {
+    let config = crate::config_file::current();
+    let secret = config
+        .github
+        .webhook_secret
+        .as_deref()
+        .filter(|secret| !secret.is_empty() && config.github.webhook_restricted())
+        .ok_or(DeliveryError::Disabled)?;
+    if !verify_signature(secret, signature, body) {
+        warn!(
+            component = "github_issues",
+            event = "github_issues.webhook_rejected",
+            "GitHub webhook signature does not match"
+        );
+        return Err(DeliveryError::InvalidSignature);
+    }
+
+    match event {
+        Some("ping") => return Ok(Delivery::Pong),
+        Some("issues") => {}
+        other => {
+            return Ok(Delivery::Ignored(format!(
+                "unhandled event {}",
+                other.unwrap_or("(none)")
+            )))
+        }
+    }
+
+    let event: IssuesEvent =
+        serde_json::from_slice(body).map_err(|e| DeliveryError::InvalidPayload(e.to_string()))?;
+    if !event.starts_session(config.github.label.as_deref()) {
+        return Ok(Delivery::Ignored(format!(
+            "action {} does not start a session",
+            event.action
+        )));
+    }
+    if !event.author_allowed(&config.github.author_associations) {
+        return Ok(Delivery::Ignored(format!(
+            "author association {} does not start a session",
+            event
+                .issue
+                .author_association
+                .as_deref()
+                .unwrap_or("(none)")
+        )));
+    }
+    let repo = event.repository.full_name;
+    if !valid_repo(&repo) {
+        return Ok(Delivery::Ignored(format!("invalid repository {repo}")));
+    }
+    let Some(project_path) = config.github.checkout(&repo) else {
+        return Ok(Delivery::Ignored(format!(
+            "no checkout configured for {repo}"
+        )));
+    };
+    if existing_session(state, &repo, event.issue.number).is_some() {
+        return Ok(Delivery::Ignored(
+            "the issue already has a live session".to_string(),
+        ));
+    }
+
+    let state = state.clone();
+    let issue = event.issue;
+    tokio::spawn(async move {
+        let number = issue.number;
+        if let Err(e) = create(&state, &repo, issue, project_path, None).await {
+            warn!(
+                component = "github_issues",
+                event = "github_issues.webhook_session_failed",
+                repo = %repo,
+                issue = number,
+                error = %e,
+                "Could not start a session for a GitHub issue"
+            );
+        }
+    });
+    Ok(Delivery::Accepted)
+}
+
+/// Issue links, loaded once at startup.
+pub fn load_all(db_path: &Path) -> Vec<(String, IssueLink)> {
+    let result = Connection::open(db_path).and_then(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT session_id, repo, number, title, url FROM session_issues")?;
+        let rows = stmt.query_map([], |row| {
+            let number: i64 = row.get(2)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                IssueLink {
+                    repo: row.get(1)?,
+                    number: number.max(0) as u64,
+                    title: row.get(3)?,
+                    url: row.get(4)?,
+                },
+            ))
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+    });
+    match result {
+        Ok(links) => links,
+        Err(e) => {
+            warn!(
+                component = "github_issues",
+                event = "github_issues.load_failed",
+                error = %e,
+                "Could not load session issue links"
+            );
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(body: Option<&str>) -> Issue {
+        Issue {
+            number: 42,
+            title: "Crash on {startup}".to_string(),
+            body: body.map(str::to_string),
+            html_url: "https://github.com/acme/app/issues/42".to_string(),
+            labels: vec![Label {
+                name: "Agent".to_string(),
+            }],
+            author_association: Some("NONE".to_string()),
+            pull_request: None,
+        }
+    }
+
+    #[test]
+    fn repos_are_owner_and_name() {
+        assert!(valid_repo("acme/app"));
+        assert!(valid_repo("acme-co/app.rs_2"));
+        assert!(!valid_repo("acme"));
+        assert!(!valid_repo("acme/"));
+        assert!(!valid_repo("acme/app/extra"));
+        assert!(!valid_repo("../etc"));
+    }
+
+    #[test]
+    fn renders_placeholders_once() {
+        let prompt = render_prompt(
+            "Fix {repo}#{number}: {title}\n{url}\n\n{body}\n{unknown} {",
+            "acme/app",
+            &issue(Some("It says {title} and {body}.")),
+        );
+        assert_eq!(
+            prompt,
+            "Fix acme/app#42: Crash on {startup}\n\
+             https://github.com/acme/app/issues/42\n\n\
+             It says {title} and {body}.\n{unknown} {"
+        );
+        assert_eq!(
+            render_prompt(DEFAULT_TEMPLATE, "acme/app", &issue(None)),
+            "Work on GitHub issue acme/app#42: Crash on {startup}\n\n\
+             https://github.com/acme/app/issues/42"
+        );
+    }
+
+    #[test]
+    fn checks_github_signatures() {
+        let body = br#"{"action":"opened"}"#;
+        let signature = crate::webhooks::sign("s3cret", r#"{"action":"opened"}"#);
+        assert!(verify_signature("s3cret", Some(&signature), body));
+        assert!(!verify_signature("other", Some(&signature), body));
+        assert!(!verify_signature("s3cret", Some(&signature), b"{}"));
+        assert!(!verify_signature("s3cret", Some("sha256=zz"), body));
+        assert!(!verify_signature("s3cret", None, body));
+    }
+
+    #[test]
+    fn opened_or_labeled_issues_start_sessions() {
+        let event = |action: &str, label: Option<&str>| IssuesEvent {
+            action: action.to_string(),
+            issue: issue(None),
+            label: label.map(|name| Label {
+                name: name.to_string(),
+            }),
+            repository: Repository {
+                full_name: "acme/app".to_string(),
+            },
+        };
+
+        assert!(event("opened", None).starts_session(None));
+        assert!(!event("edited", None).starts_session(None));
+        assert!(event("opened", None).starts_session(Some("agent")));
+        assert!(!event("opened", None).starts_session(Some("bug")));
+        assert!(event("labeled", Some("AGENT")).starts_session(Some("agent")));
+        assert!(!event("labeled", Some("bug")).starts_session(Some("agent")));
+
+        let mut pull = event("opened", None);
+        pull.issue.pull_request = Some(serde_json::json!({}));
+        assert!(!pull.starts_session(None));
+    }
+
+    #[test]
+    fn author_associations_limit_who_starts_sessions() {
+        let mut event = IssuesEvent {
+            action: "opened".to_string(),
+            issue: issue(None),
+            label: None,
+            repository: Repository {
+                full_name: "acme/app".to_string(),
+            },
+        };
+        let members = vec!["OWNER".to_string(), "member".to_string()];
+        assert!(event.author_allowed(&[]));
+        assert!(!event.author_allowed(&members));
+        event.issue.author_association = Some("MEMBER".to_string());
+        assert!(event.author_allowed(&members));
+        event.issue.author_association = None;
+        assert!(!event.author_allowed(&members));
+    }
+
+    #[test]
+    fn webhooks_need_a_label_or_author_check() {
+        let mut section = GithubSection {
+            webhook_secret: Some("s3cret".into()),
+            ..Default::default()
+        };
+        assert_eq!(section.problems().len(), 1);
+        assert!(!section.webhook_restricted());
+
+        section.author_associations = vec!["OWNER".into()];
+        assert!(section.problems().is_empty());
+        section.author_associations.clear();
+        section.label = Some("agent".into());
+        assert!(section.problems().is_empty());
+    }
+
+    #[test]
+    fn parses_issues_deliveries() {
+        let event: IssuesEvent = serde_json::from_str(
+            r#"{
+                "action": "labeled",
+                "issue": {
+                    "number": 7,
+                    "title": "Add dark mode",
+                    "body": null,
+                    "html_url": "https://github.com/acme/app/issues/7",
+                    "labels": [{"name": "agent", "color": "fff"}],
+                    "user": {"login": "someone"}
+                },
+                "label": {"name": "agent"},
+                "repository": {"full_name": "acme/app", "private": false}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(event.repository.full_name, "acme/app");
+        assert_eq!(event.issue.number, 7);
+        assert!(event.issue.body.is_none());
+        assert!(event.starts_session(Some("agent")));
+    }
+
+    #[test]
+    fn reports_config_problems() {
+        let section = GithubSection {
+            api_url: Some("api.github.com".into()),
+            prompt_template: Some("  ".into()),
+            repos: BTreeMap::from([
+                ("acme".to_string(), "/tmp".to_string()),
+                ("acme/app".to_string(), "/nonexistent/orbitdock".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let problems = section.problems();
+        assert_eq!(problems.len(), 4, "{problems:?}");
+        assert!(section.checkout("ACME/App").is_some());
+    }
+}
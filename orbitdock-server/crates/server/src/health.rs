@@ -0,0 +1,357 @@
+//! `/health` endpoint — structured subsystem report.
+//!
+//! Unauthenticated, so it reports counts and capacities only, never session
+//! contents or error details; those go to the log. Returns 503 when the database is unreachable so load balancers
+//! and monitors can act on the status code alone.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use orbitdock_protocol::{Provider, ProviderBinaryStatus};
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::state::SessionRegistry;
+use crate::VERSION;
+use crate::{cmd_tunnel, paths};
+
+/// Free space below this marks the data directory as degraded.
+const LOW_DISK_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Persistence queue fill ratio that marks the writer as backed up.
+const PERSIST_BACKLOG_RATIO: f64 = 0.8;
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    /// `ok`, `degraded` (serving but something needs attention), or `error`.
+    pub status: &'static str,
+    pub version: &'static str,
+    pub uptime_seconds: u64,
+    pub database: DatabaseHealth,
+    pub persistence: PersistenceHealth,
+    pub spool_backlog: u64,
+    pub rollout_watcher_running: bool,
+    pub connectors: ConnectorHealth,
+    pub sessions: u64,
+    pub websocket_connections: u64,
+    pub disk: DiskHealth,
+    /// Provider CLI versions from the last probe; empty until the startup
+    /// probe finishes.
+    pub providers: Vec<ProviderHealth>,
+    /// Set while `orbitdock tunnel` is running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tunnel: Option<TunnelHealth>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DatabaseHealth {
+    pub reachable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub migration_version: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PersistenceHealth {
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConnectorHealth {
+    pub codex: usize,
+    pub claude: usize,
+}
+
+/// Binary paths are left out; `get_provider_info` reports them.
+#[derive(Debug, Serialize)]
+pub struct ProviderHealth {
+    pub provider: Provider,
+    pub status: ProviderBinaryStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub min_version: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TunnelHealth {
+    pub provider: cmd_tunnel::Provider,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub started_at: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiskHealth {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available_bytes: Option<u64>,
+}
+
+pub async fn health_handler(State(state): State<Arc<SessionRegistry>>) -> impl IntoResponse {
+    let db_path = state.db_path().clone();
+    let database = tokio::task::spawn_blocking(move || check_database(&db_path))
+        .await
+        .unwrap_or_else(|e| {
+            warn!(
+                component = "health",
+                event = "health.database_check_failed",
+                error = %e,
+                "Database health check did not finish"
+            );
+            DatabaseHealth {
+                reachable: false,
+                migration_version: None,
+            }
+        });
+
+    let report = build_report(&state, database);
+    let code = if report.status == "error" {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (code, Json(report))
+}
+
+fn build_report(state: &SessionRegistry, database: DatabaseHealth) -> HealthReport {
+    let persist = state.persist();
+    let persistence = PersistenceHealth {
+        queue_depth: persist.max_capacity() - persist.capacity(),
+        queue_capacity: persist.max_capacity(),
+    };
+    let disk = DiskHealth {
+        available_bytes: available_disk_bytes(&paths::data_dir()),
+    };
+
+    let providers: Vec<ProviderHealth> = crate::provider_info::current()
+        .into_iter()
+        .map(|binary| ProviderHealth {
+            provider: binary.provider,
+            status: binary.status,
+            version: binary.version,
+            min_version: binary.min_version,
+        })
+        .collect();
+
+    let status = overall_status(&database, &persistence, &disk, &providers);
+
+    HealthReport {
+        status,
+        version: VERSION,
+        uptime_seconds: state.uptime_seconds(),
+        database,
+        persistence,
+        spool_backlog: crate::spool::stats().depth,
+        rollout_watcher_running: state.rollout_watcher_running(),
+        connectors: ConnectorHealth {
+            codex: state.codex_connector_count(),
+            claude: state.claude_connector_count(),
+        },
+        sessions: state.get_session_summaries().len() as u64,
+        websocket_connections: state.ws_connection_count(),
+        disk,
+        providers,
+        tunnel: cmd_tunnel::current().map(|tunnel| TunnelHealth {
+            provider: tunnel.provider,
+            url: tunnel.url,
+            started_at: tunnel.started_at,
+        }),
+    }
+}
+
+fn overall_status(
+    database: &DatabaseHealth,
+    persistence: &PersistenceHealth,
+    disk: &DiskHealth,
+    providers: &[ProviderHealth],
+) -> &'static str {
+    if !database.reachable {
+        return "error";
+    }
+    let backed_up = persistence.queue_capacity > 0
+        && persistence.queue_depth as f64
+            >= persistence.queue_capacity as f64 * PERSIST_BACKLOG_RATIO;
+    let low_disk = disk
+        .available_bytes
+        .is_some_and(|bytes| bytes < LOW_DISK_BYTES);
+    // A missing CLI only matters to users of that provider; an old one
+    // breaks its sessions in ways that are hard to spot.
+    let outdated_cli = providers
+        .iter()
+        .any(|provider| provider.status == ProviderBinaryStatus::Outdated);
+    if backed_up || low_disk || outdated_cli {
+        "degraded"
+    } else {
+        "ok"
+    }
+}
+
+fn check_database(db_path: &Path) -> DatabaseHealth {
+    let result =
+        Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY).and_then(|conn| {
+            conn.execute_batch("PRAGMA busy_timeout = 2000;")?;
+            conn.query_row(
+                "SELECT MAX(version) FROM refinery_schema_history",
+                [],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+        });
+    match result {
+        Ok(version) => DatabaseHealth {
+            reachable: true,
+            migration_version: version,
+        },
+        Err(e) => {
+            warn!(
+                component = "health",
+                event = "health.database_unreachable",
+                path = %db_path.display(),
+                error = %e,
+                "Health check could not read the database"
+            );
+            DatabaseHealth {
+                reachable: false,
+                migration_version: None,
+            }
+        }
+    }
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`.
+pub fn available_disk_bytes(path: &Path) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::ffi::CString;
+
+        let c_path = CString::new(path.to_string_lossy().as_bytes()).ok()?;
+        unsafe {
+            let mut stat: libc::statvfs = std::mem::zeroed();
+            if libc::statvfs(c_path.as_ptr(), &mut stat) == 0 {
+                #[allow(clippy::unnecessary_cast)]
+                return Some(stat.f_bavail as u64 * stat.f_frsize as u64);
+            }
+        }
+        None
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reachable() -> DatabaseHealth {
+        DatabaseHealth {
+            reachable: true,
+            migration_version: Some(20),
+        }
+    }
+
+    fn idle_queue() -> PersistenceHealth {
+        PersistenceHealth {
+            queue_depth: 0,
+            queue_capacity: 1000,
+        }
+    }
+
+    #[test]
+    fn unreachable_database_is_an_error() {
+        let database = DatabaseHealth {
+            reachable: false,
+            migration_version: None,
+        };
+        let disk = DiskHealth {
+            available_bytes: Some(u64::MAX),
+        };
+        assert_eq!(
+            overall_status(&database, &idle_queue(), &disk, &[]),
+            "error"
+        );
+    }
+
+    #[test]
+    fn low_disk_or_backed_up_queue_is_degraded() {
+        let plenty = DiskHealth {
+            available_bytes: Some(50 * LOW_DISK_BYTES),
+        };
+        assert_eq!(
+            overall_status(&reachable(), &idle_queue(), &plenty, &[]),
+            "ok"
+        );
+
+        let low = DiskHealth {
+            available_bytes: Some(LOW_DISK_BYTES / 2),
+        };
+        assert_eq!(
+            overall_status(&reachable(), &idle_queue(), &low, &[]),
+            "degraded"
+        );
+
+        let full_queue = PersistenceHealth {
+            queue_depth: 900,
+            queue_capacity: 1000,
+        };
+        assert_eq!(
+            overall_status(&reachable(), &full_queue, &plenty, &[]),
+            "degraded"
+        );
+    }
+
+    #[test]
+    fn outdated_provider_cli_is_degraded() {
+        let plenty = DiskHealth {
+            available_bytes: Some(50 * LOW_DISK_BYTES),
+        };
+        let binary = |status| ProviderHealth {
+            provider: Provider::Claude,
+            status,
+            version: Some("1.0.0".into()),
+            min_version: "2.0.0".into(),
+        };
+        assert_eq!(
+            overall_status(
+                &reachable(),
+                &idle_queue(),
+                &plenty,
+                &[binary(ProviderBinaryStatus::NotFound)]
+            ),
+            "ok"
+        );
+        assert_eq!(
+            overall_status(
+                &reachable(),
+                &idle_queue(),
+                &plenty,
+                &[binary(ProviderBinaryStatus::Outdated)]
+            ),
+            "degraded"
+        );
+    }
+
+    #[test]
+    fn database_check_reads_migration_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("orbitdock.db");
+        let mut conn = Connection::open(&db_path).unwrap();
+        crate::migration_runner::run_migrations(&mut conn).unwrap();
+        drop(conn);
+
+        let health = check_database(&db_path);
+        assert!(health.reachable);
+        assert!(health.migration_version.unwrap_or(0) >= 20);
+
+        let missing = check_database(&dir.path().join("missing.db"));
+        assert!(!missing.reachable);
+        let json = serde_json::to_value(&missing).unwrap();
+        assert_eq!(json, serde_json::json!({ "reachable": false }));
+    }
+}
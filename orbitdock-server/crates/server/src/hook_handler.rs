@@ -9,17 +9,29 @@
 //! If `SessionEnd` arrives first the pending entry is silently discarded, preventing
 //! ghost sessions from `claude -c` bootstrap processes.
 
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{
+    body::Bytes,
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
 use serde_json::Value;
 use tokio::sync::{mpsc, oneshot};
-use tracing::warn;
+use tracing::{debug, warn};
 
-use orbitdock_protocol::{ClientMessage, Provider, ServerMessage};
+use orbitdock_protocol::{
+    ClientMessage, CodexIntegrationMode, Provider, ServerMessage, SessionStatus, WorkStatus,
+};
 
+use crate::auth::AuthIdentity;
+use crate::hook_signature;
 use crate::persistence::PersistCommand;
+use crate::rate_limit;
 use crate::session::SessionHandle;
 use crate::session_actor::SessionActorHandle;
 use crate::session_command::SessionCommand;
@@ -45,23 +57,159 @@ pub struct PendingClaudeSession {
     pub cached_at: Instant,
 }
 
+/// Most events `/api/hook/batch` accepts in one request.
+const MAX_BATCH_EVENTS: usize = 200;
+
 /// HTTP POST handler for `/api/hook`.
 ///
 /// Accepts a `ClientMessage` JSON body, validates it's one of the 5 Claude hook
-/// types, spawns fire-and-forget processing, and returns 204 immediately.
+/// types or a Codex notification, spawns fire-and-forget processing, and returns 204 immediately.
+/// With a hook secret configured, unsigned or badly signed requests get 401 with an
+/// `invalid_hook_signature` error body (see `hook_signature`).
+/// Callers over their rate limit get 429 with a `rate_limited` error body.
 pub async fn hook_handler(
     State(state): State<Arc<SessionRegistry>>,
-    Json(msg): Json<ClientMessage>,
-) -> StatusCode {
+    identity: Option<Extension<AuthIdentity>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if let Some(response) = hook_signature_rejection(&headers, &body) {
+        return response;
+    }
+
+    let Ok(msg) = serde_json::from_slice::<ClientMessage>(&body) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
     if !is_claude_hook(&msg) {
-        return StatusCode::BAD_REQUEST;
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+
+    if let Some(response) = hook_rate_limit_rejection(identity, connect_info) {
+        return response;
     }
 
     tokio::spawn(async move {
         handle_hook_message(msg, &state).await;
     });
 
-    StatusCode::NO_CONTENT
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// HTTP POST handler for `/api/hook/batch`.
+///
+/// Like `/api/hook`, but the body is a JSON array of up to `MAX_BATCH_EVENTS`
+/// hook messages, processed in order by one task. The batch is all-or-nothing:
+/// a single malformed or non-hook entry rejects it with 400, and it counts once
+/// against the rate limit. Larger batches get 413.
+pub async fn hook_batch_handler(
+    State(state): State<Arc<SessionRegistry>>,
+    identity: Option<Extension<AuthIdentity>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if let Some(response) = hook_signature_rejection(&headers, &body) {
+        return response;
+    }
+
+    let Ok(messages) = serde_json::from_slice::<Vec<ClientMessage>>(&body) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    if messages.len() > MAX_BATCH_EVENTS {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    }
+    if !messages.iter().all(is_claude_hook) {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+
+    if let Some(response) = hook_rate_limit_rejection(identity, connect_info) {
+        return response;
+    }
+
+    tokio::spawn(async move {
+        for msg in messages {
+            handle_hook_message(msg, &state).await;
+        }
+    });
+
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// The 401 for a request that fails the hook secret check, if any.
+fn hook_signature_rejection(headers: &HeaderMap, body: &[u8]) -> Option<Response> {
+    let secret = hook_signature::server_secret()?;
+    let header_str = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    hook_signature::verify(
+        &secret,
+        header_str(hook_signature::TIMESTAMP_HEADER),
+        header_str(hook_signature::SIGNATURE_HEADER),
+        body,
+        now,
+    )
+    .err()
+    .map(|error| {
+        debug!(
+            component = "hook",
+            event = "hook.signature_rejected",
+            reason = ?error,
+            "Rejecting hook request with an invalid signature"
+        );
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ServerMessage::Error {
+                code: "invalid_hook_signature".into(),
+                message: error.message().into(),
+                session_id: None,
+                error: None,
+            }),
+        )
+            .into_response()
+    })
+}
+
+/// The 429 for a caller over the hook rate limit, if any.
+fn hook_rate_limit_rejection(
+    identity: Option<Extension<AuthIdentity>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+) -> Option<Response> {
+    let identity = identity
+        .map(|Extension(identity)| identity)
+        .unwrap_or(AuthIdentity::Anonymous);
+    let remote_addr = connect_info.map(|Extension(ConnectInfo(addr))| addr);
+    if rate_limit::allow_hook(&identity, remote_addr) {
+        return None;
+    }
+
+    // A flood would otherwise flood the log too.
+    let (rejected_total, _) = rate_limit::rejected_counts();
+    if rejected_total % 100 == 1 {
+        warn!(
+            component = "hook",
+            event = "hook.rate_limited",
+            identity = %identity.label(),
+            remote_addr = ?remote_addr,
+            rejected_total,
+            "Dropping hook events over the rate limit"
+        );
+    }
+    Some(
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, "1")],
+            Json(ServerMessage::Error {
+                code: "rate_limited".into(),
+                message: "Too many hook events; slow down".into(),
+                session_id: None,
+                error: None,
+            }),
+        )
+            .into_response(),
+    )
 }
 
 fn is_claude_hook(msg: &ClientMessage) -> bool {
@@ -72,6 +220,7 @@ fn is_claude_hook(msg: &ClientMessage) -> bool {
             | ClientMessage::ClaudeStatusEvent { .. }
             | ClientMessage::ClaudeToolEvent { .. }
             | ClientMessage::ClaudeSubagentEvent { .. }
+            | ClientMessage::CodexNotifyEvent { .. }
     )
 }
 
@@ -219,9 +368,39 @@ async fn resolve_pending_approvals_after_tool_outcome(
     }
 }
 
-/// Process a Claude hook message. Extracted from `handle_client_message` in websocket.rs.
-/// These handlers never need `client_tx` or `conn_id` — only `state`.
+/// Handle a hook delivered by Claude Code or Codex CLI (HTTP, WebSocket, or
+/// the spool).
 pub async fn handle_hook_message(msg: ClientMessage, state: &Arc<SessionRegistry>) {
+    if let ClientMessage::ClaudeSessionEnd { session_id, .. } = &msg {
+        let session_id = session_id.clone();
+        dispatch_hook_message(msg, state).await;
+        state.forget_claude_hooks(&session_id);
+        return;
+    }
+    if let Some(session_id) = claude_hook_session_id(&msg) {
+        state.note_claude_hook(session_id);
+    }
+    dispatch_hook_message(msg, state).await;
+}
+
+/// Handle a hook the Claude transcript watcher synthesized. Unlike real hooks,
+/// these don't mark the session as hook-fed.
+pub(crate) async fn handle_transcript_message(msg: ClientMessage, state: &Arc<SessionRegistry>) {
+    dispatch_hook_message(msg, state).await;
+}
+
+fn claude_hook_session_id(msg: &ClientMessage) -> Option<&str> {
+    match msg {
+        ClientMessage::ClaudeSessionStart { session_id, .. }
+        | ClientMessage::ClaudeSessionEnd { session_id, .. }
+        | ClientMessage::ClaudeStatusEvent { session_id, .. }
+        | ClientMessage::ClaudeToolEvent { session_id, .. }
+        | ClientMessage::ClaudeSubagentEvent { session_id, .. } => Some(session_id),
+        _ => None,
+    }
+}
+
+async fn dispatch_hook_message(msg: ClientMessage, state: &Arc<SessionRegistry>) {
     match msg {
         ClientMessage::ClaudeSessionStart {
             session_id,
@@ -1393,12 +1572,14 @@ pub async fn handle_hook_message(msg: ClientMessage, state: &Arc<SessionRegistry
                                 .await;
                         }
                         "SubagentStop" => {
-                            let _ = persist_tx
-                                .send(PersistCommand::ClaudeSubagentEnd {
-                                    id: agent_id,
-                                    transcript_path: agent_transcript_path,
-                                })
-                                .await;
+                            end_subagent(
+                                state,
+                                &persist_tx,
+                                &owning_id,
+                                agent_id,
+                                agent_transcript_path,
+                            )
+                            .await;
                             let _ = persist_tx
                                 .send(PersistCommand::ClaudeSessionUpdate {
                                     id: owning_id,
@@ -1483,12 +1664,14 @@ pub async fn handle_hook_message(msg: ClientMessage, state: &Arc<SessionRegistry
                         .await;
                 }
                 "SubagentStop" => {
-                    let _ = persist_tx
-                        .send(PersistCommand::ClaudeSubagentEnd {
-                            id: agent_id,
-                            transcript_path: agent_transcript_path,
-                        })
-                        .await;
+                    end_subagent(
+                        state,
+                        &persist_tx,
+                        &session_id,
+                        agent_id,
+                        agent_transcript_path,
+                    )
+                    .await;
                     let _ = persist_tx
                         .send(PersistCommand::ClaudeSessionUpdate {
                             id: session_id,
@@ -1513,6 +1696,15 @@ pub async fn handle_hook_message(msg: ClientMessage, state: &Arc<SessionRegistry
             }
         }
 
+        ClientMessage::CodexNotifyEvent {
+            event,
+            thread_id,
+            cwd,
+            ..
+        } => {
+            handle_codex_notify(state, &event, thread_id, cwd).await;
+        }
+
         _ => {
             warn!(
                 component = "hook_handler",
@@ -1523,6 +1715,138 @@ pub async fn handle_hook_message(msg: ClientMessage, state: &Arc<SessionRegistry
     }
 }
 
+/// Mark a subagent stopped, recording the usage in its transcript and adding
+/// its spend to the session that spawned it. Subagents its Task calls spawned
+/// become its children.
+async fn end_subagent(
+    state: &Arc<SessionRegistry>,
+    persist_tx: &mpsc::Sender<PersistCommand>,
+    session_id: &str,
+    agent_id: String,
+    transcript_path: Option<String>,
+) {
+    let (usage, spawned_subagent_ids) = match transcript_path.clone() {
+        Some(path) => tokio::task::spawn_blocking(move || {
+            let path = std::path::Path::new(&path);
+            (
+                crate::subagent_parser::parse_usage(path),
+                crate::subagent_parser::spawned_subagent_ids(path),
+            )
+        })
+        .await
+        .unwrap_or_default(),
+        None => (None, Vec::new()),
+    };
+    if let (Some(cost_usd), Some(actor)) = (
+        usage.as_ref().and_then(|usage| usage.estimated_cost_usd),
+        state.get_session(session_id),
+    ) {
+        actor
+            .send(SessionCommand::RecordSubagentCost {
+                subagent_id: agent_id.clone(),
+                cost_usd,
+            })
+            .await;
+    }
+    let _ = persist_tx
+        .send(PersistCommand::ClaudeSubagentEnd {
+            id: agent_id,
+            transcript_path,
+            usage,
+            spawned_subagent_ids,
+        })
+        .await;
+}
+
+/// Apply a Codex CLI `notify` event to its passive session.
+///
+/// Codex only notifies on `agent-turn-complete`, which moves the session to
+/// `waiting` the moment the turn ends rather than on the rollout watcher's
+/// next pass. Direct sessions already get this from the connector, and
+/// sessions the watcher hasn't materialized yet are left to it.
+async fn handle_codex_notify(
+    state: &Arc<SessionRegistry>,
+    event: &str,
+    thread_id: Option<String>,
+    cwd: Option<String>,
+) {
+    if event != "agent-turn-complete" {
+        debug!(
+            component = "hook_handler",
+            event = "hook_handler.codex_notify_ignored",
+            notify_type = %event,
+            "Ignoring unsupported Codex notification"
+        );
+        return;
+    }
+    let session_id = match thread_id {
+        Some(thread_id) if state.is_managed_codex_thread(&thread_id) => return,
+        Some(thread_id) => thread_id,
+        // Older Codex versions leave out the thread id.
+        None => match cwd
+            .as_deref()
+            .and_then(|cwd| find_working_passive_codex_session(state, cwd))
+        {
+            Some(session_id) => session_id,
+            None => return,
+        },
+    };
+    let Some(actor) = state.get_session(&session_id) else {
+        return;
+    };
+    let snap = actor.snapshot();
+    if snap.codex_integration_mode != Some(CodexIntegrationMode::Passive)
+        || snap.status != SessionStatus::Active
+    {
+        return;
+    }
+
+    actor
+        .send(SessionCommand::ApplyDelta {
+            changes: orbitdock_protocol::StateChanges {
+                work_status: Some(WorkStatus::Waiting),
+                last_activity_at: Some(chrono_now()),
+                ..Default::default()
+            },
+            persist_op: None,
+        })
+        .await;
+    let _ = state
+        .persist()
+        .send(PersistCommand::RolloutSessionUpdate {
+            id: session_id,
+            project_path: None,
+            model: None,
+            status: None,
+            work_status: Some(WorkStatus::Waiting),
+            attention_reason: Some(Some("awaitingReply".to_string())),
+            pending_tool_name: Some(None),
+            pending_tool_input: Some(None),
+            pending_question: Some(None),
+            total_tokens: None,
+            last_tool: None,
+            last_tool_at: None,
+            custom_name: None,
+        })
+        .await;
+}
+
+/// The most recently active passive Codex session working in `cwd`.
+fn find_working_passive_codex_session(state: &SessionRegistry, cwd: &str) -> Option<String> {
+    state
+        .iter_sessions()
+        .filter_map(|entry| {
+            let snap = entry.value().snapshot();
+            let in_cwd = snap.current_cwd.as_deref().unwrap_or(&snap.project_path) == cwd;
+            (snap.codex_integration_mode == Some(CodexIntegrationMode::Passive)
+                && snap.work_status == WorkStatus::Working
+                && in_cwd)
+                .then(|| (snap.last_activity_at.clone(), snap.id.clone()))
+        })
+        .max()
+        .map(|(_, session_id)| session_id)
+}
+
 /// Materialize a Claude session from the pending cache (or create a bare fallback).
 ///
 /// Called by status/tool/subagent handlers when the session doesn't exist yet.
@@ -1764,7 +2088,7 @@ fn find_most_recent_claude_session(
 /// Check if a session-start payload is actually from Codex CLI.
 fn is_codex_rollout_payload(transcript_path: Option<&str>, model: Option<&str>) -> bool {
     if let Some(path) = transcript_path {
-        if path.contains("/.codex/sessions/") {
+        if crate::provider_homes::is_codex_rollout_path(path) {
             return true;
         }
     }
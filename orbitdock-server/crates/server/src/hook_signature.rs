@@ -0,0 +1,199 @@
+//! Signed hook requests.
+//!
+//! With a hook secret configured, `/api/hook` only accepts requests carrying
+//! `X-OrbitDock-Hook-Timestamp` (unix seconds) and `X-OrbitDock-Hook-Signature`
+//! (`sha256=<hex HMAC-SHA256 of "<timestamp>.<body>">`) stamped within five
+//! minutes of the server clock. This holds even without a bearer token, so
+//! anything that can reach the port can't inject hook events.
+//!
+//! The secret is `[auth] hook_secret` / `ORBITDOCK_HOOK_SECRET`, else
+//! `<data dir>/hook-secret`, which `install-hooks` creates for a local server.
+//! With neither, hooks are accepted unsigned.
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::Context;
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::{config_file, paths};
+
+pub const TIMESTAMP_HEADER: &str = "X-OrbitDock-Hook-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-OrbitDock-Hook-Signature";
+const MAX_SKEW_SECS: u64 = 300;
+
+/// `<data dir>/hook-secret` as it was at first use.
+static FILE_SECRET: OnceLock<Option<String>> = OnceLock::new();
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    Missing,
+    Stale,
+    Invalid,
+}
+
+impl VerifyError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            VerifyError::Missing => "Hook request is not signed",
+            VerifyError::Stale => "Hook request timestamp is too old or too far ahead",
+            VerifyError::Invalid => "Hook request signature does not match",
+        }
+    }
+}
+
+/// The secret hook requests must be signed with, if one is configured.
+pub fn server_secret() -> Option<String> {
+    config_file::var("ORBITDOCK_HOOK_SECRET")
+        .map(|secret| secret.trim().to_string())
+        .filter(|secret| !secret.is_empty())
+        .or_else(|| {
+            FILE_SECRET
+                .get_or_init(|| read_secret_file(&paths::hook_secret_path()))
+                .clone()
+        })
+}
+
+/// The local hook secret, generated on first use.
+pub fn ensure_local_secret() -> anyhow::Result<String> {
+    let path = paths::hook_secret_path();
+    if let Some(secret) = read_secret_file(&path) {
+        return Ok(secret);
+    }
+
+    paths::ensure_dirs().context("ensure data directories for hook secret")?;
+    let mut bytes = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| anyhow::anyhow!("failed to generate hook secret"))?;
+    let secret = to_hex(&bytes);
+    write_secret_file(&path, &secret)?;
+    Ok(secret)
+}
+
+fn read_secret_file(path: &Path) -> Option<String> {
+    let secret = std::fs::read_to_string(path).ok()?;
+    let secret = secret.trim();
+    (!secret.is_empty()).then(|| secret.to_string())
+}
+
+fn write_secret_file(path: &Path, secret: &str) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)
+            .with_context(|| format!("open {} for write", path.display()))?;
+        file.write_all(secret.as_bytes())
+            .with_context(|| format!("write {}", path.display()))?;
+    }
+    #[cfg(not(unix))]
+    {
+        std::fs::write(path, secret).with_context(|| format!("write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Signature header value for `body` sent at `timestamp`.
+pub fn sign(secret: &str, timestamp: u64, body: &str) -> String {
+    crate::webhooks::sign(secret, &format!("{timestamp}.{body}"))
+}
+
+/// Check a request's timestamp and signature headers against `body`.
+pub fn verify(
+    secret: &str,
+    timestamp: Option<&str>,
+    signature: Option<&str>,
+    body: &[u8],
+    now: u64,
+) -> Result<(), VerifyError> {
+    let (Some(timestamp), Some(signature)) = (timestamp, signature) else {
+        return Err(VerifyError::Missing);
+    };
+    let sent_at: u64 = timestamp.parse().map_err(|_| VerifyError::Invalid)?;
+    if now.abs_diff(sent_at) > MAX_SKEW_SECS {
+        return Err(VerifyError::Stale);
+    }
+    let tag = signature
+        .strip_prefix("sha256=")
+        .and_then(from_hex)
+        .ok_or(VerifyError::Invalid)?;
+
+    let mut message = format!("{timestamp}.").into_bytes();
+    message.extend_from_slice(body);
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::verify(&key, &message, &tag).map_err(|_| VerifyError::Invalid)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub(crate) fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_760_000_000;
+
+    #[test]
+    fn accepts_fresh_signed_requests() {
+        let body = r#"{"type":"claude_session_end","session_id":"abc"}"#;
+        let signature = sign("s3cret", NOW, body);
+        let timestamp = NOW.to_string();
+        assert_eq!(
+            verify(
+                "s3cret",
+                Some(&timestamp),
+                Some(&signature),
+                body.as_bytes(),
+                NOW + 10
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_unsigned_stale_and_tampered_requests() {
+        let body = r#"{"type":"claude_session_end","session_id":"abc"}"#;
+        let signature = sign("s3cret", NOW, body);
+        let timestamp = NOW.to_string();
+        let check = |timestamp: Option<&str>, signature: Option<&str>, body: &str, now| {
+            verify("s3cret", timestamp, signature, body.as_bytes(), now)
+        };
+
+        assert_eq!(check(None, None, body, NOW), Err(VerifyError::Missing));
+        assert_eq!(
+            check(Some(&timestamp), Some(&signature), body, NOW + 301),
+            Err(VerifyError::Stale)
+        );
+        assert_eq!(
+            check(Some(&timestamp), Some(&signature), "{}", NOW),
+            Err(VerifyError::Invalid)
+        );
+        assert_eq!(
+            check(Some(&timestamp), Some("sha256=zz"), body, NOW),
+            Err(VerifyError::Invalid)
+        );
+        let other = sign("other", NOW, body);
+        assert_eq!(
+            check(Some(&timestamp), Some(&other), body, NOW),
+            Err(VerifyError::Invalid)
+        );
+    }
+}
@@ -1,16 +1,39 @@
 //! Image extraction — writes data-URI images to disk, returns path-based references.
+//!
+//! Extracted images are served by `GET /api/images/{session_id}/{image_id}`.
+//! On the way to clients, small ones are inlined as data URIs and larger ones
+//! become `server` references to that URL, fetched on demand with the usual
+//! auth, so they survive snapshot size limits and reconnects.
+//!
+//! Raster images also get a thumbnail (longest side `THUMBNAIL_MAX_DIMENSION`)
+//! under `<session>/thumbs/`, written when the image is stored or on first
+//! request. `?size=thumb` serves it; `?size=full`, the default, the original.
 
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use axum::{
+    extract::{Path as UrlPath, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
+use image::ImageFormat;
+use serde::Deserialize;
 use tracing::warn;
 
 use orbitdock_protocol::ImageInput;
 
 use crate::paths::images_dir;
 
+pub const IMAGE_URL_PREFIX: &str = "/api/images/";
+/// Extracted images up to this size still travel inline.
+const INLINE_MAX_BYTES: u64 = 64 * 1024;
+/// Longest side of a thumbnail, in pixels. Smaller images are their own thumbnail.
+const THUMBNAIL_MAX_DIMENSION: u32 = 480;
+const THUMBNAIL_DIR: &str = "thumbs";
+
 /// If `image` is a data URI, decode it to disk and return a path-based `ImageInput`.
 /// Already-path images and failures are returned unchanged (graceful degradation).
 pub fn extract_image_to_disk(
@@ -58,20 +81,31 @@ pub fn extract_images_to_disk(
         .collect()
 }
 
-/// Convert path-based image inputs to data URIs for cross-device transport.
+/// Convert path-based image inputs for cross-device transport: large images
+/// extracted for `session_id` become `server` URL references, the rest data URIs.
 /// Non-path and conversion failures are returned unchanged.
-pub fn normalize_images_for_transport(images: &[ImageInput]) -> Vec<ImageInput> {
+pub fn normalize_images_for_transport(images: &[ImageInput], session_id: &str) -> Vec<ImageInput> {
     images
         .iter()
-        .map(normalize_image_for_transport)
+        .map(|image| normalize_image_for_transport(image, session_id))
         .collect::<Vec<_>>()
 }
 
-fn normalize_image_for_transport(image: &ImageInput) -> ImageInput {
+fn normalize_image_for_transport(image: &ImageInput, session_id: &str) -> ImageInput {
     if image.input_type != "path" {
         return image.clone();
     }
 
+    if let Some(url) = served_image_url(Path::new(&image.value), session_id) {
+        let large = fs::metadata(&image.value).is_ok_and(|meta| meta.len() > INLINE_MAX_BYTES);
+        if large {
+            return ImageInput {
+                input_type: "server".to_string(),
+                value: url,
+            };
+        }
+    }
+
     match path_image_to_data_uri(&image.value) {
         Ok(data_uri) => ImageInput {
             input_type: "url".to_string(),
@@ -119,28 +153,8 @@ fn write_data_uri_to_disk(
         .decode(base64_data)
         .map_err(|e| format!("base64 decode: {e}"))?;
 
-    // Sanitize session_id for filesystem (replace non-alphanumeric except dash/underscore)
-    let safe_session: String = session_id
-        .chars()
-        .map(|c| {
-            if c.is_alphanumeric() || c == '-' || c == '_' {
-                c
-            } else {
-                '_'
-            }
-        })
-        .collect();
-
-    let safe_msg: String = message_id
-        .chars()
-        .map(|c| {
-            if c.is_alphanumeric() || c == '-' || c == '_' {
-                c
-            } else {
-                '_'
-            }
-        })
-        .collect();
+    let safe_session = safe_path_component(session_id);
+    let safe_msg = safe_path_component(message_id);
 
     let dir = images_dir().join(&safe_session);
     fs::create_dir_all(&dir).map_err(|e| format!("create dir: {e}"))?;
@@ -154,10 +168,182 @@ fn write_data_uri_to_disk(
     }
 
     fs::write(&path, &bytes).map_err(|e| format!("write file: {e}"))?;
+    spawn_thumbnail(path.clone());
 
     Ok(path)
 }
 
+/// Generate `path`'s thumbnail off the async runtime when there is one.
+fn spawn_thumbnail(path: PathBuf) {
+    let generate = move || {
+        if let Err(e) = ensure_thumbnail(&path) {
+            warn!(
+                event = "image.thumbnail_failed",
+                path = %path.display(),
+                error = %e,
+                "Failed to generate image thumbnail"
+            );
+        }
+    };
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => {
+            runtime.spawn_blocking(generate);
+        }
+        Err(_) => generate(),
+    }
+}
+
+fn thumbnail_path(path: &Path) -> Option<PathBuf> {
+    Some(path.parent()?.join(THUMBNAIL_DIR).join(path.file_name()?))
+}
+
+/// Formats thumbnails are re-encoded in; anything else is only served full size.
+fn thumbnail_format(path: &Path) -> Option<ImageFormat> {
+    match mime_type_for_path(path.to_str()?)? {
+        "image/png" => Some(ImageFormat::Png),
+        "image/jpeg" => Some(ImageFormat::Jpeg),
+        "image/gif" => Some(ImageFormat::Gif),
+        "image/webp" => Some(ImageFormat::WebP),
+        _ => None,
+    }
+}
+
+/// The thumbnail for the image at `path`, written if missing. `None` when the
+/// image is already thumbnail-sized or can't be re-encoded.
+fn ensure_thumbnail(path: &Path) -> Result<Option<PathBuf>, String> {
+    let (Some(format), Some(thumb_path)) = (thumbnail_format(path), thumbnail_path(path)) else {
+        return Ok(None);
+    };
+    if thumb_path.exists() {
+        return Ok(Some(thumb_path));
+    }
+
+    let source = image::open(path).map_err(|e| format!("decode image: {e}"))?;
+    if source.width() <= THUMBNAIL_MAX_DIMENSION && source.height() <= THUMBNAIL_MAX_DIMENSION {
+        return Ok(None);
+    }
+    let mut thumbnail = source.thumbnail(THUMBNAIL_MAX_DIMENSION, THUMBNAIL_MAX_DIMENSION);
+    if format == ImageFormat::Jpeg {
+        // JPEG has no alpha channel.
+        thumbnail = thumbnail.to_rgb8().into();
+    }
+
+    let dir = thumb_path.parent().ok_or("thumbnail path has no parent")?;
+    fs::create_dir_all(dir).map_err(|e| format!("create dir: {e}"))?;
+    // Write aside and rename so a concurrent request never serves a partial file.
+    let partial = dir.join(format!(".{}.partial", uuid::Uuid::new_v4()));
+    let written = thumbnail
+        .save_with_format(&partial, format)
+        .map_err(|e| format!("encode thumbnail: {e}"))
+        .and_then(|()| fs::rename(&partial, &thumb_path).map_err(|e| format!("rename: {e}")));
+    if written.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    written.map(|()| Some(thumb_path))
+}
+
+/// Replace anything but alphanumerics, dashes, and underscores for use in a file name.
+fn safe_path_component(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The `/api/images` URL for an image extracted for `session_id`, if `path` is one.
+fn served_image_url(path: &Path, session_id: &str) -> Option<String> {
+    // The URL carries the real id, which only names the directory when it's safe.
+    if safe_path_component(session_id) != session_id {
+        return None;
+    }
+    let relative = path.strip_prefix(images_dir().join(session_id)).ok()?;
+    let image_id = relative.to_str()?;
+    is_valid_image_id(image_id).then(|| format!("{IMAGE_URL_PREFIX}{session_id}/{image_id}"))
+}
+
+fn is_valid_image_id(image_id: &str) -> bool {
+    !image_id.starts_with('.')
+        && image_id
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && mime_type_for_path(image_id).is_some()
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImageQuery {
+    /// `thumb` or `full` (the default).
+    #[serde(default)]
+    pub size: Option<String>,
+}
+
+/// `GET /api/images/{session_id}/{image_id}?size=thumb|full`: an image
+/// extracted from one of the session's messages. Session access is checked by
+/// `session_access`.
+pub async fn image_handler(
+    UrlPath((session_id, image_id)): UrlPath<(String, String)>,
+    Query(query): Query<ImageQuery>,
+) -> Response {
+    let thumb = match query.size.as_deref() {
+        None | Some("full") => false,
+        Some("thumb") => true,
+        Some(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    if !is_valid_image_id(&image_id) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let mut path = images_dir()
+        .join(safe_path_component(&session_id))
+        .join(&image_id);
+    if thumb && path.exists() {
+        let source = path.clone();
+        // Images stored before thumbnails existed get one on first request.
+        match tokio::task::spawn_blocking(move || ensure_thumbnail(&source)).await {
+            Ok(Ok(Some(thumb_path))) => path = thumb_path,
+            Ok(Ok(None)) => {}
+            Ok(Err(e)) => warn!(
+                event = "image.thumbnail_failed",
+                path = %path.display(),
+                error = %e,
+                "Failed to generate image thumbnail, serving full size"
+            ),
+            Err(e) => warn!(
+                event = "image.thumbnail_failed",
+                path = %path.display(),
+                error = %e,
+                "Thumbnail task failed, serving full size"
+            ),
+        }
+    }
+    let Ok(bytes) = tokio::fs::read(&path).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let mime_type = mime_type_for_path(&image_id).unwrap_or("application/octet-stream");
+    (
+        [
+            (header::CONTENT_TYPE, mime_type),
+            // Extracted files are never rewritten.
+            (
+                header::CACHE_CONTROL,
+                "private, max-age=31536000, immutable",
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+            // SVGs must not run scripts if opened directly.
+            (
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; style-src 'unsafe-inline'",
+            ),
+        ],
+        bytes,
+    )
+        .into_response()
+}
+
 fn path_image_to_data_uri(path: &str) -> Result<String, String> {
     let mime_type = mime_type_for_path(path)
         .ok_or_else(|| format!("unsupported image extension: {}", Path::new(path).display()))?;
@@ -199,3 +385,41 @@ fn mime_type_for_path(path: &str) -> Option<&'static str> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_ids_must_be_plain_image_file_names() {
+        assert!(is_valid_image_id("3f2a-1.png"));
+        assert!(!is_valid_image_id("../secrets.png"));
+        assert!(!is_valid_image_id(".hidden.png"));
+        assert!(!is_valid_image_id("notes.txt"));
+        assert_eq!(safe_path_component("a/../b"), "a____b");
+    }
+
+    #[test]
+    fn thumbnails_downscale_large_images_only() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let large = dir.path().join("msg_0.png");
+        image::RgbaImage::new(1200, 600)
+            .save(&large)
+            .expect("write png");
+        let small = dir.path().join("msg_1.jpg");
+        image::RgbImage::new(300, 200)
+            .save(&small)
+            .expect("write jpeg");
+
+        let thumb = ensure_thumbnail(&large)
+            .expect("thumbnail")
+            .expect("large image gets a thumbnail");
+        assert_eq!(thumb, dir.path().join("thumbs/msg_0.png"));
+        assert_eq!(
+            image::image_dimensions(&thumb).expect("dimensions"),
+            (480, 240)
+        );
+        assert_eq!(ensure_thumbnail(&small), Ok(None));
+        assert_eq!(ensure_thumbnail(Path::new("/tmp/diagram.svg")), Ok(None));
+    }
+}
@@ -0,0 +1,181 @@
+//! Where the server listens.
+//!
+//! `--bind` takes a TCP address or `unix:/path/to.sock`. `--tailscale` keeps
+//! the port and swaps the address for this machine's tailnet IP, so the server
+//! is reachable from the tailnet but not from the LAN. The resolved address is
+//! written next to the PID file so `orbitdock status` checks the right place.
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::Context;
+
+use crate::paths;
+
+const UNIX_PREFIX: &str = "unix:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindTarget {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl BindTarget {
+    /// Whether only this machine can connect. Unix sockets are guarded by
+    /// file permissions.
+    pub fn is_local(&self) -> bool {
+        match self {
+            BindTarget::Tcp(addr) => addr.ip().is_loopback(),
+            BindTarget::Unix(_) => true,
+        }
+    }
+
+    /// Keep the port but listen on this machine's Tailscale address.
+    pub fn on_tailscale(&self) -> anyhow::Result<BindTarget> {
+        let BindTarget::Tcp(addr) = self else {
+            anyhow::bail!("--tailscale can't be combined with a unix socket bind");
+        };
+        let ip = tailscale_ip()
+            .context("--tailscale needs a running Tailscale client (`tailscale status`)")?;
+        Ok(BindTarget::Tcp(SocketAddr::new(ip, addr.port())))
+    }
+
+    /// Where a local client reaches this listener.
+    pub fn local_url(&self, tls: bool) -> String {
+        match self {
+            BindTarget::Tcp(addr) => {
+                let ip = match addr.ip() {
+                    IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+                    ip => ip,
+                };
+                let scheme = if tls { "https" } else { "http" };
+                format!("{scheme}://{}", SocketAddr::new(ip, addr.port()))
+            }
+            BindTarget::Unix(path) => format!("{UNIX_PREFIX}{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for BindTarget {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = value.strip_prefix(UNIX_PREFIX) {
+            if path.is_empty() {
+                return Err("unix socket bind needs a path, e.g. unix:/tmp/orbitdock.sock".into());
+            }
+            return Ok(BindTarget::Unix(PathBuf::from(path)));
+        }
+        value
+            .parse::<SocketAddr>()
+            .map(BindTarget::Tcp)
+            .map_err(|e| format!("{e} (expected host:port or unix:/path)"))
+    }
+}
+
+impl fmt::Display for BindTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindTarget::Tcp(addr) => write!(f, "{addr}"),
+            BindTarget::Unix(path) => write!(f, "{UNIX_PREFIX}{}", path.display()),
+        }
+    }
+}
+
+/// This machine's tailnet address, preferring IPv4.
+pub fn tailscale_ip() -> Option<IpAddr> {
+    let output = std::process::Command::new("tailscale")
+        .args(["status", "--json"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let addrs: Vec<IpAddr> = json
+        .get("Self")?
+        .get("TailscaleIPs")?
+        .as_array()?
+        .iter()
+        .filter_map(|addr| addr.as_str()?.parse().ok())
+        .collect();
+    addrs
+        .iter()
+        .find(|addr| addr.is_ipv4())
+        .or_else(|| addrs.first())
+        .copied()
+}
+
+/// Bind a Unix socket, replacing a stale one, readable only by this user.
+pub fn bind_unix(path: &Path) -> anyhow::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", path.display());
+        }
+        // Refuse to steal the socket from a server that is still running.
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            anyhow::bail!("another server is listening on {}", path.display());
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("remove stale socket {}", path.display()))?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("bind unix socket {}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .with_context(|| format!("restrict permissions on {}", path.display()))?;
+    Ok(listener)
+}
+
+pub fn write_listen_file(url: &str) {
+    let _ = std::fs::write(paths::listen_file_path(), url);
+}
+
+pub fn read_listen_file() -> Option<String> {
+    let url = std::fs::read_to_string(paths::listen_file_path()).ok()?;
+    Some(url.trim().to_string()).filter(|url| !url.is_empty())
+}
+
+pub fn remove_listen_file() {
+    let _ = std::fs::remove_file(paths::listen_file_path());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tcp_and_unix_binds() {
+        assert_eq!(
+            "0.0.0.0:4000".parse::<BindTarget>(),
+            Ok(BindTarget::Tcp("0.0.0.0:4000".parse().unwrap()))
+        );
+        assert_eq!(
+            "unix:/tmp/orbitdock.sock".parse::<BindTarget>(),
+            Ok(BindTarget::Unix(PathBuf::from("/tmp/orbitdock.sock")))
+        );
+        assert!("unix:".parse::<BindTarget>().is_err());
+        assert!("localhost".parse::<BindTarget>().is_err());
+    }
+
+    #[test]
+    fn local_url_points_wildcard_binds_at_loopback() {
+        let any: BindTarget = "0.0.0.0:4000".parse().unwrap();
+        assert_eq!(any.local_url(false), "http://127.0.0.1:4000");
+        assert!(!any.is_local());
+
+        let tailnet: BindTarget = "100.64.1.2:4000".parse().unwrap();
+        assert_eq!(tailnet.local_url(true), "https://100.64.1.2:4000");
+
+        let socket: BindTarget = "unix:/tmp/od.sock".parse().unwrap();
+        assert_eq!(socket.local_url(false), "unix:/tmp/od.sock");
+        assert!(socket.is_local());
+    }
+}
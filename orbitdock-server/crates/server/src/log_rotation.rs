@@ -0,0 +1,319 @@
+//! Size- and day-based rotation for `server.log`.
+//!
+//! The active file is always `server.log` so `tail -f` and `orbitdock logs`
+//! keep working. When it crosses the size limit or the UTC day changes it is
+//! renamed to `server.<date>.<n>.log`, gzipped in the background (best-effort),
+//! and archives beyond the retention count are removed.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+pub const ACTIVE_LOG_NAME: &str = "server.log";
+
+const DEFAULT_MAX_BYTES: u64 = 50 * 1024 * 1024;
+const DEFAULT_MAX_FILES: usize = 10;
+
+/// A policy from a config reload, picked up by the writer on its next write.
+static PENDING_POLICY: Mutex<Option<RotationPolicy>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotationPolicy {
+    /// Rotate once the active file would grow past this many bytes.
+    pub max_bytes: u64,
+    /// Rotated archives to keep (the active file is not counted).
+    pub max_files: usize,
+    pub compress: bool,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_files: DEFAULT_MAX_FILES,
+            compress: true,
+        }
+    }
+}
+
+impl RotationPolicy {
+    /// Read overrides from `ORBITDOCK_SERVER_LOG_MAX_BYTES`,
+    /// `ORBITDOCK_SERVER_LOG_MAX_FILES`, and `ORBITDOCK_SERVER_LOG_COMPRESS`.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let env_parse = |key: &str| crate::config_file::var(key)?.trim().parse::<u64>().ok();
+        Self {
+            max_bytes: env_parse("ORBITDOCK_SERVER_LOG_MAX_BYTES")
+                .filter(|bytes| *bytes > 0)
+                .unwrap_or(defaults.max_bytes),
+            max_files: env_parse("ORBITDOCK_SERVER_LOG_MAX_FILES")
+                .map(|count| count as usize)
+                .unwrap_or(defaults.max_files),
+            compress: crate::config_file::var("ORBITDOCK_SERVER_LOG_COMPRESS").as_deref()
+                != Some("0"),
+        }
+    }
+}
+
+/// Swap the running writer's policy. Archives over the new count are pruned
+/// at the next rotation.
+pub fn update_policy(policy: RotationPolicy) {
+    *PENDING_POLICY.lock().unwrap_or_else(|e| e.into_inner()) = Some(policy);
+}
+
+pub struct RotatingFileWriter {
+    dir: PathBuf,
+    policy: RotationPolicy,
+    file: File,
+    size: u64,
+    /// UTC day number the active file's contents belong to.
+    day: u64,
+}
+
+impl RotatingFileWriter {
+    pub fn new(dir: &Path, policy: RotationPolicy) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(ACTIVE_LOG_NAME);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        let day = metadata
+            .modified()
+            .ok()
+            .map(utc_day)
+            .unwrap_or_else(|| utc_day(SystemTime::now()));
+
+        prune_archives(dir, policy.max_files);
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            policy,
+            file,
+            size: metadata.len(),
+            day,
+        })
+    }
+
+    fn should_rotate(&self, incoming: usize, today: u64) -> bool {
+        self.size > 0 && (self.size + incoming as u64 > self.policy.max_bytes || today != self.day)
+    }
+
+    fn rotate(&mut self, today: u64) -> io::Result<()> {
+        self.file.flush()?;
+        let active = self.dir.join(ACTIVE_LOG_NAME);
+        let archive = next_archive_path(&self.dir, self.day);
+        std::fs::rename(&active, &archive)?;
+
+        self.file = OpenOptions::new().create(true).append(true).open(&active)?;
+        self.size = 0;
+        self.day = today;
+
+        let dir = self.dir.clone();
+        let policy = self.policy.clone();
+        std::thread::spawn(move || {
+            if policy.compress {
+                compress(&archive);
+            }
+            prune_archives(&dir, policy.max_files);
+        });
+        Ok(())
+    }
+}
+
+impl Write for RotatingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(policy) = PENDING_POLICY
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+        {
+            self.policy = policy;
+        }
+        let today = utc_day(SystemTime::now());
+        if self.should_rotate(buf.len(), today) {
+            // A failed rotation keeps appending to the current file rather
+            // than dropping log lines.
+            let _ = self.rotate(today);
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn utc_day(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0)
+}
+
+/// `YYYY-MM-DD` for a day count since the Unix epoch.
+fn format_utc_day(day: u64) -> String {
+    // Civil-from-days (Howard Hinnant), restricted to post-epoch dates.
+    let z = day + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + u64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+fn next_archive_path(dir: &Path, day: u64) -> PathBuf {
+    let date = format_utc_day(day);
+    (1..)
+        .map(|n| dir.join(format!("server.{date}.{n}.log")))
+        .find(|path| !path.exists() && !gz_path(path).exists())
+        .expect("unbounded archive index")
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+fn is_archive(name: &str) -> bool {
+    name != ACTIVE_LOG_NAME
+        && name.starts_with("server.")
+        && (name.ends_with(".log") || name.ends_with(".log.gz"))
+}
+
+/// Gzip `path` in place. Leaves the plain file behind if compression fails.
+fn compress(path: &Path) {
+    if let Err(err) = gzip_file(path) {
+        let _ = std::fs::remove_file(gz_path(path));
+        tracing::warn!(
+            component = "logging",
+            event = "logging.compress_failed",
+            path = %path.display(),
+            error = %err,
+            "Could not gzip rotated log; keeping it uncompressed"
+        );
+    }
+}
+
+fn gzip_file(path: &Path) -> io::Result<()> {
+    let mut input = File::open(path)?;
+    let output = File::create(gz_path(path))?;
+    let mut encoder = GzEncoder::new(io::BufWriter::new(output), Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.into_inner()?.sync_all()?;
+    std::fs::remove_file(path)
+}
+
+/// Delete the oldest rotated archives beyond `keep`.
+fn prune_archives(dir: &Path, keep: usize) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut archives: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_str().is_some_and(is_archive))
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, entry.path()))
+        })
+        .collect();
+    if archives.len() <= keep {
+        return;
+    }
+    archives.sort();
+    let excess = archives.len() - keep;
+    for (_, path) in archives.into_iter().take(excess) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archives(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| is_archive(name))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn formats_utc_days_as_calendar_dates() {
+        assert_eq!(format_utc_day(0), "1970-01-01");
+        assert_eq!(format_utc_day(11_016), "2000-02-29");
+        assert_eq!(format_utc_day(20_743), "2026-10-17");
+    }
+
+    #[test]
+    fn rotates_past_size_limit_and_prunes_old_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = RotationPolicy {
+            max_bytes: 16,
+            max_files: 2,
+            compress: false,
+        };
+        let mut writer = RotatingFileWriter::new(dir.path(), policy).unwrap();
+
+        for line in ["first line....\n", "second line...\n", "third line....\n"] {
+            writer.write_all(line.as_bytes()).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let active = std::fs::read_to_string(dir.path().join(ACTIVE_LOG_NAME)).unwrap();
+        assert_eq!(active, "third line....\n");
+
+        let date = format_utc_day(utc_day(SystemTime::now()));
+        // Pruning runs on a background thread after each rotation.
+        for _ in 0..50 {
+            if archives(dir.path()).len() == 2 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(
+            archives(dir.path()),
+            vec![
+                format!("server.{date}.1.log"),
+                format!("server.{date}.2.log")
+            ]
+        );
+
+        for n in 3..=5 {
+            std::fs::write(dir.path().join(format!("server.{date}.{n}.log")), "x").unwrap();
+        }
+        prune_archives(dir.path(), 2);
+        assert_eq!(archives(dir.path()).len(), 2);
+    }
+
+    #[test]
+    fn compress_replaces_the_archive_with_a_gzip() {
+        use std::io::Read;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("server.2026-10-17.1.log");
+        std::fs::write(&path, "rotated line\n").unwrap();
+
+        compress(&path);
+
+        assert!(!path.exists());
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(File::open(gz_path(&path)).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "rotated line\n");
+    }
+}
@@ -1,13 +1,22 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::fmt;
 use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::reload;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::{EnvFilter, Registry};
+
+use crate::log_rotation::{RotatingFileWriter, RotationPolicy, ACTIVE_LOG_NAME};
 
 const DEFAULT_FILTER: &str = "info,tower_http=warn,hyper=warn";
 
+/// Reload handle for the active filter, set once by `init_logging`.
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
 pub struct LoggingHandle {
     pub run_id: String,
     pub guard: WorkerGuard,
@@ -16,7 +25,7 @@ pub struct LoggingHandle {
 pub fn init_logging() -> anyhow::Result<LoggingHandle> {
     let log_dir = crate::paths::log_dir();
     std::fs::create_dir_all(&log_dir)?;
-    let log_path = log_dir.join("server.log");
+    let log_path = log_dir.join(ACTIVE_LOG_NAME);
 
     if std::env::var("ORBITDOCK_TRUNCATE_SERVER_LOG_ON_START").as_deref() == Ok("1") {
         let _ = std::fs::OpenOptions::new()
@@ -26,16 +35,16 @@ pub fn init_logging() -> anyhow::Result<LoggingHandle> {
             .open(&log_path)?;
     }
 
-    let filter = std::env::var("ORBITDOCK_SERVER_LOG_FILTER")
-        .ok()
-        .and_then(|value| EnvFilter::try_new(value).ok())
-        .or_else(|| EnvFilter::try_from_default_env().ok())
-        .unwrap_or_else(|| EnvFilter::new(DEFAULT_FILTER));
+    let filter = configured_filter();
 
-    let file_appender = tracing_appender::rolling::never(&log_dir, "server.log");
+    let rotation = RotationPolicy::from_env();
+    let file_appender = RotatingFileWriter::new(&log_dir, rotation.clone())?;
     let (writer, guard) = tracing_appender::non_blocking(file_appender);
     let format = std::env::var("ORBITDOCK_SERVER_LOG_FORMAT").unwrap_or_else(|_| "json".into());
 
+    let (filter, filter_handle) = reload::Layer::new(filter);
+    let _ = FILTER_HANDLE.set(filter_handle);
+
     let registry = tracing_subscriber::registry().with(filter);
     if format.eq_ignore_ascii_case("pretty") {
         registry
@@ -77,10 +86,134 @@ pub fn init_logging() -> anyhow::Result<LoggingHandle> {
         event = "logging.initialized",
         log_path = %log_path.display(),
         format = %format,
-        filter = %std::env::var("ORBITDOCK_SERVER_LOG_FILTER")
-            .or_else(|_| std::env::var("RUST_LOG"))
-            .unwrap_or_else(|_| DEFAULT_FILTER.to_string()),
+        max_bytes = rotation.max_bytes,
+        max_files = rotation.max_files,
+        compress = rotation.compress,
+        filter = %crate::config_file::var("ORBITDOCK_SERVER_LOG_FILTER")
+            .or_else(|| std::env::var("RUST_LOG").ok())
+            .unwrap_or_else(|| DEFAULT_FILTER.to_string()),
     );
 
     Ok(LoggingHandle { run_id, guard })
 }
+
+/// `ORBITDOCK_SERVER_LOG_FILTER` (env or config file), then `RUST_LOG`, then
+/// the default.
+fn configured_filter() -> EnvFilter {
+    crate::config_file::var("ORBITDOCK_SERVER_LOG_FILTER")
+        .and_then(|value| EnvFilter::try_new(value).ok())
+        .or_else(|| EnvFilter::try_from_default_env().ok())
+        .unwrap_or_else(|| EnvFilter::new(DEFAULT_FILTER))
+}
+
+/// Replace the filter with the configured one after a config reload,
+/// discarding any `SetLogLevel` changes.
+pub fn reload_filter() -> anyhow::Result<()> {
+    let handle = FILTER_HANDLE
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("logging is not initialized"))?;
+    handle.reload(configured_filter())?;
+    Ok(())
+}
+
+/// The active filter directives, or `None` before logging is initialized.
+pub fn current_filter() -> Option<String> {
+    FILTER_HANDLE
+        .get()?
+        .with_current(|filter| filter.to_string())
+        .ok()
+}
+
+/// Set `level` for `target` (or the default level when `target` is `None`),
+/// keeping every other directive. Returns the new filter.
+pub fn set_log_level(target: Option<&str>, level: &str) -> anyhow::Result<String> {
+    let handle = FILTER_HANDLE
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("logging is not initialized"))?;
+    let current = handle.with_current(|filter| filter.to_string())?;
+    let directives = merge_directive(&current, target, level)?;
+    let filter = EnvFilter::try_new(&directives)?;
+    handle.reload(filter)?;
+
+    tracing::info!(
+        component = "logging",
+        event = "logging.filter_changed",
+        target = target.unwrap_or("*"),
+        level = %level,
+        filter = %directives,
+    );
+
+    Ok(directives)
+}
+
+/// Replace the directive for `target` in a comma-separated filter string.
+fn merge_directive(current: &str, target: Option<&str>, level: &str) -> anyhow::Result<String> {
+    let level = LevelFilter::from_str(level)
+        .map_err(|_| anyhow::anyhow!("invalid log level: {level}"))?
+        .to_string()
+        .to_lowercase();
+    let target = target.map(str::trim).filter(|target| !target.is_empty());
+    if let Some(target) = target {
+        if target.contains([',', '=', '[', ']', ' ']) {
+            anyhow::bail!("invalid log target: {target}");
+        }
+    }
+
+    let mut directives: Vec<String> = current
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .filter(|directive| match (target, directive.split_once('=')) {
+            (Some(target), Some((existing, _))) => existing != target,
+            (Some(_), None) => true,
+            (None, Some(_)) => true,
+            (None, None) => LevelFilter::from_str(directive).is_err(),
+        })
+        .map(str::to_string)
+        .collect();
+
+    let directive = match target {
+        Some(target) => format!("{target}={level}"),
+        None => level,
+    };
+    // The bare default level conventionally leads the list.
+    if target.is_none() {
+        directives.insert(0, directive);
+    } else {
+        directives.push(directive);
+    }
+    Ok(directives.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_replaces_default_level_and_keeps_targets() {
+        let merged = merge_directive(DEFAULT_FILTER, None, "DEBUG").unwrap();
+        assert_eq!(merged, "debug,tower_http=warn,hyper=warn");
+
+        let merged = merge_directive("tower_http=warn", None, "trace").unwrap();
+        assert_eq!(merged, "trace,tower_http=warn");
+    }
+
+    #[test]
+    fn merge_replaces_or_appends_target_directive() {
+        let merged = merge_directive(DEFAULT_FILTER, Some("hyper"), "error").unwrap();
+        assert_eq!(merged, "info,tower_http=warn,hyper=error");
+
+        let merged =
+            merge_directive(DEFAULT_FILTER, Some("orbitdock::websocket"), "debug").unwrap();
+        assert_eq!(
+            merged,
+            "info,tower_http=warn,hyper=warn,orbitdock::websocket=debug"
+        );
+    }
+
+    #[test]
+    fn merge_rejects_unknown_levels_and_malformed_targets() {
+        assert!(merge_directive(DEFAULT_FILTER, None, "loud").is_err());
+        assert!(merge_directive(DEFAULT_FILTER, Some("a=b"), "info").is_err());
+    }
+}
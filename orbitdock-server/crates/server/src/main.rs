@@ -4,46 +4,110 @@
 //! Provides real-time session management via WebSocket.
 
 mod ai_naming;
+mod attachments;
+mod attention;
+mod audit;
 mod auth;
 mod auth_tokens;
+mod budgets;
+mod chat_notifications;
 mod claude_session;
+mod claude_transcript_watcher;
+mod cmd_config;
 mod cmd_doctor;
 mod cmd_ensure_path;
+mod cmd_gc;
 mod cmd_hook_forward;
 mod cmd_init;
 mod cmd_install_hooks;
 mod cmd_install_service;
+mod cmd_logs;
 mod cmd_pair;
+mod cmd_peers;
 mod cmd_remote_setup;
 mod cmd_setup;
+mod cmd_simulate;
+mod cmd_stats;
 mod cmd_status;
+mod cmd_stop;
 mod cmd_tunnel;
+mod cmd_upgrade;
+mod cmd_users;
+mod cmd_webhooks;
 mod codex_session;
+mod config_file;
+mod context_warnings;
+mod cors;
 pub(crate) mod crypto;
+mod dashboard;
+mod editors;
+mod effort;
+mod event_journal;
+mod federation;
 mod git;
 mod git_refresh;
+mod github_issues;
+mod health;
 mod hook_handler;
+mod hook_signature;
 mod http_api;
 pub(crate) mod images;
+mod listen;
+mod log_rotation;
 mod logging;
+mod media_gc;
+mod message_eviction;
 mod metrics;
 mod migration_runner;
 mod normalization;
 pub(crate) mod paths;
 mod persistence;
+mod pricing;
+mod project_config;
+mod provider_homes;
+mod provider_info;
+mod push;
+mod rate_limit;
+mod reconnect;
+mod redaction;
+mod resource_monitor;
+mod restore_details;
+mod rolling_summary;
 mod rollout_watcher;
+mod scopes;
+mod semantic_search;
 mod session;
+mod session_access;
 mod session_actor;
 mod session_command;
 mod session_command_handler;
+mod session_diagnostics;
+mod session_fanout;
+mod session_mcp;
 mod session_naming;
+mod session_tags;
+mod server_settings;
 mod session_utils;
 mod shell;
+mod shell_policy;
 mod snapshot_compaction;
+mod spool;
 mod state;
+mod stored_text;
 mod subagent_parser;
+mod terminal;
+mod tickets;
+mod tls;
+mod transcript_resync;
+mod transcription;
 mod transition;
+mod turn_metrics;
+mod turn_recovery;
+mod turn_summary;
 mod usage_probe;
+mod user_hooks;
+mod wal_checkpoint;
+mod webhooks;
 mod websocket;
 mod worktree_include;
 mod worktree_service;
@@ -56,19 +120,13 @@ use std::time::UNIX_EPOCH;
 
 use axum::{
     extract::DefaultBodyLimit,
-    http::{
-        header::{AUTHORIZATION, CONTENT_TYPE},
-        HeaderValue, Method,
-    },
-    response::IntoResponse,
     routing::{delete, get, patch, post, put},
     Router,
 };
 use clap::{Parser, Subcommand};
 use orbitdock_protocol::{
-    CodexIntegrationMode, Provider, SessionStatus, TokenUsage, TurnDiff, WorkStatus,
+    CodexIntegrationMode, Provider, SessionStatus, TokenUsage, WorkStatus,
 };
-use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 use tracing::{info, warn};
 
@@ -91,7 +149,7 @@ struct Cli {
 
     /// Bind address (top-level, for backward compat — prefer `start --bind`)
     #[arg(long, env = "ORBITDOCK_BIND_ADDR")]
-    bind: Option<SocketAddr>,
+    bind: Option<BindTarget>,
 
     /// Server URL for client commands (default: http://127.0.0.1:4000)
     #[arg(long, short = 's', global = true, env = "ORBITDOCK_URL")]
@@ -118,9 +176,14 @@ enum Command {
     // ── Server admin commands ────────────────────────────────────
     /// Start the server (default when no subcommand given)
     Start {
-        /// Bind address (e.g. 0.0.0.0:4000 for remote access)
+        /// Bind address (e.g. 0.0.0.0:4000 for remote access, or
+        /// unix:/path/to.sock for a Unix domain socket)
         #[arg(long, default_value = "127.0.0.1:4000", env = "ORBITDOCK_BIND_ADDR")]
-        bind: SocketAddr,
+        bind: BindTarget,
+
+        /// Listen only on this machine's Tailscale address, keeping the --bind port
+        #[arg(long, env = "ORBITDOCK_TAILSCALE", default_value_t = false)]
+        tailscale: bool,
 
         /// Auth token (requests must include `Authorization: Bearer <token>`)
         #[arg(long, env = "ORBITDOCK_AUTH_TOKEN")]
@@ -138,12 +201,13 @@ enum Command {
         #[arg(long, env = "ORBITDOCK_SERVER_SECONDARY", default_value_t = false)]
         secondary: bool,
 
-        /// Path to TLS certificate file (PEM format)
-        #[arg(long, env = "ORBITDOCK_TLS_CERT")]
+        /// Path to TLS certificate file (PEM format). Reloaded when it
+        /// changes, so ACME clients can renew it in place.
+        #[arg(long, env = "ORBITDOCK_TLS_CERT", requires = "tls_key")]
         tls_cert: Option<PathBuf>,
 
         /// Path to TLS private key file (PEM format)
-        #[arg(long, env = "ORBITDOCK_TLS_KEY")]
+        #[arg(long, env = "ORBITDOCK_TLS_KEY", requires = "tls_cert")]
         tls_key: Option<PathBuf>,
     },
 
@@ -167,6 +231,10 @@ enum Command {
         /// Auth token for the remote server
         #[arg(long, env = "ORBITDOCK_AUTH_TOKEN")]
         auth_token: Option<String>,
+
+        /// Secret to sign hook requests with (defaults to the local server's)
+        #[arg(long, env = "ORBITDOCK_HOOK_SECRET")]
+        hook_secret: Option<String>,
     },
 
     /// Internal: forward a Claude hook payload from stdin to OrbitDock server.
@@ -175,6 +243,9 @@ enum Command {
         /// Hook message type.
         hook_type: cmd_hook_forward::HookForwardType,
 
+        /// JSON payload; read from stdin when omitted. Codex passes it here.
+        payload: Option<String>,
+
         /// Override target server URL (defaults to config or localhost).
         #[arg(long)]
         server_url: Option<String>,
@@ -197,6 +268,14 @@ enum Command {
         /// Auth token for the service process (recommended for non-loopback binds)
         #[arg(long, env = "ORBITDOCK_AUTH_TOKEN")]
         auth_token: Option<String>,
+
+        /// Serve HTTPS/WSS with this certificate (PEM format)
+        #[arg(long, requires = "tls_key")]
+        tls_cert: Option<PathBuf>,
+
+        /// Private key for --tls-cert (PEM format)
+        #[arg(long, requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
     },
 
     /// Ensure the server binary directory is persisted on your shell PATH
@@ -206,7 +285,35 @@ enum Command {
     Status,
 
     /// Generate a secure auth token and store its hash in the database
-    GenerateToken,
+    GenerateToken {
+        /// Attribute sessions and actions made with this token to a user
+        #[arg(long)]
+        user: Option<String>,
+
+        /// Note shown by list-tokens
+        #[arg(long)]
+        label: Option<String>,
+
+        /// What the token may do: read, approve, or admin
+        #[arg(long, default_value = "admin")]
+        scope: String,
+
+        /// Expire the token after this long (e.g. 12h, 30d)
+        #[arg(long)]
+        expires_in: Option<String>,
+    },
+
+    /// Create, list, revoke, and rotate auth tokens
+    Token {
+        #[command(subcommand)]
+        action: TokenAction,
+    },
+
+    /// Inspect or reload the server config file (config.toml in the data dir)
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
 
     /// List issued auth tokens
     ListTokens,
@@ -217,9 +324,206 @@ enum Command {
         token_id: String,
     },
 
+    /// Add a named user that auth tokens can be issued to
+    AddUser {
+        /// Login-style name (letters, digits, . _ - @)
+        name: String,
+    },
+
+    /// List users and their active token counts
+    ListUsers,
+
+    /// Disable a user and revoke their tokens
+    RemoveUser {
+        /// User name
+        name: String,
+    },
+
+    /// Add a webhook fired on session events
+    AddWebhook {
+        /// Endpoint that receives the JSON POST
+        url: String,
+
+        /// Shared secret for the X-OrbitDock-Signature HMAC header
+        #[arg(long)]
+        secret: Option<String>,
+
+        /// Comma-separated events to send (turn_completed, approval_pending,
+        /// session_errored, session_ended). Defaults to all.
+        #[arg(long, value_delimiter = ',')]
+        events: Vec<String>,
+    },
+
+    /// Post session events to Slack
+    AddSlack {
+        /// Slack incoming webhook URL (no threading)
+        #[arg(long, conflicts_with_all = ["bot_token", "channel"])]
+        webhook_url: Option<String>,
+
+        /// Bot token (xoxb-...) for chat.postMessage; enables threaded updates
+        #[arg(long, requires = "channel")]
+        bot_token: Option<String>,
+
+        /// Channel id or name to post to with --bot-token
+        #[arg(long, requires = "bot_token")]
+        channel: Option<String>,
+
+        /// Comma-separated events to send. Defaults to all.
+        #[arg(long, value_delimiter = ',')]
+        events: Vec<String>,
+    },
+
+    /// Post session events to a Discord webhook (threads in forum channels)
+    AddDiscord {
+        /// Discord webhook URL
+        url: String,
+
+        /// Comma-separated events to send. Defaults to all.
+        #[arg(long, value_delimiter = ',')]
+        events: Vec<String>,
+    },
+
+    /// List configured webhooks
+    ListWebhooks,
+
+    /// Remove a webhook by id
+    RemoveWebhook {
+        /// Webhook id (wh_...)
+        webhook_id: String,
+    },
+
+    /// Show recent webhook delivery attempts
+    WebhookDeliveries {
+        /// Only show deliveries for this webhook id
+        webhook_id: Option<String>,
+
+        /// Number of attempts to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Add another OrbitDock server whose sessions this one lists and proxies
+    AddPeer {
+        /// Short name; prefixes the peer's session ids as <name>:<id>
+        name: String,
+
+        /// The peer's base URL (http://host:4000)
+        url: String,
+
+        /// Auth token issued by the peer
+        #[arg(long)]
+        token: Option<String>,
+    },
+
+    /// List peer servers
+    ListPeers,
+
+    /// Remove a peer server by name
+    RemovePeer {
+        /// Peer name
+        name: String,
+    },
+
     /// Run diagnostics and check system health
     Doctor,
 
+    /// Summarize sessions, tokens, estimated cost, and turn times (reads the DB)
+    Stats {
+        /// Number of days to cover
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+
+        /// How many of the busiest projects to list
+        #[arg(long, default_value_t = 5)]
+        top: usize,
+    },
+
+    /// Delete images and attachments of deleted sessions (and, with
+    /// retention.media_max_age_days, of long-ended ones)
+    Gc {
+        /// Only report what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Load-test the session pipeline with synthetic sessions (headless, in a
+    /// throwaway data directory)
+    Simulate {
+        /// Number of synthetic sessions
+        #[arg(long, default_value_t = 20)]
+        sessions: usize,
+
+        /// Connector events per second, per session
+        #[arg(long, default_value_t = 10.0)]
+        rate: f64,
+
+        /// How long to generate load, in seconds
+        #[arg(long, default_value_t = 30)]
+        duration: u64,
+
+        /// Bytes of assistant text per streamed update
+        #[arg(long, default_value_t = 256)]
+        message_bytes: usize,
+
+        /// Keep the simulation's data directory instead of deleting it
+        #[arg(long)]
+        keep_data: bool,
+    },
+
+    /// Print recent server log lines
+    Logs {
+        /// Number of lines to show
+        #[arg(short = 'n', long, default_value_t = 50)]
+        lines: usize,
+
+        /// Keep following the active log file (across rotations)
+        #[arg(long, short = 'f')]
+        tail: bool,
+    },
+
+    /// Gracefully stop the running server (flushes writes, keeps sessions resumable)
+    Stop {
+        /// Seconds to wait for the server to exit
+        #[arg(long, default_value_t = 10)]
+        timeout: u64,
+
+        /// Kill the server if it has not exited after the timeout
+        #[arg(long, conflicts_with = "remote")]
+        force: bool,
+
+        /// Ask the server at --server to shut down over WebSocket (admin token)
+        #[arg(long)]
+        remote: bool,
+    },
+
+    /// Download and install the latest release, then restart the service
+    Upgrade {
+        /// Release channel to follow
+        #[arg(
+            long,
+            value_enum,
+            default_value = "stable",
+            env = "ORBITDOCK_UPDATE_CHANNEL"
+        )]
+        channel: cmd_upgrade::Channel,
+
+        /// Install this version instead of the latest (e.g. 0.4.0)
+        #[arg(long)]
+        version: Option<String>,
+
+        /// Only report whether an update is available
+        #[arg(long)]
+        check: bool,
+
+        /// Reinstall even if already on the target version
+        #[arg(long)]
+        force: bool,
+
+        /// Don't restart the installed service afterwards
+        #[arg(long)]
+        no_restart: bool,
+    },
+
     /// Interactive setup wizard (init + hooks + token + service)
     Setup {
         /// Deploy as local-only server
@@ -250,15 +554,35 @@ enum Command {
     /// Guide secure remote exposure for an existing install
     RemoteSetup,
 
-    /// Expose the server via Cloudflare Tunnel
+    /// Expose the server via Cloudflare Tunnel, Tailscale Funnel, or SSH
     Tunnel {
+        /// How to reach the server from outside
+        #[arg(long, value_enum, default_value = "cloudflare")]
+        via: cmd_tunnel::Provider,
+
         /// Local server port to tunnel
         #[arg(long, default_value = "4000")]
         port: u16,
 
-        /// Named tunnel (requires cloudflared login). Omit for a quick temporary URL.
+        /// Named Cloudflare tunnel (requires cloudflared login). Omit for a quick temporary URL.
         #[arg(long)]
         name: Option<String>,
+
+        /// SSH destination for `--via ssh`, e.g. me@relay.example.com
+        #[arg(long, required_if_eq("via", "ssh"))]
+        ssh: Option<String>,
+
+        /// Port to open on the SSH host (defaults to --port)
+        #[arg(long)]
+        remote_port: Option<u16>,
+
+        /// Public URL to print and report, e.g. when TLS sits in front of the tunnel
+        #[arg(long)]
+        public_url: Option<String>,
+
+        /// Issue a new auth token even if some already exist
+        #[arg(long)]
+        new_token: bool,
     },
 
     /// Generate a connection URL and QR code for pairing clients
@@ -277,6 +601,7 @@ enum Command {
     Health,
 
     /// Manage sessions
+    #[command(visible_alias = "sessions")]
     Session {
         #[command(subcommand)]
         action: orbitdock_cli::cli::SessionAction,
@@ -337,17 +662,99 @@ enum Command {
         action: orbitdock_cli::cli::ShellAction,
     },
 
+    /// Send a prompt to a project's session, creating one if needed
+    Send(orbitdock_cli::cli::SendArgs),
+
+    /// Stream server events for all sessions or one session
+    Watch(orbitdock_cli::cli::WatchArgs),
+
+    /// Interactive terminal dashboard
+    Tui,
+
+    /// List pending approvals across sessions, or decide one
+    Approve(orbitdock_cli::cli::ApproveArgs),
+
     /// Generate shell completions
     Completions {
         /// Shell to generate completions for
         shell: clap_complete::Shell,
     },
+
+    /// Generate man pages (prints orbitdock(1) unless --out-dir is given)
+    Man {
+        /// Write a page for every subcommand into this directory
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Print where the server looks for its config file
+    Path,
+
+    /// Check the config file for unknown keys and invalid values
+    Validate {
+        /// Config file to check (default: the one the server would load)
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+
+    /// Apply config file changes to the running server (requires an admin token)
+    Reload,
+}
+
+#[derive(Subcommand, Debug)]
+enum TokenAction {
+    /// Generate a secure auth token and store its hash in the database
+    Create {
+        /// Attribute sessions and actions made with this token to a user
+        #[arg(long)]
+        user: Option<String>,
+
+        /// Note shown by `token list`
+        #[arg(long)]
+        label: Option<String>,
+
+        /// What the token may do: read, approve, or admin
+        #[arg(long, default_value = "admin")]
+        scope: String,
+
+        /// Expire the token after this long (e.g. 12h, 30d)
+        #[arg(long)]
+        expires_in: Option<String>,
+    },
+
+    /// List issued auth tokens
+    List,
+
+    /// Revoke a token and close connections using it
+    Revoke {
+        /// Token id (the middle segment in odtk_<id>_<secret>)
+        token_id: String,
+    },
+
+    /// Replace a token with a new one that has the same user, label, and scope
+    Rotate {
+        /// Token id to replace
+        token_id: String,
+
+        /// Expire the new token after this long (e.g. 12h, 30d)
+        #[arg(long)]
+        expires_in: Option<String>,
+
+        /// Keep the old token working this long (e.g. 1h) instead of revoking it now
+        #[arg(long)]
+        grace: Option<String>,
+    },
 }
 
+use crate::listen::BindTarget;
 use crate::logging::init_logging;
 use crate::persistence::{
-    cleanup_dangling_in_progress_messages, cleanup_stale_permission_state,
-    create_persistence_channel, load_sessions_for_startup, PersistCommand, PersistenceWriter,
+    cleanup_dangling_in_progress_messages, cleanup_interrupted_shell_jobs,
+    cleanup_stale_permission_state, create_persistence_channel, load_sessions_for_startup,
+    recover_interrupted_turns, PersistCommand, PersistenceWriter,
 };
 use crate::session::SessionHandle;
 use crate::state::SessionRegistry;
@@ -359,11 +766,18 @@ fn main() -> anyhow::Result<()> {
     // This MUST run before the tokio runtime starts (modifies env vars).
     let _arg0_guard = orbitdock_connector_codex::arg0_dispatch();
 
+    // config.toml only fills in env vars that aren't set, so flags and the
+    // real environment still win. Must run before flags are parsed.
+    config_file::load_into_env();
+
     let cli = Cli::parse();
 
     // Initialize data dir from CLI arg / env / default — before anything else
     let data_dir = paths::init_data_dir(cli.data_dir.as_deref());
 
+    // Every connection from here on can read the semantic search vectors.
+    semantic_search::register_extension();
+
     // Dispatch subcommands that don't need the async runtime
     match &cli.command {
         Some(Command::Init { server_url }) => {
@@ -373,26 +787,45 @@ fn main() -> anyhow::Result<()> {
             settings_path,
             server_url,
             auth_token,
+            hook_secret,
         }) => {
             return cmd_install_hooks::run(
                 settings_path.as_deref(),
                 server_url.as_deref(),
                 auth_token.as_deref(),
+                hook_secret.as_deref(),
             );
         }
         Some(Command::HookForward {
             hook_type,
+            payload,
             server_url,
             auth_token,
         }) => {
-            return cmd_hook_forward::run(*hook_type, server_url.as_deref(), auth_token.as_deref());
+            return cmd_hook_forward::run(
+                *hook_type,
+                payload.as_deref(),
+                server_url.as_deref(),
+                auth_token.as_deref(),
+            );
         }
         Some(Command::InstallService {
             bind,
             enable,
             auth_token,
+            tls_cert,
+            tls_key,
         }) => {
-            return cmd_install_service::run(&data_dir, *bind, *enable, auth_token.clone());
+            return cmd_install_service::run_with_opts(
+                &data_dir,
+                cmd_install_service::ServiceOptions {
+                    bind: *bind,
+                    enable: *enable,
+                    tls_cert: tls_cert.clone(),
+                    tls_key: tls_key.clone(),
+                    auth_token: auth_token.clone(),
+                },
+            );
         }
         Some(Command::EnsurePath) => {
             return cmd_ensure_path::run();
@@ -400,8 +833,48 @@ fn main() -> anyhow::Result<()> {
         Some(Command::Status) => {
             return cmd_status::run(&data_dir);
         }
-        Some(Command::GenerateToken) => {
-            return cmd_status::generate_token(&data_dir);
+        Some(Command::GenerateToken {
+            user,
+            label,
+            scope,
+            expires_in,
+        })
+        | Some(Command::Token {
+            action:
+                TokenAction::Create {
+                    user,
+                    label,
+                    scope,
+                    expires_in,
+                },
+        }) => {
+            return cmd_status::generate_token(
+                &data_dir,
+                user.as_deref(),
+                label.as_deref(),
+                scope,
+                expires_in.as_deref(),
+            );
+        }
+        Some(Command::Token {
+            action: TokenAction::List,
+        }) => {
+            return cmd_status::list_tokens();
+        }
+        Some(Command::Token {
+            action: TokenAction::Revoke { token_id },
+        }) => {
+            return cmd_status::revoke_token(token_id);
+        }
+        Some(Command::Token {
+            action:
+                TokenAction::Rotate {
+                    token_id,
+                    expires_in,
+                    grace,
+                },
+        }) => {
+            return cmd_status::rotate_token(token_id, expires_in.as_deref(), grace.as_deref());
         }
         Some(Command::ListTokens) => {
             return cmd_status::list_tokens();
@@ -409,11 +882,134 @@ fn main() -> anyhow::Result<()> {
         Some(Command::RevokeToken { token_id }) => {
             return cmd_status::revoke_token(token_id);
         }
+        Some(Command::AddUser { name }) => {
+            return cmd_users::add(name);
+        }
+        Some(Command::ListUsers) => {
+            return cmd_users::list();
+        }
+        Some(Command::RemoveUser { name }) => {
+            return cmd_users::remove(name);
+        }
+        Some(Command::AddWebhook {
+            url,
+            secret,
+            events,
+        }) => {
+            return cmd_webhooks::add(url, secret.as_deref(), events);
+        }
+        Some(Command::AddSlack {
+            webhook_url,
+            bot_token,
+            channel,
+            events,
+        }) => {
+            return cmd_webhooks::add_slack(
+                webhook_url.as_deref(),
+                bot_token.as_deref(),
+                channel.as_deref(),
+                events,
+            );
+        }
+        Some(Command::AddDiscord { url, events }) => {
+            return cmd_webhooks::add_discord(url, events);
+        }
+        Some(Command::ListWebhooks) => {
+            return cmd_webhooks::list();
+        }
+        Some(Command::RemoveWebhook { webhook_id }) => {
+            return cmd_webhooks::remove(webhook_id);
+        }
+        Some(Command::WebhookDeliveries { webhook_id, limit }) => {
+            return cmd_webhooks::deliveries(webhook_id.as_deref(), *limit);
+        }
+        Some(Command::AddPeer { name, url, token }) => {
+            return cmd_peers::add(name, url, token.as_deref());
+        }
+        Some(Command::ListPeers) => {
+            return cmd_peers::list();
+        }
+        Some(Command::RemovePeer { name }) => {
+            return cmd_peers::remove(name);
+        }
         Some(Command::Doctor) => {
             return cmd_doctor::run(&data_dir);
         }
-        Some(Command::Tunnel { port, name }) => {
-            return cmd_tunnel::run(*port, name.as_deref());
+        Some(Command::Config {
+            action: ConfigAction::Path,
+        }) => {
+            return cmd_config::path();
+        }
+        Some(Command::Config {
+            action: ConfigAction::Validate { file },
+        }) => {
+            return cmd_config::validate(file.as_deref());
+        }
+        Some(Command::Stats { days, top }) => {
+            return cmd_stats::run(*days, *top, cli.json);
+        }
+        Some(Command::Gc { dry_run }) => {
+            return cmd_gc::run(*dry_run, cli.json);
+        }
+        Some(Command::Simulate {
+            sessions,
+            rate,
+            duration,
+            message_bytes,
+            keep_data,
+        }) => {
+            return cmd_simulate::run(cmd_simulate::SimulateOptions {
+                sessions: *sessions,
+                rate: *rate,
+                duration: std::time::Duration::from_secs(*duration),
+                message_bytes: *message_bytes,
+                keep_data: *keep_data,
+                json: cli.json,
+            });
+        }
+        Some(Command::Logs { lines, tail }) => {
+            return cmd_logs::run(*lines, *tail);
+        }
+        Some(Command::Stop {
+            timeout,
+            force,
+            remote: false,
+        }) => {
+            return cmd_stop::run(*timeout, *force);
+        }
+        Some(Command::Upgrade {
+            channel,
+            version,
+            check,
+            force,
+            no_restart,
+        }) => {
+            return cmd_upgrade::run(cmd_upgrade::UpgradeOptions {
+                channel: *channel,
+                version: version.clone(),
+                check: *check,
+                force: *force,
+                restart: !*no_restart,
+            });
+        }
+        Some(Command::Tunnel {
+            via,
+            port,
+            name,
+            ssh,
+            remote_port,
+            public_url,
+            new_token,
+        }) => {
+            return cmd_tunnel::run(cmd_tunnel::TunnelOptions {
+                provider: *via,
+                port: *port,
+                name: name.clone(),
+                ssh: ssh.clone(),
+                remote_port: *remote_port,
+                public_url: public_url.clone(),
+                new_token: *new_token,
+            });
         }
         Some(Command::Pair { tunnel_url, no_qr }) => {
             return cmd_pair::run(tunnel_url.as_deref(), !*no_qr);
@@ -466,18 +1062,37 @@ fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    if let Some(Command::Man { out_dir }) = &cli.command {
+        use clap::CommandFactory;
+        let cmd = Cli::command();
+        match out_dir {
+            Some(dir) => {
+                std::fs::create_dir_all(dir)?;
+                clap_mangen::generate_to(cmd, dir)?;
+                println!("Wrote man pages to {}", dir.display());
+            }
+            None => clap_mangen::Man::new(cmd).render(&mut std::io::stdout())?,
+        }
+        return Ok(());
+    }
+
     // Resolve bind address: subcommand --bind > top-level --bind > default
     let (bind_addr, auth_token, allow_insecure_no_auth, startup_is_primary, tls_cert, tls_key) =
         match cli.command {
             Some(Command::Start {
                 bind,
+                tailscale,
                 auth_token,
                 allow_insecure_no_auth,
                 secondary,
                 tls_cert,
                 tls_key,
             }) => (
-                bind,
+                if tailscale {
+                    bind.on_tailscale()?
+                } else {
+                    bind
+                },
                 auth_token,
                 allow_insecure_no_auth,
                 !secondary,
@@ -486,7 +1101,7 @@ fn main() -> anyhow::Result<()> {
             ),
             _ => (
                 cli.bind
-                    .unwrap_or_else(|| "127.0.0.1:4000".parse().unwrap()),
+                    .unwrap_or_else(|| BindTarget::Tcp("127.0.0.1:4000".parse().unwrap())),
                 None,
                 false,
                 true,
@@ -516,7 +1131,7 @@ fn parse_server_role_value(value: &str) -> Option<bool> {
 }
 
 async fn async_main(
-    bind_addr: SocketAddr,
+    bind_addr: BindTarget,
     auth_token: Option<String>,
     allow_insecure_no_auth: bool,
     startup_is_primary: bool,
@@ -557,6 +1172,8 @@ async fn async_main(
         "Starting OrbitDock Server..."
     );
 
+    redaction::init();
+
     // Run database migrations before anything else
     let db_path = paths::db_path();
     {
@@ -569,20 +1186,12 @@ async fn async_main(
     let active_db_tokens = auth_tokens::active_token_count().unwrap_or(0);
     let has_db_tokens = active_db_tokens > 0;
 
-    if !bind_addr.ip().is_loopback()
-        && auth_token.is_none()
-        && !has_db_tokens
-        && !allow_insecure_no_auth
-    {
+    if !bind_addr.is_local() && auth_token.is_none() && !has_db_tokens && !allow_insecure_no_auth {
         anyhow::bail!(
             "Refusing to bind {bind_addr} without authentication. Create a secure token with `orbitdock generate-token`, pass --auth-token (or ORBITDOCK_AUTH_TOKEN), or explicitly pass --allow-insecure-no-auth for trusted LAN/dev use."
         );
     }
-    if !bind_addr.ip().is_loopback()
-        && auth_token.is_none()
-        && !has_db_tokens
-        && allow_insecure_no_auth
-    {
+    if !bind_addr.is_local() && auth_token.is_none() && !has_db_tokens && allow_insecure_no_auth {
         warn!(
             component = "server",
             event = "server.auth.disabled_non_loopback",
@@ -591,7 +1200,7 @@ async fn async_main(
         );
     }
 
-    if !bind_addr.ip().is_loopback() && (tls_cert.is_none() || tls_key.is_none()) {
+    if !bind_addr.is_local() && (tls_cert.is_none() || tls_key.is_none()) {
         warn!(
             component = "server",
             event = "server.tls.not_configured_non_loopback",
@@ -669,19 +1278,47 @@ async fn async_main(
         is_primary,
     ));
 
+    for (session_id, sharing) in session_access::load_all(state.db_path()) {
+        state.set_session_sharing(&session_id, sharing);
+    }
+    for (session_id, tags) in session_tags::load_all(state.db_path()) {
+        state.set_session_tags(&session_id, tags);
+    }
+    for (session_id, servers) in session_mcp::load_all(state.db_path()) {
+        state.set_session_mcp_servers(&session_id, servers);
+    }
+    for (session_id, issue) in github_issues::load_all(state.db_path()) {
+        state.set_session_issue(&session_id, issue);
+    }
+    for (session_id, ticket) in tickets::load_all(state.db_path()) {
+        state.set_session_ticket(&session_id, Some(ticket));
+    }
+
     // Clean up sessions with stale permission/question state from a prior crash.
     // Must run before load_sessions_for_startup so restored sessions see clean state.
     if let Err(e) = cleanup_stale_permission_state().await {
         warn!(component = "startup", error = %e, "Failed to run stale permission cleanup");
     }
 
+    // Close turns a crash left running so their sessions aren't restored as working.
+    if let Err(e) = recover_interrupted_turns().await {
+        warn!(component = "startup", error = %e, "Failed to recover interrupted turns");
+    }
+
     // Clean up tool messages stuck at is_in_progress from a prior crash.
     if let Err(e) = cleanup_dangling_in_progress_messages().await {
         warn!(component = "startup", error = %e, "Failed to run dangling in-progress message cleanup");
     }
 
+    // Detached shell jobs don't survive a restart; record that instead of leaving them "running".
+    if let Err(e) = cleanup_interrupted_shell_jobs().await {
+        warn!(component = "startup", error = %e, "Failed to mark interrupted shell jobs");
+    }
+
     // Restore sessions from database — all registered as passive (no connectors).
-    // Connectors are created lazily when a client subscribes to a session.
+    // Connectors are created lazily when a client subscribes to a session,
+    // messages are loaded the first time something reads them, and turn
+    // history is restored in the background.
     match load_sessions_for_startup().await {
         Ok(restored) if !restored.is_empty() => {
             info!(
@@ -693,6 +1330,7 @@ async fn async_main(
 
             // Collect sessions needing transcript backfill (0 DB messages but have a transcript)
             let mut backfill_tasks: Vec<(String, String)> = Vec::new();
+            let restored_ids: Vec<String> = restored.iter().map(|rs| rs.id.clone()).collect();
 
             for rs in restored {
                 let crate::persistence::RestoredSession {
@@ -725,10 +1363,16 @@ async fn async_main(
                     pending_question,
                     pending_approval_id,
                     messages,
+                    message_count,
+                    next_message_sequence,
                     forked_from_session_id,
+                    created_by,
                     current_diff,
                     current_plan,
+                    plan,
                     turn_diffs: restored_turn_diffs,
+                    turn_metrics,
+                    estimated_cost_usd,
                     git_branch,
                     git_sha,
                     current_cwd,
@@ -740,11 +1384,10 @@ async fn async_main(
                     terminal_app,
                     approval_version,
                     unread_count,
+                    last_error,
                 } = rs;
-                let msg_count = messages.len();
-
                 // Track Claude sessions with 0 DB messages for transcript backfill
-                if msg_count == 0 && provider == "claude" {
+                if message_count == 0 && provider == "claude" {
                     if let Some(ref tp) = transcript_path {
                         backfill_tasks.push((id.clone(), tp.clone()));
                     }
@@ -791,38 +1434,12 @@ async fn async_main(
                     messages,
                     current_diff,
                     current_plan,
+                    plan,
                     restored_turn_diffs
                         .into_iter()
-                        .map(
-                            |(
-                                turn_id,
-                                diff,
-                                input_tokens,
-                                output_tokens,
-                                cached_tokens,
-                                context_window,
-                                snapshot_kind,
-                            )| {
-                                let has_tokens =
-                                    input_tokens > 0 || output_tokens > 0 || context_window > 0;
-                                TurnDiff {
-                                    turn_id,
-                                    diff,
-                                    token_usage: if has_tokens {
-                                        Some(TokenUsage {
-                                            input_tokens: input_tokens as u64,
-                                            output_tokens: output_tokens as u64,
-                                            cached_tokens: cached_tokens as u64,
-                                            context_window: context_window as u64,
-                                        })
-                                    } else {
-                                        None
-                                    },
-                                    snapshot_kind: Some(snapshot_kind),
-                                }
-                            },
-                        )
+                        .map(persistence::restored_turn_diff)
                         .collect(),
+                    turn_metrics,
                     git_branch,
                     git_sha,
                     current_cwd,
@@ -861,6 +1478,14 @@ async fn async_main(
                 if let Some(source_id) = forked_from_session_id {
                     handle.set_forked_from(source_id);
                 }
+                handle.set_created_by(created_by);
+                handle.set_estimated_cost_usd(estimated_cost_usd);
+                handle.set_last_error(last_error);
+                handle.set_tags(state.session_tags(&id));
+                handle.set_issue(state.session_issue(&id));
+                handle.set_ticket(state.session_ticket(&id));
+                handle.defer_messages(message_count, next_message_sequence);
+                handle.defer_turn_history();
 
                 // Register thread IDs for duplicate detection.
                 // Filter through ProviderSessionId to prevent registering OrbitDock IDs.
@@ -892,11 +1517,17 @@ async fn async_main(
                         Provider::Codex => "codex",
                         Provider::Claude => "claude",
                     },
-                    messages = msg_count,
+                    messages = message_count,
                     "Registered session"
                 );
             }
 
+            // Turn history loads in the background so the server listens right away
+            tokio::spawn(restore_details::hydrate_restored_sessions(
+                state.clone(),
+                restored_ids,
+            ));
+
             // Backfill messages from transcript files for sessions that lost them
             if !backfill_tasks.is_empty() {
                 info!(
@@ -1007,8 +1638,10 @@ async fn async_main(
     let watcher_state = state.clone();
     let watcher_persist = persist_tx.clone();
     tokio::spawn(async move {
-        if let Err(e) = rollout_watcher::start_rollout_watcher(watcher_state, watcher_persist).await
-        {
+        let result =
+            rollout_watcher::start_rollout_watcher(watcher_state.clone(), watcher_persist).await;
+        watcher_state.set_rollout_watcher_running(false);
+        if let Err(e) = result {
             warn!(
                 component = "rollout_watcher",
                 event = "rollout_watcher.stopped_with_error",
@@ -1018,6 +1651,21 @@ async fn async_main(
         }
     });
 
+    // Claude transcript watcher (hook-less Claude CLI sessions -> server state)
+    let claude_watcher_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) =
+            claude_transcript_watcher::start_claude_transcript_watcher(claude_watcher_state).await
+        {
+            warn!(
+                component = "claude_transcript_watcher",
+                event = "claude_transcript_watcher.stopped_with_error",
+                error = %e,
+                "Claude transcript watcher failed"
+            );
+        }
+    });
+
     // Background expiry for pending Claude sessions that never materialize
     let expiry_state = state.clone();
     tokio::spawn(async move {
@@ -1028,6 +1676,27 @@ async fn async_main(
         }
     });
 
+    // Close connections whose token was revoked or expired
+    tokio::spawn(auth::start_token_sweep_loop(state.clone()));
+
+    // Connector process CPU/memory sampling
+    tokio::spawn(resource_monitor::start_resource_monitor_loop(state.clone()));
+    push::init();
+    tokio::spawn(webhooks::start_webhook_dispatcher(state.clone()));
+    tokio::spawn(federation::start_federation(state.clone()));
+    tokio::spawn(rolling_summary::start_summarizer(state.clone()));
+    tokio::spawn(session_tags::start_tagger(state.clone()));
+    tokio::spawn(event_journal::start_journal(state.clone()));
+    tokio::spawn(semantic_search::start_indexer(state.db_path().clone()));
+    tokio::spawn(media_gc::start_gc_loop(state.db_path().clone()));
+    tokio::spawn(message_eviction::start_eviction_loop(state.clone()));
+    tokio::spawn(attention::start_attention_loop(state.clone()));
+    tokio::spawn(provider_info::start_probe());
+
+    // `kill -HUP` re-reads config.toml
+    #[cfg(unix)]
+    tokio::spawn(config_file::start_sighup_loop());
+
     // Periodic git info refresh for subscribed sessions
     let git_state = state.clone();
     tokio::spawn(git_refresh::start_git_refresh_loop(git_state));
@@ -1041,8 +1710,23 @@ async fn async_main(
         .layer(DefaultBodyLimit::max(MAX_HTTP_BODY_BYTES))
         .route("/ws", get(ws_handler))
         .route("/api/hook", post(hook_handler::hook_handler))
+        .route("/api/hook/batch", post(hook_handler::hook_batch_handler))
         .route("/api/sessions", get(http_api::list_sessions))
         .route("/api/sessions/{session_id}", get(http_api::get_session))
+        .route(
+            "/api/images/{session_id}/{image_id}",
+            get(images::image_handler),
+        )
+        .route(
+            "/api/sessions/{session_id}/attachments",
+            get(attachments::list_handler).post(attachments::upload_handler).layer(
+                DefaultBodyLimit::max(attachments::MAX_BYTES + 1),
+            ),
+        )
+        .route(
+            "/api/sessions/{session_id}/attachments/{attachment_id}",
+            get(attachments::download_handler),
+        )
         .route(
             "/api/sessions/{session_id}/conversation",
             get(http_api::get_conversation_bootstrap),
@@ -1051,7 +1735,20 @@ async fn async_main(
             "/api/sessions/{session_id}/messages",
             get(http_api::get_conversation_history),
         )
+        .route(
+            "/api/sessions/{session_id}/messages/{message_id}",
+            get(http_api::get_full_message),
+        )
         .route("/api/approvals", get(http_api::list_approvals_endpoint))
+        .route(
+            "/api/analytics/turn-latency",
+            get(http_api::turn_latency_stats_endpoint),
+        )
+        .route("/api/analytics/usage", get(http_api::usage_analytics_endpoint))
+        .route(
+            "/api/search/semantic",
+            get(http_api::semantic_search_endpoint),
+        )
         .route(
             "/api/approvals/{approval_id}",
             delete(http_api::delete_approval_endpoint),
@@ -1060,7 +1757,12 @@ async fn async_main(
             "/api/server/openai-key",
             get(http_api::check_open_ai_key).post(http_api::set_open_ai_key),
         )
+        .route("/api/peers", get(http_api::list_peers))
+        .route("/api/github/issues", post(http_api::create_issue_session))
+        .route(github_issues::WEBHOOK_PATH, post(http_api::github_webhook))
         .route("/api/server/role", put(http_api::set_server_role))
+        .route("/api/server/config", get(http_api::get_server_config))
+        .route("/api/server/config/{key}", put(http_api::set_server_setting))
         .route("/api/usage/codex", get(http_api::fetch_codex_usage))
         .route("/api/usage/claude", get(http_api::fetch_claude_usage))
         .route("/api/models/codex", get(http_api::list_codex_models))
@@ -1072,6 +1774,17 @@ async fn async_main(
             post(http_api::codex_login_cancel),
         )
         .route("/api/codex/logout", post(http_api::codex_logout))
+        .route("/api/claude/account", get(http_api::read_claude_account))
+        .route(
+            "/api/claude/login/start",
+            post(http_api::claude_login_start),
+        )
+        .route("/api/claude/login/code", post(http_api::claude_login_code))
+        .route(
+            "/api/claude/login/cancel",
+            post(http_api::claude_login_cancel),
+        )
+        .route("/api/claude/logout", post(http_api::claude_logout))
         .route(
             "/api/sessions/{session_id}/mark-read",
             post(http_api::mark_session_read),
@@ -1085,6 +1798,10 @@ async fn async_main(
             "/api/review-comments/{comment_id}",
             patch(http_api::update_review_comment).delete(http_api::delete_review_comment_by_id),
         )
+        .route(
+            "/api/sessions/{session_id}/subagents",
+            get(http_api::list_subagents_endpoint),
+        )
         .route(
             "/api/sessions/{session_id}/subagents/{subagent_id}/tools",
             get(http_api::list_subagent_tools_endpoint),
@@ -1135,7 +1852,13 @@ async fn async_main(
         )
         .route(
             "/api/sessions/{session_id}/mcp/servers",
-            post(http_api::mcp_set_servers),
+            get(http_api::list_session_mcp_servers)
+                .put(http_api::set_session_mcp_server)
+                .post(http_api::mcp_set_servers),
+        )
+        .route(
+            "/api/sessions/{session_id}/mcp/servers/{name}",
+            delete(http_api::remove_session_mcp_server),
         )
         .route(
             "/api/sessions/{session_id}/flags",
@@ -1155,9 +1878,27 @@ async fn async_main(
             "/api/fs/recent-projects",
             get(http_api::list_recent_projects),
         )
-        .route("/health", get(health_handler))
+        .route("/dashboard", get(dashboard::index))
+        .route("/dashboard/app.js", get(dashboard::app_js))
+        .route("/dashboard/app.css", get(dashboard::app_css))
+        .route(
+            "/dashboard/sign-in",
+            get(dashboard::sign_in_page).post(dashboard::sign_in),
+        )
+        .route("/dashboard/sign-out", get(dashboard::sign_out))
+        .route("/health", get(health::health_handler))
         .route("/metrics", get(metrics::metrics_handler));
 
+    app = app.layer(axum::middleware::from_fn_with_state(
+        state.clone(),
+        session_access::http_middleware,
+    ));
+    // Outside the session check so refused requests are recorded too.
+    app = app.layer(axum::middleware::from_fn_with_state(
+        state.clone(),
+        audit::http_middleware,
+    ));
+
     let auth_state = auth::AuthState {
         static_token: auth_token.clone(),
     };
@@ -1167,7 +1908,7 @@ async fn async_main(
     ));
 
     let mut app = app.layer(TraceLayer::new_for_http());
-    if let Some(cors_layer) = configured_cors_layer()? {
+    if let Some(cors_layer) = cors::configured_layer(auth_token.is_some() || has_db_tokens)? {
         app = app.layer(cors_layer);
     }
     let app = app.with_state(state);
@@ -1175,12 +1916,41 @@ async fn async_main(
     // Write PID file after successful bind
     let use_tls = tls_cert.is_some() && tls_key.is_some();
 
-    if use_tls {
+    if let BindTarget::Unix(socket_path) = &bind_addr {
+        if use_tls {
+            anyhow::bail!("TLS isn't supported on a unix socket bind; drop --tls-cert/--tls-key");
+        }
+        let listener = listen::bind_unix(socket_path)?;
+
+        info!(
+            component = "server",
+            event = "server.listening",
+            bind_address = %bind_addr,
+            tls = false,
+            "Listening on unix socket"
+        );
+
+        write_pid_file();
+        listen::write_listen_file(&bind_addr.local_url(false));
+
+        let served = axum::serve(listener, app.into_make_service())
+            .with_graceful_shutdown(shutdown_signal(shutdown_state, shutdown_persist))
+            .await;
+        let _ = std::fs::remove_file(socket_path);
+        served?;
+    } else if use_tls {
+        let BindTarget::Tcp(tcp_addr) = bind_addr else {
+            unreachable!("unix binds are handled above");
+        };
         let cert_path = tls_cert.unwrap();
         let key_path = tls_key.unwrap();
 
-        let tls_config =
-            axum_server::tls_rustls::RustlsConfig::from_pem_file(&cert_path, &key_path).await?;
+        let tls_config = tls::load(&cert_path, &key_path).await?;
+        tokio::spawn(tls::start_reload_loop(
+            tls_config.clone(),
+            cert_path.clone(),
+            key_path,
+        ));
 
         info!(
             component = "server",
@@ -1192,6 +1962,7 @@ async fn async_main(
         );
 
         write_pid_file();
+        listen::write_listen_file(&bind_addr.local_url(true));
 
         // Wire graceful shutdown via axum_server::Handle
         let handle = axum_server::Handle::new();
@@ -1201,12 +1972,15 @@ async fn async_main(
             shutdown_handle.graceful_shutdown(Some(std::time::Duration::from_secs(5)));
         });
 
-        axum_server::bind_rustls(bind_addr, tls_config)
+        axum_server::bind_rustls(tcp_addr, tls_config)
             .handle(handle)
-            .serve(app.into_make_service())
+            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
             .await?;
     } else {
-        let listener = tokio::net::TcpListener::bind(bind_addr).await?;
+        let BindTarget::Tcp(tcp_addr) = bind_addr else {
+            unreachable!("unix binds are handled above");
+        };
+        let listener = tokio::net::TcpListener::bind(tcp_addr).await?;
 
         info!(
             component = "server",
@@ -1217,10 +1991,14 @@ async fn async_main(
         );
 
         write_pid_file();
+        listen::write_listen_file(&bind_addr.local_url(false));
 
-        axum::serve(listener, app)
-            .with_graceful_shutdown(shutdown_signal(shutdown_state, shutdown_persist))
-            .await?;
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown_signal(shutdown_state, shutdown_persist))
+        .await?;
     }
 
     Ok(())
@@ -1232,50 +2010,6 @@ fn normalize_auth_token(auth_token: Option<String>) -> Option<String> {
         .filter(|token| !token.is_empty())
 }
 
-fn configured_cors_layer() -> anyhow::Result<Option<CorsLayer>> {
-    let raw = match std::env::var("ORBITDOCK_CORS_ALLOWED_ORIGINS") {
-        Ok(value) => value,
-        Err(_) => return Ok(None),
-    };
-
-    let mut origins = Vec::new();
-    for origin in raw.split(',') {
-        let trimmed = origin.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        origins.push(
-            HeaderValue::from_str(trimmed)
-                .map_err(|e| anyhow::anyhow!("invalid CORS origin '{trimmed}': {e}"))?,
-        );
-    }
-
-    if origins.is_empty() {
-        return Ok(None);
-    }
-
-    info!(
-        component = "server",
-        event = "cors.enabled",
-        allowed_origins = origins.len(),
-        "Enabled CORS for configured origins"
-    );
-
-    Ok(Some(
-        CorsLayer::new()
-            .allow_origin(origins)
-            .allow_methods([
-                Method::GET,
-                Method::POST,
-                Method::PUT,
-                Method::PATCH,
-                Method::DELETE,
-                Method::OPTIONS,
-            ])
-            .allow_headers([AUTHORIZATION, CONTENT_TYPE]),
-    ))
-}
-
 /// Write PID file to data_dir/orbitdock.pid
 fn write_pid_file() {
     let pid_path = paths::pid_file_path();
@@ -1294,28 +2028,65 @@ fn write_pid_file() {
 fn remove_pid_file() {
     let pid_path = paths::pid_file_path();
     let _ = std::fs::remove_file(&pid_path);
+    listen::remove_listen_file();
 }
 
-/// Wait for shutdown signal. Active direct sessions stay active in DB so they
-/// auto-resume via lazy connector when a client subscribes after restart.
-async fn shutdown_signal(_state: Arc<SessionRegistry>, _persist_tx: mpsc::Sender<PersistCommand>) {
-    let _ = tokio::signal::ctrl_c().await;
+/// Wait for SIGINT, SIGTERM, or an admin `ShutdownServer`, then flush pending
+/// writes. Active direct sessions stay active in DB so they auto-resume via
+/// lazy connector when a client subscribes after restart.
+async fn shutdown_signal(state: Arc<SessionRegistry>, persist_tx: mpsc::Sender<PersistCommand>) {
+    let reason = wait_for_shutdown(&state).await;
     info!(
         component = "server",
         event = "server.shutdown",
+        reason,
         "Shutdown signal received — active direct sessions preserved for lazy resume"
     );
 
+    state.broadcast_to_list(orbitdock_protocol::ServerMessage::ServerShuttingDown);
+
+    let done = Arc::new(tokio::sync::Notify::new());
+    let flushed = async {
+        persist_tx
+            .send(PersistCommand::Flush { done: done.clone() })
+            .await
+            .is_ok()
+            && tokio::time::timeout(std::time::Duration::from_secs(5), done.notified())
+                .await
+                .is_ok()
+    };
+    if !flushed.await {
+        warn!(
+            component = "server",
+            event = "server.shutdown.flush_incomplete",
+            "Persistence did not confirm its final flush before shutdown"
+        );
+    }
+
+    state.disconnect_all_connections();
+
     // Clean up PID file
     remove_pid_file();
 }
 
-async fn health_handler() -> impl IntoResponse {
-    serde_json::json!({
-        "status": "ok",
-        "version": VERSION,
-    })
-    .to_string()
+async fn wait_for_shutdown(state: &SessionRegistry) -> &'static str {
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => "sigint",
+        _ = terminate => "sigterm",
+        _ = state.shutdown_requested() => "admin_request",
+    }
 }
 
 /// Map merged Command variants to CLI crate's Command type.
@@ -1354,7 +2125,19 @@ fn translate_to_cli_command(cli: &Cli) -> Option<orbitdock_cli::cli::Command> {
         Command::Shell { action } => Some(CliCmd::Shell {
             action: action.clone(),
         }),
-        // Server-admin commands and Completions are handled elsewhere
+        Command::Send(args) => Some(CliCmd::Send(args.clone())),
+        Command::Watch(args) => Some(CliCmd::Watch(args.clone())),
+        Command::Tui => Some(CliCmd::Tui),
+        Command::Approve(args) => Some(CliCmd::Approve(args.clone())),
+        Command::Stop { remote: true, .. } => Some(CliCmd::Server {
+            action: orbitdock_cli::cli::ServerAction::Shutdown,
+        }),
+        Command::Config {
+            action: ConfigAction::Reload,
+        } => Some(CliCmd::Server {
+            action: orbitdock_cli::cli::ServerAction::ReloadConfig,
+        }),
+        // Server-admin commands, Completions, and Man are handled elsewhere
         _ => None,
     }
 }
@@ -1392,29 +2175,31 @@ fn binary_metadata(path: &str) -> (u64, i64) {
 
 /// Drain spooled hook events written by `hook-forward` while the server was offline.
 ///
-/// Reads all `.json` files from the spool directory, processes them in
-/// timestamp order (filenames are `<epoch>-<pid>.json`), and deletes each
-/// file after successful processing. Parse failures are warned and skipped.
+/// Prunes the spool to its limits first (see `spool`), then processes the
+/// remaining `.json` files in timestamp order (filenames are
+/// `<epoch>-<pid>.json`) and deletes each file after successful processing.
+/// Parse failures are moved to the dead-letter folder.
 async fn drain_spool(state: &Arc<SessionRegistry>) {
     let spool_dir = paths::spool_dir();
-    let entries = match std::fs::read_dir(&spool_dir) {
-        Ok(e) => e,
-        Err(_) => return, // No spool dir — nothing to drain
-    };
+    let pruned = spool::prune(&spool_dir, spool::now_ms());
+    if pruned.dropped() > 0 {
+        warn!(
+            component = "spool",
+            event = "spool.pruned",
+            expired = pruned.expired,
+            evicted = pruned.evicted,
+            "Dropped spooled hook events over the spool limits"
+        );
+    }
 
-    let mut files: Vec<PathBuf> = entries
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+    let files: Vec<PathBuf> = spool::queued(&spool_dir)
+        .into_iter()
+        .map(|entry| entry.path)
         .collect();
-
     if files.is_empty() {
         return;
     }
 
-    // Sort by filename to preserve event order (timestamp prefix)
-    files.sort();
-
     let total = files.len();
     let mut drained = 0u64;
     let mut failed = 0u64;
@@ -1443,8 +2228,9 @@ async fn drain_spool(state: &Arc<SessionRegistry>) {
                     event = "spool.parse_error",
                     path = %path.display(),
                     error = %e,
-                    "Failed to parse spool file, skipping"
+                    "Failed to parse spool file, moving to dead-letter"
                 );
+                let _ = spool::dead_letter(path);
                 failed += 1;
                 continue;
             }
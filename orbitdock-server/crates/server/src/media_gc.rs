@@ -0,0 +1,257 @@
+//! Garbage collection for extracted images and uploaded attachments.
+//!
+//! Both live in per-session directories (`images/<session>/`,
+//! `attachments/<session>/`) that nothing else ever removes. A collection pass
+//! deletes a session's directories when the session is gone from the database,
+//! or, with `[retention] media_max_age_days`, when it ended and nothing in them
+//! has changed for that many days. Directories touched within the last hour are
+//! left alone so media written just before its session row is persisted
+//! survives.
+//!
+//! The server runs a pass shortly after startup and then every six hours;
+//! `orbitdock gc` runs one on demand.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use rusqlite::Connection;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{config_file, paths};
+
+const FIRST_PASS_DELAY: Duration = Duration::from_secs(5 * 60);
+const PASS_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const GRACE: Duration = Duration::from_secs(60 * 60);
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct GcReport {
+    /// Session directories removed (images and attachments counted separately).
+    pub directories: usize,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Why a session's media is collected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Keep,
+    /// The session no longer exists.
+    Orphaned,
+    /// The session ended longer ago than the retention window.
+    Expired,
+}
+
+/// `media_max_age_days` from the config, if set to a positive number of days.
+pub fn max_age() -> Option<Duration> {
+    config_file::var("ORBITDOCK_MEDIA_MAX_AGE_DAYS")?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|days| *days > 0)
+        .map(|days| Duration::from_secs(days.saturating_mul(DAY.as_secs())))
+}
+
+/// Run one pass. With `dry_run`, only report what would be removed.
+pub fn collect(db_path: &Path, dry_run: bool) -> anyhow::Result<GcReport> {
+    let conn = Connection::open(db_path)
+        .with_context(|| format!("open database at {}", db_path.display()))?;
+    let sessions = session_statuses(&conn)?;
+    let max_age = max_age();
+    let now = SystemTime::now();
+
+    let mut report = GcReport::default();
+    for root in [paths::images_dir(), paths::attachments_dir()] {
+        let Ok(entries) = std::fs::read_dir(&root) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let dir = entry.path();
+            if !dir.is_dir() {
+                continue;
+            }
+            let Some(name) = dir.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let usage = dir_usage(&dir);
+            let verdict = judge(sessions.get(name).copied(), usage.newest, now, max_age);
+            if verdict == Verdict::Keep {
+                continue;
+            }
+            if !dry_run {
+                if let Err(e) = std::fs::remove_dir_all(&dir) {
+                    warn!(
+                        component = "media_gc",
+                        event = "media_gc.remove_failed",
+                        path = %dir.display(),
+                        error = %e,
+                        "Failed to remove session media"
+                    );
+                    continue;
+                }
+            }
+            report.directories += 1;
+            report.files += usage.files;
+            report.bytes += usage.bytes;
+        }
+    }
+    Ok(report)
+}
+
+/// Session ids as they appear in media directory names, mapped to whether the
+/// session has ended.
+fn session_statuses(conn: &Connection) -> anyhow::Result<HashMap<String, bool>> {
+    let mut stmt = conn.prepare("SELECT id, status FROM sessions")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let mut sessions = HashMap::new();
+    for row in rows {
+        let (id, status) = row?;
+        sessions.insert(dir_name(&id), status == "ended");
+    }
+    Ok(sessions)
+}
+
+/// The directory name `images` and `attachments` use for a session id.
+fn dir_name(session_id: &str) -> String {
+    session_id
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn judge(
+    session_ended: Option<bool>,
+    newest: Option<SystemTime>,
+    now: SystemTime,
+    max_age: Option<Duration>,
+) -> Verdict {
+    let idle = newest
+        .and_then(|newest| now.duration_since(newest).ok())
+        .unwrap_or(Duration::MAX);
+    if idle < GRACE {
+        return Verdict::Keep;
+    }
+    match session_ended {
+        None => Verdict::Orphaned,
+        Some(true) if max_age.is_some_and(|max_age| idle > max_age) => Verdict::Expired,
+        Some(_) => Verdict::Keep,
+    }
+}
+
+#[derive(Debug, Default)]
+struct DirUsage {
+    files: u64,
+    bytes: u64,
+    /// Most recent modification of the directory or anything in it.
+    newest: Option<SystemTime>,
+}
+
+fn dir_usage(dir: &Path) -> DirUsage {
+    let mut usage = DirUsage::default();
+    let mut pending: Vec<PathBuf> = vec![dir.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if let Ok(modified) = metadata.modified() {
+            usage.newest = usage.newest.max(Some(modified));
+        }
+        if metadata.is_dir() {
+            if let Ok(entries) = std::fs::read_dir(&path) {
+                pending.extend(
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .map(|entry| entry.path()),
+                );
+            }
+        } else {
+            usage.files += 1;
+            usage.bytes += metadata.len();
+        }
+    }
+    usage
+}
+
+/// Collect on a schedule for the life of the server.
+pub async fn start_gc_loop(db_path: PathBuf) {
+    tokio::time::sleep(FIRST_PASS_DELAY).await;
+    let mut interval = tokio::time::interval(PASS_INTERVAL);
+    loop {
+        interval.tick().await;
+        let path = db_path.clone();
+        match tokio::task::spawn_blocking(move || collect(&path, false)).await {
+            Ok(Ok(report)) if report.directories > 0 => info!(
+                component = "media_gc",
+                event = "media_gc.collected",
+                directories = report.directories,
+                files = report.files,
+                reclaimed_bytes = report.bytes,
+                "Removed media for deleted or expired sessions"
+            ),
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => warn!(
+                component = "media_gc",
+                event = "media_gc.failed",
+                error = %e,
+                "Media garbage collection failed"
+            ),
+            Err(e) => warn!(
+                component = "media_gc",
+                event = "media_gc.failed",
+                error = %e,
+                "Media garbage collection task panicked"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    #[test]
+    fn orphaned_media_is_collected_after_the_grace_period() {
+        let now = SystemTime::UNIX_EPOCH + DAY * 100;
+        assert_eq!(
+            judge(None, Some(now - HOUR * 2), now, None),
+            Verdict::Orphaned
+        );
+        assert_eq!(judge(None, Some(now - HOUR / 2), now, None), Verdict::Keep);
+        assert_eq!(judge(None, None, now, None), Verdict::Orphaned);
+    }
+
+    #[test]
+    fn ended_sessions_expire_only_with_a_retention_window() {
+        let now = SystemTime::UNIX_EPOCH + DAY * 100;
+        let old = Some(now - DAY * 40);
+        assert_eq!(judge(Some(true), old, now, None), Verdict::Keep);
+        assert_eq!(
+            judge(Some(true), old, now, Some(DAY * 30)),
+            Verdict::Expired
+        );
+        assert_eq!(
+            judge(Some(true), Some(now - DAY * 10), now, Some(DAY * 30)),
+            Verdict::Keep
+        );
+        assert_eq!(judge(Some(false), old, now, Some(DAY * 30)), Verdict::Keep);
+    }
+
+    #[test]
+    fn dir_names_match_how_media_is_stored() {
+        assert_eq!(dir_name("od-1234_ab"), "od-1234_ab");
+        assert_eq!(dir_name("claude:abc/def"), "claude_abc_def");
+    }
+}
@@ -0,0 +1,97 @@
+//! Unloads the messages of idle sessions.
+//!
+//! Restored sessions start with their messages left in the database and load
+//! them the first time something reads them (a subscribe, a conversation page,
+//! a connector taking the session over). Every minute this keeps the most
+//! recently read sessions loaded and asks the rest to drop their messages once
+//! they have gone unread for a while. A session with subscribers or a live
+//! connector keeps its messages, so memory follows what is in use rather than
+//! the whole history.
+
+use std::cmp::Reverse;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+use crate::session_command::SessionCommand;
+use crate::state::SessionRegistry;
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+/// Idle sessions kept loaded regardless of age, most recently read first.
+const KEEP_LOADED: usize = 16;
+/// How long a session beyond `KEEP_LOADED` stays loaded after its last read.
+const IDLE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+pub async fn start_eviction_loop(state: Arc<SessionRegistry>) {
+    let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        unload_idle_sessions(&state).await;
+    }
+}
+
+async fn unload_idle_sessions(state: &SessionRegistry) {
+    let candidates: Vec<(String, Instant)> = state
+        .iter_sessions()
+        .filter_map(|entry| {
+            let snap = entry.value().snapshot();
+            let has_connector =
+                state.has_codex_connector(&snap.id) || state.has_claude_connector(&snap.id);
+            (snap.messages_loaded && !has_connector)
+                .then(|| (snap.id.clone(), snap.messages_read_at))
+        })
+        .collect();
+
+    for session_id in sessions_to_unload(candidates, Instant::now()) {
+        if let Some(actor) = state.get_session(&session_id) {
+            debug!(
+                component = "message_eviction",
+                event = "message_eviction.unload",
+                session_id = %session_id,
+                "Unloading idle session messages"
+            );
+            // The actor keeps them if a client subscribed in the meantime.
+            actor.send(SessionCommand::UnloadMessages).await;
+        }
+    }
+}
+
+/// Loaded sessions past the `KEEP_LOADED` most recently read that have been
+/// idle for `IDLE_AFTER`.
+fn sessions_to_unload(mut loaded: Vec<(String, Instant)>, now: Instant) -> Vec<String> {
+    loaded.sort_by_key(|(_, read_at)| Reverse(*read_at));
+    loaded
+        .into_iter()
+        .skip(KEEP_LOADED)
+        .filter(|(_, read_at)| now.saturating_duration_since(*read_at) >= IDLE_AFTER)
+        .map(|(session_id, _)| session_id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_recent_sessions_and_unloads_idle_ones_past_the_limit() {
+        let now = Instant::now() + IDLE_AFTER * 4;
+        let mut loaded: Vec<(String, Instant)> = (0..KEEP_LOADED)
+            .map(|n| (format!("recent-{n}"), now - Duration::from_secs(n as u64)))
+            .collect();
+        loaded.push(("idle".to_string(), now - IDLE_AFTER * 2));
+        loaded.push(("just-read".to_string(), now - Duration::from_secs(60)));
+        loaded.push(("oldest".to_string(), now - IDLE_AFTER * 3));
+
+        // "just-read" falls outside the most recent few but isn't idle yet.
+        let unloaded = sessions_to_unload(loaded, now);
+        assert_eq!(unloaded, vec!["idle".to_string(), "oldest".to_string()]);
+    }
+
+    #[test]
+    fn nothing_is_unloaded_within_the_limit() {
+        let now = Instant::now() + IDLE_AFTER * 2;
+        let loaded = vec![("a".to_string(), now - IDLE_AFTER * 2)];
+        assert!(sessions_to_unload(loaded, now).is_empty());
+    }
+}
@@ -10,6 +10,7 @@ use axum::http::header;
 use axum::response::IntoResponse;
 
 use crate::paths;
+use crate::spool;
 use crate::state::SessionRegistry;
 
 pub async fn metrics_handler(State(state): State<Arc<SessionRegistry>>) -> impl IntoResponse {
@@ -136,7 +137,7 @@ fn render_metrics(state: &SessionRegistry) -> String {
     }
 
     // WAL size
-    let wal_path = db_path.with_extension("db-wal");
+    let wal_path = crate::wal_checkpoint::wal_path(&db_path);
     let wal_size = std::fs::metadata(&wal_path).map(|m| m.len()).unwrap_or(0);
     gauge(
         &mut out,
@@ -145,13 +146,82 @@ fn render_metrics(state: &SessionRegistry) -> String {
         wal_size as f64,
     );
 
-    // Spool queue depth
-    let spool_depth = spool_queue_depth();
+    // WAL checkpoints
+    let checkpoints = crate::wal_checkpoint::stats();
+    let _ = writeln!(
+        out,
+        "# HELP orbitdock_db_wal_checkpoints_total WAL checkpoints run by the persistence writer"
+    );
+    let _ = writeln!(out, "# TYPE orbitdock_db_wal_checkpoints_total counter");
+    for (result, count) in [
+        ("complete", checkpoints.complete),
+        ("busy", checkpoints.busy),
+        ("failed", checkpoints.failed),
+    ] {
+        let _ = writeln!(
+            out,
+            "orbitdock_db_wal_checkpoints_total{{result=\"{}\"}} {}",
+            result, count
+        );
+    }
+    gauge(
+        &mut out,
+        "orbitdock_db_wal_checkpoint_lag_frames",
+        "WAL frames the last checkpoint could not copy back",
+        checkpoints.lag_frames as f64,
+    );
+    if let Some(since) = checkpoints.since_complete {
+        gauge(
+            &mut out,
+            "orbitdock_db_wal_checkpoint_age_seconds",
+            "Time since a checkpoint last emptied the WAL",
+            since.as_secs_f64(),
+        );
+    }
+
+    // Rate limiting
+    let (hook_rejected, ws_rejected) = crate::rate_limit::rejected_counts();
+    let _ = writeln!(
+        out,
+        "# HELP orbitdock_rate_limited_total Requests dropped by rate limits"
+    );
+    let _ = writeln!(out, "# TYPE orbitdock_rate_limited_total counter");
+    let _ = writeln!(
+        out,
+        "orbitdock_rate_limited_total{{source=\"hook\"}} {}",
+        hook_rejected
+    );
+    let _ = writeln!(
+        out,
+        "orbitdock_rate_limited_total{{source=\"websocket\"}} {}",
+        ws_rejected
+    );
+
+    // Spool queue
+    let spool = spool::stats();
     gauge(
         &mut out,
         "orbitdock_spool_queue_depth",
         "Hook events queued in spool directory",
-        spool_depth as f64,
+        spool.depth as f64,
+    );
+    gauge(
+        &mut out,
+        "orbitdock_spool_queue_bytes",
+        "Size of hook events queued in spool directory",
+        spool.bytes as f64,
+    );
+    gauge(
+        &mut out,
+        "orbitdock_spool_oldest_age_seconds",
+        "Age of the oldest queued hook event",
+        spool.oldest_age_secs.unwrap_or(0) as f64,
+    );
+    gauge(
+        &mut out,
+        "orbitdock_spool_dead_letter_depth",
+        "Spooled hook events set aside as unparseable or rejected",
+        spool.dead_letters as f64,
     );
 
     out
@@ -162,21 +232,3 @@ fn gauge(out: &mut String, name: &str, help: &str, value: f64) {
     let _ = writeln!(out, "# TYPE {} gauge", name);
     let _ = writeln!(out, "{} {}", name, value);
 }
-
-fn spool_queue_depth() -> u64 {
-    let spool_dir = paths::spool_dir();
-    std::fs::read_dir(spool_dir)
-        .map(|entries| {
-            entries
-                .filter_map(|e| e.ok())
-                .filter(|e| {
-                    e.path()
-                        .extension()
-                        .and_then(|ext| ext.to_str())
-                        .map(|ext| ext == "json")
-                        .unwrap_or(false)
-                })
-                .count() as u64
-        })
-        .unwrap_or(0)
-}
@@ -188,7 +188,7 @@ mod tests {
                 row.get(0)
             })
             .expect("count refinery history rows");
-        assert_eq!(migration_count, 18);
+        assert_eq!(migration_count, 48);
 
         let sessions_table_exists: i64 = conn
             .query_row(
@@ -273,7 +273,7 @@ mod tests {
                 row.get(0)
             })
             .expect("count refinery history rows");
-        assert_eq!(migration_count, 18);
+        assert_eq!(migration_count, 48);
 
         let imported_name: String = conn
             .query_row(
@@ -301,6 +301,6 @@ mod tests {
                 row.get(0)
             })
             .expect("count refinery history rows");
-        assert_eq!(migration_count, 18);
+        assert_eq!(migration_count, 48);
     }
 }
@@ -53,6 +53,11 @@ pub fn spool_dir() -> PathBuf {
     data_dir().join("spool")
 }
 
+/// Spooled hook events that will never be replayed (unparseable or rejected).
+pub fn spool_dead_letter_dir() -> PathBuf {
+    spool_dir().join("dead-letter")
+}
+
 pub fn rollout_state_path() -> PathBuf {
     data_dir().join("codex-rollout-state.json")
 }
@@ -61,18 +66,48 @@ pub fn hook_transport_config_path() -> PathBuf {
     data_dir().join("hook-forward.json")
 }
 
+/// Shared secret for signing hook requests, written by `install-hooks`.
+pub fn hook_secret_path() -> PathBuf {
+    data_dir().join("hook-secret")
+}
+
 pub fn pid_file_path() -> PathBuf {
     data_dir().join("orbitdock.pid")
 }
 
+/// Where the running server listens, for `orbitdock status`.
+pub fn listen_file_path() -> PathBuf {
+    data_dir().join("orbitdock.listen")
+}
+
+/// Browser origins allowed to call the API, one per line.
+pub fn cors_origins_path() -> PathBuf {
+    data_dir().join("cors-origins.txt")
+}
+
+/// Extra secret-redaction regexes, one per line.
+pub fn redaction_patterns_path() -> PathBuf {
+    data_dir().join("redact-patterns.txt")
+}
+
 pub fn images_dir() -> PathBuf {
     data_dir().join("images")
 }
 
+/// Files uploaded to attach to messages.
+pub fn attachments_dir() -> PathBuf {
+    data_dir().join("attachments")
+}
+
 pub fn encryption_key_path() -> PathBuf {
     data_dir().join("encryption.key")
 }
 
+/// The running `orbitdock tunnel`, for `/health` and `orbitdock status`.
+pub fn tunnel_status_path() -> PathBuf {
+    data_dir().join("tunnel.json")
+}
+
 pub fn cloudflared_binary_path() -> PathBuf {
     data_dir().join("bin/cloudflared")
 }
@@ -84,14 +119,17 @@ pub fn ensure_dirs() -> io::Result<()> {
     let logs = base.join("logs");
     let spool = base.join("spool");
     let images = base.join("images");
+    let attachments = base.join("attachments");
     std::fs::create_dir_all(&logs)?;
     std::fs::create_dir_all(&spool)?;
     std::fs::create_dir_all(&images)?;
+    std::fs::create_dir_all(&attachments)?;
 
     secure_dir_permissions(&base)?;
     secure_dir_permissions(&logs)?;
     secure_dir_permissions(&spool)?;
     secure_dir_permissions(&images)?;
+    secure_dir_permissions(&attachments)?;
 
     Ok(())
 }
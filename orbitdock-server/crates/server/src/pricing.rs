@@ -0,0 +1,192 @@
+//! Estimated spend from token usage.
+//!
+//! Rates are USD per million tokens. `[pricing]` in the config file adds or
+//! overrides entries by model-name substring and is re-read on every lookup,
+//! so edits apply on reload. Costs are estimates at list prices, not bills.
+//!
+//! Providers report usage with different meanings (see
+//! [`TokenUsageSnapshotKind`]), so [`TokenUsage::delta`] turns each new
+//! snapshot into the tokens it adds on top of the previous one, and
+//! [`usage_cost_delta`] into the spend.
+
+use orbitdock_protocol::{TokenUsage, TokenUsageSnapshotKind};
+use serde::Deserialize;
+
+use crate::config_file;
+
+/// USD per million tokens: (model substring, input, output, cached input).
+/// Checked in order, so more specific names come first.
+const LIST_PRICES: &[(&str, f64, f64, f64)] = &[
+    ("opus", 15.0, 75.0, 1.5),
+    ("sonnet", 3.0, 15.0, 0.3),
+    ("haiku", 0.8, 4.0, 0.08),
+    ("gpt-5-mini", 0.25, 2.0, 0.025),
+    ("gpt-5", 1.25, 10.0, 0.125),
+    ("o4-mini", 1.1, 4.4, 0.275),
+    ("o3", 2.0, 8.0, 0.5),
+];
+
+/// One `[pricing."<model substring>"]` entry.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    /// Defaults to the input rate.
+    pub cached: Option<f64>,
+}
+
+impl ModelPrice {
+    fn cached_rate(&self) -> f64 {
+        self.cached.unwrap_or(self.input)
+    }
+}
+
+/// Rates for `model`: the longest matching config entry, then the built-in list.
+pub fn price_for(model: &str) -> Option<ModelPrice> {
+    let model = model.to_ascii_lowercase();
+    let config = config_file::current();
+    let configured = config
+        .pricing
+        .iter()
+        .filter(|(name, _)| model.contains(&name.to_ascii_lowercase()))
+        .max_by_key(|(name, _)| name.len())
+        .map(|(_, price)| *price);
+    configured.or_else(|| {
+        LIST_PRICES
+            .iter()
+            .find(|(name, ..)| model.contains(name))
+            .map(|&(_, input, output, cached)| ModelPrice {
+                input,
+                output,
+                cached: Some(cached),
+            })
+    })
+}
+
+/// Estimated USD cost, or `None` for a model with no known price.
+pub fn estimate_cost(
+    provider: &str,
+    model: &str,
+    input: u64,
+    output: u64,
+    cached: u64,
+) -> Option<f64> {
+    let price = price_for(model)?;
+    Some(cost_at(&price, provider, input, output, cached))
+}
+
+fn cost_at(price: &ModelPrice, provider: &str, input: u64, output: u64, cached: u64) -> f64 {
+    // Codex reports cached tokens as a subset of input; Claude reports them separately.
+    let uncached = if provider == "codex" {
+        input.saturating_sub(cached)
+    } else {
+        input
+    };
+    let millions = |tokens: u64| tokens as f64 / 1_000_000.0;
+    millions(uncached) * price.input
+        + millions(output) * price.output
+        + millions(cached) * price.cached_rate()
+}
+
+/// Spend of the tokens [`TokenUsage::delta`] finds in `next`, or `None` for a
+/// model with no known price.
+pub fn usage_cost_delta(
+    provider: &str,
+    model: &str,
+    previous: (&TokenUsage, TokenUsageSnapshotKind),
+    next: (&TokenUsage, TokenUsageSnapshotKind),
+) -> Option<f64> {
+    let price = price_for(model)?;
+    let delta = TokenUsage::delta(previous, next);
+    Some(cost_at(
+        &price,
+        provider,
+        delta.input_tokens,
+        delta.output_tokens,
+        delta.cached_tokens,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input: u64, output: u64, cached: u64) -> TokenUsage {
+        TokenUsage {
+            input_tokens: input,
+            output_tokens: output,
+            cached_tokens: cached,
+            context_window: 0,
+        }
+    }
+
+    #[test]
+    fn codex_cached_tokens_are_not_billed_twice() {
+        let cost = estimate_cost("codex", "gpt-5", 1_000_000, 0, 1_000_000).unwrap();
+        assert!((cost - 0.125).abs() < 1e-9);
+        let cost = estimate_cost("claude", "claude-opus-4-1", 0, 0, 1_000_000).unwrap();
+        assert!((cost - 1.5).abs() < 1e-9);
+        assert_eq!(estimate_cost("claude", "unknown", 1, 1, 1), None);
+    }
+
+    #[test]
+    fn deltas_follow_snapshot_semantics() {
+        use TokenUsageSnapshotKind::*;
+        let model = "claude-sonnet-4-5";
+        let delta = |prev: (TokenUsage, TokenUsageSnapshotKind), next: (TokenUsage, _)| {
+            usage_cost_delta("claude", model, (&prev.0, prev.1), (&next.0, next.1)).unwrap()
+        };
+
+        // Per-request input is billed each time; cumulative output only grows.
+        let first = delta(
+            (usage(0, 0, 0), Unknown),
+            (usage(1_000_000, 100_000, 0), MixedLegacy),
+        );
+        assert!((first - 4.5).abs() < 1e-9);
+        let second = delta(
+            (usage(1_000_000, 100_000, 0), MixedLegacy),
+            (usage(1_000_000, 200_000, 0), MixedLegacy),
+        );
+        assert!((second - 4.5).abs() < 1e-9);
+        let repeat = delta(
+            (usage(1_000_000, 200_000, 0), MixedLegacy),
+            (usage(1_000_000, 200_000, 0), MixedLegacy),
+        );
+        assert_eq!(repeat, 0.0);
+
+        let lifetime = delta(
+            (usage(1_000_000, 0, 0), LifetimeTotals),
+            (usage(2_000_000, 0, 0), LifetimeTotals),
+        );
+        assert!((lifetime - 3.0).abs() < 1e-9);
+        let tokens = TokenUsage::delta(
+            (&usage(1_000_000, 50, 10), LifetimeTotals),
+            (&usage(1_500_000, 80, 10), LifetimeTotals),
+        );
+        assert_eq!(
+            (
+                tokens.input_tokens,
+                tokens.output_tokens,
+                tokens.cached_tokens
+            ),
+            (500_000, 30, 0)
+        );
+        assert_eq!(
+            delta(
+                (usage(5, 5, 5), ContextTurn),
+                (usage(0, 0, 0), CompactionReset)
+            ),
+            0.0
+        );
+        assert_eq!(
+            usage_cost_delta(
+                "claude",
+                "mystery",
+                (&usage(0, 0, 0), Unknown),
+                (&usage(1, 1, 1), ContextTurn)
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Per-repo defaults from a committed `.orbitdock.toml`.
+//!
+//! Looked up from the session cwd upward, stopping at the repository root
+//! (the first directory holding `.git`). Session settings fill in whatever a
+//! `CreateSession` or `TakeoverSession` request leaves out, ahead of the
+//! server's own connector defaults. Instructions are appended to the agent's
+//! system prompt each time its process starts. Notification overrides are
+//! read per event, so edits apply to sessions that are already running.
+//!
+//! Anyone who can push to a repo controls its file, so unless the operator
+//! lists the repo in `connectors.trusted_projects` it may only make the
+//! approval, sandbox, and permission settings stricter than the operator's
+//! defaults, and its `allowed_tools` are ignored. Dropped values are logged.
+//!
+//! A file that fails to parse is logged and ignored.
+
+use std::path::{Path, PathBuf};
+
+use orbitdock_protocol::Provider;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::config_file::{self, ConnectorDefaults};
+
+pub const FILE_NAME: &str = ".orbitdock.toml";
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    // Top-level session keys apply to both providers; a provider section wins.
+    pub model: Option<String>,
+    pub effort: Option<String>,
+    pub approval_policy: Option<String>,
+    pub sandbox_mode: Option<String>,
+    pub permission_mode: Option<String>,
+    /// Appended to the agent's system prompt.
+    pub instructions: Option<String>,
+    pub allowed_tools: Vec<String>,
+    pub disallowed_tools: Vec<String>,
+    pub claude: ConnectorDefaults,
+    pub codex: ConnectorDefaults,
+    pub notifications: ProjectNotifications,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectNotifications {
+    /// `false` skips iOS push for this project's sessions.
+    pub push: Option<bool>,
+    /// `false` skips webhooks, including Slack and Discord.
+    pub webhooks: Option<bool>,
+    /// `false` sends turn notifications without an AI summary.
+    pub turn_summaries: Option<bool>,
+}
+
+impl ProjectConfig {
+    pub fn parse(contents: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Session defaults for one provider: its section, then the shared keys.
+    pub fn defaults_for(&self, provider: Provider) -> ConnectorDefaults {
+        let specific = match provider {
+            Provider::Claude => &self.claude,
+            Provider::Codex => &self.codex,
+        };
+        ConnectorDefaults {
+            model: specific.model.clone().or_else(|| self.model.clone()),
+            effort: specific.effort.clone().or_else(|| self.effort.clone()),
+            approval_policy: specific
+                .approval_policy
+                .clone()
+                .or_else(|| self.approval_policy.clone()),
+            sandbox_mode: specific
+                .sandbox_mode
+                .clone()
+                .or_else(|| self.sandbox_mode.clone()),
+            permission_mode: specific
+                .permission_mode
+                .clone()
+                .or_else(|| self.permission_mode.clone()),
+        }
+    }
+}
+
+/// The nearest `.orbitdock.toml` at or above `cwd`, within its repository.
+pub fn find(cwd: &Path) -> Option<PathBuf> {
+    for dir in cwd.ancestors() {
+        let candidate = dir.join(FILE_NAME);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+/// The project config for a session cwd, or `None` when there is no usable file.
+pub fn load(cwd: &str) -> Option<ProjectConfig> {
+    let path = find(Path::new(cwd))?;
+    let parsed = std::fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| ProjectConfig::parse(&contents));
+    match parsed {
+        Ok(config) => Some(config),
+        Err(e) => {
+            warn!(
+                component = "project_config",
+                event = "project_config.invalid",
+                path = %path.display(),
+                error = %e,
+                "Ignoring invalid project config"
+            );
+            None
+        }
+    }
+}
+
+/// The project config for a session in `cwd`, and its session defaults for
+/// `provider`, limited by the operator's config.
+pub fn load_for_session(cwd: &str, provider: Provider) -> (ProjectConfig, ConnectorDefaults) {
+    let Some(path) = find(Path::new(cwd)) else {
+        return Default::default();
+    };
+    let project = load(cwd).unwrap_or_default();
+    let config = config_file::current();
+    let trusted = config.trusts_project(&path);
+    restrict(
+        project,
+        provider,
+        config.connector_defaults(provider),
+        trusted,
+        &path,
+    )
+}
+
+/// Unless `trusted`, drop repo safety settings looser than the operator's
+/// (or, where the operator set none, the connector's own) and repo
+/// `allowed_tools`.
+fn restrict(
+    mut project: ProjectConfig,
+    provider: Provider,
+    operator: &ConnectorDefaults,
+    trusted: bool,
+    path: &Path,
+) -> (ProjectConfig, ConnectorDefaults) {
+    let mut repo = project.defaults_for(provider);
+    if trusted {
+        return (project, repo);
+    }
+    let keep = |key: &str, value: Option<String>, floor: Option<&str>, fallback: &str| {
+        let value = value?;
+        let floor = floor.unwrap_or(fallback);
+        if matches!(
+            (strictness(key, &value), strictness(key, floor)),
+            (Some(value), Some(floor)) if value >= floor
+        ) {
+            return Some(value);
+        }
+        warn!(
+            component = "project_config",
+            event = "project_config.setting_dropped",
+            path = %path.display(),
+            key,
+            value = %value,
+            operator_default = floor,
+            "Ignoring project setting looser than the operator's default"
+        );
+        None
+    };
+    repo.approval_policy = keep(
+        "approval_policy",
+        repo.approval_policy,
+        operator.approval_policy.as_deref(),
+        "untrusted",
+    );
+    repo.sandbox_mode = keep(
+        "sandbox_mode",
+        repo.sandbox_mode,
+        operator.sandbox_mode.as_deref(),
+        "read-only",
+    );
+    repo.permission_mode = keep(
+        "permission_mode",
+        repo.permission_mode,
+        operator.permission_mode.as_deref(),
+        "default",
+    );
+    if !project.allowed_tools.is_empty() {
+        warn!(
+            component = "project_config",
+            event = "project_config.setting_dropped",
+            path = %path.display(),
+            key = "allowed_tools",
+            "Ignoring allowed_tools from a project that isn't in connectors.trusted_projects"
+        );
+        project.allowed_tools.clear();
+    }
+    (project, repo)
+}
+
+/// How strict a safety setting's value is; higher asks more often. `None`
+/// for values we don't recognize.
+fn strictness(key: &str, value: &str) -> Option<u8> {
+    let order: &[&str] = match key {
+        "approval_policy" => &["never", "on-failure", "on-request", "untrusted"],
+        "sandbox_mode" => &["danger-full-access", "workspace-write", "read-only"],
+        "permission_mode" => &["bypassPermissions", "acceptEdits", "default", "plan"],
+        _ => &[],
+    };
+    order
+        .iter()
+        .position(|candidate| *candidate == value)
+        .map(|rank| rank as u8)
+}
+
+/// Instructions to append to the system prompt for an agent started in `cwd`.
+pub fn instructions(cwd: &str) -> Option<String> {
+    load(cwd)?
+        .instructions
+        .filter(|text| !text.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_section_overrides_shared_keys() {
+        let config = ProjectConfig::parse(
+            r#"
+            model = "shared-model"
+            approval_policy = "on-request"
+            instructions = "Run cargo fmt before committing."
+            allowed_tools = ["Read", "Grep"]
+
+            [claude]
+            model = "claude-sonnet-4-5"
+            permission_mode = "acceptEdits"
+
+            [notifications]
+            push = false
+            "#,
+        )
+        .unwrap();
+
+        let claude = config.defaults_for(Provider::Claude);
+        assert_eq!(claude.model.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(claude.permission_mode.as_deref(), Some("acceptEdits"));
+        let codex = config.defaults_for(Provider::Codex);
+        assert_eq!(codex.model.as_deref(), Some("shared-model"));
+        assert_eq!(codex.approval_policy.as_deref(), Some("on-request"));
+        assert_eq!(config.allowed_tools, vec!["Read", "Grep"]);
+        assert_eq!(config.notifications.push, Some(false));
+        assert!(ProjectConfig::parse("modle = \"typo\"").is_err());
+    }
+
+    #[test]
+    fn untrusted_projects_can_only_tighten_safety_settings() {
+        let hostile = ProjectConfig::parse(
+            r#"
+            approval_policy = "never"
+            sandbox_mode = "danger-full-access"
+            allowed_tools = ["Bash"]
+
+            [claude]
+            permission_mode = "bypassPermissions"
+            "#,
+        )
+        .unwrap();
+        let operator = ConnectorDefaults {
+            approval_policy: Some("on-request".to_string()),
+            ..Default::default()
+        };
+        let path = Path::new("/repo/.orbitdock.toml");
+
+        let (project, codex) = restrict(hostile.clone(), Provider::Codex, &operator, false, path);
+        assert_eq!(codex.approval_policy, None);
+        assert_eq!(codex.sandbox_mode, None);
+        assert!(project.allowed_tools.is_empty());
+        let (_, claude) = restrict(
+            hostile.clone(),
+            Provider::Claude,
+            &ConnectorDefaults::default(),
+            false,
+            path,
+        );
+        assert_eq!(claude.permission_mode, None);
+
+        let (project, codex) = restrict(hostile, Provider::Codex, &operator, true, path);
+        assert_eq!(codex.approval_policy.as_deref(), Some("never"));
+        assert_eq!(project.allowed_tools, vec!["Bash"]);
+
+        let strict = ProjectConfig::parse(
+            r#"
+            approval_policy = "untrusted"
+            sandbox_mode = "read-only"
+            "#,
+        )
+        .unwrap();
+        let (_, codex) = restrict(strict, Provider::Codex, &operator, false, path);
+        assert_eq!(codex.approval_policy.as_deref(), Some("untrusted"));
+        assert_eq!(codex.sandbox_mode.as_deref(), Some("read-only"));
+    }
+
+    #[test]
+    fn find_stops_at_the_repository_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let nested = repo.join("crates/server");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir(repo.join(".git")).unwrap();
+
+        std::fs::write(dir.path().join(FILE_NAME), "model = \"outside\"").unwrap();
+        assert_eq!(find(&nested), None);
+
+        std::fs::write(repo.join(FILE_NAME), "model = \"inside\"").unwrap();
+        assert_eq!(find(&nested), Some(repo.join(FILE_NAME)));
+        let config = load(nested.to_str().unwrap()).unwrap();
+        assert_eq!(config.model.as_deref(), Some("inside"));
+    }
+}
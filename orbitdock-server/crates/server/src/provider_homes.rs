@@ -0,0 +1,107 @@
+//! Codex and Claude home directories.
+//!
+//! The default homes follow the CLIs themselves: `$CODEX_HOME` (else
+//! `~/.codex`) and `$CLAUDE_CONFIG_DIR` (else `~/.claude`). `[connectors]
+//! codex_homes` / `claude_homes` in `config.toml` add more, for alternate
+//! `CODEX_HOME` values or per-client sandboxes. The rollout and transcript
+//! watchers scan every home; hook setup writes to the default one.
+
+use std::path::{Path, PathBuf};
+
+pub fn default_codex_home() -> PathBuf {
+    env_home("CODEX_HOME", ".codex")
+}
+
+pub fn default_claude_home() -> PathBuf {
+    env_home("CLAUDE_CONFIG_DIR", ".claude")
+}
+
+/// Every Codex home, default first.
+pub fn codex_homes() -> Vec<PathBuf> {
+    let extra = crate::config_file::current().connectors.codex_homes.clone();
+    with_extra(default_codex_home(), extra.unwrap_or_default())
+}
+
+/// Every Claude home, default first.
+pub fn claude_homes() -> Vec<PathBuf> {
+    let extra = crate::config_file::current()
+        .connectors
+        .claude_homes
+        .clone();
+    with_extra(default_claude_home(), extra.unwrap_or_default())
+}
+
+/// Directories holding Codex rollout files.
+pub fn codex_session_dirs() -> Vec<PathBuf> {
+    codex_homes()
+        .into_iter()
+        .map(|home| home.join("sessions"))
+        .collect()
+}
+
+/// Directories holding Claude transcripts, one subdirectory per project.
+pub fn claude_project_dirs() -> Vec<PathBuf> {
+    claude_homes()
+        .into_iter()
+        .map(|home| home.join("projects"))
+        .collect()
+}
+
+/// Whether `path` is a rollout file under any Codex home.
+pub fn is_codex_rollout_path(path: &str) -> bool {
+    path.contains("/.codex/sessions/")
+        || codex_session_dirs()
+            .iter()
+            .any(|dir| Path::new(path).starts_with(dir))
+}
+
+fn env_home(var: &str, dir_name: &str) -> PathBuf {
+    if let Some(dir) = std::env::var_os(var).filter(|dir| !dir.is_empty()) {
+        return PathBuf::from(dir);
+    }
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(home).join(dir_name)
+}
+
+fn with_extra(default: PathBuf, extra: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut homes = vec![default];
+    for home in extra.iter().map(|home| expand_home(home)) {
+        if !homes.contains(&home) {
+            homes.push(home);
+        }
+    }
+    homes
+}
+
+pub(crate) fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extra_homes_follow_the_default_without_duplicates() {
+        let home = PathBuf::from(std::env::var("HOME").unwrap_or_default());
+        let homes = with_extra(
+            PathBuf::from("/a/.codex"),
+            vec![
+                PathBuf::from("/sandbox/codex"),
+                PathBuf::from("/a/.codex"),
+                PathBuf::from("~/alt-codex"),
+            ],
+        );
+        assert_eq!(
+            homes,
+            vec![
+                PathBuf::from("/a/.codex"),
+                PathBuf::from("/sandbox/codex"),
+                home.join("alt-codex"),
+            ]
+        );
+    }
+}
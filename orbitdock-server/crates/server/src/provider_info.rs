@@ -0,0 +1,217 @@
+//! Provider CLI discovery and version checks.
+//!
+//! Both providers' event formats change between CLI releases, and an old
+//! `claude` or `codex` binary tends to break parsing silently rather than
+//! fail loudly. At startup (and on `get_provider_info` with `refresh`) this
+//! locates each binary the same way its connector does, runs `--version`,
+//! and compares the result against the oldest release OrbitDock is tested
+//! with. Results are cached for `/health` and `provider_info` replies.
+
+use std::path::Path;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use orbitdock_protocol::{Provider, ProviderBinaryInfo, ProviderBinaryStatus};
+use tracing::{info, warn};
+
+/// Oldest Claude CLI whose `stream-json` events and control requests the
+/// Claude connector understands.
+pub(crate) const CLAUDE_MIN_VERSION: &str = "2.0.0";
+
+/// Oldest Codex CLI whose `app-server` protocol the usage probe speaks.
+pub(crate) const CODEX_MIN_VERSION: &str = "0.100.0";
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+static PROBED: RwLock<Vec<ProviderBinaryInfo>> = RwLock::new(Vec::new());
+
+/// Last probe result, one entry per provider. Empty until the startup probe
+/// finishes.
+pub(crate) fn current() -> Vec<ProviderBinaryInfo> {
+    PROBED
+        .read()
+        .map(|probed| probed.clone())
+        .unwrap_or_default()
+}
+
+/// Re-probe both binaries and replace the cached result.
+pub(crate) async fn refresh() -> Vec<ProviderBinaryInfo> {
+    let claude_path = orbitdock_connector_claude::resolve_claude_binary().ok();
+    let codex_path =
+        crate::usage_probe::find_codex_binary().map(|path| path.to_string_lossy().into_owned());
+
+    let (claude, codex) = tokio::join!(
+        probe(Provider::Claude, claude_path, CLAUDE_MIN_VERSION),
+        probe(Provider::Codex, codex_path, CODEX_MIN_VERSION),
+    );
+    let providers = vec![claude, codex];
+
+    if let Ok(mut probed) = PROBED.write() {
+        *probed = providers.clone();
+    }
+    providers
+}
+
+/// Startup probe. Logs a warning for each binary that's missing or too old
+/// so the cause of odd session behavior shows up in the server log.
+pub(crate) async fn start_probe() {
+    for binary in refresh().await {
+        let provider = provider_name(binary.provider);
+        match binary.status {
+            ProviderBinaryStatus::Ok => info!(
+                component = "provider_info",
+                event = "provider_info.binary.ok",
+                provider,
+                version = binary.version.as_deref().unwrap_or_default(),
+                path = binary.path.as_deref().unwrap_or_default(),
+                "Provider CLI found"
+            ),
+            _ => warn!(
+                component = "provider_info",
+                event = "provider_info.binary.problem",
+                provider,
+                status = ?binary.status,
+                version = binary.version.as_deref().unwrap_or_default(),
+                min_version = %binary.min_version,
+                detail = binary.detail.as_deref().unwrap_or_default(),
+                "Provider CLI needs attention"
+            ),
+        }
+    }
+}
+
+async fn probe(provider: Provider, path: Option<String>, min_version: &str) -> ProviderBinaryInfo {
+    let name = provider_name(provider);
+    let mut info = ProviderBinaryInfo {
+        provider,
+        status: ProviderBinaryStatus::NotFound,
+        path: path.clone(),
+        version: None,
+        min_version: min_version.to_string(),
+        detail: None,
+        checked_at: unix_now(),
+    };
+    let Some(path) = path else {
+        info.detail = Some(format!("{name} CLI not found on this host"));
+        return info;
+    };
+
+    match read_version(Path::new(&path)).await {
+        Ok(output) => {
+            info.version = parse_version(&output).map(|v| format!("{}.{}.{}", v.0, v.1, v.2));
+            let (status, detail) = check_version(name, info.version.as_deref(), min_version);
+            info.status = status;
+            info.detail = detail;
+        }
+        Err(error) => {
+            info.status = ProviderBinaryStatus::Unknown;
+            info.detail = Some(format!("`{name} --version` failed: {error}"));
+        }
+    }
+    info
+}
+
+async fn read_version(path: &Path) -> Result<String, String> {
+    let output = tokio::time::timeout(
+        VERSION_TIMEOUT,
+        tokio::process::Command::new(path)
+            .arg("--version")
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| "timed out".to_string())?
+    .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(format!("exited with {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn check_version(
+    name: &str,
+    version: Option<&str>,
+    min_version: &str,
+) -> (ProviderBinaryStatus, Option<String>) {
+    let (Some(found), Some(min)) = (version.and_then(parse_version), parse_version(min_version))
+    else {
+        return (
+            ProviderBinaryStatus::Unknown,
+            Some(format!("couldn't read a version from `{name} --version`")),
+        );
+    };
+    if found < min {
+        (
+            ProviderBinaryStatus::Outdated,
+            Some(format!(
+                "{name} {} is older than {min_version}; upgrade it so session events parse correctly",
+                version.unwrap_or_default()
+            )),
+        )
+    } else {
+        (ProviderBinaryStatus::Ok, None)
+    }
+}
+
+/// First `major.minor.patch` in `--version` output, e.g. `2.0.14 (Claude
+/// Code)` or `codex-cli 0.107.0`.
+fn parse_version(output: &str) -> Option<(u64, u64, u64)> {
+    output.split_whitespace().find_map(|word| {
+        let word = word.trim_start_matches('v');
+        let core = word.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+        let version = (parts.next()??, parts.next()??, parts.next()??);
+        parts.next().is_none().then_some(version)
+    })
+}
+
+fn provider_name(provider: Provider) -> &'static str {
+    match provider {
+        Provider::Claude => "claude",
+        Provider::Codex => "codex",
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions_from_cli_output() {
+        assert_eq!(parse_version("2.0.14 (Claude Code)\n"), Some((2, 0, 14)));
+        assert_eq!(parse_version("codex-cli 0.107.0"), Some((0, 107, 0)));
+        assert_eq!(parse_version("v1.2.3-beta.1"), Some((1, 2, 3)));
+        assert_eq!(parse_version("codex-cli dev"), None);
+        assert_eq!(parse_version("1.2"), None);
+    }
+
+    #[test]
+    fn compares_against_the_minimum_numerically() {
+        let (status, detail) = check_version("codex", Some("0.99.0"), "0.100.0");
+        assert_eq!(status, ProviderBinaryStatus::Outdated);
+        assert!(detail.unwrap().contains("older than 0.100.0"));
+
+        let (status, detail) = check_version("codex", Some("0.107.0"), "0.100.0");
+        assert_eq!(status, ProviderBinaryStatus::Ok);
+        assert!(detail.is_none());
+
+        let (status, _) = check_version("claude", None, CLAUDE_MIN_VERSION);
+        assert_eq!(status, ProviderBinaryStatus::Unknown);
+    }
+
+    #[tokio::test]
+    async fn missing_binary_is_not_found() {
+        let info = probe(Provider::Codex, None, CODEX_MIN_VERSION).await;
+        assert_eq!(info.status, ProviderBinaryStatus::NotFound);
+        assert!(info.version.is_none());
+        assert_eq!(info.min_version, CODEX_MIN_VERSION);
+    }
+}
@@ -0,0 +1,260 @@
+//! Token-bucket rate limits for hook posts and WebSocket messages.
+//!
+//! Hooks are limited per credential: each database token gets its own bucket,
+//! and the shared static token or an open server falls back to one bucket per
+//! remote address. WebSocket messages are limited per connection and, keyed
+//! the same way, per credential, so opening more sockets doesn't raise a
+//! token's limit. Rejections are counted for `/metrics`.
+//!
+//! `ORBITDOCK_HOOK_RATE_LIMIT` and `ORBITDOCK_WS_RATE_LIMIT` set the sustained
+//! rate in events per second; bursts of twice that are allowed. `0` turns a
+//! limit off. A config reload changes the hook and per-credential WebSocket
+//! limits immediately, and the per-connection limit for connections opened
+//! after it.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::auth::AuthIdentity;
+
+const DEFAULT_HOOK_PER_SECOND: f64 = 100.0;
+const DEFAULT_WS_PER_SECOND: f64 = 50.0;
+/// Past this many keys, idle buckets are dropped before adding another.
+const MAX_TRACKED_KEYS: usize = 1024;
+const IDLE_BUCKET_TTL: Duration = Duration::from_secs(60);
+
+static HOOK_LIMITER: OnceLock<KeyedLimiter> = OnceLock::new();
+static WS_LIMITER: OnceLock<KeyedLimiter> = OnceLock::new();
+static HOOK_REJECTED: AtomicU64 = AtomicU64::new(0);
+static WS_REJECTED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: f64,
+}
+
+impl RateLimit {
+    fn from_env(var: &str, default_per_second: f64) -> Option<Self> {
+        let per_second = match crate::config_file::var(var) {
+            Some(value) => value.trim().parse::<f64>().unwrap_or(default_per_second),
+            None => default_per_second,
+        };
+        (per_second > 0.0).then_some(RateLimit {
+            per_second,
+            burst: per_second * 2.0,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit) -> Self {
+        Self::new_at(limit, Instant::now())
+    }
+
+    fn new_at(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst,
+            refilled_at: now,
+        }
+    }
+
+    pub fn try_acquire(&mut self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&mut self, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst);
+        self.refilled_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// One bucket per key, created on first use.
+pub struct KeyedLimiter {
+    limit: RwLock<Option<RateLimit>>,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl KeyedLimiter {
+    pub fn new(limit: Option<RateLimit>) -> Self {
+        Self {
+            limit: RwLock::new(limit),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Change the limit, starting every key with a fresh bucket.
+    pub fn set_limit(&self, limit: Option<RateLimit>) {
+        *self.limit.write().unwrap_or_else(|e| e.into_inner()) = limit;
+        self.buckets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    pub fn check(&self, key: &str) -> bool {
+        let Some(limit) = *self.limit.read().unwrap_or_else(|e| e.into_inner()) else {
+            return true;
+        };
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if !buckets.contains_key(key) && buckets.len() >= MAX_TRACKED_KEYS {
+            buckets.retain(|_, bucket| {
+                now.saturating_duration_since(bucket.refilled_at) < IDLE_BUCKET_TTL
+            });
+        }
+        buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::new_at(limit, now))
+            .try_acquire_at(now)
+    }
+}
+
+/// The bucket key for a caller: its database token, or its remote address
+/// for the shared static token and open servers.
+pub fn credential_key(identity: &AuthIdentity, remote_addr: Option<SocketAddr>) -> String {
+    match (identity, remote_addr) {
+        (AuthIdentity::Token { .. }, _) | (_, None) => identity.label(),
+        (_, Some(addr)) => format!("ip:{}", addr.ip()),
+    }
+}
+
+/// Whether a hook post from this caller is within its limit.
+pub fn allow_hook(identity: &AuthIdentity, remote_addr: Option<SocketAddr>) -> bool {
+    let key = credential_key(identity, remote_addr);
+    let allowed = HOOK_LIMITER
+        .get_or_init(|| KeyedLimiter::new(hook_limit()))
+        .check(&key);
+    if !allowed {
+        HOOK_REJECTED.fetch_add(1, Ordering::Relaxed);
+    }
+    allowed
+}
+
+fn hook_limit() -> Option<RateLimit> {
+    RateLimit::from_env("ORBITDOCK_HOOK_RATE_LIMIT", DEFAULT_HOOK_PER_SECOND)
+}
+
+/// Re-read the hook limit after a config reload.
+pub fn reload_hook_limit() {
+    if let Some(limiter) = HOOK_LIMITER.get() {
+        limiter.set_limit(hook_limit());
+    }
+}
+
+fn ws_limit() -> Option<RateLimit> {
+    RateLimit::from_env("ORBITDOCK_WS_RATE_LIMIT", DEFAULT_WS_PER_SECOND)
+}
+
+/// A fresh bucket for one WebSocket connection, or `None` when unlimited.
+pub fn ws_connection_bucket() -> Option<TokenBucket> {
+    ws_limit().map(TokenBucket::new)
+}
+
+/// Whether a WebSocket message is within the limit shared by every socket
+/// opened with the credential behind `key` (see [`credential_key`]).
+pub fn allow_ws_message(key: &str) -> bool {
+    WS_LIMITER
+        .get_or_init(|| KeyedLimiter::new(ws_limit()))
+        .check(key)
+}
+
+/// Re-read the per-credential WebSocket limit after a config reload.
+pub fn reload_ws_limit() {
+    if let Some(limiter) = WS_LIMITER.get() {
+        limiter.set_limit(ws_limit());
+    }
+}
+
+pub fn note_ws_rejected() {
+    WS_REJECTED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Rejections so far, as (hook, websocket).
+pub fn rejected_counts() -> (u64, u64) {
+    (
+        HOOK_REJECTED.load(Ordering::Relaxed),
+        WS_REJECTED.load(Ordering::Relaxed),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_allows_a_burst_then_refills_at_the_rate() {
+        let limit = RateLimit {
+            per_second: 10.0,
+            burst: 20.0,
+        };
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new_at(limit, start);
+
+        assert_eq!((0..25).filter(|_| bucket.try_acquire_at(start)).count(), 20);
+        assert!(!bucket.try_acquire_at(start + Duration::from_millis(50)));
+        assert!(bucket.try_acquire_at(start + Duration::from_millis(150)));
+        let later = start + Duration::from_secs(60);
+        assert_eq!((0..25).filter(|_| bucket.try_acquire_at(later)).count(), 20);
+    }
+
+    #[test]
+    fn keyed_limiter_tracks_keys_separately() {
+        let limiter = KeyedLimiter::new(Some(RateLimit {
+            per_second: 1.0,
+            burst: 1.0,
+        }));
+        assert!(limiter.check("token:a"));
+        assert!(!limiter.check("token:a"));
+        assert!(limiter.check("token:b"));
+
+        let unlimited = KeyedLimiter::new(None);
+        assert!((0..1000).all(|_| unlimited.check("token:a")));
+
+        limiter.set_limit(None);
+        assert!((0..1000).all(|_| limiter.check("token:a")));
+    }
+
+    #[test]
+    fn sockets_with_one_token_share_a_key() {
+        let token = AuthIdentity::Token {
+            id: "tok-1".to_string(),
+            user: None,
+            scope: crate::scopes::TokenScope::Admin,
+        };
+        let phone: SocketAddr = "10.0.0.2:50000".parse().unwrap();
+        let laptop: SocketAddr = "10.0.0.3:50001".parse().unwrap();
+        assert_eq!(
+            credential_key(&token, Some(phone)),
+            credential_key(&token, Some(laptop))
+        );
+        assert_eq!(
+            credential_key(&AuthIdentity::StaticToken, Some(phone)),
+            "ip:10.0.0.2"
+        );
+        assert_eq!(
+            credential_key(&AuthIdentity::StaticToken, None),
+            "static_token"
+        );
+    }
+}
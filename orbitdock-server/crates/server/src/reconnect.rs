@@ -0,0 +1,167 @@
+//! Automatic reconnect for direct connectors.
+//!
+//! When a connector dies on its own — the Claude CLI exits, or Codex's event
+//! stream closes — the session's event loop resumes it in place instead of
+//! ending the session, backing off exponentially between attempts. Each
+//! scheduled attempt is announced as `connector_reconnecting` and a
+//! successful one as `connector_reconnected`. Once `MAX_ATTEMPTS` have
+//! failed, the session ends as it did before, with a `process` error that
+//! suggests resuming it by hand.
+
+use std::time::Duration;
+
+use orbitdock_protocol::{ErrorCategory, ServerMessage, SessionError};
+use tokio::time::Instant;
+
+pub(crate) const MAX_ATTEMPTS: u32 = 5;
+const BASE_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Delay before the 1-based `attempt`: 1s, 2s, 4s, … capped at `MAX_DELAY`.
+fn delay(attempt: u32) -> Duration {
+    let doublings = attempt.saturating_sub(1).min(16);
+    BASE_DELAY.saturating_mul(1 << doublings).min(MAX_DELAY)
+}
+
+/// Whether a Claude `SessionEnded` reason means the CLI died rather than
+/// being shut down.
+pub(crate) fn is_unexpected_exit(reason: &str) -> bool {
+    reason == "cli_exited" || reason.starts_with("read_error")
+}
+
+/// Reconnect progress for one session's event loop.
+#[derive(Debug, Default)]
+pub(crate) struct Reconnect {
+    /// Attempts since the connector last delivered an event.
+    attempts: u32,
+    due: Option<Instant>,
+    reason: String,
+}
+
+impl Reconnect {
+    /// Schedule the next attempt after the connector died for `reason`.
+    /// Returns the progress message to broadcast, or `None` once out of
+    /// attempts.
+    pub(crate) fn schedule(&mut self, session_id: &str, reason: &str) -> Option<ServerMessage> {
+        if self.attempts >= MAX_ATTEMPTS {
+            self.due = None;
+            return None;
+        }
+        self.attempts += 1;
+        let wait = delay(self.attempts);
+        self.due = Some(Instant::now() + wait);
+        self.reason = reason.to_string();
+        Some(ServerMessage::ConnectorReconnecting {
+            session_id: session_id.to_string(),
+            attempt: self.attempts,
+            max_attempts: MAX_ATTEMPTS,
+            retry_in_ms: wait.as_millis() as u64,
+            reason: reason.to_string(),
+        })
+    }
+
+    /// When the next attempt is due, if one is scheduled.
+    pub(crate) fn due(&self) -> Option<Instant> {
+        self.due
+    }
+
+    /// Start the scheduled attempt, returning why the connector died.
+    pub(crate) fn begin(&mut self) -> String {
+        self.due = None;
+        self.reason.clone()
+    }
+
+    /// The attempt started a connector. Attempts keep counting until it
+    /// delivers an event, so one that dies straight away isn't retried
+    /// forever.
+    pub(crate) fn succeeded(&self, session_id: &str) -> ServerMessage {
+        ServerMessage::ConnectorReconnected {
+            session_id: session_id.to_string(),
+            attempts: self.attempts,
+        }
+    }
+
+    /// The connector delivered an event, so it is healthy again.
+    pub(crate) fn connector_alive(&mut self) {
+        if self.due.is_none() {
+            self.attempts = 0;
+        }
+    }
+
+    pub(crate) fn is_pending(&self) -> bool {
+        self.due.is_some()
+    }
+}
+
+/// Resolves when the scheduled attempt is due; never, if none is.
+pub(crate) async fn reconnect_due(due: Option<Instant>) {
+    match due {
+        Some(due) => tokio::time::sleep_until(due).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// The error a session ends with once reconnecting gives up.
+pub(crate) fn gave_up_error(provider: &str, reason: &str) -> SessionError {
+    SessionError::new(
+        ErrorCategory::Process,
+        format!("{provider} connector stopped ({reason}) and didn't come back after {MAX_ATTEMPTS} attempts"),
+    )
+    .with_provider_code(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_up_to_the_cap() {
+        assert_eq!(delay(1), Duration::from_secs(1));
+        assert_eq!(delay(2), Duration::from_secs(2));
+        assert_eq!(delay(4), Duration::from_secs(8));
+        assert_eq!(delay(10), MAX_DELAY);
+        assert_eq!(delay(u32::MAX), MAX_DELAY);
+    }
+
+    #[test]
+    fn schedule_gives_up_after_max_attempts() {
+        let mut reconnect = Reconnect::default();
+        for attempt in 1..=MAX_ATTEMPTS {
+            match reconnect.schedule("s", "cli_exited") {
+                Some(ServerMessage::ConnectorReconnecting {
+                    attempt: scheduled, ..
+                }) => assert_eq!(scheduled, attempt),
+                other => panic!("expected reconnecting, got {other:?}"),
+            }
+            assert!(reconnect.is_pending());
+            assert_eq!(reconnect.begin(), "cli_exited");
+            // A connector that dies before sending anything doesn't reset
+            // the count.
+        }
+        assert!(reconnect.schedule("s", "cli_exited").is_none());
+        assert!(!reconnect.is_pending());
+    }
+
+    #[test]
+    fn a_live_connector_resets_the_attempts() {
+        let mut reconnect = Reconnect::default();
+        reconnect.schedule("s", "cli_exited");
+        reconnect.connector_alive();
+        assert_eq!(reconnect.attempts, 1, "still waiting on the attempt");
+
+        reconnect.begin();
+        reconnect.connector_alive();
+        match reconnect.schedule("s", "cli_exited") {
+            Some(ServerMessage::ConnectorReconnecting { attempt, .. }) => assert_eq!(attempt, 1),
+            other => panic!("expected reconnecting, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn only_cli_deaths_are_unexpected() {
+        assert!(is_unexpected_exit("cli_exited"));
+        assert!(is_unexpected_exit("read_error: broken pipe"));
+        assert!(!is_unexpected_exit("shutdown"));
+        assert!(!is_unexpected_exit("user_requested"));
+    }
+}
@@ -0,0 +1,364 @@
+//! Periodic CPU and memory sampling of connector processes.
+//!
+//! Each direct Claude session runs its own `claude` CLI child; Codex runs
+//! in-process, so the server's own entry stands in for it. Every
+//! `SAMPLE_INTERVAL` the monitor samples each process with `ps`, publishes the
+//! results for `GetConnectorStats`, and flags runaway Claude processes: the
+//! session's subscribers get an error, and unless disabled the CLI is
+//! restarted with `--resume` so the conversation carries on.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use orbitdock_protocol::{ConnectorProcessStats, Provider, ServerMessage};
+use tracing::{info, warn};
+
+use crate::claude_session::ClaudeAction;
+use crate::session_command::SessionCommand;
+use crate::session_command_handler::chrono_now;
+use crate::state::SessionRegistry;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+
+const DEFAULT_MAX_RSS_MB: u64 = 4096;
+const DEFAULT_MAX_CPU_PERCENT: f64 = 90.0;
+/// Consecutive samples above the CPU limit before a process counts as runaway.
+const CPU_STRIKES: u32 = 8;
+
+#[derive(Debug, Clone)]
+pub struct ResourceLimits {
+    pub max_rss_bytes: u64,
+    pub max_cpu_percent: f64,
+    pub auto_restart: bool,
+}
+
+impl ResourceLimits {
+    /// Read overrides from `ORBITDOCK_CONNECTOR_MAX_RSS_MB`,
+    /// `ORBITDOCK_CONNECTOR_MAX_CPU_PERCENT`, and
+    /// `ORBITDOCK_CONNECTOR_AUTO_RESTART` (`0` to only warn).
+    pub fn from_env() -> Self {
+        let max_rss_mb = std::env::var("ORBITDOCK_CONNECTOR_MAX_RSS_MB")
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .filter(|mb| *mb > 0)
+            .unwrap_or(DEFAULT_MAX_RSS_MB);
+        let max_cpu_percent = std::env::var("ORBITDOCK_CONNECTOR_MAX_CPU_PERCENT")
+            .ok()
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|percent| *percent > 0.0)
+            .unwrap_or(DEFAULT_MAX_CPU_PERCENT);
+        Self {
+            max_rss_bytes: max_rss_mb * 1024 * 1024,
+            max_cpu_percent,
+            auto_restart: std::env::var("ORBITDOCK_CONNECTOR_AUTO_RESTART").as_deref() != Ok("0"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ProcessSample {
+    cpu_seconds: f64,
+    rss_bytes: u64,
+}
+
+#[derive(Default)]
+struct Tracker {
+    /// Previous cumulative CPU time per pid, for computing usage deltas.
+    previous: HashMap<u32, (Instant, f64)>,
+    cpu_strikes: HashMap<u32, u32>,
+}
+
+struct Target {
+    session_id: Option<String>,
+    provider: Option<Provider>,
+    pid: u32,
+}
+
+pub async fn start_resource_monitor_loop(state: Arc<SessionRegistry>) {
+    let limits = ResourceLimits::from_env();
+    info!(
+        component = "resource_monitor",
+        event = "resource_monitor.started",
+        max_rss_bytes = limits.max_rss_bytes,
+        max_cpu_percent = limits.max_cpu_percent,
+        auto_restart = limits.auto_restart,
+    );
+
+    let mut tracker = Tracker::default();
+    let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+    loop {
+        interval.tick().await;
+        sample_all(&state, &limits, &mut tracker).await;
+    }
+}
+
+async fn sample_all(state: &SessionRegistry, limits: &ResourceLimits, tracker: &mut Tracker) {
+    let mut targets = vec![Target {
+        session_id: None,
+        provider: None,
+        pid: std::process::id(),
+    }];
+    targets.extend(
+        state
+            .connector_pids()
+            .into_iter()
+            .map(|(session_id, pid)| Target {
+                session_id: Some(session_id),
+                provider: Some(Provider::Claude),
+                pid,
+            }),
+    );
+
+    let Ok(samples) = tokio::task::spawn_blocking(move || {
+        targets
+            .into_iter()
+            .map(|target| {
+                let sample = sample_process(target.pid);
+                (target, sample)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    else {
+        return;
+    };
+
+    let now = Instant::now();
+    let sampled_at = chrono_now();
+    let mut stats = Vec::with_capacity(samples.len());
+    let mut runaways = Vec::new();
+
+    for (target, sample) in samples {
+        let Some(sample) = sample else {
+            continue;
+        };
+        let cpu_percent = cpu_percent(tracker.previous.get(&target.pid).copied(), now, sample);
+        tracker
+            .previous
+            .insert(target.pid, (now, sample.cpu_seconds));
+
+        if let Some(session_id) = target.session_id.as_ref() {
+            let strikes = tracker.cpu_strikes.entry(target.pid).or_insert(0);
+            if let Some(reason) = assess(limits, sample.rss_bytes, cpu_percent, strikes) {
+                *strikes = 0;
+                runaways.push((session_id.clone(), target.pid, reason));
+            }
+        }
+
+        stats.push(ConnectorProcessStats {
+            session_id: target.session_id,
+            provider: target.provider,
+            pid: target.pid,
+            cpu_percent,
+            rss_bytes: sample.rss_bytes,
+            sampled_at: sampled_at.clone(),
+        });
+    }
+
+    tracker
+        .previous
+        .retain(|pid, _| stats.iter().any(|sample| sample.pid == *pid));
+    tracker
+        .cpu_strikes
+        .retain(|pid, _| stats.iter().any(|sample| sample.pid == *pid));
+    state.set_process_stats(stats);
+
+    for (session_id, pid, reason) in runaways {
+        handle_runaway(state, &session_id, pid, &reason, limits.auto_restart).await;
+    }
+}
+
+async fn handle_runaway(
+    state: &SessionRegistry,
+    session_id: &str,
+    pid: u32,
+    reason: &str,
+    auto_restart: bool,
+) {
+    warn!(
+        component = "resource_monitor",
+        event = "connector.resource.runaway",
+        session_id = %session_id,
+        pid = pid,
+        reason = %reason,
+        auto_restart = auto_restart,
+        "Connector process exceeded resource limits"
+    );
+
+    let action = if auto_restart {
+        "restarting it"
+    } else {
+        "consider ending the session"
+    };
+    if let Some(actor) = state.get_session(session_id) {
+        actor
+            .send(SessionCommand::Broadcast {
+                msg: ServerMessage::Error {
+                    code: "connector_runaway".into(),
+                    message: format!("Claude CLI (pid {pid}) is {reason}; {action}"),
+                    session_id: Some(session_id.to_string()),
+                    error: None,
+                },
+            })
+            .await;
+    }
+
+    if auto_restart {
+        if let Some(tx) = state.get_claude_action_tx(session_id) {
+            let _ = tx
+                .send(ClaudeAction::RestartProcess {
+                    reason: reason.to_string(),
+                })
+                .await;
+        }
+    }
+}
+
+/// Check one sample against the limits. Returns why the process is runaway.
+///
+/// Memory trips immediately; CPU has to stay above the limit for
+/// `CPU_STRIKES` consecutive samples so ordinary busy turns don't count.
+fn assess(
+    limits: &ResourceLimits,
+    rss_bytes: u64,
+    cpu_percent: f64,
+    strikes: &mut u32,
+) -> Option<String> {
+    if rss_bytes > limits.max_rss_bytes {
+        return Some(format!(
+            "using {} MB of memory (limit {} MB)",
+            rss_bytes / (1024 * 1024),
+            limits.max_rss_bytes / (1024 * 1024)
+        ));
+    }
+    if cpu_percent >= limits.max_cpu_percent {
+        *strikes += 1;
+    } else {
+        *strikes = 0;
+    }
+    if *strikes >= CPU_STRIKES {
+        let minutes = (SAMPLE_INTERVAL.as_secs() * u64::from(CPU_STRIKES)) / 60;
+        return Some(format!(
+            "above {:.0}% CPU for {minutes} minutes",
+            limits.max_cpu_percent
+        ));
+    }
+    None
+}
+
+fn cpu_percent(previous: Option<(Instant, f64)>, now: Instant, sample: ProcessSample) -> f64 {
+    let Some((at, cpu_seconds)) = previous else {
+        return 0.0;
+    };
+    let elapsed = now.saturating_duration_since(at).as_secs_f64();
+    if elapsed <= 0.0 {
+        return 0.0;
+    }
+    ((sample.cpu_seconds - cpu_seconds).max(0.0) / elapsed) * 100.0
+}
+
+/// Resident memory of `pid` in bytes, via `ps`.
+pub(crate) fn resident_memory(pid: u32) -> Option<u64> {
+    sample_process(pid).map(|sample| sample.rss_bytes)
+}
+
+/// Cumulative CPU time and resident memory for `pid`, via `ps`.
+fn sample_process(pid: u32) -> Option<ProcessSample> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "rss=,time=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_ps_line(String::from_utf8_lossy(&output.stdout).trim())
+}
+
+/// Parse `ps -o rss=,time=` output: RSS in KiB, then CPU time.
+fn parse_ps_line(line: &str) -> Option<ProcessSample> {
+    let mut fields = line.split_whitespace();
+    let rss_kib: u64 = fields.next()?.parse().ok()?;
+    let cpu_seconds = parse_cpu_time(fields.next()?)?;
+    Some(ProcessSample {
+        cpu_seconds,
+        rss_bytes: rss_kib * 1024,
+    })
+}
+
+/// `[[dd-]hh:]mm:ss[.cc]` — Linux prints `00:01:05`, macOS `1:05.32`.
+fn parse_cpu_time(value: &str) -> Option<f64> {
+    let (days, clock) = match value.split_once('-') {
+        Some((days, clock)) => (days.parse::<f64>().ok()?, clock),
+        None => (0.0, value),
+    };
+    let mut seconds = 0.0;
+    for part in clock.split(':') {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(days * 86_400.0 + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> ResourceLimits {
+        ResourceLimits {
+            max_rss_bytes: 4096 * 1024 * 1024,
+            max_cpu_percent: 90.0,
+            auto_restart: true,
+        }
+    }
+
+    #[test]
+    fn parses_linux_and_macos_ps_output() {
+        assert_eq!(
+            parse_ps_line("  524288 00:01:05"),
+            Some(ProcessSample {
+                cpu_seconds: 65.0,
+                rss_bytes: 512 * 1024 * 1024,
+            })
+        );
+        assert_eq!(parse_cpu_time("1:05.50"), Some(65.5));
+        assert_eq!(
+            parse_cpu_time("2-03:00:00"),
+            Some(2.0 * 86_400.0 + 3.0 * 3_600.0)
+        );
+        assert_eq!(parse_ps_line(""), None);
+        assert_eq!(parse_ps_line("abc 00:00:01"), None);
+    }
+
+    #[test]
+    fn cpu_percent_is_relative_to_the_previous_sample() {
+        let start = Instant::now();
+        let sample = ProcessSample {
+            cpu_seconds: 27.0,
+            rss_bytes: 0,
+        };
+        assert_eq!(cpu_percent(None, start, sample), 0.0);
+        let later = start + Duration::from_secs(10);
+        assert_eq!(cpu_percent(Some((start, 12.0)), later, sample), 150.0);
+    }
+
+    #[test]
+    fn memory_trips_immediately_but_cpu_needs_sustained_load() {
+        let limits = limits();
+        let mut strikes = 0;
+
+        let reason = assess(&limits, 12 * 1024 * 1024 * 1024, 5.0, &mut strikes);
+        assert_eq!(
+            reason.as_deref(),
+            Some("using 12288 MB of memory (limit 4096 MB)")
+        );
+
+        for _ in 1..CPU_STRIKES {
+            assert!(assess(&limits, 0, 99.0, &mut strikes).is_none());
+        }
+        assert!(assess(&limits, 0, 99.0, &mut strikes).is_some());
+
+        strikes = CPU_STRIKES - 1;
+        assert!(assess(&limits, 0, 10.0, &mut strikes).is_none());
+        assert_eq!(strikes, 0);
+    }
+}
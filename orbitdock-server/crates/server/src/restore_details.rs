@@ -0,0 +1,61 @@
+//! Restores turn history for sessions registered at startup.
+//!
+//! `load_sessions_for_startup` reads only what the session list needs so the
+//! server can start listening straight away. This loads the rest — turn diffs,
+//! turn metrics and transcript summaries — in pages of sessions, several pages
+//! at a time, and hands each session its part. A session that is used before
+//! its page arrives loads its own history first, and ignores the page later.
+
+use std::sync::Arc;
+
+use futures::StreamExt;
+use tracing::{info, warn};
+
+use crate::persistence::load_session_details;
+use crate::session_command::SessionCommand;
+use crate::state::SessionRegistry;
+
+/// Sessions loaded per database read.
+const PAGE_SIZE: usize = 50;
+/// Pages loaded at once.
+const CONCURRENT_PAGES: usize = 4;
+
+pub async fn hydrate_restored_sessions(state: Arc<SessionRegistry>, session_ids: Vec<String>) {
+    let session_count = session_ids.len();
+    let pages: Vec<Vec<String>> = session_ids
+        .chunks(PAGE_SIZE)
+        .map(<[String]>::to_vec)
+        .collect();
+
+    futures::stream::iter(pages)
+        .for_each_concurrent(CONCURRENT_PAGES, |page| {
+            let state = state.clone();
+            async move {
+                let details = match load_session_details(page).await {
+                    Ok(details) => details,
+                    Err(e) => {
+                        warn!(
+                            component = "restore",
+                            event = "restore.details.load_failed",
+                            error = %e,
+                            "Failed to load restored session turn history"
+                        );
+                        return;
+                    }
+                };
+                for details in details {
+                    if let Some(actor) = state.get_session(&details.id) {
+                        actor.send(SessionCommand::RestoreDetails { details }).await;
+                    }
+                }
+            }
+        })
+        .await;
+
+    info!(
+        component = "restore",
+        event = "restore.details.complete",
+        session_count,
+        "Restored session turn history"
+    );
+}
@@ -0,0 +1,162 @@
+//! Living summaries of active sessions.
+//!
+//! The first-prompt name stops describing a session once the work moves on.
+//! Every `[naming] summary_every_turns` completed turns (5 by default) the
+//! session queues its recent turns here, and one background task asks the
+//! naming provider to fold them into the previous summary, 2-3 sentences on
+//! what the session is about and where it stands. The result replaces
+//! `summary`, is persisted, and reaches list subscribers as a `SessionDelta`.
+//! Requests are handled one at a time; when the queue is full they are
+//! dropped and the next interval catches up.
+
+use std::sync::{Arc, OnceLock};
+
+use orbitdock_protocol::StateChanges;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::ai_naming::{self, Task};
+use crate::persistence::PersistCommand;
+use crate::session_command::SessionCommand;
+use crate::state::SessionRegistry;
+
+const DEFAULT_EVERY_TURNS: u32 = 5;
+const QUEUE_CAPACITY: usize = 64;
+const SUMMARY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+const MAX_SUMMARY_CHARS: usize = 600;
+
+static REQUESTS: OnceLock<mpsc::Sender<SummaryRequest>> = OnceLock::new();
+
+#[derive(Debug)]
+pub struct SummaryRequest {
+    pub session_id: String,
+    /// The summary being refreshed, if the session has one.
+    pub previous: Option<String>,
+    /// The turns since the last refresh, from `turn_summary::recent_transcript`.
+    pub transcript: String,
+}
+
+/// How many turns a refresh covers, when `turn_count` is due for one.
+pub fn due(turn_count: u64) -> Option<usize> {
+    let every = crate::config_file::current()
+        .naming
+        .summary_every_turns
+        .unwrap_or(DEFAULT_EVERY_TURNS);
+    (every > 0 && turn_count > 0 && turn_count.is_multiple_of(u64::from(every)))
+        .then_some(every as usize)
+}
+
+/// Queue a refresh. A no-op until the summarizer is running.
+pub fn request(request: SummaryRequest) {
+    let Some(tx) = REQUESTS.get() else {
+        return;
+    };
+    if tx.try_send(request).is_err() {
+        warn!(
+            component = "rolling_summary",
+            event = "rolling_summary.dropped",
+            "Session summary queue is full; dropping request"
+        );
+    }
+}
+
+/// The provider input: the current summary, then the recent turns.
+fn prompt(request: &SummaryRequest) -> String {
+    match request.previous.as_deref() {
+        Some(previous) => format!(
+            "Current summary: {previous}\n\nRecent turns:\n{}",
+            request.transcript
+        ),
+        None => format!("Recent turns:\n{}", request.transcript),
+    }
+}
+
+pub async fn start_summarizer(state: Arc<SessionRegistry>) {
+    let (tx, mut rx) = mpsc::channel(QUEUE_CAPACITY);
+    if REQUESTS.set(tx).is_err() {
+        return;
+    }
+
+    while let Some(request) = rx.recv().await {
+        let Some(provider) = ai_naming::provider() else {
+            continue;
+        };
+        let result = tokio::time::timeout(
+            SUMMARY_TIMEOUT,
+            provider.generate(Task::SessionSummary, &prompt(&request)),
+        )
+        .await
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out")));
+
+        let summary = match result {
+            Ok(Some(summary)) => {
+                crate::chat_notifications::truncate(summary.trim(), MAX_SUMMARY_CHARS)
+            }
+            Ok(None) => continue,
+            Err(e) => {
+                warn!(
+                    component = "rolling_summary",
+                    event = "rolling_summary.failed",
+                    session_id = %request.session_id,
+                    provider = provider.label(),
+                    error = %e,
+                    "Failed to refresh session summary"
+                );
+                continue;
+            }
+        };
+        let Some(actor) = state.get_session(&request.session_id) else {
+            continue;
+        };
+        info!(
+            component = "rolling_summary",
+            event = "rolling_summary.updated",
+            session_id = %request.session_id,
+            summary = %summary,
+        );
+        actor
+            .send(SessionCommand::ApplyDelta {
+                changes: StateChanges {
+                    summary: Some(Some(summary.clone())),
+                    ..Default::default()
+                },
+                persist_op: None,
+            })
+            .await;
+        let _ = state
+            .persist()
+            .send(PersistCommand::SetSummary {
+                session_id: request.session_id,
+                summary,
+            })
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_builds_on_the_previous_summary() {
+        let mut request = SummaryRequest {
+            session_id: "sess-1".into(),
+            previous: None,
+            transcript: "User: add retries\nAssistant: Added retries.".into(),
+        };
+        assert_eq!(
+            prompt(&request),
+            "Recent turns:\nUser: add retries\nAssistant: Added retries."
+        );
+        request.previous = Some("Hardening the webhook client.".into());
+        assert!(prompt(&request).starts_with("Current summary: Hardening the webhook client.\n\n"));
+    }
+
+    #[test]
+    fn refreshes_every_fifth_turn_by_default() {
+        assert_eq!(due(0), None);
+        assert_eq!(due(4), None);
+        assert_eq!(due(5), Some(5));
+        assert_eq!(due(10), Some(5));
+    }
+}
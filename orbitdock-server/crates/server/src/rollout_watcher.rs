@@ -40,15 +40,19 @@ pub async fn start_rollout_watcher(
         return Ok(());
     }
 
-    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
-    let sessions_dir = PathBuf::from(&home).join(".codex/sessions");
-    if !sessions_dir.exists() {
+    let (sessions_dirs, missing): (Vec<PathBuf>, Vec<PathBuf>) =
+        crate::provider_homes::codex_session_dirs()
+            .into_iter()
+            .partition(|dir| dir.exists());
+    for dir in &missing {
         info!(
             component = "rollout_watcher",
             event = "rollout_watcher.sessions_dir_missing",
-            path = %sessions_dir.display(),
+            path = %dir.display(),
             "Rollout sessions directory missing"
         );
+    }
+    if sessions_dirs.is_empty() {
         return Ok(());
     }
 
@@ -80,14 +84,16 @@ pub async fn start_rollout_watcher(
         notify::Config::default(),
     )?;
 
-    watcher.watch(&sessions_dir, RecursiveMode::Recursive)?;
-
-    info!(
-        component = "rollout_watcher",
-        event = "rollout_watcher.started",
-        path = %sessions_dir.display(),
-        "Rollout watcher started"
-    );
+    for dir in &sessions_dirs {
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+        info!(
+            component = "rollout_watcher",
+            event = "rollout_watcher.started",
+            path = %dir.display(),
+            "Rollout watcher started"
+        );
+    }
+    app_state.set_rollout_watcher_running(true);
 
     let processor = RolloutFileProcessor::new(state_path, persisted_state);
 
@@ -101,7 +107,10 @@ pub async fn start_rollout_watcher(
     };
 
     // Prime watcher from existing files on startup
-    let existing_files = collect_jsonl_files(&sessions_dir);
+    let existing_files: Vec<PathBuf> = sessions_dirs
+        .iter()
+        .flat_map(|dir| collect_jsonl_files(dir))
+        .collect();
     let mut seeded = 0usize;
     for path in &existing_files {
         if let Ok(metadata) = std::fs::metadata(path) {
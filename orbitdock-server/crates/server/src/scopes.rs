@@ -0,0 +1,183 @@
+//! Token scopes: what a credential may do once it is authenticated.
+//!
+//! Scopes are ordered — `read` < `approve` < `admin` — and each grants
+//! everything below it:
+//!
+//! - `read` subscribes to sessions and lists state, but changes nothing.
+//! - `approve` can also decide approvals and answer questions.
+//! - `admin` can do anything, including sending messages, running shell
+//!   commands, and changing server settings.
+//!
+//! Anything not classified here needs `admin`, so new client messages and
+//! routes are locked down until someone decides otherwise.
+
+use axum::http::Method;
+use orbitdock_protocol::ClientMessage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TokenScope {
+    Read,
+    Approve,
+    Admin,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 3] = [TokenScope::Read, TokenScope::Approve, TokenScope::Admin];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TokenScope::Read => "read",
+            TokenScope::Approve => "approve",
+            TokenScope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == value.trim())
+    }
+
+    pub fn allows(self, required: TokenScope) -> bool {
+        self >= required
+    }
+}
+
+/// The scope a WebSocket client message needs.
+pub fn required_scope(msg: &ClientMessage) -> TokenScope {
+    match msg {
+        ClientMessage::SubscribeList
+        | ClientMessage::SubscribeSession { .. }
+        | ClientMessage::UnsubscribeSession { .. }
+        | ClientMessage::GetSessionSharing { .. }
+        | ClientMessage::ListApprovals { .. }
+        | ClientMessage::GetAuditLog { .. }
+        | ClientMessage::GetUsageAnalytics { .. }
+        | ClientMessage::SemanticSearch { .. }
+        | ClientMessage::GetLogLevel
+        | ClientMessage::GetConnectorStats
+        | ClientMessage::SetClientPrimaryClaim { .. }
+        | ClientMessage::RegisterPushDevice { .. }
+        | ClientMessage::UnregisterPushDevice { .. }
+        | ClientMessage::ListModels
+        | ClientMessage::ListClaudeModels
+        | ClientMessage::ListEffortLevels { .. }
+        | ClientMessage::GetProviderInfo { .. }
+        | ClientMessage::CodexAccountRead { .. }
+        | ClientMessage::ListSkills { .. }
+        | ClientMessage::ListRemoteSkills { .. }
+        | ClientMessage::ListMcpTools { .. }
+        | ClientMessage::ListSessionMcpServers { .. }
+        | ClientMessage::CheckOpenAiKey { .. }
+        | ClientMessage::FetchCodexUsage { .. }
+        | ClientMessage::FetchClaudeUsage { .. }
+        | ClientMessage::ListReviewComments { .. }
+        | ClientMessage::GetFullMessage { .. }
+        | ClientMessage::GetSubagentTools { .. }
+        | ClientMessage::ListJobs { .. }
+        | ClientMessage::GetJobOutput { .. }
+        | ClientMessage::ListRecentProjects { .. }
+        | ClientMessage::ListEditors { .. }
+        | ClientMessage::GetAttentionItems { .. }
+        | ClientMessage::ReplaySession { .. }
+        | ClientMessage::GetTurns { .. }
+        | ClientMessage::GetSessionEvents { .. }
+        | ClientMessage::ListWorktrees { .. } => TokenScope::Read,
+
+        ClientMessage::ApproveTool { .. }
+        | ClientMessage::AnswerQuestion { .. }
+        | ClientMessage::AcknowledgeAttention { .. }
+        | ClientMessage::SnoozeAttention { .. } => TokenScope::Approve,
+
+        _ => TokenScope::Admin,
+    }
+}
+
+/// The scope an HTTP request needs: reads for `GET`, admin for writes.
+///
+/// `/ws` upgrades with `read`; each message is then checked on its own.
+pub fn required_scope_for_request(method: &Method, path: &str) -> TokenScope {
+    // Server settings are admin-only, even to list.
+    if path == "/api/server/config" || path.starts_with("/api/server/config/") {
+        return TokenScope::Admin;
+    }
+    if *method == Method::GET || *method == Method::HEAD {
+        return TokenScope::Read;
+    }
+    // Read state is per-client bookkeeping, not a change to the session.
+    if *method == Method::POST && path.starts_with("/api/sessions/") && path.ends_with("/mark-read")
+    {
+        return TokenScope::Read;
+    }
+    TokenScope::Admin
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_are_ordered() {
+        assert!(TokenScope::Admin.allows(TokenScope::Approve));
+        assert!(TokenScope::Approve.allows(TokenScope::Read));
+        assert!(!TokenScope::Approve.allows(TokenScope::Admin));
+        assert!(!TokenScope::Read.allows(TokenScope::Approve));
+        assert_eq!(TokenScope::parse(" approve "), Some(TokenScope::Approve));
+        assert_eq!(TokenScope::parse("write"), None);
+    }
+
+    #[test]
+    fn client_messages_map_to_scopes() {
+        assert_eq!(
+            required_scope(&ClientMessage::SubscribeList),
+            TokenScope::Read
+        );
+        assert_eq!(
+            required_scope(&ClientMessage::AnswerQuestion {
+                session_id: "s".into(),
+                request_id: "r".into(),
+                answer: "yes".into(),
+                question_id: None,
+                answers: None,
+            }),
+            TokenScope::Approve
+        );
+        assert_eq!(
+            required_scope(&ClientMessage::SnoozeAttention {
+                item_id: "i".into(),
+                minutes: 30,
+            }),
+            TokenScope::Approve
+        );
+        assert_eq!(
+            required_scope(&ClientMessage::InterruptSession {
+                session_id: "s".into()
+            }),
+            TokenScope::Admin
+        );
+    }
+
+    #[test]
+    fn http_requests_map_to_scopes() {
+        assert_eq!(
+            required_scope_for_request(&Method::GET, "/api/sessions"),
+            TokenScope::Read
+        );
+        assert_eq!(
+            required_scope_for_request(&Method::POST, "/api/sessions/s1/mark-read"),
+            TokenScope::Read
+        );
+        assert_eq!(
+            required_scope_for_request(&Method::POST, "/api/hook"),
+            TokenScope::Admin
+        );
+        assert_eq!(
+            required_scope_for_request(&Method::DELETE, "/api/approvals/a1"),
+            TokenScope::Admin
+        );
+        assert_eq!(
+            required_scope_for_request(&Method::GET, "/api/server/config"),
+            TokenScope::Admin
+        );
+    }
+}
@@ -0,0 +1,505 @@
+//! Semantic search over sessions.
+//!
+//! With an `[embeddings]` provider configured (OpenAI, or an OpenAI-compatible
+//! local endpoint such as Ollama), a background task embeds user and
+//! assistant messages and session summaries as they appear. Vectors live in
+//! `embedding_vectors`, a sqlite-vec table created on first use and sized to
+//! the model; `embedding_items` maps each vector back to its session and
+//! message. Changing the model drops the index and rebuilds it.
+//!
+//! `SemanticSearch` embeds the query with the same model and returns the
+//! nearest messages and summaries by cosine distance.
+
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+use std::time::Duration;
+
+use orbitdock_protocol::SemanticSearchHit;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::auth::AuthIdentity;
+use crate::state::SessionRegistry;
+use crate::stored_text::StoredText;
+
+const OPENAI_MODEL: &str = "text-embedding-3-small";
+const INDEX_INTERVAL: Duration = Duration::from_secs(30);
+/// Texts per embeddings request.
+const BATCH_SIZE: usize = 64;
+const MAX_INPUT_CHARS: usize = 8000;
+const MAX_SNIPPET_CHARS: usize = 300;
+pub const DEFAULT_LIMIT: u32 = 10;
+pub const MAX_LIMIT: u32 = 50;
+
+static REGISTER: Once = Once::new();
+
+/// `[embeddings]` in the config file.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingsSection {
+    /// Unset turns indexing and search off.
+    pub provider: Option<EmbeddingProvider>,
+    /// Overrides `text-embedding-3-small`; required for `openai_compatible`.
+    pub model: Option<String>,
+    /// `openai_compatible` only, e.g. `http://localhost:11434/v1`.
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum EmbeddingProvider {
+    #[serde(rename = "openai")]
+    OpenAi,
+    #[serde(rename = "openai_compatible")]
+    OpenAiCompatible,
+}
+
+impl EmbeddingsSection {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.provider == Some(EmbeddingProvider::OpenAiCompatible) {
+            if self.base_url.is_none() {
+                problems.push("embeddings.base_url: required for openai_compatible".into());
+            }
+            if self.model.is_none() {
+                problems.push("embeddings.model: required for openai_compatible".into());
+            }
+        } else if self.base_url.is_some() {
+            problems.push("embeddings.base_url: only applies to openai_compatible".into());
+        }
+        problems
+    }
+
+    /// The configured embeddings client, if any.
+    fn embedder(&self) -> Option<Embedder> {
+        match self.provider? {
+            EmbeddingProvider::OpenAi => Some(Embedder {
+                url: "https://api.openai.com/v1/embeddings".into(),
+                api_key: Some(crate::ai_naming::resolve_api_key()?),
+                model: self.model.clone().unwrap_or_else(|| OPENAI_MODEL.into()),
+            }),
+            EmbeddingProvider::OpenAiCompatible => Some(Embedder {
+                url: format!(
+                    "{}/embeddings",
+                    self.base_url.as_deref()?.trim_end_matches('/')
+                ),
+                // Local servers usually ignore the key; hosted ones need it.
+                api_key: crate::ai_naming::resolve_api_key(),
+                model: self.model.clone()?,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SearchError {
+    #[error("semantic search needs an [embeddings] provider in config.toml")]
+    Unavailable,
+    #[error("{0}")]
+    Failed(#[from] anyhow::Error),
+}
+
+impl SearchError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SearchError::Unavailable => "semantic_search_unavailable",
+            SearchError::Failed(_) => "semantic_search_failed",
+        }
+    }
+}
+
+/// Make sqlite-vec available to every connection opened after this call.
+pub fn register_extension() {
+    REGISTER.call_once(|| unsafe {
+        // sqlite-vec exports its entry point without a signature.
+        rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute::<
+            *const (),
+            unsafe extern "C" fn(
+                *mut rusqlite::ffi::sqlite3,
+                *mut *mut std::os::raw::c_char,
+                *const rusqlite::ffi::sqlite3_api_routines,
+            ) -> std::os::raw::c_int,
+        >(
+            sqlite_vec::sqlite3_vec_init as *const ()
+        )));
+    });
+}
+
+/// OpenAI-style `/embeddings` endpoint.
+struct Embedder {
+    url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl Embedder {
+    async fn embed(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let mut request = reqwest::Client::new()
+            .post(&self.url)
+            .json(&json!({ "model": self.model, "input": texts }));
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {api_key}"));
+        }
+        let json = crate::ai_naming::post_json(request, "Embeddings").await?;
+
+        #[derive(Deserialize)]
+        struct Embedding {
+            index: usize,
+            embedding: Vec<f32>,
+        }
+        let mut data: Vec<Embedding> = serde_json::from_value(json["data"].clone())?;
+        data.sort_by_key(|embedding| embedding.index);
+        if data.len() != texts.len() {
+            anyhow::bail!("expected {} embeddings, got {}", texts.len(), data.len());
+        }
+        Ok(data
+            .into_iter()
+            .map(|embedding| embedding.embedding)
+            .collect())
+    }
+}
+
+/// Text waiting to be embedded.
+#[derive(Debug, Clone, PartialEq)]
+struct Pending {
+    session_id: String,
+    /// Empty for the session summary.
+    message_id: String,
+    text: String,
+}
+
+fn open(db_path: &Path) -> rusqlite::Result<Connection> {
+    register_extension();
+    let conn = Connection::open(db_path)?;
+    conn.execute_batch(
+        "PRAGMA journal_mode = WAL;
+         PRAGMA busy_timeout = 5000;",
+    )?;
+    Ok(conn)
+}
+
+fn vectors_table_exists(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'embedding_vectors')",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// Up to `limit` summaries and messages not yet embedded with `model`,
+/// dropping an index built with another model first.
+fn pending(conn: &Connection, model: &str, limit: usize) -> rusqlite::Result<Vec<Pending>> {
+    let indexed_model: Option<String> = conn
+        .query_row("SELECT model FROM embedding_items LIMIT 1", [], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    if indexed_model.is_some_and(|indexed| indexed != model) {
+        conn.execute_batch(
+            "DELETE FROM embedding_items;
+             DROP TABLE IF EXISTS embedding_vectors;",
+        )?;
+    }
+
+    let mut pending: Vec<Pending> = conn
+        .prepare(
+            "SELECT s.id, s.summary
+             FROM sessions s
+             LEFT JOIN embedding_items e ON e.session_id = s.id AND e.message_id = ''
+             WHERE TRIM(COALESCE(s.summary, '')) != ''
+               AND e.summary IS NOT s.summary
+             LIMIT ?1",
+        )?
+        .query_map(params![limit as i64], |row| {
+            Ok(Pending {
+                session_id: row.get(0)?,
+                message_id: String::new(),
+                text: row.get(1)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+
+    let remaining = limit.saturating_sub(pending.len());
+    if remaining > 0 {
+        // Newest first, so recent work is searchable soonest.
+        let messages = conn
+            .prepare(
+                "SELECT m.session_id, m.id, m.content
+                 FROM messages m
+                 WHERE m.type IN ('user', 'assistant', 'steer')
+                   AND m.is_in_progress = 0
+                   AND TRIM(COALESCE(m.content, '')) != ''
+                   AND NOT EXISTS (
+                       SELECT 1 FROM embedding_items e
+                       WHERE e.session_id = m.session_id AND e.message_id = m.id
+                   )
+                 ORDER BY m.rowid DESC
+                 LIMIT ?1",
+            )?
+            .query_map(params![remaining as i64], |row| {
+                Ok(Pending {
+                    session_id: row.get(0)?,
+                    message_id: row.get(1)?,
+                    text: row.get::<_, StoredText>(2)?.into_string(),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        pending.extend(messages);
+    }
+
+    for item in &mut pending {
+        if let Some((end, _)) = item.text.char_indices().nth(MAX_INPUT_CHARS) {
+            item.text.truncate(end);
+        }
+    }
+    Ok(pending)
+}
+
+fn store(
+    conn: &mut Connection,
+    model: &str,
+    items: &[Pending],
+    vectors: &[Vec<f32>],
+) -> rusqlite::Result<()> {
+    let Some(dimensions) = vectors.first().map(Vec::len) else {
+        return Ok(());
+    };
+    conn.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS embedding_vectors
+         USING vec0(embedding float[{dimensions}] distance_metric=cosine)"
+    ))?;
+
+    let tx = conn.transaction()?;
+    for (item, vector) in items.iter().zip(vectors) {
+        let summary = item.message_id.is_empty().then_some(item.text.as_str());
+        let id: i64 = tx.query_row(
+            "INSERT INTO embedding_items (session_id, message_id, summary, model)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(session_id, message_id)
+             DO UPDATE SET summary = excluded.summary, model = excluded.model
+             RETURNING id",
+            params![item.session_id, item.message_id, summary, model],
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "DELETE FROM embedding_vectors WHERE rowid = ?1",
+            params![id],
+        )?;
+        tx.execute(
+            "INSERT INTO embedding_vectors (rowid, embedding) VALUES (?1, ?2)",
+            params![id, vector_blob(vector)],
+        )?;
+    }
+    tx.commit()
+}
+
+/// sqlite-vec's compact vector format: little-endian f32s.
+fn vector_blob(vector: &[f32]) -> Vec<u8> {
+    vector
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Embed one batch of pending text. Returns how many items were indexed.
+async fn index_batch(db_path: &Path, embedder: &Embedder) -> anyhow::Result<usize> {
+    let path = db_path.to_path_buf();
+    let model = embedder.model.clone();
+    let items =
+        tokio::task::spawn_blocking(move || pending(&open(&path)?, &model, BATCH_SIZE)).await??;
+    if items.is_empty() {
+        return Ok(0);
+    }
+
+    let texts: Vec<String> = items.iter().map(|item| item.text.clone()).collect();
+    let vectors = embedder.embed(&texts).await?;
+
+    let path = db_path.to_path_buf();
+    let model = embedder.model.clone();
+    let count = items.len();
+    tokio::task::spawn_blocking(move || store(&mut open(&path)?, &model, &items, &vectors))
+        .await??;
+    Ok(count)
+}
+
+/// Keep the index current while a provider is configured.
+pub async fn start_indexer(db_path: PathBuf) {
+    let mut interval = tokio::time::interval(INDEX_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(embedder) = crate::config_file::current().embeddings.embedder() else {
+            continue;
+        };
+        // Drain the backlog a batch at a time, then wait for new messages.
+        loop {
+            match index_batch(&db_path, &embedder).await {
+                Ok(0) => break,
+                Ok(count) => info!(
+                    component = "semantic_search",
+                    event = "semantic_search.indexed",
+                    count,
+                    model = %embedder.model,
+                ),
+                Err(e) => {
+                    warn!(
+                        component = "semantic_search",
+                        event = "semantic_search.index_failed",
+                        error = %e,
+                        "Failed to index messages for semantic search"
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn nearest(
+    conn: &Connection,
+    query: &[f32],
+    limit: usize,
+) -> rusqlite::Result<Vec<SemanticSearchHit>> {
+    if !vectors_table_exists(conn)? {
+        return Ok(Vec::new());
+    }
+    conn.prepare(
+        "SELECT e.session_id,
+                NULLIF(e.message_id, ''),
+                COALESCE(s.custom_name, s.summary, s.first_prompt),
+                s.project_path,
+                COALESCE(m.content, e.summary, ''),
+                v.distance
+         FROM (
+             SELECT rowid, distance FROM embedding_vectors
+             WHERE embedding MATCH ?1 AND k = ?2
+         ) v
+         JOIN embedding_items e ON e.id = v.rowid
+         JOIN sessions s ON s.id = e.session_id
+         LEFT JOIN messages m ON e.message_id != '' AND m.id = e.message_id
+         ORDER BY v.distance",
+    )?
+    .query_map(params![vector_blob(query), limit as i64], |row| {
+        let snippet = row.get::<_, StoredText>(4)?.into_string();
+        Ok(SemanticSearchHit {
+            session_id: row.get(0)?,
+            message_id: row.get(1)?,
+            session_name: row.get(2)?,
+            project_path: row.get(3)?,
+            snippet: crate::chat_notifications::truncate(snippet.trim(), MAX_SNIPPET_CHARS),
+            distance: row.get(5)?,
+        })
+    })?
+    .collect()
+}
+
+/// The `limit` messages and summaries closest to `query` in sessions
+/// `identity` can access.
+pub async fn search(
+    state: &SessionRegistry,
+    identity: &AuthIdentity,
+    query: &str,
+    limit: u32,
+) -> Result<Vec<SemanticSearchHit>, SearchError> {
+    let embedder = crate::config_file::current()
+        .embeddings
+        .embedder()
+        .ok_or(SearchError::Unavailable)?;
+    let vector = embedder
+        .embed(&[query.to_string()])
+        .await?
+        .pop()
+        .unwrap_or_default();
+    let limit = limit.clamp(1, MAX_LIMIT) as usize;
+    let path = state.db_path().clone();
+    // Over-fetch so hits in other users' sessions don't starve the results.
+    let candidates =
+        tokio::task::spawn_blocking(move || nearest(&open(&path)?, &vector, limit * 2))
+            .await
+            .map_err(anyhow::Error::from)?
+            .map_err(anyhow::Error::from)?;
+
+    let mut hits = Vec::with_capacity(limit);
+    for hit in candidates {
+        if hits.len() == limit {
+            break;
+        }
+        if state.can_access_session(identity, &hit.session_id).await {
+            hits.push(hit);
+        }
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeded_db() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("orbitdock.db");
+        let mut conn = Connection::open(&db_path).unwrap();
+        crate::migration_runner::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO sessions (id, project_path, summary) VALUES
+                 ('s1', '/work/api', 'Fixing the websocket reconnect loop'),
+                 ('s2', '/work/web', NULL);
+             INSERT INTO messages (id, session_id, type, content, timestamp) VALUES
+                 ('m1', 's1', 'user', 'the socket never reconnects', '0'),
+                 ('m2', 's2', 'assistant', 'Updated the CSS grid', '0'),
+                 ('m3', 's2', 'tool', 'ls', '0'),
+                 ('m4', 's2', 'user', '   ', '0');",
+        )
+        .unwrap();
+        (dir, db_path)
+    }
+
+    #[test]
+    fn indexes_summaries_and_messages_then_finds_the_nearest() {
+        let (_dir, db_path) = seeded_db();
+        let mut conn = open(&db_path).unwrap();
+
+        let items = pending(&conn, "test-model", 10).unwrap();
+        let keys: Vec<(&str, &str)> = items
+            .iter()
+            .map(|item| (item.session_id.as_str(), item.message_id.as_str()))
+            .collect();
+        assert_eq!(keys, vec![("s1", ""), ("s2", "m2"), ("s1", "m1")]);
+
+        let vectors = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![0.9, 0.1, 0.0],
+        ];
+        store(&mut conn, "test-model", &items, &vectors).unwrap();
+        assert!(pending(&conn, "test-model", 10).unwrap().is_empty());
+
+        let hits = nearest(&conn, &[1.0, 0.0, 0.0], 2).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].session_id, "s1");
+        assert_eq!(hits[0].message_id, None);
+        assert_eq!(hits[0].snippet, "Fixing the websocket reconnect loop");
+        assert_eq!(hits[1].message_id.as_deref(), Some("m1"));
+        assert!(hits[0].distance < hits[1].distance);
+
+        // A new summary is re-embedded; a new model rebuilds everything.
+        conn.execute(
+            "UPDATE sessions SET summary = 'Reconnect fixed' WHERE id = 's1'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(pending(&conn, "test-model", 10).unwrap().len(), 1);
+        assert_eq!(pending(&conn, "other-model", 10).unwrap().len(), 3);
+        assert!(nearest(&conn, &[1.0, 0.0, 0.0], 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn openai_compatible_needs_an_endpoint_and_model() {
+        let embeddings = EmbeddingsSection {
+            provider: Some(EmbeddingProvider::OpenAiCompatible),
+            ..Default::default()
+        };
+        assert_eq!(embeddings.problems().len(), 2);
+        assert!(EmbeddingsSection::default().embedder().is_none());
+    }
+}
@@ -0,0 +1,157 @@
+//! Server settings kept in the database `config` table.
+//!
+//! Each key is declared once in [`SETTINGS`]. `GetServerConfig` /
+//! `SetServerConfig` and `/api/server/config` list and change them, so a
+//! settings screen needs no new protocol messages per setting. Values are
+//! encrypted at rest by the persistence writer; secrets are never read back
+//! to clients, only whether they are configured.
+
+use orbitdock_protocol::ServerSetting;
+
+use crate::persistence::PersistCommand;
+use crate::state::SessionRegistry;
+
+pub struct SettingSpec {
+    pub key: &'static str,
+    pub description: &'static str,
+    pub secret: bool,
+    /// `null` clears the stored value.
+    pub clearable: bool,
+    pub choices: &'static [&'static str],
+    /// Environment variable that takes precedence over the stored value.
+    pub env: Option<&'static str>,
+}
+
+pub const SETTINGS: &[SettingSpec] = &[
+    SettingSpec {
+        key: "openai_api_key",
+        description: "OpenAI API key for AI session naming and turn summaries",
+        secret: true,
+        clearable: true,
+        choices: &[],
+        env: Some("OPENAI_API_KEY"),
+    },
+    SettingSpec {
+        key: "anthropic_api_key",
+        description: "Anthropic API key for AI session naming and turn summaries",
+        secret: true,
+        clearable: true,
+        choices: &[],
+        env: Some("ANTHROPIC_API_KEY"),
+    },
+    SettingSpec {
+        key: "server_role",
+        description: "Whether this server is the primary control plane for its clients",
+        secret: false,
+        clearable: false,
+        choices: &["primary", "secondary"],
+        env: None,
+    },
+];
+
+pub fn spec(key: &str) -> Option<&'static SettingSpec> {
+    SETTINGS.iter().find(|spec| spec.key == key)
+}
+
+/// The value to store for `key`, or `None` to clear it.
+pub fn validate(key: &str, value: Option<&str>) -> Result<Option<String>, String> {
+    let spec = spec(key).ok_or_else(|| format!("Unknown setting: {key}"))?;
+    let value = value.map(str::trim).filter(|value| !value.is_empty());
+    let Some(value) = value else {
+        return if spec.clearable {
+            Ok(None)
+        } else {
+            Err(format!("{key} cannot be cleared"))
+        };
+    };
+    if !spec.choices.is_empty() {
+        let value = value.to_ascii_lowercase();
+        if !spec.choices.contains(&value.as_str()) {
+            return Err(format!("{key} must be one of: {}", spec.choices.join(", ")));
+        }
+        return Ok(Some(value));
+    }
+    Ok(Some(value.to_string()))
+}
+
+fn describe(spec: &SettingSpec, stored: Option<String>) -> ServerSetting {
+    let from_env = spec
+        .env
+        .and_then(|name| std::env::var(name).ok())
+        .is_some_and(|value| !value.is_empty());
+    ServerSetting {
+        key: spec.key.to_string(),
+        description: spec.description.to_string(),
+        configured: from_env || stored.is_some(),
+        value: if spec.secret { None } else { stored },
+        secret: spec.secret,
+        choices: spec
+            .choices
+            .iter()
+            .map(|choice| choice.to_string())
+            .collect(),
+    }
+}
+
+/// Every setting with its stored value. Reads the database, so call it off
+/// the async runtime.
+pub fn list() -> Vec<ServerSetting> {
+    SETTINGS
+        .iter()
+        .map(|spec| describe(spec, crate::persistence::load_config_value(spec.key)))
+        .collect()
+}
+
+/// Validate, store, and apply one setting.
+pub async fn set(
+    state: &SessionRegistry,
+    key: &str,
+    value: Option<&str>,
+) -> Result<ServerSetting, String> {
+    let value = validate(key, value)?;
+    let command = match &value {
+        Some(value) => PersistCommand::SetConfig {
+            key: key.to_string(),
+            value: value.clone(),
+        },
+        None => PersistCommand::DeleteConfig {
+            key: key.to_string(),
+        },
+    };
+    let _ = state.persist().send(command).await;
+
+    if key == "server_role" {
+        state.set_primary(value.as_deref() == Some("primary"));
+        state.broadcast_to_list(crate::websocket::server_info_message(state));
+    }
+
+    let spec = spec(key).expect("validated key has a spec");
+    Ok(describe(spec, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_normalizes_and_rejects() {
+        assert_eq!(
+            validate("server_role", Some(" Secondary ")),
+            Ok(Some("secondary".into()))
+        );
+        assert!(validate("server_role", Some("tertiary")).is_err());
+        assert!(validate("server_role", None).is_err());
+        assert_eq!(validate("openai_api_key", Some("  ")), Ok(None));
+        assert!(validate("no_such_key", Some("x")).is_err());
+    }
+
+    #[test]
+    fn secrets_are_never_described_with_a_value() {
+        let setting = describe(spec("openai_api_key").unwrap(), Some("sk-test".into()));
+        assert!(setting.configured);
+        assert_eq!(setting.value, None);
+        let role = describe(spec("server_role").unwrap(), Some("primary".into()));
+        assert_eq!(role.value.as_deref(), Some("primary"));
+        assert_eq!(role.choices, vec!["primary", "secondary"]);
+    }
+}
@@ -1,13 +1,15 @@
 //! Session management
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use arc_swap::ArcSwap;
 use orbitdock_protocol::{
     ApprovalPreview, ApprovalQuestionOption, ApprovalQuestionPrompt, ApprovalRequest, ApprovalType,
-    ClaudeIntegrationMode, CodexIntegrationMode, Message, Provider, SessionState, SessionStatus,
-    SessionSummary, StateChanges, SubagentInfo, TokenUsage, TokenUsageSnapshotKind, TurnDiff,
+    ClaudeIntegrationMode, CodexIntegrationMode, IssueLink, Message, Provider, SessionError,
+    SessionPlan, SessionState, SessionStatus, SessionSummary, SessionViewer, StateChanges,
+    SubagentInfo, TicketLink, TokenUsage, TokenUsageSnapshotKind, TurnDiff, TurnMetrics,
     WorkStatus,
 };
 use tokio::sync::broadcast;
@@ -15,8 +17,10 @@ use tracing::info;
 
 use orbitdock_protocol::ServerMessage;
 
-use crate::session_command::{ConversationBootstrap, ConversationPage};
-use crate::transition::{approval_preview, TransitionState, WorkPhase};
+use crate::session_command::{ConversationBootstrap, ConversationPage, SessionCommand};
+use crate::session_fanout::{SessionFanout, SessionReceiver};
+use crate::transition::{approval_preview, CompactionProgress, Input, TransitionState, WorkPhase};
+use crate::turn_metrics::TurnTimer;
 
 /// Events that matter for the session list sidebar (status, mode, name changes).
 /// Per-message events (streaming deltas, message appends) are excluded to avoid
@@ -29,6 +33,9 @@ fn is_list_relevant(msg: &ServerMessage) -> bool {
             | ServerMessage::SessionDelta { .. }
             | ServerMessage::SessionForked { .. }
             | ServerMessage::SessionSnapshot { .. }
+            | ServerMessage::ContextWarning { .. }
+            | ServerMessage::ConnectorReconnecting { .. }
+            | ServerMessage::ConnectorReconnected { .. }
     )
 }
 
@@ -280,16 +287,25 @@ pub struct SessionSnapshot {
     pub approval_policy: Option<String>,
     pub sandbox_mode: Option<String>,
     pub permission_mode: Option<String>,
+    /// Claude tool allow/deny lists the CLI runs with.
+    pub allowed_tools: Vec<String>,
+    pub disallowed_tools: Vec<String>,
     pub has_pending_approval: bool,
     pub pending_tool_name: Option<String>,
     pub pending_tool_input: Option<String>,
     pub pending_question: Option<String>,
     pub pending_approval_id: Option<String>,
     pub message_count: usize,
+    /// False while the stored messages are left in the database.
+    pub messages_loaded: bool,
+    /// When messages were last read, for unloading idle sessions.
+    pub messages_read_at: Instant,
     pub token_usage: TokenUsage,
     pub token_usage_snapshot_kind: TokenUsageSnapshotKind,
+    pub estimated_cost_usd: Option<f64>,
     pub started_at: Option<String>,
     pub last_activity_at: Option<String>,
+    pub created_by: Option<String>,
     pub revision: u64,
     pub git_branch: Option<String>,
     pub git_sha: Option<String>,
@@ -305,6 +321,8 @@ pub struct SessionSnapshot {
     pub subscriber_count: usize,
     /// Cached count of unread messages.
     pub unread_count: u64,
+    /// Latest error, until the next turn starts.
+    pub last_error: Option<SessionError>,
 }
 
 #[derive(Debug, Clone)]
@@ -315,7 +333,23 @@ struct PendingApprovalEntry {
 }
 
 const EVENT_LOG_CAPACITY: usize = 1000;
-const BROADCAST_CAPACITY: usize = 512;
+/// How long `ApplyDelta` changes after a broadcast delta are held and merged.
+const DELTA_COALESCE_WINDOW: Duration = Duration::from_millis(50);
+
+/// Whether `SteerTurn` can reach the agent: both providers take steering
+/// through their direct connector, and passive sessions have none.
+pub(crate) fn steerable(
+    provider: Provider,
+    status: SessionStatus,
+    codex_integration_mode: Option<CodexIntegrationMode>,
+    claude_integration_mode: Option<ClaudeIntegrationMode>,
+) -> bool {
+    status == SessionStatus::Active
+        && match provider {
+            Provider::Codex => codex_integration_mode == Some(CodexIntegrationMode::Direct),
+            Provider::Claude => claude_integration_mode == Some(ClaudeIntegrationMode::Direct),
+        }
+}
 
 /// Handle to a running session
 pub struct SessionHandle {
@@ -335,16 +369,39 @@ pub struct SessionHandle {
     work_status: WorkStatus,
     last_tool: Option<String>,
     messages: Vec<Message>,
+    /// False while `messages` holds only what arrived since the session was
+    /// restored or unloaded; the rest is in the database.
+    messages_loaded: bool,
+    /// Stored messages not in `messages` while unloaded.
+    unloaded_message_count: u64,
+    /// Sequence after the last unloaded message.
+    unloaded_next_sequence: u64,
+    messages_read_at: Instant,
     token_usage: TokenUsage,
     token_usage_snapshot_kind: TokenUsageSnapshotKind,
+    /// `None` until a priced usage update arrives.
+    estimated_cost_usd: Option<f64>,
+    /// Context warning level last broadcast, in percent.
+    context_warned_at: Option<f64>,
     current_diff: Option<String>,
     current_plan: Option<String>,
+    plan: Option<SessionPlan>,
+    /// Set while the provider compacts the context.
+    compaction: Option<CompactionProgress>,
+    /// Tokens spent by the running turn.
+    turn_usage: TokenUsage,
     current_turn_id: Option<String>,
     turn_count: u64,
     turn_diffs: Vec<TurnDiff>,
+    turn_metrics: Vec<TurnMetrics>,
+    /// False while the turn history restored at startup is still in the
+    /// database; `turn_diffs` and `turn_metrics` hold only newer turns.
+    turn_history_loaded: bool,
+    turn_timer: Option<TurnTimer>,
     started_at: Option<String>,
     last_activity_at: Option<String>,
     forked_from_session_id: Option<String>,
+    created_by: Option<String>,
     git_branch: Option<String>,
     git_sha: Option<String>,
     current_cwd: Option<String>,
@@ -354,8 +411,12 @@ pub struct SessionHandle {
     terminal_session_id: Option<String>,
     terminal_app: Option<String>,
     subagents: Vec<SubagentInfo>,
+    /// Spend already added for each stopped subagent.
+    subagent_costs: HashMap<String, f64>,
     pending_approval: Option<ApprovalRequest>,
     permission_mode: Option<String>,
+    allowed_tools: Vec<String>,
+    disallowed_tools: Vec<String>,
     pending_tool_name: Option<String>,
     pending_tool_input: Option<String>,
     pending_question: Option<String>,
@@ -374,13 +435,29 @@ pub struct SessionHandle {
     worktree_id: Option<String>,
     /// Cached count of unread messages (non-user, non-steer with sequence > last_read).
     unread_count: u64,
-    broadcast_tx: broadcast::Sender<orbitdock_protocol::ServerMessage>,
+    /// Latest error, until the next turn starts.
+    last_error: Option<SessionError>,
+    /// Topic tags, mirrored from `SessionRegistry::session_tags`.
+    tags: Vec<String>,
+    /// GitHub issue the session was started from, mirrored from
+    /// `SessionRegistry::session_issue`.
+    issue: Option<IssueLink>,
+    /// Linked ticket, mirrored from `SessionRegistry::session_ticket`.
+    ticket: Option<TicketLink>,
+    /// Subscribed connections, mirrored from `SessionRegistry::session_viewers`.
+    viewers: Vec<SessionViewer>,
+    /// Per-subscriber queues for session messages
+    fanout: SessionFanout,
     /// Optional sender for list-level broadcasts (dashboard sidebar updates)
     list_tx: Option<broadcast::Sender<orbitdock_protocol::ServerMessage>>,
     /// Monotonic revision counter, incremented on every broadcast
     revision: u64,
     /// Ring buffer of (revision, pre-serialized JSON with revision injected)
     event_log: VecDeque<(u64, String)>,
+    /// `ApplyDelta` changes held until the coalescing window closes
+    pending_delta: Option<StateChanges>,
+    /// End of the current coalescing window
+    delta_window_until: Option<Instant>,
     /// Lock-free snapshot for read-only access from outside the actor
     snapshot_handle: Arc<ArcSwap<SessionSnapshot>>,
 }
@@ -391,7 +468,7 @@ impl SessionHandle {
             .last()
             .and_then(|message| message.sequence)
             .map(|sequence| sequence + 1)
-            .unwrap_or(0)
+            .unwrap_or(self.unloaded_next_sequence)
     }
 
     fn normalize_message_sequences(messages: &mut [Message]) {
@@ -462,7 +539,6 @@ impl SessionHandle {
     /// Create a new session handle
     pub fn new(id: String, provider: Provider, project_path: String) -> Self {
         let now = chrono_now();
-        let (broadcast_tx, _) = broadcast::channel(BROADCAST_CAPACITY);
         let snapshot = SessionSnapshot {
             id: id.clone(),
             provider,
@@ -481,16 +557,22 @@ impl SessionHandle {
             approval_policy: None,
             sandbox_mode: None,
             permission_mode: None,
+            allowed_tools: Vec::new(),
+            disallowed_tools: Vec::new(),
             has_pending_approval: false,
             pending_tool_name: None,
             pending_tool_input: None,
             pending_question: None,
             pending_approval_id: None,
             message_count: 0,
+            messages_loaded: true,
+            messages_read_at: Instant::now(),
             token_usage: TokenUsage::default(),
             token_usage_snapshot_kind: TokenUsageSnapshotKind::Unknown,
+            estimated_cost_usd: None,
             started_at: Some(now.clone()),
             last_activity_at: Some(now.clone()),
+            created_by: None,
             revision: 0,
             git_branch: None,
             git_sha: None,
@@ -504,6 +586,7 @@ impl SessionHandle {
             worktree_id: None,
             subscriber_count: 0,
             unread_count: 0,
+            last_error: None,
         };
         Self {
             id,
@@ -522,16 +605,29 @@ impl SessionHandle {
             work_status: WorkStatus::Waiting,
             last_tool: None,
             messages: Vec::new(),
+            messages_loaded: true,
+            unloaded_message_count: 0,
+            unloaded_next_sequence: 0,
+            messages_read_at: Instant::now(),
             token_usage: TokenUsage::default(),
             token_usage_snapshot_kind: TokenUsageSnapshotKind::Unknown,
+            estimated_cost_usd: None,
+            context_warned_at: None,
             current_diff: None,
             current_plan: None,
+            plan: None,
+            compaction: None,
+            turn_usage: TokenUsage::default(),
             current_turn_id: None,
             turn_count: 0,
             turn_diffs: Vec::new(),
+            turn_metrics: Vec::new(),
+            turn_history_loaded: true,
+            turn_timer: None,
             started_at: Some(now.clone()),
             last_activity_at: Some(now),
             forked_from_session_id: None,
+            created_by: None,
             git_branch: None,
             git_sha: None,
             current_cwd: None,
@@ -541,8 +637,11 @@ impl SessionHandle {
             terminal_session_id: None,
             terminal_app: None,
             subagents: Vec::new(),
+            subagent_costs: HashMap::new(),
             pending_approval: None,
             permission_mode: None,
+            allowed_tools: Vec::new(),
+            disallowed_tools: Vec::new(),
             pending_tool_name: None,
             pending_tool_input: None,
             pending_question: None,
@@ -553,10 +652,17 @@ impl SessionHandle {
             is_worktree: false,
             worktree_id: None,
             unread_count: 0,
-            broadcast_tx,
+            last_error: None,
+            tags: Vec::new(),
+            issue: None,
+            ticket: None,
+            viewers: Vec::new(),
+            fanout: SessionFanout::default(),
             list_tx: None,
             revision: 0,
             event_log: VecDeque::new(),
+            pending_delta: None,
+            delta_window_until: None,
             snapshot_handle: Arc::new(ArcSwap::from_pointee(snapshot)),
         }
     }
@@ -584,7 +690,9 @@ impl SessionHandle {
         messages: Vec<Message>,
         current_diff: Option<String>,
         current_plan: Option<String>,
+        plan: Option<SessionPlan>,
         turn_diffs: Vec<TurnDiff>,
+        turn_metrics: Vec<TurnMetrics>,
         git_branch: Option<String>,
         git_sha: Option<String>,
         current_cwd: Option<String>,
@@ -600,7 +708,6 @@ impl SessionHandle {
         approval_version: u64,
         unread_count: u64,
     ) -> Self {
-        let (broadcast_tx, _) = broadcast::channel(BROADCAST_CAPACITY);
         let snapshot = SessionSnapshot {
             id: id.clone(),
             provider,
@@ -617,6 +724,8 @@ impl SessionHandle {
             approval_policy: approval_policy.clone(),
             sandbox_mode: sandbox_mode.clone(),
             permission_mode: permission_mode.clone(),
+            allowed_tools: Vec::new(),
+            disallowed_tools: Vec::new(),
             has_pending_approval: pending_tool_name.is_some()
                 || pending_question.is_some()
                 || pending_approval_id.is_some(),
@@ -625,10 +734,14 @@ impl SessionHandle {
             pending_question: pending_question.clone(),
             pending_approval_id: pending_approval_id.clone(),
             message_count: messages.len(),
+            messages_loaded: true,
+            messages_read_at: Instant::now(),
             token_usage: token_usage.clone(),
             token_usage_snapshot_kind,
+            estimated_cost_usd: None,
             started_at: started_at.clone(),
             last_activity_at: last_activity_at.clone(),
+            created_by: None,
             revision: 0,
             git_branch: git_branch.clone(),
             git_sha: git_sha.clone(),
@@ -644,6 +757,7 @@ impl SessionHandle {
             worktree_id: None,
             subscriber_count: 0,
             unread_count,
+            last_error: None,
         };
         let mut handle = Self {
             id,
@@ -662,16 +776,27 @@ impl SessionHandle {
             work_status,
             last_tool: None,
             messages,
+            messages_loaded: true,
+            unloaded_message_count: 0,
+            unloaded_next_sequence: 0,
+            messages_read_at: Instant::now(),
             token_usage,
             token_usage_snapshot_kind,
+            estimated_cost_usd: None,
+            context_warned_at: None,
             current_diff,
             current_plan,
+            plan,
             current_turn_id: None,
             turn_count: turn_diffs.len() as u64,
             turn_diffs,
+            turn_metrics,
+            turn_history_loaded: true,
+            turn_timer: None,
             started_at,
             last_activity_at,
             forked_from_session_id: None,
+            created_by: None,
             git_branch,
             git_sha,
             current_cwd,
@@ -681,8 +806,11 @@ impl SessionHandle {
             terminal_session_id,
             terminal_app,
             subagents: Vec::new(),
+            subagent_costs: HashMap::new(),
             pending_approval: None,
             permission_mode,
+            allowed_tools: Vec::new(),
+            disallowed_tools: Vec::new(),
             pending_tool_name,
             pending_tool_input,
             pending_question,
@@ -693,10 +821,17 @@ impl SessionHandle {
             is_worktree: false,
             worktree_id: None,
             unread_count,
-            broadcast_tx,
+            last_error: None,
+            tags: Vec::new(),
+            issue: None,
+            ticket: None,
+            viewers: Vec::new(),
+            fanout: SessionFanout::default(),
             list_tx: None,
             revision: 0,
             event_log: VecDeque::new(),
+            pending_delta: None,
+            delta_window_until: None,
             snapshot_handle: Arc::new(ArcSwap::from_pointee(snapshot)),
         };
         handle.bootstrap_pending_approval_from_persisted_fields();
@@ -724,6 +859,44 @@ impl SessionHandle {
         self.provider
     }
 
+    /// Get current model
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    /// Get the number of turns started in this session
+    pub fn turn_count(&self) -> u64 {
+        self.turn_count
+    }
+
+    /// Feed a transition input to the turn timer before it is applied.
+    ///
+    /// Returns (and records) the finished turn's metrics when the input ends a turn.
+    pub fn observe_turn_timing(&mut self, input: &Input) -> Option<TurnMetrics> {
+        let metrics = crate::turn_metrics::observe_input(
+            &mut self.turn_timer,
+            input,
+            self.current_turn_id.as_deref(),
+        )?;
+        self.turn_metrics.push(metrics.clone());
+        crate::turn_metrics::keep_recent(&mut self.turn_metrics);
+        Some(metrics)
+    }
+
+    /// Whether `SteerTurn` can reach the agent; see [`steerable`].
+    pub fn steerable(&self) -> bool {
+        steerable(
+            self.provider,
+            self.status,
+            self.codex_integration_mode,
+            self.claude_integration_mode,
+        )
+    }
+
+    pub fn has_summary(&self) -> bool {
+        self.summary.is_some()
+    }
+
     /// Get a summary of this session
     pub fn summary(&self) -> SessionSummary {
         SessionSummary {
@@ -739,6 +912,7 @@ impl SessionHandle {
             work_status: self.work_status,
             token_usage: self.token_usage.clone(),
             token_usage_snapshot_kind: self.token_usage_snapshot_kind,
+            estimated_cost_usd: self.estimated_cost_usd,
             has_pending_approval: self.pending_approval.is_some()
                 || self.pending_tool_name.is_some()
                 || self.pending_question.is_some()
@@ -757,6 +931,7 @@ impl SessionHandle {
                 .or_else(|| self.pending_approval.as_ref().map(|a| a.id.clone())),
             started_at: self.started_at.clone(),
             last_activity_at: self.last_activity_at.clone(),
+            created_by: self.created_by.clone(),
             git_branch: self.git_branch.clone(),
             git_sha: self.git_sha.clone(),
             current_cwd: self.current_cwd.clone(),
@@ -768,6 +943,10 @@ impl SessionHandle {
             is_worktree: self.is_worktree,
             worktree_id: self.worktree_id.clone(),
             unread_count: self.unread_count,
+            tags: self.tags.clone(),
+            origin: None,
+            issue: self.issue.clone(),
+            ticket: self.ticket.clone(),
         }
     }
 
@@ -791,6 +970,9 @@ impl SessionHandle {
             newest_sequence: self.messages.last().and_then(|message| message.sequence),
             pending_approval: self.pending_approval.clone(),
             permission_mode: self.permission_mode.clone(),
+            allowed_tools: self.allowed_tools.clone(),
+            disallowed_tools: self.disallowed_tools.clone(),
+            last_error: self.last_error.clone(),
             pending_tool_name: self.pending_tool_name.clone(),
             pending_tool_input: self.pending_tool_input.clone(),
             pending_question: self.pending_question.clone(),
@@ -800,8 +982,12 @@ impl SessionHandle {
                 .or_else(|| self.pending_approval.as_ref().map(|a| a.id.clone())),
             token_usage: self.token_usage.clone(),
             token_usage_snapshot_kind: self.token_usage_snapshot_kind,
+            estimated_cost_usd: self.estimated_cost_usd,
             current_diff: self.current_diff.clone(),
             current_plan: self.current_plan.clone(),
+            plan: self.plan.clone(),
+            steerable: self.steerable(),
+            compact_in_progress: self.compaction.is_some(),
             codex_integration_mode: self.codex_integration_mode,
             claude_integration_mode: self.claude_integration_mode,
             approval_policy: self.approval_policy.clone(),
@@ -809,10 +995,12 @@ impl SessionHandle {
             started_at: self.started_at.clone(),
             last_activity_at: self.last_activity_at.clone(),
             forked_from_session_id: self.forked_from_session_id.clone(),
+            created_by: self.created_by.clone(),
             revision: Some(self.revision),
             current_turn_id: self.current_turn_id.clone(),
             turn_count: self.turn_count,
             turn_diffs: self.turn_diffs.clone(),
+            turn_metrics: self.turn_metrics.clone(),
             git_branch: self.git_branch.clone(),
             git_sha: self.git_sha.clone(),
             current_cwd: self.current_cwd.clone(),
@@ -827,6 +1015,7 @@ impl SessionHandle {
             is_worktree: self.is_worktree,
             worktree_id: self.worktree_id.clone(),
             unread_count: self.unread_count,
+            viewers: self.viewers.clone(),
         }
     }
 
@@ -843,8 +1032,25 @@ impl SessionHandle {
     }
 
     /// Subscribe to session updates
-    pub fn subscribe(&self) -> broadcast::Receiver<orbitdock_protocol::ServerMessage> {
-        self.broadcast_tx.subscribe()
+    pub fn subscribe(&mut self) -> SessionReceiver {
+        self.fanout.subscribe()
+    }
+
+    /// Point subscribers' resync requests at the actor loop now running this
+    /// session (see `session_fanout`).
+    pub fn set_command_tx(&self, command_tx: &tokio::sync::mpsc::Sender<SessionCommand>) {
+        self.fanout.set_command_tx(command_tx);
+    }
+
+    /// Send a snapshot to subscribers that overflowed and have since drained.
+    pub fn resync_subscribers(&mut self) {
+        let state = self.fanout.wants_resync().then(|| self.state());
+        self.fanout.resync(state);
+    }
+
+    /// Number of live subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.fanout.subscriber_count()
     }
 
     /// Set the custom name for this session
@@ -874,6 +1080,70 @@ impl SessionHandle {
         &self.messages
     }
 
+    /// Get per-turn diff snapshots
+    pub fn turn_diffs(&self) -> &[TurnDiff] {
+        &self.turn_diffs
+    }
+
+    /// Leave the session's turn history in the database until
+    /// `restore_turn_history` is given it.
+    pub fn defer_turn_history(&mut self) {
+        self.turn_history_loaded = false;
+    }
+
+    pub fn turn_history_loaded(&self) -> bool {
+        self.turn_history_loaded
+    }
+
+    /// Put deferred turn history ahead of any turns recorded since. A turn in
+    /// both is taken from memory, which may be newer than the database.
+    pub fn restore_turn_history(&mut self, diffs: Vec<TurnDiff>, metrics: Vec<TurnMetrics>) {
+        if self.turn_history_loaded {
+            return;
+        }
+        self.turn_count += diffs.len() as u64;
+
+        let mut turn_diffs = diffs;
+        for diff in std::mem::take(&mut self.turn_diffs) {
+            match turn_diffs
+                .iter_mut()
+                .find(|existing| existing.turn_id == diff.turn_id)
+            {
+                Some(existing) => *existing = diff,
+                None => turn_diffs.push(diff),
+            }
+        }
+        self.turn_diffs = turn_diffs;
+
+        let mut turn_metrics = metrics;
+        for metric in std::mem::take(&mut self.turn_metrics) {
+            match turn_metrics
+                .iter_mut()
+                .find(|existing| existing.turn_id == metric.turn_id)
+            {
+                Some(existing) => *existing = metric,
+                None => turn_metrics.push(metric),
+            }
+        }
+        crate::turn_metrics::keep_recent(&mut turn_metrics);
+        self.turn_metrics = turn_metrics;
+        self.turn_history_loaded = true;
+    }
+
+    /// Attach a summary to the turn diff with `turn_id`, if it is still held.
+    pub fn set_turn_diff_summary(&mut self, turn_id: &str, summary: &str) -> bool {
+        let Some(turn_diff) = self
+            .turn_diffs
+            .iter_mut()
+            .rev()
+            .find(|turn_diff| turn_diff.turn_id == turn_id)
+        else {
+            return false;
+        };
+        turn_diff.summary = Some(summary.to_string());
+        true
+    }
+
     /// Get first prompt
     #[allow(dead_code)]
     pub fn first_prompt(&self) -> Option<&str> {
@@ -906,13 +1176,12 @@ impl SessionHandle {
         self.transcript_path = transcript_path;
     }
 
-    #[allow(dead_code)]
     pub fn transcript_path(&self) -> Option<&str> {
         self.transcript_path.as_deref()
     }
 
     pub fn message_count(&self) -> usize {
-        self.messages.len()
+        self.messages.len() + self.unloaded_message_count as usize
     }
 
     /// Check if a user message with this content already exists (dedup for connector echo)
@@ -938,6 +1207,19 @@ impl SessionHandle {
         self.refresh_snapshot();
     }
 
+    /// Set the latest error, e.g. as restored from the database
+    pub fn set_last_error(&mut self, error: Option<SessionError>) {
+        self.last_error = error;
+        self.refresh_snapshot();
+    }
+
+    /// Set the Claude tool allow/deny lists
+    pub fn set_tool_lists(&mut self, allowed_tools: Vec<String>, disallowed_tools: Vec<String>) {
+        self.allowed_tools = allowed_tools;
+        self.disallowed_tools = disallowed_tools;
+        self.refresh_snapshot();
+    }
+
     /// Set autonomy configuration
     pub fn set_config(&mut self, approval_policy: Option<String>, sandbox_mode: Option<String>) {
         self.approval_policy = approval_policy;
@@ -950,6 +1232,12 @@ impl SessionHandle {
         self.forked_from_session_id = Some(source_session_id);
     }
 
+    /// Set the user who created the session
+    pub fn set_created_by(&mut self, created_by: Option<String>) {
+        self.created_by = created_by;
+        self.refresh_snapshot();
+    }
+
     /// Set terminal session ID and app
     pub fn set_terminal_info(
         &mut self,
@@ -1038,8 +1326,97 @@ impl SessionHandle {
         self.token_usage = usage;
     }
 
+    /// Estimated spend so far, or `None` for an unpriced model.
+    pub fn estimated_cost_usd(&self) -> Option<f64> {
+        self.estimated_cost_usd
+    }
+
+    /// Seed the spend estimate persisted before a restart.
+    pub fn set_estimated_cost_usd(&mut self, cost: Option<f64>) {
+        self.estimated_cost_usd = cost;
+        self.refresh_snapshot();
+    }
+
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags;
+    }
+
+    pub fn set_issue(&mut self, issue: Option<IssueLink>) {
+        self.issue = issue;
+    }
+
+    pub fn set_ticket(&mut self, ticket: Option<TicketLink>) {
+        self.ticket = ticket;
+    }
+
+    /// Replace who is viewing the session, telling subscribers if it changed.
+    pub fn set_viewers(&mut self, viewers: Vec<SessionViewer>) {
+        if self.viewers == viewers {
+            return;
+        }
+        self.viewers = viewers.clone();
+        self.broadcast(orbitdock_protocol::ServerMessage::SessionPresence {
+            session_id: self.id.clone(),
+            viewers,
+        });
+    }
+
+    /// Track context fill from a token snapshot, returning a warning when it
+    /// crosses a new threshold.
+    pub fn observe_context_usage(
+        &mut self,
+        usage: &TokenUsage,
+        kind: TokenUsageSnapshotKind,
+    ) -> Option<crate::context_warnings::ContextWarning> {
+        crate::context_warnings::observe(&mut self.context_warned_at, self.provider, usage, kind)
+    }
+
+    /// Add a stopped subagent's spend to the session and the running turn,
+    /// counting a repeated stop only for what it adds.
+    pub fn record_subagent_cost(&mut self, subagent_id: &str, cost_usd: f64) {
+        let recorded = self
+            .subagent_costs
+            .entry(subagent_id.to_string())
+            .or_insert(0.0);
+        let delta = cost_usd - *recorded;
+        if delta <= 0.0 {
+            return;
+        }
+        *recorded = cost_usd;
+        self.estimated_cost_usd = Some(self.estimated_cost_usd.unwrap_or(0.0) + delta);
+        if let Some(timer) = self.turn_timer.as_mut() {
+            timer.add_cost(delta);
+        }
+        self.refresh_snapshot();
+    }
+
+    /// Add the spend implied by a new token snapshot to the session and the
+    /// running turn.
+    fn accrue_cost(&mut self, usage: &TokenUsage, kind: TokenUsageSnapshotKind) {
+        let Some(model) = self.model.as_deref() else {
+            return;
+        };
+        let provider = match self.provider {
+            Provider::Claude => "claude",
+            Provider::Codex => "codex",
+        };
+        let Some(delta) = crate::pricing::usage_cost_delta(
+            provider,
+            model,
+            (&self.token_usage, self.token_usage_snapshot_kind),
+            (usage, kind),
+        ) else {
+            return;
+        };
+        self.estimated_cost_usd = Some(self.estimated_cost_usd.unwrap_or(0.0) + delta);
+        if let Some(timer) = self.turn_timer.as_mut() {
+            timer.add_cost(delta);
+        }
+    }
+
     /// Add a message
     pub fn add_message(&mut self, mut message: Message) -> Message {
+        crate::redaction::redact_message(&mut message);
         if message.sequence.is_none() {
             message.sequence = Some(self.next_message_sequence());
         }
@@ -1085,8 +1462,60 @@ impl SessionHandle {
 
     /// Replace all messages (used for snapshot hydration from transcript fallback)
     pub fn replace_messages(&mut self, mut messages: Vec<Message>) {
+        for message in &mut messages {
+            crate::redaction::redact_message(message);
+        }
         Self::normalize_message_sequences(&mut messages);
         self.messages = messages;
+        self.messages_loaded = true;
+        self.unloaded_message_count = 0;
+        self.unloaded_next_sequence = 0;
+    }
+
+    pub fn messages_loaded(&self) -> bool {
+        self.messages_loaded
+    }
+
+    pub fn note_messages_read(&mut self) {
+        self.messages_read_at = Instant::now();
+    }
+
+    /// Leave the session's `count` stored messages in the database until
+    /// they are read.
+    pub fn defer_messages(&mut self, count: u64, next_sequence: u64) {
+        self.messages.clear();
+        self.messages_loaded = false;
+        self.unloaded_message_count = count;
+        self.unloaded_next_sequence = next_sequence;
+    }
+
+    /// Drop loaded messages from memory; they are read back from the
+    /// database on next use.
+    pub fn unload_messages(&mut self) {
+        if !self.messages_loaded {
+            return;
+        }
+        let count = self.messages.len() as u64;
+        let next_sequence = self.next_message_sequence();
+        self.messages = Vec::new();
+        self.defer_messages(count, next_sequence);
+    }
+
+    /// Load `stored` messages, keeping anything that arrived while unloaded.
+    /// A message in both is taken from memory, which may be newer than the
+    /// database.
+    pub fn load_messages(&mut self, stored: Vec<Message>) {
+        let mut messages = stored;
+        for message in std::mem::take(&mut self.messages) {
+            match messages
+                .iter_mut()
+                .find(|existing| existing.id == message.id)
+            {
+                Some(existing) => *existing = message,
+                None => messages.push(message),
+            }
+        }
+        self.replace_messages(messages);
     }
 
     /// Update aggregated diff
@@ -1397,6 +1826,15 @@ impl SessionHandle {
         if let Some(ref permission_mode) = changes.permission_mode {
             self.permission_mode = permission_mode.clone();
         }
+        if let Some(ref allowed_tools) = changes.allowed_tools {
+            self.allowed_tools = allowed_tools.clone();
+        }
+        if let Some(ref disallowed_tools) = changes.disallowed_tools {
+            self.disallowed_tools = disallowed_tools.clone();
+        }
+        if let Some(ref last_error) = changes.last_error {
+            self.last_error = last_error.clone();
+        }
         if let Some(ref codex_integration_mode) = changes.codex_integration_mode {
             self.codex_integration_mode = *codex_integration_mode;
         }
@@ -1407,6 +1845,10 @@ impl SessionHandle {
             self.last_activity_at = Some(last_activity_at.clone());
         }
         if let Some(ref token_usage) = changes.token_usage {
+            let kind = changes
+                .token_usage_snapshot_kind
+                .unwrap_or(self.token_usage_snapshot_kind);
+            self.accrue_cost(token_usage, kind);
             self.token_usage = token_usage.clone();
         }
         if let Some(snapshot_kind) = changes.token_usage_snapshot_kind {
@@ -1477,6 +1919,8 @@ impl SessionHandle {
             approval_policy: self.approval_policy.clone(),
             sandbox_mode: self.sandbox_mode.clone(),
             permission_mode: self.permission_mode.clone(),
+            allowed_tools: self.allowed_tools.clone(),
+            disallowed_tools: self.disallowed_tools.clone(),
             has_pending_approval: self.pending_approval.is_some()
                 || self.pending_tool_name.is_some()
                 || self.pending_question.is_some()
@@ -1488,11 +1932,15 @@ impl SessionHandle {
                 .pending_approval_id
                 .clone()
                 .or_else(|| self.pending_approval.as_ref().map(|a| a.id.clone())),
-            message_count: self.messages.len(),
+            message_count: self.message_count(),
+            messages_loaded: self.messages_loaded,
+            messages_read_at: self.messages_read_at,
             token_usage: self.token_usage.clone(),
             token_usage_snapshot_kind: self.token_usage_snapshot_kind,
+            estimated_cost_usd: self.estimated_cost_usd,
             started_at: self.started_at.clone(),
             last_activity_at: self.last_activity_at.clone(),
+            created_by: self.created_by.clone(),
             revision: self.revision,
             git_branch: self.git_branch.clone(),
             git_sha: self.git_sha.clone(),
@@ -1506,8 +1954,9 @@ impl SessionHandle {
             repository_root: self.repository_root.clone(),
             is_worktree: self.is_worktree,
             worktree_id: self.worktree_id.clone(),
-            subscriber_count: self.broadcast_tx.receiver_count(),
+            subscriber_count: self.fanout.subscriber_count(),
             unread_count: self.unread_count,
+            last_error: self.last_error.clone(),
         }
     }
 
@@ -1523,19 +1972,63 @@ impl SessionHandle {
 
     /// Broadcast a message to all subscribers
     pub fn broadcast(&mut self, msg: orbitdock_protocol::ServerMessage) {
+        // Held changes go first so they never land on top of newer ones.
+        self.flush_coalesced_delta();
+        self.broadcast_now(msg);
+    }
+
+    /// Broadcast changes applied through `ApplyDelta`, merging bursts. The
+    /// first delta goes out at once; later ones within `DELTA_COALESCE_WINDOW`
+    /// are held and sent as one when the window closes.
+    pub fn broadcast_coalesced_delta(&mut self, changes: StateChanges) {
+        let now = Instant::now();
+        if self.delta_window_until.is_some_and(|until| now < until) {
+            match self.pending_delta.as_mut() {
+                Some(pending) => pending.merge(changes),
+                None => self.pending_delta = Some(changes),
+            }
+            self.refresh_snapshot();
+            return;
+        }
+        self.flush_coalesced_delta();
+        self.delta_window_until = Some(now + DELTA_COALESCE_WINDOW);
+        self.broadcast_now(orbitdock_protocol::ServerMessage::SessionDelta {
+            session_id: self.id.clone(),
+            changes,
+        });
+    }
+
+    /// When held delta changes are due to be sent, if there are any.
+    pub fn coalesced_delta_deadline(&self) -> Option<Instant> {
+        self.pending_delta.as_ref().and(self.delta_window_until)
+    }
+
+    /// Send any held delta changes now.
+    pub fn flush_coalesced_delta(&mut self) {
+        if let Some(changes) = self.pending_delta.take() {
+            self.broadcast_now(orbitdock_protocol::ServerMessage::SessionDelta {
+                session_id: self.id.clone(),
+                changes,
+            });
+        }
+    }
+
+    fn broadcast_now(&mut self, msg: orbitdock_protocol::ServerMessage) {
         self.revision += 1;
         let rev = self.revision;
 
-        // Pre-serialize with revision for event log
+        // Pre-serialize with revision for event log and journal
         if let Ok(json) = serialize_with_revision(&msg, rev) {
+            crate::event_journal::record(&self.id, rev, &json);
             self.event_log.push_back((rev, json));
             if self.event_log.len() > EVENT_LOG_CAPACITY {
                 self.event_log.pop_front();
             }
         }
 
-        // Non-blocking fan-out to all receivers
-        let _ = self.broadcast_tx.send(msg.clone());
+        // Non-blocking fan-out; subscribers that fell behind get a snapshot
+        let resync = self.fanout.wants_resync().then(|| self.state());
+        self.fanout.send(&msg, resync);
 
         // Forward session-level events to list subscribers (dashboard sidebar).
         // Per-message events (streaming deltas, message appends, etc.) are too
@@ -1598,6 +2091,7 @@ impl SessionHandle {
 
         TransitionState {
             id: self.id.clone(),
+            provider: self.provider,
             revision: self.revision,
             phase,
             messages: self.messages.clone(),
@@ -1605,6 +2099,7 @@ impl SessionHandle {
             token_usage_snapshot_kind: self.token_usage_snapshot_kind,
             current_diff: self.current_diff.clone(),
             current_plan: self.current_plan.clone(),
+            plan: self.plan.clone(),
             custom_name: self.custom_name.clone(),
             project_path: self.project_path.clone(),
             last_activity_at: self.last_activity_at.clone(),
@@ -1617,6 +2112,9 @@ impl SessionHandle {
             pending_approval: self.pending_approval.clone(),
             repository_root: self.repository_root.clone(),
             is_worktree: self.is_worktree,
+            compaction: self.compaction.clone(),
+            turn_usage: self.turn_usage.clone(),
+            last_error: self.last_error.clone(),
         }
     }
 
@@ -1625,10 +2123,15 @@ impl SessionHandle {
         let phase = state.phase.clone();
         self.work_status = phase.to_work_status();
         self.messages = state.messages;
+        self.accrue_cost(&state.token_usage, state.token_usage_snapshot_kind);
         self.token_usage = state.token_usage;
         self.token_usage_snapshot_kind = state.token_usage_snapshot_kind;
         self.current_diff = state.current_diff;
         self.current_plan = state.current_plan;
+        self.plan = state.plan;
+        self.compaction = state.compaction;
+        self.turn_usage = state.turn_usage;
+        self.last_error = state.last_error;
         self.custom_name = state.custom_name;
         self.last_activity_at = state.last_activity_at;
         self.current_turn_id = state.current_turn_id;
@@ -1718,6 +2221,154 @@ mod tests {
         handle.apply_state(state);
     }
 
+    fn assistant_message(id: &str, sequence: Option<u64>, content: &str) -> Message {
+        Message {
+            id: id.to_string(),
+            session_id: "lazy-session".to_string(),
+            sequence,
+            message_type: orbitdock_protocol::MessageType::Assistant,
+            content: content.to_string(),
+            tool_name: None,
+            tool_input: None,
+            tool_output: None,
+            is_error: false,
+            is_in_progress: false,
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            duration_ms: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn deferred_messages_load_on_demand_and_keep_newer_arrivals() {
+        let mut handle = SessionHandle::new(
+            "lazy-session".to_string(),
+            Provider::Claude,
+            "/tmp/lazy".to_string(),
+        );
+        handle.defer_messages(2, 2);
+        assert!(!handle.messages_loaded());
+        assert_eq!(handle.to_snapshot().message_count, 2);
+
+        // Arrivals while unloaded continue the stored sequence.
+        let appended = handle.add_message(assistant_message("m2", None, "live"));
+        assert_eq!(appended.sequence, Some(2));
+        assert_eq!(handle.message_count(), 3);
+
+        handle.load_messages(vec![
+            assistant_message("m0", Some(0), "first"),
+            assistant_message("m1", Some(1), "second"),
+            assistant_message("m2", Some(2), "stale"),
+        ]);
+        assert!(handle.messages_loaded());
+        let contents: Vec<&str> = handle
+            .messages()
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["first", "second", "live"]);
+
+        handle.unload_messages();
+        assert!(handle.messages().is_empty());
+        assert_eq!(handle.message_count(), 3);
+        let next = handle.add_message(assistant_message("m3", None, "after unload"));
+        assert_eq!(next.sequence, Some(3));
+    }
+
+    #[test]
+    fn deferred_turn_history_goes_ahead_of_newer_turns() {
+        fn turn_diff(turn_id: &str, diff: &str) -> TurnDiff {
+            TurnDiff {
+                turn_id: turn_id.to_string(),
+                diff: diff.to_string(),
+                token_usage: None,
+                snapshot_kind: None,
+                turn_usage: None,
+                summary: None,
+            }
+        }
+
+        let mut handle = SessionHandle::new(
+            "history-session".to_string(),
+            Provider::Codex,
+            "/tmp/history".to_string(),
+        );
+        handle.defer_turn_history();
+        assert!(!handle.turn_history_loaded());
+        handle.turn_count = 1;
+        handle.turn_diffs = vec![turn_diff("turn-2", "live")];
+
+        handle.restore_turn_history(
+            vec![turn_diff("turn-0", "first"), turn_diff("turn-1", "second")],
+            Vec::new(),
+        );
+        assert!(handle.turn_history_loaded());
+        assert_eq!(handle.turn_count(), 3);
+        let diffs: Vec<&str> = handle
+            .turn_diffs()
+            .iter()
+            .map(|turn_diff| turn_diff.diff.as_str())
+            .collect();
+        assert_eq!(diffs, vec!["first", "second", "live"]);
+
+        // A second delivery is ignored.
+        handle.restore_turn_history(vec![turn_diff("turn-0", "again")], Vec::new());
+        assert_eq!(handle.turn_diffs().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn apply_delta_bursts_are_merged_into_one_broadcast() {
+        let mut handle = SessionHandle::new(
+            "coalesce-session".to_string(),
+            Provider::Claude,
+            "/tmp/coalesce".to_string(),
+        );
+        let mut rx = handle.subscribe();
+
+        handle.broadcast_coalesced_delta(StateChanges {
+            work_status: Some(WorkStatus::Working),
+            ..Default::default()
+        });
+        assert!(handle.coalesced_delta_deadline().is_none());
+
+        handle.broadcast_coalesced_delta(StateChanges {
+            work_status: Some(WorkStatus::Waiting),
+            ..Default::default()
+        });
+        handle.broadcast_coalesced_delta(StateChanges {
+            last_message: Some(Some("done".to_string())),
+            ..Default::default()
+        });
+        assert!(handle.coalesced_delta_deadline().is_some());
+
+        // Any other broadcast sends the held changes first.
+        handle.broadcast(ServerMessage::TurnSummarized {
+            session_id: "coalesce-session".to_string(),
+            turn_id: "turn-1".to_string(),
+            summary: "Summary".to_string(),
+        });
+        assert!(handle.coalesced_delta_deadline().is_none());
+
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            received.push(rx.recv_message().await.expect("message"));
+        }
+        match &received[0] {
+            ServerMessage::SessionDelta { changes, .. } => {
+                assert_eq!(changes.work_status, Some(WorkStatus::Working));
+            }
+            other => panic!("expected SessionDelta, got {other:?}"),
+        }
+        match &received[1] {
+            ServerMessage::SessionDelta { changes, .. } => {
+                assert_eq!(changes.work_status, Some(WorkStatus::Waiting));
+                assert_eq!(changes.last_message, Some(Some("done".to_string())));
+            }
+            other => panic!("expected SessionDelta, got {other:?}"),
+        }
+        assert!(matches!(received[2], ServerMessage::TurnSummarized { .. }));
+    }
+
     #[test]
     fn resolve_pending_approval_promotes_next_request() {
         let mut handle = SessionHandle::new(
@@ -1864,6 +2515,23 @@ mod tests {
         let state = handle.state();
         assert_eq!(state.pending_approval_id.as_deref(), Some("req-2"));
     }
+
+    #[test]
+    fn only_active_direct_sessions_are_steerable() {
+        let mut handle = SessionHandle::new(
+            "session-steer".to_string(),
+            Provider::Claude,
+            "/tmp/project".to_string(),
+        );
+        handle.set_claude_integration_mode(Some(ClaudeIntegrationMode::Passive));
+        assert!(!handle.state().steerable);
+
+        handle.set_claude_integration_mode(Some(ClaudeIntegrationMode::Direct));
+        assert!(handle.state().steerable);
+
+        handle.set_status(SessionStatus::Ended);
+        assert!(!handle.state().steerable);
+    }
 }
 
 /// Serialize a ServerMessage with a revision field injected at the top level
@@ -0,0 +1,299 @@
+//! Session ownership and sharing on multi-user servers.
+//!
+//! A session created with a user's token belongs to that user and is private
+//! until they share it with specific users or the whole team. Credentials
+//! without a user — the static `--auth-token`, unattributed tokens, or an open
+//! server — act as the operator and can reach everything, and sessions without
+//! an owner (hook-observed and passive sessions) stay visible to everyone.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use orbitdock_protocol::{ClientMessage, ServerMessage, SessionVisibility};
+use rusqlite::{params, Connection, OptionalExtension};
+use tracing::warn;
+
+use crate::auth::AuthIdentity;
+use crate::state::SessionRegistry;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionSharing {
+    pub visibility: SessionVisibility,
+    pub shared_with: Vec<String>,
+}
+
+impl SessionSharing {
+    /// Trimmed, de-duplicated, and without the owner, who always has access.
+    pub fn new(visibility: SessionVisibility, users: Vec<String>, owner: Option<&str>) -> Self {
+        let mut shared_with: Vec<String> = users
+            .into_iter()
+            .map(|user| user.trim().to_string())
+            .filter(|user| !user.is_empty() && Some(user.as_str()) != owner)
+            .collect();
+        shared_with.sort();
+        shared_with.dedup();
+        Self {
+            visibility,
+            shared_with,
+        }
+    }
+}
+
+/// Whether `user` may see and act on a session owned by `owner`.
+pub fn allows(user: Option<&str>, owner: Option<&str>, sharing: &SessionSharing) -> bool {
+    let (Some(user), Some(owner)) = (user, owner) else {
+        return true;
+    };
+    user == owner
+        || sharing.visibility == SessionVisibility::Team
+        || sharing.shared_with.iter().any(|shared| shared == user)
+}
+
+/// Only the owner, or an operator credential, may change who has access.
+pub fn can_manage(user: Option<&str>, owner: Option<&str>) -> bool {
+    match (user, owner) {
+        (Some(user), Some(owner)) => user == owner,
+        _ => true,
+    }
+}
+
+/// The session a client message reads from or acts on, if any.
+///
+/// Claude hook events are excluded: their ids come from the Claude SDK and
+/// they create sessions rather than act on existing ones.
+pub fn target_session(msg: &ClientMessage) -> Option<&str> {
+    let session_id = match msg {
+        ClientMessage::SubscribeSession { session_id, .. }
+        | ClientMessage::SendMessage { session_id, .. }
+        | ClientMessage::SteerTurn { session_id, .. }
+        | ClientMessage::SetComposing { session_id, .. }
+        | ClientMessage::ApproveTool { session_id, .. }
+        | ClientMessage::AnswerQuestion { session_id, .. }
+        | ClientMessage::InterruptSession { session_id }
+        | ClientMessage::EndSession { session_id }
+        | ClientMessage::UpdateSessionConfig { session_id, .. }
+        | ClientMessage::UpdateSessionTools { session_id, .. }
+        | ClientMessage::RenameSession { session_id, .. }
+        | ClientMessage::SetSessionTags { session_id, .. }
+        | ClientMessage::SetSessionTicket { session_id, .. }
+        | ClientMessage::ShareSession { session_id, .. }
+        | ClientMessage::GetSessionSharing { session_id }
+        | ClientMessage::ResumeSession { session_id }
+        | ClientMessage::TakeoverSession { session_id, .. }
+        | ClientMessage::ListSkills { session_id, .. }
+        | ClientMessage::ListRemoteSkills { session_id }
+        | ClientMessage::DownloadRemoteSkill { session_id, .. }
+        | ClientMessage::ListMcpTools { session_id }
+        | ClientMessage::RefreshMcpServers { session_id }
+        | ClientMessage::ListSessionMcpServers { session_id }
+        | ClientMessage::SetSessionMcpServer { session_id, .. }
+        | ClientMessage::RemoveSessionMcpServer { session_id, .. }
+        | ClientMessage::CompactContext { session_id }
+        | ClientMessage::UndoLastTurn { session_id }
+        | ClientMessage::RollbackTurns { session_id, .. }
+        | ClientMessage::StopTask { session_id, .. }
+        | ClientMessage::RewindFiles { session_id, .. }
+        | ClientMessage::CreateReviewComment { session_id, .. }
+        | ClientMessage::ListReviewComments { session_id, .. }
+        | ClientMessage::GetFullMessage { session_id, .. }
+        | ClientMessage::GetSubagentTools { session_id, .. }
+        | ClientMessage::ExecuteShell { session_id, .. }
+        | ClientMessage::CancelShell { session_id, .. }
+        | ClientMessage::ListJobs { session_id }
+        | ClientMessage::GetJobOutput { session_id, .. }
+        | ClientMessage::OpenTerminal { session_id, .. }
+        | ClientMessage::OpenInEditor { session_id, .. }
+        | ClientMessage::ReplaySession { session_id, .. }
+        | ClientMessage::GetTurns { session_id }
+        | ClientMessage::GetSessionEvents { session_id }
+        | ClientMessage::ResyncSession { session_id } => session_id,
+        ClientMessage::ForkSession {
+            source_session_id, ..
+        }
+        | ClientMessage::ForkSessionToWorktree {
+            source_session_id, ..
+        }
+        | ClientMessage::ForkSessionToExistingWorktree {
+            source_session_id, ..
+        } => source_session_id,
+        ClientMessage::ListApprovals { session_id, .. }
+        | ClientMessage::GetAuditLog { session_id, .. } => session_id.as_ref()?,
+        _ => return None,
+    };
+    Some(session_id.as_str())
+}
+
+/// The session a list-channel message is about, if any.
+pub fn list_message_session(msg: &ServerMessage) -> Option<&str> {
+    match msg {
+        ServerMessage::SessionCreated { session } => Some(&session.id),
+        ServerMessage::SessionSnapshot { session } => Some(&session.id),
+        ServerMessage::SessionEnded { session_id, .. }
+        | ServerMessage::SessionDelta { session_id, .. }
+        | ServerMessage::ContextWarning { session_id, .. }
+        | ServerMessage::SessionTagsChanged { session_id, .. }
+        | ServerMessage::SessionTicketChanged { session_id, .. }
+        | ServerMessage::ConnectorReconnecting { session_id, .. }
+        | ServerMessage::ConnectorReconnected { session_id, .. } => Some(session_id),
+        ServerMessage::SessionForked { new_session_id, .. } => Some(new_session_id),
+        _ => None,
+    }
+}
+
+/// Refuse `/api/sessions/{id}/...` and `/api/images/{id}/...` requests for
+/// sessions the caller can't access. Runs after `auth_middleware`, which
+/// attaches the identity.
+pub async fn http_middleware(
+    State(state): State<Arc<SessionRegistry>>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let path = req.uri().path();
+    let session_id = path
+        .strip_prefix("/api/sessions/")
+        .or_else(|| path.strip_prefix(crate::images::IMAGE_URL_PREFIX))
+        .and_then(|rest| rest.split('/').next())
+        .filter(|id| !id.is_empty());
+    if let (Some(session_id), Some(identity)) = (session_id, req.extensions().get::<AuthIdentity>())
+    {
+        if !state.can_access_session(identity, session_id).await {
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+    Ok(next.run(req).await)
+}
+
+/// Saved sharing settings, loaded once at startup.
+pub fn load_all(db_path: &Path) -> Vec<(String, SessionSharing)> {
+    let result = Connection::open(db_path).and_then(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT session_id, visibility, shared_with FROM session_sharing")?;
+        let rows = stmt.query_map([], |row| {
+            let visibility: String = row.get(1)?;
+            let shared_with: String = row.get(2)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                SessionSharing {
+                    visibility: parse_visibility(&visibility),
+                    shared_with: serde_json::from_str(&shared_with).unwrap_or_default(),
+                },
+            ))
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+    });
+    match result {
+        Ok(sharing) => sharing,
+        Err(e) => {
+            warn!(
+                component = "session_access",
+                event = "session_access.load_failed",
+                error = %e,
+                "Could not load session sharing"
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// The user who created a session that is no longer in memory.
+pub async fn load_owner(db_path: &Path, session_id: &str) -> Option<String> {
+    let db_path = db_path.to_path_buf();
+    let session_id = session_id.to_string();
+    tokio::task::spawn_blocking(move || {
+        if !db_path.exists() {
+            return None;
+        }
+        let conn = Connection::open(&db_path).ok()?;
+        conn.query_row(
+            "SELECT created_by FROM sessions WHERE id = ?1",
+            params![session_id],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()
+        .ok()
+        .flatten()
+        .flatten()
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+pub fn visibility_str(visibility: SessionVisibility) -> &'static str {
+    match visibility {
+        SessionVisibility::Private => "private",
+        SessionVisibility::Team => "team",
+    }
+}
+
+fn parse_visibility(value: &str) -> SessionVisibility {
+    match value {
+        "team" => SessionVisibility::Team,
+        _ => SessionVisibility::Private,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn private_sessions_are_limited_to_owner_and_shares() {
+        let private = SessionSharing::default();
+        assert!(allows(Some("ana"), Some("ana"), &private));
+        assert!(!allows(Some("sam"), Some("ana"), &private));
+
+        let shared = SessionSharing::new(
+            SessionVisibility::Private,
+            vec![" sam ".into(), "ana".into(), "sam".into(), "".into()],
+            Some("ana"),
+        );
+        assert_eq!(shared.shared_with, vec!["sam".to_string()]);
+        assert!(allows(Some("sam"), Some("ana"), &shared));
+        assert!(!allows(Some("kim"), Some("ana"), &shared));
+
+        let team = SessionSharing::new(SessionVisibility::Team, Vec::new(), Some("ana"));
+        assert!(allows(Some("kim"), Some("ana"), &team));
+    }
+
+    #[test]
+    fn operators_and_unowned_sessions_are_unrestricted() {
+        let private = SessionSharing::default();
+        assert!(allows(None, Some("ana"), &private));
+        assert!(allows(Some("sam"), None, &private));
+
+        assert!(can_manage(None, Some("ana")));
+        assert!(can_manage(Some("ana"), Some("ana")));
+        assert!(!can_manage(Some("sam"), Some("ana")));
+    }
+
+    #[test]
+    fn target_session_covers_reads_and_actions() {
+        let send = ClientMessage::SendMessage {
+            session_id: "s1".into(),
+            content: "hi".into(),
+            model: None,
+            effort: None,
+            skills: Vec::new(),
+            images: Vec::new(),
+            mentions: Vec::new(),
+            attachment_ids: Vec::new(),
+        };
+        assert_eq!(target_session(&send), Some("s1"));
+        assert_eq!(
+            target_session(&ClientMessage::ListApprovals {
+                session_id: None,
+                limit: None,
+            }),
+            None
+        );
+        assert_eq!(target_session(&ClientMessage::SubscribeList), None);
+    }
+}
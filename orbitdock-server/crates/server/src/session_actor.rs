@@ -42,6 +42,7 @@ impl SessionActorHandle {
         persist_tx: mpsc::Sender<PersistCommand>,
     ) -> SessionActorHandle {
         let (command_tx, command_rx) = mpsc::channel(256);
+        handle.set_command_tx(&command_tx);
         let snapshot = handle.snapshot_arc();
         let id = handle.id().to_string();
         handle.refresh_snapshot();
@@ -105,8 +106,24 @@ async fn passive_actor_loop(
     mut command_rx: mpsc::Receiver<SessionCommand>,
     persist_tx: mpsc::Sender<PersistCommand>,
 ) {
-    while let Some(cmd) = command_rx.recv().await {
+    loop {
+        let delta_deadline = handle.coalesced_delta_deadline();
+        let cmd = tokio::select! {
+            cmd = command_rx.recv() => cmd,
+            _ = crate::session_command_handler::coalesced_delta_due(delta_deadline),
+                if delta_deadline.is_some() =>
+            {
+                handle.flush_coalesced_delta();
+                continue;
+            }
+        };
+        let Some(cmd) = cmd else {
+            break;
+        };
         if let SessionCommand::TakeHandle { reply } = cmd {
+            // Connector loops expect the full conversation and turn history in memory.
+            crate::session_command_handler::ensure_messages_loaded(&mut handle).await;
+            crate::session_command_handler::ensure_turn_history_loaded(&mut handle).await;
             let _ = reply.send(handle);
             return; // Stop the passive loop — handle is now owned by the caller
         }
@@ -2,9 +2,12 @@
 
 use orbitdock_protocol::{
     ApprovalRequest, ApprovalType, ClaudeIntegrationMode, CodexIntegrationMode, Message,
-    ServerMessage, SessionState, SessionStatus, SessionSummary, StateChanges, WorkStatus,
+    ServerMessage, SessionState, SessionStatus, SessionSummary, SessionViewer, StateChanges,
+    TicketLink, WorkStatus,
 };
-use tokio::sync::{broadcast, oneshot};
+use tokio::sync::oneshot;
+
+use crate::session_fanout::SessionReceiver;
 
 #[derive(Debug, Clone)]
 pub struct ConversationPage {
@@ -61,7 +64,7 @@ pub enum SessionCommand {
     },
 
     /// Subscribe to session updates.
-    /// Returns (Option<SessionState>, broadcast::Receiver, Vec<String> for replay).
+    /// Returns (Option<SessionState>, SessionReceiver, Vec<String> for replay).
     /// If `since_revision` is provided and replay is possible, state is None and
     /// replay events are returned. Otherwise state is a full snapshot.
     Subscribe {
@@ -113,9 +116,22 @@ pub enum SessionCommand {
     SetForkedFrom {
         source_id: String,
     },
+    SetTags {
+        tags: Vec<String>,
+    },
+    SetTicket {
+        ticket: Option<TicketLink>,
+    },
+    SetViewers {
+        viewers: Vec<SessionViewer>,
+    },
     SetLastTool {
         tool: Option<String>,
     },
+    RecordSubagentCost {
+        subagent_id: String,
+        cost_usd: f64,
+    },
 
     // -- Compound operations --
     /// Apply a StateChanges delta, optionally persist, and broadcast SessionDelta.
@@ -127,6 +143,14 @@ pub enum SessionCommand {
     /// Mark session ended locally: status=Ended, work_status=Ended, broadcast delta.
     EndLocally,
 
+    /// Show a turn summary as last_message if `replaces` is still the current one,
+    /// and store it with the turn's diff snapshot.
+    ApplyTurnSummary {
+        summary: String,
+        replaces: Option<String>,
+        turn_id: Option<String>,
+    },
+
     /// Set custom name, optionally persist, broadcast delta, and return summary.
     SetCustomNameAndNotify {
         name: Option<String>,
@@ -167,6 +191,8 @@ pub enum SessionCommand {
     Broadcast {
         msg: ServerMessage,
     },
+    /// Send a snapshot to subscribers that overflowed and have since drained
+    ResyncSubscribers,
 
     // -- Complex operations --
     /// Load transcript from path and sync messages into session
@@ -210,6 +236,11 @@ pub enum SessionCommand {
         num_turns_from_end: u32,
         reply: oneshot::Sender<Option<String>>,
     },
+    /// One message by ID, uncapped.
+    GetMessage {
+        message_id: String,
+        reply: oneshot::Sender<Option<Message>>,
+    },
 
     /// Extract the owned SessionHandle from a passive actor, stopping its loop.
     /// Used for upgrading a passive session to one with a live connector.
@@ -221,6 +252,48 @@ pub enum SessionCommand {
     MarkRead {
         reply: oneshot::Sender<u64>,
     },
+
+    /// Drop the session's messages from memory if nobody is subscribed.
+    UnloadMessages,
+
+    /// Hand over turn history deferred at startup, and a transcript summary
+    /// for a session restored without one.
+    RestoreDetails {
+        details: crate::persistence::RestoredSessionDetails,
+    },
+}
+
+impl SessionCommand {
+    /// Commands that need the session's messages in memory; the actor loads
+    /// them first if the session was restored or unloaded without them.
+    pub fn reads_messages(&self) -> bool {
+        matches!(
+            self,
+            SessionCommand::GetState { .. }
+                | SessionCommand::Subscribe { .. }
+                | SessionCommand::GetConversationBootstrap { .. }
+                | SessionCommand::GetConversationPage { .. }
+                | SessionCommand::ResolveUserMessageId { .. }
+                | SessionCommand::GetMessage { .. }
+                | SessionCommand::LoadTranscriptAndSync { .. }
+                | SessionCommand::TakeHandle { .. }
+        )
+    }
+
+    /// Commands that read or add to turn history; the actor loads what was
+    /// deferred at startup first if the background restore hasn't reached it.
+    pub fn reads_turn_history(&self) -> bool {
+        matches!(
+            self,
+            SessionCommand::GetState { .. }
+                | SessionCommand::Subscribe { .. }
+                | SessionCommand::ProcessEvent { .. }
+                | SessionCommand::ApplyTurnSummary { .. }
+                | SessionCommand::LoadTranscriptAndSync { .. }
+                | SessionCommand::GetConversationBootstrap { .. }
+                | SessionCommand::TakeHandle { .. }
+        )
+    }
 }
 
 pub struct PendingApprovalResolution {
@@ -236,11 +309,11 @@ pub enum SubscribeResult {
     /// Full snapshot (when replay not possible)
     Snapshot {
         state: Box<SessionState>,
-        rx: broadcast::Receiver<ServerMessage>,
+        rx: SessionReceiver,
     },
     /// Replay events (when revision is close enough)
     Replay {
         events: Vec<String>,
-        rx: broadcast::Receiver<ServerMessage>,
+        rx: SessionReceiver,
     },
 }
@@ -12,9 +12,9 @@ use orbitdock_protocol::{
 };
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
-use tracing::warn;
+use tracing::{debug, warn};
 
-use crate::persistence::PersistCommand;
+use crate::persistence::{PersistCommand, RestoredSessionDetails};
 use crate::session::SessionHandle;
 use crate::session_command::{
     PendingApprovalResolution, PersistOp, SessionCommand, SubscribeResult,
@@ -90,6 +90,101 @@ fn latest_completed_conversation_message(messages: &[Message]) -> Option<String>
         .find_map(completed_conversation_message_snippet)
 }
 
+/// Load the session's stored messages if it was restored or unloaded without
+/// them. On failure the session stays unloaded and the next read retries.
+pub(crate) async fn ensure_messages_loaded(handle: &mut SessionHandle) {
+    handle.note_messages_read();
+    if handle.messages_loaded() {
+        return;
+    }
+    let session_id = handle.id().to_string();
+    let transcript_path = handle.transcript_path().map(str::to_string);
+    match crate::persistence::load_messages_for_hydration(&session_id, transcript_path.as_deref())
+        .await
+    {
+        Ok(messages) => {
+            debug!(
+                component = "session",
+                event = "session.messages.loaded",
+                session_id = %session_id,
+                messages = messages.len(),
+                "Loaded session messages"
+            );
+            handle.load_messages(messages);
+        }
+        Err(e) => {
+            warn!(
+                component = "session",
+                event = "session.messages.load_failed",
+                session_id = %session_id,
+                error = %e,
+                "Failed to load session messages"
+            );
+        }
+    }
+}
+
+/// Resolves when held `ApplyDelta` changes are due to be broadcast. Actor
+/// loops select on this with the deadline from `coalesced_delta_deadline`.
+pub(crate) async fn coalesced_delta_due(deadline: Option<std::time::Instant>) {
+    match deadline {
+        Some(deadline) => {
+            tokio::time::sleep_until(tokio::time::Instant::from_std(deadline)).await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Load turn history deferred at startup, for a command that reaches the
+/// session before the background restore does.
+pub(crate) async fn ensure_turn_history_loaded(handle: &mut SessionHandle) {
+    if handle.turn_history_loaded() {
+        return;
+    }
+    let session_id = handle.id().to_string();
+    match crate::persistence::load_session_details(vec![session_id.clone()]).await {
+        Ok(details) => {
+            for details in details {
+                restore_session_details(handle, details);
+            }
+        }
+        Err(e) => {
+            warn!(
+                component = "session",
+                event = "session.details.load_failed",
+                session_id = %session_id,
+                error = %e,
+                "Failed to load session turn history"
+            );
+        }
+    }
+}
+
+fn restore_session_details(handle: &mut SessionHandle, details: RestoredSessionDetails) {
+    if handle.turn_history_loaded() {
+        return;
+    }
+    let RestoredSessionDetails {
+        id: _,
+        turn_diffs,
+        turn_metrics,
+        transcript_summary,
+    } = details;
+    handle.restore_turn_history(turn_diffs, turn_metrics);
+
+    if let Some(summary) = transcript_summary.filter(|_| !handle.has_summary()) {
+        let changes = StateChanges {
+            summary: Some(Some(summary)),
+            ..Default::default()
+        };
+        handle.apply_changes(&changes);
+        handle.broadcast(ServerMessage::SessionDelta {
+            session_id: handle.id().to_string(),
+            changes,
+        });
+    }
+}
+
 /// Handle a SessionCommand on the owned SessionHandle.
 /// This is used by both the CodexSession event loop and the passive SessionActor.
 pub async fn handle_session_command(
@@ -97,6 +192,12 @@ pub async fn handle_session_command(
     handle: &mut SessionHandle,
     persist_tx: &mpsc::Sender<PersistCommand>,
 ) {
+    if cmd.reads_messages() {
+        ensure_messages_loaded(handle).await;
+    }
+    if cmd.reads_turn_history() {
+        ensure_turn_history_loaded(handle).await;
+    }
     match cmd {
         SessionCommand::GetState { reply } => {
             let _ = reply.send(handle.state());
@@ -164,6 +265,14 @@ pub async fn handle_session_command(
                 .map(|m| m.id.clone());
             let _ = reply.send(result);
         }
+        SessionCommand::GetMessage { message_id, reply } => {
+            let message = handle
+                .messages()
+                .iter()
+                .find(|m| m.id == message_id)
+                .cloned();
+            let _ = reply.send(message);
+        }
         SessionCommand::ProcessEvent { event } => {
             let session_id = handle.id().to_string();
             dispatch_transition_input(&session_id, event, handle, persist_tx).await;
@@ -199,10 +308,29 @@ pub async fn handle_session_command(
             handle.set_last_activity_at(ts);
         }
         SessionCommand::SetCodexIntegrationMode { mode } => {
+            let was_steerable = handle.steerable();
             handle.set_codex_integration_mode(mode);
+            broadcast_steerable_change(handle, was_steerable);
+        }
+        SessionCommand::SetTags { tags } => {
+            handle.set_tags(tags);
+        }
+        SessionCommand::SetTicket { ticket } => {
+            handle.set_ticket(ticket);
+        }
+        SessionCommand::SetViewers { viewers } => {
+            handle.set_viewers(viewers);
+        }
+        SessionCommand::RecordSubagentCost {
+            subagent_id,
+            cost_usd,
+        } => {
+            handle.record_subagent_cost(&subagent_id, cost_usd);
         }
         SessionCommand::SetClaudeIntegrationMode { mode } => {
+            let was_steerable = handle.steerable();
             handle.set_claude_integration_mode(mode);
+            broadcast_steerable_change(handle, was_steerable);
         }
         SessionCommand::SetForkedFrom { source_id } => {
             handle.set_forked_from(source_id);
@@ -216,15 +344,44 @@ pub async fn handle_session_command(
             changes,
             persist_op,
         } => {
-            let session_id = handle.id().to_string();
             handle.apply_changes(&changes);
             if let Some(op) = persist_op {
                 execute_persist_op(op, persist_tx).await;
             }
-            handle.broadcast(ServerMessage::SessionDelta {
-                session_id,
-                changes,
-            });
+            handle.broadcast_coalesced_delta(changes);
+        }
+        SessionCommand::ApplyTurnSummary {
+            summary,
+            replaces,
+            turn_id,
+        } => {
+            if let Some(turn_id) = turn_id {
+                if handle.set_turn_diff_summary(&turn_id, &summary) {
+                    let _ = persist_tx
+                        .send(PersistCommand::TurnDiffSummary {
+                            session_id: handle.id().to_string(),
+                            turn_id: turn_id.clone(),
+                            summary: summary.clone(),
+                        })
+                        .await;
+                    handle.broadcast(ServerMessage::TurnSummarized {
+                        session_id: handle.id().to_string(),
+                        turn_id,
+                        summary: summary.clone(),
+                    });
+                }
+            }
+            // A newer message arrived while the summary was generated.
+            if handle.to_snapshot().last_message == replaces {
+                handle.set_last_message(Some(summary.clone()));
+                handle.broadcast(ServerMessage::SessionDelta {
+                    session_id: handle.id().to_string(),
+                    changes: StateChanges {
+                        last_message: Some(Some(summary)),
+                        ..Default::default()
+                    },
+                });
+            }
         }
         SessionCommand::EndLocally => {
             let session_id = handle.id().to_string();
@@ -238,6 +395,7 @@ pub async fn handle_session_command(
                     status: Some(SessionStatus::Ended),
                     work_status: Some(WorkStatus::Ended),
                     last_activity_at: Some(now),
+                    steerable: Some(false),
                     ..Default::default()
                 },
             });
@@ -270,7 +428,8 @@ pub async fn handle_session_command(
         SessionCommand::ReplaceMessages { messages } => {
             handle.replace_messages(messages);
         }
-        SessionCommand::AddMessageAndBroadcast { message } => {
+        SessionCommand::AddMessageAndBroadcast { mut message } => {
+            crate::redaction::redact_message(&mut message);
             let session_id = handle.id().to_string();
             let mut last_message_delta: Option<String> = None;
             let should_broadcast_unread = !matches!(message.message_type, MessageType::User | MessageType::Steer);
@@ -350,6 +509,17 @@ pub async fn handle_session_command(
         SessionCommand::Broadcast { msg } => {
             handle.broadcast(msg);
         }
+        SessionCommand::ResyncSubscribers => {
+            handle.resync_subscribers();
+        }
+        SessionCommand::UnloadMessages => {
+            if handle.subscriber_count() == 0 {
+                handle.unload_messages();
+            }
+        }
+        SessionCommand::RestoreDetails { details } => {
+            restore_session_details(handle, details);
+        }
         SessionCommand::TakeHandle { reply: _ } => {
             // TakeHandle is only meaningful in passive_actor_loop — if it arrives
             // here (active event loop), drop it. The oneshot will fail on the caller side.
@@ -428,28 +598,53 @@ pub(crate) async fn dispatch_connector_event(
     dispatch_transition_input(session_id, input, handle, persist_tx).await;
 }
 
+/// Scrub secrets from message content before it reaches state, clients, or
+/// the database.
+fn redact_input(input: &mut transition::Input) {
+    match input {
+        transition::Input::MessageCreated(message) => crate::redaction::redact_message(message),
+        transition::Input::MessageUpdated {
+            content,
+            tool_output,
+            ..
+        } => {
+            crate::redaction::redact_option(content);
+            crate::redaction::redact_option(tool_output);
+        }
+        _ => {}
+    }
+}
+
 /// Run a transition `Input` through the state machine and apply effects.
 ///
 /// Used by `dispatch_connector_event` (from provider event loops) and
 /// `ProcessEvent` (from session commands).
 pub(crate) async fn dispatch_transition_input(
     _session_id: &str,
-    input: transition::Input,
+    mut input: transition::Input,
     handle: &mut SessionHandle,
     persist_tx: &mpsc::Sender<PersistCommand>,
 ) {
+    redact_input(&mut input);
     let now = chrono_now();
+    let turn_metrics = handle.observe_turn_timing(&input);
+    let webhook_event = crate::webhooks::event_for_input(&input);
+    let turn_diff_count = handle.turn_diffs().len();
+    let previous_snippet = latest_completed_conversation_message(handle.messages());
     let state = handle.extract_state();
     let (new_state, effects) = transition::transition(state, input, &now);
     handle.apply_state(new_state);
 
     // Update last_message from the latest completed user/assistant message.
-    // In-progress assistant streaming deltas are intentionally ignored.
+    // In-progress assistant streaming deltas are intentionally ignored, and a
+    // turn summary standing in for an unchanged message is kept.
     let mut last_message_delta: Option<String> = None;
     let mut unread_count_delta: Option<u64> = None;
     let previous_last_message = handle.to_snapshot().last_message.clone();
     if let Some(snippet) = latest_completed_conversation_message(handle.messages()) {
-        if previous_last_message.as_deref() != Some(snippet.as_str()) {
+        let message_changed =
+            previous_last_message.is_none() || previous_snippet.as_ref() != Some(&snippet);
+        if message_changed && previous_last_message.as_deref() != Some(snippet.as_str()) {
             handle.set_last_message(Some(snippet.clone()));
             last_message_delta = Some(snippet);
         }
@@ -487,11 +682,91 @@ pub(crate) async fn dispatch_transition_input(
                     );
                 }
                 inject_approval_version(&mut msg, handle.approval_version());
+                let mut context_warning = None;
+                if let ServerMessage::TokensUpdated {
+                    ref usage,
+                    snapshot_kind,
+                    ref mut estimated_cost_usd,
+                    ..
+                } = msg
+                {
+                    *estimated_cost_usd = handle.estimated_cost_usd();
+                    context_warning = handle.observe_context_usage(usage, snapshot_kind);
+                }
                 handle.broadcast(msg);
+                if let Some(warning) = context_warning {
+                    broadcast_context_warning(handle, warning);
+                }
             }
         }
     }
 
+    if let Some((kind, mut data)) = webhook_event {
+        let snapshot = handle.to_snapshot();
+        let mut turn_transcript = None;
+        let mut turn_diff_id = None;
+        if kind == crate::webhooks::WebhookEventKind::TurnCompleted {
+            // A diff snapshot is only taken when this turn produced one.
+            let turn_diff = handle
+                .turn_diffs()
+                .get(turn_diff_count..)
+                .and_then(|added| added.last());
+            let diff_stats = turn_diff
+                .map(|turn_diff| crate::turn_summary::DiffStats::from_diff(&turn_diff.diff));
+            turn_diff_id = turn_diff.map(|turn_diff| turn_diff.turn_id.clone());
+            data["last_message"] = serde_json::json!(snapshot.last_message);
+            data["turn_count"] = serde_json::json!(handle.turn_count());
+            data["diff_stats"] = serde_json::json!(diff_stats);
+            turn_transcript =
+                crate::turn_summary::transcript(handle.messages(), diff_stats.as_ref());
+            if let Some(turns) = crate::rolling_summary::due(handle.turn_count()) {
+                if let Some(transcript) =
+                    crate::turn_summary::recent_transcript(handle.messages(), turns)
+                {
+                    crate::rolling_summary::request(crate::rolling_summary::SummaryRequest {
+                        session_id: handle.id().to_string(),
+                        previous: snapshot.summary.clone(),
+                        transcript,
+                    });
+                }
+            }
+            if let Some(turns) = crate::session_tags::due(handle.turn_count()) {
+                if let Some(transcript) =
+                    crate::turn_summary::recent_transcript(handle.messages(), turns)
+                {
+                    crate::session_tags::request(crate::session_tags::TagRequest {
+                        session_id: handle.id().to_string(),
+                        transcript,
+                    });
+                }
+            }
+        }
+        let mut event = crate::webhooks::WebhookEvent::new(
+            kind,
+            crate::webhooks::WebhookSession::from_snapshot(&snapshot),
+            data,
+        );
+        event.turn_transcript = turn_transcript;
+        event.turn_diff_id = turn_diff_id;
+        crate::webhooks::emit(event);
+    }
+
+    if let Some(metrics) = turn_metrics {
+        let _ = persist_tx
+            .send(PersistCommand::TurnMetricsInsert {
+                session_id: handle.id().to_string(),
+                turn_seq: handle.turn_count(),
+                provider: handle.provider(),
+                model: handle.model().map(str::to_string),
+                metrics: metrics.clone(),
+            })
+            .await;
+        handle.broadcast(ServerMessage::TurnMetricsRecorded {
+            session_id: handle.id().to_string(),
+            metrics,
+        });
+    }
+
     if last_message_delta.is_some() || unread_count_delta.is_some() {
         handle.broadcast(ServerMessage::SessionDelta {
             session_id: handle.id().to_string(),
@@ -506,6 +781,48 @@ pub(crate) async fn dispatch_transition_input(
     handle.refresh_snapshot();
 }
 
+/// Tell viewers the context is filling up, and webhooks too when
+/// `[context] notify` is on.
+fn broadcast_context_warning(
+    handle: &mut SessionHandle,
+    warning: crate::context_warnings::ContextWarning,
+) {
+    if crate::context_warnings::notify() {
+        crate::webhooks::emit(crate::webhooks::WebhookEvent::new(
+            crate::webhooks::WebhookEventKind::ContextWarning,
+            crate::webhooks::WebhookSession::from_snapshot(&handle.to_snapshot()),
+            serde_json::json!({
+                "used_tokens": warning.used_tokens,
+                "context_window": warning.context_window,
+                "percent": warning.percent,
+                "threshold": warning.threshold,
+            }),
+        ));
+    }
+    handle.broadcast(ServerMessage::ContextWarning {
+        session_id: handle.id().to_string(),
+        used_tokens: warning.used_tokens,
+        context_window: warning.context_window,
+        percent: warning.percent,
+        threshold: warning.threshold,
+    });
+}
+
+/// Tell viewers when an integration mode change (a passive takeover, say)
+/// gained or lost the session its steering.
+fn broadcast_steerable_change(handle: &mut SessionHandle, was_steerable: bool) {
+    let steerable = handle.steerable();
+    if steerable != was_steerable {
+        handle.broadcast(ServerMessage::SessionDelta {
+            session_id: handle.id().to_string(),
+            changes: StateChanges {
+                steerable: Some(steerable),
+                ..Default::default()
+            },
+        });
+    }
+}
+
 /// Returns `true` if the event signals the end of a turn (used to cancel
 /// interrupt watchdogs).
 pub(crate) fn is_turn_ending(event: &ConnectorEvent) -> bool {
@@ -543,7 +860,9 @@ pub(crate) fn spawn_interrupt_watchdog(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use orbitdock_protocol::{Provider, SessionStatus, StateChanges};
+    use orbitdock_protocol::{
+        ClaudeIntegrationMode, CodexIntegrationMode, Provider, SessionStatus, StateChanges,
+    };
     use tokio::sync::oneshot;
 
     #[tokio::test]
@@ -583,13 +902,16 @@ mod tests {
         assert_eq!(state.status, SessionStatus::Active);
         assert_eq!(state.unread_count, 1);
 
-        let first = rx.recv().await.expect("expected message append");
+        let first = rx.recv_message().await.expect("expected message append");
         assert!(
             matches!(first, ServerMessage::MessageAppended { .. }),
             "expected first broadcast to be MessageAppended, got {first:?}"
         );
 
-        let second = rx.recv().await.expect("expected unread session delta");
+        let second = rx
+            .recv_message()
+            .await
+            .expect("expected unread session delta");
         match second {
             ServerMessage::SessionDelta { changes, .. } => {
                 assert_eq!(changes.unread_count, Some(1));
@@ -604,6 +926,49 @@ mod tests {
         );
     }
 
+    async fn next_delta(rx: &mut crate::session_fanout::SessionReceiver) -> StateChanges {
+        match rx.recv_message().await.expect("expected session delta") {
+            ServerMessage::SessionDelta { changes, .. } => changes,
+            other => panic!("expected SessionDelta, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn ending_or_going_passive_clears_steerable() {
+        let (persist_tx, _persist_rx) = mpsc::channel(8);
+        let mut handle = SessionHandle::new(
+            "session-steer-passive".to_string(),
+            Provider::Claude,
+            "/tmp/project".to_string(),
+        );
+        handle.set_claude_integration_mode(Some(ClaudeIntegrationMode::Direct));
+        let mut rx = handle.subscribe();
+
+        handle_session_command(
+            SessionCommand::SetClaudeIntegrationMode {
+                mode: Some(ClaudeIntegrationMode::Passive),
+            },
+            &mut handle,
+            &persist_tx,
+        )
+        .await;
+        assert_eq!(next_delta(&mut rx).await.steerable, Some(false));
+        assert!(!handle.steerable());
+
+        let mut handle = SessionHandle::new(
+            "session-steer-end".to_string(),
+            Provider::Codex,
+            "/tmp/project".to_string(),
+        );
+        handle.set_codex_integration_mode(Some(CodexIntegrationMode::Direct));
+        let mut rx = handle.subscribe();
+
+        handle_session_command(SessionCommand::EndLocally, &mut handle, &persist_tx).await;
+        let changes = next_delta(&mut rx).await;
+        assert_eq!(changes.status, Some(SessionStatus::Ended));
+        assert_eq!(changes.steerable, Some(false));
+    }
+
     #[tokio::test]
     async fn subscribe_with_current_revision_returns_empty_replay_not_snapshot() {
         let (persist_tx, _persist_rx) = mpsc::channel(8);
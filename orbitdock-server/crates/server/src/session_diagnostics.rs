@@ -0,0 +1,60 @@
+//! Per-session diagnostic log.
+//!
+//! Connector lifecycle milestones — spawned, resumed, timed out, taken over,
+//! created lazily on subscribe, reconnected, thread id changed — are written
+//! to `session_diagnostics` so a session that misbehaves has a history to
+//! attach to a bug report. `get_session_events` returns it. Thread id changes
+//! are recorded by the persistence writer itself, since several paths set
+//! them.
+
+use orbitdock_protocol::{Provider, ProviderBinaryStatus};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use crate::persistence::PersistCommand;
+
+/// Events kept per session; older ones are dropped as new ones arrive.
+pub const MAX_PER_SESSION: usize = 500;
+
+/// Queue an event for `session_id`'s diagnostic log.
+pub(crate) async fn record(
+    persist_tx: &mpsc::Sender<PersistCommand>,
+    session_id: &str,
+    kind: &str,
+    details: Value,
+) {
+    let _ = persist_tx
+        .send(PersistCommand::SessionDiagnostic {
+            session_id: session_id.to_string(),
+            kind: kind.to_string(),
+            details,
+        })
+        .await;
+}
+
+/// Details for a connector event: which provider, what started it
+/// (`create`, `resume`, `takeover`, `lazy`, `reconnect`), and the provider
+/// CLI version from the last probe, since version drift is the usual suspect.
+pub(crate) fn connector(provider: Provider, trigger: &str) -> Value {
+    let binary = crate::provider_info::current()
+        .into_iter()
+        .find(|binary| binary.provider == provider);
+    let mut details = json!({
+        "provider": provider,
+        "trigger": trigger,
+    });
+    if let Some(binary) = binary {
+        details["cli_version"] = json!(binary.version);
+        if binary.status != ProviderBinaryStatus::Ok {
+            details["cli_status"] = json!(binary.status);
+        }
+    }
+    details
+}
+
+/// `connector` details plus the error that stopped it.
+pub(crate) fn connector_error(provider: Provider, trigger: &str, error: &str) -> Value {
+    let mut details = connector(provider, trigger);
+    details["error"] = json!(error);
+    details
+}
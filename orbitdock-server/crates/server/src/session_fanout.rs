@@ -0,0 +1,330 @@
+//! Session fan-out — one bounded queue per subscriber.
+//!
+//! A subscriber that keeps up sees every message in order. One that falls
+//! behind (a slow client during a Claude `--resume` replay, say) is never
+//! handed a silent gap: once its queue is full the actor stops queueing for
+//! it, and as soon as it has drained what it has, it is sent a fresh
+//! `SessionSnapshot` and continues live from there. The actor never blocks on
+//! a slow subscriber, and other subscribers are unaffected.
+//!
+//! Each message is compacted and serialized once, when it is broadcast, and
+//! the same JSON is shared by every WebSocket forwarder.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use orbitdock_protocol::{ServerMessage, SessionState};
+use tokio::sync::mpsc::{self, error::TryRecvError, error::TrySendError};
+use tracing::warn;
+
+use crate::session_command::SessionCommand;
+use crate::snapshot_compaction::sanitize_server_message_for_transport;
+
+/// Messages queued per subscriber before it is switched to resync.
+pub const SUBSCRIBER_QUEUE_CAPACITY: usize = 512;
+
+/// Where a drained subscriber asks its session actor for a resync. Weak so
+/// subscribers don't keep the actor's command channel open, and swapped when
+/// the handle moves to a connector event loop.
+type CommandSlot = Arc<Mutex<Option<mpsc::WeakSender<SessionCommand>>>>;
+
+/// A broadcast as queued for subscribers.
+pub struct SharedMessage {
+    /// For in-process subscribers (HTTP handlers waiting on a reply).
+    pub message: ServerMessage,
+    /// Wire JSON, compacted for transport; `None` if serialization failed.
+    pub json: Option<Arc<str>>,
+}
+
+impl SharedMessage {
+    fn new(message: ServerMessage) -> Arc<Self> {
+        let compacted = sanitize_server_message_for_transport(message.clone());
+        let json = match serde_json::to_string(&compacted) {
+            Ok(json) => Some(Arc::from(json)),
+            Err(e) => {
+                warn!(
+                    component = "session",
+                    event = "session.broadcast.serialize_failed",
+                    error = %e,
+                    "Failed to serialize broadcast message"
+                );
+                None
+            }
+        };
+        Arc::new(SharedMessage { message, json })
+    }
+}
+
+struct Subscriber {
+    tx: mpsc::Sender<Arc<SharedMessage>>,
+    /// Set while the subscriber is owed a snapshot instead of messages.
+    overflowed: Arc<AtomicBool>,
+}
+
+impl Subscriber {
+    fn is_drained(&self) -> bool {
+        self.tx.capacity() == self.tx.max_capacity()
+    }
+}
+
+/// The subscriber side of a session, owned by `SessionHandle`.
+#[derive(Default)]
+pub struct SessionFanout {
+    subscribers: Vec<Subscriber>,
+    commands: CommandSlot,
+}
+
+impl SessionFanout {
+    /// Route resync requests to the actor loop that now owns the session.
+    pub fn set_command_tx(&self, command_tx: &mpsc::Sender<SessionCommand>) {
+        *self.commands.lock().unwrap_or_else(|e| e.into_inner()) = Some(command_tx.downgrade());
+    }
+
+    pub fn subscribe(&mut self) -> SessionReceiver {
+        let (tx, rx) = mpsc::channel(SUBSCRIBER_QUEUE_CAPACITY);
+        let overflowed = Arc::new(AtomicBool::new(false));
+        self.subscribers.push(Subscriber {
+            tx,
+            overflowed: overflowed.clone(),
+        });
+        SessionReceiver {
+            rx,
+            overflowed,
+            commands: self.commands.clone(),
+            resync_requested: false,
+        }
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers
+            .iter()
+            .filter(|subscriber| !subscriber.tx.is_closed())
+            .count()
+    }
+
+    /// True if a subscriber has drained its queue and is waiting for the
+    /// snapshot `send` and `resync` take.
+    pub fn wants_resync(&self) -> bool {
+        self.subscribers.iter().any(|subscriber| {
+            subscriber.overflowed.load(Ordering::Acquire) && subscriber.is_drained()
+        })
+    }
+
+    /// Queue `msg` for every subscriber that is keeping up. `state` is the
+    /// session after `msg`, needed only when `wants_resync` said so.
+    pub fn send(&mut self, msg: &ServerMessage, state: Option<SessionState>) {
+        self.subscribers
+            .retain(|subscriber| !subscriber.tx.is_closed());
+        if self.subscribers.is_empty() {
+            return;
+        }
+        let shared = SharedMessage::new(msg.clone());
+        let snapshot =
+            state.map(|session| SharedMessage::new(ServerMessage::SessionSnapshot { session }));
+
+        for subscriber in &self.subscribers {
+            if subscriber.overflowed.load(Ordering::Acquire) {
+                // The snapshot already includes `msg`.
+                resync_subscriber(subscriber, snapshot.as_ref());
+                continue;
+            }
+            match subscriber.tx.try_send(shared.clone()) {
+                Ok(()) | Err(TrySendError::Closed(_)) => {}
+                Err(TrySendError::Full(_)) => {
+                    warn!(
+                        component = "session",
+                        event = "session.subscriber.overflowed",
+                        capacity = SUBSCRIBER_QUEUE_CAPACITY,
+                        "Subscriber queue full, switching it to snapshot resync"
+                    );
+                    subscriber.overflowed.store(true, Ordering::Release);
+                }
+            }
+        }
+    }
+
+    /// Send `state` to every overflowed subscriber that has drained its queue.
+    pub fn resync(&mut self, state: Option<SessionState>) {
+        let snapshot =
+            state.map(|session| SharedMessage::new(ServerMessage::SessionSnapshot { session }));
+        for subscriber in &self.subscribers {
+            if subscriber.overflowed.load(Ordering::Acquire) {
+                resync_subscriber(subscriber, snapshot.as_ref());
+            }
+        }
+    }
+}
+
+fn resync_subscriber(subscriber: &Subscriber, snapshot: Option<&Arc<SharedMessage>>) {
+    let Some(snapshot) = snapshot.filter(|_| subscriber.is_drained()) else {
+        return;
+    };
+    if subscriber.tx.try_send(snapshot.clone()).is_ok() {
+        subscriber.overflowed.store(false, Ordering::Release);
+    }
+}
+
+/// A subscription to one session's messages.
+pub struct SessionReceiver {
+    rx: mpsc::Receiver<Arc<SharedMessage>>,
+    overflowed: Arc<AtomicBool>,
+    commands: CommandSlot,
+    resync_requested: bool,
+}
+
+impl SessionReceiver {
+    /// The next message, or `None` once the session is gone. After an
+    /// overflow this is a `SessionSnapshot` that replaces what was skipped.
+    pub async fn recv(&mut self) -> Option<Arc<SharedMessage>> {
+        match self.rx.try_recv() {
+            Ok(msg) => return Some(msg),
+            Err(TryRecvError::Disconnected) => return None,
+            Err(TryRecvError::Empty) => {}
+        }
+        if !self.overflowed.load(Ordering::Acquire) {
+            self.resync_requested = false;
+        } else if !self.resync_requested {
+            // Drained after an overflow; ask now rather than waiting for the
+            // session's next message, which may never come.
+            self.resync_requested = true;
+            self.request_resync();
+        }
+        self.rx.recv().await
+    }
+
+    /// `recv`, for subscribers that only need the message itself.
+    pub async fn recv_message(&mut self) -> Option<ServerMessage> {
+        let shared = self.recv().await?;
+        Some(shared.message.clone())
+    }
+
+    fn request_resync(&self) {
+        let command_tx = self
+            .commands
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .and_then(mpsc::WeakSender::upgrade);
+        if let Some(command_tx) = command_tx {
+            tokio::spawn(async move {
+                let _ = command_tx.send(SessionCommand::ResyncSubscribers).await;
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orbitdock_protocol::Provider;
+
+    use crate::session::SessionHandle;
+    use crate::session_actor::SessionActorHandle;
+
+    fn ping(n: u64) -> ServerMessage {
+        ServerMessage::Error {
+            code: "ping".to_string(),
+            message: n.to_string(),
+            session_id: None,
+            error: None,
+        }
+    }
+
+    fn test_handle() -> SessionHandle {
+        SessionHandle::new(
+            "fanout-session".to_string(),
+            Provider::Claude,
+            "/tmp/fanout".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn slow_subscriber_is_resynced_without_slowing_others() {
+        let mut handle = test_handle();
+        let mut slow = handle.subscribe();
+        let mut fast = handle.subscribe();
+
+        let total = SUBSCRIBER_QUEUE_CAPACITY as u64 + 10;
+        for n in 0..total {
+            handle.broadcast(ping(n));
+            assert!(matches!(
+                fast.recv_message().await,
+                Some(ServerMessage::Error { .. })
+            ));
+        }
+
+        for _ in 0..SUBSCRIBER_QUEUE_CAPACITY {
+            assert!(matches!(
+                slow.recv_message().await,
+                Some(ServerMessage::Error { .. })
+            ));
+        }
+        // Drained: the next broadcast reaches it as a snapshot that covers
+        // everything it missed, then messages flow again.
+        handle.broadcast(ping(total));
+        match slow.recv_message().await {
+            Some(ServerMessage::SessionSnapshot { session }) => {
+                assert_eq!(session.revision, Some(total + 1));
+            }
+            other => panic!("expected resync snapshot, got {other:?}"),
+        }
+        handle.broadcast(ping(total + 1));
+        assert!(matches!(
+            slow.recv_message().await,
+            Some(ServerMessage::Error { .. })
+        ));
+        assert_eq!(handle.subscriber_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn broadcasts_are_serialized_once_for_all_subscribers() {
+        let mut handle = test_handle();
+        let mut first = handle.subscribe();
+        let mut second = handle.subscribe();
+
+        handle.broadcast(ping(7));
+        let a = first.recv().await.unwrap();
+        let b = second.recv().await.unwrap();
+        let (Some(a_json), Some(b_json)) = (&a.json, &b.json) else {
+            panic!("expected serialized payloads");
+        };
+        assert!(Arc::ptr_eq(a_json, b_json));
+        let decoded: ServerMessage = serde_json::from_str(a_json).unwrap();
+        assert!(matches!(decoded, ServerMessage::Error { message, .. } if message == "7"));
+    }
+
+    #[tokio::test]
+    async fn drained_subscriber_asks_the_actor_for_a_snapshot() {
+        let mut handle = test_handle();
+        let mut rx = handle.subscribe();
+        let (persist_tx, _persist_rx) = mpsc::channel(64);
+        let actor = SessionActorHandle::spawn(handle, persist_tx);
+
+        let total = SUBSCRIBER_QUEUE_CAPACITY as u64 + 10;
+        for n in 0..total {
+            actor.send(SessionCommand::Broadcast { msg: ping(n) }).await;
+        }
+        let (reply, state) = tokio::sync::oneshot::channel();
+        actor.send(SessionCommand::GetState { reply }).await;
+        assert_eq!(state.await.unwrap().revision, Some(total));
+
+        for _ in 0..SUBSCRIBER_QUEUE_CAPACITY {
+            assert!(matches!(
+                rx.recv_message().await,
+                Some(ServerMessage::Error { .. })
+            ));
+        }
+
+        // Nothing else is broadcast; the snapshot still arrives with the last
+        // revision.
+        let resync = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv_message())
+            .await
+            .expect("resync snapshot");
+        match resync {
+            Some(ServerMessage::SessionSnapshot { session }) => {
+                assert_eq!(session.revision, Some(total));
+            }
+            other => panic!("expected resync snapshot, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! MCP servers added to a session from OrbitDock.
+//!
+//! Providers otherwise only know the MCP servers in their own dotfiles
+//! (`~/.codex/config.toml`, `~/.claude.json`), which OrbitDock can't see or
+//! change. A session's servers live in `session_mcp_servers`, with env values
+//! encrypted, and are loaded into the registry at startup. They're passed to
+//! the connector when it's created (Codex config overrides, Claude's
+//! `--mcp-config`) and pushed to a running one whenever they change.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use orbitdock_protocol::SessionMcpServer;
+use rusqlite::Connection;
+use tracing::warn;
+
+use crate::claude_session::ClaudeAction;
+use crate::codex_session::CodexAction;
+use crate::persistence::PersistCommand;
+use crate::state::SessionRegistry;
+
+/// Most servers a session can carry.
+const MAX_SERVERS: usize = 32;
+const MAX_NAME_CHARS: usize = 64;
+
+/// A server as written to `session_mcp_servers`.
+#[derive(Debug, Clone)]
+pub struct StoredServer {
+    pub name: String,
+    pub command: String,
+    /// JSON array
+    pub args: String,
+    /// Encrypted JSON object, or `None` without env vars
+    pub env: Option<String>,
+    pub enabled: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum McpServerError {
+    #[error("MCP server names may only use letters, digits, '-' and '_' (at most 64)")]
+    InvalidName,
+    #[error("MCP server command is required")]
+    MissingCommand,
+    #[error("A session can have at most {MAX_SERVERS} MCP servers")]
+    TooMany,
+    #[error("No MCP server named {0}")]
+    NotFound(String),
+    #[error("Could not encrypt MCP server env: {0}")]
+    Encrypt(String),
+    #[error("MCP servers can't be added or changed while the shell allowlist is enabled")]
+    ShellRestricted,
+}
+
+/// Names become config keys (`mcp_servers.<name>`) for Codex, so keep them
+/// to what both providers accept without quoting.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_NAME_CHARS
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+}
+
+/// The servers with env values replaced by [`SessionMcpServer::REDACTED`].
+pub fn redacted(servers: Vec<SessionMcpServer>) -> Vec<SessionMcpServer> {
+    servers
+        .into_iter()
+        .map(|mut server| {
+            for value in server.env.values_mut() {
+                *value = SessionMcpServer::REDACTED.to_string();
+            }
+            server
+        })
+        .collect()
+}
+
+/// Add `server` to the session, or replace the one with its name. Env values
+/// sent back redacted keep what's stored.
+pub async fn upsert(
+    state: &SessionRegistry,
+    session_id: &str,
+    mut server: SessionMcpServer,
+) -> Result<Vec<SessionMcpServer>, McpServerError> {
+    server.name = server.name.trim().to_string();
+    server.command = server.command.trim().to_string();
+    if !valid_name(&server.name) {
+        return Err(McpServerError::InvalidName);
+    }
+    if server.command.is_empty() {
+        return Err(McpServerError::MissingCommand);
+    }
+    // The connector spawns `command` as-is, which would sidestep the allowlist.
+    if state.shell_policy().is_restricted() {
+        warn!(
+            component = "session_mcp",
+            event = "session_mcp.denied",
+            session_id = %session_id,
+            server = %server.name,
+            "MCP server change denied while shell allowlist is enabled"
+        );
+        return Err(McpServerError::ShellRestricted);
+    }
+
+    let mut servers = state.session_mcp_servers(session_id);
+    match servers
+        .iter_mut()
+        .find(|existing| existing.name == server.name)
+    {
+        Some(existing) => {
+            keep_redacted_env(&mut server.env, &existing.env);
+            *existing = server;
+        }
+        None => {
+            if servers.len() >= MAX_SERVERS {
+                return Err(McpServerError::TooMany);
+            }
+            server
+                .env
+                .retain(|_, value| value != SessionMcpServer::REDACTED);
+            servers.push(server);
+        }
+    }
+    set(state, session_id, servers.clone()).await?;
+    Ok(servers)
+}
+
+fn keep_redacted_env(env: &mut BTreeMap<String, String>, stored: &BTreeMap<String, String>) {
+    env.retain(|key, value| {
+        if value != SessionMcpServer::REDACTED {
+            return true;
+        }
+        match stored.get(key) {
+            Some(stored) => {
+                value.clone_from(stored);
+                true
+            }
+            None => false,
+        }
+    });
+}
+
+pub async fn remove(
+    state: &SessionRegistry,
+    session_id: &str,
+    name: &str,
+) -> Result<Vec<SessionMcpServer>, McpServerError> {
+    let mut servers = state.session_mcp_servers(session_id);
+    let before = servers.len();
+    servers.retain(|server| server.name != name);
+    if servers.len() == before {
+        return Err(McpServerError::NotFound(name.to_string()));
+    }
+    set(state, session_id, servers.clone()).await?;
+    Ok(servers)
+}
+
+/// Replace a session's servers everywhere: registry, database, and the
+/// running connector if there is one. Without one, they apply when it starts.
+async fn set(
+    state: &SessionRegistry,
+    session_id: &str,
+    servers: Vec<SessionMcpServer>,
+) -> Result<(), McpServerError> {
+    let stored = servers
+        .iter()
+        .map(to_stored)
+        .collect::<Result<Vec<_>, _>>()?;
+    state.set_session_mcp_servers(session_id, servers.clone());
+    let _ = state
+        .persist()
+        .send(PersistCommand::SessionMcpServersSet {
+            session_id: session_id.to_string(),
+            servers: stored,
+        })
+        .await;
+
+    if let Some(tx) = state.get_codex_action_tx(session_id) {
+        let _ = tx.send(CodexAction::RefreshMcpServers { servers }).await;
+    } else if let Some(tx) = state.get_claude_action_tx(session_id) {
+        let servers = orbitdock_connector_claude::mcp_servers_config(&servers);
+        let _ = tx.send(ClaudeAction::McpSetServers { servers }).await;
+    }
+    Ok(())
+}
+
+fn to_stored(server: &SessionMcpServer) -> Result<StoredServer, McpServerError> {
+    let env = if server.env.is_empty() {
+        None
+    } else {
+        let json = serde_json::to_string(&server.env).unwrap_or_default();
+        Some(crate::crypto::encrypt(&json).map_err(|e| McpServerError::Encrypt(e.to_string()))?)
+    };
+    Ok(StoredServer {
+        name: server.name.clone(),
+        command: server.command.clone(),
+        args: serde_json::to_string(&server.args).unwrap_or_else(|_| "[]".to_string()),
+        env,
+        enabled: server.enabled,
+    })
+}
+
+/// Saved servers, loaded once at startup.
+pub fn load_all(db_path: &Path) -> Vec<(String, Vec<SessionMcpServer>)> {
+    let result = Connection::open(db_path).and_then(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT session_id, name, command, args, env, enabled
+             FROM session_mcp_servers
+             ORDER BY session_id, position",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let session_id: String = row.get(0)?;
+            let args: String = row.get(3)?;
+            let env: Option<String> = row.get(4)?;
+            let server = SessionMcpServer {
+                name: row.get(1)?,
+                command: row.get(2)?,
+                args: serde_json::from_str(&args).unwrap_or_default(),
+                env: env
+                    .as_deref()
+                    .and_then(crate::crypto::decrypt)
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default(),
+                enabled: row.get(5)?,
+            };
+            Ok((session_id, server))
+        })?;
+        let mut servers: Vec<(String, Vec<SessionMcpServer>)> = Vec::new();
+        for row in rows {
+            let (session_id, server) = row?;
+            match servers.last_mut() {
+                Some((last, session_servers)) if *last == session_id => {
+                    session_servers.push(server)
+                }
+                _ => servers.push((session_id, vec![server])),
+            }
+        }
+        Ok(servers)
+    });
+    match result {
+        Ok(servers) => servers,
+        Err(e) => {
+            warn!(
+                component = "session_mcp",
+                event = "session_mcp.load_failed",
+                error = %e,
+                "Could not load session MCP servers"
+            );
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_must_be_plain_config_keys() {
+        assert!(valid_name("github"));
+        assert!(valid_name("my_server-2"));
+        assert!(!valid_name(""));
+        assert!(!valid_name("git.hub"));
+        assert!(!valid_name("has space"));
+        assert!(!valid_name(&"x".repeat(65)));
+    }
+
+    #[test]
+    fn redacted_env_values_keep_what_is_stored() {
+        let stored = BTreeMap::from([
+            ("GITHUB_TOKEN".to_string(), "ghp_secret".to_string()),
+            ("LOG".to_string(), "info".to_string()),
+        ]);
+        let mut env = BTreeMap::from([
+            (
+                "GITHUB_TOKEN".to_string(),
+                SessionMcpServer::REDACTED.to_string(),
+            ),
+            ("LOG".to_string(), "debug".to_string()),
+            ("NEW".to_string(), SessionMcpServer::REDACTED.to_string()),
+        ]);
+        keep_redacted_env(&mut env, &stored);
+        assert_eq!(
+            env,
+            BTreeMap::from([
+                ("GITHUB_TOKEN".to_string(), "ghp_secret".to_string()),
+                ("LOG".to_string(), "debug".to_string()),
+            ])
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Topic tags on sessions.
+//!
+//! Tags are short lowercase labels ("auth", "migration", "tests") that let
+//! clients group and filter the session list. They live in `session_tags`,
+//! are loaded into the registry at startup, and ride along on session
+//! summaries. `SetSessionTags` replaces them by hand; otherwise, once a
+//! session completes `[naming] tag_after_turns` turns (3 by default), one
+//! background task asks the naming provider for 1-3 tags from the
+//! conversation so far. Automatic tags never replace tags a session
+//! already has.
+
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use orbitdock_protocol::ServerMessage;
+use rusqlite::Connection;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::ai_naming::{self, Task};
+use crate::persistence::PersistCommand;
+use crate::session_command::SessionCommand;
+use crate::state::SessionRegistry;
+
+const DEFAULT_TAG_AFTER_TURNS: u32 = 3;
+/// Most tags a session can carry.
+const MAX_TAGS: usize = 10;
+/// Most tags the naming provider assigns.
+const MAX_AUTO_TAGS: usize = 3;
+const MAX_TAG_CHARS: usize = 32;
+const QUEUE_CAPACITY: usize = 64;
+const TAG_TIMEOUT: Duration = Duration::from_secs(30);
+
+static REQUESTS: OnceLock<mpsc::Sender<TagRequest>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagSource {
+    Manual,
+    Auto,
+}
+
+impl TagSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            TagSource::Manual => "manual",
+            TagSource::Auto => "auto",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TagRequest {
+    pub session_id: String,
+    /// The session's turns so far, from `turn_summary::recent_transcript`.
+    pub transcript: String,
+}
+
+/// How many turns to tag from, when `turn_count` is the turn to tag after.
+pub fn due(turn_count: u64) -> Option<usize> {
+    let after = crate::config_file::current()
+        .naming
+        .tag_after_turns
+        .unwrap_or(DEFAULT_TAG_AFTER_TURNS);
+    (after > 0 && turn_count == u64::from(after)).then_some(after as usize)
+}
+
+/// Queue automatic tagging. A no-op until the tagger is running.
+pub fn request(request: TagRequest) {
+    let Some(tx) = REQUESTS.get() else {
+        return;
+    };
+    if tx.try_send(request).is_err() {
+        warn!(
+            component = "session_tags",
+            event = "session_tags.dropped",
+            "Session tag queue is full; dropping request"
+        );
+    }
+}
+
+/// Lowercase and hyphenate `tags`, dropping duplicates and any left empty.
+pub fn normalize<I, S>(tags: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag: String = tag
+            .as_ref()
+            .trim()
+            .to_lowercase()
+            .chars()
+            .map(|c| {
+                if c.is_whitespace() || c == '_' {
+                    '-'
+                } else {
+                    c
+                }
+            })
+            .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '.' | '/'))
+            .take(MAX_TAG_CHARS)
+            .collect();
+        let tag = tag.trim_matches('-');
+        if !tag.is_empty() && !normalized.iter().any(|existing| existing == tag) {
+            normalized.push(tag.to_string());
+        }
+        if normalized.len() == MAX_TAGS {
+            break;
+        }
+    }
+    normalized
+}
+
+/// Tags from a provider reply: a comma- or line-separated list.
+fn parse_reply(reply: &str) -> Vec<String> {
+    let mut tags = normalize(reply.split([',', '\n']));
+    tags.truncate(MAX_AUTO_TAGS);
+    tags
+}
+
+/// Saved tags, loaded once at startup.
+pub fn load_all(db_path: &Path) -> Vec<(String, Vec<String>)> {
+    let result = Connection::open(db_path).and_then(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT session_id, tag FROM session_tags ORDER BY session_id, position")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut tags: Vec<(String, Vec<String>)> = Vec::new();
+        for row in rows {
+            let (session_id, tag) = row?;
+            match tags.last_mut() {
+                Some((last, session_tags)) if *last == session_id => session_tags.push(tag),
+                _ => tags.push((session_id, vec![tag])),
+            }
+        }
+        Ok(tags)
+    });
+    match result {
+        Ok(tags) => tags,
+        Err(e) => {
+            warn!(
+                component = "session_tags",
+                event = "session_tags.load_failed",
+                error = %e,
+                "Could not load session tags"
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Replace a session's tags everywhere: registry, live session, database,
+/// and list subscribers.
+pub async fn set(state: &SessionRegistry, session_id: &str, tags: Vec<String>, source: TagSource) {
+    state.set_session_tags(session_id, tags.clone());
+    if let Some(actor) = state.get_session(session_id) {
+        actor
+            .send(SessionCommand::SetTags { tags: tags.clone() })
+            .await;
+    }
+    let _ = state
+        .persist()
+        .send(PersistCommand::SessionTagsSet {
+            session_id: session_id.to_string(),
+            tags: tags.clone(),
+            source: source.as_str(),
+        })
+        .await;
+    state.broadcast_to_list(ServerMessage::SessionTagsChanged {
+        session_id: session_id.to_string(),
+        tags,
+    });
+}
+
+pub async fn start_tagger(state: Arc<SessionRegistry>) {
+    let (tx, mut rx) = mpsc::channel(QUEUE_CAPACITY);
+    if REQUESTS.set(tx).is_err() {
+        return;
+    }
+
+    while let Some(request) = rx.recv().await {
+        if !state.session_tags(&request.session_id).is_empty() {
+            continue;
+        }
+        let Some(provider) = ai_naming::provider() else {
+            continue;
+        };
+        let result = tokio::time::timeout(
+            TAG_TIMEOUT,
+            provider.generate(Task::SessionTags, &request.transcript),
+        )
+        .await
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out")));
+
+        let tags = match result {
+            Ok(Some(reply)) => parse_reply(&reply),
+            Ok(None) => continue,
+            Err(e) => {
+                warn!(
+                    component = "session_tags",
+                    event = "session_tags.failed",
+                    session_id = %request.session_id,
+                    provider = provider.label(),
+                    error = %e,
+                    "Failed to tag session"
+                );
+                continue;
+            }
+        };
+        // Skip empty replies and sessions tagged by hand in the meantime.
+        if tags.is_empty() || !state.session_tags(&request.session_id).is_empty() {
+            continue;
+        }
+        info!(
+            component = "session_tags",
+            event = "session_tags.tagged",
+            session_id = %request.session_id,
+            tags = %tags.join(","),
+        );
+        set(&state, &request.session_id, tags, TagSource::Auto).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_lowercases_hyphenates_and_dedupes() {
+        assert_eq!(
+            normalize([
+                "Auth",
+                " auth ",
+                "DB migration",
+                "#tests",
+                "snake_case",
+                "  ",
+                "-"
+            ]),
+            vec!["auth", "db-migration", "tests", "snake-case"]
+        );
+        assert_eq!(normalize((0..20).map(|i| format!("t{i}"))).len(), MAX_TAGS);
+    }
+
+    #[test]
+    fn provider_reply_yields_at_most_three_tags() {
+        assert_eq!(
+            parse_reply("auth, Migration\ntests, ci, docs"),
+            vec!["auth", "migration", "tests"]
+        );
+        assert!(parse_reply("").is_empty());
+    }
+
+    #[test]
+    fn tags_after_the_third_turn_by_default() {
+        assert_eq!(due(2), None);
+        assert_eq!(due(3), Some(3));
+        assert_eq!(due(6), None);
+    }
+}
@@ -4,6 +4,7 @@
 //! syncing, and path resolution. Used by WebSocket handlers, hook
 //! handlers, and session management code.
 
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -14,9 +15,8 @@ use orbitdock_protocol::{
     StateChanges, TokenUsageSnapshotKind, WorkStatus,
 };
 
-use crate::persistence::{
-    load_messages_from_transcript_path, load_token_usage_from_transcript_path, PersistCommand,
-};
+use crate::persistence::{load_transcript_tail_from_path, PersistCommand};
+use crate::session::SessionSnapshot;
 use crate::session_actor::SessionActorHandle;
 use crate::session_command::{PersistOp, SessionCommand};
 use crate::state::SessionRegistry;
@@ -91,6 +91,7 @@ pub(crate) fn direct_mode_activation_changes(provider: Provider) -> StateChanges
     let mut changes = StateChanges {
         status: Some(SessionStatus::Active),
         work_status: Some(WorkStatus::Waiting),
+        steerable: Some(true),
         ..Default::default()
     };
 
@@ -106,6 +107,19 @@ pub(crate) fn direct_mode_activation_changes(provider: Provider) -> StateChanges
     changes
 }
 
+/// Whether OrbitDock only observes the session through hooks or its
+/// transcript, rather than driving it with a connector. Hook-created Claude
+/// sessions have no integration mode, so they count as passive.
+pub(crate) fn is_passive_session(snap: &SessionSnapshot) -> bool {
+    match snap.provider {
+        Provider::Codex => {
+            snap.codex_integration_mode == Some(CodexIntegrationMode::Passive)
+                || (snap.codex_integration_mode.is_none() && snap.transcript_path.is_some())
+        }
+        Provider::Claude => snap.claude_integration_mode != Some(ClaudeIntegrationMode::Direct),
+    }
+}
+
 pub(crate) fn parse_unix_z(value: Option<&str>) -> Option<u64> {
     let raw = value?;
     let stripped = raw.strip_suffix('Z').unwrap_or(raw);
@@ -154,19 +168,25 @@ pub(crate) fn project_name_from_cwd(cwd: &str) -> Option<String> {
 }
 
 pub(crate) fn claude_transcript_path_from_cwd(cwd: &str, session_id: &str) -> Option<String> {
-    let home = std::env::var("HOME").ok()?;
     let trimmed = cwd.trim_start_matches('/');
     if trimmed.is_empty() {
         return None;
     }
     let dir = format!("-{}", trimmed.replace('/', "-"));
-    Some(format!(
-        "{}/.claude/projects/{}/{}.jsonl",
-        home, dir, session_id
-    ))
+    let candidates: Vec<PathBuf> = crate::provider_homes::claude_project_dirs()
+        .into_iter()
+        .map(|projects| projects.join(&dir).join(format!("{session_id}.jsonl")))
+        .collect();
+    // The home that has the transcript, else the default one.
+    let path = candidates
+        .iter()
+        .find(|path| path.exists())
+        .or_else(|| candidates.first())?;
+    Some(path.to_string_lossy().to_string())
 }
 
-/// Re-read a session's transcript and broadcast any new messages to subscribers.
+/// Read what was appended to a session's transcript and broadcast any new
+/// messages to subscribers.
 /// Works for any hook-triggered session (Claude CLI, future Codex CLI hooks).
 pub(crate) async fn sync_transcript_messages(
     actor: &SessionActorHandle,
@@ -180,13 +200,13 @@ pub(crate) async fn sync_transcript_messages(
     let session_id = snap.id.clone();
     let existing_count = snap.message_count;
 
-    let all_messages = match load_messages_from_transcript_path(&transcript_path, &session_id).await
-    {
-        Ok(msgs) => msgs,
-        Err(_) => return,
-    };
+    let tail =
+        match load_transcript_tail_from_path(&transcript_path, &session_id, existing_count).await {
+            Ok(tail) => tail,
+            Err(_) => return,
+        };
 
-    if let Ok(Some(usage)) = load_token_usage_from_transcript_path(&transcript_path).await {
+    if let Some(usage) = tail.usage {
         let current_usage = &snap.token_usage;
         if usage.input_tokens != current_usage.input_tokens
             || usage.output_tokens != current_usage.output_tokens
@@ -207,11 +227,11 @@ pub(crate) async fn sync_transcript_messages(
         }
     }
 
-    if all_messages.len() <= existing_count {
+    if tail.messages.is_empty() {
         return;
     }
 
-    let new_messages = all_messages[existing_count..].to_vec();
+    let new_messages = tail.messages;
 
     // Double-check count hasn't changed while we were reading
     let (count_tx, count_rx) = oneshot::channel();
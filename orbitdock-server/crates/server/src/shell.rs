@@ -4,12 +4,12 @@
 //! Provider-independent - works alongside any AI session.
 
 use std::process::Stdio;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 use dashmap::{mapref::entry::Entry, DashMap};
 use tokio::io::AsyncReadExt;
-use tokio::process::Command;
+use tokio::process::{Child, Command};
 use tokio::sync::{mpsc, oneshot, watch};
 use tokio::task::JoinHandle;
 
@@ -64,6 +64,23 @@ pub struct ShellService {
 struct ActiveShellExecution {
     session_id: String,
     cancel_tx: watch::Sender<bool>,
+    /// Detached jobs outlive their session, ignore the timeout, and are only
+    /// stopped by an explicit cancel.
+    detached: bool,
+    /// Output captured so far, so `GetJobOutput` can report a running job.
+    live_output: Arc<Mutex<ShellChunk>>,
+}
+
+/// Per-stream cap on output retained for background jobs (keeps the tail).
+pub const MAX_JOB_OUTPUT_BYTES: usize = 256 * 1024;
+
+/// Limits on a single command run.
+#[derive(Debug, Clone, Copy)]
+struct RunLimits {
+    /// Kill the command after this long; `None` lets it run until it exits.
+    timeout_secs: Option<u64>,
+    /// Keep only this much of the tail of each stream.
+    max_output_bytes: Option<usize>,
 }
 
 impl ShellService {
@@ -78,31 +95,52 @@ impl ShellService {
         command: String,
         cwd: String,
         timeout_secs: u64,
+        detached: bool,
     ) -> Result<ShellExecution, ShellStartError> {
         let (chunk_tx, chunk_rx) = mpsc::unbounded_channel();
         let (completion_tx, completion_rx) = oneshot::channel();
         let (cancel_tx, cancel_rx) = watch::channel(false);
+        let live_output = Arc::new(Mutex::new(ShellChunk {
+            stdout: String::new(),
+            stderr: String::new(),
+        }));
 
         match self.active.entry(request_id.clone()) {
             Entry::Vacant(entry) => {
                 entry.insert(ActiveShellExecution {
                     session_id,
                     cancel_tx,
+                    detached,
+                    live_output: live_output.clone(),
                 });
             }
             Entry::Occupied(_) => return Err(ShellStartError::DuplicateRequestId),
         }
 
+        // Tee chunks into the live buffer before handing them to the caller.
+        let (tee_tx, mut tee_rx) = mpsc::unbounded_channel::<ShellChunk>();
+        tokio::spawn(async move {
+            while let Some(chunk) = tee_rx.recv().await {
+                {
+                    let mut live = live_output.lock().unwrap_or_else(|e| e.into_inner());
+                    live.stdout.push_str(&chunk.stdout);
+                    live.stderr.push_str(&chunk.stderr);
+                    truncate_output_tail(&mut live.stdout, MAX_JOB_OUTPUT_BYTES);
+                    truncate_output_tail(&mut live.stderr, MAX_JOB_OUTPUT_BYTES);
+                }
+                let _ = chunk_tx.send(chunk);
+            }
+        });
+
+        let limits = RunLimits {
+            timeout_secs: (!detached).then_some(timeout_secs),
+            max_output_bytes: detached.then_some(MAX_JOB_OUTPUT_BYTES),
+        };
         let active = self.active.clone();
         tokio::spawn(async move {
-            let result = execute_with_stream_cancelable(
-                &command,
-                &cwd,
-                timeout_secs,
-                Some(chunk_tx),
-                cancel_rx,
-            )
-            .await;
+            let result =
+                execute_with_stream_cancelable(&command, &cwd, limits, Some(tee_tx), cancel_rx)
+                    .await;
             active.remove(&request_id);
             let _ = completion_tx.send(result);
         });
@@ -131,11 +169,12 @@ impl ShellService {
         }
     }
 
+    /// Cancel a session's attached commands. Detached jobs keep running.
     pub fn cancel_session(&self, session_id: &str) -> usize {
         let request_ids: Vec<String> = self
             .active
             .iter()
-            .filter(|entry| entry.value().session_id == session_id)
+            .filter(|entry| entry.value().session_id == session_id && !entry.value().detached)
             .map(|entry| entry.key().clone())
             .collect();
 
@@ -148,6 +187,28 @@ impl ShellService {
 
         canceled
     }
+
+    /// Output captured so far for a running command, as `(stdout, stderr)`.
+    pub fn live_output(&self, session_id: &str, request_id: &str) -> Option<(String, String)> {
+        let entry = self.active.get(request_id)?;
+        if entry.session_id != session_id {
+            return None;
+        }
+        let live = entry.live_output.lock().unwrap_or_else(|e| e.into_inner());
+        Some((live.stdout.clone(), live.stderr.clone()))
+    }
+}
+
+/// Drop the head of `output` so it is at most `max_bytes`, on a char boundary.
+pub fn truncate_output_tail(output: &mut String, max_bytes: usize) {
+    if output.len() <= max_bytes {
+        return;
+    }
+    let mut cut = output.len() - max_bytes;
+    while !output.is_char_boundary(cut) {
+        cut += 1;
+    }
+    output.drain(..cut);
 }
 
 /// Execute a shell command and optionally stream incremental output chunks.
@@ -159,19 +220,23 @@ pub async fn execute_with_stream(
     chunk_tx: Option<mpsc::UnboundedSender<ShellChunk>>,
 ) -> ShellResult {
     let (_cancel_tx, cancel_rx) = watch::channel(false);
-    execute_with_stream_cancelable(command, cwd, timeout_secs, chunk_tx, cancel_rx).await
+    let limits = RunLimits {
+        timeout_secs: Some(timeout_secs),
+        max_output_bytes: None,
+    };
+    execute_with_stream_cancelable(command, cwd, limits, chunk_tx, cancel_rx).await
 }
 
 async fn execute_with_stream_cancelable(
     command: &str,
     cwd: &str,
-    timeout_secs: u64,
+    limits: RunLimits,
     chunk_tx: Option<mpsc::UnboundedSender<ShellChunk>>,
     mut cancel_rx: watch::Receiver<bool>,
 ) -> ShellResult {
     let start = Instant::now();
 
-    let result = run_command(command, cwd, timeout_secs, chunk_tx, &mut cancel_rx).await;
+    let result = run_command(command, cwd, limits, chunk_tx, &mut cancel_rx).await;
     let duration_ms = start.elapsed().as_millis() as u64;
 
     match result {
@@ -197,7 +262,10 @@ async fn execute_with_stream_cancelable(
             outcome: ShellOutcome::Failed,
         },
         Err(RunCommandError::Timeout { stdout, stderr }) => {
-            let timeout_msg = format!("Command timed out after {timeout_secs}s");
+            let timeout_msg = format!(
+                "Command timed out after {}s",
+                limits.timeout_secs.unwrap_or_default()
+            );
             let stderr = if stderr.is_empty() {
                 timeout_msg
             } else {
@@ -243,16 +311,19 @@ enum StreamKind {
 async fn run_command(
     command: &str,
     cwd: &str,
-    timeout_secs: u64,
+    limits: RunLimits,
     chunk_tx: Option<mpsc::UnboundedSender<ShellChunk>>,
     cancel_rx: &mut watch::Receiver<bool>,
 ) -> Result<(String, String, i32), RunCommandError> {
+    // Run in a fresh process group so cancel/timeout can take down everything
+    // `sh -c` spawned, not just the shell itself.
     let mut child = Command::new("sh")
         .arg("-c")
         .arg(command)
         .current_dir(cwd)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
+        .process_group(0)
         .spawn()
         .map_err(RunCommandError::Io)?;
 
@@ -265,24 +336,40 @@ async fn run_command(
         .take()
         .ok_or_else(|| RunCommandError::Io(std::io::Error::other("stderr pipe unavailable")))?;
 
-    let stdout_task = tokio::spawn(read_stream(stdout, StreamKind::Stdout, chunk_tx.clone()));
-    let stderr_task = tokio::spawn(read_stream(stderr, StreamKind::Stderr, chunk_tx));
-
+    let stdout_task = tokio::spawn(read_stream(
+        stdout,
+        StreamKind::Stdout,
+        limits.max_output_bytes,
+        chunk_tx.clone(),
+    ));
+    let stderr_task = tokio::spawn(read_stream(
+        stderr,
+        StreamKind::Stderr,
+        limits.max_output_bytes,
+        chunk_tx,
+    ));
+
+    let wait = async {
+        match limits.timeout_secs {
+            Some(secs) => {
+                tokio::time::timeout(std::time::Duration::from_secs(secs), child.wait()).await
+            }
+            None => Ok(child.wait().await),
+        }
+    };
     let status = tokio::select! {
-        status = tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), child.wait()) => {
+        status = wait => {
             match status {
                 Ok(Ok(status)) => Some(status),
                 Ok(Err(e)) => return Err(RunCommandError::Io(e)),
                 Err(_) => {
-                    let _ = child.kill().await;
-                    let _ = child.wait().await;
+                    kill_process_group(&mut child).await;
                     None
                 }
             }
         }
         _ = wait_for_cancel(cancel_rx) => {
-            let _ = child.kill().await;
-            let _ = child.wait().await;
+            kill_process_group(&mut child).await;
             return finalize_canceled(stdout_task, stderr_task).await;
         }
     };
@@ -296,6 +383,22 @@ async fn run_command(
     }
 }
 
+/// Kill the command's entire process group and reap the shell.
+///
+/// Killing only `sh` leaves grandchildren (`npm install`, `sleep`, ...) running
+/// and holding the stdout/stderr pipes open, so the readers would never finish.
+pub(crate) async fn kill_process_group(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        // SAFETY: killpg only sends a signal; the group id is the child's pid
+        // because it was spawned with `process_group(0)`.
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill().await;
+    let _ = child.wait().await;
+}
+
 async fn finalize_canceled(
     stdout_task: JoinHandle<Result<String, std::io::Error>>,
     stderr_task: JoinHandle<Result<String, std::io::Error>>,
@@ -319,9 +422,11 @@ async fn wait_for_cancel(cancel_rx: &mut watch::Receiver<bool>) {
     }
 }
 
+/// Read a stream to the end, keeping the last `max_bytes` of it if set.
 async fn read_stream<R>(
     mut reader: R,
     stream_kind: StreamKind,
+    max_bytes: Option<usize>,
     chunk_tx: Option<mpsc::UnboundedSender<ShellChunk>>,
 ) -> Result<String, std::io::Error>
 where
@@ -338,6 +443,11 @@ where
 
         let chunk = String::from_utf8_lossy(&buf[..n]).into_owned();
         full_output.push_str(&chunk);
+        // Trim in batches so long-running jobs don't shift the buffer on
+        // every read.
+        if let Some(max_bytes) = max_bytes.filter(|max| full_output.len() > max * 2) {
+            truncate_output_tail(&mut full_output, max_bytes);
+        }
 
         if let Some(tx) = &chunk_tx {
             let _ = match stream_kind {
@@ -353,6 +463,9 @@ where
         }
     }
 
+    if let Some(max_bytes) = max_bytes {
+        truncate_output_tail(&mut full_output, max_bytes);
+    }
     Ok(full_output)
 }
 
@@ -371,7 +484,8 @@ async fn join_reader(
 #[cfg(test)]
 mod tests {
     use super::{
-        execute_with_stream, ShellCancelStatus, ShellOutcome, ShellService, ShellStartError,
+        execute_with_stream, truncate_output_tail, ShellCancelStatus, ShellOutcome, ShellService,
+        ShellStartError, MAX_JOB_OUTPUT_BYTES,
     };
     use tokio::time::{timeout, Duration};
 
@@ -391,6 +505,17 @@ mod tests {
         assert!(result.stderr.contains("timed out"));
     }
 
+    #[tokio::test]
+    async fn timeout_kills_background_children_holding_output_pipes() {
+        let result = timeout(
+            Duration::from_secs(5),
+            execute_with_stream("sleep 30 & sleep 30; wait", "/tmp", 1, None),
+        )
+        .await
+        .expect("timed-out command should release its pipes");
+        assert_eq!(result.outcome, ShellOutcome::TimedOut);
+    }
+
     #[tokio::test]
     async fn shell_service_can_cancel_running_command() {
         let service = ShellService::new();
@@ -404,6 +529,7 @@ mod tests {
                 "sleep 30".to_string(),
                 "/tmp".to_string(),
                 60,
+                false,
             )
             .expect("start");
 
@@ -432,6 +558,7 @@ mod tests {
                 "sleep 10".to_string(),
                 "/tmp".to_string(),
                 30,
+                false,
             )
             .expect("first start");
 
@@ -441,7 +568,86 @@ mod tests {
             "echo nope".to_string(),
             "/tmp".to_string(),
             30,
+            false,
         );
         assert_eq!(second.err(), Some(ShellStartError::DuplicateRequestId));
     }
+
+    #[tokio::test]
+    async fn cancel_session_leaves_detached_jobs_running() {
+        let service = ShellService::new();
+        let session_id = "sess-jobs".to_string();
+
+        let _attached = service
+            .start(
+                "req-attached".to_string(),
+                session_id.clone(),
+                "sleep 10".to_string(),
+                "/tmp".to_string(),
+                30,
+                false,
+            )
+            .expect("attached start");
+        let detached = service
+            .start(
+                "req-detached".to_string(),
+                session_id.clone(),
+                "echo building; sleep 10".to_string(),
+                "/tmp".to_string(),
+                30,
+                true,
+            )
+            .expect("detached start");
+
+        assert_eq!(service.cancel_session(&session_id), 1);
+
+        let mut chunk_rx = detached.chunk_rx;
+        timeout(Duration::from_secs(3), chunk_rx.recv())
+            .await
+            .expect("detached output timeout")
+            .expect("detached output");
+        let (stdout, _) = service
+            .live_output(&session_id, "req-detached")
+            .expect("detached job still active");
+        assert_eq!(stdout, "building\n");
+
+        assert_eq!(
+            service.cancel(&session_id, "req-detached"),
+            ShellCancelStatus::Canceled
+        );
+    }
+
+    #[tokio::test]
+    async fn detached_jobs_outlive_the_timeout_and_keep_an_output_tail() {
+        let service = ShellService::new();
+        let execution = service
+            .start(
+                "req-long".to_string(),
+                "sess-long".to_string(),
+                "head -c 600000 /dev/zero | tr '\\0' a; sleep 2; printf done".to_string(),
+                "/tmp".to_string(),
+                1,
+                true,
+            )
+            .expect("start");
+
+        let result = timeout(Duration::from_secs(10), execution.completion_rx)
+            .await
+            .expect("completion timeout")
+            .expect("completion result");
+        assert_eq!(result.outcome, ShellOutcome::Completed);
+        assert_eq!(result.stdout.len(), MAX_JOB_OUTPUT_BYTES);
+        assert!(result.stdout.ends_with("aaadone"));
+    }
+
+    #[test]
+    fn truncate_output_tail_keeps_char_boundaries() {
+        let mut output = "aé".repeat(4);
+        truncate_output_tail(&mut output, 4);
+        assert_eq!(output, "aé");
+
+        let mut short = "ok".to_string();
+        truncate_output_tail(&mut short, 4);
+        assert_eq!(short, "ok");
+    }
 }
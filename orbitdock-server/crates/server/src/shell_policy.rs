@@ -0,0 +1,228 @@
+//! Restricted shell mode.
+//!
+//! When `ORBITDOCK_SHELL_ALLOWLIST` is set, user-initiated shell commands must
+//! start with one of the listed programs (or multi-word prefixes such as
+//! `git status`), and may not chain extra commands through shell operators.
+//! Interactive terminals are disabled in this mode because a PTY cannot be
+//! policed command-by-command. Set `ORBITDOCK_SHELL_ALLOWLIST_AGENTS=1` to
+//! also refuse approving agent exec requests that fall outside the list.
+
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::audit::AuditEntry;
+use crate::state::SessionRegistry;
+
+const ALLOWLIST_ENV: &str = "ORBITDOCK_SHELL_ALLOWLIST";
+const APPLY_TO_AGENTS_ENV: &str = "ORBITDOCK_SHELL_ALLOWLIST_AGENTS";
+
+/// Characters that would let a command run something besides its leading program.
+const SHELL_OPERATORS: &[&str] = &[";", "&", "|", "`", "$(", ">", "<", "\n", "\r"];
+
+#[derive(Debug, Clone, Default)]
+pub struct ShellPolicy {
+    /// `None` means unrestricted. Each entry is a whitespace-split command prefix.
+    allowlist: Option<Vec<Vec<String>>>,
+    apply_to_agents: bool,
+}
+
+/// Why a command was rejected by the policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellPolicyDenial {
+    Empty,
+    ShellOperator(String),
+    NotAllowed(String),
+}
+
+impl std::fmt::Display for ShellPolicyDenial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShellPolicyDenial::Empty => f.write_str("empty command"),
+            ShellPolicyDenial::ShellOperator(op) => write!(
+                f,
+                "shell operator {op:?} is not permitted while the shell allowlist is enabled"
+            ),
+            ShellPolicyDenial::NotAllowed(program) => {
+                write!(f, "command {program:?} is not in the shell allowlist")
+            }
+        }
+    }
+}
+
+impl ShellPolicy {
+    pub fn from_env() -> Self {
+        let policy = Self::new(
+            std::env::var(ALLOWLIST_ENV).ok().as_deref(),
+            std::env::var(APPLY_TO_AGENTS_ENV)
+                .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+        );
+        if let Some(allowlist) = &policy.allowlist {
+            info!(
+                component = "shell",
+                event = "shell.policy.enabled",
+                allowed_commands = allowlist.len(),
+                apply_to_agents = policy.apply_to_agents,
+                "Restricted shell allowlist enabled"
+            );
+        }
+        policy
+    }
+
+    /// Build a policy from a comma-separated allowlist (`None` = unrestricted).
+    pub fn new(raw_allowlist: Option<&str>, apply_to_agents: bool) -> Self {
+        let allowlist = raw_allowlist.map(|raw| {
+            raw.split(',')
+                .map(|entry| {
+                    entry
+                        .split_whitespace()
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|prefix| !prefix.is_empty())
+                .collect::<Vec<_>>()
+        });
+        Self {
+            allowlist,
+            apply_to_agents,
+        }
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        self.allowlist.is_some()
+    }
+
+    pub fn applies_to_agents(&self) -> bool {
+        self.is_restricted() && self.apply_to_agents
+    }
+
+    pub fn check(&self, command: &str) -> Result<(), ShellPolicyDenial> {
+        let Some(allowlist) = &self.allowlist else {
+            return Ok(());
+        };
+
+        let command = command.trim();
+        if let Some(op) = SHELL_OPERATORS.iter().find(|op| command.contains(**op)) {
+            return Err(ShellPolicyDenial::ShellOperator(op.to_string()));
+        }
+
+        let tokens: Vec<&str> = command.split_whitespace().collect();
+        let Some(program) = tokens.first() else {
+            return Err(ShellPolicyDenial::Empty);
+        };
+
+        let allowed = allowlist.iter().any(|prefix| {
+            prefix.len() <= tokens.len()
+                && prefix
+                    .iter()
+                    .zip(&tokens)
+                    .all(|(allowed, token)| allowed == token)
+        });
+        if allowed {
+            Ok(())
+        } else {
+            Err(ShellPolicyDenial::NotAllowed(program.to_string()))
+        }
+    }
+}
+
+/// Record a denied command in the audit trail.
+pub async fn audit_denied(
+    state: &SessionRegistry,
+    source: &'static str,
+    conn_id: u64,
+    session_id: &str,
+    command: &str,
+    denial: &ShellPolicyDenial,
+) {
+    let command = crate::redaction::redact(command);
+    warn!(
+        component = "audit",
+        event = "audit.shell.denied",
+        source = source,
+        connection_id = conn_id,
+        session_id = %session_id,
+        command = %command,
+        reason = %denial,
+        "Shell command denied by allowlist"
+    );
+    crate::audit::record(
+        state,
+        conn_id,
+        AuditEntry {
+            action: "shell_command_denied",
+            session_id: Some(session_id.to_string()),
+            details: json!({
+                "source": source,
+                "command": command,
+                "reason": denial.to_string(),
+            }),
+        },
+    )
+    .await;
+}
+
+/// Whether an approval decision would let the agent run the command.
+pub fn is_approving_decision(decision: &str) -> bool {
+    matches!(
+        decision.trim().to_lowercase().as_str(),
+        "approved" | "approved_for_session" | "approved_always"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_approving_decision, ShellPolicy, ShellPolicyDenial};
+
+    #[test]
+    fn unrestricted_policy_allows_everything() {
+        let policy = ShellPolicy::new(None, true);
+        assert!(!policy.is_restricted());
+        assert!(!policy.applies_to_agents());
+        assert_eq!(policy.check("rm -rf / && echo hi"), Ok(()));
+    }
+
+    #[test]
+    fn allowlist_matches_program_and_multi_word_prefixes() {
+        let policy = ShellPolicy::new(Some("ls, git status ,cargo"), false);
+        assert_eq!(policy.check("ls -la"), Ok(()));
+        assert_eq!(policy.check("  git status --short"), Ok(()));
+        assert_eq!(policy.check("cargo test --workspace"), Ok(()));
+        assert_eq!(
+            policy.check("git push --force"),
+            Err(ShellPolicyDenial::NotAllowed("git".to_string()))
+        );
+        assert_eq!(
+            policy.check("lsof -i"),
+            Err(ShellPolicyDenial::NotAllowed("lsof".to_string()))
+        );
+        assert_eq!(policy.check("   "), Err(ShellPolicyDenial::Empty));
+    }
+
+    #[test]
+    fn allowlist_rejects_command_chaining() {
+        let policy = ShellPolicy::new(Some("ls"), false);
+        for command in [
+            "ls; rm -rf ~",
+            "ls && curl x",
+            "ls | sh",
+            "ls $(whoami)",
+            "ls > f",
+        ] {
+            assert!(
+                matches!(
+                    policy.check(command),
+                    Err(ShellPolicyDenial::ShellOperator(_))
+                ),
+                "{command} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn approving_decisions_are_detected() {
+        assert!(is_approving_decision("approved_for_session"));
+        assert!(!is_approving_decision("denied"));
+        assert!(!is_approving_decision("abort"));
+    }
+}
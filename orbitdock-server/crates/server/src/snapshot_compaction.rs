@@ -4,21 +4,126 @@
 //! progressively truncating message content, trimming images, deduplicating
 //! turn diffs, and searching for the smallest representation that stays
 //! under the target byte budget.
+//!
+//! The message and content caps come from [`SnapshotLimits`]: the built-in
+//! defaults, overridden by `[snapshots]` in the config file and then by a
+//! client's `SubscribeSession` options.
 
 use std::collections::HashSet;
 
 use tracing::warn;
 
-use orbitdock_protocol::{MessageChanges, ServerMessage, SessionState, StateChanges};
+use orbitdock_protocol::{
+    MessageChanges, MessageType, ServerMessage, SessionState, SnapshotOptions, SnapshotTruncation,
+    StateChanges,
+};
 
 pub(crate) const SNAPSHOT_MAX_MESSAGES: usize = 200;
 pub(crate) const SNAPSHOT_MAX_CONTENT_CHARS: usize = 16_000;
 const SNAPSHOT_MIN_CONTENT_CHARS: usize = 250;
+/// Upper bounds for configured or client-requested limits.
+const SNAPSHOT_MESSAGES_CEILING: usize = 2_000;
+const SNAPSHOT_CONTENT_CHARS_CEILING: usize = 64_000;
+/// Opening user prompts a head+tail snapshot keeps, at most a quarter of the budget.
+const SNAPSHOT_HEAD_PROMPTS: usize = 3;
 const SNAPSHOT_MAX_TURN_DIFFS: usize = 80;
 /// Keep outbound frames within common client defaults (Apple URLSession WS default is 1 MiB).
 pub(crate) const WS_MAX_TEXT_MESSAGE_BYTES: usize = 1024 * 1024;
 /// Snapshots should stay much smaller than the hard transport ceiling to avoid reconnect churn.
 pub(crate) const SNAPSHOT_TARGET_TEXT_MESSAGE_BYTES: usize = 256 * 1024;
+/// Target once limits are raised past the defaults; the caller asked for a bigger frame.
+const SNAPSHOT_EXTENDED_TARGET_TEXT_MESSAGE_BYTES: usize = 768 * 1024;
+
+// ── Limits ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SnapshotLimits {
+    pub max_messages: usize,
+    pub max_content_chars: usize,
+    pub truncation: SnapshotTruncation,
+}
+
+impl Default for SnapshotLimits {
+    fn default() -> Self {
+        Self {
+            max_messages: SNAPSHOT_MAX_MESSAGES,
+            max_content_chars: SNAPSHOT_MAX_CONTENT_CHARS,
+            truncation: SnapshotTruncation::Tail,
+        }
+    }
+}
+
+impl SnapshotLimits {
+    /// The defaults with the server's `ORBITDOCK_SNAPSHOT_*` settings applied.
+    pub(crate) fn configured() -> Self {
+        let defaults = Self::default();
+        let number = |name: &str| {
+            crate::config_file::var(name).and_then(|value| value.trim().parse::<usize>().ok())
+        };
+        Self {
+            max_messages: number("ORBITDOCK_SNAPSHOT_MAX_MESSAGES")
+                .unwrap_or(defaults.max_messages),
+            max_content_chars: number("ORBITDOCK_SNAPSHOT_MAX_CONTENT_CHARS")
+                .unwrap_or(defaults.max_content_chars),
+            truncation: crate::config_file::var("ORBITDOCK_SNAPSHOT_TRUNCATION")
+                .and_then(|value| parse_truncation(&value))
+                .unwrap_or(defaults.truncation),
+        }
+        .clamped()
+    }
+
+    /// Apply a client's per-subscription overrides.
+    pub(crate) fn with_options(self, options: Option<&SnapshotOptions>) -> Self {
+        let Some(options) = options else {
+            return self;
+        };
+        Self {
+            max_messages: options
+                .max_messages
+                .map_or(self.max_messages, |value| value as usize),
+            max_content_chars: options
+                .max_content_chars
+                .map_or(self.max_content_chars, |value| value as usize),
+            truncation: options.truncation.unwrap_or(self.truncation),
+        }
+        .clamped()
+    }
+
+    fn clamped(self) -> Self {
+        Self {
+            max_messages: self.max_messages.clamp(1, SNAPSHOT_MESSAGES_CEILING),
+            max_content_chars: self
+                .max_content_chars
+                .clamp(SNAPSHOT_MIN_CONTENT_CHARS, SNAPSHOT_CONTENT_CHARS_CEILING),
+            truncation: self.truncation,
+        }
+    }
+
+    fn target_bytes(&self) -> usize {
+        if self.max_messages > SNAPSHOT_MAX_MESSAGES
+            || self.max_content_chars > SNAPSHOT_MAX_CONTENT_CHARS
+        {
+            SNAPSHOT_EXTENDED_TARGET_TEXT_MESSAGE_BYTES
+        } else {
+            SNAPSHOT_TARGET_TEXT_MESSAGE_BYTES
+        }
+    }
+}
+
+pub(crate) fn parse_truncation(value: &str) -> Option<SnapshotTruncation> {
+    match value.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+        "tail" => Some(SnapshotTruncation::Tail),
+        "head_tail" => Some(SnapshotTruncation::HeadTail),
+        _ => None,
+    }
+}
+
+pub(crate) fn truncation_name(truncation: SnapshotTruncation) -> &'static str {
+    match truncation {
+        SnapshotTruncation::Tail => "tail",
+        SnapshotTruncation::HeadTail => "head_tail",
+    }
+}
 
 // ── Text truncation ─────────────────────────────────────────────────────
 
@@ -117,7 +222,8 @@ fn compact_message_for_transport_inner(
     max_chars: usize,
     truncate_tool_input: bool,
 ) {
-    message.images = crate::images::normalize_images_for_transport(&message.images);
+    message.images =
+        crate::images::normalize_images_for_transport(&message.images, &message.session_id);
     truncate_string_in_place(&mut message.content, max_chars);
     if truncate_tool_input {
         truncate_option_string_in_place(&mut message.tool_input, max_chars);
@@ -163,24 +269,57 @@ fn dedupe_turn_diffs_keep_latest(turn_diffs: &mut Vec<orbitdock_protocol::TurnDi
 
 // ── Snapshot compaction ─────────────────────────────────────────────────
 
+/// Keep the last `max_messages`, or with head+tail the opening user prompts
+/// followed by the most recent messages. Returns how many head messages were kept.
+fn truncate_messages(
+    messages: &mut Vec<orbitdock_protocol::Message>,
+    max_messages: usize,
+    truncation: SnapshotTruncation,
+) -> usize {
+    if messages.len() <= max_messages {
+        return 0;
+    }
+    let head: Vec<usize> = match truncation {
+        SnapshotTruncation::Tail => Vec::new(),
+        SnapshotTruncation::HeadTail => messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| message.message_type == MessageType::User)
+            .map(|(index, _)| index)
+            .take(SNAPSHOT_HEAD_PROMPTS.min(max_messages / 4))
+            .collect(),
+    };
+    let keep_from = messages.len() - (max_messages - head.len());
+    let mut kept: Vec<_> = head
+        .into_iter()
+        .filter(|index| *index < keep_from)
+        .map(|index| messages[index].clone())
+        .collect();
+    let head_len = kept.len();
+    kept.extend(messages.split_off(keep_from));
+    *messages = kept;
+    head_len
+}
+
 fn compact_snapshot_for_transport_with_limits(
     mut snapshot: SessionState,
     max_messages: usize,
     max_content_chars: usize,
+    truncation: SnapshotTruncation,
 ) -> SessionState {
-    let max_messages = max_messages.min(SNAPSHOT_MAX_MESSAGES);
+    let max_messages = max_messages.min(SNAPSHOT_MESSAGES_CEILING);
     let max_content_chars =
-        max_content_chars.clamp(SNAPSHOT_MIN_CONTENT_CHARS, SNAPSHOT_MAX_CONTENT_CHARS);
+        max_content_chars.clamp(SNAPSHOT_MIN_CONTENT_CHARS, SNAPSHOT_CONTENT_CHARS_CEILING);
     let original_total_message_count = snapshot
         .total_message_count
         .unwrap_or(snapshot.messages.len() as u64);
 
-    if max_messages == 0 {
+    let head_len = if max_messages == 0 {
         snapshot.messages.clear();
-    } else if snapshot.messages.len() > max_messages {
-        let keep_from = snapshot.messages.len() - max_messages;
-        snapshot.messages = snapshot.messages.split_off(keep_from);
-    }
+        0
+    } else {
+        truncate_messages(&mut snapshot.messages, max_messages, truncation)
+    };
 
     for message in &mut snapshot.messages {
         compact_message_for_transport(message, max_content_chars);
@@ -219,8 +358,28 @@ fn compact_snapshot_for_transport_with_limits(
     }
 
     snapshot.total_message_count = Some(original_total_message_count);
-    snapshot.oldest_sequence = snapshot.messages.first().and_then(|message| message.sequence);
-    snapshot.newest_sequence = snapshot.messages.last().and_then(|message| message.sequence);
+    // Paging back continues from the tail, not from the head prompts. A
+    // snapshot compacted twice keeps the cursor the first pass chose.
+    let tail_start = match truncation {
+        SnapshotTruncation::HeadTail if head_len == 0 => snapshot
+            .oldest_sequence
+            .and_then(|sequence| {
+                snapshot
+                    .messages
+                    .iter()
+                    .position(|message| message.sequence == Some(sequence))
+            })
+            .unwrap_or(0),
+        _ => head_len,
+    };
+    snapshot.oldest_sequence = snapshot
+        .messages
+        .get(tail_start)
+        .and_then(|message| message.sequence);
+    snapshot.newest_sequence = snapshot
+        .messages
+        .last()
+        .and_then(|message| message.sequence);
     snapshot.has_more_before = Some(
         snapshot.has_more_before.unwrap_or(false)
             || original_total_message_count > snapshot.messages.len() as u64
@@ -268,33 +427,41 @@ fn trim_snapshot_images_to_transport_limit(snapshot: &mut SessionState, limit_by
     }
 }
 
-pub(crate) fn compact_snapshot_to_transport_limit(snapshot: SessionState) -> SessionState {
+pub(crate) fn compact_snapshot_to_transport_limit(
+    snapshot: SessionState,
+    limits: &SnapshotLimits,
+) -> SessionState {
+    let target_bytes = limits.target_bytes();
     let mut portable_snapshot = snapshot;
     for message in &mut portable_snapshot.messages {
-        message.images = crate::images::normalize_images_for_transport(&message.images);
+        message.images =
+            crate::images::normalize_images_for_transport(&message.images, &message.session_id);
     }
 
     let default_compacted = compact_snapshot_for_transport_with_limits(
         portable_snapshot.clone(),
-        SNAPSHOT_MAX_MESSAGES,
-        SNAPSHOT_MAX_CONTENT_CHARS,
+        limits.max_messages,
+        limits.max_content_chars,
+        limits.truncation,
     );
     let mut default_compacted = default_compacted;
-    trim_snapshot_images_to_transport_limit(
-        &mut default_compacted,
-        SNAPSHOT_TARGET_TEXT_MESSAGE_BYTES,
-    );
+    trim_snapshot_images_to_transport_limit(&mut default_compacted, target_bytes);
 
-    if snapshot_transport_size_bytes(&default_compacted)
-        .is_some_and(|size| size <= SNAPSHOT_TARGET_TEXT_MESSAGE_BYTES)
-    {
+    if snapshot_transport_size_bytes(&default_compacted).is_some_and(|size| size <= target_bytes) {
         return default_compacted;
     }
 
     // Never go to 0 — sending an empty snapshot makes the client think the
     // conversation was cleared. Better to exceed the target than lose all context.
-    let message_caps = [160, 120, 96, 72, 48, 32, 24, 16, 8, 4, 2, 1];
-    let content_caps = [
+    let message_caps = [
+        1_500, 1_000, 500, 160, 120, 96, 72, 48, 32, 24, 16, 8, 4, 2, 1,
+    ]
+    .into_iter()
+    .filter(|cap| *cap < limits.max_messages);
+    let content_caps: Vec<usize> = [
+        48_000,
+        32_000,
+        16_000,
         12_000,
         8_000,
         4_000,
@@ -302,21 +469,22 @@ pub(crate) fn compact_snapshot_to_transport_limit(snapshot: SessionState) -> Ses
         1_000,
         500,
         SNAPSHOT_MIN_CONTENT_CHARS,
-    ];
+    ]
+    .into_iter()
+    .filter(|cap| *cap <= limits.max_content_chars)
+    .collect();
     let mut smallest = default_compacted;
     let mut smallest_size = snapshot_transport_size_bytes(&smallest).unwrap_or(usize::MAX);
 
     for max_messages in message_caps {
-        for max_content_chars in content_caps {
+        for &max_content_chars in &content_caps {
             let mut candidate = compact_snapshot_for_transport_with_limits(
                 portable_snapshot.clone(),
                 max_messages,
                 max_content_chars,
+                limits.truncation,
             );
-            trim_snapshot_images_to_transport_limit(
-                &mut candidate,
-                SNAPSHOT_TARGET_TEXT_MESSAGE_BYTES,
-            );
+            trim_snapshot_images_to_transport_limit(&mut candidate, target_bytes);
             let Some(size) = snapshot_transport_size_bytes(&candidate) else {
                 continue;
             };
@@ -324,7 +492,7 @@ pub(crate) fn compact_snapshot_to_transport_limit(snapshot: SessionState) -> Ses
                 smallest_size = size;
                 smallest = candidate.clone();
             }
-            if size <= SNAPSHOT_TARGET_TEXT_MESSAGE_BYTES {
+            if size <= target_bytes {
                 return candidate;
             }
         }
@@ -337,12 +505,14 @@ pub(crate) fn compact_snapshot_to_transport_limit(snapshot: SessionState) -> Ses
     smallest
 }
 
-/// Compact a snapshot with default limits (used by handlers before sending).
+/// Compact a snapshot with the server's configured limits (used by handlers before sending).
 pub(crate) fn compact_snapshot_for_transport(snapshot: SessionState) -> SessionState {
+    let limits = SnapshotLimits::configured();
     compact_snapshot_for_transport_with_limits(
         snapshot,
-        SNAPSHOT_MAX_MESSAGES,
-        SNAPSHOT_MAX_CONTENT_CHARS,
+        limits.max_messages,
+        limits.max_content_chars,
+        limits.truncation,
     )
 }
 
@@ -381,12 +551,13 @@ fn trim_message_images_to_transport_limit(
 pub(crate) fn sanitize_server_message_for_transport(msg: ServerMessage) -> ServerMessage {
     match msg {
         ServerMessage::SessionSnapshot { session } => {
+            let limits = SnapshotLimits::configured();
             let before = snapshot_transport_size_bytes(&session);
-            let compacted = compact_snapshot_to_transport_limit(session);
+            let compacted = compact_snapshot_to_transport_limit(session, &limits);
             let after = snapshot_transport_size_bytes(&compacted);
 
             if let (Some(before), Some(after)) = (before, after) {
-                if before > SNAPSHOT_TARGET_TEXT_MESSAGE_BYTES && after < before {
+                if before > limits.target_bytes() && after < before {
                     warn!(
                         component = "websocket",
                         event = "ws.transport.snapshot_compacted",
@@ -394,7 +565,7 @@ pub(crate) fn sanitize_server_message_for_transport(msg: ServerMessage) -> Serve
                         before_bytes = before,
                         after_bytes = after,
                         messages_in_snapshot = compacted.messages.len(),
-                        target_bytes = SNAPSHOT_TARGET_TEXT_MESSAGE_BYTES,
+                        target_bytes = limits.target_bytes(),
                         max_bytes = WS_MAX_TEXT_MESSAGE_BYTES,
                         "Compacted session snapshot to fit outbound payload target"
                     );
@@ -0,0 +1,209 @@
+//! Hook event spool.
+//!
+//! `hook-forward` queues events in `<data dir>/spool/` as `<epoch ms>-<pid>.json`
+//! while the server is unreachable; it replays them on its next successful
+//! delivery and the server drains the rest on startup. The queue is bounded by
+//! count, size, and age, oldest events going first, so a long outage can't pile
+//! up unbounded work. Events that can't be parsed, or that the server rejects on
+//! replay, move to `spool/dead-letter/` instead of being retried forever.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::paths;
+
+pub const MAX_FILES: usize = 2_000;
+pub const MAX_BYTES: u64 = 16 * 1024 * 1024;
+pub const MAX_AGE: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+const DEAD_LETTER_MAX_FILES: usize = 500;
+
+#[derive(Debug, Clone)]
+pub struct SpoolEntry {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub queued_at_ms: u64,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Older than `MAX_AGE`.
+    pub expired: usize,
+    /// Oldest events dropped to get back under `MAX_FILES` / `MAX_BYTES`.
+    pub evicted: usize,
+}
+
+impl PruneReport {
+    pub fn dropped(&self) -> usize {
+        self.expired + self.evicted
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SpoolStats {
+    pub depth: u64,
+    pub bytes: u64,
+    pub oldest_age_secs: Option<u64>,
+    pub dead_letters: u64,
+}
+
+/// Queued events in `dir`, oldest first.
+pub fn queued(dir: &Path) -> Vec<SpoolEntry> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut queued: Vec<SpoolEntry> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
+        .filter_map(|path| {
+            let metadata = std::fs::metadata(&path).ok()?;
+            let queued_at_ms = queued_at_from_name(&path).unwrap_or_else(|| {
+                metadata
+                    .modified()
+                    .ok()
+                    .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+                    .map(|elapsed| elapsed.as_millis() as u64)
+                    .unwrap_or(0)
+            });
+            Some(SpoolEntry {
+                path,
+                bytes: metadata.len(),
+                queued_at_ms,
+            })
+        })
+        .collect();
+    queued.sort_by(|a, b| {
+        a.queued_at_ms
+            .cmp(&b.queued_at_ms)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    queued
+}
+
+/// Drop expired events, then the oldest until the queue fits its limits.
+pub fn prune(dir: &Path, now_ms: u64) -> PruneReport {
+    let mut report = PruneReport::default();
+    let max_age_ms = MAX_AGE.as_millis() as u64;
+
+    let mut kept = Vec::new();
+    for entry in queued(dir) {
+        if now_ms.saturating_sub(entry.queued_at_ms) > max_age_ms {
+            if std::fs::remove_file(&entry.path).is_ok() {
+                report.expired += 1;
+            }
+        } else {
+            kept.push(entry);
+        }
+    }
+
+    let mut bytes: u64 = kept.iter().map(|entry| entry.bytes).sum();
+    let mut count = kept.len();
+    for entry in kept {
+        if count <= MAX_FILES && bytes <= MAX_BYTES {
+            break;
+        }
+        if std::fs::remove_file(&entry.path).is_ok() {
+            report.evicted += 1;
+        }
+        count -= 1;
+        bytes = bytes.saturating_sub(entry.bytes);
+    }
+
+    report
+}
+
+/// Move a spooled event aside so it is never replayed again.
+pub fn dead_letter(path: &Path) -> std::io::Result<()> {
+    let dir = paths::spool_dead_letter_dir();
+    std::fs::create_dir_all(&dir)?;
+    let Some(name) = path.file_name() else {
+        return std::fs::remove_file(path);
+    };
+    std::fs::rename(path, dir.join(name))?;
+
+    // Only kept for inspection; the newest are the interesting ones.
+    let letters = queued(&dir);
+    let excess = letters.len().saturating_sub(DEAD_LETTER_MAX_FILES);
+    for entry in letters.into_iter().take(excess) {
+        let _ = std::fs::remove_file(entry.path);
+    }
+    Ok(())
+}
+
+pub fn stats() -> SpoolStats {
+    let entries = queued(&paths::spool_dir());
+    let now_ms = now_ms();
+    SpoolStats {
+        depth: entries.len() as u64,
+        bytes: entries.iter().map(|entry| entry.bytes).sum(),
+        oldest_age_secs: entries
+            .first()
+            .map(|entry| now_ms.saturating_sub(entry.queued_at_ms) / 1000),
+        dead_letters: queued(&paths::spool_dead_letter_dir()).len() as u64,
+    }
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn queued_at_from_name(path: &Path) -> Option<u64> {
+    let stem = path.file_stem()?.to_str()?;
+    stem.split('-').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+    fn write_event(dir: &Path, queued_at_ms: u64, bytes: usize) {
+        let path = dir.join(format!("{queued_at_ms}-1.json"));
+        std::fs::write(path, "x".repeat(bytes)).expect("write spool file");
+    }
+
+    #[test]
+    fn prune_drops_expired_then_oldest_over_limits() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let now = 100 * DAY_MS;
+        write_event(dir.path(), now - 4 * DAY_MS, 10);
+        for i in 0..MAX_FILES as u64 + 2 {
+            write_event(dir.path(), now - DAY_MS + i, 10);
+        }
+        std::fs::create_dir(dir.path().join("dead-letter")).expect("mkdir");
+
+        let report = prune(dir.path(), now);
+
+        assert_eq!(
+            report,
+            PruneReport {
+                expired: 1,
+                evicted: 2
+            }
+        );
+        let remaining = queued(dir.path());
+        assert_eq!(remaining.len(), MAX_FILES);
+        assert_eq!(remaining[0].queued_at_ms, now - DAY_MS + 2);
+    }
+
+    #[test]
+    fn prune_enforces_the_byte_limit() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let now = 100 * DAY_MS;
+        let chunk = (MAX_BYTES / 4) as usize;
+        for i in 0..5 {
+            write_event(dir.path(), now - 1000 + i, chunk);
+        }
+
+        let report = prune(dir.path(), now);
+
+        assert_eq!(report.evicted, 1);
+        assert_eq!(queued(dir.path()).len(), 4);
+    }
+}
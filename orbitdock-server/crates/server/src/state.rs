@@ -1,24 +1,52 @@
 //! Application state
 
 use dashmap::DashMap;
-use orbitdock_protocol::{ClientPrimaryClaim, SessionSummary};
-use std::collections::BTreeMap;
+use orbitdock_protocol::{
+    ApprovalHistoryItem, AttentionItem, AuditLogEntry, ClientPrimaryClaim, ConnectionInfo,
+    ConnectorProcessStats, IssueLink, SessionMcpServer, SessionSummary, SessionViewer, TicketLink,
+};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::future::Future;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::{broadcast, mpsc, Notify};
 
 use crate::ai_naming::NamingGuard;
+use crate::attention::AttentionInbox;
+use crate::auth::AuthIdentity;
 use crate::claude_session::ClaudeAction;
 use crate::codex_session::CodexAction;
+use crate::federation::{self, Peers};
 use crate::hook_handler::PendingClaudeSession;
-use crate::persistence::PersistCommand;
+use crate::persistence::{self, PersistCommand};
 use crate::session::SessionHandle;
+use crate::session_access::{self, SessionSharing};
 use crate::session_actor::SessionActorHandle;
+use crate::session_command::SessionCommand;
 use crate::shell::ShellService;
+use crate::shell_policy::ShellPolicy;
+use crate::terminal::TerminalService;
+use orbitdock_connector_claude::auth::ClaudeAuthService;
 use orbitdock_connector_codex::auth::CodexAuthService;
 
+/// A live WebSocket connection.
+pub struct ConnectionEntry {
+    pub identity: AuthIdentity,
+    pub remote_addr: Option<SocketAddr>,
+    pub client_name: Option<String>,
+    pub client_version: Option<String>,
+    pub connected_at: String,
+    pub subscribed_to_list: bool,
+    pub subscriptions: BTreeSet<String>,
+    /// Subscribed sessions this connection is writing a prompt for.
+    pub composing: BTreeSet<String>,
+    /// Signalled to make the connection's read loop close the socket.
+    pub disconnect: Arc<Notify>,
+}
+
 #[derive(Clone)]
 struct ClientPrimaryClaimState {
     client_id: String,
@@ -53,6 +81,9 @@ pub struct SessionRegistry {
     /// Global Codex account auth coordinator (not session-specific)
     codex_auth: Arc<CodexAuthService>,
 
+    /// Claude CLI account status and login (not session-specific)
+    claude_auth: Arc<ClaudeAuthService>,
+
     /// Dedup guard for AI session naming
     naming_guard: Arc<NamingGuard>,
 
@@ -60,20 +91,68 @@ pub struct SessionRegistry {
     /// Keyed by Claude SDK session_id from SessionStart.
     pending_claude_sessions: DashMap<String, PendingClaudeSession>,
 
+    /// When each Claude session last delivered a real hook. The transcript
+    /// watcher leaves these sessions to their hooks.
+    claude_hooks_seen: DashMap<String, Instant>,
+
     /// Provider-agnostic shell runtime service for user-initiated commands.
     shell_service: Arc<ShellService>,
 
+    /// Interactive PTY terminals opened by connected clients.
+    terminal_service: Arc<TerminalService>,
+
+    /// Allowlist applied to user shell commands (and optionally agent exec approvals).
+    shell_policy: Arc<ShellPolicy>,
+
     /// True when this server should act as the primary control-plane endpoint.
     is_primary: AtomicBool,
 
     /// Per-WebSocket-connection primary claim state from connected client devices.
     client_primary_claims: DashMap<u64, ClientPrimaryClaimState>,
 
+    /// Live WebSocket connections, for the audit log, admin checks, and
+    /// `ListConnections`.
+    connections: DashMap<u64, ConnectionEntry>,
+
+    /// Sharing settings for sessions that have been shared; absent means private.
+    session_sharing: DashMap<String, SessionSharing>,
+
+    /// Topic tags for sessions that have any.
+    session_tags: DashMap<String, Vec<String>>,
+
+    /// MCP servers added from OrbitDock, for sessions that have any.
+    session_mcp_servers: DashMap<String, Vec<SessionMcpServer>>,
+
+    /// GitHub issue each issue session was started from.
+    session_issues: DashMap<String, IssueLink>,
+
+    /// Linear or Jira ticket for sessions linked to one.
+    session_tickets: DashMap<String, TicketLink>,
+
+    /// Peer servers whose sessions are relayed here.
+    peers: Peers,
+
+    /// The "needs attention" inbox.
+    attention: AttentionInbox,
+
+    /// Claude CLI process id per direct session, sampled by the resource monitor.
+    connector_pids: DashMap<String, u32>,
+
+    /// Latest resource sample per process id (connectors plus the server itself).
+    process_stats: DashMap<u32, ConnectorProcessStats>,
+
+    /// Whether the Codex rollout watcher loop is currently running (for /health).
+    rollout_watcher_running: AtomicBool,
+
     /// Active WebSocket connection count (for metrics).
     ws_connections: AtomicU64,
 
     /// Server start time (for uptime metrics).
     started_at: Instant,
+
+    /// Signalled by an admin `ShutdownServer` to run the same graceful
+    /// shutdown as SIGINT/SIGTERM.
+    shutdown: Notify,
 }
 
 impl SessionRegistry {
@@ -85,6 +164,7 @@ impl SessionRegistry {
     pub fn new_with_primary(persist_tx: mpsc::Sender<PersistCommand>, is_primary: bool) -> Self {
         let (list_tx, _) = broadcast::channel(64);
         let codex_auth = Arc::new(CodexAuthService::new(list_tx.clone()));
+        let claude_auth = Arc::new(ClaudeAuthService::new(list_tx.clone()));
         Self {
             sessions: DashMap::new(),
             codex_actions: DashMap::new(),
@@ -95,13 +175,29 @@ impl SessionRegistry {
             persist_tx,
             db_path: crate::paths::db_path(),
             codex_auth,
+            claude_auth,
             naming_guard: Arc::new(NamingGuard::new()),
             pending_claude_sessions: DashMap::new(),
+            claude_hooks_seen: DashMap::new(),
             shell_service: Arc::new(ShellService::new()),
+            terminal_service: Arc::new(TerminalService::new()),
+            shell_policy: Arc::new(ShellPolicy::from_env()),
             is_primary: AtomicBool::new(is_primary),
             client_primary_claims: DashMap::new(),
+            connections: DashMap::new(),
+            session_sharing: DashMap::new(),
+            session_tags: DashMap::new(),
+            session_mcp_servers: DashMap::new(),
+            session_issues: DashMap::new(),
+            session_tickets: DashMap::new(),
+            peers: Peers::default(),
+            attention: AttentionInbox::default(),
+            connector_pids: DashMap::new(),
+            process_stats: DashMap::new(),
+            rollout_watcher_running: AtomicBool::new(false),
             ws_connections: AtomicU64::new(0),
             started_at: Instant::now(),
+            shutdown: Notify::new(),
         }
     }
 
@@ -130,6 +226,71 @@ impl SessionRegistry {
         self.started_at.elapsed().as_secs()
     }
 
+    pub fn request_shutdown(&self) {
+        self.shutdown.notify_one();
+    }
+
+    /// Resolves once `request_shutdown` has been called.
+    pub async fn shutdown_requested(&self) {
+        self.shutdown.notified().await;
+    }
+
+    pub fn set_rollout_watcher_running(&self, running: bool) {
+        self.rollout_watcher_running
+            .store(running, Ordering::Relaxed);
+    }
+
+    pub fn rollout_watcher_running(&self) -> bool {
+        self.rollout_watcher_running.load(Ordering::Relaxed)
+    }
+
+    /// Live Codex connectors (direct sessions with an action channel).
+    pub fn codex_connector_count(&self) -> usize {
+        self.codex_actions.len()
+    }
+
+    /// Live Claude connectors (direct sessions with an action channel).
+    pub fn claude_connector_count(&self) -> usize {
+        self.claude_actions.len()
+    }
+
+    pub fn register_connector_pid(&self, session_id: &str, pid: u32) {
+        self.connector_pids.insert(session_id.to_string(), pid);
+    }
+
+    /// Forget a session's connector process, unless it has since been replaced.
+    pub fn remove_connector_pid(&self, session_id: &str, pid: u32) {
+        self.connector_pids
+            .remove_if(session_id, |_, current| *current == pid);
+        self.process_stats.remove(&pid);
+    }
+
+    pub fn connector_pids(&self) -> Vec<(String, u32)> {
+        self.connector_pids
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect()
+    }
+
+    /// Replace the latest samples; processes missing from `stats` are dropped.
+    pub fn set_process_stats(&self, stats: Vec<ConnectorProcessStats>) {
+        self.process_stats
+            .retain(|pid, _| stats.iter().any(|sample| sample.pid == *pid));
+        for sample in stats {
+            self.process_stats.insert(sample.pid, sample);
+        }
+    }
+
+    pub fn process_stats(&self) -> Vec<ConnectorProcessStats> {
+        let mut stats: Vec<_> = self
+            .process_stats
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        stats.sort_by_key(|sample| (sample.session_id.clone(), sample.pid));
+        stats
+    }
+
     pub fn set_client_primary_claim(
         &self,
         conn_id: u64,
@@ -151,6 +312,141 @@ impl SessionRegistry {
         self.client_primary_claims.remove(&conn_id).is_some()
     }
 
+    pub fn register_connection(&self, conn_id: u64, entry: ConnectionEntry) {
+        self.connections.insert(conn_id, entry);
+    }
+
+    /// Forget a closed connection, returning the sessions it was subscribed to.
+    pub fn remove_connection(&self, conn_id: u64) -> BTreeSet<String> {
+        self.peers.end_connection(conn_id);
+        self.connections
+            .remove(&conn_id)
+            .map(|(_, entry)| entry.subscriptions)
+            .unwrap_or_default()
+    }
+
+    /// Identity for a connection, `Anonymous` when unknown.
+    pub fn connection_identity(&self, conn_id: u64) -> AuthIdentity {
+        self.connections
+            .get(&conn_id)
+            .map(|entry| entry.identity.clone())
+            .unwrap_or(AuthIdentity::Anonymous)
+    }
+
+    pub fn note_list_subscription(&self, conn_id: u64) {
+        if let Some(mut entry) = self.connections.get_mut(&conn_id) {
+            entry.subscribed_to_list = true;
+        }
+    }
+
+    pub fn note_session_subscription(&self, conn_id: u64, session_id: &str, subscribed: bool) {
+        if let Some(mut entry) = self.connections.get_mut(&conn_id) {
+            if subscribed {
+                entry.subscriptions.insert(session_id.to_string());
+            } else {
+                entry.subscriptions.remove(session_id);
+                entry.composing.remove(session_id);
+            }
+        }
+    }
+
+    /// Mark whether a connection is writing a prompt for a session it's
+    /// subscribed to. Returns whether that changed.
+    pub fn set_composing(&self, conn_id: u64, session_id: &str, composing: bool) -> bool {
+        let Some(mut entry) = self.connections.get_mut(&conn_id) else {
+            return false;
+        };
+        if !composing {
+            return entry.composing.remove(session_id);
+        }
+        entry.subscriptions.contains(session_id) && entry.composing.insert(session_id.to_string())
+    }
+
+    /// Connections subscribed to a session, oldest first.
+    pub fn session_viewers(&self, session_id: &str) -> Vec<SessionViewer> {
+        let mut viewers: Vec<SessionViewer> = self
+            .connections
+            .iter()
+            .filter(|entry| entry.subscriptions.contains(session_id))
+            .map(|entry| SessionViewer {
+                connection_id: *entry.key(),
+                user: entry.identity.user().map(str::to_string),
+                client_name: entry.client_name.clone(),
+                device_name: self
+                    .client_primary_claims
+                    .get(entry.key())
+                    .map(|claim| claim.device_name.clone()),
+                composing: entry.composing.contains(session_id),
+            })
+            .collect();
+        viewers.sort_by_key(|viewer| viewer.connection_id);
+        viewers
+    }
+
+    /// Send a session's current viewers to its actor, which tells the
+    /// session's subscribers when they changed.
+    pub async fn publish_presence(&self, session_id: &str) {
+        if let Some(actor) = self.get_session(session_id) {
+            let viewers = self.session_viewers(session_id);
+            actor.send(SessionCommand::SetViewers { viewers }).await;
+        }
+    }
+
+    /// Live connections, oldest first.
+    pub fn list_connections(&self) -> Vec<ConnectionInfo> {
+        let mut connections: Vec<ConnectionInfo> = self
+            .connections
+            .iter()
+            .map(|entry| {
+                let connection = entry.value();
+                let primary_claim = self.client_primary_claims.get(entry.key());
+                ConnectionInfo {
+                    connection_id: *entry.key(),
+                    identity: connection.identity.label(),
+                    user: connection.identity.user().map(str::to_string),
+                    remote_addr: connection.remote_addr.map(|addr| addr.to_string()),
+                    client_name: connection.client_name.clone(),
+                    client_version: connection.client_version.clone(),
+                    device_name: primary_claim.map(|claim| claim.device_name.clone()),
+                    connected_at: connection.connected_at.clone(),
+                    subscribed_to_list: connection.subscribed_to_list,
+                    subscriptions: connection.subscriptions.iter().cloned().collect(),
+                }
+            })
+            .collect();
+        connections.sort_by_key(|connection| connection.connection_id);
+        connections
+    }
+
+    /// Connections authenticated with a database token, with the token id.
+    pub fn token_connections(&self) -> Vec<(u64, String)> {
+        self.connections
+            .iter()
+            .filter_map(|entry| match &entry.value().identity {
+                AuthIdentity::Token { id, .. } => Some((*entry.key(), id.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Ask a connection to close. Returns false if it is not connected.
+    pub fn disconnect_connection(&self, conn_id: u64) -> bool {
+        match self.connections.get(&conn_id) {
+            Some(entry) => {
+                entry.disconnect.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ask every connection to close, e.g. on shutdown.
+    pub fn disconnect_all_connections(&self) {
+        for entry in self.connections.iter() {
+            entry.disconnect.notify_one();
+        }
+    }
+
     pub fn active_client_primary_claims(&self) -> Vec<ClientPrimaryClaim> {
         let mut by_client: BTreeMap<String, String> = BTreeMap::new();
         for claim in self.client_primary_claims.iter() {
@@ -185,6 +481,10 @@ impl SessionRegistry {
         self.codex_auth.clone()
     }
 
+    pub fn claude_auth(&self) -> Arc<ClaudeAuthService> {
+        self.claude_auth.clone()
+    }
+
     /// Get naming guard for AI session naming dedup
     pub fn naming_guard(&self) -> &Arc<NamingGuard> {
         &self.naming_guard
@@ -194,6 +494,19 @@ impl SessionRegistry {
         self.shell_service.clone()
     }
 
+    pub fn terminal_service(&self) -> Arc<TerminalService> {
+        self.terminal_service.clone()
+    }
+
+    pub fn shell_policy(&self) -> Arc<ShellPolicy> {
+        self.shell_policy.clone()
+    }
+
+    #[cfg(test)]
+    pub fn set_shell_policy(&mut self, policy: ShellPolicy) {
+        self.shell_policy = Arc::new(policy);
+    }
+
     /// Store a Codex action sender
     pub fn set_codex_action_tx(&self, session_id: &str, tx: mpsc::Sender<CodexAction>) {
         self.codex_actions.insert(session_id.to_string(), tx);
@@ -224,6 +537,307 @@ impl SessionRegistry {
         self.claude_actions.remove(session_id);
     }
 
+    pub fn session_sharing(&self, session_id: &str) -> SessionSharing {
+        self.session_sharing
+            .get(session_id)
+            .map(|entry| entry.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_session_sharing(&self, session_id: &str, sharing: SessionSharing) {
+        self.session_sharing.insert(session_id.to_string(), sharing);
+    }
+
+    pub fn session_tags(&self, session_id: &str) -> Vec<String> {
+        self.session_tags
+            .get(session_id)
+            .map(|entry| entry.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_session_tags(&self, session_id: &str, tags: Vec<String>) {
+        if tags.is_empty() {
+            self.session_tags.remove(session_id);
+        } else {
+            self.session_tags.insert(session_id.to_string(), tags);
+        }
+    }
+
+    pub fn session_mcp_servers(&self, session_id: &str) -> Vec<SessionMcpServer> {
+        self.session_mcp_servers
+            .get(session_id)
+            .map(|entry| entry.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_session_mcp_servers(&self, session_id: &str, servers: Vec<SessionMcpServer>) {
+        if servers.is_empty() {
+            self.session_mcp_servers.remove(session_id);
+        } else {
+            self.session_mcp_servers
+                .insert(session_id.to_string(), servers);
+        }
+    }
+
+    pub fn session_issue(&self, session_id: &str) -> Option<IssueLink> {
+        self.session_issues
+            .get(session_id)
+            .map(|entry| entry.clone())
+    }
+
+    pub fn set_session_issue(&self, session_id: &str, issue: IssueLink) {
+        self.session_issues.insert(session_id.to_string(), issue);
+    }
+
+    pub fn session_ticket(&self, session_id: &str) -> Option<TicketLink> {
+        self.session_tickets
+            .get(session_id)
+            .map(|entry| entry.clone())
+    }
+
+    pub fn set_session_ticket(&self, session_id: &str, ticket: Option<TicketLink>) {
+        match ticket {
+            Some(ticket) => {
+                self.session_tickets.insert(session_id.to_string(), ticket);
+            }
+            None => {
+                self.session_tickets.remove(session_id);
+            }
+        }
+    }
+
+    /// Live sessions started from `repo`#`number`.
+    pub fn sessions_for_issue(&self, repo: &str, number: u64) -> Vec<String> {
+        self.session_issues
+            .iter()
+            .filter(|entry| entry.repo.eq_ignore_ascii_case(repo) && entry.number == number)
+            .map(|entry| entry.key().clone())
+            .filter(|session_id| self.sessions.contains_key(session_id))
+            .collect()
+    }
+
+    pub fn peers(&self) -> &Peers {
+        &self.peers
+    }
+
+    pub fn attention(&self) -> &AttentionInbox {
+        &self.attention
+    }
+
+    /// The user who created a session, checking the database for sessions
+    /// that are no longer in memory.
+    pub async fn session_owner(&self, session_id: &str) -> Option<String> {
+        match self.get_session(session_id) {
+            Some(actor) => actor.snapshot().created_by.clone(),
+            None => session_access::load_owner(&self.db_path, session_id).await,
+        }
+    }
+
+    /// Whether `identity` may see and act on `session_id`.
+    pub async fn can_access_session(&self, identity: &AuthIdentity, session_id: &str) -> bool {
+        let Some(user) = identity.user() else {
+            return true;
+        };
+        if federation::is_peer_session(session_id) {
+            return false;
+        }
+        let owner = self.session_owner(session_id).await;
+        session_access::allows(
+            Some(user),
+            owner.as_deref(),
+            &self.session_sharing(session_id),
+        )
+    }
+
+    /// Approval history rows for sessions `identity` can access.
+    pub async fn visible_approvals(
+        &self,
+        identity: &AuthIdentity,
+        approvals: Vec<ApprovalHistoryItem>,
+    ) -> Vec<ApprovalHistoryItem> {
+        if identity.user().is_none() {
+            return approvals;
+        }
+        let allowed = self
+            .accessible_sessions(
+                identity,
+                approvals.iter().map(|row| row.session_id.as_str()),
+            )
+            .await;
+        approvals
+            .into_iter()
+            .filter(|row| allowed.contains(&row.session_id))
+            .collect()
+    }
+
+    /// Audit entries `identity` may read: those for sessions it can access,
+    /// and server-wide entries for actions it took itself.
+    pub async fn visible_audit_entries(
+        &self,
+        identity: &AuthIdentity,
+        entries: Vec<AuditLogEntry>,
+    ) -> Vec<AuditLogEntry> {
+        let Some(user) = identity.user() else {
+            return entries;
+        };
+        let allowed = self
+            .accessible_sessions(
+                identity,
+                entries
+                    .iter()
+                    .filter_map(|entry| entry.session_id.as_deref()),
+            )
+            .await;
+        entries
+            .into_iter()
+            .filter(|entry| match &entry.session_id {
+                Some(session_id) => allowed.contains(session_id),
+                None => entry.user.as_deref() == Some(user),
+            })
+            .collect()
+    }
+
+    /// A page of approval history `identity` may read, newest first.
+    pub async fn list_visible_approvals(
+        &self,
+        identity: &AuthIdentity,
+        session_id: Option<String>,
+        limit: Option<u32>,
+    ) -> anyhow::Result<Vec<ApprovalHistoryItem>> {
+        let limit = persistence::list_page_limit(limit);
+        collect_visible_pages(
+            limit,
+            None,
+            |row: &ApprovalHistoryItem| row.id,
+            |before_id| persistence::list_approvals(session_id.clone(), Some(limit), before_id),
+            |page| self.visible_approvals(identity, page),
+        )
+        .await
+    }
+
+    /// A page of audit entries `identity` may read, newest first.
+    pub async fn list_visible_audit_log(
+        &self,
+        identity: &AuthIdentity,
+        session_id: Option<String>,
+        limit: Option<u32>,
+        before_id: Option<i64>,
+    ) -> anyhow::Result<Vec<AuditLogEntry>> {
+        let limit = persistence::list_page_limit(limit);
+        collect_visible_pages(
+            limit,
+            before_id,
+            |entry: &AuditLogEntry| entry.id,
+            |before_id| persistence::list_audit_log(session_id.clone(), Some(limit), before_id),
+            |page| self.visible_audit_entries(identity, page),
+        )
+        .await
+    }
+
+    /// The distinct `session_ids` that `identity` can access.
+    async fn accessible_sessions<'a>(
+        &self,
+        identity: &AuthIdentity,
+        session_ids: impl Iterator<Item = &'a str>,
+    ) -> HashSet<String> {
+        let mut allowed = HashSet::new();
+        let mut checked = HashSet::new();
+        for session_id in session_ids {
+            if checked.insert(session_id) && self.can_access_session(identity, session_id).await {
+                allowed.insert(session_id.to_string());
+            }
+        }
+        allowed
+    }
+
+    /// Whether a list subscriber with `identity` should receive `msg`.
+    /// Sessions that are no longer in memory are let through.
+    pub fn can_see_list_message(
+        &self,
+        identity: &AuthIdentity,
+        msg: &orbitdock_protocol::ServerMessage,
+    ) -> bool {
+        let Some(user) = identity.user() else {
+            return true;
+        };
+        let Some(session_id) = session_access::list_message_session(msg) else {
+            return true;
+        };
+        if federation::is_peer_session(session_id) {
+            return false;
+        }
+        let owner = match msg {
+            orbitdock_protocol::ServerMessage::SessionCreated { session } => {
+                session.created_by.clone()
+            }
+            _ => self
+                .get_session(session_id)
+                .and_then(|actor| actor.snapshot().created_by.clone()),
+        };
+        session_access::allows(
+            Some(user),
+            owner.as_deref(),
+            &self.session_sharing(session_id),
+        )
+    }
+
+    /// `msg` as a list subscriber with `identity` should receive it, or None
+    /// if they shouldn't. Attention updates are narrowed to their sessions.
+    pub fn list_message_for(
+        &self,
+        identity: &AuthIdentity,
+        msg: orbitdock_protocol::ServerMessage,
+    ) -> Option<orbitdock_protocol::ServerMessage> {
+        match msg {
+            orbitdock_protocol::ServerMessage::AttentionChanged { items } => {
+                Some(orbitdock_protocol::ServerMessage::AttentionChanged {
+                    items: self.visible_attention_items(identity, items),
+                })
+            }
+            msg => self.can_see_list_message(identity, &msg).then_some(msg),
+        }
+    }
+
+    /// Attention items for live sessions `identity` can access.
+    pub fn visible_attention_items(
+        &self,
+        identity: &AuthIdentity,
+        mut items: Vec<AttentionItem>,
+    ) -> Vec<AttentionItem> {
+        let Some(user) = identity.user() else {
+            return items;
+        };
+        items.retain(|item| {
+            let owner = self
+                .get_session(&item.session_id)
+                .and_then(|actor| actor.snapshot().created_by.clone());
+            !federation::is_peer_session(&item.session_id)
+                && session_access::allows(
+                    Some(user),
+                    owner.as_deref(),
+                    &self.session_sharing(&item.session_id),
+                )
+        });
+        items
+    }
+
+    /// Session summaries `identity` is allowed to see. Peer sessions are
+    /// only listed for operator credentials.
+    pub fn visible_session_summaries(&self, identity: &AuthIdentity) -> Vec<SessionSummary> {
+        let mut summaries = self.get_session_summaries();
+        match identity.user() {
+            Some(user) => summaries.retain(|summary| {
+                session_access::allows(
+                    Some(user),
+                    summary.created_by.as_deref(),
+                    &self.session_sharing(&summary.id),
+                )
+            }),
+            None => summaries.extend(self.peers.summaries()),
+        }
+        summaries
+    }
+
     /// Get all session summaries (lock-free via snapshots)
     pub fn get_session_summaries(&self) -> Vec<SessionSummary> {
         self.sessions
@@ -244,6 +858,7 @@ impl SessionRegistry {
                     work_status: snap.work_status,
                     token_usage: snap.token_usage.clone(),
                     token_usage_snapshot_kind: snap.token_usage_snapshot_kind,
+                    estimated_cost_usd: snap.estimated_cost_usd,
                     has_pending_approval: snap.has_pending_approval,
                     codex_integration_mode: snap.codex_integration_mode,
                     claude_integration_mode: snap.claude_integration_mode,
@@ -256,6 +871,7 @@ impl SessionRegistry {
                     pending_approval_id: snap.pending_approval_id.clone(),
                     started_at: snap.started_at.clone(),
                     last_activity_at: snap.last_activity_at.clone(),
+                    created_by: snap.created_by.clone(),
                     git_branch: snap.git_branch.clone(),
                     git_sha: snap.git_sha.clone(),
                     current_cwd: snap.current_cwd.clone(),
@@ -267,6 +883,10 @@ impl SessionRegistry {
                     is_worktree: snap.is_worktree,
                     worktree_id: snap.worktree_id.clone(),
                     unread_count: snap.unread_count,
+                    tags: self.session_tags(&snap.id),
+                    origin: None,
+                    issue: self.session_issue(&snap.id),
+                    ticket: self.session_ticket(&snap.id),
                 }
             })
             .collect()
@@ -302,6 +922,7 @@ impl SessionRegistry {
         self.claude_actions.remove(id);
         self.codex_threads.retain(|_, session_id| session_id != id);
         self.claude_threads.retain(|_, session_id| session_id != id);
+        self.claude_hooks_seen.remove(id);
         self.sessions.remove(id).map(|(_, v)| v)
     }
 
@@ -455,6 +1076,24 @@ impl SessionRegistry {
             .retain(|_, pending| pending.cached_at > cutoff);
     }
 
+    /// Record that a Claude session delivered a hook just now.
+    pub fn note_claude_hook(&self, session_id: &str) {
+        self.claude_hooks_seen
+            .insert(session_id.to_string(), Instant::now());
+    }
+
+    /// Stop tracking hooks for a Claude session that has ended.
+    pub fn forget_claude_hooks(&self, session_id: &str) {
+        self.claude_hooks_seen.remove(session_id);
+    }
+
+    /// Whether a Claude session delivered a hook within `window`.
+    pub fn claude_hook_seen_within(&self, session_id: &str, window: Duration) -> bool {
+        self.claude_hooks_seen
+            .get(session_id)
+            .is_some_and(|seen| seen.elapsed() < window)
+    }
+
     /// Collect recent project paths from active/ended sessions.
     pub async fn list_recent_projects(&self) -> Vec<orbitdock_protocol::RecentProject> {
         use std::collections::HashMap;
@@ -496,4 +1135,34 @@ impl SessionRegistry {
     }
 }
 
+/// Page newest-first through `fetch(before_id)` until `limit` rows survive
+/// `visible`, so rows a user can't see don't shorten their page.
+pub(crate) async fn collect_visible_pages<T, Fetch, FetchFut, Visible, VisibleFut>(
+    limit: u32,
+    mut before_id: Option<i64>,
+    row_id: impl Fn(&T) -> i64,
+    mut fetch: Fetch,
+    visible: Visible,
+) -> anyhow::Result<Vec<T>>
+where
+    Fetch: FnMut(Option<i64>) -> FetchFut,
+    FetchFut: Future<Output = anyhow::Result<Vec<T>>>,
+    Visible: Fn(Vec<T>) -> VisibleFut,
+    VisibleFut: Future<Output = Vec<T>>,
+{
+    let limit = limit as usize;
+    let mut rows = Vec::new();
+    loop {
+        let page = fetch(before_id).await?;
+        let last_page = page.len() < limit;
+        before_id = page.last().map(&row_id);
+        rows.extend(visible(page).await);
+        if last_page || rows.len() >= limit {
+            break;
+        }
+    }
+    rows.truncate(limit);
+    Ok(rows)
+}
+
 // Note: No Default impl - requires persist_tx
@@ -0,0 +1,91 @@
+//! Message text as stored in SQLite.
+//!
+//! A message's `content` or `tool_output` longer than `COMPRESS_OVER_BYTES`
+//! is written as a zstd-compressed BLOB instead of TEXT; build logs and test
+//! runs captured as tool output shrink several times over. Reads go through
+//! [`StoredText`], which accepts either, so rows written before compression
+//! existed still load. SQL that inspects these columns directly (`trim`,
+//! `LIKE`) doesn't see inside compressed rows.
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, Value, ValueRef};
+
+/// Text up to this size stays plain TEXT.
+pub const COMPRESS_OVER_BYTES: usize = 16 * 1024;
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The column value to write for `text`.
+pub fn to_sql(text: &str) -> Value {
+    if text.len() > COMPRESS_OVER_BYTES {
+        if let Ok(compressed) = zstd::encode_all(text.as_bytes(), COMPRESSION_LEVEL) {
+            return Value::Blob(compressed);
+        }
+    }
+    Value::Text(text.to_string())
+}
+
+/// [`to_sql`] for a nullable column.
+pub fn option_to_sql(text: Option<&str>) -> Value {
+    text.map_or(Value::Null, to_sql)
+}
+
+/// A `content`/`tool_output` column read back as text, plain or compressed.
+pub struct StoredText(pub Option<String>);
+
+impl StoredText {
+    pub fn into_string(self) -> String {
+        self.0.unwrap_or_default()
+    }
+}
+
+impl FromSql for StoredText {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(Self(None)),
+            ValueRef::Text(bytes) => Ok(Self(Some(String::from_utf8_lossy(bytes).into_owned()))),
+            ValueRef::Blob(bytes) => {
+                let decoded = zstd::decode_all(bytes).map_err(|e| FromSqlError::Other(e.into()))?;
+                String::from_utf8(decoded)
+                    .map(|text| Self(Some(text)))
+                    .map_err(|e| FromSqlError::Other(e.into()))
+            }
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn round_trip(value: Value) -> (String, StoredText) {
+        let conn = Connection::open_in_memory().expect("open db");
+        conn.execute_batch("CREATE TABLE t (body TEXT)")
+            .expect("create table");
+        conn.execute("INSERT INTO t (body) VALUES (?1)", [value])
+            .expect("insert");
+        conn.query_row("SELECT typeof(body), body FROM t", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .expect("select")
+    }
+
+    #[test]
+    fn large_text_is_stored_compressed_and_reads_back() {
+        let log = "cargo build: compiling crate\n".repeat(2_000);
+        let (column_type, stored) = round_trip(to_sql(&log));
+        assert_eq!(column_type, "blob");
+        assert_eq!(stored.0.as_deref(), Some(log.as_str()));
+    }
+
+    #[test]
+    fn small_and_missing_text_stay_plain() {
+        let (column_type, stored) = round_trip(to_sql("ok"));
+        assert_eq!(column_type, "text");
+        assert_eq!(stored.into_string(), "ok");
+
+        let (column_type, stored) = round_trip(option_to_sql(None));
+        assert_eq!(column_type, "null");
+        assert!(stored.0.is_none());
+    }
+}
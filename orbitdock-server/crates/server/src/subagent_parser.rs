@@ -1,14 +1,127 @@
 //! Subagent transcript JSONL parser
 //!
-//! Parses tool calls from subagent transcript files.
-//! Two-pass approach: first collect tool_result outputs, then build tool list.
+//! Parses tool calls, token usage, and spawned subagents from subagent
+//! transcript files. Tool calls and their results are parsed separately and
+//! matched by `tool_use_id`, which lets persistence parse a growing
+//! transcript a chunk at a time (see `parse_tool_lines`).
+//!
+//! A subagent can spawn subagents of its own with the Task tool. The result of
+//! each Task call names the subagent it ran (`toolUseResult.agentId`), which is
+//! how nested subagents are tied to their parent.
 
 use std::collections::HashMap;
 use std::path::Path;
 
-use orbitdock_protocol::SubagentTool;
+use orbitdock_protocol::{SubagentTool, TokenUsage};
 use serde_json::Value;
 
+/// What a subagent consumed over its whole transcript.
+#[derive(Debug, Clone, Default)]
+pub struct SubagentUsage {
+    pub model: Option<String>,
+    /// Summed over every request; `context_window` is left at zero.
+    pub usage: TokenUsage,
+    /// `None` when the model has no known price.
+    pub estimated_cost_usd: Option<f64>,
+}
+
+/// Sum token usage over a subagent transcript JSONL file, pricing it at the
+/// subagent's model. `None` if the file can't be read or reports no usage.
+pub fn parse_usage(path: &Path) -> Option<SubagentUsage> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut usage = sum_usage(&content)?;
+    usage.estimated_cost_usd = usage.model.as_deref().and_then(|model| {
+        crate::pricing::estimate_cost(
+            "claude",
+            model,
+            usage.usage.input_tokens,
+            usage.usage.output_tokens,
+            usage.usage.cached_tokens,
+        )
+    });
+    Some(usage)
+}
+
+fn sum_usage(content: &str) -> Option<SubagentUsage> {
+    // One API response is written as a line per content block, each repeating
+    // the response's usage; keep the last line of each so it counts once.
+    let mut responses: Vec<TokenUsage> = Vec::new();
+    let mut index_by_id: HashMap<String, usize> = HashMap::new();
+    let mut model = None;
+    for line in content.lines().filter(|l| !l.is_empty()) {
+        let json: Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if json.get("type").and_then(|t| t.as_str()) != Some("assistant") {
+            continue;
+        }
+        let Some(message) = json.get("message") else {
+            continue;
+        };
+        let Some(usage) = message.get("usage") else {
+            continue;
+        };
+        let tokens = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        let entry = TokenUsage {
+            input_tokens: tokens("input_tokens"),
+            output_tokens: tokens("output_tokens"),
+            cached_tokens: tokens("cache_read_input_tokens")
+                + tokens("cache_creation_input_tokens"),
+            context_window: 0,
+        };
+        if let Some(name) = message.get("model").and_then(|m| m.as_str()) {
+            if name != "<synthetic>" {
+                model = Some(name.to_string());
+            }
+        }
+        match message.get("id").and_then(|id| id.as_str()) {
+            Some(id) => match index_by_id.get(id) {
+                Some(&index) => responses[index] = entry,
+                None => {
+                    index_by_id.insert(id.to_string(), responses.len());
+                    responses.push(entry);
+                }
+            },
+            None => responses.push(entry),
+        }
+    }
+    if responses.is_empty() {
+        return None;
+    }
+
+    let mut total = TokenUsage::default();
+    for entry in responses {
+        total.input_tokens += entry.input_tokens;
+        total.output_tokens += entry.output_tokens;
+        total.cached_tokens += entry.cached_tokens;
+    }
+    Some(SubagentUsage {
+        model,
+        usage: total,
+        estimated_cost_usd: None,
+    })
+}
+
+/// Tool calls and tool results found in a run of transcript lines.
+///
+/// Results can land in a later run than their call, so the two are kept apart
+/// and matched by `tool_use_id` by whoever holds the earlier calls.
+#[derive(Debug, Default)]
+pub struct ParsedToolLines {
+    /// `(tool_use_id, tool)` in transcript order, with no output yet.
+    pub calls: Vec<(String, SubagentTool)>,
+    pub results: Vec<ToolResult>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolResult {
+    pub tool_use_id: String,
+    pub output: String,
+    /// Subagent a Task call spawned, when this is a Task result.
+    pub subagent_id: Option<String>,
+}
+
 /// Parse tool calls from a subagent transcript JSONL file.
 /// Returns a list of tools with their summaries and outputs.
 pub fn parse_tools(path: &Path) -> Vec<SubagentTool> {
@@ -17,95 +130,146 @@ pub fn parse_tools(path: &Path) -> Vec<SubagentTool> {
         Err(_) => return Vec::new(),
     };
 
-    let lines: Vec<&str> = content.lines().filter(|l| !l.is_empty()).collect();
+    let parsed = parse_tool_lines(&content);
+    let results: HashMap<String, ToolResult> = parsed
+        .results
+        .into_iter()
+        .map(|result| (result.tool_use_id.clone(), result))
+        .collect();
+
+    parsed
+        .calls
+        .into_iter()
+        .map(|(tool_use_id, mut tool)| {
+            if let Some(result) = results.get(&tool_use_id) {
+                tool.output = Some(result.output.clone());
+                tool.is_in_progress = false;
+                tool.subagent_id = result.subagent_id.clone();
+            }
+            tool
+        })
+        .collect()
+}
 
-    // Pass 1: collect tool_result content by tool_use_id
-    let mut tool_results: HashMap<String, String> = HashMap::new();
+/// Parse the tool calls and results in `content`, a run of whole JSONL lines.
+pub fn parse_tool_lines(content: &str) -> ParsedToolLines {
+    let mut parsed = ParsedToolLines::default();
 
-    for line in &lines {
+    for line in content.lines().filter(|l| !l.is_empty()) {
         let json: Value = match serde_json::from_str(line) {
             Ok(v) => v,
             Err(_) => continue,
         };
 
-        if json.get("type").and_then(|t| t.as_str()) != Some("user") {
-            continue;
+        match json.get("type").and_then(|t| t.as_str()) {
+            Some("user") => collect_tool_results(&json, &mut parsed.results),
+            Some("assistant") => collect_tool_calls(&json, &mut parsed.calls),
+            _ => {}
         }
+    }
 
-        if let Some(content_array) = json
-            .get("message")
-            .and_then(|m| m.get("content"))
-            .and_then(|c| c.as_array())
-        {
-            for item in content_array {
-                if item.get("type").and_then(|t| t.as_str()) == Some("tool_result") {
-                    if let Some(tool_use_id) = item.get("tool_use_id").and_then(|id| id.as_str()) {
-                        let result_text = extract_tool_result_content(item);
-                        tool_results.insert(tool_use_id.to_string(), result_text);
-                    }
-                }
-            }
+    parsed
+}
+
+/// Tool results in a user line, and the subagents Task results spawned.
+fn collect_tool_results(json: &Value, results: &mut Vec<ToolResult>) {
+    let Some(content_array) = json
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_array())
+    else {
+        return;
+    };
+
+    for item in content_array {
+        if item.get("type").and_then(|t| t.as_str()) != Some("tool_result") {
+            continue;
+        }
+        if let Some(tool_use_id) = item.get("tool_use_id").and_then(|id| id.as_str()) {
+            let output = extract_tool_result_content(item);
+            let subagent_id = spawned_agent_id(json, &output);
+            results.push(ToolResult {
+                tool_use_id: tool_use_id.to_string(),
+                output,
+                subagent_id,
+            });
         }
     }
+}
 
-    // Pass 2: build tool messages from assistant tool_use items
-    let mut tools: Vec<SubagentTool> = Vec::new();
+/// Tool calls from the tool_use items of an assistant line.
+fn collect_tool_calls(json: &Value, calls: &mut Vec<(String, SubagentTool)>) {
+    let uuid = match json.get("uuid").and_then(|u| u.as_str()) {
+        Some(u) => u,
+        None => return,
+    };
 
-    for line in &lines {
-        let json: Value = match serde_json::from_str(line) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
+    let content_array = match json
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_array())
+    {
+        Some(arr) => arr,
+        None => return,
+    };
 
-        if json.get("type").and_then(|t| t.as_str()) != Some("assistant") {
+    for (index, item) in content_array.iter().enumerate() {
+        if item.get("type").and_then(|t| t.as_str()) != Some("tool_use") {
             continue;
         }
 
-        let uuid = match json.get("uuid").and_then(|u| u.as_str()) {
-            Some(u) => u,
+        let tool_name = match item.get("name").and_then(|n| n.as_str()) {
+            Some(n) => n.to_string(),
             None => continue,
         };
 
-        let content_array = match json
-            .get("message")
-            .and_then(|m| m.get("content"))
-            .and_then(|c| c.as_array())
-        {
-            Some(arr) => arr,
+        let tool_id = match item.get("id").and_then(|id| id.as_str()) {
+            Some(id) => id.to_string(),
             None => continue,
         };
 
-        for (index, item) in content_array.iter().enumerate() {
-            if item.get("type").and_then(|t| t.as_str()) != Some("tool_use") {
-                continue;
-            }
-
-            let tool_name = match item.get("name").and_then(|n| n.as_str()) {
-                Some(n) => n.to_string(),
-                None => continue,
-            };
-
-            let tool_id = match item.get("id").and_then(|id| id.as_str()) {
-                Some(id) => id.to_string(),
-                None => continue,
-            };
+        let summary = create_tool_summary(&tool_name, item.get("input"));
 
-            let input = item.get("input");
-            let summary = create_tool_summary(&tool_name, input);
-            let output = tool_results.get(&tool_id).cloned();
-            let is_in_progress = output.is_none();
-
-            tools.push(SubagentTool {
+        calls.push((
+            tool_id,
+            SubagentTool {
                 id: format!("{}-tool-{}", uuid, index),
                 tool_name,
                 summary,
-                output,
-                is_in_progress,
-            });
-        }
+                output: None,
+                is_in_progress: true,
+                subagent_id: None,
+            },
+        ));
     }
+}
 
-    tools
+/// Ids of the subagents a transcript's Task calls spawned, in order.
+pub fn spawned_subagent_ids(path: &Path) -> Vec<String> {
+    parse_tools(path)
+        .into_iter()
+        .filter_map(|tool| tool.subagent_id)
+        .collect()
+}
+
+/// The subagent a Task result line reports, from the structured result or,
+/// in older transcripts, the `agentId: <id>` line in its text.
+fn spawned_agent_id(line: &Value, result_text: &str) -> Option<String> {
+    if let Some(agent_id) = line
+        .get("toolUseResult")
+        .and_then(|result| result.get("agentId"))
+        .and_then(|id| id.as_str())
+    {
+        return Some(agent_id.to_string());
+    }
+    result_text.lines().find_map(|text| {
+        let id = text
+            .trim()
+            .strip_prefix("agentId:")?
+            .split_whitespace()
+            .next()?;
+        Some(id.to_string())
+    })
 }
 
 /// Extract text content from a tool_result item.
@@ -265,6 +429,44 @@ mod tests {
         assert_eq!(create_tool_summary("Unknown", None), "Unknown");
     }
 
+    #[test]
+    fn test_sum_usage_counts_each_response_once() {
+        let transcript = [
+            r#"{"type":"user","message":{"role":"user","content":"Find the config loader"}}"#,
+            r#"{"type":"assistant","message":{"id":"msg_1","model":"claude-sonnet-4-5","usage":{"input_tokens":10,"output_tokens":5,"cache_read_input_tokens":100,"cache_creation_input_tokens":20},"content":[{"type":"text","text":"Looking"}]}}"#,
+            r#"{"type":"assistant","message":{"id":"msg_1","model":"claude-sonnet-4-5","usage":{"input_tokens":10,"output_tokens":40,"cache_read_input_tokens":100,"cache_creation_input_tokens":20},"content":[{"type":"tool_use","id":"t1","name":"Grep","input":{}}]}}"#,
+            r#"{"type":"assistant","message":{"id":"msg_2","model":"claude-sonnet-4-5","usage":{"input_tokens":3,"output_tokens":60,"cache_read_input_tokens":150},"content":[{"type":"text","text":"Found it"}]}}"#,
+        ]
+        .join("\n");
+
+        let usage = sum_usage(&transcript).unwrap();
+        assert_eq!(usage.model.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(usage.usage.input_tokens, 13);
+        assert_eq!(usage.usage.output_tokens, 100);
+        assert_eq!(usage.usage.cached_tokens, 270);
+        assert!(sum_usage(r#"{"type":"user","message":{"content":"hi"}}"#).is_none());
+    }
+
+    #[test]
+    fn test_parse_tools_links_task_calls_to_spawned_subagents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent-parent.jsonl");
+        let transcript = [
+            r#"{"type":"assistant","uuid":"u1","message":{"content":[{"type":"tool_use","id":"t1","name":"Task","input":{"description":"Audit auth","subagent_type":"Explore"}},{"type":"tool_use","id":"t2","name":"Task","input":{"description":"Audit db"}}]}}"#,
+            r#"{"type":"user","toolUseResult":{"status":"completed","agentId":"child-1"},"message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":[{"type":"text","text":"No issues"}]}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t2","content":"Two slow queries\nagentId: child-2 (for resuming)"}]}}"#,
+            r#"{"type":"assistant","uuid":"u2","message":{"content":[{"type":"tool_use","id":"t3","name":"Read","input":{"file_path":"/repo/src/db.rs"}}]}}"#,
+        ]
+        .join("\n");
+        std::fs::write(&path, transcript).unwrap();
+
+        let tools = parse_tools(&path);
+        assert_eq!(tools[0].subagent_id.as_deref(), Some("child-1"));
+        assert_eq!(tools[1].subagent_id.as_deref(), Some("child-2"));
+        assert_eq!(tools[2].subagent_id, None);
+        assert_eq!(spawned_subagent_ids(&path), ["child-1", "child-2"]);
+    }
+
     #[test]
     fn test_extract_tool_result_string() {
         let item = serde_json::json!({"content": "hello world", "type": "tool_result"});
@@ -0,0 +1,299 @@
+//! Interactive PTY terminals for user-initiated shell sessions.
+//!
+//! Each terminal runs the user's shell inside a pseudo-terminal rooted at a
+//! session's working directory, so REPLs, `git rebase -i`, and password
+//! prompts work. Terminals are owned by the WebSocket connection that opened
+//! them and are torn down when that connection closes.
+
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+use dashmap::DashMap;
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// Terminal dimensions in character cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalSize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+impl TerminalSize {
+    fn to_pty_size(self) -> PtySize {
+        PtySize {
+            rows: self.rows.max(1),
+            cols: self.cols.max(1),
+            pixel_width: 0,
+            pixel_height: 0,
+        }
+    }
+}
+
+/// Events emitted by a running terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalEvent {
+    /// Raw bytes written by the shell to the PTY.
+    Output(Vec<u8>),
+    /// The shell exited; no more events follow.
+    Exited { exit_code: Option<i32> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalError {
+    NotFound,
+    Spawn(String),
+    Io(String),
+}
+
+impl std::fmt::Display for TerminalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TerminalError::NotFound => f.write_str("terminal not found"),
+            TerminalError::Spawn(e) => write!(f, "failed to start terminal: {e}"),
+            TerminalError::Io(e) => write!(f, "terminal I/O failed: {e}"),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TerminalService {
+    active: Arc<DashMap<String, ActiveTerminal>>,
+}
+
+struct ActiveTerminal {
+    session_id: String,
+    conn_id: u64,
+    input_tx: std::sync::mpsc::Sender<Vec<u8>>,
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    killer: Mutex<Box<dyn ChildKiller + Send + Sync>>,
+}
+
+impl TerminalService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawn a shell in a new PTY and return its event stream.
+    pub fn open(
+        &self,
+        terminal_id: String,
+        session_id: String,
+        conn_id: u64,
+        cwd: &str,
+        size: TerminalSize,
+    ) -> Result<mpsc::UnboundedReceiver<TerminalEvent>, TerminalError> {
+        let pair = native_pty_system()
+            .openpty(size.to_pty_size())
+            .map_err(|e| TerminalError::Spawn(e.to_string()))?;
+
+        let mut cmd = CommandBuilder::new(default_shell());
+        cmd.cwd(cwd);
+        cmd.env("TERM", "xterm-256color");
+        let mut child = pair
+            .slave
+            .spawn_command(cmd)
+            .map_err(|e| TerminalError::Spawn(e.to_string()))?;
+        // Drop our copy of the slave so reads hit EOF once the shell exits.
+        drop(pair.slave);
+
+        let mut reader = pair
+            .master
+            .try_clone_reader()
+            .map_err(|e| TerminalError::Io(e.to_string()))?;
+        let mut writer = pair
+            .master
+            .take_writer()
+            .map_err(|e| TerminalError::Io(e.to_string()))?;
+
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let (input_tx, input_rx) = std::sync::mpsc::channel::<Vec<u8>>();
+
+        self.active.insert(
+            terminal_id.clone(),
+            ActiveTerminal {
+                session_id,
+                conn_id,
+                input_tx,
+                master: Mutex::new(pair.master),
+                killer: Mutex::new(child.clone_killer()),
+            },
+        );
+
+        // PTY I/O is blocking, so both directions run on dedicated threads.
+        std::thread::spawn(move || {
+            while let Ok(bytes) = input_rx.recv() {
+                if writer
+                    .write_all(&bytes)
+                    .and_then(|_| writer.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let active = self.active.clone();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 8192];
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        if event_tx
+                            .send(TerminalEvent::Output(buf[..n].to_vec()))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            let exit_code = match child.wait() {
+                Ok(status) => Some(status.exit_code() as i32),
+                Err(e) => {
+                    warn!(
+                        component = "terminal",
+                        event = "terminal.wait_failed",
+                        terminal_id = %terminal_id,
+                        error = %e,
+                        "Failed to reap terminal shell"
+                    );
+                    None
+                }
+            };
+            active.remove(&terminal_id);
+            debug!(
+                component = "terminal",
+                event = "terminal.exited",
+                terminal_id = %terminal_id,
+                exit_code = ?exit_code,
+                "Terminal shell exited"
+            );
+            let _ = event_tx.send(TerminalEvent::Exited { exit_code });
+        });
+
+        Ok(event_rx)
+    }
+
+    pub fn write(&self, terminal_id: &str, conn_id: u64, data: &[u8]) -> Result<(), TerminalError> {
+        let entry = self.owned(terminal_id, conn_id)?;
+        entry
+            .input_tx
+            .send(data.to_vec())
+            .map_err(|e| TerminalError::Io(e.to_string()))
+    }
+
+    pub fn resize(
+        &self,
+        terminal_id: &str,
+        conn_id: u64,
+        size: TerminalSize,
+    ) -> Result<(), TerminalError> {
+        let entry = self.owned(terminal_id, conn_id)?;
+        let master = entry.master.lock().unwrap_or_else(|e| e.into_inner());
+        master
+            .resize(size.to_pty_size())
+            .map_err(|e| TerminalError::Io(e.to_string()))
+    }
+
+    /// Kill the terminal's shell. The exit event is still delivered to the owner.
+    pub fn close(&self, terminal_id: &str, conn_id: u64) -> Result<(), TerminalError> {
+        let entry = self.owned(terminal_id, conn_id)?;
+        let mut killer = entry.killer.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = killer.kill();
+        Ok(())
+    }
+
+    /// Close every terminal opened by a WebSocket connection.
+    pub fn close_connection(&self, conn_id: u64) -> usize {
+        self.close_matching(|terminal| terminal.conn_id == conn_id)
+    }
+
+    /// Close every terminal attached to a session (e.g. when the session ends).
+    pub fn close_session(&self, session_id: &str) -> usize {
+        self.close_matching(|terminal| terminal.session_id == session_id)
+    }
+
+    fn close_matching(&self, predicate: impl Fn(&ActiveTerminal) -> bool) -> usize {
+        let mut closed = 0usize;
+        for entry in self.active.iter() {
+            if predicate(entry.value()) {
+                let mut killer = entry.killer.lock().unwrap_or_else(|e| e.into_inner());
+                let _ = killer.kill();
+                closed += 1;
+            }
+        }
+        closed
+    }
+
+    fn owned(
+        &self,
+        terminal_id: &str,
+        conn_id: u64,
+    ) -> Result<dashmap::mapref::one::Ref<'_, String, ActiveTerminal>, TerminalError> {
+        match self.active.get(terminal_id) {
+            Some(entry) if entry.conn_id == conn_id => Ok(entry),
+            _ => Err(TerminalError::NotFound),
+        }
+    }
+}
+
+fn default_shell() -> String {
+    std::env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.trim().is_empty())
+        .unwrap_or_else(|| "/bin/sh".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TerminalError, TerminalEvent, TerminalService, TerminalSize};
+    use tokio::time::{timeout, Duration};
+
+    const SIZE: TerminalSize = TerminalSize { cols: 80, rows: 24 };
+
+    #[tokio::test]
+    async fn terminal_echoes_input_and_reports_exit() {
+        let service = TerminalService::new();
+        let mut events = service
+            .open("term-1".into(), "sess-1".into(), 7, "/tmp", SIZE)
+            .expect("open terminal");
+
+        service
+            .write("term-1", 7, b"echo orbit-$((40+2))\nexit 3\n")
+            .expect("write input");
+
+        let mut output = Vec::new();
+        let exit_code = timeout(Duration::from_secs(10), async {
+            while let Some(event) = events.recv().await {
+                match event {
+                    TerminalEvent::Output(bytes) => output.extend(bytes),
+                    TerminalEvent::Exited { exit_code } => return exit_code,
+                }
+            }
+            None
+        })
+        .await
+        .expect("terminal exit");
+
+        assert!(String::from_utf8_lossy(&output).contains("orbit-42"));
+        assert_eq!(exit_code, Some(3));
+    }
+
+    #[tokio::test]
+    async fn terminal_rejects_input_from_other_connections() {
+        let service = TerminalService::new();
+        let _events = service
+            .open("term-2".into(), "sess-2".into(), 1, "/tmp", SIZE)
+            .expect("open terminal");
+
+        assert_eq!(
+            service.write("term-2", 2, b"ls\n"),
+            Err(TerminalError::NotFound)
+        );
+        assert_eq!(service.close_connection(1), 1);
+    }
+}
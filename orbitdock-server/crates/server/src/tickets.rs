@@ -0,0 +1,535 @@
+//! Sessions linked to Linear and Jira tickets.
+//!
+//! `SetSessionTicket` links a session to a ticket; session summaries carry
+//! the link and list subscribers hear about changes as
+//! `session_ticket_changed`. A link with `sync` set posts a comment to the
+//! ticket when the session ends: the session's name and summary, its branch,
+//! and the last pull request URL the conversation mentions. Each link
+//! comments once; linking again re-arms it.
+//!
+//! Credentials come from `[tickets]`. A ticket whose tracker isn't
+//! configured stays linked but is never commented on.
+
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use orbitdock_protocol::{Message, ServerMessage, TicketLink, TicketTracker};
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::persistence::{PersistCommand, RestoredSession};
+use crate::session_command::SessionCommand;
+use crate::state::SessionRegistry;
+
+const LINEAR_API_URL: &str = "https://api.linear.app/graphql";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_KEY_CHARS: usize = 32;
+/// Cap on the summary quoted in a comment.
+const MAX_SUMMARY_CHARS: usize = 2_000;
+
+static PR_URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"https://(?:github\.com/[\w.-]+/[\w.-]+/pull/\d+|gitlab\.com/[\w./-]+/-/merge_requests/\d+)",
+    )
+    .expect("pull request URL pattern")
+});
+
+/// `[tickets]` in the config file.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TicketsSection {
+    /// Personal API key; `LINEAR_API_KEY` otherwise.
+    pub linear_api_key: Option<String>,
+    /// Site URL, e.g. `https://acme.atlassian.net`.
+    pub jira_url: Option<String>,
+    pub jira_email: Option<String>,
+    /// API token for `jira_email`.
+    pub jira_api_token: Option<String>,
+}
+
+impl TicketsSection {
+    fn linear_api_key(&self) -> Option<String> {
+        self.linear_api_key
+            .clone()
+            .or_else(|| std::env::var("LINEAR_API_KEY").ok())
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty())
+    }
+
+    fn jira_url(&self) -> Option<&str> {
+        self.jira_url
+            .as_deref()
+            .map(|url| url.trim().trim_end_matches('/'))
+            .filter(|url| !url.is_empty())
+    }
+
+    /// Problems that parsing alone doesn't catch.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some(url) = self.jira_url() {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                problems.push("tickets.jira_url: must be an http(s) URL".into());
+            }
+        }
+        let jira = [
+            self.jira_url.is_some(),
+            self.jira_email.is_some(),
+            self.jira_api_token.is_some(),
+        ];
+        if jira.contains(&true) && jira.contains(&false) {
+            problems.push(
+                "tickets: jira_url, jira_email, and jira_api_token are needed together".into(),
+            );
+        }
+        problems
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum TicketError {
+    #[error("Ticket ids look like ENG-123")]
+    InvalidId,
+    #[error("Ticket URL must be an http(s) URL")]
+    InvalidUrl,
+}
+
+pub fn tracker_name(tracker: TicketTracker) -> &'static str {
+    match tracker {
+        TicketTracker::Linear => "linear",
+        TicketTracker::Jira => "jira",
+    }
+}
+
+fn parse_tracker(name: &str) -> Option<TicketTracker> {
+    match name {
+        "linear" => Some(TicketTracker::Linear),
+        "jira" => Some(TicketTracker::Jira),
+        _ => None,
+    }
+}
+
+/// `KEY-123`: a team or project key, a dash, and a number.
+fn valid_id(id: &str) -> bool {
+    let Some((key, number)) = id.rsplit_once('-') else {
+        return false;
+    };
+    key.len() <= MAX_KEY_CHARS
+        && key.starts_with(|c: char| c.is_ascii_uppercase())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        && !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Uppercase the id, check it, and fill in a Jira URL from `jira_url`.
+pub fn normalize(
+    mut ticket: TicketLink,
+    config: &TicketsSection,
+) -> Result<TicketLink, TicketError> {
+    ticket.id = ticket.id.trim().to_ascii_uppercase();
+    if !valid_id(&ticket.id) {
+        return Err(TicketError::InvalidId);
+    }
+    ticket.url = ticket
+        .url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    match &ticket.url {
+        Some(url) if !url.starts_with("http://") && !url.starts_with("https://") => {
+            return Err(TicketError::InvalidUrl);
+        }
+        Some(_) => {}
+        None => {
+            if ticket.tracker == TicketTracker::Jira {
+                ticket.url = config
+                    .jira_url()
+                    .map(|base| format!("{base}/browse/{}", ticket.id));
+            }
+        }
+    }
+    Ok(ticket)
+}
+
+/// Replace a session's ticket everywhere: registry, live session, database,
+/// and list subscribers.
+pub async fn set(state: &SessionRegistry, session_id: &str, ticket: Option<TicketLink>) {
+    state.set_session_ticket(session_id, ticket.clone());
+    if let Some(actor) = state.get_session(session_id) {
+        actor
+            .send(SessionCommand::SetTicket {
+                ticket: ticket.clone(),
+            })
+            .await;
+    }
+    let _ = state
+        .persist()
+        .send(PersistCommand::SessionTicketSet {
+            session_id: session_id.to_string(),
+            ticket: ticket.clone(),
+        })
+        .await;
+    state.broadcast_to_list(ServerMessage::SessionTicketChanged {
+        session_id: session_id.to_string(),
+        ticket,
+    });
+}
+
+/// Ticket links, loaded once at startup.
+pub fn load_all(db_path: &Path) -> Vec<(String, TicketLink)> {
+    let result = Connection::open(db_path).and_then(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT session_id, tracker, ticket_id, url, sync FROM session_tickets")?;
+        let rows = stmt.query_map([], |row| {
+            let tracker: String = row.get(1)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                parse_tracker(&tracker),
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, bool>(4)?,
+            ))
+        })?;
+        let mut tickets = Vec::new();
+        for row in rows {
+            let (session_id, tracker, id, url, sync) = row?;
+            if let Some(tracker) = tracker {
+                tickets.push((
+                    session_id,
+                    TicketLink {
+                        tracker,
+                        id,
+                        url,
+                        sync,
+                    },
+                ));
+            }
+        }
+        Ok(tickets)
+    });
+    match result {
+        Ok(tickets) => tickets,
+        Err(e) => {
+            warn!(
+                component = "tickets",
+                event = "tickets.load_failed",
+                error = %e,
+                "Could not load session tickets"
+            );
+            Vec::new()
+        }
+    }
+}
+
+fn already_synced(db_path: &Path, session_id: &str) -> bool {
+    Connection::open(db_path)
+        .and_then(|conn| {
+            conn.query_row(
+                "SELECT synced_at IS NOT NULL FROM session_tickets WHERE session_id = ?1",
+                params![session_id],
+                |row| row.get::<_, bool>(0),
+            )
+            .optional()
+        })
+        .ok()
+        .flatten()
+        .unwrap_or(false)
+}
+
+/// Comment on the session's ticket, if it has one with `sync` set.
+pub fn session_ended(state: &Arc<SessionRegistry>, session_id: &str, reason: &str) {
+    let Some(ticket) = state.session_ticket(session_id).filter(|t| t.sync) else {
+        return;
+    };
+    tokio::spawn(sync(
+        state.clone(),
+        session_id.to_string(),
+        ticket,
+        reason.to_string(),
+    ));
+}
+
+async fn sync(state: Arc<SessionRegistry>, session_id: String, ticket: TicketLink, reason: String) {
+    let db_path = state.db_path().clone();
+    let id = session_id.clone();
+    let synced = tokio::task::spawn_blocking(move || already_synced(&db_path, &id))
+        .await
+        .unwrap_or(true);
+    if synced {
+        return;
+    }
+    let session = match crate::persistence::load_session_by_id(&session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return,
+        Err(e) => {
+            warn!(
+                component = "tickets",
+                event = "tickets.sync_failed",
+                session_id = %session_id,
+                error = %e,
+                "Could not load the session to comment on its ticket"
+            );
+            return;
+        }
+    };
+
+    let body = comment_body(&session, &reason, find_pr_url(&session.messages).as_deref());
+    let config = crate::config_file::current();
+    match post_comment(&config.tickets, &ticket, &body).await {
+        Ok(()) => {
+            let _ = state
+                .persist()
+                .send(PersistCommand::SessionTicketSynced {
+                    session_id: session_id.clone(),
+                })
+                .await;
+            info!(
+                component = "tickets",
+                event = "tickets.synced",
+                session_id = %session_id,
+                tracker = tracker_name(ticket.tracker),
+                ticket = %ticket.id,
+                "Commented on the session's ticket"
+            );
+        }
+        Err(e) => warn!(
+            component = "tickets",
+            event = "tickets.sync_failed",
+            session_id = %session_id,
+            tracker = tracker_name(ticket.tracker),
+            ticket = %ticket.id,
+            error = %e,
+            "Could not comment on the session's ticket"
+        ),
+    }
+}
+
+/// The last pull or merge request URL in the conversation.
+pub fn find_pr_url(messages: &[Message]) -> Option<String> {
+    messages.iter().rev().find_map(|message| {
+        [
+            message.tool_output.as_deref(),
+            Some(message.content.as_str()),
+        ]
+        .into_iter()
+        .flatten()
+        .find_map(|text| PR_URL.find_iter(text).last())
+        .map(|found| found.as_str().to_string())
+    })
+}
+
+/// Markdown for the end-of-session comment; both trackers render it.
+pub fn comment_body(session: &RestoredSession, reason: &str, pr_url: Option<&str>) -> String {
+    let name = session
+        .custom_name
+        .as_deref()
+        .or(session.first_prompt.as_deref())
+        .unwrap_or(&session.id);
+    let mut body = format!("OrbitDock session ended ({reason}): {name}\n");
+    let summary = session
+        .summary
+        .as_deref()
+        .or(session.last_message.as_deref())
+        .map(str::trim)
+        .filter(|summary| !summary.is_empty());
+    if let Some(summary) = summary {
+        let summary: String = summary.chars().take(MAX_SUMMARY_CHARS).collect();
+        body.push_str(&format!("\n{summary}\n"));
+    }
+    body.push('\n');
+    if let Some(pr_url) = pr_url {
+        body.push_str(&format!("Pull request: {pr_url}\n"));
+    }
+    if let Some(branch) = &session.git_branch {
+        body.push_str(&format!("Branch: {branch}\n"));
+    }
+    body.push_str(&format!("Project: {}\n", session.project_path));
+    body.trim_end().to_string()
+}
+
+async fn post_comment(
+    config: &TicketsSection,
+    ticket: &TicketLink,
+    body: &str,
+) -> anyhow::Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+    match ticket.tracker {
+        TicketTracker::Linear => {
+            let Some(api_key) = config.linear_api_key() else {
+                anyhow::bail!("set [tickets] linear_api_key or LINEAR_API_KEY");
+            };
+            let response: serde_json::Value = client
+                .post(LINEAR_API_URL)
+                .header(reqwest::header::AUTHORIZATION, api_key)
+                .json(&json!({
+                    "query": "mutation($issueId: String!, $body: String!) { \
+                              commentCreate(input: { issueId: $issueId, body: $body }) { success } }",
+                    "variables": { "issueId": ticket.id, "body": body },
+                }))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            if let Some(error) = response["errors"][0]["message"].as_str() {
+                anyhow::bail!("Linear: {error}");
+            }
+            if response["data"]["commentCreate"]["success"] != json!(true) {
+                anyhow::bail!("Linear did not create the comment");
+            }
+        }
+        TicketTracker::Jira => {
+            let (Some(url), Some(email), Some(token)) = (
+                config.jira_url(),
+                config.jira_email.as_deref(),
+                config.jira_api_token.as_deref(),
+            ) else {
+                anyhow::bail!("set [tickets] jira_url, jira_email, and jira_api_token");
+            };
+            client
+                .post(format!("{url}/rest/api/2/issue/{}/comment", ticket.id))
+                .basic_auth(email, Some(token))
+                .json(&json!({ "body": body }))
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orbitdock_protocol::MessageType;
+
+    fn ticket(tracker: TicketTracker, id: &str, url: Option<&str>) -> TicketLink {
+        TicketLink {
+            tracker,
+            id: id.to_string(),
+            url: url.map(str::to_string),
+            sync: true,
+        }
+    }
+
+    fn message(content: &str, tool_output: Option<&str>) -> Message {
+        Message {
+            id: "m".into(),
+            session_id: "s".into(),
+            sequence: None,
+            message_type: MessageType::Assistant,
+            content: content.into(),
+            tool_name: None,
+            tool_input: None,
+            tool_output: tool_output.map(str::to_string),
+            is_error: false,
+            is_in_progress: false,
+            timestamp: "2026-10-17T00:00:00Z".into(),
+            duration_ms: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn normalizes_ticket_ids_and_jira_urls() {
+        let config = TicketsSection {
+            jira_url: Some("https://acme.atlassian.net/".into()),
+            ..Default::default()
+        };
+        let jira = normalize(ticket(TicketTracker::Jira, " ops-42 ", None), &config).unwrap();
+        assert_eq!(jira.id, "OPS-42");
+        assert_eq!(
+            jira.url.as_deref(),
+            Some("https://acme.atlassian.net/browse/OPS-42")
+        );
+
+        let linear =
+            normalize(ticket(TicketTracker::Linear, "ENG2-7", Some(" ")), &config).unwrap();
+        assert_eq!(linear.url, None);
+
+        for bad in ["", "ENG", "ENG-", "-12", "1ENG-2", "ENG-12a", "EN G-1"] {
+            assert_eq!(
+                normalize(ticket(TicketTracker::Linear, bad, None), &config),
+                Err(TicketError::InvalidId),
+                "{bad}"
+            );
+        }
+        assert_eq!(
+            normalize(
+                ticket(TicketTracker::Linear, "ENG-1", Some("linear.app/x")),
+                &config
+            ),
+            Err(TicketError::InvalidUrl)
+        );
+    }
+
+    #[test]
+    fn finds_the_last_pull_request_url() {
+        let messages = vec![
+            message("Opened https://github.com/acme/api/pull/7.", None),
+            message(
+                "Pushed the fix.",
+                Some("remote: https://github.com/acme/api/pull/12\nhttps://gitlab.com/acme/web/-/merge_requests/3"),
+            ),
+            message("Done, see the PR.", None),
+        ];
+        assert_eq!(
+            find_pr_url(&messages).as_deref(),
+            Some("https://gitlab.com/acme/web/-/merge_requests/3")
+        );
+        assert_eq!(
+            find_pr_url(&messages[..1]).as_deref(),
+            Some("https://github.com/acme/api/pull/7")
+        );
+        assert_eq!(
+            find_pr_url(&[message("https://github.com/acme/api/issues/7", None)]),
+            None
+        );
+    }
+
+    #[test]
+    fn jira_settings_go_together() {
+        let partial = TicketsSection {
+            jira_url: Some("acme.atlassian.net".into()),
+            ..Default::default()
+        };
+        assert_eq!(partial.problems().len(), 2);
+        assert!(TicketsSection::default().problems().is_empty());
+    }
+
+    #[test]
+    fn loads_saved_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("orbitdock.db");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(include_str!(
+            "../../../../migrations/V041__session_tickets.sql"
+        ))
+        .unwrap();
+        conn.execute(
+            "INSERT INTO session_tickets (session_id, tracker, ticket_id, url, sync)
+             VALUES ('s1', 'linear', 'ENG-1', NULL, 1), ('s2', 'trello', 'X-1', NULL, 0)",
+            [],
+        )
+        .unwrap();
+
+        let tickets = load_all(&db_path);
+        assert_eq!(tickets.len(), 1);
+        assert_eq!(tickets[0].0, "s1");
+        assert!(tickets[0].1.sync);
+        assert!(!already_synced(&db_path, "s1"));
+        conn.execute(
+            "UPDATE session_tickets SET synced_at = 'now' WHERE session_id = 's1'",
+            [],
+        )
+        .unwrap();
+        assert!(already_synced(&db_path, "s1"));
+        assert!(!already_synced(&db_path, "missing"));
+    }
+}
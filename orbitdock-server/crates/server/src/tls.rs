@@ -0,0 +1,69 @@
+//! Native TLS for the HTTP/WebSocket listener.
+//!
+//! Certificates come from PEM files, usually written by an ACME client such as
+//! certbot, lego, or `tailscale cert`. Those clients renew in place, so the
+//! server polls the files and reloads them without dropping connections or
+//! needing a restart.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use axum_server::tls_rustls::RustlsConfig;
+use tracing::{info, warn};
+
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+pub async fn load(cert_path: &Path, key_path: &Path) -> anyhow::Result<RustlsConfig> {
+    RustlsConfig::from_pem_file(cert_path, key_path)
+        .await
+        .with_context(|| {
+            format!(
+                "load TLS certificate {} and key {}",
+                cert_path.display(),
+                key_path.display()
+            )
+        })
+}
+
+/// Reload the certificate whenever either file changes on disk.
+pub async fn start_reload_loop(config: RustlsConfig, cert_path: PathBuf, key_path: PathBuf) {
+    let mut last_seen = modified_times(&cert_path, &key_path);
+    let mut interval = tokio::time::interval(RELOAD_CHECK_INTERVAL);
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        let current = modified_times(&cert_path, &key_path);
+        if current == last_seen {
+            continue;
+        }
+
+        match config.reload_from_pem_file(&cert_path, &key_path).await {
+            Ok(()) => {
+                info!(
+                    component = "tls",
+                    event = "tls.certificate_reloaded",
+                    cert = %cert_path.display(),
+                    "Reloaded TLS certificate"
+                );
+                last_seen = current;
+            }
+            // A renewal may be halfway through writing the pair; the old
+            // certificate stays in use and the next check tries again.
+            Err(e) => warn!(
+                component = "tls",
+                event = "tls.certificate_reload_failed",
+                cert = %cert_path.display(),
+                error = %e,
+                "Failed to reload TLS certificate; keeping the current one"
+            ),
+        }
+    }
+}
+
+fn modified_times(cert_path: &Path, key_path: &Path) -> (Option<SystemTime>, Option<SystemTime>) {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    (modified(cert_path), modified(key_path))
+}
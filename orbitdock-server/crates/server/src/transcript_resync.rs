@@ -0,0 +1,530 @@
+//! Forced transcript re-reads for passive sessions.
+//!
+//! Passive sessions follow their transcript incrementally: each hook or
+//! watcher event reads what was appended since the last one. A missed hook,
+//! a dropped watcher event, or a persist queue hiccup leaves a session short
+//! of messages or tool output, and nothing reads those lines again until the
+//! server restarts. `resync_session` re-reads the whole file and corrects the
+//! difference — missing messages are appended, messages that disagree with
+//! the transcript are updated, rows the database lost are written back, and
+//! token usage is set to what the file says. Subscribers see the corrections
+//! as ordinary `message_appended`, `message_updated`, and `tokens_updated`
+//! broadcasts.
+//!
+//! Claude transcripts are matched message by message, since passive Claude
+//! messages are numbered by transcript position. Codex passive messages come
+//! from the rollout watcher's event stream and don't line up with the
+//! transcript parser, so for Codex only token usage is reconciled.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+
+use orbitdock_protocol::{
+    Message, Provider, SessionResync, SessionStatus, TokenUsage, TokenUsageSnapshotKind,
+};
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+use tracing::info;
+
+use crate::auth::AuthIdentity;
+use crate::persistence::{
+    load_messages_for_session, load_transcript_tail_from_path, PersistCommand,
+};
+use crate::session_command::SessionCommand;
+use crate::session_utils::is_passive_session;
+use crate::state::SessionRegistry;
+use crate::transition::Input;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResyncError {
+    #[error("Session {0} not found")]
+    NotFound(String),
+    #[error("Session {0} is driven by OrbitDock, not following a transcript")]
+    NotPassive(String),
+    #[error("Session {0} has no transcript to read")]
+    NoTranscript(String),
+    #[error("Could not read {path}: {error}")]
+    Read { path: String, error: String },
+    #[error("Could not load stored messages: {0}")]
+    Database(String),
+}
+
+impl ResyncError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ResyncError::NotFound(_) => "not_found",
+            ResyncError::NotPassive(_) => "not_passive",
+            ResyncError::NoTranscript(_) => "no_transcript",
+            ResyncError::Read { .. } => "transcript_read_failed",
+            ResyncError::Database(_) => "db_error",
+        }
+    }
+}
+
+/// Re-read one passive session's transcript and apply the corrections.
+pub(crate) async fn resync_session(
+    state: &Arc<SessionRegistry>,
+    session_id: &str,
+) -> Result<SessionResync, ResyncError> {
+    let actor = state
+        .get_session(session_id)
+        .ok_or_else(|| ResyncError::NotFound(session_id.to_string()))?;
+    let snap = actor.snapshot();
+    if !is_passive_session(&snap) {
+        return Err(ResyncError::NotPassive(session_id.to_string()));
+    }
+    let path = snap
+        .transcript_path
+        .clone()
+        .ok_or_else(|| ResyncError::NoTranscript(session_id.to_string()))?;
+    let read_error = |error: String| ResyncError::Read {
+        path: path.clone(),
+        error,
+    };
+
+    let mut resync = SessionResync {
+        session_id: session_id.to_string(),
+        ..Default::default()
+    };
+    let persist_tx = state.persist();
+
+    let (usage, snapshot_kind) = match snap.provider {
+        Provider::Claude => {
+            // A read from zero also rebuilds the incremental sync's cursor.
+            let tail = load_transcript_tail_from_path(&path, session_id, 0)
+                .await
+                .map_err(|e| read_error(e.to_string()))?;
+
+            let (state_tx, state_rx) = oneshot::channel();
+            actor
+                .send(SessionCommand::GetState { reply: state_tx })
+                .await;
+            let current = state_rx
+                .await
+                .map_err(|_| ResyncError::NotFound(session_id.to_string()))?;
+            let stored = load_messages_for_session(session_id)
+                .await
+                .map_err(|e| ResyncError::Database(e.to_string()))?;
+
+            let corrections = reconcile(tail.messages, &current.messages, &stored);
+            resync.messages_added = corrections.append.len() as u32;
+            resync.messages_updated = corrections.update.len() as u32;
+            resync.messages_restored =
+                (corrections.restore.len() + corrections.restore_fields.len()) as u32;
+            apply(&actor, persist_tx, session_id, corrections).await;
+
+            (tail.usage, TokenUsageSnapshotKind::MixedLegacy)
+        }
+        Provider::Codex => {
+            let rollout = path.clone();
+            let usage = tokio::task::spawn_blocking(move || codex_lifetime_usage(&rollout))
+                .await
+                .map_err(|e| read_error(e.to_string()))?
+                .map_err(|e| read_error(e.to_string()))?;
+            (usage, TokenUsageSnapshotKind::LifetimeTotals)
+        }
+    };
+
+    if let Some(usage) = usage {
+        if !same_usage(&usage, &snap.token_usage) || snap.token_usage_snapshot_kind != snapshot_kind
+        {
+            actor
+                .send(SessionCommand::ProcessEvent {
+                    event: Input::TokensUpdated {
+                        usage,
+                        snapshot_kind,
+                    },
+                })
+                .await;
+            resync.token_usage_corrected = true;
+        }
+    }
+
+    if corrected(&resync) {
+        crate::session_diagnostics::record(
+            persist_tx,
+            session_id,
+            "transcript_resynced",
+            json!({
+                "messages_added": resync.messages_added,
+                "messages_updated": resync.messages_updated,
+                "messages_restored": resync.messages_restored,
+                "token_usage_corrected": resync.token_usage_corrected,
+            }),
+        )
+        .await;
+    }
+    info!(
+        component = "transcript_resync",
+        event = "transcript_resync.completed",
+        session_id = %session_id,
+        messages_added = resync.messages_added,
+        messages_updated = resync.messages_updated,
+        messages_restored = resync.messages_restored,
+        token_usage_corrected = resync.token_usage_corrected,
+        "Resynced session from its transcript"
+    );
+    Ok(resync)
+}
+
+/// Resync each of `session_ids`, or every active passive session with a
+/// transcript that `identity` can access when it's empty. Failures are
+/// reported per session.
+pub(crate) async fn resync_sessions(
+    state: &Arc<SessionRegistry>,
+    identity: &AuthIdentity,
+    session_ids: Vec<String>,
+) -> Vec<SessionResync> {
+    let session_ids = if session_ids.is_empty() {
+        let candidates: Vec<String> = state
+            .iter_sessions()
+            .filter(|entry| {
+                let snap = entry.value().snapshot();
+                snap.status == SessionStatus::Active
+                    && snap.transcript_path.is_some()
+                    && is_passive_session(&snap)
+            })
+            .map(|entry| entry.key().clone())
+            .collect();
+        let mut accessible = Vec::with_capacity(candidates.len());
+        for session_id in candidates {
+            if state.can_access_session(identity, &session_id).await {
+                accessible.push(session_id);
+            }
+        }
+        accessible
+    } else {
+        session_ids
+    };
+
+    let mut results = Vec::with_capacity(session_ids.len());
+    for session_id in session_ids {
+        let result = match resync_session(state, &session_id).await {
+            Ok(resync) => resync,
+            Err(e) => SessionResync {
+                session_id,
+                error: Some(e.to_string()),
+                ..Default::default()
+            },
+        };
+        results.push(result);
+    }
+    results
+}
+
+fn corrected(resync: &SessionResync) -> bool {
+    resync.messages_added > 0
+        || resync.messages_updated > 0
+        || resync.messages_restored > 0
+        || resync.token_usage_corrected
+}
+
+fn same_usage(a: &TokenUsage, b: &TokenUsage) -> bool {
+    a.input_tokens == b.input_tokens
+        && a.output_tokens == b.output_tokens
+        && a.cached_tokens == b.cached_tokens
+        && a.context_window == b.context_window
+}
+
+/// Fields of a message that disagree with the transcript, set to the
+/// transcript's values.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct MessageFix {
+    message_id: String,
+    content: Option<String>,
+    tool_output: Option<String>,
+    is_error: Option<bool>,
+    is_in_progress: Option<bool>,
+}
+
+impl MessageFix {
+    /// `None` when `current` already matches `expected`. Tool output the
+    /// transcript hasn't recorded yet isn't a disagreement.
+    fn between(current: &Message, expected: &Message) -> Option<Self> {
+        let fix = MessageFix {
+            message_id: expected.id.clone(),
+            content: (current.content != expected.content).then(|| expected.content.clone()),
+            tool_output: expected
+                .tool_output
+                .clone()
+                .filter(|output| current.tool_output.as_ref() != Some(output)),
+            is_error: (current.is_error != expected.is_error).then_some(expected.is_error),
+            is_in_progress: (current.is_in_progress != expected.is_in_progress)
+                .then_some(expected.is_in_progress),
+        };
+        let changed = fix.content.is_some()
+            || fix.tool_output.is_some()
+            || fix.is_error.is_some()
+            || fix.is_in_progress.is_some();
+        changed.then_some(fix)
+    }
+}
+
+/// What it takes to bring a session and its stored rows in line with the
+/// transcript.
+#[derive(Debug, Default)]
+struct Corrections {
+    /// Transcript messages the session is missing.
+    append: Vec<Message>,
+    /// Messages the session has but that disagree with the transcript.
+    update: Vec<MessageFix>,
+    /// Messages the session has right but the database lost.
+    restore: Vec<Message>,
+    /// Stored rows that disagree although the session has them right.
+    restore_fields: Vec<MessageFix>,
+}
+
+fn reconcile(transcript: Vec<Message>, in_memory: &[Message], stored: &[Message]) -> Corrections {
+    let in_memory: HashMap<&str, &Message> = in_memory.iter().map(|m| (m.id.as_str(), m)).collect();
+    let stored: HashMap<&str, &Message> = stored.iter().map(|m| (m.id.as_str(), m)).collect();
+
+    let mut corrections = Corrections::default();
+    for message in transcript {
+        match in_memory.get(message.id.as_str()) {
+            None => corrections.append.push(message),
+            Some(current) => {
+                if let Some(fix) = MessageFix::between(current, &message) {
+                    corrections.update.push(fix);
+                } else {
+                    match stored.get(message.id.as_str()) {
+                        None => corrections.restore.push(message),
+                        Some(row) => corrections
+                            .restore_fields
+                            .extend(MessageFix::between(row, &message)),
+                    }
+                }
+            }
+        }
+    }
+    corrections
+}
+
+async fn apply(
+    actor: &crate::session_actor::SessionActorHandle,
+    persist_tx: &tokio::sync::mpsc::Sender<PersistCommand>,
+    session_id: &str,
+    corrections: Corrections,
+) {
+    for message in corrections.append {
+        let _ = persist_tx
+            .send(PersistCommand::MessageAppend {
+                session_id: session_id.to_string(),
+                message: message.clone(),
+            })
+            .await;
+        actor
+            .send(SessionCommand::AddMessageAndBroadcast { message })
+            .await;
+    }
+    for fix in corrections.update {
+        actor
+            .send(SessionCommand::ProcessEvent {
+                event: Input::MessageUpdated {
+                    message_id: fix.message_id,
+                    content: fix.content,
+                    tool_output: fix.tool_output,
+                    is_error: fix.is_error,
+                    is_in_progress: fix.is_in_progress,
+                    duration_ms: None,
+                },
+            })
+            .await;
+    }
+    for message in corrections.restore {
+        let _ = persist_tx
+            .send(PersistCommand::MessageAppend {
+                session_id: session_id.to_string(),
+                message,
+            })
+            .await;
+    }
+    for fix in corrections.restore_fields {
+        let _ = persist_tx
+            .send(PersistCommand::MessageUpdate {
+                session_id: session_id.to_string(),
+                message_id: fix.message_id,
+                content: fix.content,
+                tool_output: fix.tool_output,
+                duration_ms: None,
+                is_error: fix.is_error,
+                is_in_progress: fix.is_in_progress,
+            })
+            .await;
+    }
+}
+
+/// Lifetime token totals from a Codex rollout's last `token_count` event,
+/// the same figures the rollout watcher reports.
+fn codex_lifetime_usage(path: &str) -> std::io::Result<Option<TokenUsage>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut usage = None;
+    for line in reader.lines() {
+        let line = line?;
+        if !line.contains("\"token_count\"") {
+            continue;
+        }
+        let Ok(entry) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if let Some(found) = token_count_usage(&entry) {
+            usage = Some(found);
+        }
+    }
+    Ok(usage)
+}
+
+fn token_count_usage(entry: &Value) -> Option<TokenUsage> {
+    if entry.get("type").and_then(Value::as_str) != Some("event_msg") {
+        return None;
+    }
+    let payload = entry.get("payload")?;
+    if payload.get("type").and_then(Value::as_str) != Some("token_count") {
+        return None;
+    }
+    let info = payload.get("info")?;
+    let totals = info.get("total_token_usage")?;
+    let count = |value: &Value, key: &str| {
+        value.get(key).and_then(Value::as_i64).unwrap_or(0).max(0) as u64
+    };
+    Some(TokenUsage {
+        input_tokens: count(totals, "input_tokens"),
+        output_tokens: count(totals, "output_tokens"),
+        cached_tokens: count(totals, "cached_input_tokens"),
+        context_window: count(info, "model_context_window"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orbitdock_protocol::MessageType;
+
+    fn message(index: usize, message_type: MessageType, content: &str) -> Message {
+        Message {
+            id: format!("sess:transcript:{index}"),
+            session_id: "sess".to_string(),
+            sequence: Some(index as u64),
+            message_type,
+            content: content.to_string(),
+            tool_name: None,
+            tool_input: None,
+            tool_output: None,
+            is_error: false,
+            is_in_progress: false,
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            duration_ms: None,
+            images: vec![],
+        }
+    }
+
+    #[test]
+    fn reconcile_appends_missing_messages_and_fixes_drifted_ones() {
+        let mut tool = message(1, MessageType::Tool, "cargo test");
+        tool.tool_output = Some("ok".to_string());
+        let transcript = vec![
+            message(0, MessageType::User, "run the tests"),
+            tool.clone(),
+            message(2, MessageType::Assistant, "All green."),
+        ];
+
+        let mut stale_tool = tool.clone();
+        stale_tool.tool_output = None;
+        stale_tool.is_in_progress = true;
+        let in_memory = vec![message(0, MessageType::User, "run the tests"), stale_tool];
+        let stored = in_memory.clone();
+
+        let corrections = reconcile(transcript, &in_memory, &stored);
+        assert_eq!(corrections.append.len(), 1);
+        assert_eq!(corrections.append[0].id, "sess:transcript:2");
+        assert_eq!(
+            corrections.update,
+            vec![MessageFix {
+                message_id: "sess:transcript:1".to_string(),
+                tool_output: Some("ok".to_string()),
+                is_in_progress: Some(false),
+                ..Default::default()
+            }]
+        );
+        assert!(corrections.restore.is_empty());
+        assert!(corrections.restore_fields.is_empty());
+    }
+
+    #[test]
+    fn reconcile_writes_back_rows_the_database_lost() {
+        let transcript = vec![
+            message(0, MessageType::User, "hello"),
+            message(1, MessageType::Assistant, "Hi."),
+        ];
+        let in_memory = transcript.clone();
+        let stored = vec![message(0, MessageType::User, "hel")];
+
+        let corrections = reconcile(transcript, &in_memory, &stored);
+        assert!(corrections.append.is_empty());
+        assert!(corrections.update.is_empty());
+        assert_eq!(corrections.restore.len(), 1);
+        assert_eq!(corrections.restore[0].id, "sess:transcript:1");
+        assert_eq!(corrections.restore_fields.len(), 1);
+        assert_eq!(
+            corrections.restore_fields[0].content.as_deref(),
+            Some("hello")
+        );
+    }
+
+    #[test]
+    fn reconcile_leaves_matching_and_extra_messages_alone() {
+        let transcript = vec![message(0, MessageType::User, "hello")];
+        let mut in_memory = transcript.clone();
+        let mut note = message(0, MessageType::Assistant, "Turn interrupted");
+        note.id = "interrupted-turn-1".to_string();
+        in_memory.push(note);
+
+        let corrections = reconcile(transcript, &in_memory, &in_memory);
+        assert!(corrections.append.is_empty());
+        assert!(corrections.update.is_empty());
+        assert!(corrections.restore.is_empty());
+        assert!(corrections.restore_fields.is_empty());
+    }
+
+    #[test]
+    fn codex_usage_is_the_last_token_count_total() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("rollout.jsonl");
+        let token_count = |input: u64, output: u64| {
+            json!({
+                "type": "event_msg",
+                "payload": {
+                    "type": "token_count",
+                    "info": {
+                        "total_token_usage": {
+                            "input_tokens": input,
+                            "cached_input_tokens": 10,
+                            "output_tokens": output,
+                            "total_tokens": input + output
+                        },
+                        "last_token_usage": { "input_tokens": 1, "output_tokens": 1 },
+                        "model_context_window": 272000
+                    }
+                }
+            })
+            .to_string()
+        };
+        let lines = [
+            token_count(100, 20),
+            json!({ "type": "event_msg", "payload": { "type": "token_count", "info": null } })
+                .to_string(),
+            token_count(250, 40),
+            json!({ "type": "response_item", "payload": { "type": "message" } }).to_string(),
+        ];
+        std::fs::write(&path, lines.join("\n")).expect("write rollout");
+
+        let usage = codex_lifetime_usage(path.to_str().unwrap())
+            .expect("read rollout")
+            .expect("usage");
+        assert_eq!(usage.input_tokens, 250);
+        assert_eq!(usage.output_tokens, 40);
+        assert_eq!(usage.cached_tokens, 10);
+        assert_eq!(usage.context_window, 272000);
+    }
+}
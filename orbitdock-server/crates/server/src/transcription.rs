@@ -0,0 +1,322 @@
+//! Speech-to-text for voice prompts.
+//!
+//! Audio uploaded as an attachment and sent with `send_message` or
+//! `steer_turn` is transcribed here before the message reaches the connector;
+//! the transcript becomes (or is appended to) the prompt text, so voice prompts
+//! work with every provider. `[transcription]` picks the backend: OpenAI's
+//! Whisper API, an OpenAI-compatible `/audio/transcriptions` endpoint, or a
+//! local whisper.cpp binary that keeps the audio on the machine.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::Context;
+use serde::Deserialize;
+use tokio::process::Command;
+
+use crate::config_file;
+
+const OPENAI_MODEL: &str = "whisper-1";
+const WHISPER_CPP_COMMAND: &str = "whisper-cli";
+/// Formats whisper.cpp decodes itself; anything else goes through ffmpeg first.
+const WHISPER_CPP_FORMATS: &[&str] = &["audio/wav", "audio/mpeg", "audio/flac", "audio/ogg"];
+const TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// `[transcription]` in the config file.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TranscriptionSection {
+    /// Unset turns voice prompts off.
+    pub provider: Option<TranscriptionProvider>,
+    /// Overrides `whisper-1`; required for `openai_compatible`.
+    pub model: Option<String>,
+    /// `openai_compatible` only, e.g. `http://localhost:8000/v1`.
+    pub base_url: Option<String>,
+    /// `whisper_cpp` only: the binary, `whisper-cli` on `PATH` by default.
+    pub command: Option<String>,
+    /// `whisper_cpp` only: the ggml model file.
+    pub model_path: Option<PathBuf>,
+    /// Spoken language as an ISO-639-1 code; detected when unset.
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum TranscriptionProvider {
+    #[serde(rename = "openai")]
+    OpenAi,
+    #[serde(rename = "openai_compatible")]
+    OpenAiCompatible,
+    #[serde(rename = "whisper_cpp")]
+    WhisperCpp,
+}
+
+impl TranscriptionSection {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        match self.provider {
+            Some(TranscriptionProvider::OpenAiCompatible) => {
+                if self.base_url.is_none() {
+                    problems.push("transcription.base_url: required for openai_compatible".into());
+                }
+                if self.model.is_none() {
+                    problems.push("transcription.model: required for openai_compatible".into());
+                }
+            }
+            Some(TranscriptionProvider::WhisperCpp) if self.model_path.is_none() => {
+                problems.push("transcription.model_path: required for whisper_cpp".into());
+            }
+            _ => {}
+        }
+        if self.base_url.is_some() && self.provider != Some(TranscriptionProvider::OpenAiCompatible)
+        {
+            problems.push("transcription.base_url: only applies to openai_compatible".into());
+        }
+        if self.provider != Some(TranscriptionProvider::WhisperCpp) {
+            if self.command.is_some() {
+                problems.push("transcription.command: only applies to whisper_cpp".into());
+            }
+            if self.model_path.is_some() {
+                problems.push("transcription.model_path: only applies to whisper_cpp".into());
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TranscriptionError {
+    #[error("voice prompts need a [transcription] provider in config.toml")]
+    Unavailable,
+    #[error("transcription failed: {0:#}")]
+    Failed(#[from] anyhow::Error),
+}
+
+impl TranscriptionError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            TranscriptionError::Unavailable => "transcription_unavailable",
+            TranscriptionError::Failed(_) => "transcription_failed",
+        }
+    }
+}
+
+/// Transcribe the audio file at `path` with the configured provider.
+pub async fn transcribe(
+    path: &Path,
+    file_name: &str,
+    mime_type: &str,
+) -> Result<String, TranscriptionError> {
+    let config = config_file::current();
+    let section = &config.transcription;
+    let provider = section.provider.ok_or(TranscriptionError::Unavailable)?;
+    let text = match provider {
+        TranscriptionProvider::OpenAi => {
+            let api_key =
+                crate::ai_naming::resolve_api_key().ok_or(TranscriptionError::Unavailable)?;
+            let model = section.model.as_deref().unwrap_or(OPENAI_MODEL);
+            let url = "https://api.openai.com/v1/audio/transcriptions";
+            post_audio(
+                url,
+                Some(&api_key),
+                model,
+                section,
+                path,
+                file_name,
+                mime_type,
+            )
+            .await?
+        }
+        TranscriptionProvider::OpenAiCompatible => {
+            let base_url = section
+                .base_url
+                .as_deref()
+                .ok_or(TranscriptionError::Unavailable)?;
+            let model = section
+                .model
+                .as_deref()
+                .ok_or(TranscriptionError::Unavailable)?;
+            let url = format!("{}/audio/transcriptions", base_url.trim_end_matches('/'));
+            // Local servers usually ignore the key; hosted ones need it.
+            let api_key = crate::ai_naming::resolve_api_key();
+            post_audio(
+                &url,
+                api_key.as_deref(),
+                model,
+                section,
+                path,
+                file_name,
+                mime_type,
+            )
+            .await?
+        }
+        TranscriptionProvider::WhisperCpp => run_whisper_cpp(section, path, mime_type).await?,
+    };
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err(anyhow::anyhow!("no speech recognized in {file_name}").into());
+    }
+    Ok(text)
+}
+
+/// OpenAI-style multipart `/audio/transcriptions` request.
+async fn post_audio(
+    url: &str,
+    api_key: Option<&str>,
+    model: &str,
+    section: &TranscriptionSection,
+    path: &Path,
+    file_name: &str,
+    mime_type: &str,
+) -> anyhow::Result<String> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("read {}", path.display()))?;
+    let file = reqwest::multipart::Part::bytes(bytes)
+        .file_name(file_name.to_string())
+        .mime_str(mime_type)?;
+    let mut form = reqwest::multipart::Form::new()
+        .part("file", file)
+        .text("model", model.to_string());
+    if let Some(language) = &section.language {
+        form = form.text("language", language.clone());
+    }
+    let mut request = reqwest::Client::new()
+        .post(url)
+        .timeout(TIMEOUT)
+        .multipart(form);
+    if let Some(api_key) = api_key {
+        request = request.header("Authorization", format!("Bearer {api_key}"));
+    }
+    let json = crate::ai_naming::post_json(request, "Transcription").await?;
+    json["text"]
+        .as_str()
+        .map(str::to_string)
+        .context("transcription response has no text")
+}
+
+/// Run whisper.cpp on `path`, converting it to 16 kHz WAV with ffmpeg first
+/// when whisper.cpp can't read the format.
+async fn run_whisper_cpp(
+    section: &TranscriptionSection,
+    path: &Path,
+    mime_type: &str,
+) -> anyhow::Result<String> {
+    let model_path = section
+        .model_path
+        .as_deref()
+        .context("transcription.model_path is not set")?;
+    let converted = if WHISPER_CPP_FORMATS.contains(&mime_type) {
+        None
+    } else {
+        let wav = path.with_extension("wav");
+        let mut ffmpeg = Command::new("ffmpeg");
+        ffmpeg
+            .args(["-nostdin", "-y", "-loglevel", "error", "-i"])
+            .arg(path)
+            .args(["-ar", "16000", "-ac", "1", "-c:a", "pcm_s16le"])
+            .arg(&wav);
+        run(ffmpeg, "ffmpeg").await?;
+        Some(wav)
+    };
+
+    let command = section.command.as_deref().unwrap_or(WHISPER_CPP_COMMAND);
+    let mut whisper = Command::new(command);
+    whisper
+        .arg("-m")
+        .arg(model_path)
+        .arg("-f")
+        .arg(converted.as_deref().unwrap_or(path))
+        .args(["--no-timestamps", "--no-prints"]);
+    if let Some(language) = &section.language {
+        whisper.args(["-l", language]);
+    }
+    let result = run(whisper, command).await;
+    if let Some(wav) = converted {
+        let _ = tokio::fs::remove_file(wav).await;
+    }
+    result
+}
+
+/// Stdout of `command`, or an error with its stderr.
+async fn run(mut command: Command, name: &str) -> anyhow::Result<String> {
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("start {name}"))?;
+    // Dropping the wait on timeout kills the child.
+    let output = tokio::time::timeout(TIMEOUT, child.wait_with_output())
+        .await
+        .with_context(|| format!("{name} timed out"))??;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{name} exited with {}: {}", output.status, stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The prompt sent for a message: its typed text followed by each transcript.
+pub fn prompt_with_transcripts(content: &str, transcripts: &[String]) -> String {
+    let mut parts: Vec<&str> = Vec::with_capacity(transcripts.len() + 1);
+    if !content.trim().is_empty() {
+        parts.push(content);
+    }
+    parts.extend(transcripts.iter().map(String::as_str));
+    parts.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transcripts_follow_the_typed_prompt() {
+        let transcripts = vec!["fix the flaky login test".to_string()];
+        assert_eq!(
+            prompt_with_transcripts("", &transcripts),
+            "fix the flaky login test"
+        );
+        assert_eq!(
+            prompt_with_transcripts("From my phone:", &transcripts),
+            "From my phone:\n\nfix the flaky login test"
+        );
+        assert_eq!(prompt_with_transcripts("hello", &[]), "hello");
+    }
+
+    #[test]
+    fn whisper_cpp_needs_a_model_and_keeps_its_keys_to_itself() {
+        let section = TranscriptionSection {
+            provider: Some(TranscriptionProvider::WhisperCpp),
+            ..Default::default()
+        };
+        assert_eq!(
+            section.problems(),
+            vec!["transcription.model_path: required for whisper_cpp".to_string()]
+        );
+
+        let section = TranscriptionSection {
+            provider: Some(TranscriptionProvider::OpenAi),
+            model_path: Some(PathBuf::from("/models/ggml-base.en.bin")),
+            ..Default::default()
+        };
+        assert_eq!(
+            section.problems(),
+            vec!["transcription.model_path: only applies to whisper_cpp".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn run_reports_stderr_of_failed_commands() {
+        let mut failing = Command::new("sh");
+        failing.args(["-c", "echo 'no model' >&2; exit 2"]);
+        let err = run(failing, "whisper-cli").await.unwrap_err();
+        assert!(err.to_string().contains("no model"), "{err}");
+
+        let mut ok = Command::new("sh");
+        ok.args(["-c", "echo ' hello world'"]);
+        assert_eq!(run(ok, "whisper-cli").await.unwrap(), " hello world\n");
+    }
+}
@@ -62,6 +62,43 @@ pub fn persist_op_to_command(op: PersistOp) -> PersistCommand {
             diff,
             plan,
         },
+        PersistOp::PlanStepsUpdate {
+            session_id,
+            plan,
+            changes,
+        } => PersistCommand::PlanStepsUpdate {
+            session_id,
+            plan,
+            changes,
+        },
+        PersistOp::TurnStart {
+            session_id,
+            turn_id,
+            turn_seq,
+            started_at,
+        } => PersistCommand::TurnStart {
+            session_id,
+            turn_id,
+            turn_seq,
+            started_at,
+        },
+        PersistOp::TurnEnd {
+            session_id,
+            turn_id,
+            ended_at,
+            aborted,
+            token_usage,
+            snapshot_kind,
+            turn_usage,
+        } => PersistCommand::TurnEnd {
+            session_id,
+            turn_id,
+            ended_at,
+            aborted,
+            token_usage,
+            snapshot_kind,
+            turn_usage,
+        },
         PersistOp::TurnDiffInsert {
             session_id,
             turn_id,
@@ -72,6 +109,7 @@ pub fn persist_op_to_command(op: PersistOp) -> PersistCommand {
             cached_tokens,
             context_window,
             snapshot_kind,
+            turn_usage,
         } => PersistCommand::TurnDiffInsert {
             session_id,
             turn_id,
@@ -82,6 +120,7 @@ pub fn persist_op_to_command(op: PersistOp) -> PersistCommand {
             cached_tokens,
             context_window,
             snapshot_kind,
+            turn_usage,
         },
         PersistOp::SetCustomName {
             session_id,
@@ -152,5 +191,8 @@ pub fn persist_op_to_command(op: PersistOp) -> PersistCommand {
             sandbox_mode: None,
             permission_mode: Some(permission_mode),
         },
+        PersistOp::LastErrorUpdate { session_id, error } => {
+            PersistCommand::LastErrorUpdate { session_id, error }
+        }
     }
 }
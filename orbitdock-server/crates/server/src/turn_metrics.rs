@@ -0,0 +1,266 @@
+//! Per-turn latency tracking.
+//!
+//! The transition function only sees second-resolution timestamps, so the
+//! session handle keeps a monotonic `TurnTimer` alongside it: started on
+//! `TurnStarted`, fed message events and spend, and finished into a
+//! `TurnMetrics` record when the turn completes, aborts, or the session ends.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use orbitdock_protocol::{Message, MessageType, TurnMetrics};
+
+use crate::transition::Input;
+
+/// Turns of metrics kept on a session and sent in its snapshots. Older turns
+/// stay in the database and `/api/analytics/turn-latency`.
+pub const SNAPSHOT_TURN_METRICS: usize = 50;
+
+/// Drop all but the newest [`SNAPSHOT_TURN_METRICS`] turns.
+pub fn keep_recent(metrics: &mut Vec<TurnMetrics>) {
+    let excess = metrics.len().saturating_sub(SNAPSHOT_TURN_METRICS);
+    metrics.drain(..excess);
+}
+
+#[derive(Debug)]
+pub struct TurnTimer {
+    started: Instant,
+    first_output: Option<Duration>,
+    /// Latest reported duration per tool message started in this turn.
+    tool_durations: HashMap<String, u64>,
+    /// `None` until a priced usage update arrives.
+    cost_usd: Option<f64>,
+}
+
+impl TurnTimer {
+    fn started_at(started: Instant) -> Self {
+        Self {
+            started,
+            first_output: None,
+            tool_durations: HashMap::new(),
+            cost_usd: None,
+        }
+    }
+
+    pub fn add_cost(&mut self, delta_usd: f64) {
+        self.cost_usd = Some(self.cost_usd.unwrap_or(0.0) + delta_usd);
+    }
+
+    fn observe_message(&mut self, message: &Message, now: Instant) {
+        if self.first_output.is_none() && produces_output(message.message_type) {
+            self.first_output = Some(now.saturating_duration_since(self.started));
+        }
+        if message.message_type == MessageType::Tool {
+            self.tool_durations
+                .insert(message.id.clone(), message.duration_ms.unwrap_or(0));
+        }
+    }
+
+    fn observe_tool_duration(&mut self, message_id: &str, duration_ms: u64) {
+        if let Some(existing) = self.tool_durations.get_mut(message_id) {
+            *existing = duration_ms;
+        }
+    }
+
+    fn finish(self, turn_id: String, aborted: bool, now: Instant) -> TurnMetrics {
+        TurnMetrics {
+            turn_id,
+            time_to_first_token_ms: self.first_output.map(|d| d.as_millis() as u64),
+            duration_ms: now.saturating_duration_since(self.started).as_millis() as u64,
+            tool_duration_ms: self.tool_durations.values().sum(),
+            aborted,
+            estimated_cost_usd: self.cost_usd,
+        }
+    }
+}
+
+/// Anything the model emits counts as its first token; user echoes do not.
+fn produces_output(message_type: MessageType) -> bool {
+    !matches!(message_type, MessageType::User | MessageType::Steer)
+}
+
+/// Advance `timer` for a transition input. Returns metrics when a timed turn ends.
+///
+/// `current_turn_id` is the handle's turn id *before* the input is applied, so
+/// a finishing turn is attributed to the turn that just ran.
+pub fn observe_input(
+    timer: &mut Option<TurnTimer>,
+    input: &Input,
+    current_turn_id: Option<&str>,
+) -> Option<TurnMetrics> {
+    observe_input_at(timer, input, current_turn_id, Instant::now())
+}
+
+fn observe_input_at(
+    timer: &mut Option<TurnTimer>,
+    input: &Input,
+    current_turn_id: Option<&str>,
+    now: Instant,
+) -> Option<TurnMetrics> {
+    match input {
+        Input::TurnStarted => {
+            *timer = Some(TurnTimer::started_at(now));
+            None
+        }
+        Input::MessageCreated(message) => {
+            if let Some(timer) = timer.as_mut() {
+                timer.observe_message(message, now);
+            }
+            None
+        }
+        Input::MessageUpdated {
+            message_id,
+            duration_ms: Some(duration_ms),
+            ..
+        } => {
+            if let Some(timer) = timer.as_mut() {
+                timer.observe_tool_duration(message_id, *duration_ms);
+            }
+            None
+        }
+        Input::TurnCompleted => finish(timer, current_turn_id, false, now),
+        Input::TurnAborted { .. } | Input::SessionEnded { .. } => {
+            finish(timer, current_turn_id, true, now)
+        }
+        _ => None,
+    }
+}
+
+fn finish(
+    timer: &mut Option<TurnTimer>,
+    current_turn_id: Option<&str>,
+    aborted: bool,
+    now: Instant,
+) -> Option<TurnMetrics> {
+    let timer = timer.take()?;
+    let turn_id = current_turn_id?;
+    Some(timer.finish(turn_id.to_string(), aborted, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, message_type: MessageType, duration_ms: Option<u64>) -> Message {
+        Message {
+            id: id.to_string(),
+            session_id: "sess-1".to_string(),
+            sequence: None,
+            message_type,
+            content: String::new(),
+            tool_name: None,
+            tool_input: None,
+            tool_output: None,
+            is_error: false,
+            is_in_progress: false,
+            timestamp: "0Z".to_string(),
+            duration_ms,
+            images: vec![],
+        }
+    }
+
+    #[test]
+    fn completed_turn_reports_first_token_duration_and_tool_time() {
+        let start = Instant::now();
+        let mut timer = None;
+
+        observe_input_at(&mut timer, &Input::TurnStarted, None, start);
+        timer.as_mut().unwrap().add_cost(0.25);
+        timer.as_mut().unwrap().add_cost(0.5);
+        observe_input_at(
+            &mut timer,
+            &Input::MessageCreated(message("u1", MessageType::User, None)),
+            Some("turn-1"),
+            start + Duration::from_millis(5),
+        );
+        observe_input_at(
+            &mut timer,
+            &Input::MessageCreated(message("a1", MessageType::Assistant, None)),
+            Some("turn-1"),
+            start + Duration::from_millis(400),
+        );
+        observe_input_at(
+            &mut timer,
+            &Input::MessageCreated(message("t1", MessageType::Tool, None)),
+            Some("turn-1"),
+            start + Duration::from_millis(500),
+        );
+        for duration_ms in [100, 250] {
+            observe_input_at(
+                &mut timer,
+                &Input::MessageUpdated {
+                    message_id: "t1".to_string(),
+                    content: None,
+                    tool_output: None,
+                    is_error: None,
+                    is_in_progress: Some(false),
+                    duration_ms: Some(duration_ms),
+                },
+                Some("turn-1"),
+                start + Duration::from_millis(800),
+            );
+        }
+
+        let metrics = observe_input_at(
+            &mut timer,
+            &Input::TurnCompleted,
+            Some("turn-1"),
+            start + Duration::from_millis(1_200),
+        )
+        .expect("turn end produces metrics");
+
+        assert_eq!(metrics.turn_id, "turn-1");
+        assert_eq!(metrics.time_to_first_token_ms, Some(400));
+        assert_eq!(metrics.duration_ms, 1_200);
+        assert_eq!(metrics.tool_duration_ms, 250);
+        assert!(!metrics.aborted);
+        assert_eq!(metrics.estimated_cost_usd, Some(0.75));
+        assert!(timer.is_none());
+    }
+
+    #[test]
+    fn aborted_turn_is_flagged_and_untimed_turns_are_ignored() {
+        let start = Instant::now();
+        let mut timer = None;
+
+        assert!(
+            observe_input_at(&mut timer, &Input::TurnCompleted, Some("turn-1"), start).is_none()
+        );
+
+        observe_input_at(&mut timer, &Input::TurnStarted, None, start);
+        let metrics = observe_input_at(
+            &mut timer,
+            &Input::TurnAborted {
+                reason: "interrupted".to_string(),
+            },
+            Some("turn-2"),
+            start + Duration::from_millis(50),
+        )
+        .expect("aborted turn still reports duration");
+        assert!(metrics.aborted);
+        assert_eq!(metrics.time_to_first_token_ms, None);
+        assert_eq!(metrics.duration_ms, 50);
+        assert_eq!(metrics.estimated_cost_usd, None);
+    }
+
+    #[test]
+    fn snapshots_keep_only_the_newest_turns() {
+        let mut metrics: Vec<TurnMetrics> = (0..SNAPSHOT_TURN_METRICS + 5)
+            .map(|n| TurnMetrics {
+                turn_id: format!("turn-{n}"),
+                time_to_first_token_ms: None,
+                duration_ms: 10,
+                tool_duration_ms: 0,
+                aborted: false,
+                estimated_cost_usd: None,
+            })
+            .collect();
+        keep_recent(&mut metrics);
+        assert_eq!(metrics.len(), SNAPSHOT_TURN_METRICS);
+        assert_eq!(metrics[0].turn_id, "turn-5");
+        assert_eq!(
+            metrics.last().map(|m| m.turn_id.as_str()),
+            Some(format!("turn-{}", SNAPSHOT_TURN_METRICS + 4).as_str())
+        );
+    }
+}
@@ -0,0 +1,439 @@
+//! Startup recovery for turns a crash left running.
+//!
+//! A turn's row in `turns` stays open (`ended_at` NULL) until its connector
+//! reports the end, so after a crash the turn journal still shows it running
+//! and the session would be restored as working forever. At startup:
+//!
+//! - Direct sessions' connectors died with the server, so their open turns
+//!   are closed as aborted with a note appended to the transcript, and the
+//!   session waits for the next prompt.
+//! - Passive sessions keep running in their own CLI. The tail of their
+//!   transcript says whether the turn finished (or was interrupted) while
+//!   the server was down; if it did, the turn is closed and the session
+//!   waits for a reply. A turn still running is left for hooks or the
+//!   watcher to finish.
+//! - Ended sessions' open turns are closed as aborted.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+
+/// How much of a transcript's end to read when looking for the last turn
+/// boundary.
+const TAIL_BYTES: u64 = 256 * 1024;
+
+pub(crate) const INTERRUPTED_MESSAGE: &str =
+    "Turn interrupted: the server stopped while this turn was running. Send a message to continue.";
+
+/// Where the newest turn in a transcript stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TailTurn {
+    Running,
+    Finished,
+    Aborted,
+}
+
+/// Sessions whose turns were recovered, for the startup log.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Recovered {
+    /// Direct sessions whose open turn was aborted.
+    pub aborted: u64,
+    /// Passive sessions whose turn ended while the server was down.
+    pub settled: u64,
+}
+
+struct Candidate {
+    session_id: String,
+    provider: String,
+    direct: bool,
+    transcript_path: Option<String>,
+    open_turns: Vec<String>,
+}
+
+pub(crate) fn recover(conn: &Connection, now: &str) -> Result<Recovered, rusqlite::Error> {
+    let mut recovered = Recovered::default();
+
+    conn.execute(
+        "UPDATE turns SET
+            ended_at = ?1,
+            aborted = 1,
+            last_message_seq = (SELECT MAX(sequence) FROM messages m WHERE m.session_id = turns.session_id)
+         WHERE ended_at IS NULL
+           AND session_id IN (SELECT id FROM sessions WHERE status = 'ended')",
+        params![now],
+    )?;
+
+    for candidate in candidates(conn)? {
+        if candidate.direct {
+            abort_direct(conn, &candidate, now)?;
+            if !candidate.open_turns.is_empty() {
+                recovered.aborted += 1;
+            }
+            continue;
+        }
+
+        let tail = candidate
+            .transcript_path
+            .as_deref()
+            .and_then(|path| read_tail_turn(Path::new(path), &candidate.provider));
+        let aborted = match tail {
+            Some(TailTurn::Finished) => false,
+            Some(TailTurn::Aborted) => true,
+            Some(TailTurn::Running) | None => continue,
+        };
+        close_turns(conn, &candidate.session_id, now, aborted)?;
+        conn.execute(
+            "UPDATE sessions SET work_status = 'reply' WHERE id = ?1",
+            params![candidate.session_id],
+        )?;
+        recovered.settled += 1;
+    }
+
+    Ok(recovered)
+}
+
+/// Active sessions that are working or have a turn still open.
+fn candidates(conn: &Connection) -> Result<Vec<Candidate>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.provider, s.transcript_path,
+                (s.claude_integration_mode = 'direct' OR s.codex_integration_mode = 'direct')
+         FROM sessions s
+         WHERE s.status = 'active'
+           AND (s.work_status = 'working'
+             OR EXISTS (SELECT 1 FROM turns t WHERE t.session_id = s.id AND t.ended_at IS NULL))",
+    )?;
+    let mut candidates = stmt
+        .query_map([], |row| {
+            Ok(Candidate {
+                session_id: row.get(0)?,
+                provider: row.get(1)?,
+                transcript_path: row.get(2)?,
+                direct: row.get::<_, Option<bool>>(3)?.unwrap_or(false),
+                open_turns: Vec::new(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut open = conn.prepare(
+        "SELECT turn_id FROM turns WHERE session_id = ?1 AND ended_at IS NULL ORDER BY turn_seq",
+    )?;
+    for candidate in &mut candidates {
+        candidate.open_turns = open
+            .query_map(params![candidate.session_id], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+    }
+    Ok(candidates)
+}
+
+fn abort_direct(
+    conn: &Connection,
+    candidate: &Candidate,
+    now: &str,
+) -> Result<(), rusqlite::Error> {
+    let session_id = &candidate.session_id;
+    if let Some(turn_id) = candidate.open_turns.last() {
+        conn.execute(
+            "INSERT OR IGNORE INTO messages (id, session_id, type, content, timestamp, sequence, is_error)
+             VALUES (?1, ?2, 'assistant', ?3, ?4,
+                (SELECT COALESCE(MAX(sequence), -1) + 1 FROM messages WHERE session_id = ?2), 1)",
+            params![
+                format!("interrupted-{turn_id}"),
+                session_id,
+                INTERRUPTED_MESSAGE,
+                now
+            ],
+        )?;
+        crate::persistence::insert_session_diagnostic(
+            conn,
+            session_id,
+            "turn_interrupted",
+            &json!({ "turn_id": turn_id }),
+        )?;
+    }
+    close_turns(conn, session_id, now, true)?;
+    conn.execute(
+        "UPDATE messages SET is_in_progress = 0 WHERE session_id = ?1 AND is_in_progress = 1",
+        params![session_id],
+    )?;
+    conn.execute(
+        "UPDATE sessions SET work_status = 'reply' WHERE id = ?1",
+        params![session_id],
+    )?;
+    Ok(())
+}
+
+fn close_turns(
+    conn: &Connection,
+    session_id: &str,
+    now: &str,
+    aborted: bool,
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE turns SET
+            ended_at = ?2,
+            aborted = ?3,
+            last_message_seq = (SELECT MAX(sequence) FROM messages WHERE session_id = ?1)
+         WHERE session_id = ?1 AND ended_at IS NULL",
+        params![session_id, now, aborted],
+    )?;
+    Ok(())
+}
+
+/// The state of the newest turn in the transcript at `path`, if its tail
+/// shows one.
+fn read_tail_turn(path: &Path, provider: &str) -> Option<TailTurn> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(TAIL_BYTES)))
+        .ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+    let tail = String::from_utf8_lossy(&tail);
+
+    tail.lines().rev().find_map(|line| {
+        let entry: Value = serde_json::from_str(line.trim()).ok()?;
+        match provider {
+            "claude" => claude_turn_state(&entry),
+            "codex" => codex_turn_state(&entry),
+            _ => None,
+        }
+    })
+}
+
+/// A Claude transcript entry's bearing on the current turn: a text-only
+/// assistant reply ends it, tool use and tool results continue it, and
+/// the CLI's interrupt marker aborts it. Other entries say nothing.
+pub(crate) fn claude_turn_state(entry: &Value) -> Option<TailTurn> {
+    if entry.get("isSidechain").and_then(Value::as_bool) == Some(true) {
+        return None;
+    }
+    let content = entry.pointer("/message/content")?;
+    let blocks = content.as_array();
+    let has_block = |kind: &str| {
+        blocks.is_some_and(|blocks| {
+            blocks
+                .iter()
+                .any(|block| block.get("type").and_then(Value::as_str) == Some(kind))
+        })
+    };
+
+    match entry.get("type").and_then(Value::as_str)? {
+        "assistant" if has_block("tool_use") => Some(TailTurn::Running),
+        "assistant" if has_block("text") => Some(TailTurn::Finished),
+        "user" => {
+            let text = match content {
+                Value::String(text) => Some(text.as_str()),
+                _ => blocks
+                    .and_then(|blocks| blocks.first())
+                    .filter(|block| block.get("type").and_then(Value::as_str) == Some("text"))
+                    .and_then(|block| block.get("text"))
+                    .and_then(Value::as_str),
+            };
+            if text.is_some_and(|text| text.starts_with("[Request interrupted by user")) {
+                Some(TailTurn::Aborted)
+            } else {
+                Some(TailTurn::Running)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// A Codex rollout line's bearing on the current turn, from its task
+/// lifecycle events.
+pub(crate) fn codex_turn_state(entry: &Value) -> Option<TailTurn> {
+    if entry.get("type").and_then(Value::as_str) != Some("event_msg") {
+        return None;
+    }
+    match entry.pointer("/payload/type").and_then(Value::as_str)? {
+        "task_started" | "user_message" => Some(TailTurn::Running),
+        "task_complete" => Some(TailTurn::Finished),
+        "turn_aborted" => Some(TailTurn::Aborted),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claude_tail_reads_the_turn_state() {
+        let reply = json!({
+            "type": "assistant",
+            "message": { "content": [{ "type": "text", "text": "Done." }] }
+        });
+        assert_eq!(claude_turn_state(&reply), Some(TailTurn::Finished));
+
+        let tool = json!({
+            "type": "assistant",
+            "message": { "content": [{ "type": "tool_use", "name": "Bash", "input": {} }] }
+        });
+        assert_eq!(claude_turn_state(&tool), Some(TailTurn::Running));
+
+        let interrupted = json!({
+            "type": "user",
+            "message": { "content": [{ "type": "text", "text": "[Request interrupted by user]" }] }
+        });
+        assert_eq!(claude_turn_state(&interrupted), Some(TailTurn::Aborted));
+
+        let prompt = json!({ "type": "user", "message": { "content": "fix the build" } });
+        assert_eq!(claude_turn_state(&prompt), Some(TailTurn::Running));
+
+        let subagent = json!({ "isSidechain": true, "type": "assistant", "message": { "content": [{ "type": "text", "text": "sub" }] } });
+        assert_eq!(claude_turn_state(&subagent), None);
+        assert_eq!(claude_turn_state(&json!({ "type": "summary" })), None);
+    }
+
+    #[test]
+    fn codex_tail_reads_task_events() {
+        let event = |kind: &str| json!({ "type": "event_msg", "payload": { "type": kind } });
+        assert_eq!(
+            codex_turn_state(&event("task_complete")),
+            Some(TailTurn::Finished)
+        );
+        assert_eq!(
+            codex_turn_state(&event("turn_aborted")),
+            Some(TailTurn::Aborted)
+        );
+        assert_eq!(
+            codex_turn_state(&event("task_started")),
+            Some(TailTurn::Running)
+        );
+        assert_eq!(codex_turn_state(&event("token_count")), None);
+        assert_eq!(codex_turn_state(&json!({ "type": "response_item" })), None);
+    }
+
+    #[test]
+    fn direct_turns_are_aborted_with_a_transcript_note() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut conn = Connection::open(dir.path().join("orbitdock.db")).unwrap();
+        crate::migration_runner::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO sessions (id, project_path, provider, status, work_status, claude_integration_mode)
+             VALUES ('direct', '/tmp/p', 'claude', 'active', 'working', 'direct');
+             INSERT INTO messages (id, session_id, type, content, timestamp, sequence, is_in_progress)
+             VALUES ('m0', 'direct', 'user', 'go', '2026-01-01T00:00:00Z', 0, 0),
+                    ('m1', 'direct', 'tool', 'ls', '2026-01-01T00:00:01Z', 1, 1);
+             INSERT INTO turns (session_id, turn_id, started_at) VALUES ('direct', 'turn-1', '2026-01-01T00:00:00Z');",
+        )
+        .unwrap();
+
+        let recovered = recover(&conn, "2026-01-01T00:05:00Z").unwrap();
+        assert_eq!(
+            recovered,
+            Recovered {
+                aborted: 1,
+                settled: 0
+            }
+        );
+
+        let (ended_at, aborted, last_seq): (Option<String>, bool, i64) = conn
+            .query_row(
+                "SELECT ended_at, aborted, last_message_seq FROM turns WHERE turn_id = 'turn-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(ended_at.as_deref(), Some("2026-01-01T00:05:00Z"));
+        assert!(aborted);
+        assert_eq!(last_seq, 2, "the turn covers the interruption note");
+
+        let (content, is_error): (String, bool) = conn
+            .query_row(
+                "SELECT content, is_error FROM messages WHERE sequence = 2",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(content, INTERRUPTED_MESSAGE);
+        assert!(is_error);
+
+        let work_status: String = conn
+            .query_row(
+                "SELECT work_status FROM sessions WHERE id = 'direct'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(work_status, "reply");
+        let in_progress: i64 = conn
+            .query_row("SELECT SUM(is_in_progress) FROM messages", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(in_progress, 0);
+    }
+
+    #[test]
+    fn passive_turns_settle_from_the_transcript_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let finished = dir.path().join("finished.jsonl");
+        std::fs::write(
+            &finished,
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Done."}]}}"#,
+        )
+        .unwrap();
+        let running = dir.path().join("running.jsonl");
+        std::fs::write(
+            &running,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Bash"}]}}"#,
+        )
+        .unwrap();
+
+        let mut conn = Connection::open(dir.path().join("orbitdock.db")).unwrap();
+        crate::migration_runner::run_migrations(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO sessions (id, project_path, provider, status, work_status, transcript_path)
+             VALUES ('done', '/tmp/p', 'claude', 'active', 'working', ?1),
+                    ('busy', '/tmp/p', 'claude', 'active', 'working', ?2)",
+            params![finished.to_string_lossy(), running.to_string_lossy()],
+        )
+        .unwrap();
+
+        let recovered = recover(&conn, "2026-01-01T00:05:00Z").unwrap();
+        assert_eq!(
+            recovered,
+            Recovered {
+                aborted: 0,
+                settled: 1
+            }
+        );
+
+        let status = |id: &str| -> String {
+            conn.query_row(
+                "SELECT work_status FROM sessions WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(status("done"), "reply");
+        assert_eq!(status("busy"), "working");
+    }
+
+    #[test]
+    fn tail_skips_entries_after_the_last_turn_boundary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rollout.jsonl");
+        std::fs::write(
+            &path,
+            [
+                r#"{"type":"event_msg","payload":{"type":"task_started"}}"#,
+                r#"{"type":"event_msg","payload":{"type":"task_complete"}}"#,
+                r#"{"type":"event_msg","payload":{"type":"token_count"}}"#,
+                "not json",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        assert_eq!(read_tail_turn(&path, "codex"), Some(TailTurn::Finished));
+        assert_eq!(
+            read_tail_turn(&dir.path().join("missing.jsonl"), "codex"),
+            None
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! One-line summaries of completed turns.
+//!
+//! When a turn completes, the session hands the webhook dispatcher a compact
+//! transcript of the turn along with its diff stats. Before notifying, the
+//! dispatcher asks the `ai_naming` provider for a one-line summary, adds it
+//! to the event payload, and shows it as the session's `last_message` until a
+//! newer message arrives. Without a provider that summarizes, or with
+//! `ORBITDOCK_TURN_SUMMARIES=0`, notifications go out with diff stats only.
+
+use std::time::Duration;
+
+use orbitdock_protocol::{Message, MessageType};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+const SUMMARY_TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_ENTRY_CHARS: usize = 500;
+const MAX_TRANSCRIPT_CHARS: usize = 6000;
+const MAX_SUMMARY_CHARS: usize = 200;
+
+/// Files touched and lines changed by a turn's unified diff.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffStats {
+    pub files: u32,
+    pub additions: u32,
+    pub deletions: u32,
+}
+
+impl DiffStats {
+    pub fn from_diff(diff: &str) -> Self {
+        let mut stats = DiffStats::default();
+        // Lines left in the current hunk, from its `@@ -a,b +c,d @@` header.
+        let (mut old_left, mut new_left) = (0u32, 0u32);
+        // A `diff --git` header already counted the file its `+++` line names.
+        let mut git_header = false;
+
+        for line in diff.lines() {
+            if old_left > 0 || new_left > 0 {
+                match line.as_bytes().first() {
+                    Some(b'+') => {
+                        stats.additions += 1;
+                        new_left = new_left.saturating_sub(1);
+                    }
+                    Some(b'-') => {
+                        stats.deletions += 1;
+                        old_left = old_left.saturating_sub(1);
+                    }
+                    Some(b'\\') => {}
+                    _ => {
+                        old_left = old_left.saturating_sub(1);
+                        new_left = new_left.saturating_sub(1);
+                    }
+                }
+                continue;
+            }
+
+            if line.starts_with("diff --git ") {
+                stats.files += 1;
+                git_header = true;
+            } else if line.starts_with("+++ ") {
+                if !git_header {
+                    stats.files += 1;
+                }
+                git_header = false;
+            } else if let Some(ranges) = line.strip_prefix("@@ ") {
+                let mut specs = ranges.split_whitespace();
+                old_left = specs.next().map_or(0, range_len);
+                new_left = specs.next().map_or(0, range_len);
+            }
+        }
+        stats
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files == 0 && self.additions == 0 && self.deletions == 0
+    }
+
+    /// e.g. `3 files, +40 -12`
+    pub fn describe(&self) -> String {
+        let noun = if self.files == 1 { "file" } else { "files" };
+        format!(
+            "{} {noun}, +{} -{}",
+            self.files, self.additions, self.deletions
+        )
+    }
+}
+
+/// Line count of a hunk range like `-12,5` (a bare `-12` means one line).
+fn range_len(spec: &str) -> u32 {
+    match spec.split_once(',') {
+        Some((_, len)) => len.parse().unwrap_or(0),
+        None => 1,
+    }
+}
+
+/// Summaries are on unless `ORBITDOCK_TURN_SUMMARIES` is `0` or `false`.
+pub fn enabled() -> bool {
+    !matches!(
+        crate::config_file::var("ORBITDOCK_TURN_SUMMARIES").as_deref(),
+        Some("0") | Some("false")
+    )
+}
+
+/// The latest turn, from its user prompt on, as plain text for the summarizer.
+///
+/// Returns `None` when the agent did nothing since the last prompt. Long
+/// turns keep the prompt and drop their oldest entries to fit.
+pub fn transcript(messages: &[Message], stats: Option<&DiffStats>) -> Option<String> {
+    transcript_of_turns(messages, 1, stats)
+}
+
+/// The last `turns` turns as plain text, for the rolling session summary.
+pub fn recent_transcript(messages: &[Message], turns: usize) -> Option<String> {
+    transcript_of_turns(messages, turns, None)
+}
+
+fn transcript_of_turns(
+    messages: &[Message],
+    turns: usize,
+    stats: Option<&DiffStats>,
+) -> Option<String> {
+    let start = messages
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, message)| message.message_type == MessageType::User)
+        .nth(turns.saturating_sub(1))
+        .map_or(0, |(index, _)| index);
+
+    let mut entries: Vec<String> = messages[start..]
+        .iter()
+        .filter_map(transcript_entry)
+        .collect();
+    if !entries.iter().any(|entry| !entry.starts_with("User: ")) {
+        return None;
+    }
+    if let Some(stats) = stats.filter(|stats| !stats.is_empty()) {
+        entries.push(format!("Diff: {}", stats.describe()));
+    }
+
+    let mut total: usize = entries.iter().map(|entry| entry.len() + 1).sum();
+    while total > MAX_TRANSCRIPT_CHARS && entries.len() > 2 {
+        total -= entries.remove(1).len() + 1;
+    }
+    Some(entries.join("\n"))
+}
+
+fn transcript_entry(message: &Message) -> Option<String> {
+    let (label, text) = match message.message_type {
+        MessageType::User | MessageType::Steer => ("User".to_string(), message.content.as_str()),
+        MessageType::Assistant => ("Assistant".to_string(), message.content.as_str()),
+        MessageType::Tool => (
+            format!(
+                "Tool ({})",
+                message.tool_name.as_deref().unwrap_or("unknown")
+            ),
+            message.tool_input.as_deref().unwrap_or(&message.content),
+        ),
+        MessageType::Shell => ("Shell".to_string(), message.content.as_str()),
+        MessageType::Thinking | MessageType::ToolResult => return None,
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let text = crate::chat_notifications::truncate(text, MAX_ENTRY_CHARS);
+    Some(format!("{label}: {}", text.replace('\n', " ")))
+}
+
+/// Ask the naming provider for a one-line summary; `None` without one that
+/// summarizes, or on failure.
+pub async fn summarize(session_id: &str, transcript: &str) -> Option<String> {
+    let provider = crate::ai_naming::provider()?;
+    let result = tokio::time::timeout(
+        SUMMARY_TIMEOUT,
+        provider.generate(crate::ai_naming::Task::TurnSummary, transcript),
+    )
+    .await
+    .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out")));
+
+    match result {
+        Ok(None) => None,
+        Ok(Some(summary)) => {
+            let line = summary.lines().next().unwrap_or_default().trim();
+            let summary = crate::chat_notifications::truncate(line, MAX_SUMMARY_CHARS);
+            info!(
+                component = "turn_summary",
+                event = "turn_summary.generated",
+                session_id = %session_id,
+                summary = %summary,
+            );
+            Some(summary).filter(|summary| !summary.is_empty())
+        }
+        Err(e) => {
+            warn!(
+                component = "turn_summary",
+                event = "turn_summary.failed",
+                session_id = %session_id,
+                error = %e,
+                "Failed to summarize turn"
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(message_type: MessageType, content: &str) -> Message {
+        Message {
+            id: "m".into(),
+            session_id: "sess-1".into(),
+            sequence: None,
+            message_type,
+            content: content.into(),
+            tool_name: None,
+            tool_input: None,
+            tool_output: None,
+            is_error: false,
+            is_in_progress: false,
+            timestamp: "1760700000Z".into(),
+            duration_ms: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn diff_stats_count_files_and_changed_lines() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@
+ fn main() {
+--- removed
++++ added
++    run();
+ }
+\\ No newline at end of file
+diff --git a/logo.png b/logo.png
+Binary files a/logo.png and b/logo.png differ
+diff --git a/NOTES.md b/NOTES.md
+new file mode 100644
+--- /dev/null
++++ b/NOTES.md
+@@ -0,0 +1 @@
++notes
+";
+        let stats = DiffStats::from_diff(diff);
+        assert_eq!(
+            stats,
+            DiffStats {
+                files: 3,
+                additions: 3,
+                deletions: 1,
+            }
+        );
+        assert_eq!(stats.describe(), "3 files, +3 -1");
+        assert!(DiffStats::from_diff("").is_empty());
+    }
+
+    #[test]
+    fn transcript_covers_the_latest_turn() {
+        let mut tool = message(MessageType::Tool, "");
+        tool.tool_name = Some("Bash".into());
+        tool.tool_input = Some("cargo test".into());
+        let messages = vec![
+            message(MessageType::User, "old prompt"),
+            message(MessageType::Assistant, "old reply"),
+            message(MessageType::User, "fix the build"),
+            message(MessageType::Thinking, "hmm"),
+            tool,
+            message(MessageType::Assistant, "Fixed.\nAll green."),
+        ];
+        let stats = DiffStats {
+            files: 1,
+            additions: 2,
+            deletions: 0,
+        };
+
+        assert_eq!(
+            transcript(&messages, Some(&stats)).as_deref(),
+            Some(
+                "User: fix the build\nTool (Bash): cargo test\nAssistant: Fixed. All green.\nDiff: 1 file, +2 -0"
+            )
+        );
+        assert_eq!(
+            transcript(&messages[..3], None),
+            None,
+            "a turn with only the prompt has nothing to summarize"
+        );
+        assert_eq!(
+            recent_transcript(&messages, 2).as_deref(),
+            Some(
+                "User: old prompt\nAssistant: old reply\nUser: fix the build\nTool (Bash): cargo test\nAssistant: Fixed. All green."
+            )
+        );
+        assert_eq!(
+            recent_transcript(&messages, 5),
+            recent_transcript(&messages, 2)
+        );
+    }
+}
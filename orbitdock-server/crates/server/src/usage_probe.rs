@@ -193,6 +193,10 @@ pub async fn fetch_codex_usage() -> Result<CodexUsageSnapshot, UsageProbeError>
         Ok(CodexUsageSnapshot {
             primary: parse_codex_limit(rate_limits.get("primary")),
             secondary: parse_codex_limit(rate_limits.get("secondary")),
+            plan_type: account_value
+                .get("planType")
+                .and_then(Value::as_str)
+                .map(str::to_lowercase),
             fetched_at_unix: unix_now(),
         })
     }
@@ -372,7 +376,7 @@ async fn read_json_rpc_response(
     }
 }
 
-fn find_codex_binary() -> Option<PathBuf> {
+pub(crate) fn find_codex_binary() -> Option<PathBuf> {
     if let Ok(value) = std::env::var("ORBITDOCK_CODEX_PATH") {
         let path = PathBuf::from(value);
         if is_executable_file(&path) {
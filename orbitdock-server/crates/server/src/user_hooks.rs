@@ -0,0 +1,305 @@
+//! User scripts run on session events.
+//!
+//! Each `[[hooks]]` entry in the config file names a shell command and the
+//! webhook event kinds it wants (every kind when `events` is omitted). For a
+//! matching event the command runs under `sh -c` with the generic webhook
+//! payload on stdin and `ORBITDOCK_EVENT` / `ORBITDOCK_SESSION_ID` set, and is
+//! killed, along with anything it started, once its timeout passes. Every run
+//! is recorded in the audit log as
+//! `user_hook_ran`, with the exit status and the tail of its output.
+//!
+//! Hooks run concurrently and never hold up webhook delivery. The config is
+//! re-read for every event, so edits apply on reload.
+
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::config_file;
+use crate::persistence::PersistCommand;
+use crate::shell::truncate_output_tail;
+use crate::webhooks::{WebhookEvent, WebhookEventKind};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Bytes of stdout and of stderr kept in the audit log.
+const OUTPUT_LIMIT: usize = 4096;
+
+/// One `[[hooks]]` entry.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserHook {
+    pub command: String,
+    /// Webhook event kinds, e.g. `["turn_completed", "approval_pending"]`.
+    #[serde(default)]
+    pub events: Option<Vec<String>>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl UserHook {
+    fn subscribes_to(&self, kind: WebhookEventKind) -> bool {
+        self.events
+            .as_ref()
+            .is_none_or(|events| events.iter().any(|event| event.trim() == kind.as_str()))
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS))
+    }
+}
+
+pub fn problems(hooks: &[UserHook]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (index, hook) in hooks.iter().enumerate() {
+        if hook.command.trim().is_empty() {
+            problems.push(format!("hooks[{index}].command: must not be empty"));
+        }
+        for event in hook.events.iter().flatten() {
+            if WebhookEventKind::parse(event).is_none() {
+                problems.push(format!("hooks[{index}].events: unknown event \"{event}\""));
+            }
+        }
+        if hook.timeout_secs == Some(0) {
+            problems.push(format!("hooks[{index}].timeout_secs: must be at least 1"));
+        }
+    }
+    problems
+}
+
+/// Start every configured hook that subscribes to `event`.
+pub fn run(persist_tx: &mpsc::Sender<PersistCommand>, event: &WebhookEvent) {
+    let config = config_file::current();
+    let hooks: Vec<UserHook> = config
+        .hooks
+        .iter()
+        .filter(|hook| hook.subscribes_to(event.kind))
+        .cloned()
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+
+    let payload = event.payload(&uuid::Uuid::new_v4().to_string()).to_string();
+    for hook in hooks {
+        let persist_tx = persist_tx.clone();
+        let payload = payload.clone();
+        let kind = event.kind;
+        let session_id = event.session.id.clone();
+        tokio::spawn(async move {
+            let env = [
+                ("ORBITDOCK_EVENT", kind.as_str()),
+                ("ORBITDOCK_SESSION_ID", session_id.as_str()),
+            ];
+            let outcome = execute(&hook.command, &payload, &env, hook.timeout()).await;
+            if !outcome.succeeded() {
+                warn!(
+                    component = "user_hooks",
+                    event = "user_hooks.failed",
+                    command = %hook.command,
+                    hook_event = kind.as_str(),
+                    session_id = %session_id,
+                    exit_code = ?outcome.exit_code,
+                    timed_out = outcome.timed_out,
+                    error = ?outcome.error,
+                    "User hook did not exit cleanly"
+                );
+            }
+            let _ = persist_tx
+                .send(PersistCommand::AuditLogInsert {
+                    connection_id: 0,
+                    identity: "user_hook".to_string(),
+                    user: None,
+                    action: "user_hook_ran".to_string(),
+                    session_id: Some(session_id),
+                    details: outcome.details(&hook.command, kind),
+                })
+                .await;
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+struct HookOutcome {
+    exit_code: Option<i32>,
+    timed_out: bool,
+    /// The command could not be started or waited on.
+    error: Option<String>,
+    stdout: String,
+    stderr: String,
+    duration_ms: u64,
+}
+
+impl HookOutcome {
+    fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    fn details(&self, command: &str, kind: WebhookEventKind) -> Value {
+        json!({
+            "command": command,
+            "event": kind.as_str(),
+            "exit_code": self.exit_code,
+            "timed_out": self.timed_out,
+            "error": self.error,
+            "stdout": self.stdout,
+            "stderr": self.stderr,
+            "duration_ms": self.duration_ms,
+        })
+    }
+}
+
+async fn execute(
+    command: &str,
+    stdin: &str,
+    env: &[(&str, &str)],
+    timeout: Duration,
+) -> HookOutcome {
+    let started = Instant::now();
+    let mut outcome = HookOutcome::default();
+
+    // In its own process group so a timeout takes down everything it started.
+    let spawned = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .kill_on_drop(true)
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            outcome.error = Some(e.to_string());
+            return outcome;
+        }
+    };
+
+    if let Some(mut pipe) = child.stdin.take() {
+        let body = stdin.as_bytes().to_vec();
+        // A script that never reads stdin must not stall on a full pipe.
+        tokio::spawn(async move {
+            let _ = pipe.write_all(&body).await;
+        });
+    }
+
+    // Output is collected as it arrives so a hook that times out still
+    // reports what it printed.
+    let stdout = Arc::new(Mutex::new(String::new()));
+    let stderr = Arc::new(Mutex::new(String::new()));
+    let stdout_task = tokio::spawn(read_tail(child.stdout.take(), stdout.clone()));
+    let stderr_task = tokio::spawn(read_tail(child.stderr.take(), stderr.clone()));
+
+    // Background processes the hook leaves holding its output open count
+    // against the same timeout.
+    let finished = tokio::time::timeout(timeout, async {
+        let status = child.wait().await;
+        let _ = tokio::join!(stdout_task, stderr_task);
+        status
+    })
+    .await;
+    match finished {
+        Ok(Ok(status)) => outcome.exit_code = status.code(),
+        Ok(Err(e)) => outcome.error = Some(e.to_string()),
+        Err(_) => {
+            outcome.timed_out = true;
+            crate::shell::kill_process_group(&mut child).await;
+        }
+    }
+    outcome.stdout = stdout.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    outcome.stderr = stderr.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    outcome.duration_ms = started.elapsed().as_millis() as u64;
+    outcome
+}
+
+/// Append everything read from `pipe` to `output`, keeping the last
+/// `OUTPUT_LIMIT` bytes.
+async fn read_tail(pipe: Option<impl AsyncRead + Unpin>, output: Arc<Mutex<String>>) {
+    let Some(mut pipe) = pipe else {
+        return;
+    };
+    let mut buf = [0u8; 4096];
+    while let Ok(n) = pipe.read(&mut buf).await {
+        if n == 0 {
+            break;
+        }
+        let mut output = output.lock().unwrap_or_else(|e| e.into_inner());
+        output.push_str(&String::from_utf8_lossy(&buf[..n]));
+        truncate_output_tail(&mut output, OUTPUT_LIMIT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(events: Option<&[&str]>) -> UserHook {
+        UserHook {
+            command: "true".to_string(),
+            events: events.map(|events| events.iter().map(|e| e.to_string()).collect()),
+            timeout_secs: None,
+        }
+    }
+
+    #[test]
+    fn hooks_match_listed_events_or_all_when_unlisted() {
+        assert!(hook(None).subscribes_to(WebhookEventKind::SessionEnded));
+        let turns = hook(Some(&["turn_completed"]));
+        assert!(turns.subscribes_to(WebhookEventKind::TurnCompleted));
+        assert!(!turns.subscribes_to(WebhookEventKind::ApprovalPending));
+
+        assert_eq!(
+            problems(&[hook(Some(&["turn_done"]))]),
+            vec!["hooks[0].events: unknown event \"turn_done\"".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn execute_pipes_the_event_and_captures_output() {
+        let outcome = execute(
+            "cat; echo \"$ORBITDOCK_EVENT\" >&2; exit 3",
+            "{\"event\":\"turn_completed\"}",
+            &[("ORBITDOCK_EVENT", "turn_completed")],
+            Duration::from_secs(5),
+        )
+        .await;
+
+        assert_eq!(outcome.exit_code, Some(3));
+        assert!(!outcome.timed_out);
+        assert_eq!(outcome.stdout, "{\"event\":\"turn_completed\"}");
+        assert_eq!(outcome.stderr, "turn_completed\n");
+    }
+
+    #[tokio::test]
+    async fn execute_kills_hooks_past_their_timeout() {
+        let outcome = execute("sleep 5", "", &[], Duration::from_millis(100)).await;
+
+        assert!(outcome.timed_out);
+        assert_eq!(outcome.exit_code, None);
+        assert!(outcome.duration_ms < 5000);
+    }
+
+    #[tokio::test]
+    async fn timed_out_hooks_keep_their_output_and_lose_their_children() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("survived");
+        let command = format!(
+            "echo started; (sleep 1; touch '{}') & sleep 30",
+            marker.display()
+        );
+        let outcome = execute(&command, "", &[], Duration::from_millis(300)).await;
+
+        assert!(outcome.timed_out);
+        assert_eq!(outcome.stdout, "started\n");
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!marker.exists(), "the hook's background child kept running");
+    }
+}
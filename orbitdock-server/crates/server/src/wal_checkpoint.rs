@@ -0,0 +1,193 @@
+//! WAL checkpoints for the SQLite database.
+//!
+//! SQLite's automatic checkpoints move committed pages back into the main
+//! database but never shrink the `-wal` file, and they stall whenever a reader
+//! holds an old snapshot. Under a steady stream of events the file grows to
+//! hundreds of MB and stays that size until restart. The persistence writer
+//! calls [`due`] every few seconds and, when it says so, runs
+//! `PRAGMA wal_checkpoint(TRUNCATE)` between batches.
+//!
+//! A checkpoint is due every `[database] wal_checkpoint_secs` (default 300)
+//! or as soon as the WAL passes `[database] wal_checkpoint_mb` (default 64);
+//! 0 turns either trigger off. Both are re-read on each check, so config
+//! reloads apply straight away.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+
+use crate::config_file;
+
+/// How often the writer asks whether a checkpoint is due.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+const DEFAULT_INTERVAL_SECS: u64 = 300;
+const DEFAULT_MAX_WAL_MB: u64 = 64;
+
+static LAST_COMPLETE: Mutex<Option<Instant>> = Mutex::new(None);
+static LAST_ATTEMPT: Mutex<Option<Instant>> = Mutex::new(None);
+static LAG_FRAMES: AtomicU64 = AtomicU64::new(0);
+static COMPLETE: AtomicU64 = AtomicU64::new(0);
+static BUSY: AtomicU64 = AtomicU64::new(0);
+static FAILED: AtomicU64 = AtomicU64::new(0);
+
+/// Result of one `PRAGMA wal_checkpoint(TRUNCATE)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// A reader or writer kept the checkpoint from finishing.
+    pub busy: bool,
+    /// Frames in the WAL when the checkpoint ran.
+    pub log_frames: u64,
+    /// Frames copied back into the database.
+    pub checkpointed_frames: u64,
+}
+
+impl Checkpoint {
+    /// Frames still only in the WAL.
+    pub fn lag_frames(&self) -> u64 {
+        self.log_frames.saturating_sub(self.checkpointed_frames)
+    }
+}
+
+/// For `/metrics`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stats {
+    pub complete: u64,
+    pub busy: u64,
+    pub failed: u64,
+    /// Frames the last checkpoint left in the WAL.
+    pub lag_frames: u64,
+    /// Since the last checkpoint that emptied the WAL, if there has been one.
+    pub since_complete: Option<Duration>,
+}
+
+fn setting(name: &str, default: u64) -> u64 {
+    config_file::var(name)
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(default)
+}
+
+fn interval() -> Option<Duration> {
+    match setting("ORBITDOCK_WAL_CHECKPOINT_SECS", DEFAULT_INTERVAL_SECS) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+fn max_wal_bytes() -> Option<u64> {
+    match setting("ORBITDOCK_WAL_CHECKPOINT_MB", DEFAULT_MAX_WAL_MB) {
+        0 => None,
+        mb => Some(mb.saturating_mul(1024 * 1024)),
+    }
+}
+
+pub fn wal_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push("-wal");
+    PathBuf::from(path)
+}
+
+/// Whether the writer should checkpoint now.
+pub fn due(db_path: &Path) -> bool {
+    let wal_bytes = std::fs::metadata(wal_path(db_path))
+        .map(|meta| meta.len())
+        .unwrap_or(0);
+    if wal_bytes == 0 {
+        return false;
+    }
+    if max_wal_bytes().is_some_and(|max| wal_bytes >= max) {
+        return true;
+    }
+    let Some(interval) = interval() else {
+        return false;
+    };
+    let last = *LAST_ATTEMPT.lock().unwrap_or_else(|e| e.into_inner());
+    last.is_none_or(|last| last.elapsed() >= interval)
+}
+
+/// Checkpoint and truncate the WAL, recording the outcome for [`stats`].
+pub fn run(db_path: &Path) -> rusqlite::Result<Checkpoint> {
+    *LAST_ATTEMPT.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+    let result = checkpoint(db_path);
+    match &result {
+        Ok(checkpoint) => {
+            LAG_FRAMES.store(checkpoint.lag_frames(), Ordering::Relaxed);
+            if checkpoint.busy {
+                BUSY.fetch_add(1, Ordering::Relaxed);
+            } else {
+                COMPLETE.fetch_add(1, Ordering::Relaxed);
+                *LAST_COMPLETE.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+            }
+        }
+        Err(_) => {
+            FAILED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    result
+}
+
+fn checkpoint(db_path: &Path) -> rusqlite::Result<Checkpoint> {
+    let conn = Connection::open(db_path)?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+        Ok(Checkpoint {
+            busy: row.get::<_, i64>(0)? != 0,
+            log_frames: row.get::<_, i64>(1)?.max(0) as u64,
+            checkpointed_frames: row.get::<_, i64>(2)?.max(0) as u64,
+        })
+    })
+}
+
+pub fn stats() -> Stats {
+    Stats {
+        complete: COMPLETE.load(Ordering::Relaxed),
+        busy: BUSY.load(Ordering::Relaxed),
+        failed: FAILED.load(Ordering::Relaxed),
+        lag_frames: LAG_FRAMES.load(Ordering::Relaxed),
+        since_complete: LAST_COMPLETE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .map(|at| at.elapsed()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_checkpoint_empties_the_wal() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let db_path = dir.path().join("orbitdock.db");
+
+        let writer = Connection::open(&db_path).expect("open db");
+        writer
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 PRAGMA wal_autocheckpoint = 0;
+                 CREATE TABLE events (body TEXT);",
+            )
+            .expect("set up db");
+        for _ in 0..200 {
+            writer
+                .execute("INSERT INTO events (body) VALUES (?1)", ["x".repeat(1024)])
+                .expect("insert");
+        }
+        let wal = wal_path(&db_path);
+        assert!(std::fs::metadata(&wal).expect("wal exists").len() > 0);
+
+        let checkpoint = run(&db_path).expect("checkpoint");
+        assert!(!checkpoint.busy);
+        assert_eq!(checkpoint.lag_frames(), 0);
+        assert_eq!(std::fs::metadata(&wal).expect("wal exists").len(), 0);
+        assert!(!due(&db_path));
+
+        let rows: i64 = writer
+            .query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))
+            .expect("count");
+        assert_eq!(rows, 200);
+    }
+}
@@ -3,28 +3,37 @@
 //! Handler logic lives in `ws_handlers/`, compaction in `snapshot_compaction`,
 //! session utilities in `session_utils`, and normalization in `normalization`.
 
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use axum::{
     extract::{
         ws::{Message, WebSocket},
-        State, WebSocketUpgrade,
+        ConnectInfo, State, WebSocketUpgrade,
     },
+    http::{header, HeaderMap},
     response::IntoResponse,
+    Extension,
 };
 use bytes::Bytes;
 use futures::{SinkExt, StreamExt};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Notify};
 use tracing::{debug, error, info, warn};
 
 use orbitdock_protocol::{ClientMessage, ServerMessage, SessionState};
 
+use crate::auth::AuthIdentity;
+use crate::session_command_handler::chrono_now;
+use crate::session_fanout::SessionReceiver;
 use crate::snapshot_compaction::{
-    compact_snapshot_for_transport, replay_has_oversize_event, sanitize_replay_event_for_transport,
-    sanitize_server_message_for_transport, WS_MAX_TEXT_MESSAGE_BYTES,
+    compact_snapshot_to_transport_limit, replay_has_oversize_event,
+    sanitize_replay_event_for_transport, sanitize_server_message_for_transport, SnapshotLimits,
+    WS_MAX_TEXT_MESSAGE_BYTES,
 };
-use crate::state::SessionRegistry;
+use crate::state::{ConnectionEntry, SessionRegistry};
+use crate::ws_handlers::HandlerContext;
 
 static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
 
@@ -35,28 +44,80 @@ pub(crate) enum OutboundMessage {
     Json(ServerMessage),
     /// Pre-serialized JSON string (for replay)
     Raw(String),
+    /// Broadcast JSON serialized once and shared by every subscriber
+    Shared(Arc<str>),
     /// Raw pong response
     Pong(Bytes),
+    /// Send a close frame and stop writing
+    Close,
 }
 
 /// WebSocket upgrade handler
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<SessionRegistry>>,
+    identity: Option<Extension<AuthIdentity>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+    let identity = identity
+        .map(|Extension(identity)| identity)
+        .unwrap_or(AuthIdentity::Anonymous);
+    let (client_name, client_version) = client_from_headers(&headers);
+    let entry = ConnectionEntry {
+        identity,
+        remote_addr: connect_info.map(|Extension(ConnectInfo(addr))| addr),
+        client_name,
+        client_version,
+        connected_at: chrono_now(),
+        subscribed_to_list: false,
+        subscriptions: BTreeSet::new(),
+        composing: BTreeSet::new(),
+        disconnect: Arc::new(Notify::new()),
+    };
+    ws.on_upgrade(move |socket| handle_socket(socket, state, entry))
+}
+
+/// Client name and version from `X-OrbitDock-Client` (`name/version`), falling
+/// back to `User-Agent`.
+fn client_from_headers(headers: &HeaderMap) -> (Option<String>, Option<String>) {
+    let value = headers
+        .get("x-orbitdock-client")
+        .or_else(|| headers.get(header::USER_AGENT))
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    let Some(value) = value else {
+        return (None, None);
+    };
+    // User agents can carry several `product/version` tokens; keep the first.
+    let product = value.split_whitespace().next().unwrap_or(value);
+    match product.split_once('/') {
+        Some((name, version)) if !name.is_empty() && !version.is_empty() => {
+            (Some(name.to_string()), Some(version.to_string()))
+        }
+        _ => (Some(product.to_string()), None),
+    }
 }
 
 /// Handle a WebSocket connection
-async fn handle_socket(socket: WebSocket, state: Arc<SessionRegistry>) {
+async fn handle_socket(socket: WebSocket, state: Arc<SessionRegistry>, entry: ConnectionEntry) {
     let conn_id = NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
     state.ws_connect();
+    let identity = entry.identity.label();
+    let rate_key = crate::rate_limit::credential_key(&entry.identity, entry.remote_addr);
+    let disconnect = entry.disconnect.clone();
     info!(
         component = "websocket",
         event = "ws.connection.opened",
         connection_id = conn_id,
+        identity = %identity,
+        remote_addr = ?entry.remote_addr,
+        client_name = ?entry.client_name,
+        client_version = ?entry.client_version,
         "WebSocket connection opened"
     );
+    state.register_connection(conn_id, entry);
 
     let (mut ws_tx, mut ws_rx) = socket.split();
 
@@ -64,7 +125,7 @@ async fn handle_socket(socket: WebSocket, state: Arc<SessionRegistry>) {
     let (outbound_tx, mut outbound_rx) = mpsc::channel::<OutboundMessage>(100);
 
     // Spawn task to forward messages to WebSocket
-    let send_task = tokio::spawn(async move {
+    let mut send_task = tokio::spawn(async move {
         while let Some(msg) = outbound_rx.recv().await {
             let result = match msg {
                 OutboundMessage::Json(server_msg) => {
@@ -110,7 +171,25 @@ async fn handle_socket(socket: WebSocket, state: Arc<SessionRegistry>) {
                     }
                     ws_tx.send(Message::Text(json.into())).await
                 }
+                OutboundMessage::Shared(json) => {
+                    if json.len() > WS_MAX_TEXT_MESSAGE_BYTES {
+                        warn!(
+                            component = "websocket",
+                            event = "ws.send.shared_dropped_oversize",
+                            connection_id = conn_id,
+                            bytes = json.len(),
+                            max_bytes = WS_MAX_TEXT_MESSAGE_BYTES,
+                            "Dropped oversized server message after compaction"
+                        );
+                        continue;
+                    }
+                    ws_tx.send(Message::Text(json.as_ref().into())).await
+                }
                 OutboundMessage::Pong(data) => ws_tx.send(Message::Pong(data)).await,
+                OutboundMessage::Close => {
+                    let _ = ws_tx.send(Message::Close(None)).await;
+                    break;
+                }
             };
 
             if result.is_err() {
@@ -131,8 +210,38 @@ async fn handle_socket(socket: WebSocket, state: Arc<SessionRegistry>) {
     // Announce server role immediately so clients can derive control-plane routing.
     send_json(&outbound_tx, server_info_message(&state)).await;
 
-    // Handle incoming messages
-    while let Some(result) = ws_rx.next().await {
+    // Handle incoming messages until the client leaves or an admin disconnects it
+    let mut disconnected_by_admin = false;
+    let mut rate_bucket = crate::rate_limit::ws_connection_bucket();
+    // Report a flood once, not once per dropped message.
+    let mut rate_limited = false;
+    loop {
+        let result = tokio::select! {
+            result = ws_rx.next() => match result {
+                Some(result) => result,
+                None => break,
+            },
+            _ = disconnect.notified() => {
+                info!(
+                    component = "websocket",
+                    event = "ws.connection.disconnected_by_admin",
+                    connection_id = conn_id,
+                    "Closing connection on admin request"
+                );
+                send_json(
+                    &client_tx,
+                    ServerMessage::Error {
+                        code: "disconnected".into(),
+                        message: "Disconnected by a server administrator".into(),
+                        session_id: None,
+                        error: None,
+                    },
+                )
+                .await;
+                disconnected_by_admin = true;
+                break;
+            }
+        };
         let msg = match result {
             Ok(Message::Text(text)) => text,
             Ok(Message::Ping(data)) => {
@@ -162,6 +271,36 @@ async fn handle_socket(socket: WebSocket, state: Arc<SessionRegistry>) {
             }
         };
 
+        let within_limit = rate_bucket
+            .as_mut()
+            .is_none_or(|bucket| bucket.try_acquire())
+            && crate::rate_limit::allow_ws_message(&rate_key);
+        if !within_limit {
+            crate::rate_limit::note_ws_rejected();
+            if !rate_limited {
+                rate_limited = true;
+                warn!(
+                    component = "websocket",
+                    event = "ws.message.rate_limited",
+                    connection_id = conn_id,
+                    identity = %identity,
+                    "Dropping client messages over the rate limit"
+                );
+                send_json(
+                    &client_tx,
+                    ServerMessage::Error {
+                        code: "rate_limited".into(),
+                        message: "Too many messages; some were dropped".into(),
+                        session_id: None,
+                        error: None,
+                    },
+                )
+                .await;
+            }
+            continue;
+        }
+        rate_limited = false;
+
         // Parse client message
         let client_msg: ClientMessage = match serde_json::from_str(&msg) {
             Ok(m) => m,
@@ -181,6 +320,7 @@ async fn handle_socket(socket: WebSocket, state: Arc<SessionRegistry>) {
                         code: "parse_error".into(),
                         message: e.to_string(),
                         session_id: None,
+                        error: None,
                     },
                 )
                 .await;
@@ -192,6 +332,16 @@ async fn handle_socket(socket: WebSocket, state: Arc<SessionRegistry>) {
     }
 
     state.ws_disconnect();
+    let closed_terminals = state.terminal_service().close_connection(conn_id);
+    if closed_terminals > 0 {
+        info!(
+            component = "terminal",
+            event = "terminal.close.connection_closed",
+            connection_id = conn_id,
+            closed_terminals,
+            "Closed terminals owned by disconnected client"
+        );
+    }
     info!(
         component = "websocket",
         event = "ws.connection.closed",
@@ -201,6 +351,14 @@ async fn handle_socket(socket: WebSocket, state: Arc<SessionRegistry>) {
     if state.clear_client_primary_claim(conn_id) {
         state.broadcast_to_list(server_info_message(&state));
     }
+    for session_id in state.remove_connection(conn_id) {
+        state.publish_presence(&session_id).await;
+    }
+    if disconnected_by_admin {
+        // Flush the notice and a close frame before tearing down.
+        let _ = outbound_tx.send(OutboundMessage::Close).await;
+        let _ = tokio::time::timeout(std::time::Duration::from_secs(1), &mut send_task).await;
+    }
     send_task.abort();
 }
 
@@ -224,6 +382,7 @@ pub(crate) async fn send_rest_only_error(
             code: "http_only_endpoint".into(),
             message: format!("Use REST endpoint {endpoint} for this request"),
             session_id,
+            error: None,
         },
     )
     .await;
@@ -277,6 +436,7 @@ pub(crate) async fn send_replay_or_snapshot_fallback(
                     "Replay payload exceeded transport limit; re-bootstrap the conversation"
                         .to_string(),
                 session_id: Some(session_id.to_string()),
+                error: None,
             },
         )
         .await;
@@ -288,21 +448,31 @@ pub(crate) async fn send_replay_or_snapshot_fallback(
     }
 }
 
+/// Sent pre-compacted with the subscriber's limits, which may differ from the
+/// server's, so the outbound sanitizer doesn't compact it a second time.
 pub(crate) async fn send_snapshot_if_requested(
     tx: &mpsc::Sender<OutboundMessage>,
     session_id: &str,
     snapshot: SessionState,
     include_snapshot: bool,
+    limits: &SnapshotLimits,
     conn_id: u64,
 ) {
     if include_snapshot {
-        send_json(
-            tx,
-            ServerMessage::SessionSnapshot {
-                session: compact_snapshot_for_transport(snapshot),
-            },
-        )
-        .await;
+        let message = ServerMessage::SessionSnapshot {
+            session: compact_snapshot_to_transport_limit(snapshot, limits),
+        };
+        match serde_json::to_string(&message) {
+            Ok(json) => send_raw(tx, json).await,
+            Err(e) => warn!(
+                component = "websocket",
+                event = "ws.subscribe.snapshot_serialize_failed",
+                connection_id = conn_id,
+                session_id = %session_id,
+                error = %e,
+                "Failed to serialize session snapshot"
+            ),
+        }
         return;
     }
 
@@ -320,21 +490,45 @@ pub(crate) async fn send_raw(tx: &mpsc::Sender<OutboundMessage>, json: String) {
     let _ = tx.send(OutboundMessage::Raw(json)).await;
 }
 
-/// Spawn a task that drains a broadcast receiver and forwards messages to an outbound channel.
-/// When the outbound channel closes (client disconnects), the task exits and the
-/// broadcast::Receiver is dropped — automatic cleanup, no manual unsubscribe needed.
+/// Spawn a task that drains a session subscription and forwards messages to an
+/// outbound channel. When the outbound channel closes (client disconnects), the
+/// task exits and the receiver is dropped — automatic cleanup, no manual
+/// unsubscribe needed.
 ///
-/// If `session_id` is provided and the subscriber lags behind the broadcast buffer,
-/// a `lagged` error is sent to the client so it can re-bootstrap the conversation.
-pub(crate) fn spawn_broadcast_forwarder(
+/// A client too slow for its queue gets a `session_snapshot` in place of the
+/// messages it missed (see `session_fanout`), so nothing is skipped silently.
+/// Messages arrive already serialized and are forwarded as-is.
+pub(crate) fn spawn_session_forwarder(
+    mut rx: SessionReceiver,
+    outbound_tx: mpsc::Sender<OutboundMessage>,
+) {
+    tokio::spawn(async move {
+        while let Some(shared) = rx.recv().await {
+            let outbound = match &shared.json {
+                Some(json) => OutboundMessage::Shared(json.clone()),
+                None => OutboundMessage::Json(shared.message.clone()),
+            };
+            if outbound_tx.send(outbound).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Spawn a task that drains a list broadcast receiver, forwarding whatever
+/// `filter` returns for each message to an outbound channel.
+pub(crate) fn spawn_filtered_broadcast_forwarder(
     mut rx: tokio::sync::broadcast::Receiver<ServerMessage>,
     outbound_tx: mpsc::Sender<OutboundMessage>,
-    session_id: Option<String>,
+    filter: impl Fn(ServerMessage) -> Option<ServerMessage> + Send + 'static,
 ) {
     tokio::spawn(async move {
         loop {
             match rx.recv().await {
                 Ok(msg) => {
+                    let Some(msg) = filter(msg) else {
+                        continue;
+                    };
                     if outbound_tx.send(OutboundMessage::Json(msg)).await.is_err() {
                         break;
                     }
@@ -343,16 +537,16 @@ pub(crate) fn spawn_broadcast_forwarder(
                     warn!(
                         component = "websocket",
                         event = "ws.broadcast.lagged",
-                        session_id = ?session_id,
                         skipped = n,
                         "Broadcast subscriber lagged, skipped {n} messages"
                     );
-                    // Notify the client so it can re-bootstrap over the paged HTTP path.
+                    // Notify the client so it can refetch the session list.
                     let _ = outbound_tx
                         .send(OutboundMessage::Json(ServerMessage::Error {
                             code: "lagged".to_string(),
                             message: format!("Subscriber lagged, skipped {n} messages"),
-                            session_id: session_id.clone(),
+                            session_id: None,
+                            error: None,
                         }))
                         .await;
                 }
@@ -362,127 +556,32 @@ pub(crate) fn spawn_broadcast_forwarder(
     });
 }
 
-/// Dispatch a single client WebSocket message.
+/// Dispatch a single client WebSocket message (see `ws_handlers::dispatch`).
 ///
-/// Each handler group lives in its own module under , so each
-/// `.await` site produces an independently-sized future. This keeps the
-/// parent future small enough for the default 2 MiB thread stack in debug
-/// builds.
-fn handle_client_message<'a>(
+/// Boxed so the connection loop holds a pointer rather than the largest
+/// handler's future, which keeps it within the default 2 MiB thread stack in
+/// debug builds.
+async fn handle_client_message(
     msg: ClientMessage,
-    client_tx: &'a mpsc::Sender<OutboundMessage>,
-    state: &'a Arc<SessionRegistry>,
+    client_tx: &mpsc::Sender<OutboundMessage>,
+    state: &Arc<SessionRegistry>,
     conn_id: u64,
-) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + 'a>> {
-    Box::pin(async move {
-        debug!(
-            component = "websocket",
-            event = "ws.message.received",
-            connection_id = conn_id,
-            message = ?msg,
-            "Received client message"
-        );
-
-        match msg {
-            // ── Subscribe ────────────────────────────────────────────
-            ClientMessage::SubscribeList
-            | ClientMessage::SubscribeSession { .. }
-            | ClientMessage::UnsubscribeSession { .. } => {
-                crate::ws_handlers::subscribe::handle(msg, client_tx, state, conn_id).await;
-            }
-
-            // ── Session CRUD ─────────────────────────────────────────
-            ClientMessage::CreateSession { .. }
-            | ClientMessage::EndSession { .. }
-            | ClientMessage::RenameSession { .. }
-            | ClientMessage::UpdateSessionConfig { .. }
-            | ClientMessage::ForkSession { .. }
-            | ClientMessage::ForkSessionToWorktree { .. }
-            | ClientMessage::ForkSessionToExistingWorktree { .. } => {
-                crate::ws_handlers::session_crud::handle(msg, client_tx, state, conn_id).await;
-            }
-
-            // ── Session lifecycle (resume / takeover) ────────────────
-            ClientMessage::ResumeSession { .. } | ClientMessage::TakeoverSession { .. } => {
-                crate::ws_handlers::session_lifecycle::handle(msg, client_tx, state, conn_id).await;
-            }
-
-            // ── Messaging ────────────────────────────────────────────
-            ClientMessage::SendMessage { .. }
-            | ClientMessage::SteerTurn { .. }
-            | ClientMessage::AnswerQuestion { .. }
-            | ClientMessage::InterruptSession { .. }
-            | ClientMessage::CompactContext { .. }
-            | ClientMessage::UndoLastTurn { .. }
-            | ClientMessage::RollbackTurns { .. }
-            | ClientMessage::StopTask { .. }
-            | ClientMessage::RewindFiles { .. } => {
-                crate::ws_handlers::messaging::handle(msg, client_tx, state, conn_id).await;
-            }
-
-            // ── Approvals ────────────────────────────────────────────
-            ClientMessage::ApproveTool { .. }
-            | ClientMessage::ListApprovals { .. }
-            | ClientMessage::DeleteApproval { .. } => {
-                crate::ws_handlers::approvals::handle(msg, client_tx, state, conn_id).await;
-            }
-
-            // ── Config (WS-only: SetClientPrimaryClaim) ────────────
-            ClientMessage::SetClientPrimaryClaim { .. } => {
-                crate::ws_handlers::config::handle(msg, client_tx, state, conn_id).await;
-            }
-
-            // ── Claude hooks ─────────────────────────────────────────
-            ClientMessage::ClaudeSessionStart { .. }
-            | ClientMessage::ClaudeSessionEnd { .. }
-            | ClientMessage::ClaudeStatusEvent { .. }
-            | ClientMessage::ClaudeToolEvent { .. }
-            | ClientMessage::ClaudeSubagentEvent { .. }
-            | ClientMessage::GetSubagentTools { .. } => {
-                crate::ws_handlers::claude_hooks::handle(msg, client_tx, state).await;
-            }
-
-            // ── Shell execution ──────────────────────────────────────
-            ClientMessage::ExecuteShell { .. } | ClientMessage::CancelShell { .. } => {
-                crate::ws_handlers::shell::handle(msg, client_tx, state, conn_id).await;
-            }
-
-            // ── REST-only stubs ──────────────────────────────────────
-            ClientMessage::BrowseDirectory { .. }
-            | ClientMessage::ListRecentProjects { .. }
-            | ClientMessage::CheckOpenAiKey { .. }
-            | ClientMessage::FetchCodexUsage { .. }
-            | ClientMessage::FetchClaudeUsage { .. }
-            | ClientMessage::SetServerRole { .. }
-            | ClientMessage::SetOpenAiKey { .. }
-            | ClientMessage::ListModels
-            | ClientMessage::ListClaudeModels
-            | ClientMessage::CodexAccountRead { .. }
-            | ClientMessage::CodexLoginChatgptStart
-            | ClientMessage::CodexLoginChatgptCancel { .. }
-            | ClientMessage::CodexAccountLogout
-            | ClientMessage::ListSkills { .. }
-            | ClientMessage::ListRemoteSkills { .. }
-            | ClientMessage::DownloadRemoteSkill { .. }
-            | ClientMessage::ListMcpTools { .. }
-            | ClientMessage::RefreshMcpServers { .. }
-            | ClientMessage::ListWorktrees { .. }
-            | ClientMessage::CreateWorktree { .. }
-            | ClientMessage::RemoveWorktree { .. }
-            | ClientMessage::DiscoverWorktrees { .. }
-            | ClientMessage::CreateReviewComment { .. }
-            | ClientMessage::UpdateReviewComment { .. }
-            | ClientMessage::DeleteReviewComment { .. }
-            | ClientMessage::ListReviewComments { .. } => {
-                crate::ws_handlers::rest_only::handle(msg, client_tx).await;
-            }
-        }
-    })
+) {
+    let ctx = HandlerContext {
+        client_tx,
+        state,
+        conn_id,
+    };
+    Box::pin(crate::ws_handlers::dispatch(msg, &ctx)).await;
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{handle_client_message, send_replay_or_snapshot_fallback, OutboundMessage};
+    use super::{
+        client_from_headers, handle_client_message, send_replay_or_snapshot_fallback,
+        OutboundMessage,
+    };
+    use crate::auth::AuthIdentity;
     use crate::claude_session::ClaudeAction;
     use crate::codex_session::CodexAction;
     use crate::normalization::work_status_for_approval_decision;
@@ -497,15 +596,17 @@ mod tests {
     use crate::snapshot_compaction::{
         compact_message_for_transport, compact_snapshot_to_transport_limit,
         replay_has_oversize_event, sanitize_replay_event_for_transport,
-        sanitize_server_message_for_transport, snapshot_transport_size_bytes,
+        sanitize_server_message_for_transport, snapshot_transport_size_bytes, SnapshotLimits,
         SNAPSHOT_MAX_CONTENT_CHARS, SNAPSHOT_TARGET_TEXT_MESSAGE_BYTES, WS_MAX_TEXT_MESSAGE_BYTES,
     };
-    use crate::state::SessionRegistry;
+    use crate::state::{ConnectionEntry, SessionRegistry};
     use crate::transition::Input;
+    use crate::ws_handlers::HandlerContext;
+    use axum::http::{header, HeaderMap};
     use orbitdock_protocol::{
         new_id, ApprovalType, ClaudeIntegrationMode, ClientMessage, CodexIntegrationMode,
         ImageInput, MentionInput, Message, MessageType, Provider, ServerMessage, SessionStatus,
-        TurnDiff, WorkStatus,
+        SessionVisibility, SnapshotOptions, SnapshotTruncation, TurnDiff, WorkStatus,
     };
     use std::sync::{Arc, Once};
     use tokio::sync::mpsc;
@@ -817,6 +918,7 @@ mod tests {
             Some(Some(CodexIntegrationMode::Direct))
         );
         assert_eq!(changes.claude_integration_mode, None);
+        assert_eq!(changes.steerable, Some(true));
     }
 
     #[test]
@@ -829,6 +931,7 @@ mod tests {
             Some(Some(ClaudeIntegrationMode::Direct))
         );
         assert_eq!(changes.codex_integration_mode, None);
+        assert_eq!(changes.steerable, Some(true));
     }
 
     #[test]
@@ -893,10 +996,12 @@ mod tests {
                 diff: "H".repeat(120_000),
                 token_usage: None,
                 snapshot_kind: None,
+                turn_usage: None,
+                summary: None,
             })
             .collect();
 
-        let compacted = compact_snapshot_to_transport_limit(snapshot);
+        let compacted = compact_snapshot_to_transport_limit(snapshot, &SnapshotLimits::default());
         let compacted_size =
             snapshot_transport_size_bytes(&compacted).expect("compacted snapshot serialized");
 
@@ -940,28 +1045,104 @@ mod tests {
                 diff: "old".to_string(),
                 token_usage: None,
                 snapshot_kind: None,
+                turn_usage: None,
+                summary: None,
             },
             TurnDiff {
                 turn_id: "turn-21".to_string(),
                 diff: "next".to_string(),
                 token_usage: None,
                 snapshot_kind: None,
+                turn_usage: None,
+                summary: None,
             },
             TurnDiff {
                 turn_id: "turn-20".to_string(),
                 diff: "new".to_string(),
                 token_usage: None,
                 snapshot_kind: None,
+                turn_usage: None,
+                summary: None,
             },
         ];
 
-        let compacted = compact_snapshot_to_transport_limit(snapshot);
+        let compacted = compact_snapshot_to_transport_limit(snapshot, &SnapshotLimits::default());
         assert_eq!(compacted.turn_diffs.len(), 2);
         assert_eq!(compacted.turn_diffs[0].turn_id, "turn-21");
         assert_eq!(compacted.turn_diffs[1].turn_id, "turn-20");
         assert_eq!(compacted.turn_diffs[1].diff, "new");
     }
 
+    #[test]
+    fn head_tail_snapshot_keeps_opening_prompts() {
+        let mut snapshot =
+            SessionHandle::new("head-tail".to_string(), Provider::Claude, "/tmp/ht".into()).state();
+        snapshot.messages = (0..50)
+            .map(|index| Message {
+                id: format!("m-{index}"),
+                session_id: snapshot.id.clone(),
+                sequence: Some(index as u64),
+                message_type: if index % 5 == 0 {
+                    MessageType::User
+                } else {
+                    MessageType::Assistant
+                },
+                content: format!("message {index}"),
+                tool_name: None,
+                tool_input: None,
+                tool_output: None,
+                is_error: false,
+                is_in_progress: false,
+                timestamp: "2026-01-01T00:00:00Z".to_string(),
+                duration_ms: None,
+                images: vec![],
+            })
+            .collect();
+
+        let limits = SnapshotLimits::default().with_options(Some(&SnapshotOptions {
+            max_messages: Some(12),
+            max_content_chars: None,
+            truncation: Some(SnapshotTruncation::HeadTail),
+        }));
+        let compacted = compact_snapshot_to_transport_limit(snapshot.clone(), &limits);
+        let ids: Vec<&str> = compacted.messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids.len(), 12);
+        assert_eq!(&ids[..3], ["m-0", "m-5", "m-10"]);
+        assert_eq!(ids[3], "m-41");
+        assert_eq!(ids[11], "m-49");
+        assert_eq!(compacted.oldest_sequence, Some(41));
+        assert_eq!(compacted.has_more_before, Some(true));
+
+        let again = compact_snapshot_to_transport_limit(compacted, &limits);
+        assert_eq!(again.oldest_sequence, Some(41));
+
+        let tail = compact_snapshot_to_transport_limit(
+            snapshot,
+            &SnapshotLimits::default().with_options(Some(&SnapshotOptions {
+                max_messages: Some(12),
+                ..Default::default()
+            })),
+        );
+        assert_eq!(tail.messages[0].id, "m-38");
+        assert_eq!(tail.oldest_sequence, Some(38));
+    }
+
+    #[test]
+    fn snapshot_options_are_clamped() {
+        let limits = SnapshotLimits::default().with_options(Some(&SnapshotOptions {
+            max_messages: Some(0),
+            max_content_chars: Some(u32::MAX),
+            truncation: None,
+        }));
+        assert_eq!(limits.max_messages, 1);
+        assert_eq!(limits.max_content_chars, 64_000);
+        assert_eq!(limits.truncation, SnapshotTruncation::Tail);
+        assert_eq!(
+            SnapshotLimits::default().with_options(None),
+            SnapshotLimits::default()
+        );
+    }
+
     #[test]
     fn compact_message_transport_preserves_data_uri_images() {
         let mut message = Message {
@@ -1129,6 +1310,14 @@ mod tests {
         Arc::new(SessionRegistry::new(persist_tx))
     }
 
+    /// Wait for the actor to work through the commands a hook queued. Hooks
+    /// don't wait on the actor, so its snapshot can otherwise lag behind.
+    async fn settle(actor: &crate::session_actor::SessionActorHandle) {
+        let (reply, rx) = tokio::sync::oneshot::channel();
+        actor.send(SessionCommand::GetSummary { reply }).await;
+        let _ = rx.await;
+    }
+
     #[tokio::test]
     async fn claim_codex_thread_ends_shadow_runtime_session_and_persists_cleanup() {
         ensure_test_data_dir();
@@ -1210,11 +1399,549 @@ mod tests {
     async fn recv_json(client_rx: &mut mpsc::Receiver<OutboundMessage>) -> ServerMessage {
         match client_rx.recv().await.expect("expected outbound message") {
             OutboundMessage::Json(msg) => msg,
+            OutboundMessage::Shared(json) => {
+                serde_json::from_str(&json).expect("shared payload is a server message")
+            }
             OutboundMessage::Raw(_) => panic!("expected JSON message, got raw payload"),
             OutboundMessage::Pong(_) => panic!("expected JSON message, got pong"),
+            OutboundMessage::Close => panic!("expected JSON message, got close"),
+        }
+    }
+
+    fn connection_entry(client_name: Option<&str>) -> ConnectionEntry {
+        ConnectionEntry {
+            identity: AuthIdentity::Anonymous,
+            remote_addr: Some("100.64.0.2:51234".parse().unwrap()),
+            client_name: client_name.map(str::to_string),
+            client_version: None,
+            connected_at: "1700000000Z".to_string(),
+            subscribed_to_list: false,
+            subscriptions: Default::default(),
+            composing: Default::default(),
+            disconnect: Arc::new(tokio::sync::Notify::new()),
+        }
+    }
+
+    #[test]
+    fn client_name_and_version_come_from_client_header_or_user_agent() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_from_headers(&headers), (None, None));
+
+        headers.insert(
+            header::USER_AGENT,
+            "OrbitDock/42 CFNetwork/1490".parse().unwrap(),
+        );
+        assert_eq!(
+            client_from_headers(&headers),
+            (Some("OrbitDock".to_string()), Some("42".to_string()))
+        );
+
+        headers.insert("x-orbitdock-client", "orbitdock-cli/0.3.0".parse().unwrap());
+        assert_eq!(
+            client_from_headers(&headers),
+            (Some("orbitdock-cli".to_string()), Some("0.3.0".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn list_and_disconnect_connections() {
+        let state = new_test_state();
+        let (client_tx, mut client_rx) = mpsc::channel::<OutboundMessage>(8);
+        state.register_connection(1, connection_entry(Some("orbitdock-cli")));
+        let target = connection_entry(Some("OrbitDock-iOS"));
+        let target_disconnect = target.disconnect.clone();
+        state.register_connection(2, target);
+        state.note_list_subscription(2);
+        state.note_session_subscription(2, "sess-1", true);
+
+        handle_client_message(ClientMessage::ListConnections, &client_tx, &state, 1).await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::ConnectionsList { connections } => {
+                assert_eq!(connections.len(), 2);
+                assert_eq!(connections[1].connection_id, 2);
+                assert_eq!(connections[1].client_name.as_deref(), Some("OrbitDock-iOS"));
+                assert_eq!(
+                    connections[1].remote_addr.as_deref(),
+                    Some("100.64.0.2:51234")
+                );
+                assert!(connections[1].subscribed_to_list);
+                assert_eq!(connections[1].subscriptions, vec!["sess-1".to_string()]);
+            }
+            other => panic!("expected ConnectionsList, got {other:?}"),
+        }
+
+        handle_client_message(
+            ClientMessage::DisconnectConnection { connection_id: 2 },
+            &client_tx,
+            &state,
+            1,
+        )
+        .await;
+        tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            target_disconnect.notified(),
+        )
+        .await
+        .expect("target connection should be signalled");
+        match recv_json(&mut client_rx).await {
+            ServerMessage::ConnectionsList { connections } => {
+                assert_eq!(connections.len(), 1);
+                assert_eq!(connections[0].connection_id, 1);
+            }
+            other => panic!("expected ConnectionsList, got {other:?}"),
+        }
+
+        handle_client_message(
+            ClientMessage::DisconnectConnection { connection_id: 99 },
+            &client_tx,
+            &state,
+            1,
+        )
+        .await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::Error { code, .. } => assert_eq!(code, "not_found"),
+            other => panic!("expected not_found error, got {other:?}"),
         }
     }
 
+    #[tokio::test]
+    async fn command_handlers_run_directly_with_a_context() {
+        let state = new_test_state();
+        let (client_tx, mut client_rx) = mpsc::channel::<OutboundMessage>(8);
+        state.register_connection(1, connection_entry(Some("orbitdock-cli")));
+        state.register_connection(2, connection_entry(Some("OrbitDock-iOS")));
+        let ctx = HandlerContext {
+            client_tx: &client_tx,
+            state: &state,
+            conn_id: 1,
+        };
+
+        crate::ws_handlers::connections::list_connections(&ctx).await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::ConnectionsList { connections } => assert_eq!(connections.len(), 2),
+            other => panic!("expected ConnectionsList, got {other:?}"),
+        }
+
+        crate::ws_handlers::connections::disconnect_connection(&ctx, 99).await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::Error { code, .. } => assert_eq!(code, "not_found"),
+            other => panic!("expected not_found error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn shutdown_server_requests_graceful_shutdown() {
+        let state = new_test_state();
+        let (client_tx, mut client_rx) = mpsc::channel::<OutboundMessage>(8);
+        state.register_connection(1, connection_entry(Some("orbitdock-cli")));
+
+        handle_client_message(ClientMessage::ShutdownServer, &client_tx, &state, 1).await;
+        assert!(matches!(
+            recv_json(&mut client_rx).await,
+            ServerMessage::ServerShuttingDown
+        ));
+        tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            state.shutdown_requested(),
+        )
+        .await
+        .expect("shutdown should be requested");
+    }
+
+    #[tokio::test]
+    async fn viewers_follow_subscriptions_and_composing() {
+        let state = new_test_state();
+        let (client_tx, _client_rx) = mpsc::channel::<OutboundMessage>(8);
+        state.register_connection(1, connection_entry(Some("OrbitDock")));
+        state.register_connection(2, connection_entry(Some("orbitdock-cli")));
+        state.note_session_subscription(1, "sess-1", true);
+
+        // Only subscribers can be composing.
+        handle_client_message(
+            ClientMessage::SetComposing {
+                session_id: "sess-1".to_string(),
+                composing: true,
+            },
+            &client_tx,
+            &state,
+            2,
+        )
+        .await;
+        state.note_session_subscription(2, "sess-1", true);
+        let viewers = state.session_viewers("sess-1");
+        assert_eq!(viewers.len(), 2);
+        assert!(viewers.iter().all(|viewer| !viewer.composing));
+
+        handle_client_message(
+            ClientMessage::SetComposing {
+                session_id: "sess-1".to_string(),
+                composing: true,
+            },
+            &client_tx,
+            &state,
+            2,
+        )
+        .await;
+        let viewers = state.session_viewers("sess-1");
+        assert_eq!(viewers[1].client_name.as_deref(), Some("orbitdock-cli"));
+        assert!(viewers[1].composing);
+        assert!(!viewers[0].composing);
+
+        state.note_session_subscription(2, "sess-1", false);
+        assert!(!state.set_composing(2, "sess-1", false));
+        assert_eq!(state.remove_connection(1).len(), 1);
+        assert!(state.session_viewers("sess-1").is_empty());
+    }
+
+    #[tokio::test]
+    async fn private_sessions_are_hidden_until_shared() {
+        let state = new_test_state();
+        let session_id = format!("od-{}", orbitdock_protocol::new_id());
+        let mut handle = SessionHandle::new(
+            session_id.clone(),
+            Provider::Codex,
+            "/tmp/project".to_string(),
+        );
+        handle.set_created_by(Some("ana".to_string()));
+        state.add_session(handle);
+
+        let user_entry = |user: &str| ConnectionEntry {
+            identity: AuthIdentity::Token {
+                id: format!("tok-{user}"),
+                user: Some(user.to_string()),
+                scope: crate::scopes::TokenScope::Admin,
+            },
+            ..connection_entry(None)
+        };
+        state.register_connection(1, user_entry("sam"));
+        state.register_connection(2, user_entry("ana"));
+        let (client_tx, mut client_rx) = mpsc::channel::<OutboundMessage>(16);
+        let list_contains_session = |sessions: &[orbitdock_protocol::SessionSummary]| {
+            sessions.iter().any(|session| session.id == session_id)
+        };
+
+        handle_client_message(ClientMessage::SubscribeList, &client_tx, &state, 1).await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::SessionsList { sessions } => {
+                assert!(!list_contains_session(&sessions))
+            }
+            other => panic!("expected SessionsList, got {other:?}"),
+        }
+
+        handle_client_message(
+            ClientMessage::SendMessage {
+                session_id: session_id.clone(),
+                content: "hi".to_string(),
+                model: None,
+                effort: None,
+                skills: vec![],
+                images: vec![],
+                mentions: vec![],
+                attachment_ids: vec![],
+            },
+            &client_tx,
+            &state,
+            1,
+        )
+        .await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::Error { code, .. } => assert_eq!(code, "forbidden"),
+            other => panic!("expected forbidden error, got {other:?}"),
+        }
+
+        handle_client_message(
+            ClientMessage::ShareSession {
+                session_id: session_id.clone(),
+                visibility: SessionVisibility::Private,
+                users: vec!["sam".to_string()],
+            },
+            &client_tx,
+            &state,
+            2,
+        )
+        .await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::SessionSharing {
+                owner, shared_with, ..
+            } => {
+                assert_eq!(owner.as_deref(), Some("ana"));
+                assert_eq!(shared_with, vec!["sam".to_string()]);
+            }
+            other => panic!("expected SessionSharing, got {other:?}"),
+        }
+
+        handle_client_message(ClientMessage::SubscribeList, &client_tx, &state, 1).await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::SessionsList { sessions } => {
+                assert!(list_contains_session(&sessions))
+            }
+            other => panic!("expected SessionsList, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn unfiltered_approval_and_audit_lists_skip_other_users_private_sessions() {
+        let state = new_test_state();
+        let private_id = format!("od-{}", orbitdock_protocol::new_id());
+        let mut handle = SessionHandle::new(
+            private_id.clone(),
+            Provider::Codex,
+            "/tmp/project".to_string(),
+        );
+        handle.set_created_by(Some("ana".to_string()));
+        state.add_session(handle);
+        let own_id = format!("od-{}", orbitdock_protocol::new_id());
+        let mut handle =
+            SessionHandle::new(own_id.clone(), Provider::Codex, "/tmp/project".to_string());
+        handle.set_created_by(Some("sam".to_string()));
+        state.add_session(handle);
+
+        let sam = AuthIdentity::Token {
+            id: "tok-sam".to_string(),
+            user: Some("sam".to_string()),
+            scope: crate::scopes::TokenScope::Read,
+        };
+        let approval = |id: i64, session_id: &str| -> orbitdock_protocol::ApprovalHistoryItem {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "session_id": session_id,
+                "request_id": format!("req-{id}"),
+                "approval_type": "exec",
+                "command": "cat ~/.aws/credentials",
+                "created_at": "2026-01-01T00:00:00Z",
+            }))
+            .expect("approval row")
+        };
+
+        // GET /api/approvals without session_id
+        let approvals = vec![approval(1, &private_id), approval(2, &own_id)];
+        let visible = state.visible_approvals(&sam, approvals.clone()).await;
+        assert_eq!(
+            visible.iter().map(|row| row.id).collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(
+            state
+                .visible_approvals(&AuthIdentity::Anonymous, approvals)
+                .await
+                .len(),
+            2
+        );
+
+        // get_audit_log without session_id
+        let entry =
+            |id: i64, session_id: Option<&str>, user: &str| orbitdock_protocol::AuditLogEntry {
+                id,
+                created_at: "2026-01-01T00:00:00Z".to_string(),
+                connection_id: 1,
+                identity: format!("token:tok-{user}"),
+                user: Some(user.to_string()),
+                action: "send_message".to_string(),
+                session_id: session_id.map(str::to_string),
+                details: serde_json::Value::Null,
+            };
+        let entries = vec![
+            entry(1, Some(&private_id), "ana"),
+            entry(2, Some(&own_id), "sam"),
+            entry(3, None, "ana"),
+            entry(4, None, "sam"),
+        ];
+        let visible = state.visible_audit_entries(&sam, entries).await;
+        assert_eq!(
+            visible.iter().map(|entry| entry.id).collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+
+        // Pages keep coming until the user has a full page of their own rows.
+        let table: Vec<_> = (1..=8)
+            .rev()
+            .map(|id| {
+                let session_id = if id % 4 == 0 { &own_id } else { &private_id };
+                entry(id, Some(session_id), "sam")
+            })
+            .collect();
+        let fetch = |before_id: Option<i64>| {
+            let page: Vec<_> = table
+                .iter()
+                .filter(|entry| before_id.is_none_or(|before| entry.id < before))
+                .take(2)
+                .cloned()
+                .collect();
+            async move { Ok(page) }
+        };
+        let page = crate::state::collect_visible_pages(
+            2,
+            None,
+            |entry: &orbitdock_protocol::AuditLogEntry| entry.id,
+            fetch,
+            |page| state.visible_audit_entries(&sam, page),
+        )
+        .await
+        .expect("collect pages");
+        assert_eq!(
+            page.iter().map(|entry| entry.id).collect::<Vec<_>>(),
+            vec![8, 4]
+        );
+        let page = crate::state::collect_visible_pages(
+            2,
+            Some(4),
+            |entry: &orbitdock_protocol::AuditLogEntry| entry.id,
+            fetch,
+            |page| state.visible_audit_entries(&sam, page),
+        )
+        .await
+        .expect("collect pages");
+        assert!(page.is_empty());
+    }
+
+    #[tokio::test]
+    async fn attention_items_are_limited_to_accessible_sessions() {
+        let state = new_test_state();
+        let session_id = format!("od-{}", orbitdock_protocol::new_id());
+        let mut handle = SessionHandle::new(
+            session_id.clone(),
+            Provider::Codex,
+            "/tmp/project".to_string(),
+        );
+        handle.set_created_by(Some("ana".to_string()));
+        handle.set_work_status(WorkStatus::Permission);
+        state.add_session(handle);
+        crate::attention::refresh(&state);
+
+        let user_entry = |user: &str| ConnectionEntry {
+            identity: AuthIdentity::Token {
+                id: format!("tok-{user}"),
+                user: Some(user.to_string()),
+                scope: crate::scopes::TokenScope::Admin,
+            },
+            ..connection_entry(None)
+        };
+        state.register_connection(1, user_entry("sam"));
+        state.register_connection(2, user_entry("ana"));
+        let (client_tx, mut client_rx) = mpsc::channel::<OutboundMessage>(16);
+        let request = || ClientMessage::GetAttentionItems {
+            request_id: "req".to_string(),
+        };
+
+        handle_client_message(request(), &client_tx, &state, 2).await;
+        let item_id = match recv_json(&mut client_rx).await {
+            ServerMessage::AttentionItems { items, .. } => {
+                assert_eq!(items.len(), 1);
+                items[0].id.clone()
+            }
+            other => panic!("expected AttentionItems, got {other:?}"),
+        };
+
+        handle_client_message(request(), &client_tx, &state, 1).await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::AttentionItems { items, .. } => assert!(items.is_empty()),
+            other => panic!("expected AttentionItems, got {other:?}"),
+        }
+
+        let sam = state.connection_identity(1);
+        let broadcast = ServerMessage::AttentionChanged {
+            items: crate::attention::items(&state),
+        };
+        match state.list_message_for(&sam, broadcast) {
+            Some(ServerMessage::AttentionChanged { items }) => assert!(items.is_empty()),
+            other => panic!("expected AttentionChanged, got {other:?}"),
+        }
+
+        handle_client_message(
+            ClientMessage::AcknowledgeAttention {
+                item_id: item_id.clone(),
+            },
+            &client_tx,
+            &state,
+            1,
+        )
+        .await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::Error { code, .. } => assert_eq!(code, "not_found"),
+            other => panic!("expected not_found error, got {other:?}"),
+        }
+        assert_eq!(crate::attention::items(&state).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn resync_sessions_is_limited_to_accessible_sessions() {
+        let state = new_test_state();
+        let session_id = format!("od-{}", orbitdock_protocol::new_id());
+        let mut handle = SessionHandle::new(
+            session_id.clone(),
+            Provider::Codex,
+            "/tmp/project".to_string(),
+        );
+        handle.set_created_by(Some("ana".to_string()));
+        handle.set_transcript_path(Some("/tmp/orbitdock-missing-rollout.jsonl".to_string()));
+        state.add_session(handle);
+
+        let user_entry = |user: &str| ConnectionEntry {
+            identity: AuthIdentity::Token {
+                id: format!("tok-{user}"),
+                user: Some(user.to_string()),
+                scope: crate::scopes::TokenScope::Admin,
+            },
+            ..connection_entry(None)
+        };
+        state.register_connection(1, user_entry("sam"));
+        state.register_connection(2, user_entry("ana"));
+        let (client_tx, mut client_rx) = mpsc::channel::<OutboundMessage>(16);
+        let request = |session_ids: Vec<String>| ClientMessage::ResyncSessions {
+            request_id: "req".to_string(),
+            session_ids,
+        };
+
+        handle_client_message(request(vec![session_id.clone()]), &client_tx, &state, 1).await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::Error {
+                code,
+                session_id: denied,
+                ..
+            } => {
+                assert_eq!(code, "forbidden");
+                assert_eq!(denied.as_deref(), Some(session_id.as_str()));
+            }
+            other => panic!("expected forbidden error, got {other:?}"),
+        }
+
+        handle_client_message(request(Vec::new()), &client_tx, &state, 1).await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::SessionsResynced { results, .. } => assert!(results.is_empty()),
+            other => panic!("expected SessionsResynced, got {other:?}"),
+        }
+
+        handle_client_message(request(Vec::new()), &client_tx, &state, 2).await;
+        match recv_json(&mut client_rx).await {
+            ServerMessage::SessionsResynced { results, .. } => {
+                assert_eq!(results.len(), 1);
+                assert_eq!(results[0].session_id, session_id);
+            }
+            other => panic!("expected SessionsResynced, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn ended_claude_sessions_stop_being_tracked_as_hook_fed() {
+        let state = new_test_state();
+        let window = std::time::Duration::from_secs(60);
+
+        state.note_claude_hook("claude-ended");
+        crate::hook_handler::handle_hook_message(
+            ClientMessage::ClaudeSessionEnd {
+                session_id: "claude-ended".to_string(),
+                reason: None,
+            },
+            &state,
+        )
+        .await;
+        assert!(!state.claude_hook_seen_within("claude-ended", window));
+
+        state.note_claude_hook("claude-removed");
+        state.remove_session("claude-removed");
+        assert!(!state.claude_hook_seen_within("claude-removed", window));
+    }
+
     #[tokio::test]
     async fn oversized_replay_requests_rebootstrap_error_instead_of_snapshot() {
         let (client_tx, mut client_rx) = mpsc::channel::<OutboundMessage>(4);
@@ -1232,6 +1959,7 @@ mod tests {
                 code,
                 message,
                 session_id,
+                ..
             } => {
                 assert_eq!(code, "replay_oversized");
                 assert!(message.contains("re-bootstrap"));
@@ -1258,6 +1986,7 @@ mod tests {
                 session_id: session_id.clone(),
                 since_revision: None,
                 include_snapshot: false,
+                snapshot: None,
             },
             &client_tx,
             &state,
@@ -1323,6 +2052,7 @@ mod tests {
                 code,
                 message,
                 session_id,
+                ..
             } => {
                 assert_eq!(code, "http_only_endpoint");
                 assert!(message.contains("GET /api/server/openai-key"));
@@ -1352,6 +2082,7 @@ mod tests {
                 code,
                 message,
                 session_id,
+                ..
             } => {
                 assert_eq!(code, "http_only_endpoint");
                 assert!(message.contains("GET /api/fs/recent-projects"));
@@ -1382,6 +2113,7 @@ mod tests {
                 code,
                 message,
                 session_id,
+                ..
             } => {
                 assert_eq!(code, "http_only_endpoint");
                 assert!(message.contains("GET /api/fs/browse"));
@@ -1412,6 +2144,7 @@ mod tests {
                 code,
                 message,
                 session_id,
+                ..
             } => {
                 assert_eq!(code, "http_only_endpoint");
                 assert!(message.contains("GET /api/fs/browse"));
@@ -1518,6 +2251,7 @@ mod tests {
                 code,
                 message,
                 session_id,
+                ..
             } => {
                 assert_eq!(code, "http_only_endpoint");
                 assert!(message.contains("GET /api/usage/codex"));
@@ -1547,6 +2281,7 @@ mod tests {
                 code,
                 message,
                 session_id,
+                ..
             } => {
                 assert_eq!(code, "http_only_endpoint");
                 assert!(message.contains("GET /api/usage/claude"));
@@ -1676,6 +2411,7 @@ mod tests {
         let actor = state
             .get_session(&session_id)
             .expect("session should exist");
+        settle(&actor).await;
         let before = actor.snapshot();
         assert!(
             before.pending_approval_id.is_some(),
@@ -1702,9 +2438,7 @@ mod tests {
         )
         .await;
 
-        tokio::task::yield_now().await;
-        tokio::task::yield_now().await;
-
+        settle(&actor).await;
         let after = actor.snapshot();
         assert_eq!(
             after.pending_approval_id, None,
@@ -1747,6 +2481,7 @@ mod tests {
         let actor = state
             .get_session(&session_id)
             .expect("session should exist");
+        settle(&actor).await;
         let before = actor.snapshot();
         assert_eq!(
             before.pending_approval_id.as_deref(),
@@ -1774,9 +2509,7 @@ mod tests {
         )
         .await;
 
-        tokio::task::yield_now().await;
-        tokio::task::yield_now().await;
-
+        settle(&actor).await;
         let after = actor.snapshot();
         assert_eq!(
             after.pending_approval_id.as_deref(),
@@ -1861,6 +2594,7 @@ mod tests {
                 skills: vec![],
                 images: vec![],
                 mentions: vec![],
+                attachment_ids: vec![],
             },
             &client_tx,
             &state,
@@ -1878,6 +2612,7 @@ mod tests {
                 skills: vec![],
                 images: vec![],
                 mentions: vec![],
+                attachment_ids: vec![],
             },
             &client_tx,
             &state,
@@ -1930,6 +2665,7 @@ mod tests {
                 skills: vec![],
                 images: vec![],
                 mentions: vec![],
+                attachment_ids: vec![],
             },
             &client_tx,
             &state,
@@ -1974,6 +2710,7 @@ mod tests {
                     value: "data:image/png;base64,aGVsbG8=".to_string(),
                 }],
                 mentions: vec![],
+                attachment_ids: vec![],
             },
             &client_tx,
             &state,
@@ -2020,6 +2757,7 @@ mod tests {
                     value: "data:image/png;base64,aGVsbG8=".to_string(),
                 }],
                 mentions: vec![],
+                attachment_ids: vec![],
             },
             &client_tx,
             &state,
@@ -2066,6 +2804,7 @@ mod tests {
                     name: "main.rs".to_string(),
                     path: "/project/src/main.rs".to_string(),
                 }],
+                attachment_ids: vec![],
             },
             &client_tx,
             &state,
@@ -2114,6 +2853,7 @@ mod tests {
                     value: "data:image/png;base64,aGVsbG8=".to_string(),
                 }],
                 mentions: vec![],
+                attachment_ids: vec![],
             },
             &client_tx,
             &state,
@@ -2132,6 +2872,150 @@ mod tests {
         }
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn send_message_dispatches_attachments_to_claude_connector() {
+        ensure_test_data_dir();
+        let state = new_test_state();
+        let (client_tx, _client_rx) = mpsc::channel::<OutboundMessage>(16);
+        let session_id = "send-message-attachments-claude".to_string();
+        let (action_tx, mut action_rx) = mpsc::channel(8);
+
+        {
+            state.add_session(SessionHandle::new(
+                session_id.clone(),
+                Provider::Claude,
+                "/Users/tester/repo".to_string(),
+            ));
+            state.set_claude_action_tx(&session_id, action_tx);
+        }
+        let uploaded = crate::attachments::store(&session_id, "build.log", b"error: E0063\n")
+            .expect("store attachment");
+
+        handle_client_message(
+            ClientMessage::SendMessage {
+                session_id: session_id.clone(),
+                content: "why did this fail?".to_string(),
+                model: None,
+                effort: None,
+                skills: vec![],
+                images: vec![],
+                mentions: vec![],
+                attachment_ids: vec![uploaded.id.clone()],
+            },
+            &client_tx,
+            &state,
+            1,
+        )
+        .await;
+
+        let action = action_rx.recv().await.expect("expected claude action");
+        match action {
+            ClaudeAction::SendMessage { attachments, .. } => {
+                assert_eq!(attachments.len(), 1);
+                assert_eq!(attachments[0].name, "build.log");
+                assert_eq!(attachments[0].mime_type, "text/plain");
+                assert_eq!(
+                    std::fs::read(&attachments[0].path).expect("read attachment"),
+                    b"error: E0063\n"
+                );
+            }
+            other => panic!("expected SendMessage action, got {:?}", other),
+        }
+        let (recorded, _) =
+            crate::attachments::load(&session_id, &uploaded.id).expect("load attachment");
+        assert!(recorded
+            .message_id
+            .is_some_and(|id| id.starts_with("user-ws-")));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn send_message_with_attachments_is_refused_for_codex() {
+        ensure_test_data_dir();
+        let state = new_test_state();
+        let (client_tx, mut client_rx) = mpsc::channel::<OutboundMessage>(16);
+        let session_id = "send-message-attachments-codex".to_string();
+        let (action_tx, mut action_rx) = mpsc::channel(8);
+
+        {
+            state.add_session(SessionHandle::new(
+                session_id.clone(),
+                Provider::Codex,
+                "/Users/tester/repo".to_string(),
+            ));
+            state.set_codex_action_tx(&session_id, action_tx);
+        }
+        let uploaded = crate::attachments::store(&session_id, "build.log", b"error: E0063\n")
+            .expect("store attachment");
+
+        handle_client_message(
+            ClientMessage::SendMessage {
+                session_id,
+                content: "read this log".to_string(),
+                model: None,
+                effort: None,
+                skills: vec![],
+                images: vec![],
+                mentions: vec![],
+                attachment_ids: vec![uploaded.id],
+            },
+            &client_tx,
+            &state,
+            1,
+        )
+        .await;
+
+        match recv_json(&mut client_rx).await {
+            ServerMessage::Error { code, .. } => assert_eq!(code, "attachments_unsupported"),
+            other => panic!("expected error, got {:?}", other),
+        }
+        assert!(action_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn voice_prompts_need_transcription_but_not_file_support() {
+        ensure_test_data_dir();
+        let state = new_test_state();
+        let (client_tx, mut client_rx) = mpsc::channel::<OutboundMessage>(16);
+        let session_id = "send-message-voice-codex".to_string();
+        let (action_tx, mut action_rx) = mpsc::channel(8);
+
+        {
+            state.add_session(SessionHandle::new(
+                session_id.clone(),
+                Provider::Codex,
+                "/Users/tester/repo".to_string(),
+            ));
+            state.set_codex_action_tx(&session_id, action_tx);
+        }
+        let uploaded =
+            crate::attachments::store(&session_id, "memo.wav", b"RIFF\x24\0\0\0WAVEfmt ")
+                .expect("store attachment");
+        assert_eq!(uploaded.mime_type, "audio/wav");
+
+        handle_client_message(
+            ClientMessage::SendMessage {
+                session_id,
+                content: String::new(),
+                model: None,
+                effort: None,
+                skills: vec![],
+                images: vec![],
+                mentions: vec![],
+                attachment_ids: vec![uploaded.id],
+            },
+            &client_tx,
+            &state,
+            1,
+        )
+        .await;
+
+        match recv_json(&mut client_rx).await {
+            ServerMessage::Error { code, .. } => assert_eq!(code, "transcription_unavailable"),
+            other => panic!("expected error, got {:?}", other),
+        }
+        assert!(action_rx.try_recv().is_err());
+    }
+
     #[tokio::test]
     async fn send_message_without_effort_preserves_existing_effort() {
         let state = new_test_state();
@@ -2162,6 +3046,7 @@ mod tests {
                 skills: vec![],
                 images: vec![],
                 mentions: vec![],
+                attachment_ids: vec![],
             },
             &client_tx,
             &state,
@@ -2205,6 +3090,7 @@ mod tests {
                 skills: vec![],
                 images: vec![],
                 mentions: vec![],
+                attachment_ids: vec![],
             },
             &client_tx,
             &state,
@@ -2252,6 +3138,7 @@ mod tests {
                 skills: vec![],
                 images: vec![],
                 mentions: vec![],
+                attachment_ids: vec![],
             },
             &client_tx,
             &state,
@@ -2305,6 +3192,7 @@ mod tests {
                 skills: vec![],
                 images: vec![],
                 mentions: vec![],
+                attachment_ids: vec![],
             },
             &client_tx,
             &state,
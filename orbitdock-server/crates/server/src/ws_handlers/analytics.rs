@@ -0,0 +1,49 @@
+use orbitdock_protocol::{ClientMessage, ServerMessage, UsageGroupBy, UsageRange};
+
+use crate::persistence::load_usage_analytics;
+use crate::websocket::send_json;
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    match msg {
+        ClientMessage::GetUsageAnalytics { group_by, range } => {
+            get_usage_analytics(ctx, group_by, range).await
+        }
+
+        _ => {
+            tracing::warn!(?msg, "analytics::handle called with unexpected variant");
+        }
+    }
+}
+
+pub(crate) async fn get_usage_analytics(
+    ctx: &HandlerContext<'_>,
+    group_by: UsageGroupBy,
+    range: UsageRange,
+) {
+    match load_usage_analytics(group_by, range.clone()).await {
+        Ok(rows) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::UsageAnalytics {
+                    group_by,
+                    range,
+                    rows,
+                },
+            )
+            .await;
+        }
+        Err(e) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "db_error".to_string(),
+                    message: e.to_string(),
+                    session_id: None,
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}
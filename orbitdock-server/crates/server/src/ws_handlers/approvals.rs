@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
-use tokio::sync::{mpsc, oneshot};
+use serde_json::Value;
+use tokio::sync::oneshot;
 use tracing::info;
 
 use crate::claude_session::ClaudeAction;
@@ -8,17 +9,14 @@ use crate::codex_session::CodexAction;
 use crate::normalization::work_status_for_approval_decision;
 use crate::persistence::PersistCommand;
 use crate::session_command::SessionCommand;
+use crate::shell_policy::{audit_denied, is_approving_decision, ShellPolicyDenial};
 use crate::state::SessionRegistry;
-use crate::websocket::{send_json, send_rest_only_error, OutboundMessage};
+use crate::websocket::{send_json, send_rest_only_error};
+use crate::ws_handlers::HandlerContext;
 use orbitdock_protocol::ClientMessage;
 use orbitdock_protocol::ServerMessage;
 
-pub(crate) async fn handle(
-    msg: ClientMessage,
-    client_tx: &mpsc::Sender<OutboundMessage>,
-    state: &Arc<SessionRegistry>,
-    conn_id: u64,
-) {
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
     match msg {
         ClientMessage::ApproveTool {
             session_id,
@@ -28,154 +26,229 @@ pub(crate) async fn handle(
             interrupt,
             updated_input,
         } => {
-            info!(
-                component = "approval",
-                event = "approval.decision.received",
-                connection_id = conn_id,
-                session_id = %session_id,
-                request_id = %request_id,
-                decision = %decision,
-                "Approval decision received"
-            );
-
-            let fallback_work_status = work_status_for_approval_decision(&decision);
-            let mut resolved_work_status = fallback_work_status;
-
-            // Resolve pending approval server-side and promote next queued request.
-            // This keeps queue ownership inside the session actor.
-            let (approval_type, proposed_amendment, next_pending_request_id, approval_version) =
-                if let Some(actor) = state.get_session(&session_id) {
-                    let (reply_tx, reply_rx) = oneshot::channel();
-                    actor
-                        .send(SessionCommand::ResolvePendingApproval {
-                            request_id: request_id.clone(),
-                            fallback_work_status,
-                            reply: reply_tx,
-                        })
-                        .await;
-
-                    if let Ok(resolution) = reply_rx.await {
-                        resolved_work_status = resolution.work_status;
-                        (
-                            resolution.approval_type,
-                            resolution.proposed_amendment,
-                            resolution.next_pending_approval.map(|approval| approval.id),
-                            resolution.approval_version,
-                        )
-                    } else {
-                        (None, None, None, 0)
-                    }
-                } else {
-                    (None, None, None, 0)
-                };
-
-            if state.get_session(&session_id).is_some() && approval_type.is_none() {
-                send_json(
-                    client_tx,
-                    ServerMessage::ApprovalDecisionResult {
-                        session_id: session_id.clone(),
-                        request_id: request_id.clone(),
-                        outcome: "stale".to_string(),
-                        active_request_id: next_pending_request_id.clone(),
-                        approval_version,
-                    },
-                )
-                .await;
-                return;
-            }
-
-            let request_id_for_result = request_id.clone();
+            approve_tool(
+                ctx,
+                session_id,
+                request_id,
+                decision,
+                message,
+                interrupt,
+                updated_input,
+            )
+            .await
+        }
 
-            let _ = state
-                .persist()
-                .send(PersistCommand::ApprovalDecision {
-                    session_id: session_id.clone(),
-                    request_id: request_id.clone(),
-                    decision: decision.clone(),
-                })
-                .await;
+        ClientMessage::ListApprovals { session_id, .. } => list_approvals(ctx, session_id).await,
 
-            if let Some(tx) = state.get_codex_action_tx(&session_id) {
-                let action = match approval_type {
-                    Some(orbitdock_protocol::ApprovalType::Patch) => {
-                        info!(
-                            component = "approval",
-                            event = "approval.dispatch.patch",
-                            connection_id = conn_id,
-                            session_id = %session_id,
-                            request_id = %request_id,
-                            "Dispatching patch approval"
-                        );
-                        CodexAction::ApprovePatch {
-                            request_id,
-                            decision: decision.clone(),
-                        }
-                    }
-                    _ => {
-                        // Default to exec for exec and unknown types
-                        CodexAction::ApproveExec {
-                            request_id,
-                            decision: decision.clone(),
-                            proposed_amendment,
-                        }
-                    }
-                };
-                let _ = tx.send(action).await;
-            } else if let Some(tx) = state.get_claude_action_tx(&session_id) {
-                let _ = tx
-                    .send(ClaudeAction::ApproveTool {
-                        request_id,
-                        decision: decision.clone(),
-                        message,
-                        interrupt,
-                        updated_input,
-                    })
-                    .await;
-            }
+        ClientMessage::DeleteApproval { .. } => delete_approval(ctx).await,
 
-            let _ = state
-                .persist()
-                .send(PersistCommand::SessionUpdate {
-                    id: session_id.clone(),
-                    status: None,
-                    work_status: Some(resolved_work_status),
-                    last_activity_at: None,
-                })
-                .await;
+        _ => {
+            tracing::warn!(?msg, "approvals::handle called with unexpected variant");
+        }
+    }
+}
 
+pub(crate) async fn approve_tool(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    request_id: String,
+    decision: String,
+    message: Option<String>,
+    interrupt: Option<bool>,
+    updated_input: Option<Value>,
+) {
+    info!(
+        component = "approval",
+        event = "approval.decision.received",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        request_id = %request_id,
+        decision = %decision,
+        "Approval decision received"
+    );
+
+    if ctx.state.shell_policy().applies_to_agents() && is_approving_decision(&decision) {
+        if let Some(denial) =
+            denied_exec_approval(ctx.state, &session_id, &request_id, ctx.conn_id).await
+        {
             send_json(
-                client_tx,
-                ServerMessage::ApprovalDecisionResult {
-                    session_id: session_id.clone(),
-                    request_id: request_id_for_result,
-                    outcome: "applied".to_string(),
-                    active_request_id: next_pending_request_id.clone(),
-                    approval_version,
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "shell_command_not_allowed".to_string(),
+                    message: format!("Cannot approve exec request: {denial}"),
+                    session_id: Some(session_id),
+                    error: None,
                 },
             )
             .await;
+            return;
+        }
+    }
+
+    let fallback_work_status = work_status_for_approval_decision(&decision);
+    let mut resolved_work_status = fallback_work_status;
 
-            if let Some(next_pending_request_id) = next_pending_request_id {
+    // Resolve pending approval server-side and promote next queued request.
+    // This keeps queue ownership inside the session actor.
+    let (approval_type, proposed_amendment, next_pending_request_id, approval_version) =
+        if let Some(actor) = ctx.state.get_session(&session_id) {
+            let (reply_tx, reply_rx) = oneshot::channel();
+            actor
+                .send(SessionCommand::ResolvePendingApproval {
+                    request_id: request_id.clone(),
+                    fallback_work_status,
+                    reply: reply_tx,
+                })
+                .await;
+
+            if let Ok(resolution) = reply_rx.await {
+                resolved_work_status = resolution.work_status;
+                (
+                    resolution.approval_type,
+                    resolution.proposed_amendment,
+                    resolution.next_pending_approval.map(|approval| approval.id),
+                    resolution.approval_version,
+                )
+            } else {
+                (None, None, None, 0)
+            }
+        } else {
+            (None, None, None, 0)
+        };
+
+    if ctx.state.get_session(&session_id).is_some() && approval_type.is_none() {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::ApprovalDecisionResult {
+                session_id: session_id.clone(),
+                request_id: request_id.clone(),
+                outcome: "stale".to_string(),
+                active_request_id: next_pending_request_id.clone(),
+                approval_version,
+            },
+        )
+        .await;
+        return;
+    }
+
+    let request_id_for_result = request_id.clone();
+
+    let _ = ctx
+        .state
+        .persist()
+        .send(PersistCommand::ApprovalDecision {
+            session_id: session_id.clone(),
+            request_id: request_id.clone(),
+            decision: decision.clone(),
+        })
+        .await;
+
+    if let Some(tx) = ctx.state.get_codex_action_tx(&session_id) {
+        let action = match approval_type {
+            Some(orbitdock_protocol::ApprovalType::Patch) => {
                 info!(
                     component = "approval",
-                    event = "approval.queue.promoted",
+                    event = "approval.dispatch.patch",
+                    connection_id = ctx.conn_id,
                     session_id = %session_id,
-                    next_request_id = %next_pending_request_id,
-                    "Promoted next queued approval"
+                    request_id = %request_id,
+                    "Dispatching patch approval"
                 );
+                CodexAction::ApprovePatch {
+                    request_id,
+                    decision: decision.clone(),
+                }
             }
-        }
+            _ => {
+                // Default to exec for exec and unknown types
+                CodexAction::ApproveExec {
+                    request_id,
+                    decision: decision.clone(),
+                    proposed_amendment,
+                }
+            }
+        };
+        let _ = tx.send(action).await;
+    } else if let Some(tx) = ctx.state.get_claude_action_tx(&session_id) {
+        let _ = tx
+            .send(ClaudeAction::ApproveTool {
+                request_id,
+                decision: decision.clone(),
+                message,
+                interrupt,
+                updated_input,
+            })
+            .await;
+    }
 
-        ClientMessage::ListApprovals { session_id, .. } => {
-            send_rest_only_error(client_tx, "GET /api/approvals", session_id).await;
-        }
+    let _ = ctx
+        .state
+        .persist()
+        .send(PersistCommand::SessionUpdate {
+            id: session_id.clone(),
+            status: None,
+            work_status: Some(resolved_work_status),
+            last_activity_at: None,
+        })
+        .await;
+
+    send_json(
+        ctx.client_tx,
+        ServerMessage::ApprovalDecisionResult {
+            session_id: session_id.clone(),
+            request_id: request_id_for_result,
+            outcome: "applied".to_string(),
+            active_request_id: next_pending_request_id.clone(),
+            approval_version,
+        },
+    )
+    .await;
+
+    if let Some(next_pending_request_id) = next_pending_request_id {
+        info!(
+            component = "approval",
+            event = "approval.queue.promoted",
+            session_id = %session_id,
+            next_request_id = %next_pending_request_id,
+            "Promoted next queued approval"
+        );
+    }
+}
 
-        ClientMessage::DeleteApproval { .. } => {
-            send_rest_only_error(client_tx, "DELETE /api/approvals/{approval_id}", None).await;
-        }
+pub(crate) async fn list_approvals(ctx: &HandlerContext<'_>, session_id: Option<String>) {
+    send_rest_only_error(ctx.client_tx, "GET /api/approvals", session_id).await;
+}
 
-        _ => {
-            tracing::warn!(?msg, "approvals::handle called with unexpected variant");
-        }
+pub(crate) async fn delete_approval(ctx: &HandlerContext<'_>) {
+    send_rest_only_error(ctx.client_tx, "DELETE /api/approvals/{approval_id}", None).await;
+}
+
+/// Check a pending exec approval against the shell allowlist.
+async fn denied_exec_approval(
+    state: &Arc<SessionRegistry>,
+    session_id: &str,
+    request_id: &str,
+    conn_id: u64,
+) -> Option<ShellPolicyDenial> {
+    let actor = state.get_session(session_id)?;
+    let (reply_tx, reply_rx) = oneshot::channel();
+    actor
+        .send(SessionCommand::GetState { reply: reply_tx })
+        .await;
+    let pending = reply_rx.await.ok()?.pending_approval?;
+    if pending.id != request_id || pending.approval_type != orbitdock_protocol::ApprovalType::Exec {
+        return None;
     }
+    let command = pending.command?;
+    let denial = state.shell_policy().check(&command).err()?;
+    audit_denied(
+        state,
+        "agent_exec_approval",
+        conn_id,
+        session_id,
+        &command,
+        &denial,
+    )
+    .await;
+    Some(denial)
 }
@@ -0,0 +1,62 @@
+use orbitdock_protocol::{ClientMessage, ServerMessage};
+
+use crate::attention;
+use crate::websocket::send_json;
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    match msg {
+        ClientMessage::GetAttentionItems { request_id } => {
+            let identity = ctx.state.connection_identity(ctx.conn_id);
+            let items = ctx
+                .state
+                .visible_attention_items(&identity, attention::items(ctx.state));
+            send_json(
+                ctx.client_tx,
+                ServerMessage::AttentionItems { request_id, items },
+            )
+            .await;
+        }
+
+        ClientMessage::AcknowledgeAttention { item_id } => {
+            if !can_access_item(ctx, &item_id).await || !attention::acknowledge(ctx.state, &item_id)
+            {
+                send_not_found(ctx, &item_id).await;
+            }
+        }
+
+        ClientMessage::SnoozeAttention { item_id, minutes } => {
+            if !can_access_item(ctx, &item_id).await
+                || !attention::snooze(ctx.state, &item_id, minutes)
+            {
+                send_not_found(ctx, &item_id).await;
+            }
+        }
+
+        _ => {
+            tracing::warn!(?msg, "attention::handle called with unexpected variant");
+        }
+    }
+}
+
+/// Items for sessions the connection can't access are reported as missing.
+async fn can_access_item(ctx: &HandlerContext<'_>, item_id: &str) -> bool {
+    let Some(session_id) = attention::item_session(ctx.state, item_id) else {
+        return false;
+    };
+    let identity = ctx.state.connection_identity(ctx.conn_id);
+    ctx.state.can_access_session(&identity, &session_id).await
+}
+
+async fn send_not_found(ctx: &HandlerContext<'_>, item_id: &str) {
+    send_json(
+        ctx.client_tx,
+        ServerMessage::Error {
+            code: "not_found".to_string(),
+            message: format!("Attention item {item_id} not found"),
+            session_id: None,
+            error: None,
+        },
+    )
+    .await;
+}
@@ -0,0 +1,55 @@
+use orbitdock_protocol::{ClientMessage, ServerMessage};
+
+use crate::websocket::send_json;
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    match msg {
+        ClientMessage::GetAuditLog {
+            session_id,
+            limit,
+            before_id,
+        } => get_audit_log(ctx, session_id, limit, before_id).await,
+
+        _ => {
+            tracing::warn!(?msg, "audit::handle called with unexpected variant");
+        }
+    }
+}
+
+pub(crate) async fn get_audit_log(
+    ctx: &HandlerContext<'_>,
+    session_id: Option<String>,
+    limit: Option<u32>,
+    before_id: Option<i64>,
+) {
+    let identity = ctx.state.connection_identity(ctx.conn_id);
+    match ctx
+        .state
+        .list_visible_audit_log(&identity, session_id.clone(), limit, before_id)
+        .await
+    {
+        Ok(entries) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::AuditLog {
+                    session_id,
+                    entries,
+                },
+            )
+            .await;
+        }
+        Err(e) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "db_error".to_string(),
+                    message: e.to_string(),
+                    session_id,
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}
@@ -1,44 +1,44 @@
-use std::sync::Arc;
-
-use tokio::sync::mpsc;
-
-use crate::state::SessionRegistry;
-use crate::websocket::{send_rest_only_error, OutboundMessage};
+use crate::websocket::send_rest_only_error;
+use crate::ws_handlers::HandlerContext;
 use orbitdock_protocol::ClientMessage;
 
-/// Handles Claude Code hook events forwarded over WebSocket.
+/// Handles Claude Code hook events (and Codex CLI notifications) forwarded
+/// over WebSocket.
 ///
 /// Most variants delegate directly to `hook_handler::handle_hook_message`,
 /// which processes the event against the session registry. The
 /// `GetSubagentTools` variant is a REST-only endpoint and returns an error
 /// directing the client to the HTTP API.
-pub(crate) async fn handle(
-    msg: ClientMessage,
-    client_tx: &mpsc::Sender<OutboundMessage>,
-    state: &Arc<SessionRegistry>,
-) {
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
     match msg {
         ClientMessage::ClaudeSessionStart { .. }
         | ClientMessage::ClaudeSessionEnd { .. }
         | ClientMessage::ClaudeStatusEvent { .. }
         | ClientMessage::ClaudeToolEvent { .. }
-        | ClientMessage::ClaudeSubagentEvent { .. } => {
-            crate::hook_handler::handle_hook_message(msg, state).await;
+        | ClientMessage::ClaudeSubagentEvent { .. }
+        | ClientMessage::CodexNotifyEvent { .. } => {
+            crate::hook_handler::handle_hook_message(msg, ctx.state).await;
         }
 
         ClientMessage::GetSubagentTools {
             session_id,
             subagent_id,
-        } => {
-            let _ = subagent_id;
-            send_rest_only_error(
-                client_tx,
-                "GET /api/sessions/{session_id}/subagents/{subagent_id}/tools",
-                Some(session_id),
-            )
-            .await;
-        }
+        } => get_subagent_tools(ctx, session_id, subagent_id).await,
 
         _ => {}
     }
 }
+
+pub(crate) async fn get_subagent_tools(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    subagent_id: String,
+) {
+    let _ = subagent_id;
+    send_rest_only_error(
+        ctx.client_tx,
+        "GET /api/sessions/{session_id}/subagents/{subagent_id}/tools",
+        Some(session_id),
+    )
+    .await;
+}
@@ -1,42 +1,299 @@
-use std::sync::Arc;
-
-use tokio::sync::mpsc;
 use tracing::info;
 
-use orbitdock_protocol::ClientMessage;
+use orbitdock_protocol::{ClientMessage, Provider, ServerMessage};
 
-use crate::state::SessionRegistry;
-use crate::websocket::{send_json, server_info_message, OutboundMessage};
+use crate::websocket::{send_json, server_info_message};
+use crate::ws_handlers::HandlerContext;
 
-pub(crate) async fn handle(
-    msg: ClientMessage,
-    client_tx: &mpsc::Sender<OutboundMessage>,
-    state: &Arc<SessionRegistry>,
-    conn_id: u64,
-) {
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
     match msg {
         ClientMessage::SetClientPrimaryClaim {
             client_id,
             device_name,
             is_primary,
-        } => {
+        } => set_client_primary_claim(ctx, client_id, device_name, is_primary).await,
+
+        ClientMessage::GetLogLevel => get_log_level(ctx).await,
+
+        ClientMessage::SetLogLevel { target, level } => set_log_level(ctx, target, level).await,
+
+        ClientMessage::ReloadConfig => reload_config(ctx).await,
+
+        ClientMessage::GetServerConfig { request_id } => get_server_config(ctx, request_id).await,
+
+        ClientMessage::SetServerConfig {
+            request_id,
+            key,
+            value,
+        } => set_server_config(ctx, request_id, key, value).await,
+
+        ClientMessage::ListEffortLevels {
+            request_id,
+            provider,
+        } => list_effort_levels(ctx, request_id, provider).await,
+
+        ClientMessage::GetProviderInfo {
+            request_id,
+            refresh,
+        } => get_provider_info(ctx, request_id, refresh).await,
+
+        _ => unreachable!("config::handle called with non-config message"),
+    }
+}
+
+pub(crate) async fn set_client_primary_claim(
+    ctx: &HandlerContext<'_>,
+    client_id: String,
+    device_name: String,
+    is_primary: bool,
+) {
+    info!(
+        component = "config",
+        event = "config.client_primary_claim.set",
+        connection_id = ctx.conn_id,
+        client_id = %client_id,
+        device_name = %device_name,
+        is_primary = is_primary,
+        "Client primary claim updated"
+    );
+
+    ctx.state
+        .set_client_primary_claim(ctx.conn_id, client_id, device_name, is_primary);
+
+    let update = server_info_message(ctx.state);
+    send_json(ctx.client_tx, update.clone()).await;
+    ctx.state.broadcast_to_list(update);
+}
+
+pub(crate) async fn get_log_level(ctx: &HandlerContext<'_>) {
+    match crate::logging::current_filter() {
+        Some(filter) => {
+            send_json(ctx.client_tx, ServerMessage::LogLevel { filter }).await;
+        }
+        None => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "log_reload_unavailable".into(),
+                    message: "Logging filter is not reloadable in this process".into(),
+                    session_id: None,
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}
+
+pub(crate) async fn set_log_level(ctx: &HandlerContext<'_>, target: Option<String>, level: String) {
+    if !ctx.state.connection_identity(ctx.conn_id).is_admin() {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "forbidden".into(),
+                message: "Changing the log level requires an admin token".into(),
+                session_id: None,
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    match crate::logging::set_log_level(target.as_deref(), &level) {
+        Ok(filter) => {
             info!(
                 component = "config",
-                event = "config.client_primary_claim.set",
-                connection_id = conn_id,
-                client_id = %client_id,
-                device_name = %device_name,
-                is_primary = is_primary,
-                "Client primary claim updated"
+                event = "config.log_level.set",
+                connection_id = ctx.conn_id,
+                target = target.as_deref().unwrap_or("*"),
+                level = %level,
+                "Log level updated"
             );
+            send_json(ctx.client_tx, ServerMessage::LogLevel { filter }).await;
+        }
+        Err(e) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "invalid_log_level".into(),
+                    message: e.to_string(),
+                    session_id: None,
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}
 
-            state.set_client_primary_claim(conn_id, client_id, device_name, is_primary);
+pub(crate) async fn reload_config(ctx: &HandlerContext<'_>) {
+    if !ctx.state.connection_identity(ctx.conn_id).is_admin() {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "forbidden".into(),
+                message: "Reloading the server config requires an admin token".into(),
+                session_id: None,
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
 
-            let update = server_info_message(state);
-            send_json(client_tx, update.clone()).await;
-            state.broadcast_to_list(update);
+    // Reading the file and swapping the log filter are quick, but
+    // still blocking I/O.
+    match tokio::task::spawn_blocking(crate::config_file::reload).await {
+        Ok(Ok(report)) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::ConfigReloaded {
+                    applied: report.applied,
+                    restart_required: report.restart_required,
+                    overridden: report.overridden,
+                },
+            )
+            .await;
         }
+        Ok(Err(e)) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "invalid_config".into(),
+                    message: format!("{e:#}"),
+                    session_id: None,
+                    error: None,
+                },
+            )
+            .await;
+        }
+        Err(e) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "config_reload_failed".into(),
+                    message: e.to_string(),
+                    session_id: None,
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}
 
-        _ => unreachable!("config::handle called with non-config message"),
+pub(crate) async fn get_server_config(ctx: &HandlerContext<'_>, request_id: String) {
+    if !ctx.state.connection_identity(ctx.conn_id).is_admin() {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "forbidden".into(),
+                message: "Reading server settings requires an admin token".into(),
+                session_id: None,
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    let settings = tokio::task::spawn_blocking(crate::server_settings::list)
+        .await
+        .unwrap_or_default();
+    send_json(
+        ctx.client_tx,
+        ServerMessage::ServerConfig {
+            request_id,
+            settings,
+        },
+    )
+    .await;
+}
+
+pub(crate) async fn set_server_config(
+    ctx: &HandlerContext<'_>,
+    request_id: String,
+    key: String,
+    value: Option<String>,
+) {
+    if !ctx.state.connection_identity(ctx.conn_id).is_admin() {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "forbidden".into(),
+                message: "Changing server settings requires an admin token".into(),
+                session_id: None,
+                error: None,
+            },
+        )
+        .await;
+        return;
     }
+
+    match crate::server_settings::set(ctx.state, &key, value.as_deref()).await {
+        Ok(setting) => {
+            info!(
+                component = "config",
+                event = "config.setting.set",
+                connection_id = ctx.conn_id,
+                key = %key,
+                cleared = value.is_none(),
+                "Server setting updated"
+            );
+            send_json(
+                ctx.client_tx,
+                ServerMessage::ServerConfig {
+                    request_id,
+                    settings: vec![setting],
+                },
+            )
+            .await;
+        }
+        Err(message) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "invalid_setting".into(),
+                    message,
+                    session_id: None,
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}
+
+pub(crate) async fn list_effort_levels(
+    ctx: &HandlerContext<'_>,
+    request_id: String,
+    provider: Provider,
+) {
+    send_json(
+        ctx.client_tx,
+        ServerMessage::EffortLevelsList {
+            request_id,
+            provider,
+            levels: crate::effort::levels(provider),
+        },
+    )
+    .await;
+}
+
+pub(crate) async fn get_provider_info(ctx: &HandlerContext<'_>, request_id: String, refresh: bool) {
+    let cached = crate::provider_info::current();
+    let providers = if refresh || cached.is_empty() {
+        crate::provider_info::refresh().await
+    } else {
+        cached
+    };
+    send_json(
+        ctx.client_tx,
+        ServerMessage::ProviderInfo {
+            request_id,
+            providers,
+        },
+    )
+    .await;
 }
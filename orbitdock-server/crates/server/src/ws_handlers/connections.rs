@@ -0,0 +1,98 @@
+use tracing::info;
+
+use orbitdock_protocol::{ClientMessage, ServerMessage};
+
+use crate::websocket::send_json;
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    if !ctx.state.connection_identity(ctx.conn_id).is_admin() {
+        let message = match msg {
+            ClientMessage::ShutdownServer => "Shutting down the server requires an admin token",
+            _ => "Managing connections requires an admin token",
+        };
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "forbidden".into(),
+                message: message.into(),
+                session_id: None,
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    match msg {
+        ClientMessage::ListConnections => list_connections(ctx).await,
+
+        ClientMessage::DisconnectConnection { connection_id } => {
+            disconnect_connection(ctx, connection_id).await
+        }
+
+        ClientMessage::ShutdownServer => shutdown_server(ctx).await,
+
+        _ => unreachable!("connections::handle called with non-connection message"),
+    }
+}
+
+pub(crate) async fn list_connections(ctx: &HandlerContext<'_>) {
+    send_json(
+        ctx.client_tx,
+        ServerMessage::ConnectionsList {
+            connections: ctx.state.list_connections(),
+        },
+    )
+    .await;
+}
+
+pub(crate) async fn disconnect_connection(ctx: &HandlerContext<'_>, connection_id: u64) {
+    if !ctx.state.disconnect_connection(connection_id) {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "not_found".into(),
+                message: format!("Connection {connection_id} not found"),
+                session_id: None,
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    info!(
+        component = "websocket",
+        event = "ws.connection.disconnect_requested",
+        connection_id = ctx.conn_id,
+        target_connection_id = connection_id,
+        "Admin requested connection close"
+    );
+
+    if connection_id != ctx.conn_id {
+        // The target closes asynchronously; report it as already gone.
+        let connections = ctx
+            .state
+            .list_connections()
+            .into_iter()
+            .filter(|connection| connection.connection_id != connection_id)
+            .collect();
+        send_json(
+            ctx.client_tx,
+            ServerMessage::ConnectionsList { connections },
+        )
+        .await;
+    }
+}
+
+pub(crate) async fn shutdown_server(ctx: &HandlerContext<'_>) {
+    info!(
+        component = "websocket",
+        event = "ws.server.shutdown_requested",
+        connection_id = ctx.conn_id,
+        "Admin requested server shutdown"
+    );
+    send_json(ctx.client_tx, ServerMessage::ServerShuttingDown).await;
+    ctx.state.request_shutdown();
+}
@@ -0,0 +1,90 @@
+use orbitdock_protocol::{ClientMessage, ServerMessage};
+
+use crate::persistence::list_session_diagnostics;
+use crate::transcript_resync::{resync_session, resync_sessions};
+use crate::websocket::send_json;
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    match msg {
+        ClientMessage::GetSessionEvents { session_id } => get_session_events(ctx, session_id).await,
+        ClientMessage::ResyncSession { session_id } => {
+            match resync_session(ctx.state, &session_id).await {
+                Ok(resync) => {
+                    send_json(ctx.client_tx, ServerMessage::SessionResynced { resync }).await;
+                }
+                Err(e) => {
+                    send_json(
+                        ctx.client_tx,
+                        ServerMessage::Error {
+                            code: e.code().to_string(),
+                            message: e.to_string(),
+                            session_id: Some(session_id),
+                            error: None,
+                        },
+                    )
+                    .await;
+                }
+            }
+        }
+        ClientMessage::ResyncSessions {
+            request_id,
+            session_ids,
+        } => {
+            // Explicit ids bypass the dispatcher's single-session check.
+            let identity = ctx.state.connection_identity(ctx.conn_id);
+            for session_id in &session_ids {
+                if !ctx.state.can_access_session(&identity, session_id).await {
+                    send_json(
+                        ctx.client_tx,
+                        ServerMessage::Error {
+                            code: "forbidden".into(),
+                            message: "This session hasn't been shared with you".into(),
+                            session_id: Some(session_id.clone()),
+                            error: None,
+                        },
+                    )
+                    .await;
+                    return;
+                }
+            }
+            let results = resync_sessions(ctx.state, &identity, session_ids).await;
+            send_json(
+                ctx.client_tx,
+                ServerMessage::SessionsResynced {
+                    request_id,
+                    results,
+                },
+            )
+            .await;
+        }
+
+        _ => {
+            tracing::warn!(?msg, "diagnostics::handle called with unexpected variant");
+        }
+    }
+}
+
+pub(crate) async fn get_session_events(ctx: &HandlerContext<'_>, session_id: String) {
+    match list_session_diagnostics(session_id.clone()).await {
+        Ok(events) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::SessionEvents { session_id, events },
+            )
+            .await;
+        }
+        Err(e) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "db_error".to_string(),
+                    message: e.to_string(),
+                    session_id: Some(session_id),
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}
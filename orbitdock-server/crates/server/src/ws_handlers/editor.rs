@@ -0,0 +1,118 @@
+use std::path::Path;
+
+use tracing::{info, warn};
+
+use orbitdock_protocol::{ClientMessage, ServerMessage};
+
+use crate::editors::{self, EditorError};
+use crate::websocket::send_json;
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    match msg {
+        ClientMessage::OpenInEditor {
+            session_id,
+            file_path,
+            line,
+            editor,
+        } => open_in_editor(ctx, session_id, file_path, line, editor).await,
+
+        ClientMessage::ListEditors { request_id } => list_editors(ctx, request_id).await,
+
+        _ => {}
+    }
+}
+
+pub(crate) async fn open_in_editor(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    file_path: String,
+    line: Option<u32>,
+    editor: Option<String>,
+) {
+    let Some(actor) = ctx.state.get_session(&session_id) else {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "not_found".to_string(),
+                message: format!("Session {session_id} not found"),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    };
+
+    let snap = actor.snapshot();
+    let project = Path::new(&snap.project_path);
+    let cwd = snap
+        .current_cwd
+        .as_deref()
+        .map(Path::new)
+        .unwrap_or(project);
+    let config = crate::config_file::current();
+    let opened = editors::resolve_file(&file_path, cwd, &[project]).and_then(|path| {
+        editors::open(&config.editor, editor.as_deref(), &path, line).map(|name| (name, path))
+    });
+
+    match opened {
+        Ok((editor, path)) => {
+            info!(
+                component = "editor",
+                event = "editor.opened",
+                connection_id = ctx.conn_id,
+                session_id = %session_id,
+                editor = %editor,
+                path = %path.display(),
+                "Opened file in editor"
+            );
+            send_json(
+                ctx.client_tx,
+                ServerMessage::EditorOpened {
+                    session_id,
+                    editor,
+                    file_path: path.to_string_lossy().into_owned(),
+                    line,
+                },
+            )
+            .await;
+        }
+        Err(e) => {
+            if matches!(e, EditorError::Launch { .. }) {
+                warn!(
+                    component = "editor",
+                    event = "editor.launch_failed",
+                    connection_id = ctx.conn_id,
+                    session_id = %session_id,
+                    error = %e,
+                    "Could not launch editor"
+                );
+            }
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: e.code().to_string(),
+                    message: e.to_string(),
+                    session_id: Some(session_id),
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}
+
+pub(crate) async fn list_editors(ctx: &HandlerContext<'_>, request_id: String) {
+    let config = crate::config_file::current();
+    let (editors, default_editor) = editors::list(&config.editor);
+    send_json(
+        ctx.client_tx,
+        ServerMessage::EditorsList {
+            request_id,
+            editors,
+            default_editor,
+        },
+    )
+    .await;
+}
@@ -1,11 +1,15 @@
-use std::sync::Arc;
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use tokio::sync::{mpsc, oneshot};
-use tracing::{debug, info, warn};
+use tracing::{info, warn};
 
-use orbitdock_protocol::{ClientMessage, ServerMessage, WorkStatus};
+use orbitdock_connector_claude::FileAttachment;
+use orbitdock_protocol::{
+    ClientMessage, ImageInput, MentionInput, ServerMessage, SkillInput, WorkStatus,
+};
 
+use crate::attachments;
 use crate::claude_session::ClaudeAction;
 use crate::codex_session::CodexAction;
 use crate::normalization::{
@@ -15,15 +19,11 @@ use crate::persistence::PersistCommand;
 use crate::session_command::SessionCommand;
 use crate::session_naming::name_from_first_prompt;
 use crate::session_utils::{iso_timestamp, mark_session_working_after_send};
-use crate::state::SessionRegistry;
+use crate::transcription;
 use crate::websocket::{send_json, OutboundMessage};
+use crate::ws_handlers::HandlerContext;
 
-pub(crate) async fn handle(
-    msg: ClientMessage,
-    client_tx: &mpsc::Sender<OutboundMessage>,
-    state: &Arc<SessionRegistry>,
-    conn_id: u64,
-) {
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
     match msg {
         ClientMessage::SendMessage {
             session_id,
@@ -33,722 +33,917 @@ pub(crate) async fn handle(
             skills,
             images,
             mentions,
+            attachment_ids,
         } => {
-            info!(
-                component = "session",
-                event = "session.message.send_requested",
-                connection_id = conn_id,
-                session_id = %session_id,
-                content_chars = content.chars().count(),
-                model = ?model,
-                effort = ?effort,
-                skills_count = skills.len(),
-                images_count = images.len(),
-                mentions_count = mentions.len(),
-                "Sending message to session"
-            );
-
-            // Try Codex action channel first, then Claude
-            let codex_tx = state.get_codex_action_tx(&session_id);
-            let claude_tx = state.get_claude_action_tx(&session_id);
+            send_message(
+                ctx,
+                session_id,
+                content,
+                model,
+                effort,
+                skills,
+                images,
+                mentions,
+                attachment_ids,
+            )
+            .await
+        }
 
-            if codex_tx.is_some() || claude_tx.is_some() {
-                let session_is_claude = state.get_session(&session_id).is_some_and(|actor| {
-                    actor.snapshot().provider == orbitdock_protocol::Provider::Claude
-                });
+        ClientMessage::SteerTurn {
+            session_id,
+            content,
+            images,
+            mentions,
+            attachment_ids,
+        } => steer_turn(ctx, session_id, content, images, mentions, attachment_ids).await,
 
-                let first_prompt = name_from_first_prompt(&content);
+        ClientMessage::AnswerQuestion {
+            session_id,
+            request_id,
+            answer,
+            question_id,
+            answers,
+        } => answer_question(ctx, session_id, request_id, answer, question_id, answers).await,
 
-                let _ = state
-                    .persist()
-                    .send(PersistCommand::CodexPromptIncrement {
-                        id: session_id.clone(),
-                        first_prompt: first_prompt.clone(),
-                    })
-                    .await;
+        ClientMessage::InterruptSession { session_id } => interrupt_session(ctx, session_id).await,
 
-                // Broadcast first_prompt delta and trigger AI naming
-                if let Some(prompt) = first_prompt {
-                    if let Some(actor) = state.get_session(&session_id) {
-                        let changes = orbitdock_protocol::StateChanges {
-                            first_prompt: Some(Some(prompt.clone())),
-                            ..Default::default()
-                        };
-                        let _ = actor
-                            .send(SessionCommand::ApplyDelta {
-                                changes,
-                                persist_op: None,
-                            })
-                            .await;
-
-                        // Trigger AI naming (fire-and-forget, deduped)
-                        if state.naming_guard().try_claim(&session_id) {
-                            crate::ai_naming::spawn_naming_task(
-                                session_id.clone(),
-                                prompt,
-                                actor,
-                                state.persist().clone(),
-                                state.list_tx(),
-                            );
-                        }
-                    }
-                }
+        ClientMessage::CompactContext { session_id } => compact_context(ctx, session_id).await,
 
-                let action_model = normalize_model_override(model.clone());
-                let action_effort = normalize_non_empty(effort.clone());
-                let action_effort_for_connector = if session_is_claude {
-                    None
-                } else {
-                    action_effort.clone()
-                };
+        ClientMessage::UndoLastTurn { session_id } => undo_last_turn(ctx, session_id).await,
 
-                // Persist model override and broadcast delta only when explicitly provided.
-                if let Some(actor) = state.get_session(&session_id) {
-                    if let Some(ref model_name) = action_model {
-                        let _ = state
-                            .persist()
-                            .send(PersistCommand::ModelUpdate {
-                                session_id: session_id.clone(),
-                                model: model_name.clone(),
-                            })
-                            .await;
-                        let changes = orbitdock_protocol::StateChanges {
-                            model: Some(Some(model_name.clone())),
-                            ..Default::default()
-                        };
-                        let _ = actor
-                            .send(SessionCommand::ApplyDelta {
-                                changes,
-                                persist_op: None,
-                            })
-                            .await;
-                    }
-                }
+        ClientMessage::RollbackTurns {
+            session_id,
+            num_turns,
+        } => rollback_turns(ctx, session_id, num_turns).await,
 
-                // Persist effort override and broadcast delta only when explicitly provided,
-                // and only for providers that support mid-session effort changes.
-                if let Some(actor) = state.get_session(&session_id) {
-                    if let Some(ref effort_name) = action_effort {
-                        if session_is_claude {
-                            debug!(
-                                component = "session",
-                                event = "session.message.effort_ignored_for_claude",
-                                connection_id = conn_id,
-                                session_id = %session_id,
-                                effort = %effort_name,
-                                "Claude sessions do not support effort updates after create"
-                            );
-                        } else {
-                            let _ = state
-                                .persist()
-                                .send(PersistCommand::EffortUpdate {
-                                    session_id: session_id.clone(),
-                                    effort: Some(effort_name.clone()),
-                                })
-                                .await;
-                            let changes = orbitdock_protocol::StateChanges {
-                                effort: Some(Some(effort_name.clone())),
-                                ..Default::default()
-                            };
-                            let _ = actor
-                                .send(SessionCommand::ApplyDelta {
-                                    changes,
-                                    persist_op: None,
-                                })
-                                .await;
-                        }
-                    }
-                }
+        ClientMessage::StopTask {
+            session_id,
+            task_id,
+        } => stop_task(ctx, session_id, task_id).await,
 
-                // Persist user message immediately
-                let ts_millis = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_millis();
-                let msg_id = format!("user-ws-{}-{}", ts_millis, conn_id);
-                // Keep client message payload portable; only connector dispatch needs path images.
-                let connector_images =
-                    crate::images::extract_images_to_disk(&images, &session_id, &msg_id);
-                let user_msg = orbitdock_protocol::Message {
-                    id: msg_id,
-                    session_id: session_id.clone(),
-                    sequence: None,
-                    message_type: orbitdock_protocol::MessageType::User,
-                    content: content.clone(),
-                    tool_name: None,
-                    tool_input: None,
-                    tool_output: None,
-                    is_error: false,
-                    is_in_progress: false,
-                    timestamp: iso_timestamp(ts_millis),
-                    duration_ms: None,
-                    images: images.clone(),
-                };
+        ClientMessage::RewindFiles {
+            session_id,
+            user_message_id,
+        } => rewind_files(ctx, session_id, user_message_id).await,
 
-                if let Some(actor) = state.get_session(&session_id) {
-                    let _ = state
-                        .persist()
-                        .send(PersistCommand::MessageAppend {
-                            session_id: session_id.clone(),
-                            message: user_msg.clone(),
-                        })
-                        .await;
-                    actor
-                        .send(SessionCommand::AddMessageAndBroadcast { message: user_msg })
-                        .await;
-                }
+        _ => {}
+    }
+}
 
-                if let Some(tx) = codex_tx {
-                    if tx
-                        .send(CodexAction::SendMessage {
-                            content,
-                            model: action_model,
-                            effort: action_effort_for_connector,
-                            skills,
-                            images: connector_images.clone(),
-                            mentions,
-                        })
-                        .await
-                        .is_ok()
-                    {
-                        mark_session_working_after_send(state, &session_id).await;
-                    } else {
-                        warn!(
-                            component = "session",
-                            event = "session.message.action_channel_closed",
-                            connection_id = conn_id,
-                            session_id = %session_id,
-                            provider = "codex",
-                            "Codex action channel closed while sending message"
-                        );
-                    }
-                } else if let Some(tx) = claude_tx {
-                    if tx
-                        .send(ClaudeAction::SendMessage {
-                            content,
-                            model: action_model,
-                            effort: action_effort_for_connector,
-                            images: connector_images,
-                        })
-                        .await
-                        .is_ok()
-                    {
-                        mark_session_working_after_send(state, &session_id).await;
-                    } else {
-                        warn!(
-                            component = "session",
-                            event = "session.message.action_channel_closed",
-                            connection_id = conn_id,
-                            session_id = %session_id,
-                            provider = "claude",
-                            "Claude action channel closed while sending message"
-                        );
-                    }
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn send_message(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    content: String,
+    model: Option<String>,
+    effort: Option<String>,
+    skills: Vec<SkillInput>,
+    images: Vec<ImageInput>,
+    mentions: Vec<MentionInput>,
+    attachment_ids: Vec<String>,
+) {
+    info!(
+        component = "session",
+        event = "session.message.send_requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        content_chars = content.chars().count(),
+        model = ?model,
+        effort = ?effort,
+        skills_count = skills.len(),
+        images_count = images.len(),
+        mentions_count = mentions.len(),
+        attachments_count = attachment_ids.len(),
+        "Sending message to session"
+    );
+
+    // Try Codex action channel first, then Claude
+    let codex_tx = ctx.state.get_codex_action_tx(&session_id);
+    let claude_tx = ctx.state.get_claude_action_tx(&session_id);
+
+    if codex_tx.is_some() || claude_tx.is_some() {
+        let provider = ctx
+            .state
+            .get_session(&session_id)
+            .map(|actor| actor.snapshot().provider);
+        let action_effort = match (provider, normalize_non_empty(effort.clone())) {
+            (Some(provider), Some(effort)) => match crate::effort::validate(provider, &effort) {
+                Ok(level) => Some(level),
+                Err(message) => {
+                    send_json(
+                        ctx.client_tx,
+                        ServerMessage::Error {
+                            code: "invalid_effort".to_string(),
+                            message,
+                            session_id: Some(session_id),
+                            error: None,
+                        },
+                    )
+                    .await;
+                    return;
                 }
-            } else {
-                warn!(
-                    component = "session",
-                    event = "session.message.missing_action_channel",
-                    connection_id = conn_id,
-                    session_id = %session_id,
-                    "No action channel for session"
-                );
-                send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "not_found".into(),
-                        message: format!(
-                            "Session {} not found or has no active connector",
-                            session_id
-                        ),
-                        session_id: Some(session_id),
-                    },
-                )
-                .await;
-            }
-        }
-
-        ClientMessage::SteerTurn {
-            session_id,
+            },
+            (_, effort) => effort,
+        };
+
+        let ts_millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let msg_id = format!("user-ws-{}-{}", ts_millis, ctx.conn_id);
+        let Some((attachments, content)) = resolve_attachments(
+            ctx.client_tx,
+            &session_id,
+            &attachment_ids,
+            &msg_id,
+            codex_tx.is_none(),
             content,
-            images,
-            mentions,
-        } => {
-            info!(
-                component = "session",
-                event = "session.steer.requested",
-                connection_id = conn_id,
-                session_id = %session_id,
-                content_chars = content.chars().count(),
-                images_count = images.len(),
-                mentions_count = mentions.len(),
-                "Steering active turn"
-            );
+        )
+        .await
+        else {
+            return;
+        };
+
+        let first_prompt = name_from_first_prompt(&content);
+
+        let _ = ctx
+            .state
+            .persist()
+            .send(PersistCommand::CodexPromptIncrement {
+                id: session_id.clone(),
+                first_prompt: first_prompt.clone(),
+            })
+            .await;
 
-            // Try Codex action channel first, then Claude
-            let codex_tx = state.get_codex_action_tx(&session_id);
-            let claude_tx = state.get_claude_action_tx(&session_id);
-
-            if codex_tx.is_some() || claude_tx.is_some() {
-                // Persist steer message so it appears in conversation
-                let ts_millis = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_millis();
-                let steer_msg_id = format!("steer-ws-{}-{}", ts_millis, conn_id);
-                let connector_images =
-                    crate::images::extract_images_to_disk(&images, &session_id, &steer_msg_id);
-                let steer_msg = orbitdock_protocol::Message {
-                    id: steer_msg_id.clone(),
-                    session_id: session_id.clone(),
-                    sequence: None,
-                    message_type: orbitdock_protocol::MessageType::Steer,
-                    content: content.clone(),
-                    tool_name: None,
-                    tool_input: None,
-                    tool_output: None,
-                    is_error: false,
-                    is_in_progress: false,
-                    timestamp: iso_timestamp(ts_millis),
-                    duration_ms: None,
-                    images: images.clone(),
+        // Broadcast first_prompt delta and trigger AI naming
+        if let Some(prompt) = first_prompt {
+            if let Some(actor) = ctx.state.get_session(&session_id) {
+                let changes = orbitdock_protocol::StateChanges {
+                    first_prompt: Some(Some(prompt.clone())),
+                    ..Default::default()
                 };
+                let _ = actor
+                    .send(SessionCommand::ApplyDelta {
+                        changes,
+                        persist_op: None,
+                    })
+                    .await;
 
-                if let Some(actor) = state.get_session(&session_id) {
-                    let _ = state
-                        .persist()
-                        .send(PersistCommand::MessageAppend {
-                            session_id: session_id.clone(),
-                            message: steer_msg.clone(),
-                        })
-                        .await;
-                    actor
-                        .send(SessionCommand::AddMessageAndBroadcast { message: steer_msg })
-                        .await;
-                }
-
-                if let Some(tx) = codex_tx {
-                    let _ = tx
-                        .send(CodexAction::SteerTurn {
-                            content,
-                            message_id: steer_msg_id,
-                            images: connector_images.clone(),
-                            mentions,
-                        })
-                        .await;
-                } else if let Some(tx) = claude_tx {
-                    let _ = tx
-                        .send(ClaudeAction::SteerTurn {
-                            content,
-                            message_id: steer_msg_id,
-                            images: connector_images,
-                        })
-                        .await;
+                // Trigger AI naming (fire-and-forget, deduped)
+                if ctx.state.naming_guard().try_claim(&session_id) {
+                    crate::ai_naming::spawn_naming_task(
+                        session_id.clone(),
+                        prompt,
+                        actor,
+                        ctx.state.persist().clone(),
+                        ctx.state.list_tx(),
+                    );
                 }
-            } else {
-                send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "not_found".into(),
-                        message: format!(
-                            "Session {} not found or has no active connector",
-                            session_id
-                        ),
-                        session_id: Some(session_id),
-                    },
-                )
-                .await;
             }
         }
 
-        ClientMessage::AnswerQuestion {
-            session_id,
-            request_id,
-            answer,
-            question_id,
-            answers,
-        } => {
-            let mut normalized_answers = normalize_question_answers(answers);
-            let trimmed_answer = answer.trim().to_string();
-            if normalized_answers.is_empty() && !trimmed_answer.is_empty() {
-                let key = question_id.clone().unwrap_or_else(|| "0".to_string());
-                normalized_answers.insert(key, vec![trimmed_answer.clone()]);
-            }
-
-            info!(
-                component = "approval",
-                event = "approval.answer.submitted",
-                connection_id = conn_id,
-                session_id = %session_id,
-                request_id = %request_id,
-                answer_chars = trimmed_answer.chars().count(),
-                answer_questions = normalized_answers.len(),
-                "Answer submitted for question approval"
-            );
-            if normalized_answers.is_empty() {
-                warn!(
-                    component = "approval",
-                    event = "approval.answer.missing_payload",
-                    connection_id = conn_id,
-                    session_id = %session_id,
-                    request_id = %request_id,
-                    "Question answer request had no usable answer payload"
-                );
-                send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "invalid_answer_payload".into(),
-                        message: "Question approvals require a non-empty answer or answers map"
-                            .into(),
-                        session_id: Some(session_id),
-                    },
-                )
-                .await;
-                return;
-            }
+        let action_model = normalize_model_override(model.clone());
 
-            let fallback_work_status = WorkStatus::Working;
-            let mut resolved_work_status = fallback_work_status;
-            let mut resolved = false;
-            let mut next_pending_request_id: Option<String> = None;
-            let mut approval_version: u64 = 0;
-            if let Some(actor) = state.get_session(&session_id) {
-                let (reply_tx, reply_rx) = oneshot::channel();
-                actor
-                    .send(SessionCommand::ResolvePendingApproval {
-                        request_id: request_id.clone(),
-                        fallback_work_status,
-                        reply: reply_tx,
+        // Persist model override and broadcast delta only when explicitly provided.
+        if let Some(actor) = ctx.state.get_session(&session_id) {
+            if let Some(ref model_name) = action_model {
+                let _ = ctx
+                    .state
+                    .persist()
+                    .send(PersistCommand::ModelUpdate {
+                        session_id: session_id.clone(),
+                        model: model_name.clone(),
+                    })
+                    .await;
+                let changes = orbitdock_protocol::StateChanges {
+                    model: Some(Some(model_name.clone())),
+                    ..Default::default()
+                };
+                let _ = actor
+                    .send(SessionCommand::ApplyDelta {
+                        changes,
+                        persist_op: None,
                     })
                     .await;
-                if let Ok(resolution) = reply_rx.await {
-                    resolved = resolution.approval_type.is_some();
-                    resolved_work_status = resolution.work_status;
-                    next_pending_request_id = resolution.next_pending_approval.map(|a| a.id);
-                    approval_version = resolution.approval_version;
-                }
             }
+        }
 
-            if state.get_session(&session_id).is_some() && !resolved {
-                send_json(
-                    client_tx,
-                    ServerMessage::ApprovalDecisionResult {
+        // Persist the validated effort override and broadcast delta only when
+        // explicitly provided.
+        if let Some(actor) = ctx.state.get_session(&session_id) {
+            if let Some(ref effort_name) = action_effort {
+                let _ = ctx
+                    .state
+                    .persist()
+                    .send(PersistCommand::EffortUpdate {
                         session_id: session_id.clone(),
-                        request_id: request_id.clone(),
-                        outcome: "stale".to_string(),
-                        active_request_id: next_pending_request_id.clone(),
-                        approval_version,
-                    },
-                )
-                .await;
-                return;
+                        effort: Some(effort_name.clone()),
+                    })
+                    .await;
+                let changes = orbitdock_protocol::StateChanges {
+                    effort: Some(Some(effort_name.clone())),
+                    ..Default::default()
+                };
+                let _ = actor
+                    .send(SessionCommand::ApplyDelta {
+                        changes,
+                        persist_op: None,
+                    })
+                    .await;
             }
+        }
 
-            let request_id_for_result = request_id.clone();
-
-            let _ = state
+        // Persist user message immediately
+        // Keep client message payload portable; only connector dispatch needs path images.
+        let connector_images = crate::images::extract_images_to_disk(&images, &session_id, &msg_id);
+        let user_msg = orbitdock_protocol::Message {
+            id: msg_id,
+            session_id: session_id.clone(),
+            sequence: None,
+            message_type: orbitdock_protocol::MessageType::User,
+            content: content.clone(),
+            tool_name: None,
+            tool_input: None,
+            tool_output: None,
+            is_error: false,
+            is_in_progress: false,
+            timestamp: iso_timestamp(ts_millis),
+            duration_ms: None,
+            images: images.clone(),
+        };
+
+        if let Some(actor) = ctx.state.get_session(&session_id) {
+            let _ = ctx
+                .state
                 .persist()
-                .send(PersistCommand::ApprovalDecision {
+                .send(PersistCommand::MessageAppend {
                     session_id: session_id.clone(),
-                    request_id: request_id.clone(),
-                    decision: "approved".to_string(),
+                    message: user_msg.clone(),
                 })
                 .await;
+            actor
+                .send(SessionCommand::AddMessageAndBroadcast { message: user_msg })
+                .await;
+        }
 
-            if let Some(tx) = state.get_codex_action_tx(&session_id) {
-                let _ = tx
-                    .send(CodexAction::AnswerQuestion {
-                        request_id: request_id.clone(),
-                        answers: normalized_answers,
-                    })
-                    .await;
-            } else if let Some(tx) = state.get_claude_action_tx(&session_id) {
-                // If the client sent a plain text answer, normalize it into the
-                // answers map so the connector always gets structured data.
-                let mut claude_answers = normalized_answers.clone();
-                if claude_answers.is_empty() && !trimmed_answer.is_empty() {
-                    let key = question_id.clone().unwrap_or_else(|| "0".to_string());
-                    claude_answers.insert(key, vec![trimmed_answer]);
-                }
-                if claude_answers.is_empty() {
-                    warn!(
-                        component = "approval",
-                        event = "approval.answer.missing_payload",
-                        connection_id = conn_id,
-                        session_id = %session_id,
-                        request_id = %request_id,
-                        "Question answer request had no usable answer payload"
-                    );
-                    return;
-                }
-                let _ = tx
-                    .send(ClaudeAction::AnswerQuestion {
-                        request_id,
-                        answers: claude_answers,
-                    })
-                    .await;
+        if let Some(tx) = codex_tx {
+            if tx
+                .send(CodexAction::SendMessage {
+                    content,
+                    model: action_model,
+                    effort: action_effort.clone(),
+                    skills,
+                    images: connector_images.clone(),
+                    mentions,
+                })
+                .await
+                .is_ok()
+            {
+                mark_session_working_after_send(ctx.state, &session_id).await;
+            } else {
+                warn!(
+                    component = "session",
+                    event = "session.message.action_channel_closed",
+                    connection_id = ctx.conn_id,
+                    session_id = %session_id,
+                    provider = "codex",
+                    "Codex action channel closed while sending message"
+                );
             }
+        } else if let Some(tx) = claude_tx {
+            if tx
+                .send(ClaudeAction::SendMessage {
+                    content,
+                    model: action_model,
+                    effort: action_effort,
+                    images: connector_images,
+                    attachments,
+                })
+                .await
+                .is_ok()
+            {
+                mark_session_working_after_send(ctx.state, &session_id).await;
+            } else {
+                warn!(
+                    component = "session",
+                    event = "session.message.action_channel_closed",
+                    connection_id = ctx.conn_id,
+                    session_id = %session_id,
+                    provider = "claude",
+                    "Claude action channel closed while sending message"
+                );
+            }
+        }
+    } else {
+        warn!(
+            component = "session",
+            event = "session.message.missing_action_channel",
+            connection_id = ctx.conn_id,
+            session_id = %session_id,
+            "No action channel for session"
+        );
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "not_found".into(),
+                message: format!(
+                    "Session {} not found or has no active connector",
+                    session_id
+                ),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+    }
+}
 
-            let _ = state
+pub(crate) async fn steer_turn(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    content: String,
+    images: Vec<ImageInput>,
+    mentions: Vec<MentionInput>,
+    attachment_ids: Vec<String>,
+) {
+    info!(
+        component = "session",
+        event = "session.steer.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        content_chars = content.chars().count(),
+        images_count = images.len(),
+        mentions_count = mentions.len(),
+        "Steering active turn"
+    );
+
+    // Try Codex action channel first, then Claude
+    let codex_tx = ctx.state.get_codex_action_tx(&session_id);
+    let claude_tx = ctx.state.get_claude_action_tx(&session_id);
+
+    if codex_tx.is_some() || claude_tx.is_some() {
+        // Persist steer message so it appears in conversation
+        let ts_millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let steer_msg_id = format!("steer-ws-{}-{}", ts_millis, ctx.conn_id);
+        let Some((attachments, content)) = resolve_attachments(
+            ctx.client_tx,
+            &session_id,
+            &attachment_ids,
+            &steer_msg_id,
+            codex_tx.is_none(),
+            content,
+        )
+        .await
+        else {
+            return;
+        };
+        let connector_images =
+            crate::images::extract_images_to_disk(&images, &session_id, &steer_msg_id);
+        let steer_msg = orbitdock_protocol::Message {
+            id: steer_msg_id.clone(),
+            session_id: session_id.clone(),
+            sequence: None,
+            message_type: orbitdock_protocol::MessageType::Steer,
+            content: content.clone(),
+            tool_name: None,
+            tool_input: None,
+            tool_output: None,
+            is_error: false,
+            is_in_progress: false,
+            timestamp: iso_timestamp(ts_millis),
+            duration_ms: None,
+            images: images.clone(),
+        };
+
+        if let Some(actor) = ctx.state.get_session(&session_id) {
+            let _ = ctx
+                .state
                 .persist()
-                .send(PersistCommand::SessionUpdate {
-                    id: session_id.clone(),
-                    status: None,
-                    work_status: Some(resolved_work_status),
-                    last_activity_at: None,
+                .send(PersistCommand::MessageAppend {
+                    session_id: session_id.clone(),
+                    message: steer_msg.clone(),
                 })
                 .await;
+            actor
+                .send(SessionCommand::AddMessageAndBroadcast { message: steer_msg })
+                .await;
+        }
 
-            send_json(
-                client_tx,
-                ServerMessage::ApprovalDecisionResult {
-                    session_id: session_id.clone(),
-                    request_id: request_id_for_result,
-                    outcome: "applied".to_string(),
-                    active_request_id: next_pending_request_id.clone(),
-                    approval_version,
-                },
-            )
+        if let Some(tx) = codex_tx {
+            let _ = tx
+                .send(CodexAction::SteerTurn {
+                    content,
+                    message_id: steer_msg_id,
+                    images: connector_images.clone(),
+                    mentions,
+                })
+                .await;
+        } else if let Some(tx) = claude_tx {
+            let _ = tx
+                .send(ClaudeAction::SteerTurn {
+                    content,
+                    message_id: steer_msg_id,
+                    images: connector_images,
+                    attachments,
+                })
+                .await;
+        }
+    } else {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "not_found".into(),
+                message: format!(
+                    "Session {} not found or has no active connector",
+                    session_id
+                ),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+    }
+}
+
+pub(crate) async fn answer_question(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    request_id: String,
+    answer: String,
+    question_id: Option<String>,
+    answers: Option<HashMap<String, Vec<String>>>,
+) {
+    let mut normalized_answers = normalize_question_answers(answers);
+    let trimmed_answer = answer.trim().to_string();
+    if normalized_answers.is_empty() && !trimmed_answer.is_empty() {
+        let key = question_id.clone().unwrap_or_else(|| "0".to_string());
+        normalized_answers.insert(key, vec![trimmed_answer.clone()]);
+    }
+
+    info!(
+        component = "approval",
+        event = "approval.answer.submitted",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        request_id = %request_id,
+        answer_chars = trimmed_answer.chars().count(),
+        answer_questions = normalized_answers.len(),
+        "Answer submitted for question approval"
+    );
+    if normalized_answers.is_empty() {
+        warn!(
+            component = "approval",
+            event = "approval.answer.missing_payload",
+            connection_id = ctx.conn_id,
+            session_id = %session_id,
+            request_id = %request_id,
+            "Question answer request had no usable answer payload"
+        );
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "invalid_answer_payload".into(),
+                message: "Question approvals require a non-empty answer or answers map".into(),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    let fallback_work_status = WorkStatus::Working;
+    let mut resolved_work_status = fallback_work_status;
+    let mut resolved = false;
+    let mut next_pending_request_id: Option<String> = None;
+    let mut approval_version: u64 = 0;
+    if let Some(actor) = ctx.state.get_session(&session_id) {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        actor
+            .send(SessionCommand::ResolvePendingApproval {
+                request_id: request_id.clone(),
+                fallback_work_status,
+                reply: reply_tx,
+            })
             .await;
+        if let Ok(resolution) = reply_rx.await {
+            resolved = resolution.approval_type.is_some();
+            resolved_work_status = resolution.work_status;
+            next_pending_request_id = resolution.next_pending_approval.map(|a| a.id);
+            approval_version = resolution.approval_version;
+        }
+    }
 
-            if let Some(next_pending_request_id) = next_pending_request_id {
-                info!(
-                    component = "approval",
-                    event = "approval.queue.promoted",
-                    session_id = %session_id,
-                    next_request_id = %next_pending_request_id,
-                    "Promoted next queued approval"
-                );
-            }
+    if ctx.state.get_session(&session_id).is_some() && !resolved {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::ApprovalDecisionResult {
+                session_id: session_id.clone(),
+                request_id: request_id.clone(),
+                outcome: "stale".to_string(),
+                active_request_id: next_pending_request_id.clone(),
+                approval_version,
+            },
+        )
+        .await;
+        return;
+    }
+
+    let request_id_for_result = request_id.clone();
+
+    let _ = ctx
+        .state
+        .persist()
+        .send(PersistCommand::ApprovalDecision {
+            session_id: session_id.clone(),
+            request_id: request_id.clone(),
+            decision: "approved".to_string(),
+        })
+        .await;
+
+    if let Some(tx) = ctx.state.get_codex_action_tx(&session_id) {
+        let _ = tx
+            .send(CodexAction::AnswerQuestion {
+                request_id: request_id.clone(),
+                answers: normalized_answers,
+            })
+            .await;
+    } else if let Some(tx) = ctx.state.get_claude_action_tx(&session_id) {
+        // If the client sent a plain text answer, normalize it into the
+        // answers map so the connector always gets structured data.
+        let mut claude_answers = normalized_answers.clone();
+        if claude_answers.is_empty() && !trimmed_answer.is_empty() {
+            let key = question_id.clone().unwrap_or_else(|| "0".to_string());
+            claude_answers.insert(key, vec![trimmed_answer]);
         }
+        if claude_answers.is_empty() {
+            warn!(
+                component = "approval",
+                event = "approval.answer.missing_payload",
+                connection_id = ctx.conn_id,
+                session_id = %session_id,
+                request_id = %request_id,
+                "Question answer request had no usable answer payload"
+            );
+            return;
+        }
+        let _ = tx
+            .send(ClaudeAction::AnswerQuestion {
+                request_id,
+                answers: claude_answers,
+            })
+            .await;
+    }
 
-        ClientMessage::InterruptSession { session_id } => {
+    let _ = ctx
+        .state
+        .persist()
+        .send(PersistCommand::SessionUpdate {
+            id: session_id.clone(),
+            status: None,
+            work_status: Some(resolved_work_status),
+            last_activity_at: None,
+        })
+        .await;
+
+    send_json(
+        ctx.client_tx,
+        ServerMessage::ApprovalDecisionResult {
+            session_id: session_id.clone(),
+            request_id: request_id_for_result,
+            outcome: "applied".to_string(),
+            active_request_id: next_pending_request_id.clone(),
+            approval_version,
+        },
+    )
+    .await;
+
+    if let Some(next_pending_request_id) = next_pending_request_id {
+        info!(
+            component = "approval",
+            event = "approval.queue.promoted",
+            session_id = %session_id,
+            next_request_id = %next_pending_request_id,
+            "Promoted next queued approval"
+        );
+    }
+}
+
+pub(crate) async fn interrupt_session(ctx: &HandlerContext<'_>, session_id: String) {
+    info!(
+        component = "session",
+        event = "session.interrupt.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        "Interrupt session requested"
+    );
+
+    let send_result = if let Some(tx) = ctx.state.get_codex_action_tx(&session_id) {
+        tx.send(CodexAction::Interrupt).await.map_err(|_| "codex")
+    } else if let Some(tx) = ctx.state.get_claude_action_tx(&session_id) {
+        tx.send(ClaudeAction::Interrupt).await.map_err(|_| "claude")
+    } else {
+        Err("none")
+    };
+
+    match send_result {
+        Ok(()) => {
             info!(
                 component = "session",
-                event = "session.interrupt.requested",
-                connection_id = conn_id,
+                event = "session.interrupt.dispatched",
                 session_id = %session_id,
-                "Interrupt session requested"
+                "Interrupt dispatched to connector"
             );
+        }
+        Err(provider) => {
+            warn!(
+                component = "session",
+                event = "session.interrupt.failed",
+                session_id = %session_id,
+                provider = %provider,
+                "Interrupt failed — no active action channel"
+            );
+            // Clean up stale channels
+            if provider == "codex" {
+                ctx.state.remove_codex_action_tx(&session_id);
+            } else if provider == "claude" {
+                ctx.state.remove_claude_action_tx(&session_id);
+            }
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "interrupt_failed".into(),
+                    message: format!(
+                        "Could not interrupt session {}: connector not reachable",
+                        session_id
+                    ),
+                    session_id: Some(session_id.clone()),
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}
 
-            let send_result = if let Some(tx) = state.get_codex_action_tx(&session_id) {
-                tx.send(CodexAction::Interrupt).await.map_err(|_| "codex")
-            } else if let Some(tx) = state.get_claude_action_tx(&session_id) {
-                tx.send(ClaudeAction::Interrupt).await.map_err(|_| "claude")
-            } else {
-                Err("none")
-            };
+pub(crate) async fn compact_context(ctx: &HandlerContext<'_>, session_id: String) {
+    info!(
+        component = "session",
+        event = "session.compact.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        "Compact context requested"
+    );
+
+    if let Some(tx) = ctx.state.get_codex_action_tx(&session_id) {
+        let _ = tx.send(CodexAction::Compact).await;
+    } else if let Some(tx) = ctx.state.get_claude_action_tx(&session_id) {
+        let _ = tx.send(ClaudeAction::Compact).await;
+    }
+}
 
-            match send_result {
-                Ok(()) => {
-                    info!(
-                        component = "session",
-                        event = "session.interrupt.dispatched",
-                        session_id = %session_id,
-                        "Interrupt dispatched to connector"
-                    );
+pub(crate) async fn undo_last_turn(ctx: &HandlerContext<'_>, session_id: String) {
+    info!(
+        component = "session",
+        event = "session.undo.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        "Undo last turn requested"
+    );
+
+    if let Some(tx) = ctx.state.get_codex_action_tx(&session_id) {
+        let _ = tx.send(CodexAction::Undo).await;
+    } else if let Some(tx) = ctx.state.get_claude_action_tx(&session_id) {
+        let _ = tx.send(ClaudeAction::Undo).await;
+    }
+}
+
+pub(crate) async fn rollback_turns(ctx: &HandlerContext<'_>, session_id: String, num_turns: u32) {
+    if num_turns < 1 {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "invalid_argument".into(),
+                message: "num_turns must be >= 1".into(),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    info!(
+        component = "session",
+        event = "session.rollback.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        num_turns = num_turns,
+        "Rollback turns requested"
+    );
+
+    if let Some(tx) = ctx.state.get_codex_action_tx(&session_id) {
+        let _ = tx.send(CodexAction::ThreadRollback { num_turns }).await;
+    } else if let Some(tx) = ctx.state.get_claude_action_tx(&session_id) {
+        // Claude uses rewind_files which needs a user_message_id.
+        // Resolve the Nth user message from the end via session actor.
+        if let Some(actor) = ctx.state.get_session(&session_id) {
+            let (reply_tx, reply_rx) = oneshot::channel();
+            actor
+                .send(SessionCommand::ResolveUserMessageId {
+                    num_turns_from_end: num_turns,
+                    reply: reply_tx,
+                })
+                .await;
+            match reply_rx.await {
+                Ok(Some(user_message_id)) => {
+                    let _ = tx.send(ClaudeAction::RewindFiles { user_message_id }).await;
                 }
-                Err(provider) => {
+                Ok(None) => {
                     warn!(
                         component = "session",
-                        event = "session.interrupt.failed",
+                        event = "session.rollback.no_user_message",
                         session_id = %session_id,
-                        provider = %provider,
-                        "Interrupt failed — no active action channel"
+                        num_turns = num_turns,
+                        "Could not resolve user message for rollback"
                     );
-                    // Clean up stale channels
-                    if provider == "codex" {
-                        state.remove_codex_action_tx(&session_id);
-                    } else if provider == "claude" {
-                        state.remove_claude_action_tx(&session_id);
-                    }
                     send_json(
-                        client_tx,
+                        ctx.client_tx,
                         ServerMessage::Error {
-                            code: "interrupt_failed".into(),
+                            code: "rollback_failed".into(),
                             message: format!(
-                                "Could not interrupt session {}: connector not reachable",
-                                session_id
+                                "Could not find user message {} turns back",
+                                num_turns
                             ),
-                            session_id: Some(session_id.clone()),
+                            session_id: Some(session_id),
+                            error: None,
                         },
                     )
                     .await;
                 }
-            }
-        }
-
-        ClientMessage::CompactContext { session_id } => {
-            info!(
-                component = "session",
-                event = "session.compact.requested",
-                connection_id = conn_id,
-                session_id = %session_id,
-                "Compact context requested"
-            );
-
-            if let Some(tx) = state.get_codex_action_tx(&session_id) {
-                let _ = tx.send(CodexAction::Compact).await;
-            } else if let Some(tx) = state.get_claude_action_tx(&session_id) {
-                let _ = tx.send(ClaudeAction::Compact).await;
-            }
-        }
-
-        ClientMessage::UndoLastTurn { session_id } => {
-            info!(
-                component = "session",
-                event = "session.undo.requested",
-                connection_id = conn_id,
-                session_id = %session_id,
-                "Undo last turn requested"
-            );
-
-            if let Some(tx) = state.get_codex_action_tx(&session_id) {
-                let _ = tx.send(CodexAction::Undo).await;
-            } else if let Some(tx) = state.get_claude_action_tx(&session_id) {
-                let _ = tx.send(ClaudeAction::Undo).await;
-            }
-        }
-
-        ClientMessage::RollbackTurns {
-            session_id,
-            num_turns,
-        } => {
-            if num_turns < 1 {
-                send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "invalid_argument".into(),
-                        message: "num_turns must be >= 1".into(),
-                        session_id: Some(session_id),
-                    },
-                )
-                .await;
-                return;
-            }
-
-            info!(
-                component = "session",
-                event = "session.rollback.requested",
-                connection_id = conn_id,
-                session_id = %session_id,
-                num_turns = num_turns,
-                "Rollback turns requested"
-            );
-
-            if let Some(tx) = state.get_codex_action_tx(&session_id) {
-                let _ = tx.send(CodexAction::ThreadRollback { num_turns }).await;
-            } else if let Some(tx) = state.get_claude_action_tx(&session_id) {
-                // Claude uses rewind_files which needs a user_message_id.
-                // Resolve the Nth user message from the end via session actor.
-                if let Some(actor) = state.get_session(&session_id) {
-                    let (reply_tx, reply_rx) = oneshot::channel();
-                    actor
-                        .send(SessionCommand::ResolveUserMessageId {
-                            num_turns_from_end: num_turns,
-                            reply: reply_tx,
-                        })
-                        .await;
-                    match reply_rx.await {
-                        Ok(Some(user_message_id)) => {
-                            let _ = tx.send(ClaudeAction::RewindFiles { user_message_id }).await;
-                        }
-                        Ok(None) => {
-                            warn!(
-                                component = "session",
-                                event = "session.rollback.no_user_message",
-                                session_id = %session_id,
-                                num_turns = num_turns,
-                                "Could not resolve user message for rollback"
-                            );
-                            send_json(
-                                client_tx,
-                                ServerMessage::Error {
-                                    code: "rollback_failed".into(),
-                                    message: format!(
-                                        "Could not find user message {} turns back",
-                                        num_turns
-                                    ),
-                                    session_id: Some(session_id),
-                                },
-                            )
-                            .await;
-                        }
-                        Err(_) => {
-                            warn!(
-                                component = "session",
-                                event = "session.rollback.actor_closed",
-                                session_id = %session_id,
-                                "Session actor closed during rollback resolution"
-                            );
-                        }
-                    }
+                Err(_) => {
+                    warn!(
+                        component = "session",
+                        event = "session.rollback.actor_closed",
+                        session_id = %session_id,
+                        "Session actor closed during rollback resolution"
+                    );
                 }
             }
         }
+    }
+}
 
-        ClientMessage::StopTask {
-            session_id,
-            task_id,
-        } => {
-            info!(
-                component = "session",
-                event = "session.stop_task.requested",
-                connection_id = conn_id,
-                session_id = %session_id,
-                task_id = %task_id,
-                "Stop task requested"
-            );
-
-            if let Some(tx) = state.get_claude_action_tx(&session_id) {
-                let _ = tx.send(ClaudeAction::StopTask { task_id }).await;
-            } else {
-                send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "not_found".into(),
-                        message: format!(
-                            "Session {} not found or has no active connector",
-                            session_id
-                        ),
-                        session_id: Some(session_id),
-                    },
-                )
-                .await;
-            }
-        }
+pub(crate) async fn stop_task(ctx: &HandlerContext<'_>, session_id: String, task_id: String) {
+    info!(
+        component = "session",
+        event = "session.stop_task.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        task_id = %task_id,
+        "Stop task requested"
+    );
+
+    if let Some(tx) = ctx.state.get_claude_action_tx(&session_id) {
+        let _ = tx.send(ClaudeAction::StopTask { task_id }).await;
+    } else {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "not_found".into(),
+                message: format!(
+                    "Session {} not found or has no active connector",
+                    session_id
+                ),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+    }
+}
 
-        ClientMessage::RewindFiles {
-            session_id,
-            user_message_id,
-        } => {
-            info!(
-                component = "session",
-                event = "session.rewind.requested",
-                connection_id = conn_id,
-                session_id = %session_id,
-                user_message_id = %user_message_id,
-                "Rewind files requested"
-            );
+pub(crate) async fn rewind_files(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    user_message_id: String,
+) {
+    info!(
+        component = "session",
+        event = "session.rewind.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        user_message_id = %user_message_id,
+        "Rewind files requested"
+    );
+
+    if let Some(tx) = ctx.state.get_claude_action_tx(&session_id) {
+        let _ = tx.send(ClaudeAction::RewindFiles { user_message_id }).await;
+    } else {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "not_found".into(),
+                message: format!(
+                    "Session {} not found or has no active connector",
+                    session_id
+                ),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+    }
+}
 
-            if let Some(tx) = state.get_claude_action_tx(&session_id) {
-                let _ = tx.send(ClaudeAction::RewindFiles { user_message_id }).await;
-            } else {
-                send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "not_found".into(),
-                        message: format!(
-                            "Session {} not found or has no active connector",
-                            session_id
-                        ),
-                        session_id: Some(session_id),
-                    },
-                )
-                .await;
-            }
+/// Resolve the attachments sent with `message_id` into the files to hand to
+/// the connector and the prompt text, with any audio transcribed into it, or
+/// tell the client why the message can't be sent. Only Claude takes files.
+async fn resolve_attachments(
+    client_tx: &mpsc::Sender<OutboundMessage>,
+    session_id: &str,
+    attachment_ids: &[String],
+    message_id: &str,
+    connector_takes_files: bool,
+    content: String,
+) -> Option<(Vec<FileAttachment>, String)> {
+    if attachment_ids.is_empty() {
+        return Some((Vec::new(), content));
+    }
+    match transcribe_attachments(
+        session_id,
+        attachment_ids,
+        message_id,
+        connector_takes_files,
+        content,
+    )
+    .await
+    {
+        Ok(resolved) => Some(resolved),
+        Err((code, message)) => {
+            send_json(
+                client_tx,
+                ServerMessage::Error {
+                    code: code.into(),
+                    message,
+                    session_id: Some(session_id.to_string()),
+                    error: None,
+                },
+            )
+            .await;
+            None
         }
+    }
+}
 
-        _ => {}
+async fn transcribe_attachments(
+    session_id: &str,
+    attachment_ids: &[String],
+    message_id: &str,
+    connector_takes_files: bool,
+    content: String,
+) -> Result<(Vec<FileAttachment>, String), (&'static str, String)> {
+    let mut loaded = attachments::load_all(session_id, attachment_ids)
+        .map_err(|err| (err.code(), err.to_string()))?;
+    if !connector_takes_files
+        && loaded
+            .iter()
+            .any(|(attachment, _)| !attachments::is_audio(attachment))
+    {
+        return Err((
+            "attachments_unsupported",
+            "This session's provider doesn't accept file attachments".to_string(),
+        ));
+    }
+
+    let mut transcripts = Vec::new();
+    for (attachment, path) in loaded
+        .iter_mut()
+        .filter(|(attachment, _)| attachments::is_audio(attachment))
+    {
+        let transcript = transcription::transcribe(path, &attachment.name, &attachment.mime_type)
+            .await
+            .map_err(|err| (err.code(), err.to_string()))?;
+        info!(
+            component = "session",
+            event = "session.voice_prompt.transcribed",
+            session_id = %session_id,
+            attachment_id = %attachment.id,
+            transcript_chars = transcript.chars().count(),
+            "Transcribed voice prompt"
+        );
+        attachment.transcript = Some(transcript.clone());
+        transcripts.push(transcript);
     }
+
+    let files = attachments::mark_sent(session_id, loaded, message_id);
+    let content = if transcripts.is_empty() {
+        content
+    } else {
+        transcription::prompt_with_transcripts(&content, &transcripts)
+    };
+    Ok((files, content))
 }
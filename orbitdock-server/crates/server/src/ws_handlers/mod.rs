@@ -1,9 +1,341 @@
+//! WebSocket command dispatch.
+//!
+//! `dispatch` checks a client message against the connection's token scope
+//! and session access, records it in the audit log, and hands it to the module
+//! for its area. Each module routes every `ClientMessage` variant to a handler
+//! fn of its own, so each handler is an independently-sized future that tests
+//! can call directly with a `HandlerContext`.
+
+use std::sync::Arc;
+
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use orbitdock_protocol::{ClientMessage, ServerMessage};
+
+use crate::state::SessionRegistry;
+use crate::websocket::{send_json, OutboundMessage};
+
+pub(crate) mod analytics;
 pub(crate) mod approvals;
+pub(crate) mod attention;
+pub(crate) mod audit;
 pub(crate) mod claude_hooks;
 pub(crate) mod config;
+pub(crate) mod connections;
+pub(crate) mod diagnostics;
+pub(crate) mod editor;
 pub(crate) mod messaging;
+pub(crate) mod push;
+pub(crate) mod replay;
+pub(crate) mod resources;
 pub(crate) mod rest_only;
+pub(crate) mod search;
 pub(crate) mod session_crud;
 pub(crate) mod session_lifecycle;
+pub(crate) mod sharing;
 pub(crate) mod shell;
 pub(crate) mod subscribe;
+pub(crate) mod terminal;
+pub(crate) mod turns;
+
+/// What every handler gets: the connection's outbound channel, the registry,
+/// and the connection id.
+#[derive(Clone, Copy)]
+pub(crate) struct HandlerContext<'a> {
+    pub client_tx: &'a mpsc::Sender<OutboundMessage>,
+    pub state: &'a Arc<SessionRegistry>,
+    pub conn_id: u64,
+}
+
+/// Dispatch a single client WebSocket message.
+pub(crate) async fn dispatch(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    debug!(
+        component = "websocket",
+        event = "ws.message.received",
+        connection_id = ctx.conn_id,
+        message = ?msg,
+        "Received client message"
+    );
+
+    let scope = ctx.state.connection_identity(ctx.conn_id).scope();
+    let required = crate::scopes::required_scope(&msg);
+    if !scope.allows(required) {
+        warn!(
+            component = "websocket",
+            event = "ws.message.scope_denied",
+            connection_id = ctx.conn_id,
+            scope = scope.as_str(),
+            required = required.as_str(),
+            "Client message rejected by token scope"
+        );
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "forbidden".into(),
+                message: format!(
+                    "This token's {} scope does not allow this action",
+                    scope.as_str()
+                ),
+                session_id: None,
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    if let Some(session_id) = crate::session_access::target_session(&msg) {
+        let identity = ctx.state.connection_identity(ctx.conn_id);
+        if !ctx.state.can_access_session(&identity, session_id).await {
+            warn!(
+                component = "websocket",
+                event = "ws.message.session_denied",
+                connection_id = ctx.conn_id,
+                session_id = %session_id,
+                user = ?identity.user(),
+                "Client message rejected by session access"
+            );
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "forbidden".into(),
+                    message: "This session hasn't been shared with you".into(),
+                    session_id: Some(session_id.to_string()),
+                    error: None,
+                },
+            )
+            .await;
+            return;
+        }
+        if let Some((peer, _)) = crate::federation::split_id(session_id) {
+            if !matches!(msg, ClientMessage::SubscribeSession { .. }) {
+                send_json(
+                    ctx.client_tx,
+                    ServerMessage::Error {
+                        code: "peer_session_read_only".into(),
+                        message: format!(
+                            "This session runs on peer {peer}; act on it from that server"
+                        ),
+                        session_id: Some(session_id.to_string()),
+                        error: None,
+                    },
+                )
+                .await;
+                return;
+            }
+        }
+    }
+
+    // Recorded once the handler has run, so the log follows what happened.
+    let audit = crate::audit::audit_entry(&msg);
+
+    match msg {
+        // ── Subscribe ────────────────────────────────────────────
+        ClientMessage::SubscribeList
+        | ClientMessage::SubscribeSession { .. }
+        | ClientMessage::UnsubscribeSession { .. }
+        | ClientMessage::SetComposing { .. } => {
+            match &msg {
+                ClientMessage::SubscribeSession { session_id, .. } => {
+                    ctx.state
+                        .note_session_subscription(ctx.conn_id, session_id, true);
+                }
+                ClientMessage::UnsubscribeSession { session_id } => {
+                    ctx.state
+                        .note_session_subscription(ctx.conn_id, session_id, false);
+                }
+                ClientMessage::SubscribeList => ctx.state.note_list_subscription(ctx.conn_id),
+                _ => {}
+            }
+            subscribe::handle(msg, ctx).await;
+        }
+
+        // ── Session CRUD ─────────────────────────────────────────
+        ClientMessage::CreateSession { .. }
+        | ClientMessage::EndSession { .. }
+        | ClientMessage::RenameSession { .. }
+        | ClientMessage::SetSessionTags { .. }
+        | ClientMessage::SetSessionTicket { .. }
+        | ClientMessage::UpdateSessionConfig { .. }
+        | ClientMessage::UpdateSessionTools { .. }
+        | ClientMessage::ForkSession { .. }
+        | ClientMessage::ForkSessionToWorktree { .. }
+        | ClientMessage::ForkSessionToExistingWorktree { .. } => {
+            session_crud::handle(msg, ctx).await;
+        }
+
+        // ── Session sharing ──────────────────────────────────────
+        ClientMessage::ShareSession { .. } | ClientMessage::GetSessionSharing { .. } => {
+            sharing::handle(msg, ctx).await;
+        }
+
+        // ── Session lifecycle (resume / takeover) ────────────────
+        ClientMessage::ResumeSession { .. } | ClientMessage::TakeoverSession { .. } => {
+            session_lifecycle::handle(msg, ctx).await;
+        }
+
+        // ── Messaging ────────────────────────────────────────────
+        ClientMessage::SendMessage { .. }
+        | ClientMessage::SteerTurn { .. }
+        | ClientMessage::AnswerQuestion { .. }
+        | ClientMessage::InterruptSession { .. }
+        | ClientMessage::CompactContext { .. }
+        | ClientMessage::UndoLastTurn { .. }
+        | ClientMessage::RollbackTurns { .. }
+        | ClientMessage::StopTask { .. }
+        | ClientMessage::RewindFiles { .. } => {
+            if let ClientMessage::SendMessage { session_id, .. }
+            | ClientMessage::SteerTurn { session_id, .. } = &msg
+            {
+                subscribe::set_composing(ctx, session_id, false).await;
+            }
+            messaging::handle(msg, ctx).await;
+        }
+
+        // ── Approvals ────────────────────────────────────────────
+        ClientMessage::ApproveTool { .. }
+        | ClientMessage::ListApprovals { .. }
+        | ClientMessage::DeleteApproval { .. } => {
+            approvals::handle(msg, ctx).await;
+        }
+
+        // ── Audit log ────────────────────────────────────────────
+        ClientMessage::GetAuditLog { .. } => {
+            audit::handle(msg, ctx).await;
+        }
+
+        // ── Usage analytics ──────────────────────────────────────
+        ClientMessage::GetUsageAnalytics { .. } => {
+            analytics::handle(msg, ctx).await;
+        }
+
+        // ── Semantic search ──────────────────────────────────────
+        ClientMessage::SemanticSearch { .. } => {
+            search::handle(msg, ctx).await;
+        }
+
+        // ── Connector process resources ──────────────────────────
+        ClientMessage::GetConnectorStats => {
+            resources::handle(msg, ctx).await;
+        }
+
+        // ── Connected clients and server lifecycle (admin) ───────
+        ClientMessage::ListConnections
+        | ClientMessage::DisconnectConnection { .. }
+        | ClientMessage::ShutdownServer => {
+            connections::handle(msg, ctx).await;
+        }
+
+        // ── Push notifications ───────────────────────────────────
+        ClientMessage::RegisterPushDevice { .. } | ClientMessage::UnregisterPushDevice { .. } => {
+            push::handle(msg, ctx).await;
+        }
+
+        // ── Config (WS-only: SetClientPrimaryClaim, log level, reload, settings) ──
+        ClientMessage::SetClientPrimaryClaim { .. }
+        | ClientMessage::SetLogLevel { .. }
+        | ClientMessage::GetLogLevel
+        | ClientMessage::ReloadConfig
+        | ClientMessage::GetServerConfig { .. }
+        | ClientMessage::SetServerConfig { .. }
+        | ClientMessage::ListEffortLevels { .. }
+        | ClientMessage::GetProviderInfo { .. } => {
+            config::handle(msg, ctx).await;
+        }
+
+        // ── Claude hooks ─────────────────────────────────────────
+        ClientMessage::ClaudeSessionStart { .. }
+        | ClientMessage::ClaudeSessionEnd { .. }
+        | ClientMessage::ClaudeStatusEvent { .. }
+        | ClientMessage::ClaudeToolEvent { .. }
+        | ClientMessage::ClaudeSubagentEvent { .. }
+        | ClientMessage::CodexNotifyEvent { .. }
+        | ClientMessage::GetSubagentTools { .. } => {
+            claude_hooks::handle(msg, ctx).await;
+        }
+
+        // ── Shell execution ──────────────────────────────────────
+        ClientMessage::ExecuteShell { .. }
+        | ClientMessage::CancelShell { .. }
+        | ClientMessage::ListJobs { .. }
+        | ClientMessage::GetJobOutput { .. } => {
+            shell::handle(msg, ctx).await;
+        }
+
+        // ── Interactive terminals ────────────────────────────────
+        ClientMessage::OpenTerminal { .. }
+        | ClientMessage::TerminalInput { .. }
+        | ClientMessage::ResizeTerminal { .. }
+        | ClientMessage::CloseTerminal { .. } => {
+            terminal::handle(msg, ctx).await;
+        }
+
+        // ── Attention inbox ──────────────────────────────────────
+        ClientMessage::GetAttentionItems { .. }
+        | ClientMessage::AcknowledgeAttention { .. }
+        | ClientMessage::SnoozeAttention { .. } => {
+            attention::handle(msg, ctx).await;
+        }
+
+        // ── Editors ──────────────────────────────────────────────
+        ClientMessage::OpenInEditor { .. } | ClientMessage::ListEditors { .. } => {
+            editor::handle(msg, ctx).await;
+        }
+
+        // ── Event journal replay ─────────────────────────────────
+        ClientMessage::ReplaySession { .. } => {
+            replay::handle(msg, ctx).await;
+        }
+
+        // ── Turn timeline ────────────────────────────────────────
+        ClientMessage::GetTurns { .. } => {
+            turns::handle(msg, ctx).await;
+        }
+
+        // ── Session diagnostics ──────────────────────────────────
+        ClientMessage::GetSessionEvents { .. }
+        | ClientMessage::ResyncSession { .. }
+        | ClientMessage::ResyncSessions { .. } => {
+            diagnostics::handle(msg, ctx).await;
+        }
+
+        // ── REST-only stubs ──────────────────────────────────────
+        ClientMessage::BrowseDirectory { .. }
+        | ClientMessage::ListRecentProjects { .. }
+        | ClientMessage::CheckOpenAiKey { .. }
+        | ClientMessage::FetchCodexUsage { .. }
+        | ClientMessage::FetchClaudeUsage { .. }
+        | ClientMessage::SetServerRole { .. }
+        | ClientMessage::SetOpenAiKey { .. }
+        | ClientMessage::ListModels
+        | ClientMessage::ListClaudeModels
+        | ClientMessage::CodexAccountRead { .. }
+        | ClientMessage::CodexLoginChatgptStart
+        | ClientMessage::CodexLoginChatgptCancel { .. }
+        | ClientMessage::CodexAccountLogout
+        | ClientMessage::ListSkills { .. }
+        | ClientMessage::ListRemoteSkills { .. }
+        | ClientMessage::DownloadRemoteSkill { .. }
+        | ClientMessage::ListMcpTools { .. }
+        | ClientMessage::RefreshMcpServers { .. }
+        | ClientMessage::ListSessionMcpServers { .. }
+        | ClientMessage::SetSessionMcpServer { .. }
+        | ClientMessage::RemoveSessionMcpServer { .. }
+        | ClientMessage::ListWorktrees { .. }
+        | ClientMessage::CreateWorktree { .. }
+        | ClientMessage::RemoveWorktree { .. }
+        | ClientMessage::DiscoverWorktrees { .. }
+        | ClientMessage::CreateReviewComment { .. }
+        | ClientMessage::UpdateReviewComment { .. }
+        | ClientMessage::DeleteReviewComment { .. }
+        | ClientMessage::ListReviewComments { .. }
+        | ClientMessage::GetFullMessage { .. } => {
+            rest_only::handle(msg, ctx.client_tx).await;
+        }
+    }
+
+    if let Some(entry) = audit {
+        crate::audit::record(ctx.state, ctx.conn_id, entry).await;
+    }
+}
@@ -0,0 +1,105 @@
+use tokio::sync::mpsc;
+use tracing::info;
+
+use orbitdock_protocol::{ClientMessage, ServerMessage};
+
+use crate::persistence::PersistCommand;
+use crate::push;
+use crate::websocket::{send_json, OutboundMessage};
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    match msg {
+        ClientMessage::RegisterPushDevice {
+            device_token,
+            environment,
+        } => register_push_device(ctx, device_token, environment).await,
+
+        ClientMessage::UnregisterPushDevice { device_token } => {
+            unregister_push_device(ctx, device_token).await
+        }
+
+        _ => unreachable!("push::handle called with non-push message"),
+    }
+}
+
+pub(crate) async fn register_push_device(
+    ctx: &HandlerContext<'_>,
+    device_token: String,
+    environment: Option<String>,
+) {
+    if !push::enabled() {
+        send_error(
+            ctx.client_tx,
+            "push_disabled",
+            "Push notifications are not configured on this server",
+        )
+        .await;
+        return;
+    }
+
+    let device_token = device_token.trim().to_ascii_lowercase();
+    if device_token.is_empty() || !device_token.chars().all(|c| c.is_ascii_hexdigit()) {
+        send_error(
+            ctx.client_tx,
+            "invalid_device_token",
+            "Device token must be the hex APNs token",
+        )
+        .await;
+        return;
+    }
+    let environment = environment.unwrap_or_else(|| "production".to_string());
+    if !push::ENVIRONMENTS.contains(&environment.as_str()) {
+        send_error(
+            ctx.client_tx,
+            "invalid_push_environment",
+            "Push environment must be production or sandbox",
+        )
+        .await;
+        return;
+    }
+
+    info!(
+        component = "push",
+        event = "push.device_registered",
+        connection_id = ctx.conn_id,
+        environment = %environment,
+    );
+    let _ = ctx
+        .state
+        .persist()
+        .send(PersistCommand::PushDeviceUpsert {
+            device_token: device_token.clone(),
+            environment,
+            identity: ctx.state.connection_identity(ctx.conn_id).label(),
+        })
+        .await;
+    send_json(
+        ctx.client_tx,
+        ServerMessage::PushDeviceRegistered { device_token },
+    )
+    .await;
+}
+
+pub(crate) async fn unregister_push_device(ctx: &HandlerContext<'_>, device_token: String) {
+    let _ = ctx
+        .state
+        .persist()
+        .send(PersistCommand::PushDeviceDelete {
+            device_token: device_token.trim().to_ascii_lowercase(),
+        })
+        .await;
+}
+
+async fn send_error(client_tx: &mpsc::Sender<OutboundMessage>, code: &str, message: &str) {
+    send_json(
+        client_tx,
+        ServerMessage::Error {
+            code: code.into(),
+            message: message.into(),
+            session_id: None,
+            error: None,
+        },
+    )
+    .await;
+}
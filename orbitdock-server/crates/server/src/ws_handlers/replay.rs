@@ -0,0 +1,130 @@
+use tracing::{info, warn};
+
+use orbitdock_protocol::{new_id, ClientMessage, ServerMessage};
+
+use crate::event_journal::{self, MAX_REPLAY_EVENTS, MAX_REPLAY_SPEED};
+use crate::websocket::send_json;
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    if let ClientMessage::ReplaySession { session_id, speed } = msg {
+        replay_session(ctx, session_id, speed).await;
+    }
+}
+
+pub(crate) async fn replay_session(ctx: &HandlerContext<'_>, session_id: String, speed: f64) {
+    if !event_journal::valid_speed(speed) {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "invalid_speed".to_string(),
+                message: format!("Replay speed must be above 0 and at most {MAX_REPLAY_SPEED}"),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    let db_path = ctx.state.db_path().clone();
+    let id = session_id.clone();
+    let loaded =
+        tokio::task::spawn_blocking(move || event_journal::load(&db_path, &id, MAX_REPLAY_EVENTS))
+            .await;
+    let events = match loaded {
+        Ok(Ok(events)) if !events.is_empty() => events,
+        Ok(Ok(_)) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "not_found".to_string(),
+                    message: format!("No recorded events for session {session_id}"),
+                    session_id: Some(session_id),
+                    error: None,
+                },
+            )
+            .await;
+            return;
+        }
+        Ok(Err(e)) => {
+            warn!(
+                component = "event_journal",
+                event = "event_journal.load_failed",
+                session_id = %session_id,
+                error = %e,
+                "Could not load the session's event journal"
+            );
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "replay_failed".to_string(),
+                    message: "Could not load the session's event journal".to_string(),
+                    session_id: Some(session_id),
+                    error: None,
+                },
+            )
+            .await;
+            return;
+        }
+        Err(_) => return,
+    };
+
+    let replay_id = new_id();
+    let first = events.first().map_or(0, |event| event.recorded_at_ms);
+    let last = events.last().map_or(0, |event| event.recorded_at_ms);
+    info!(
+        component = "event_journal",
+        event = "event_journal.replay_started",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        replay_id = %replay_id,
+        event_count = events.len(),
+        speed,
+        "Session replay started"
+    );
+    send_json(
+        ctx.client_tx,
+        ServerMessage::ReplayStarted {
+            session_id: session_id.clone(),
+            replay_id: replay_id.clone(),
+            event_count: events.len() as u64,
+            duration_ms: last.saturating_sub(first),
+        },
+    )
+    .await;
+
+    // Paced in the background so the connection keeps handling messages; it
+    // stops early if the client goes away.
+    let client_tx = ctx.client_tx.clone();
+    tokio::spawn(async move {
+        let delays = event_journal::delays(&events, speed);
+        for (event, delay) in events.into_iter().zip(delays) {
+            tokio::time::sleep(delay).await;
+            if client_tx.is_closed() {
+                return;
+            }
+            let Ok(payload) = serde_json::from_str(&event.payload) else {
+                continue;
+            };
+            send_json(
+                &client_tx,
+                ServerMessage::ReplayEvent {
+                    session_id: session_id.clone(),
+                    replay_id: replay_id.clone(),
+                    recorded_at_ms: event.recorded_at_ms,
+                    event: payload,
+                },
+            )
+            .await;
+        }
+        send_json(
+            &client_tx,
+            ServerMessage::ReplayFinished {
+                session_id,
+                replay_id,
+            },
+        )
+        .await;
+    });
+}
@@ -0,0 +1,22 @@
+use orbitdock_protocol::{ClientMessage, ServerMessage};
+
+use crate::websocket::send_json;
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    match msg {
+        ClientMessage::GetConnectorStats => get_connector_stats(ctx).await,
+
+        _ => unreachable!("resources::handle called with non-resource message"),
+    }
+}
+
+pub(crate) async fn get_connector_stats(ctx: &HandlerContext<'_>) {
+    send_json(
+        ctx.client_tx,
+        ServerMessage::ConnectorStats {
+            processes: ctx.state.process_stats(),
+        },
+    )
+    .await;
+}
@@ -115,6 +115,16 @@ pub(crate) async fn handle(msg: ClientMessage, client_tx: &mpsc::Sender<Outbound
             .await;
         }
 
+        // ── Messages ──────────────────────────────────────────────
+        ClientMessage::GetFullMessage { session_id, .. } => {
+            send_rest_only_error(
+                client_tx,
+                "GET /api/sessions/{session_id}/messages/{message_id}",
+                Some(session_id),
+            )
+            .await;
+        }
+
         // ── Codex account ─────────────────────────────────────────
         ClientMessage::CodexAccountRead { .. } => {
             send_rest_only_error(client_tx, "GET /api/codex/account", None).await;
@@ -170,6 +180,30 @@ pub(crate) async fn handle(msg: ClientMessage, client_tx: &mpsc::Sender<Outbound
             )
             .await;
         }
+        ClientMessage::ListSessionMcpServers { session_id } => {
+            send_rest_only_error(
+                client_tx,
+                "GET /api/sessions/{session_id}/mcp/servers",
+                Some(session_id),
+            )
+            .await;
+        }
+        ClientMessage::SetSessionMcpServer { session_id, .. } => {
+            send_rest_only_error(
+                client_tx,
+                "PUT /api/sessions/{session_id}/mcp/servers",
+                Some(session_id),
+            )
+            .await;
+        }
+        ClientMessage::RemoveSessionMcpServer { session_id, .. } => {
+            send_rest_only_error(
+                client_tx,
+                "DELETE /api/sessions/{session_id}/mcp/servers/{name}",
+                Some(session_id),
+            )
+            .await;
+        }
 
         _ => {
             tracing::warn!(?msg, "rest_only::handle called with unexpected variant");
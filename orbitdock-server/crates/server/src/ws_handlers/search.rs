@@ -0,0 +1,41 @@
+use orbitdock_protocol::{ClientMessage, ServerMessage};
+
+use crate::semantic_search::{self, DEFAULT_LIMIT};
+use crate::websocket::send_json;
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    match msg {
+        ClientMessage::SemanticSearch { query, limit } => semantic_search(ctx, query, limit).await,
+
+        _ => {
+            tracing::warn!(?msg, "search::handle called with unexpected variant");
+        }
+    }
+}
+
+pub(crate) async fn semantic_search(ctx: &HandlerContext<'_>, query: String, limit: Option<u32>) {
+    let identity = ctx.state.connection_identity(ctx.conn_id);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    match semantic_search::search(ctx.state, &identity, &query, limit).await {
+        Ok(hits) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::SemanticSearchResults { query, hits },
+            )
+            .await;
+        }
+        Err(e) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: e.code().to_string(),
+                    message: e.to_string(),
+                    session_id: None,
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}
@@ -1,21 +1,23 @@
-use std::sync::Arc;
-
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::oneshot;
 use tracing::{debug, error, info, warn};
 
 use orbitdock_protocol::{
-    ClaudeIntegrationMode, ClientMessage, CodexIntegrationMode, Message, MessageType, Provider,
-    ServerMessage, WorktreeOrigin,
+    ClaudeIntegrationMode, ClientMessage, CodexIntegrationMode, IssueLink, Message, MessageType,
+    Provider, ServerMessage, TicketLink, WorktreeOrigin,
 };
 
+use crate::budgets::OverBudget;
 use crate::claude_session::{ClaudeAction, ClaudeSession};
 use crate::codex_session::{CodexAction, CodexSession};
 use crate::persistence::{load_messages_from_transcript_path, load_worktree_by_id, PersistCommand};
 use crate::session::SessionHandle;
 use crate::session_command::{PersistOp, SessionCommand};
+use crate::session_tags::{self, TagSource};
 use crate::session_utils::claim_codex_thread_for_direct_session;
-use crate::state::SessionRegistry;
-use crate::websocket::{send_json, spawn_broadcast_forwarder, OutboundMessage};
+use crate::tickets;
+use crate::websocket::{send_json, spawn_session_forwarder};
+use crate::ws_handlers::session_lifecycle::claude_connector_failed;
+use crate::ws_handlers::HandlerContext;
 
 fn truncate_messages_before_nth_user_message(
     messages: &[Message],
@@ -60,12 +62,7 @@ fn remap_messages_for_fork(messages: Vec<Message>, new_session_id: &str) -> Vec<
         .collect()
 }
 
-pub(crate) async fn handle(
-    msg: ClientMessage,
-    client_tx: &mpsc::Sender<OutboundMessage>,
-    state: &Arc<SessionRegistry>,
-    conn_id: u64,
-) {
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
     match msg {
         ClientMessage::CreateSession {
             provider,
@@ -78,1154 +75,1658 @@ pub(crate) async fn handle(
             disallowed_tools,
             effort,
             system_prompt: _system_prompt,
-            append_system_prompt: _append_system_prompt,
+            append_system_prompt,
+            confirm_over_budget,
         } => {
-            info!(
-                component = "session",
-                event = "session.create.requested",
-                connection_id = conn_id,
-                provider = %match provider {
-                    Provider::Codex => "codex",
-                    Provider::Claude => "claude",
-                },
-                project_path = %cwd,
-                "Create session requested"
-            );
+            create_session(
+                ctx,
+                provider,
+                cwd,
+                model,
+                approval_policy,
+                sandbox_mode,
+                permission_mode,
+                allowed_tools,
+                disallowed_tools,
+                effort,
+                _system_prompt,
+                append_system_prompt,
+                confirm_over_budget,
+                None,
+            )
+            .await
+        }
 
-            let id = orbitdock_protocol::new_id();
-            let project_name = cwd.split('/').next_back().map(String::from);
-            let git_branch = crate::git::resolve_git_branch(&cwd).await;
+        ClientMessage::EndSession { session_id } => end_session(ctx, session_id).await,
 
-            let mut handle = crate::session::SessionHandle::new(id.clone(), provider, cwd.clone());
-            handle.set_git_branch(git_branch.clone());
+        ClientMessage::RenameSession { session_id, name } => {
+            rename_session(ctx, session_id, name).await
+        }
 
-            if let Some(ref m) = model {
-                handle.set_model(Some(m.clone()));
-            }
+        ClientMessage::SetSessionTags { session_id, tags } => {
+            set_session_tags(ctx, session_id, tags).await
+        }
 
-            if let Some(ref effort_level) = effort {
-                handle.set_effort(Some(effort_level.clone()));
-            }
+        ClientMessage::SetSessionTicket { session_id, ticket } => {
+            set_session_ticket(ctx, session_id, ticket).await
+        }
 
-            if provider == Provider::Codex {
-                handle.set_codex_integration_mode(Some(CodexIntegrationMode::Direct));
-                handle.set_config(approval_policy.clone(), sandbox_mode.clone());
-            } else if provider == Provider::Claude {
-                handle.set_claude_integration_mode(Some(ClaudeIntegrationMode::Direct));
-            }
+        ClientMessage::UpdateSessionConfig {
+            session_id,
+            approval_policy,
+            sandbox_mode,
+            permission_mode,
+        } => {
+            update_session_config(
+                ctx,
+                session_id,
+                approval_policy,
+                sandbox_mode,
+                permission_mode,
+            )
+            .await
+        }
 
-            // Subscribe the creator before handing off handle
-            let rx = handle.subscribe();
-            spawn_broadcast_forwarder(rx, client_tx.clone(), Some(id.clone()));
+        ClientMessage::UpdateSessionTools {
+            session_id,
+            allowed_tools,
+            disallowed_tools,
+        } => update_session_tools(ctx, session_id, allowed_tools, disallowed_tools).await,
 
-            let summary = handle.summary();
-            let snapshot = handle.state();
+        ClientMessage::ForkSessionToWorktree {
+            source_session_id,
+            branch_name,
+            base_branch,
+            nth_user_message,
+        } => {
+            fork_session_to_worktree(
+                ctx,
+                source_session_id,
+                branch_name,
+                base_branch,
+                nth_user_message,
+            )
+            .await
+        }
 
-            // Persist session creation
-            let persist_tx = state.persist().clone();
-            let _ = persist_tx
-                .send(PersistCommand::SessionCreate {
-                    id: id.clone(),
-                    provider,
-                    project_path: cwd.clone(),
-                    project_name,
-                    branch: git_branch,
-                    model: model.clone(),
-                    approval_policy: approval_policy.clone(),
-                    sandbox_mode: sandbox_mode.clone(),
-                    permission_mode: permission_mode.clone(),
-                    forked_from_session_id: None,
-                })
-                .await;
-            if let Some(ref effort_name) = effort {
-                let _ = persist_tx
-                    .send(PersistCommand::EffortUpdate {
-                        session_id: id.clone(),
-                        effort: Some(effort_name.clone()),
-                    })
-                    .await;
-            }
+        ClientMessage::ForkSessionToExistingWorktree {
+            source_session_id,
+            worktree_id,
+            nth_user_message,
+        } => {
+            fork_session_to_existing_worktree(ctx, source_session_id, worktree_id, nth_user_message)
+                .await
+        }
+
+        ClientMessage::ForkSession {
+            source_session_id,
+            nth_user_message,
+            model,
+            approval_policy,
+            sandbox_mode,
+            cwd,
+            permission_mode,
+            allowed_tools,
+            disallowed_tools,
+        } => {
+            fork_session(
+                ctx,
+                source_session_id,
+                nth_user_message,
+                model,
+                approval_policy,
+                sandbox_mode,
+                cwd,
+                permission_mode,
+                allowed_tools,
+                disallowed_tools,
+            )
+            .await
+        }
+
+        _ => {
+            tracing::warn!(?msg, "session_crud::handle called with unexpected variant");
+        }
+    }
+}
 
-            // Notify creator
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn create_session(
+    ctx: &HandlerContext<'_>,
+    provider: Provider,
+    cwd: String,
+    model: Option<String>,
+    approval_policy: Option<String>,
+    sandbox_mode: Option<String>,
+    permission_mode: Option<String>,
+    allowed_tools: Vec<String>,
+    disallowed_tools: Vec<String>,
+    effort: Option<String>,
+    _system_prompt: Option<String>,
+    append_system_prompt: Option<String>,
+    confirm_over_budget: bool,
+    issue: Option<IssueLink>,
+) {
+    info!(
+        component = "session",
+        event = "session.create.requested",
+        connection_id = ctx.conn_id,
+        provider = %match provider {
+            Provider::Codex => "codex",
+            Provider::Claude => "claude",
+        },
+        project_path = %cwd,
+        "Create session requested"
+    );
+
+    let db_path = ctx.state.db_path().clone();
+    let budget_path = cwd.clone();
+    let exhausted =
+        tokio::task::spawn_blocking(move || crate::budgets::exhausted(&db_path, &budget_path))
+            .await
+            .ok()
+            .flatten();
+    if let Some(exhausted) = exhausted {
+        let code = match exhausted.budget.over_budget {
+            OverBudget::Block => Some("budget_exceeded"),
+            OverBudget::Confirm if !confirm_over_budget => Some("budget_confirmation_required"),
+            _ => None,
+        };
+        if let Some(code) = code {
             send_json(
-                client_tx,
-                ServerMessage::SessionSnapshot { session: snapshot },
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: code.into(),
+                    message: exhausted.message(),
+                    session_id: None,
+                    error: None,
+                },
             )
             .await;
+            return;
+        }
+    }
 
-            // Spawn Codex connector if it's a Codex session
-            if provider == Provider::Codex {
-                let session_id = id.clone();
-                let cwd_clone = cwd.clone();
-                let model_clone = model.clone();
-                let approval_clone = approval_policy.clone();
-                let sandbox_clone = sandbox_mode.clone();
-                let connector_timeout = std::time::Duration::from_secs(15);
-                let task_session_id = session_id.clone();
-
-                // Codex startup does a lot of async initialization. Running it in a
-                // dedicated task avoids deep poll stack growth in this large handler.
-                let mut connector_task = tokio::spawn(async move {
-                    CodexSession::new(
-                        task_session_id.clone(),
-                        &cwd_clone,
-                        model_clone.as_deref(),
-                        approval_clone.as_deref(),
-                        sandbox_clone.as_deref(),
-                    )
-                    .await
-                });
+    // Fill gaps from the repo's .orbitdock.toml, then the server
+    // config's connector defaults.
+    let (project, repo) = crate::project_config::load_for_session(&cwd, provider);
+    let config = crate::config_file::current();
+    let defaults = config.connector_defaults(provider);
+    let model = model.or(repo.model).or_else(|| defaults.model.clone());
+    let effort = match effort
+        .or(repo.effort)
+        .or_else(|| defaults.effort.clone())
+        .map(|effort| crate::effort::validate(provider, &effort))
+        .transpose()
+    {
+        Ok(effort) => effort,
+        Err(message) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "invalid_effort".into(),
+                    message,
+                    session_id: None,
+                    error: None,
+                },
+            )
+            .await;
+            return;
+        }
+    };
+    let approval_policy = approval_policy
+        .or(repo.approval_policy)
+        .or_else(|| defaults.approval_policy.clone());
+    let sandbox_mode = sandbox_mode
+        .or(repo.sandbox_mode)
+        .or_else(|| defaults.sandbox_mode.clone());
+    let permission_mode = permission_mode
+        .or(repo.permission_mode)
+        .or_else(|| defaults.permission_mode.clone());
+    let allowed_tools = if allowed_tools.is_empty() {
+        project.allowed_tools
+    } else {
+        allowed_tools
+    };
+    let disallowed_tools = if disallowed_tools.is_empty() {
+        project.disallowed_tools
+    } else {
+        disallowed_tools
+    };
+    let instructions = match (project.instructions, append_system_prompt) {
+        (Some(repo), Some(request)) => Some(format!("{repo}\n\n{request}")),
+        (repo, request) => repo.or(request),
+    };
 
-                let codex_start =
-                    match tokio::time::timeout(connector_timeout, &mut connector_task).await {
-                        Ok(Ok(Ok(codex_session))) => Ok(codex_session),
-                        Ok(Ok(Err(e))) => Err(e.to_string()),
-                        Ok(Err(join_err)) => Err(format!("Connector task panicked: {}", join_err)),
-                        Err(_) => {
-                            connector_task.abort();
-                            Err("Connector creation timed out".to_string())
-                        }
-                    };
-
-                match codex_start {
-                    Ok(codex_session) => {
-                        let thread_id = codex_session.thread_id().to_string();
-                        claim_codex_thread_for_direct_session(
-                            state,
-                            &persist_tx,
-                            &session_id,
-                            &thread_id,
-                            "legacy_codex_thread_row_cleanup",
-                        )
-                        .await;
+    let id = orbitdock_protocol::new_id();
+    let project_name = cwd.split('/').next_back().map(String::from);
+    let git_branch = crate::git::resolve_git_branch(&cwd).await;
 
-                        handle.set_list_tx(state.list_tx());
-                        let (actor_handle, action_tx) = crate::codex_session::start_event_loop(
-                            codex_session,
-                            handle,
-                            persist_tx,
-                            state.clone(),
-                        );
-                        state.add_session_actor(actor_handle);
-                        state.set_codex_action_tx(&session_id, action_tx);
-                        info!(
-                            component = "session",
-                            event = "session.create.connector_started",
-                            connection_id = conn_id,
-                            session_id = %session_id,
-                            "Codex connector started"
-                        );
-                    }
-                    Err(error_message) => {
-                        // Direct sessions that failed to connect have no way to
-                        // receive messages — don't keep as passive (creates ghosts).
-                        let _ = persist_tx
-                            .send(PersistCommand::SessionEnd {
-                                id: session_id.clone(),
-                                reason: "connector_failed".to_string(),
-                            })
-                            .await;
-                        state.broadcast_to_list(ServerMessage::SessionEnded {
-                            session_id: session_id.clone(),
-                            reason: "connector_failed".into(),
-                        });
-                        error!(
-                            component = "session",
-                            event = "session.create.connector_failed",
-                            connection_id = conn_id,
-                            session_id = %session_id,
-                            error = %error_message,
-                            "Failed to start Codex session — ended immediately"
-                        );
-                        send_json(
-                            client_tx,
-                            ServerMessage::Error {
-                                code: "codex_error".into(),
-                                message: error_message,
-                                session_id: Some(session_id),
-                            },
-                        )
-                        .await;
-                    }
-                }
-            } else if provider == Provider::Claude {
-                // Claude direct session
-                let session_id = id.clone();
-                let cwd_clone = cwd.clone();
-                let model_clone = model.clone();
-                let effort_clone = effort.clone();
-
-                match ClaudeSession::new(
-                    session_id.clone(),
-                    &cwd_clone,
-                    model_clone.as_deref(),
-                    None,
-                    permission_mode.as_deref(),
-                    &allowed_tools,
-                    &disallowed_tools,
-                    effort_clone.as_deref(),
-                )
-                .await
-                {
-                    Ok(claude_session) => {
-                        handle.set_list_tx(state.list_tx());
-                        let (actor_handle, action_tx) = crate::claude_session::start_event_loop(
-                            claude_session,
-                            handle,
-                            persist_tx,
-                            state.list_tx(),
-                            state.clone(),
-                        );
+    let mut handle = crate::session::SessionHandle::new(id.clone(), provider, cwd.clone());
+    handle.set_git_branch(git_branch.clone());
 
-                        // Emit permission_mode delta so the Swift UI picks it up.
-                        // Initial SessionSnapshot uses a direct connector state where
-                        // permission_mode has not yet propagated.
-                        if let Some(ref mode) = permission_mode {
-                            let _ = actor_handle
-                                .send(SessionCommand::ApplyDelta {
-                                    changes: orbitdock_protocol::StateChanges {
-                                        permission_mode: Some(Some(mode.clone())),
-                                        ..Default::default()
-                                    },
-                                    persist_op: None,
-                                })
-                                .await;
-                        }
+    if let Some(ref m) = model {
+        handle.set_model(Some(m.clone()));
+    }
 
-                        state.add_session_actor(actor_handle);
-                        state.set_claude_action_tx(&session_id, action_tx.clone());
-                        info!(
-                            component = "session",
-                            event = "session.create.claude_connector_started",
-                            connection_id = conn_id,
-                            session_id = %session_id,
-                            "Claude connector started"
-                        );
+    if let Some(ref effort_level) = effort {
+        handle.set_effort(Some(effort_level.clone()));
+    }
 
-                        // Init-timeout watchdog: if the CLI never sends system/init
-                        // within 45s, the session is a ghost — kill it.
-                        let watchdog_state = state.clone();
-                        let watchdog_session_id = session_id.clone();
-                        let watchdog_action_tx = action_tx;
-                        let watchdog_persist_tx = state.persist().clone();
-                        tokio::spawn(async move {
-                            tokio::time::sleep(std::time::Duration::from_secs(45)).await;
-
-                            // Check if the session registered a Claude SDK ID (set on init)
-                            let has_sdk_id = watchdog_state
-                                .claude_sdk_id_for_session(&watchdog_session_id)
-                                .is_some();
-
-                            if !has_sdk_id {
-                                warn!(
-                                    component = "session",
-                                    event = "session.init_timeout",
-                                    session_id = %watchdog_session_id,
-                                    "Claude session never initialized after 45s — ending ghost"
-                                );
-
-                                // Kill the CLI subprocess
-                                let _ = watchdog_action_tx.send(ClaudeAction::EndSession).await;
-
-                                // End in DB
-                                let _ = watchdog_persist_tx
-                                    .send(PersistCommand::SessionEnd {
-                                        id: watchdog_session_id.clone(),
-                                        reason: "init_timeout".to_string(),
-                                    })
-                                    .await;
-
-                                // Remove from registry and broadcast
-                                watchdog_state.remove_session(&watchdog_session_id);
-                                watchdog_state.broadcast_to_list(ServerMessage::SessionEnded {
-                                    session_id: watchdog_session_id,
-                                    reason: "init_timeout".into(),
-                                });
-                            }
-                        });
-                    }
-                    Err(e) => {
-                        // Direct sessions that failed to connect have no way to
-                        // receive messages — don't keep as passive (creates ghosts).
-                        // End immediately.
-                        let _ = persist_tx
-                            .send(PersistCommand::SessionEnd {
-                                id: session_id.clone(),
-                                reason: "connector_failed".to_string(),
-                            })
-                            .await;
-                        state.broadcast_to_list(ServerMessage::SessionEnded {
-                            session_id: session_id.clone(),
-                            reason: "connector_failed".into(),
-                        });
-                        error!(
-                            component = "session",
-                            event = "session.create.claude_connector_failed",
-                            connection_id = conn_id,
-                            session_id = %session_id,
-                            error = %e,
-                            "Failed to start Claude session — ended immediately"
-                        );
-                        send_json(
-                            client_tx,
-                            ServerMessage::Error {
-                                code: "claude_error".into(),
-                                message: e.to_string(),
-                                session_id: Some(session_id),
-                            },
-                        )
-                        .await;
-                    }
-                }
-            } else {
-                state.add_session(handle);
-            }
+    if provider == Provider::Claude {
+        handle.set_tool_lists(allowed_tools.clone(), disallowed_tools.clone());
+    }
 
-            // Notify list subscribers
-            state.broadcast_to_list(ServerMessage::SessionCreated { session: summary });
-        }
+    if provider == Provider::Codex {
+        handle.set_codex_integration_mode(Some(CodexIntegrationMode::Direct));
+        handle.set_config(approval_policy.clone(), sandbox_mode.clone());
+    } else if provider == Provider::Claude {
+        handle.set_claude_integration_mode(Some(ClaudeIntegrationMode::Direct));
+    }
 
-        ClientMessage::EndSession { session_id } => {
-            info!(
-                component = "session",
-                event = "session.end.requested",
-                connection_id = conn_id,
-                session_id = %session_id,
-                "End session requested"
-            );
+    let created_by = ctx
+        .state
+        .connection_identity(ctx.conn_id)
+        .user()
+        .map(str::to_string);
+    handle.set_created_by(created_by.clone());
+    if let Some(ref issue) = issue {
+        ctx.state.set_session_issue(&id, issue.clone());
+    }
+    handle.set_issue(issue.clone());
 
-            let actor = state.get_session(&session_id);
-            let is_passive_rollout = if let Some(ref actor) = actor {
-                let snap = actor.snapshot();
-                snap.provider == Provider::Codex
-                    && (snap.codex_integration_mode == Some(CodexIntegrationMode::Passive)
-                        || (snap.codex_integration_mode != Some(CodexIntegrationMode::Direct)
-                            && snap.transcript_path.is_some()))
-            } else {
-                false
-            };
+    // Subscribe the creator before handing off handle
+    let rx = handle.subscribe();
+    spawn_session_forwarder(rx, ctx.client_tx.clone());
 
-            let canceled_shells = state.shell_service().cancel_session(&session_id);
-            if canceled_shells > 0 {
-                info!(
-                    component = "shell",
-                    event = "shell.cancel.session_end",
-                    connection_id = conn_id,
-                    session_id = %session_id,
-                    canceled_shells,
-                    "Canceled active shell commands while ending session"
-                );
-            }
+    let summary = handle.summary();
+    let snapshot = handle.state();
 
-            // Tell direct connectors to shutdown gracefully.
-            if !is_passive_rollout {
-                if let Some(tx) = state.get_codex_action_tx(&session_id) {
-                    let _ = tx.send(CodexAction::EndSession).await;
-                } else if let Some(tx) = state.get_claude_action_tx(&session_id) {
-                    let _ = tx.send(ClaudeAction::EndSession).await;
-                }
-            }
+    // Persist session creation
+    let persist_tx = ctx.state.persist().clone();
+    let _ = persist_tx
+        .send(PersistCommand::SessionCreate {
+            id: id.clone(),
+            provider,
+            project_path: cwd.clone(),
+            project_name,
+            branch: git_branch,
+            model: model.clone(),
+            approval_policy: approval_policy.clone(),
+            sandbox_mode: sandbox_mode.clone(),
+            permission_mode: permission_mode.clone(),
+            forked_from_session_id: None,
+        })
+        .await;
+    if let Some(created_by) = created_by {
+        let _ = persist_tx
+            .send(PersistCommand::SetSessionCreatedBy {
+                session_id: id.clone(),
+                created_by,
+            })
+            .await;
+    }
+    if let Some(issue) = issue {
+        let _ = persist_tx
+            .send(PersistCommand::SessionIssueSet {
+                session_id: id.clone(),
+                issue,
+            })
+            .await;
+    }
+    if let Some(ref effort_name) = effort {
+        let _ = persist_tx
+            .send(PersistCommand::EffortUpdate {
+                session_id: id.clone(),
+                effort: Some(effort_name.clone()),
+            })
+            .await;
+    }
+    if provider == Provider::Claude && !(allowed_tools.is_empty() && disallowed_tools.is_empty()) {
+        let _ = persist_tx
+            .send(PersistCommand::ToolListsUpdate {
+                session_id: id.clone(),
+                allowed_tools: allowed_tools.clone(),
+                disallowed_tools: disallowed_tools.clone(),
+            })
+            .await;
+    }
 
-            // Persist session end
-            let _ = state
-                .persist()
-                .send(PersistCommand::SessionEnd {
-                    id: session_id.clone(),
-                    reason: "user_requested".to_string(),
-                })
+    // Notify creator
+    send_json(
+        ctx.client_tx,
+        ServerMessage::SessionSnapshot { session: snapshot },
+    )
+    .await;
+
+    // Spawn Codex connector if it's a Codex session
+    if provider == Provider::Codex {
+        let session_id = id.clone();
+        let cwd_clone = cwd.clone();
+        let model_clone = model.clone();
+        let approval_clone = approval_policy.clone();
+        let sandbox_clone = sandbox_mode.clone();
+        let instructions_clone = instructions.clone();
+        let connector_timeout = std::time::Duration::from_secs(15);
+        let task_session_id = session_id.clone();
+
+        // Codex startup does a lot of async initialization. Running it in a
+        // dedicated task avoids deep poll stack growth in this large handler.
+        let mut connector_task = tokio::spawn(async move {
+            CodexSession::new(
+                task_session_id.clone(),
+                &cwd_clone,
+                model_clone.as_deref(),
+                approval_clone.as_deref(),
+                sandbox_clone.as_deref(),
+                instructions_clone.as_deref(),
+                &[], // mcp_servers
+            )
+            .await
+        });
+
+        let codex_start = match tokio::time::timeout(connector_timeout, &mut connector_task).await {
+            Ok(Ok(Ok(codex_session))) => Ok(codex_session),
+            Ok(Ok(Err(e))) => Err(e.to_string()),
+            Ok(Err(join_err)) => Err(format!("Connector task panicked: {}", join_err)),
+            Err(_) => {
+                connector_task.abort();
+                Err("Connector creation timed out".to_string())
+            }
+        };
+
+        match codex_start {
+            Ok(codex_session) => {
+                let thread_id = codex_session.thread_id().to_string();
+                claim_codex_thread_for_direct_session(
+                    ctx.state,
+                    &persist_tx,
+                    &session_id,
+                    &thread_id,
+                    "legacy_codex_thread_row_cleanup",
+                )
                 .await;
 
-            // Passive rollout sessions must remain in-memory so watcher activity can
-            // reactivate them in-place (ended -> active) without restart.
-            if is_passive_rollout {
+                handle.set_list_tx(ctx.state.list_tx());
+                let (actor_handle, action_tx) = crate::codex_session::start_event_loop(
+                    codex_session,
+                    handle,
+                    persist_tx,
+                    ctx.state.clone(),
+                );
+                ctx.state.add_session_actor(actor_handle);
+                ctx.state.set_codex_action_tx(&session_id, action_tx);
                 info!(
                     component = "session",
-                    event = "session.end.passive_mark_ended",
-                    connection_id = conn_id,
+                    event = "session.create.connector_started",
+                    connection_id = ctx.conn_id,
                     session_id = %session_id,
-                    "Keeping passive rollout session in memory for future watcher reactivation"
+                    "Codex connector started"
                 );
-                if let Some(actor) = actor {
-                    actor.send(SessionCommand::EndLocally).await;
-                }
-                state.broadcast_to_list(ServerMessage::SessionEnded {
-                    session_id,
-                    reason: "user_requested".to_string(),
+                crate::session_diagnostics::record(
+                    ctx.state.persist(),
+                    &session_id,
+                    "connector_spawned",
+                    crate::session_diagnostics::connector(Provider::Codex, "create"),
+                )
+                .await;
+            }
+            Err(error_message) => {
+                // Direct sessions that failed to connect have no way to
+                // receive messages — don't keep as passive (creates ghosts).
+                let _ = persist_tx
+                    .send(PersistCommand::SessionEnd {
+                        id: session_id.clone(),
+                        reason: "connector_failed".to_string(),
+                    })
+                    .await;
+                ctx.state.broadcast_to_list(ServerMessage::SessionEnded {
+                    session_id: session_id.clone(),
+                    reason: "connector_failed".into(),
                 });
-            // Direct sessions are removed from active runtime state.
-            } else if state.remove_session(&session_id).is_some() {
-                info!(
+                error!(
                     component = "session",
-                    event = "session.end.direct_removed",
-                    connection_id = conn_id,
+                    event = "session.create.connector_failed",
+                    connection_id = ctx.conn_id,
                     session_id = %session_id,
-                    "Removed direct session from runtime state"
+                    error = %error_message,
+                    "Failed to start Codex session — ended immediately"
                 );
-                state.broadcast_to_list(ServerMessage::SessionEnded {
-                    session_id,
-                    reason: "user_requested".to_string(),
-                });
+                crate::session_diagnostics::record(
+                    ctx.state.persist(),
+                    &session_id,
+                    "connector_failed",
+                    crate::session_diagnostics::connector_error(
+                        Provider::Codex,
+                        "create",
+                        &error_message,
+                    ),
+                )
+                .await;
+                send_json(
+                    ctx.client_tx,
+                    ServerMessage::Error {
+                        code: "codex_error".into(),
+                        message: error_message,
+                        session_id: Some(session_id),
+                        error: None,
+                    },
+                )
+                .await;
             }
         }
+    } else if provider == Provider::Claude {
+        // Claude direct session
+        let session_id = id.clone();
+        let cwd_clone = cwd.clone();
+        let model_clone = model.clone();
+        let effort_clone = effort.clone();
+
+        match ClaudeSession::new(
+            session_id.clone(),
+            &cwd_clone,
+            model_clone.as_deref(),
+            None,
+            permission_mode.as_deref(),
+            &allowed_tools,
+            &disallowed_tools,
+            effort_clone.as_deref(),
+            instructions.as_deref(),
+            &[], // mcp_servers
+        )
+        .await
+        {
+            Ok(claude_session) => {
+                handle.set_list_tx(ctx.state.list_tx());
+                let (actor_handle, action_tx) = crate::claude_session::start_event_loop(
+                    claude_session,
+                    handle,
+                    persist_tx,
+                    ctx.state.list_tx(),
+                    ctx.state.clone(),
+                );
 
-        ClientMessage::RenameSession { session_id, name } => {
-            info!(
-                component = "session",
-                event = "session.rename.requested",
-                connection_id = conn_id,
-                session_id = %session_id,
-                has_name = name.is_some(),
-                "Rename session requested"
-            );
-
-            if let Some(actor) = state.get_session(&session_id) {
-                let (sum_tx, sum_rx) = oneshot::channel();
-                actor
-                    .send(SessionCommand::SetCustomNameAndNotify {
-                        name: name.clone(),
-                        persist_op: Some(PersistOp::SetCustomName {
-                            session_id: session_id.clone(),
-                            name: name.clone(),
-                        }),
-                        reply: sum_tx,
-                    })
-                    .await;
-                if let Ok(summary) = sum_rx.await {
-                    state.broadcast_to_list(ServerMessage::SessionCreated { session: summary });
-                }
-            }
-
-            if let Some(tx) = state.get_codex_action_tx(&session_id) {
-                if let Some(ref n) = name {
-                    let _ = tx
-                        .send(CodexAction::SetThreadName { name: n.clone() })
+                // Emit permission_mode delta so the Swift UI picks it up.
+                // Initial SessionSnapshot uses a direct connector state where
+                // permission_mode has not yet propagated.
+                if let Some(ref mode) = permission_mode {
+                    let _ = actor_handle
+                        .send(SessionCommand::ApplyDelta {
+                            changes: orbitdock_protocol::StateChanges {
+                                permission_mode: Some(Some(mode.clone())),
+                                ..Default::default()
+                            },
+                            persist_op: None,
+                        })
                         .await;
                 }
-            }
-        }
 
-        ClientMessage::UpdateSessionConfig {
-            session_id,
-            approval_policy,
-            sandbox_mode,
-            permission_mode,
-        } => {
-            info!(
-                component = "session",
-                event = "session.config.update_requested",
-                connection_id = conn_id,
-                session_id = %session_id,
-                approval_policy = ?approval_policy,
-                sandbox_mode = ?sandbox_mode,
-                permission_mode = ?permission_mode,
-                "Session config update requested"
-            );
+                ctx.state.add_session_actor(actor_handle);
+                ctx.state
+                    .set_claude_action_tx(&session_id, action_tx.clone());
+                info!(
+                    component = "session",
+                    event = "session.create.claude_connector_started",
+                    connection_id = ctx.conn_id,
+                    session_id = %session_id,
+                    "Claude connector started"
+                );
+                crate::session_diagnostics::record(
+                    ctx.state.persist(),
+                    &session_id,
+                    "connector_spawned",
+                    crate::session_diagnostics::connector(Provider::Claude, "create"),
+                )
+                .await;
 
-            if let Some(actor) = state.get_session(&session_id) {
-                actor
-                    .send(SessionCommand::ApplyDelta {
-                        changes: orbitdock_protocol::StateChanges {
-                            approval_policy: Some(approval_policy.clone()),
-                            sandbox_mode: Some(sandbox_mode.clone()),
-                            permission_mode: Some(permission_mode.clone()),
-                            ..Default::default()
-                        },
-                        persist_op: Some(PersistOp::SetSessionConfig {
-                            session_id: session_id.clone(),
-                            approval_policy: approval_policy.clone(),
-                            sandbox_mode: sandbox_mode.clone(),
-                            permission_mode: permission_mode.clone(),
-                        }),
-                    })
-                    .await;
+                // Init-timeout watchdog: if the CLI never sends system/init
+                // within 45s, the session is a ghost — kill it.
+                let watchdog_state = ctx.state.clone();
+                let watchdog_session_id = session_id.clone();
+                let watchdog_action_tx = action_tx;
+                let watchdog_persist_tx = ctx.state.persist().clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(std::time::Duration::from_secs(45)).await;
+
+                    // Check if the session registered a Claude SDK ID (set on init)
+                    let has_sdk_id = watchdog_state
+                        .claude_sdk_id_for_session(&watchdog_session_id)
+                        .is_some();
+
+                    if !has_sdk_id {
+                        warn!(
+                            component = "session",
+                            event = "session.init_timeout",
+                            session_id = %watchdog_session_id,
+                            "Claude session never initialized after 45s — ending ghost"
+                        );
+                        crate::session_diagnostics::record(
+                            &watchdog_persist_tx,
+                            &watchdog_session_id,
+                            "connector_timed_out",
+                            crate::session_diagnostics::connector(Provider::Claude, "create"),
+                        )
+                        .await;
 
-                let (sum_tx, sum_rx) = oneshot::channel();
-                actor
-                    .send(SessionCommand::GetSummary { reply: sum_tx })
-                    .await;
-                if let Ok(summary) = sum_rx.await {
-                    state.broadcast_to_list(ServerMessage::SessionCreated { session: summary });
-                }
-            }
+                        // Kill the CLI subprocess
+                        let _ = watchdog_action_tx.send(ClaudeAction::EndSession).await;
 
-            // Send permission_mode to Claude sessions mid-flight
-            if let Some(ref mode) = permission_mode {
-                if let Some(tx) = state.get_claude_action_tx(&session_id) {
-                    let _ = tx
-                        .send(ClaudeAction::SetPermissionMode { mode: mode.clone() })
-                        .await;
-                }
-            }
+                        // End in DB
+                        let _ = watchdog_persist_tx
+                            .send(PersistCommand::SessionEnd {
+                                id: watchdog_session_id.clone(),
+                                reason: "init_timeout".to_string(),
+                            })
+                            .await;
 
-            if let Some(tx) = state.get_codex_action_tx(&session_id) {
-                let _ = tx
-                    .send(CodexAction::UpdateConfig {
-                        approval_policy,
-                        sandbox_mode,
-                        permission_mode,
+                        // Remove from registry and broadcast
+                        watchdog_state.remove_session(&watchdog_session_id);
+                        watchdog_state.broadcast_to_list(ServerMessage::SessionEnded {
+                            session_id: watchdog_session_id,
+                            reason: "init_timeout".into(),
+                        });
+                    }
+                });
+            }
+            Err(e) => {
+                // Direct sessions that failed to connect have no way to
+                // receive messages — don't keep as passive (creates ghosts).
+                // End immediately.
+                let _ = persist_tx
+                    .send(PersistCommand::SessionEnd {
+                        id: session_id.clone(),
+                        reason: "connector_failed".to_string(),
                     })
                     .await;
-            }
-        }
-
-        ClientMessage::ForkSessionToWorktree {
-            source_session_id,
-            branch_name,
-            base_branch,
-            nth_user_message,
-        } => {
-            let trimmed_branch = branch_name.trim().to_string();
-            if trimmed_branch.is_empty() {
+                ctx.state.broadcast_to_list(ServerMessage::SessionEnded {
+                    session_id: session_id.clone(),
+                    reason: "connector_failed".into(),
+                });
+                error!(
+                    component = "session",
+                    event = "session.create.claude_connector_failed",
+                    connection_id = ctx.conn_id,
+                    session_id = %session_id,
+                    error = %e,
+                    "Failed to start Claude session — ended immediately"
+                );
+                crate::session_diagnostics::record(
+                    ctx.state.persist(),
+                    &session_id,
+                    "connector_failed",
+                    crate::session_diagnostics::connector_error(
+                        Provider::Claude,
+                        "create",
+                        &e.to_string(),
+                    ),
+                )
+                .await;
                 send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "worktree_create_invalid_input".into(),
-                        message: "Branch name is required".into(),
-                        session_id: Some(source_session_id),
-                    },
+                    ctx.client_tx,
+                    claude_connector_failed(ctx.state, e.to_string(), &session_id).await,
                 )
                 .await;
-                return;
             }
+        }
+    } else {
+        ctx.state.add_session(handle);
+    }
 
-            let source_snapshot = match state.get_session(&source_session_id) {
-                Some(session) => session.snapshot(),
-                None => {
-                    send_json(
-                        client_tx,
-                        ServerMessage::Error {
-                            code: "not_found".into(),
-                            message: format!("Source session {} not found", source_session_id),
-                            session_id: Some(source_session_id),
-                        },
-                    )
-                    .await;
-                    return;
-                }
-            };
+    // Notify list subscribers
+    ctx.state
+        .broadcast_to_list(ServerMessage::SessionCreated { session: summary });
+}
 
-            let repo_root = if let Some(root) = source_snapshot
-                .repository_root
-                .clone()
-                .map(|r| r.trim().to_string())
-                .filter(|r| !r.is_empty())
-            {
-                root
-            } else if let Some(git_info) =
-                crate::git::resolve_git_info(&source_snapshot.project_path).await
-            {
-                git_info.common_dir_root
-            } else {
-                source_snapshot.project_path.clone()
-            };
+pub(crate) async fn end_session(ctx: &HandlerContext<'_>, session_id: String) {
+    info!(
+        component = "session",
+        event = "session.end.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        "End session requested"
+    );
+
+    let actor = ctx.state.get_session(&session_id);
+    let is_passive_rollout = if let Some(ref actor) = actor {
+        let snap = actor.snapshot();
+        snap.provider == Provider::Codex
+            && (snap.codex_integration_mode == Some(CodexIntegrationMode::Passive)
+                || (snap.codex_integration_mode != Some(CodexIntegrationMode::Direct)
+                    && snap.transcript_path.is_some()))
+    } else {
+        false
+    };
 
-            let worktree_summary = match crate::worktree_service::create_tracked_worktree(
-                state,
-                &repo_root,
-                &trimmed_branch,
-                base_branch.as_deref(),
-                WorktreeOrigin::User,
-            )
-            .await
-            {
-                Ok(summary) => summary,
-                Err(err) => {
-                    send_json(
-                        client_tx,
-                        ServerMessage::Error {
-                            code: "worktree_create_failed".into(),
-                            message: err,
-                            session_id: Some(source_session_id),
-                        },
-                    )
-                    .await;
-                    return;
-                }
-            };
-            let fork_worktree_path = worktree_summary.worktree_path.clone();
+    let canceled_shells = ctx.state.shell_service().cancel_session(&session_id);
+    if canceled_shells > 0 {
+        info!(
+            component = "shell",
+            event = "shell.cancel.session_end",
+            connection_id = ctx.conn_id,
+            session_id = %session_id,
+            canceled_shells,
+            "Canceled active shell commands while ending session"
+        );
+    }
+    let closed_terminals = ctx.state.terminal_service().close_session(&session_id);
+    if closed_terminals > 0 {
+        info!(
+            component = "terminal",
+            event = "terminal.close.session_end",
+            connection_id = ctx.conn_id,
+            session_id = %session_id,
+            closed_terminals,
+            "Closed terminals while ending session"
+        );
+    }
 
-            state.broadcast_to_list(ServerMessage::WorktreeCreated {
-                request_id: String::new(),
-                worktree: worktree_summary,
-            });
+    // Tell direct connectors to shutdown gracefully.
+    if !is_passive_rollout {
+        if let Some(tx) = ctx.state.get_codex_action_tx(&session_id) {
+            let _ = tx.send(CodexAction::EndSession).await;
+        } else if let Some(tx) = ctx.state.get_claude_action_tx(&session_id) {
+            let _ = tx.send(ClaudeAction::EndSession).await;
+        }
+    }
 
-            Box::pin(handle(
-                ClientMessage::ForkSession {
-                    source_session_id,
-                    nth_user_message,
-                    model: None,
-                    approval_policy: None,
-                    sandbox_mode: None,
-                    cwd: Some(fork_worktree_path),
-                    permission_mode: None,
-                    allowed_tools: Vec::new(),
-                    disallowed_tools: Vec::new(),
-                },
-                client_tx,
-                state,
-                conn_id,
-            ))
+    // Persist session end
+    let _ = ctx
+        .state
+        .persist()
+        .send(PersistCommand::SessionEnd {
+            id: session_id.clone(),
+            reason: "user_requested".to_string(),
+        })
+        .await;
+
+    // Passive rollout sessions must remain in-memory so watcher activity can
+    // reactivate them in-place (ended -> active) without restart.
+    if is_passive_rollout {
+        info!(
+            component = "session",
+            event = "session.end.passive_mark_ended",
+            connection_id = ctx.conn_id,
+            session_id = %session_id,
+            "Keeping passive rollout session in memory for future watcher reactivation"
+        );
+        if let Some(actor) = actor {
+            actor.send(SessionCommand::EndLocally).await;
+        }
+        ctx.state.broadcast_to_list(ServerMessage::SessionEnded {
+            session_id,
+            reason: "user_requested".to_string(),
+        });
+    // Direct sessions are removed from active runtime state.
+    } else if ctx.state.remove_session(&session_id).is_some() {
+        info!(
+            component = "session",
+            event = "session.end.direct_removed",
+            connection_id = ctx.conn_id,
+            session_id = %session_id,
+            "Removed direct session from runtime state"
+        );
+        ctx.state.broadcast_to_list(ServerMessage::SessionEnded {
+            session_id,
+            reason: "user_requested".to_string(),
+        });
+    }
+}
+
+pub(crate) async fn rename_session(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    name: Option<String>,
+) {
+    info!(
+        component = "session",
+        event = "session.rename.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        has_name = name.is_some(),
+        "Rename session requested"
+    );
+
+    if let Some(actor) = ctx.state.get_session(&session_id) {
+        let (sum_tx, sum_rx) = oneshot::channel();
+        actor
+            .send(SessionCommand::SetCustomNameAndNotify {
+                name: name.clone(),
+                persist_op: Some(PersistOp::SetCustomName {
+                    session_id: session_id.clone(),
+                    name: name.clone(),
+                }),
+                reply: sum_tx,
+            })
             .await;
+        if let Ok(summary) = sum_rx.await {
+            ctx.state
+                .broadcast_to_list(ServerMessage::SessionCreated { session: summary });
         }
+    }
 
-        ClientMessage::ForkSessionToExistingWorktree {
-            source_session_id,
-            worktree_id,
-            nth_user_message,
-        } => {
-            let source_snapshot = match state.get_session(&source_session_id) {
-                Some(session) => session.snapshot(),
-                None => {
-                    send_json(
-                        client_tx,
-                        ServerMessage::Error {
-                            code: "not_found".into(),
-                            message: format!("Source session {} not found", source_session_id),
-                            session_id: Some(source_session_id),
-                        },
-                    )
-                    .await;
-                    return;
-                }
-            };
+    if let Some(tx) = ctx.state.get_codex_action_tx(&session_id) {
+        if let Some(ref n) = name {
+            let _ = tx
+                .send(CodexAction::SetThreadName { name: n.clone() })
+                .await;
+        }
+    }
+}
 
-            let source_repo_root = if let Some(root) = source_snapshot
-                .repository_root
-                .clone()
-                .map(|r| r.trim().trim_end_matches('/').to_string())
-                .filter(|r| !r.is_empty())
-            {
-                root
-            } else if let Some(git_info) =
-                crate::git::resolve_git_info(&source_snapshot.project_path).await
-            {
-                git_info.common_dir_root.trim_end_matches('/').to_string()
-            } else {
-                source_snapshot
-                    .project_path
-                    .trim()
-                    .trim_end_matches('/')
-                    .to_string()
-            };
+pub(crate) async fn set_session_tags(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    tags: Vec<String>,
+) {
+    let tags = session_tags::normalize(tags);
+    info!(
+        component = "session",
+        event = "session.tags.set",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        tags = %tags.join(","),
+        "Session tags set"
+    );
+    session_tags::set(ctx.state, &session_id, tags, TagSource::Manual).await;
+}
 
-            let target_worktree = match load_worktree_by_id(state.db_path(), &worktree_id) {
-                Some(row) => row,
-                None => {
-                    send_json(
-                        client_tx,
-                        ServerMessage::Error {
-                            code: "worktree_not_found".into(),
-                            message: format!("Worktree {} not found", worktree_id),
-                            session_id: Some(source_session_id),
-                        },
-                    )
-                    .await;
-                    return;
-                }
-            };
+pub(crate) async fn set_session_ticket(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    ticket: Option<TicketLink>,
+) {
+    let config = crate::config_file::current();
+    let ticket = match ticket
+        .map(|ticket| tickets::normalize(ticket, &config.tickets))
+        .transpose()
+    {
+        Ok(ticket) => ticket,
+        Err(e) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "invalid_ticket".into(),
+                    message: e.to_string(),
+                    session_id: Some(session_id),
+                    error: None,
+                },
+            )
+            .await;
+            return;
+        }
+    };
+    info!(
+        component = "session",
+        event = "session.ticket.set",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        ticket = ticket.as_ref().map(|t| t.id.as_str()).unwrap_or(""),
+        "Session ticket set"
+    );
+    tickets::set(ctx.state, &session_id, ticket).await;
+}
 
-            if target_worktree.status == "removed" {
-                send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "worktree_not_found".into(),
-                        message: "Selected worktree has been removed".into(),
-                        session_id: Some(source_session_id),
-                    },
-                )
-                .await;
-                return;
-            }
+pub(crate) async fn update_session_config(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    approval_policy: Option<String>,
+    sandbox_mode: Option<String>,
+    permission_mode: Option<String>,
+) {
+    info!(
+        component = "session",
+        event = "session.config.update_requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        approval_policy = ?approval_policy,
+        sandbox_mode = ?sandbox_mode,
+        permission_mode = ?permission_mode,
+        "Session config update requested"
+    );
+
+    if let Some(actor) = ctx.state.get_session(&session_id) {
+        actor
+            .send(SessionCommand::ApplyDelta {
+                changes: orbitdock_protocol::StateChanges {
+                    approval_policy: Some(approval_policy.clone()),
+                    sandbox_mode: Some(sandbox_mode.clone()),
+                    permission_mode: Some(permission_mode.clone()),
+                    ..Default::default()
+                },
+                persist_op: Some(PersistOp::SetSessionConfig {
+                    session_id: session_id.clone(),
+                    approval_policy: approval_policy.clone(),
+                    sandbox_mode: sandbox_mode.clone(),
+                    permission_mode: permission_mode.clone(),
+                }),
+            })
+            .await;
 
-            let target_repo_root = target_worktree
-                .repo_root
-                .trim()
-                .trim_end_matches('/')
-                .to_string();
-            if target_repo_root != source_repo_root {
-                send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "worktree_repo_mismatch".into(),
-                        message: "Selected worktree belongs to a different repository".into(),
-                        session_id: Some(source_session_id),
-                    },
-                )
-                .await;
-                return;
-            }
+        let (sum_tx, sum_rx) = oneshot::channel();
+        actor
+            .send(SessionCommand::GetSummary { reply: sum_tx })
+            .await;
+        if let Ok(summary) = sum_rx.await {
+            ctx.state
+                .broadcast_to_list(ServerMessage::SessionCreated { session: summary });
+        }
+    }
 
-            if !crate::git::worktree_exists_on_disk(&target_worktree.worktree_path).await {
-                send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "worktree_missing".into(),
-                        message: "Selected worktree no longer exists on disk".into(),
-                        session_id: Some(source_session_id),
-                    },
-                )
+    // Send permission_mode to Claude sessions mid-flight
+    if let Some(ref mode) = permission_mode {
+        if let Some(tx) = ctx.state.get_claude_action_tx(&session_id) {
+            let _ = tx
+                .send(ClaudeAction::SetPermissionMode { mode: mode.clone() })
                 .await;
-                return;
-            }
+        }
+    }
 
-            Box::pin(handle(
-                ClientMessage::ForkSession {
-                    source_session_id,
-                    nth_user_message,
-                    model: None,
-                    approval_policy: None,
-                    sandbox_mode: None,
-                    cwd: Some(target_worktree.worktree_path),
-                    permission_mode: None,
-                    allowed_tools: Vec::new(),
-                    disallowed_tools: Vec::new(),
-                },
-                client_tx,
-                state,
-                conn_id,
-            ))
+    if let Some(tx) = ctx.state.get_codex_action_tx(&session_id) {
+        let _ = tx
+            .send(CodexAction::UpdateConfig {
+                approval_policy,
+                sandbox_mode,
+                permission_mode,
+            })
             .await;
+    }
+}
+
+pub(crate) async fn update_session_tools(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    allowed_tools: Vec<String>,
+    disallowed_tools: Vec<String>,
+) {
+    let Some(actor) = ctx.state.get_session(&session_id) else {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "not_found".into(),
+                message: format!("Session {session_id} not found"),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    };
+    if actor.snapshot().provider != Provider::Claude {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "tool_lists_unsupported".into(),
+                message: "Codex sessions have no tool lists; use update_session_config to change approval policy or sandbox".into(),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    let normalize = |tools: Vec<String>| {
+        let mut normalized: Vec<String> = Vec::new();
+        for tool in tools {
+            let tool = tool.trim();
+            if !tool.is_empty() && !normalized.iter().any(|t| t == tool) {
+                normalized.push(tool.to_string());
+            }
         }
+        normalized
+    };
+    let allowed_tools = normalize(allowed_tools);
+    let disallowed_tools = normalize(disallowed_tools);
+    info!(
+        component = "session",
+        event = "session.tools.update_requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        allowed_tools = %allowed_tools.join(","),
+        disallowed_tools = %disallowed_tools.join(","),
+        "Session tool lists update requested"
+    );
+
+    let _ = ctx
+        .state
+        .persist()
+        .send(PersistCommand::ToolListsUpdate {
+            session_id: session_id.clone(),
+            allowed_tools: allowed_tools.clone(),
+            disallowed_tools: disallowed_tools.clone(),
+        })
+        .await;
+    actor
+        .send(SessionCommand::ApplyDelta {
+            changes: orbitdock_protocol::StateChanges {
+                allowed_tools: Some(allowed_tools.clone()),
+                disallowed_tools: Some(disallowed_tools.clone()),
+                ..Default::default()
+            },
+            persist_op: None,
+        })
+        .await;
+
+    // The running CLI takes the lists as permission rules; a restart or
+    // resume passes them as --allowedTools / --disallowedTools instead.
+    if let Some(tx) = ctx.state.get_claude_action_tx(&session_id) {
+        let _ = tx
+            .send(ClaudeAction::ApplyFlagSettings {
+                settings: serde_json::json!({
+                    "permissions": {
+                        "allow": allowed_tools,
+                        "deny": disallowed_tools,
+                    }
+                }),
+            })
+            .await;
+    }
+}
 
-        ClientMessage::ForkSession {
-            source_session_id,
-            nth_user_message,
-            model,
-            approval_policy,
-            sandbox_mode,
-            cwd,
-            permission_mode,
-            allowed_tools,
-            disallowed_tools,
-        } => {
-            info!(
-                component = "session",
-                event = "session.fork.requested",
-                connection_id = conn_id,
-                source_session_id = %source_session_id,
-                nth_user_message = ?nth_user_message,
-                "Fork session requested"
-            );
+pub(crate) async fn fork_session_to_worktree(
+    ctx: &HandlerContext<'_>,
+    source_session_id: String,
+    branch_name: String,
+    base_branch: Option<String>,
+    nth_user_message: Option<u32>,
+) {
+    let trimmed_branch = branch_name.trim().to_string();
+    if trimmed_branch.is_empty() {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "worktree_create_invalid_input".into(),
+                message: "Branch name is required".into(),
+                session_id: Some(source_session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
 
-            let source_snapshot = state.get_session(&source_session_id).map(|s| s.snapshot());
+    let source_snapshot = match ctx.state.get_session(&source_session_id) {
+        Some(session) => session.snapshot(),
+        None => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "not_found".into(),
+                    message: format!("Source session {} not found", source_session_id),
+                    session_id: Some(source_session_id),
+                    error: None,
+                },
+            )
+            .await;
+            return;
+        }
+    };
 
-            // Determine source session's provider
-            let source_provider = source_snapshot.as_ref().map(|s| s.provider);
+    let repo_root = if let Some(root) = source_snapshot
+        .repository_root
+        .clone()
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+    {
+        root
+    } else if let Some(git_info) = crate::git::resolve_git_info(&source_snapshot.project_path).await
+    {
+        git_info.common_dir_root
+    } else {
+        source_snapshot.project_path.clone()
+    };
 
-            let source_cwd = source_snapshot.as_ref().map(|s| s.project_path.clone());
+    let worktree_summary = match crate::worktree_service::create_tracked_worktree(
+        ctx.state,
+        &repo_root,
+        &trimmed_branch,
+        base_branch.as_deref(),
+        WorktreeOrigin::User,
+    )
+    .await
+    {
+        Ok(summary) => summary,
+        Err(err) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "worktree_create_failed".into(),
+                    message: err,
+                    session_id: Some(source_session_id),
+                    error: None,
+                },
+            )
+            .await;
+            return;
+        }
+    };
+    let fork_worktree_path = worktree_summary.worktree_path.clone();
 
-            let source_model = model
-                .clone()
-                .or_else(|| source_snapshot.as_ref().and_then(|s| s.model.clone()));
-            let source_approval_policy = source_snapshot
-                .as_ref()
-                .and_then(|s| s.approval_policy.clone());
-            let source_sandbox_mode = source_snapshot
-                .as_ref()
-                .and_then(|s| s.sandbox_mode.clone());
-            let effective_approval_policy = approval_policy.clone().or(source_approval_policy);
-            let effective_sandbox_mode = sandbox_mode.clone().or(source_sandbox_mode);
-
-            match source_provider {
-                Some(Provider::Claude) => {
-                    // ── Claude fork: spawn a new CLI, copy messages ──
-                    let effective_cwd = cwd
-                        .clone()
-                        .or(source_cwd)
-                        .unwrap_or_else(|| ".".to_string());
-                    let project_name = effective_cwd.split('/').next_back().map(String::from);
-                    let fork_branch = crate::git::resolve_git_branch(&effective_cwd).await;
-
-                    // Spawn new Claude CLI session (starts fresh — no message copying)
-                    let new_id = orbitdock_protocol::new_id();
-                    match ClaudeSession::new(
-                        new_id.clone(),
-                        &effective_cwd,
-                        source_model.as_deref(),
-                        None,
-                        permission_mode.as_deref(),
-                        &allowed_tools,
-                        &disallowed_tools,
-                        None, // effort
-                    )
-                    .await
-                    {
-                        Ok(claude_session) => {
-                            let mut handle = SessionHandle::new(
-                                new_id.clone(),
-                                Provider::Claude,
-                                effective_cwd.clone(),
-                            );
-                            handle.set_git_branch(fork_branch.clone());
-                            handle.set_claude_integration_mode(Some(ClaudeIntegrationMode::Direct));
-                            handle.set_forked_from(source_session_id.clone());
-                            if let Some(ref m) = source_model {
-                                handle.set_model(Some(m.clone()));
-                            }
-
-                            let rx = handle.subscribe();
-                            spawn_broadcast_forwarder(rx, client_tx.clone(), Some(new_id.clone()));
-
-                            let summary = handle.summary();
-                            let snapshot = handle.state();
-
-                            let persist_tx = state.persist().clone();
-                            let _ = persist_tx
-                                .send(PersistCommand::SessionCreate {
-                                    id: new_id.clone(),
-                                    provider: Provider::Claude,
-                                    project_path: effective_cwd,
-                                    project_name,
-                                    branch: fork_branch,
-                                    model: source_model,
-                                    approval_policy: None,
-                                    sandbox_mode: None,
-                                    permission_mode: permission_mode.clone(),
-                                    forked_from_session_id: Some(source_session_id.clone()),
-                                })
-                                .await;
-
-                            handle.set_list_tx(state.list_tx());
-                            let (actor_handle, action_tx) = crate::claude_session::start_event_loop(
-                                claude_session,
-                                handle,
-                                persist_tx,
-                                state.list_tx(),
-                                state.clone(),
-                            );
-                            state.add_session_actor(actor_handle);
-                            state.set_claude_action_tx(&new_id, action_tx);
+    ctx.state.broadcast_to_list(ServerMessage::WorktreeCreated {
+        request_id: String::new(),
+        worktree: worktree_summary,
+    });
 
-                            send_json(
-                                client_tx,
-                                ServerMessage::SessionSnapshot { session: snapshot },
-                            )
-                            .await;
-                            send_json(
-                                client_tx,
-                                ServerMessage::SessionForked {
-                                    source_session_id: source_session_id.clone(),
-                                    new_session_id: new_id.clone(),
-                                    forked_from_thread_id: None,
-                                },
-                            )
-                            .await;
-                            state.broadcast_to_list(ServerMessage::SessionCreated {
-                                session: summary,
-                            });
+    Box::pin(handle(
+        ClientMessage::ForkSession {
+            source_session_id,
+            nth_user_message,
+            model: None,
+            approval_policy: None,
+            sandbox_mode: None,
+            cwd: Some(fork_worktree_path),
+            permission_mode: None,
+            allowed_tools: Vec::new(),
+            disallowed_tools: Vec::new(),
+        },
+        ctx,
+    ))
+    .await;
+}
 
-                            info!(
-                                component = "session",
-                                event = "session.fork.claude_completed",
-                                connection_id = conn_id,
-                                source_session_id = %source_session_id,
-                                new_session_id = %new_id,
-                                "Claude session forked successfully"
-                            );
-                        }
-                        Err(e) => {
-                            error!(
-                                component = "session",
-                                event = "session.fork.claude_failed",
-                                connection_id = conn_id,
-                                source_session_id = %source_session_id,
-                                error = %e,
-                                "Failed to fork Claude session"
-                            );
-                            send_json(
-                                client_tx,
-                                ServerMessage::Error {
-                                    code: "fork_failed".into(),
-                                    message: e.to_string(),
-                                    session_id: Some(source_session_id),
-                                },
-                            )
-                            .await;
-                        }
-                    }
-                }
+pub(crate) async fn fork_session_to_existing_worktree(
+    ctx: &HandlerContext<'_>,
+    source_session_id: String,
+    worktree_id: String,
+    nth_user_message: Option<u32>,
+) {
+    let source_snapshot = match ctx.state.get_session(&source_session_id) {
+        Some(session) => session.snapshot(),
+        None => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "not_found".into(),
+                    message: format!("Source session {} not found", source_session_id),
+                    session_id: Some(source_session_id),
+                    error: None,
+                },
+            )
+            .await;
+            return;
+        }
+    };
 
-                Some(Provider::Codex) => {
-                    // ── Codex fork: use codex-core fork via action channel ──
-                    let source_action_tx = match state.get_codex_action_tx(&source_session_id) {
-                        Some(tx) => tx,
-                        None => {
-                            send_json(
-                                client_tx,
-                                ServerMessage::Error {
-                                    code: "not_found".into(),
-                                    message: format!(
-                                        "Source session {} has no active Codex connector",
-                                        source_session_id
-                                    ),
-                                    session_id: Some(source_session_id),
-                                },
-                            )
-                            .await;
-                            return;
-                        }
-                    };
+    let source_repo_root = if let Some(root) = source_snapshot
+        .repository_root
+        .clone()
+        .map(|r| r.trim().trim_end_matches('/').to_string())
+        .filter(|r| !r.is_empty())
+    {
+        root
+    } else if let Some(git_info) = crate::git::resolve_git_info(&source_snapshot.project_path).await
+    {
+        git_info.common_dir_root.trim_end_matches('/').to_string()
+    } else {
+        source_snapshot
+            .project_path
+            .trim()
+            .trim_end_matches('/')
+            .to_string()
+    };
 
-                    let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
-                    let effective_cwd = cwd.clone().or(source_cwd);
+    let target_worktree = match load_worktree_by_id(ctx.state.db_path(), &worktree_id) {
+        Some(row) => row,
+        None => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "worktree_not_found".into(),
+                    message: format!("Worktree {} not found", worktree_id),
+                    session_id: Some(source_session_id),
+                    error: None,
+                },
+            )
+            .await;
+            return;
+        }
+    };
 
-                    if source_action_tx
-                        .send(CodexAction::ForkSession {
-                            source_session_id: source_session_id.clone(),
-                            nth_user_message,
-                            model: model.clone(),
-                            approval_policy: effective_approval_policy.clone(),
-                            sandbox_mode: effective_sandbox_mode.clone(),
-                            cwd: effective_cwd.clone(),
-                            reply_tx,
-                        })
-                        .await
-                        .is_err()
-                    {
-                        send_json(
-                            client_tx,
-                            ServerMessage::Error {
-                                code: "channel_closed".into(),
-                                message: "Source session's action channel is closed".into(),
-                                session_id: Some(source_session_id),
-                            },
-                        )
-                        .await;
-                        return;
-                    }
+    if target_worktree.status == "removed" {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "worktree_not_found".into(),
+                message: "Selected worktree has been removed".into(),
+                session_id: Some(source_session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
 
-                    let fork_result = match reply_rx.await {
-                        Ok(result) => result,
-                        Err(_) => {
-                            send_json(
-                                client_tx,
-                                ServerMessage::Error {
-                                    code: "fork_failed".into(),
-                                    message: "Fork operation was cancelled".into(),
-                                    session_id: Some(source_session_id),
-                                },
-                            )
-                            .await;
-                            return;
-                        }
-                    };
+    let target_repo_root = target_worktree
+        .repo_root
+        .trim()
+        .trim_end_matches('/')
+        .to_string();
+    if target_repo_root != source_repo_root {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "worktree_repo_mismatch".into(),
+                message: "Selected worktree belongs to a different repository".into(),
+                session_id: Some(source_session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
 
-                    let (new_connector, new_thread_id) = match fork_result {
-                        Ok(result) => result,
-                        Err(e) => {
-                            error!(
-                                component = "session",
-                                event = "session.fork.failed",
-                                connection_id = conn_id,
-                                source_session_id = %source_session_id,
-                                error = %e,
-                                "Failed to fork session"
-                            );
-                            send_json(
-                                client_tx,
-                                ServerMessage::Error {
-                                    code: "fork_failed".into(),
-                                    message: e.to_string(),
-                                    session_id: Some(source_session_id),
-                                },
-                            )
-                            .await;
-                            return;
-                        }
-                    };
+    if !crate::git::worktree_exists_on_disk(&target_worktree.worktree_path).await {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "worktree_missing".into(),
+                message: "Selected worktree no longer exists on disk".into(),
+                session_id: Some(source_session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
 
-                    let new_id = orbitdock_protocol::new_id();
-                    let fork_cwd = effective_cwd.unwrap_or_else(|| ".".to_string());
-                    let project_name = fork_cwd.split('/').next_back().map(String::from);
+    Box::pin(handle(
+        ClientMessage::ForkSession {
+            source_session_id,
+            nth_user_message,
+            model: None,
+            approval_policy: None,
+            sandbox_mode: None,
+            cwd: Some(target_worktree.worktree_path),
+            permission_mode: None,
+            allowed_tools: Vec::new(),
+            disallowed_tools: Vec::new(),
+        },
+        ctx,
+    ))
+    .await;
+}
 
-                    let fork_branch = crate::git::resolve_git_branch(&fork_cwd).await;
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn fork_session(
+    ctx: &HandlerContext<'_>,
+    source_session_id: String,
+    nth_user_message: Option<u32>,
+    model: Option<String>,
+    approval_policy: Option<String>,
+    sandbox_mode: Option<String>,
+    cwd: Option<String>,
+    permission_mode: Option<String>,
+    allowed_tools: Vec<String>,
+    disallowed_tools: Vec<String>,
+) {
+    info!(
+        component = "session",
+        event = "session.fork.requested",
+        connection_id = ctx.conn_id,
+        source_session_id = %source_session_id,
+        nth_user_message = ?nth_user_message,
+        "Fork session requested"
+    );
+
+    let source_snapshot = ctx
+        .state
+        .get_session(&source_session_id)
+        .map(|s| s.snapshot());
+
+    // Determine source session's provider
+    let source_provider = source_snapshot.as_ref().map(|s| s.provider);
+
+    let source_cwd = source_snapshot.as_ref().map(|s| s.project_path.clone());
+
+    let source_model = model
+        .clone()
+        .or_else(|| source_snapshot.as_ref().and_then(|s| s.model.clone()));
+    let source_approval_policy = source_snapshot
+        .as_ref()
+        .and_then(|s| s.approval_policy.clone());
+    let source_sandbox_mode = source_snapshot
+        .as_ref()
+        .and_then(|s| s.sandbox_mode.clone());
+    let effective_approval_policy = approval_policy.clone().or(source_approval_policy);
+    let effective_sandbox_mode = sandbox_mode.clone().or(source_sandbox_mode);
+
+    match source_provider {
+        Some(Provider::Claude) => {
+            // ── Claude fork: spawn a new CLI, copy messages ──
+            let effective_cwd = cwd
+                .clone()
+                .or(source_cwd)
+                .unwrap_or_else(|| ".".to_string());
+            let project_name = effective_cwd.split('/').next_back().map(String::from);
+            let fork_branch = crate::git::resolve_git_branch(&effective_cwd).await;
+
+            // Spawn new Claude CLI session (starts fresh — no message copying)
+            let new_id = orbitdock_protocol::new_id();
+            match ClaudeSession::new(
+                new_id.clone(),
+                &effective_cwd,
+                source_model.as_deref(),
+                None,
+                permission_mode.as_deref(),
+                &allowed_tools,
+                &disallowed_tools,
+                None, // effort
+                crate::project_config::instructions(&effective_cwd).as_deref(),
+                &[], // mcp_servers
+            )
+            .await
+            {
+                Ok(claude_session) => {
                     let mut handle =
-                        SessionHandle::new(new_id.clone(), Provider::Codex, fork_cwd.clone());
+                        SessionHandle::new(new_id.clone(), Provider::Claude, effective_cwd.clone());
                     handle.set_git_branch(fork_branch.clone());
-                    handle.set_codex_integration_mode(Some(CodexIntegrationMode::Direct));
-                    handle.set_config(
-                        effective_approval_policy.clone(),
-                        effective_sandbox_mode.clone(),
-                    );
+                    handle.set_claude_integration_mode(Some(ClaudeIntegrationMode::Direct));
                     handle.set_forked_from(source_session_id.clone());
-
-                    let source_fork_messages =
-                        if let Some(source_actor) = state.get_session(&source_session_id) {
-                            let (state_tx, state_rx) = oneshot::channel();
-                            source_actor
-                                .send(SessionCommand::GetState { reply: state_tx })
-                                .await;
-
-                            match state_rx.await {
-                                Ok(source_state) => remap_messages_for_fork(
-                                    truncate_messages_before_nth_user_message(
-                                        &source_state.messages,
-                                        nth_user_message,
-                                    ),
-                                    &new_id,
-                                ),
-                                Err(_) => {
-                                    warn!(
-                                        component = "session",
-                                        event = "session.fork.source_state_unavailable",
-                                        source_session_id = %source_session_id,
-                                        new_session_id = %new_id,
-                                        "Failed to read source session state for fork hydration"
-                                    );
-                                    Vec::new()
-                                }
-                            }
-                        } else {
-                            Vec::new()
-                        };
-
-                    let rollout_messages =
-                        if let Some(rollout_path) = new_connector.rollout_path().await {
-                            match load_messages_from_transcript_path(&rollout_path, &new_id).await {
-                                Ok(messages) if !messages.is_empty() => {
-                                    info!(
-                                        component = "session",
-                                        event = "session.fork.messages_loaded",
-                                        new_session_id = %new_id,
-                                        message_count = messages.len(),
-                                        "Loaded forked conversation history"
-                                    );
-                                    messages
-                                }
-                                Ok(_) => {
-                                    debug!(
-                                        component = "session",
-                                        event = "session.fork.no_messages",
-                                        new_session_id = %new_id,
-                                        "Forked thread rollout has no parseable messages"
-                                    );
-                                    Vec::new()
-                                }
-                                Err(e) => {
-                                    warn!(
-                                        component = "session",
-                                        event = "session.fork.messages_load_failed",
-                                        new_session_id = %new_id,
-                                        error = %e,
-                                        "Failed to load forked conversation history"
-                                    );
-                                    Vec::new()
-                                }
-                            }
-                        } else {
-                            Vec::new()
-                        };
-
-                    let forked_messages = if source_fork_messages.len() >= rollout_messages.len() {
-                        if !source_fork_messages.is_empty()
-                            && rollout_messages.len() < source_fork_messages.len()
-                        {
-                            info!(
-                                component = "session",
-                                event = "session.fork.messages_source_selected",
-                                new_session_id = %new_id,
-                                source_message_count = source_fork_messages.len(),
-                                rollout_message_count = rollout_messages.len(),
-                                "Selected source session messages for fork hydration"
-                            );
-                        }
-                        source_fork_messages
-                    } else {
-                        rollout_messages
-                    };
-
-                    if !forked_messages.is_empty() {
-                        handle.replace_messages(forked_messages.clone());
+                    if let Some(ref m) = source_model {
+                        handle.set_model(Some(m.clone()));
                     }
+                    handle.set_tool_lists(allowed_tools.clone(), disallowed_tools.clone());
+
+                    let created_by = ctx
+                        .state
+                        .connection_identity(ctx.conn_id)
+                        .user()
+                        .map(str::to_string);
+                    handle.set_created_by(created_by.clone());
 
                     let rx = handle.subscribe();
-                    spawn_broadcast_forwarder(rx, client_tx.clone(), Some(new_id.clone()));
+                    spawn_session_forwarder(rx, ctx.client_tx.clone());
 
                     let summary = handle.summary();
                     let snapshot = handle.state();
 
-                    let persist_tx = state.persist().clone();
-
+                    let persist_tx = ctx.state.persist().clone();
                     let _ = persist_tx
                         .send(PersistCommand::SessionCreate {
                             id: new_id.clone(),
-                            provider: Provider::Codex,
-                            project_path: fork_cwd,
+                            provider: Provider::Claude,
+                            project_path: effective_cwd,
                             project_name,
                             branch: fork_branch,
-                            model,
-                            approval_policy: effective_approval_policy,
-                            sandbox_mode: effective_sandbox_mode,
-                            permission_mode: None,
+                            model: source_model,
+                            approval_policy: None,
+                            sandbox_mode: None,
+                            permission_mode: permission_mode.clone(),
                             forked_from_session_id: Some(source_session_id.clone()),
                         })
                         .await;
-
-                    for msg in forked_messages {
+                    if let Some(created_by) = created_by {
                         let _ = persist_tx
-                            .send(PersistCommand::MessageAppend {
+                            .send(PersistCommand::SetSessionCreatedBy {
                                 session_id: new_id.clone(),
-                                message: msg,
+                                created_by,
+                            })
+                            .await;
+                    }
+                    if !(allowed_tools.is_empty() && disallowed_tools.is_empty()) {
+                        let _ = persist_tx
+                            .send(PersistCommand::ToolListsUpdate {
+                                session_id: new_id.clone(),
+                                allowed_tools: allowed_tools.clone(),
+                                disallowed_tools: disallowed_tools.clone(),
                             })
                             .await;
                     }
 
-                    claim_codex_thread_for_direct_session(
-                        state,
-                        &persist_tx,
-                        &new_id,
-                        &new_thread_id,
-                        "legacy_codex_thread_row_cleanup",
-                    )
-                    .await;
-
-                    let codex_session = CodexSession {
-                        session_id: new_id.clone(),
-                        connector: new_connector,
-                    };
-                    handle.set_list_tx(state.list_tx());
-                    let (actor_handle, action_tx) = crate::codex_session::start_event_loop(
-                        codex_session,
+                    handle.set_list_tx(ctx.state.list_tx());
+                    let (actor_handle, action_tx) = crate::claude_session::start_event_loop(
+                        claude_session,
                         handle,
                         persist_tx,
-                        state.clone(),
+                        ctx.state.list_tx(),
+                        ctx.state.clone(),
                     );
-                    state.add_session_actor(actor_handle);
-                    state.set_codex_action_tx(&new_id, action_tx);
+                    ctx.state.add_session_actor(actor_handle);
+                    ctx.state.set_claude_action_tx(&new_id, action_tx);
 
                     send_json(
-                        client_tx,
+                        ctx.client_tx,
                         ServerMessage::SessionSnapshot { session: snapshot },
                     )
                     .await;
                     send_json(
-                        client_tx,
+                        ctx.client_tx,
                         ServerMessage::SessionForked {
                             source_session_id: source_session_id.clone(),
                             new_session_id: new_id.clone(),
-                            forked_from_thread_id: Some(new_thread_id),
+                            forked_from_thread_id: None,
+                        },
+                    )
+                    .await;
+                    ctx.state
+                        .broadcast_to_list(ServerMessage::SessionCreated { session: summary });
+
+                    info!(
+                        component = "session",
+                        event = "session.fork.claude_completed",
+                        connection_id = ctx.conn_id,
+                        source_session_id = %source_session_id,
+                        new_session_id = %new_id,
+                        "Claude session forked successfully"
+                    );
+                }
+                Err(e) => {
+                    error!(
+                        component = "session",
+                        event = "session.fork.claude_failed",
+                        connection_id = ctx.conn_id,
+                        source_session_id = %source_session_id,
+                        error = %e,
+                        "Failed to fork Claude session"
+                    );
+                    send_json(
+                        ctx.client_tx,
+                        ServerMessage::Error {
+                            code: "fork_failed".into(),
+                            message: e.to_string(),
+                            session_id: Some(source_session_id),
+                            error: None,
                         },
                     )
                     .await;
-                    state.broadcast_to_list(ServerMessage::SessionCreated { session: summary });
                 }
+            }
+        }
 
+        Some(Provider::Codex) => {
+            // ── Codex fork: use codex-core fork via action channel ──
+            let source_action_tx = match ctx.state.get_codex_action_tx(&source_session_id) {
+                Some(tx) => tx,
                 None => {
                     send_json(
-                        client_tx,
+                        ctx.client_tx,
                         ServerMessage::Error {
                             code: "not_found".into(),
-                            message: format!("Source session {} not found", source_session_id),
+                            message: format!(
+                                "Source session {} has no active Codex connector",
+                                source_session_id
+                            ),
                             session_id: Some(source_session_id),
+                            error: None,
                         },
                     )
                     .await;
+                    return;
                 }
+            };
+
+            let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+            let effective_cwd = cwd.clone().or(source_cwd);
+
+            if source_action_tx
+                .send(CodexAction::ForkSession {
+                    source_session_id: source_session_id.clone(),
+                    nth_user_message,
+                    model: model.clone(),
+                    approval_policy: effective_approval_policy.clone(),
+                    sandbox_mode: effective_sandbox_mode.clone(),
+                    cwd: effective_cwd.clone(),
+                    reply_tx,
+                })
+                .await
+                .is_err()
+            {
+                send_json(
+                    ctx.client_tx,
+                    ServerMessage::Error {
+                        code: "channel_closed".into(),
+                        message: "Source session's action channel is closed".into(),
+                        session_id: Some(source_session_id),
+                        error: None,
+                    },
+                )
+                .await;
+                return;
             }
+
+            let fork_result = match reply_rx.await {
+                Ok(result) => result,
+                Err(_) => {
+                    send_json(
+                        ctx.client_tx,
+                        ServerMessage::Error {
+                            code: "fork_failed".into(),
+                            message: "Fork operation was cancelled".into(),
+                            session_id: Some(source_session_id),
+                            error: None,
+                        },
+                    )
+                    .await;
+                    return;
+                }
+            };
+
+            let (new_connector, new_thread_id) = match fork_result {
+                Ok(result) => result,
+                Err(e) => {
+                    error!(
+                        component = "session",
+                        event = "session.fork.failed",
+                        connection_id = ctx.conn_id,
+                        source_session_id = %source_session_id,
+                        error = %e,
+                        "Failed to fork session"
+                    );
+                    send_json(
+                        ctx.client_tx,
+                        ServerMessage::Error {
+                            code: "fork_failed".into(),
+                            message: e.to_string(),
+                            session_id: Some(source_session_id),
+                            error: None,
+                        },
+                    )
+                    .await;
+                    return;
+                }
+            };
+
+            let new_id = orbitdock_protocol::new_id();
+            let fork_cwd = effective_cwd.unwrap_or_else(|| ".".to_string());
+            let project_name = fork_cwd.split('/').next_back().map(String::from);
+
+            let fork_branch = crate::git::resolve_git_branch(&fork_cwd).await;
+            let mut handle = SessionHandle::new(new_id.clone(), Provider::Codex, fork_cwd.clone());
+            handle.set_git_branch(fork_branch.clone());
+            handle.set_codex_integration_mode(Some(CodexIntegrationMode::Direct));
+            handle.set_config(
+                effective_approval_policy.clone(),
+                effective_sandbox_mode.clone(),
+            );
+            handle.set_forked_from(source_session_id.clone());
+
+            let source_fork_messages =
+                if let Some(source_actor) = ctx.state.get_session(&source_session_id) {
+                    let (state_tx, state_rx) = oneshot::channel();
+                    source_actor
+                        .send(SessionCommand::GetState { reply: state_tx })
+                        .await;
+
+                    match state_rx.await {
+                        Ok(source_state) => remap_messages_for_fork(
+                            truncate_messages_before_nth_user_message(
+                                &source_state.messages,
+                                nth_user_message,
+                            ),
+                            &new_id,
+                        ),
+                        Err(_) => {
+                            warn!(
+                                component = "session",
+                                event = "session.fork.source_state_unavailable",
+                                source_session_id = %source_session_id,
+                                new_session_id = %new_id,
+                                "Failed to read source session state for fork hydration"
+                            );
+                            Vec::new()
+                        }
+                    }
+                } else {
+                    Vec::new()
+                };
+
+            let rollout_messages = if let Some(rollout_path) = new_connector.rollout_path().await {
+                match load_messages_from_transcript_path(&rollout_path, &new_id).await {
+                    Ok(messages) if !messages.is_empty() => {
+                        info!(
+                            component = "session",
+                            event = "session.fork.messages_loaded",
+                            new_session_id = %new_id,
+                            message_count = messages.len(),
+                            "Loaded forked conversation history"
+                        );
+                        messages
+                    }
+                    Ok(_) => {
+                        debug!(
+                            component = "session",
+                            event = "session.fork.no_messages",
+                            new_session_id = %new_id,
+                            "Forked thread rollout has no parseable messages"
+                        );
+                        Vec::new()
+                    }
+                    Err(e) => {
+                        warn!(
+                            component = "session",
+                            event = "session.fork.messages_load_failed",
+                            new_session_id = %new_id,
+                            error = %e,
+                            "Failed to load forked conversation history"
+                        );
+                        Vec::new()
+                    }
+                }
+            } else {
+                Vec::new()
+            };
+
+            let forked_messages = if source_fork_messages.len() >= rollout_messages.len() {
+                if !source_fork_messages.is_empty()
+                    && rollout_messages.len() < source_fork_messages.len()
+                {
+                    info!(
+                        component = "session",
+                        event = "session.fork.messages_source_selected",
+                        new_session_id = %new_id,
+                        source_message_count = source_fork_messages.len(),
+                        rollout_message_count = rollout_messages.len(),
+                        "Selected source session messages for fork hydration"
+                    );
+                }
+                source_fork_messages
+            } else {
+                rollout_messages
+            };
+
+            if !forked_messages.is_empty() {
+                handle.replace_messages(forked_messages.clone());
+            }
+
+            let created_by = ctx
+                .state
+                .connection_identity(ctx.conn_id)
+                .user()
+                .map(str::to_string);
+            handle.set_created_by(created_by.clone());
+
+            let rx = handle.subscribe();
+            spawn_session_forwarder(rx, ctx.client_tx.clone());
+
+            let summary = handle.summary();
+            let snapshot = handle.state();
+
+            let persist_tx = ctx.state.persist().clone();
+
+            let _ = persist_tx
+                .send(PersistCommand::SessionCreate {
+                    id: new_id.clone(),
+                    provider: Provider::Codex,
+                    project_path: fork_cwd,
+                    project_name,
+                    branch: fork_branch,
+                    model,
+                    approval_policy: effective_approval_policy,
+                    sandbox_mode: effective_sandbox_mode,
+                    permission_mode: None,
+                    forked_from_session_id: Some(source_session_id.clone()),
+                })
+                .await;
+            if let Some(created_by) = created_by {
+                let _ = persist_tx
+                    .send(PersistCommand::SetSessionCreatedBy {
+                        session_id: new_id.clone(),
+                        created_by,
+                    })
+                    .await;
+            }
+
+            for msg in forked_messages {
+                let _ = persist_tx
+                    .send(PersistCommand::MessageAppend {
+                        session_id: new_id.clone(),
+                        message: msg,
+                    })
+                    .await;
+            }
+
+            claim_codex_thread_for_direct_session(
+                ctx.state,
+                &persist_tx,
+                &new_id,
+                &new_thread_id,
+                "legacy_codex_thread_row_cleanup",
+            )
+            .await;
+
+            let codex_session = CodexSession {
+                session_id: new_id.clone(),
+                connector: new_connector,
+            };
+            handle.set_list_tx(ctx.state.list_tx());
+            let (actor_handle, action_tx) = crate::codex_session::start_event_loop(
+                codex_session,
+                handle,
+                persist_tx,
+                ctx.state.clone(),
+            );
+            ctx.state.add_session_actor(actor_handle);
+            ctx.state.set_codex_action_tx(&new_id, action_tx);
+
+            send_json(
+                ctx.client_tx,
+                ServerMessage::SessionSnapshot { session: snapshot },
+            )
+            .await;
+            send_json(
+                ctx.client_tx,
+                ServerMessage::SessionForked {
+                    source_session_id: source_session_id.clone(),
+                    new_session_id: new_id.clone(),
+                    forked_from_thread_id: Some(new_thread_id),
+                },
+            )
+            .await;
+            ctx.state
+                .broadcast_to_list(ServerMessage::SessionCreated { session: summary });
         }
 
-        _ => {
-            tracing::warn!(?msg, "session_crud::handle called with unexpected variant");
+        None => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "not_found".into(),
+                    message: format!("Source session {} not found", source_session_id),
+                    session_id: Some(source_session_id),
+                    error: None,
+                },
+            )
+            .await;
         }
     }
 }
@@ -0,0 +1,95 @@
+use tokio::sync::mpsc;
+use tracing::info;
+
+use orbitdock_protocol::{ClientMessage, ServerMessage, SessionVisibility};
+
+use crate::persistence::PersistCommand;
+use crate::session_access::{self, SessionSharing};
+use crate::state::SessionRegistry;
+use crate::websocket::{send_json, OutboundMessage};
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    match msg {
+        ClientMessage::GetSessionSharing { session_id } => {
+            get_session_sharing(ctx, session_id).await
+        }
+
+        ClientMessage::ShareSession {
+            session_id,
+            visibility,
+            users,
+        } => share_session(ctx, session_id, visibility, users).await,
+
+        _ => unreachable!("sharing::handle called with non-sharing message"),
+    }
+}
+
+pub(crate) async fn get_session_sharing(ctx: &HandlerContext<'_>, session_id: String) {
+    let owner = ctx.state.session_owner(&session_id).await;
+    send_sharing(ctx.client_tx, ctx.state, session_id, owner).await;
+}
+
+pub(crate) async fn share_session(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    visibility: SessionVisibility,
+    users: Vec<String>,
+) {
+    let identity = ctx.state.connection_identity(ctx.conn_id);
+    let owner = ctx.state.session_owner(&session_id).await;
+    if !session_access::can_manage(identity.user(), owner.as_deref()) {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "forbidden".into(),
+                message: "Only the session's creator can change who it's shared with".into(),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    let sharing = SessionSharing::new(visibility, users, owner.as_deref());
+    let _ = ctx
+        .state
+        .persist()
+        .send(PersistCommand::SessionSharingSet {
+            session_id: session_id.clone(),
+            visibility: session_access::visibility_str(sharing.visibility).to_string(),
+            shared_with: sharing.shared_with.clone(),
+        })
+        .await;
+    info!(
+        component = "session_access",
+        event = "session_access.shared",
+        session_id = %session_id,
+        visibility = session_access::visibility_str(sharing.visibility),
+        shared_with = sharing.shared_with.len(),
+        "Updated session sharing"
+    );
+    ctx.state.set_session_sharing(&session_id, sharing);
+
+    send_sharing(ctx.client_tx, ctx.state, session_id, owner).await;
+}
+
+async fn send_sharing(
+    client_tx: &mpsc::Sender<OutboundMessage>,
+    state: &SessionRegistry,
+    session_id: String,
+    owner: Option<String>,
+) {
+    let sharing = state.session_sharing(&session_id);
+    send_json(
+        client_tx,
+        ServerMessage::SessionSharing {
+            session_id,
+            owner,
+            visibility: sharing.visibility,
+            shared_with: sharing.shared_with,
+        },
+    )
+    .await;
+}
@@ -1,288 +1,484 @@
-use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use tokio::sync::mpsc;
 use tracing::info;
 
 use orbitdock_protocol::{
-    new_id, ClientMessage, MessageType, ServerMessage, ShellExecutionOutcome,
+    new_id, ClientMessage, MessageType, ServerMessage, ShellExecutionOutcome, ShellJob,
+    ShellJobStatus,
 };
 
+use crate::persistence::{load_shell_job_output, load_shell_jobs_for_session, PersistCommand};
 use crate::session_command::SessionCommand;
 use crate::session_utils::iso_timestamp;
-use crate::state::SessionRegistry;
-use crate::websocket::{send_json, OutboundMessage};
-
-pub(crate) async fn handle(
-    msg: ClientMessage,
-    client_tx: &mpsc::Sender<OutboundMessage>,
-    state: &Arc<SessionRegistry>,
-    conn_id: u64,
-) {
+use crate::shell::{truncate_output_tail, MAX_JOB_OUTPUT_BYTES};
+use crate::shell_policy::audit_denied;
+use crate::websocket::send_json;
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
     match msg {
         ClientMessage::ExecuteShell {
             session_id,
             command,
             cwd,
             timeout_secs,
-        } => {
-            info!(
-                component = "shell",
-                event = "shell.execute.requested",
-                connection_id = conn_id,
-                session_id = %session_id,
-                "Shell execution requested"
-            );
+            detach,
+        } => execute_shell(ctx, session_id, command, cwd, timeout_secs, detach).await,
 
-            let resolved_cwd = if let Some(ref explicit) = cwd {
-                explicit.clone()
-            } else if let Some(actor) = state.get_session(&session_id) {
-                let snap = actor.snapshot();
-                snap.current_cwd
-                    .clone()
-                    .unwrap_or_else(|| snap.project_path.clone())
-            } else {
-                send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "not_found".to_string(),
-                        message: format!("Session {session_id} not found"),
-                        session_id: Some(session_id),
-                    },
-                )
-                .await;
-                return;
-            };
+        ClientMessage::ListJobs { session_id } => list_jobs(ctx, session_id).await,
 
-            let request_id = new_id();
-            let sid = session_id.clone();
-            let rid = request_id.clone();
-            let cmd_clone = command.clone();
+        ClientMessage::GetJobOutput { session_id, job_id } => {
+            get_job_output(ctx, session_id, job_id).await
+        }
 
-            let actor = match state.get_session(&sid) {
-                Some(a) => a,
-                None => return,
-            };
+        ClientMessage::CancelShell {
+            session_id,
+            request_id,
+        } => cancel_shell(ctx, session_id, request_id).await,
 
-            actor
-                .send(SessionCommand::Broadcast {
-                    msg: ServerMessage::ShellStarted {
-                        session_id: sid.clone(),
-                        request_id: rid.clone(),
-                        command: cmd_clone.clone(),
-                    },
-                })
-                .await;
+        _ => {}
+    }
+}
+
+pub(crate) async fn execute_shell(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    command: String,
+    cwd: Option<String>,
+    timeout_secs: u64,
+    detach: bool,
+) {
+    info!(
+        component = "shell",
+        event = "shell.execute.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        detach,
+        "Shell execution requested"
+    );
+
+    if let Err(denial) = ctx.state.shell_policy().check(&command) {
+        audit_denied(
+            ctx.state,
+            "execute_shell",
+            ctx.conn_id,
+            &session_id,
+            &command,
+            &denial,
+        )
+        .await;
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "shell_command_not_allowed".to_string(),
+                message: denial.to_string(),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    let resolved_cwd = if let Some(ref explicit) = cwd {
+        explicit.clone()
+    } else if let Some(actor) = ctx.state.get_session(&session_id) {
+        let snap = actor.snapshot();
+        snap.current_cwd
+            .clone()
+            .unwrap_or_else(|| snap.project_path.clone())
+    } else {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "not_found".to_string(),
+                message: format!("Session {session_id} not found"),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    };
+
+    let request_id = new_id();
+    let sid = session_id.clone();
+    let rid = request_id.clone();
+    let cmd_clone = command.clone();
+
+    let actor = match ctx.state.get_session(&sid) {
+        Some(a) => a,
+        None => return,
+    };
+
+    actor
+        .send(SessionCommand::Broadcast {
+            msg: ServerMessage::ShellStarted {
+                session_id: sid.clone(),
+                request_id: rid.clone(),
+                command: cmd_clone.clone(),
+            },
+        })
+        .await;
+
+    let shell_msg = orbitdock_protocol::Message {
+        id: rid.clone(),
+        session_id: sid.clone(),
+        sequence: None,
+        message_type: MessageType::Shell,
+        content: cmd_clone.clone(),
+        tool_name: None,
+        tool_input: None,
+        tool_output: None,
+        is_error: false,
+        is_in_progress: true,
+        timestamp: now_iso(),
+        duration_ms: None,
+        images: vec![],
+    };
+
+    actor
+        .send(SessionCommand::ProcessEvent {
+            event: crate::transition::Input::MessageCreated(shell_msg),
+        })
+        .await;
+
+    let shell_execution = match ctx.state.shell_service().start(
+        rid.clone(),
+        sid.clone(),
+        cmd_clone.clone(),
+        resolved_cwd.clone(),
+        timeout_secs,
+        detach,
+    ) {
+        Ok(execution) => execution,
+        Err(crate::shell::ShellStartError::DuplicateRequestId) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "shell_duplicate_request_id".to_string(),
+                    message: format!("Shell request {rid} is already active"),
+                    session_id: Some(sid.clone()),
+                    error: None,
+                },
+            )
+            .await;
+            return;
+        }
+    };
+
+    let started_at = now_iso();
+    if detach {
+        let _ = ctx
+            .state
+            .persist()
+            .send(PersistCommand::ShellJobStarted {
+                id: rid.clone(),
+                session_id: sid.clone(),
+                command: cmd_clone.clone(),
+                cwd: resolved_cwd.clone(),
+                started_at: started_at.clone(),
+            })
+            .await;
+    }
+
+    let state_ref = ctx.state.clone();
+    tokio::spawn(async move {
+        let mut chunk_rx = shell_execution.chunk_rx;
+        let completion_rx = shell_execution.completion_rx;
+
+        let mut streamed_output = String::new();
+        let mut last_stream_emit = std::time::Instant::now();
+        const SHELL_STREAM_THROTTLE_MS: u128 = 120;
+
+        while let Some(chunk) = chunk_rx.recv().await {
+            if !chunk.stdout.is_empty() {
+                streamed_output.push_str(&chunk.stdout);
+            }
+            if !chunk.stderr.is_empty() {
+                streamed_output.push_str(&chunk.stderr);
+            }
+            if detach {
+                truncate_output_tail(&mut streamed_output, MAX_JOB_OUTPUT_BYTES);
+            }
+
+            let now = std::time::Instant::now();
+            if now.duration_since(last_stream_emit).as_millis() < SHELL_STREAM_THROTTLE_MS {
+                continue;
+            }
+            last_stream_emit = now;
+
+            if let Some(actor) = state_ref.get_session(&sid) {
+                actor
+                    .send(SessionCommand::ProcessEvent {
+                        event: crate::transition::Input::MessageUpdated {
+                            message_id: rid.clone(),
+                            content: None,
+                            tool_output: Some(streamed_output.clone()),
+                            is_error: None,
+                            is_in_progress: Some(true),
+                            duration_ms: None,
+                        },
+                    })
+                    .await;
+            }
+        }
+
+        let mut result = match completion_rx.await {
+            Ok(result) => result,
+            Err(recv_err) => crate::shell::ShellResult {
+                stdout: String::new(),
+                stderr: format!("Shell execution completion channel failed: {recv_err}"),
+                exit_code: None,
+                duration_ms: 0,
+                outcome: crate::shell::ShellOutcome::Failed,
+            },
+        };
+        crate::redaction::redact_string(&mut result.stdout);
+        crate::redaction::redact_string(&mut result.stderr);
 
-            let shell_msg = orbitdock_protocol::Message {
+        let is_error = match result.outcome {
+            crate::shell::ShellOutcome::Completed => result.exit_code != Some(0),
+            crate::shell::ShellOutcome::Failed | crate::shell::ShellOutcome::TimedOut => true,
+            crate::shell::ShellOutcome::Canceled => false,
+        };
+        let combined_output = if result.stderr.is_empty() {
+            result.stdout.clone()
+        } else if result.stdout.is_empty() {
+            result.stderr.clone()
+        } else {
+            format!("{}\n{}", result.stdout, result.stderr)
+        };
+        let final_output = if combined_output.is_empty() {
+            streamed_output
+        } else {
+            combined_output
+        };
+        let outcome = match result.outcome {
+            crate::shell::ShellOutcome::Completed => ShellExecutionOutcome::Completed,
+            crate::shell::ShellOutcome::Failed => ShellExecutionOutcome::Failed,
+            crate::shell::ShellOutcome::TimedOut => ShellExecutionOutcome::TimedOut,
+            crate::shell::ShellOutcome::Canceled => ShellExecutionOutcome::Canceled,
+        };
+
+        let completed_job = if detach {
+            let job = ShellJob {
                 id: rid.clone(),
                 session_id: sid.clone(),
-                sequence: None,
-                message_type: MessageType::Shell,
-                content: cmd_clone.clone(),
-                tool_name: None,
-                tool_input: None,
-                tool_output: None,
-                is_error: false,
-                is_in_progress: true,
-                timestamp: iso_timestamp(
-                    SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_millis(),
-                ),
-                duration_ms: None,
-                images: vec![],
+                command: cmd_clone,
+                cwd: resolved_cwd,
+                status: ShellJobStatus::from(outcome),
+                exit_code: result.exit_code,
+                started_at,
+                finished_at: Some(now_iso()),
+                duration_ms: Some(result.duration_ms),
             };
+            let mut stdout = result.stdout.clone();
+            let mut stderr = result.stderr.clone();
+            truncate_output_tail(&mut stdout, MAX_JOB_OUTPUT_BYTES);
+            truncate_output_tail(&mut stderr, MAX_JOB_OUTPUT_BYTES);
+            let _ = state_ref
+                .persist()
+                .send(PersistCommand::ShellJobFinished {
+                    id: job.id.clone(),
+                    status: job.status.as_str().to_string(),
+                    exit_code: job.exit_code,
+                    stdout,
+                    stderr,
+                    finished_at: job.finished_at.clone().unwrap_or_default(),
+                    duration_ms: result.duration_ms,
+                })
+                .await;
+            info!(
+                component = "shell",
+                event = "shell.job.finished",
+                session_id = %sid,
+                request_id = %rid,
+                status = job.status.as_str(),
+                duration_ms = result.duration_ms,
+                "Background shell job finished"
+            );
+            Some(job)
+        } else {
+            None
+        };
 
+        if let Some(actor) = state_ref.get_session(&sid) {
             actor
                 .send(SessionCommand::ProcessEvent {
-                    event: crate::transition::Input::MessageCreated(shell_msg),
+                    event: crate::transition::Input::MessageUpdated {
+                        message_id: rid.clone(),
+                        content: None,
+                        tool_output: Some(final_output),
+                        is_error: Some(is_error),
+                        is_in_progress: Some(false),
+                        duration_ms: Some(result.duration_ms),
+                    },
                 })
                 .await;
 
-            let shell_execution = match state.shell_service().start(
-                rid.clone(),
-                sid.clone(),
-                cmd_clone.clone(),
-                resolved_cwd.clone(),
-                timeout_secs,
-            ) {
-                Ok(execution) => execution,
-                Err(crate::shell::ShellStartError::DuplicateRequestId) => {
-                    send_json(
-                        client_tx,
-                        ServerMessage::Error {
-                            code: "shell_duplicate_request_id".to_string(),
-                            message: format!("Shell request {rid} is already active"),
-                            session_id: Some(sid.clone()),
+            actor
+                .send(SessionCommand::Broadcast {
+                    msg: ServerMessage::ShellOutput {
+                        session_id: sid.clone(),
+                        request_id: rid,
+                        stdout: result.stdout,
+                        stderr: result.stderr,
+                        exit_code: result.exit_code,
+                        duration_ms: result.duration_ms,
+                        outcome,
+                    },
+                })
+                .await;
+
+            if let Some(job) = completed_job {
+                actor
+                    .send(SessionCommand::Broadcast {
+                        msg: ServerMessage::ShellJobCompleted {
+                            session_id: sid,
+                            job,
                         },
-                    )
+                    })
                     .await;
-                    return;
-                }
-            };
+            }
+        }
+    });
+}
 
-            let state_ref = state.clone();
-            tokio::spawn(async move {
-                let mut chunk_rx = shell_execution.chunk_rx;
-                let completion_rx = shell_execution.completion_rx;
-
-                let mut streamed_output = String::new();
-                let mut last_stream_emit = std::time::Instant::now();
-                const SHELL_STREAM_THROTTLE_MS: u128 = 120;
-
-                while let Some(chunk) = chunk_rx.recv().await {
-                    if !chunk.stdout.is_empty() {
-                        streamed_output.push_str(&chunk.stdout);
-                    }
-                    if !chunk.stderr.is_empty() {
-                        streamed_output.push_str(&chunk.stderr);
-                    }
-
-                    let now = std::time::Instant::now();
-                    if now.duration_since(last_stream_emit).as_millis() < SHELL_STREAM_THROTTLE_MS {
-                        continue;
-                    }
-                    last_stream_emit = now;
-
-                    if let Some(actor) = state_ref.get_session(&sid) {
-                        actor
-                            .send(SessionCommand::ProcessEvent {
-                                event: crate::transition::Input::MessageUpdated {
-                                    message_id: rid.clone(),
-                                    content: None,
-                                    tool_output: Some(streamed_output.clone()),
-                                    is_error: None,
-                                    is_in_progress: Some(true),
-                                    duration_ms: None,
-                                },
-                            })
-                            .await;
-                    }
-                }
-
-                let result = match completion_rx.await {
-                    Ok(result) => result,
-                    Err(recv_err) => crate::shell::ShellResult {
-                        stdout: String::new(),
-                        stderr: format!("Shell execution completion channel failed: {recv_err}"),
-                        exit_code: None,
-                        duration_ms: 0,
-                        outcome: crate::shell::ShellOutcome::Failed,
-                    },
-                };
-
-                let is_error = match result.outcome {
-                    crate::shell::ShellOutcome::Completed => result.exit_code != Some(0),
-                    crate::shell::ShellOutcome::Failed | crate::shell::ShellOutcome::TimedOut => {
-                        true
-                    }
-                    crate::shell::ShellOutcome::Canceled => false,
-                };
-                let combined_output = if result.stderr.is_empty() {
-                    result.stdout.clone()
-                } else if result.stdout.is_empty() {
-                    result.stderr.clone()
-                } else {
-                    format!("{}\n{}", result.stdout, result.stderr)
-                };
-                let final_output = if combined_output.is_empty() {
-                    streamed_output
-                } else {
-                    combined_output
-                };
-                let outcome = match result.outcome {
-                    crate::shell::ShellOutcome::Completed => ShellExecutionOutcome::Completed,
-                    crate::shell::ShellOutcome::Failed => ShellExecutionOutcome::Failed,
-                    crate::shell::ShellOutcome::TimedOut => ShellExecutionOutcome::TimedOut,
-                    crate::shell::ShellOutcome::Canceled => ShellExecutionOutcome::Canceled,
-                };
-
-                if let Some(actor) = state_ref.get_session(&sid) {
-                    actor
-                        .send(SessionCommand::ProcessEvent {
-                            event: crate::transition::Input::MessageUpdated {
-                                message_id: rid.clone(),
-                                content: None,
-                                tool_output: Some(final_output),
-                                is_error: Some(is_error),
-                                is_in_progress: Some(false),
-                                duration_ms: Some(result.duration_ms),
-                            },
-                        })
-                        .await;
-
-                    actor
-                        .send(SessionCommand::Broadcast {
-                            msg: ServerMessage::ShellOutput {
-                                session_id: sid,
-                                request_id: rid,
-                                stdout: result.stdout,
-                                stderr: result.stderr,
-                                exit_code: result.exit_code,
-                                duration_ms: result.duration_ms,
-                                outcome,
-                            },
-                        })
-                        .await;
-                }
-            });
+pub(crate) async fn list_jobs(ctx: &HandlerContext<'_>, session_id: String) {
+    match load_shell_jobs_for_session(&session_id).await {
+        Ok(jobs) => {
+            send_json(ctx.client_tx, ServerMessage::JobsList { session_id, jobs }).await;
+        }
+        Err(e) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "db_error".to_string(),
+                    message: e.to_string(),
+                    session_id: Some(session_id),
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}
+
+pub(crate) async fn get_job_output(ctx: &HandlerContext<'_>, session_id: String, job_id: String) {
+    let loaded = match load_shell_job_output(&job_id).await {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "db_error".to_string(),
+                    message: e.to_string(),
+                    session_id: Some(session_id),
+                    error: None,
+                },
+            )
+            .await;
+            return;
         }
+    };
 
-        ClientMessage::CancelShell {
+    let Some((job, stdout, stderr)) = loaded.filter(|(job, _, _)| job.session_id == session_id)
+    else {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "job_not_found".to_string(),
+                message: format!("No shell job {job_id} found for session {session_id}"),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    };
+
+    // Running jobs only have output in memory until they finish.
+    let (stdout, stderr) = if job.status == ShellJobStatus::Running {
+        ctx.state
+            .shell_service()
+            .live_output(&session_id, &job_id)
+            .unwrap_or((stdout, stderr))
+    } else {
+        (stdout, stderr)
+    };
+    let stdout = crate::redaction::redact(&stdout).into_owned();
+    let stderr = crate::redaction::redact(&stderr).into_owned();
+
+    send_json(
+        ctx.client_tx,
+        ServerMessage::JobOutput {
             session_id,
-            request_id,
-        } => {
+            job,
+            stdout,
+            stderr,
+        },
+    )
+    .await;
+}
+
+pub(crate) async fn cancel_shell(ctx: &HandlerContext<'_>, session_id: String, request_id: String) {
+    info!(
+        component = "shell",
+        event = "shell.cancel.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        request_id = %request_id,
+        "Shell cancel requested"
+    );
+
+    if ctx.state.get_session(&session_id).is_none() {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "not_found".to_string(),
+                message: format!("Session {session_id} not found"),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    match ctx.state.shell_service().cancel(&session_id, &request_id) {
+        crate::shell::ShellCancelStatus::Canceled => {
             info!(
                 component = "shell",
-                event = "shell.cancel.requested",
-                connection_id = conn_id,
+                event = "shell.cancel.accepted",
+                connection_id = ctx.conn_id,
                 session_id = %session_id,
                 request_id = %request_id,
-                "Shell cancel requested"
+                "Shell cancel accepted"
             );
-
-            if state.get_session(&session_id).is_none() {
-                send_json(
-                    client_tx,
-                    ServerMessage::Error {
-                        code: "not_found".to_string(),
-                        message: format!("Session {session_id} not found"),
-                        session_id: Some(session_id),
-                    },
-                )
-                .await;
-                return;
-            }
-
-            match state.shell_service().cancel(&session_id, &request_id) {
-                crate::shell::ShellCancelStatus::Canceled => {
-                    info!(
-                        component = "shell",
-                        event = "shell.cancel.accepted",
-                        connection_id = conn_id,
-                        session_id = %session_id,
-                        request_id = %request_id,
-                        "Shell cancel accepted"
-                    );
-                }
-                crate::shell::ShellCancelStatus::NotFound => {
-                    send_json(
-                        client_tx,
-                        ServerMessage::Error {
-                            code: "shell_not_found".to_string(),
-                            message: format!(
-                                "No active shell request {request_id} found for session {session_id}"
-                            ),
-                            session_id: Some(session_id),
-                        },
-                    )
-                    .await;
-                }
-            }
         }
-
-        _ => {}
+        crate::shell::ShellCancelStatus::NotFound => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "shell_not_found".to_string(),
+                    message: format!(
+                        "No active shell request {request_id} found for session {session_id}"
+                    ),
+                    session_id: Some(session_id),
+                    error: None,
+                },
+            )
+            .await;
+        }
     }
 }
+
+fn now_iso() -> String {
+    iso_timestamp(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis(),
+    )
+}
@@ -0,0 +1,221 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use orbitdock_protocol::{new_id, ClientMessage, ServerMessage};
+
+use crate::terminal::{TerminalError, TerminalEvent, TerminalSize};
+use crate::websocket::{send_json, OutboundMessage};
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    match msg {
+        ClientMessage::OpenTerminal {
+            session_id,
+            request_id,
+            cwd,
+            cols,
+            rows,
+        } => open_terminal(ctx, session_id, request_id, cwd, cols, rows).await,
+
+        ClientMessage::TerminalInput { terminal_id, data } => {
+            terminal_input(ctx, terminal_id, data).await
+        }
+
+        ClientMessage::ResizeTerminal {
+            terminal_id,
+            cols,
+            rows,
+        } => resize_terminal(ctx, terminal_id, cols, rows).await,
+
+        ClientMessage::CloseTerminal { terminal_id } => close_terminal(ctx, terminal_id).await,
+
+        _ => {}
+    }
+}
+
+pub(crate) async fn open_terminal(
+    ctx: &HandlerContext<'_>,
+    session_id: String,
+    request_id: String,
+    cwd: Option<String>,
+    cols: u16,
+    rows: u16,
+) {
+    info!(
+        component = "terminal",
+        event = "terminal.open.requested",
+        connection_id = ctx.conn_id,
+        session_id = %session_id,
+        request_id = %request_id,
+        "Terminal open requested"
+    );
+
+    if ctx.state.shell_policy().is_restricted() {
+        warn!(
+            component = "audit",
+            event = "audit.terminal.denied",
+            connection_id = ctx.conn_id,
+            session_id = %session_id,
+            "Terminal denied while shell allowlist is enabled"
+        );
+        crate::audit::record(
+            ctx.state,
+            ctx.conn_id,
+            crate::audit::AuditEntry {
+                action: "terminal_denied",
+                session_id: Some(session_id.clone()),
+                details: serde_json::Value::Null,
+            },
+        )
+        .await;
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "terminal_disabled".to_string(),
+                message: "Interactive terminals are disabled while the shell allowlist is enabled"
+                    .to_string(),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    }
+
+    let Some(actor) = ctx.state.get_session(&session_id) else {
+        send_json(
+            ctx.client_tx,
+            ServerMessage::Error {
+                code: "not_found".to_string(),
+                message: format!("Session {session_id} not found"),
+                session_id: Some(session_id),
+                error: None,
+            },
+        )
+        .await;
+        return;
+    };
+
+    let resolved_cwd = cwd.unwrap_or_else(|| {
+        let snap = actor.snapshot();
+        snap.current_cwd
+            .clone()
+            .unwrap_or_else(|| snap.project_path.clone())
+    });
+
+    let terminal_id = new_id();
+    let mut events = match ctx.state.terminal_service().open(
+        terminal_id.clone(),
+        session_id.clone(),
+        ctx.conn_id,
+        &resolved_cwd,
+        TerminalSize { cols, rows },
+    ) {
+        Ok(events) => events,
+        Err(e) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "terminal_open_failed".to_string(),
+                    message: e.to_string(),
+                    session_id: Some(session_id),
+                    error: None,
+                },
+            )
+            .await;
+            return;
+        }
+    };
+
+    send_json(
+        ctx.client_tx,
+        ServerMessage::TerminalOpened {
+            session_id,
+            request_id,
+            terminal_id: terminal_id.clone(),
+            cwd: resolved_cwd,
+        },
+    )
+    .await;
+
+    let outbound = ctx.client_tx.clone();
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            let msg = match event {
+                TerminalEvent::Output(bytes) => ServerMessage::TerminalOutput {
+                    terminal_id: terminal_id.clone(),
+                    data: STANDARD.encode(bytes),
+                },
+                TerminalEvent::Exited { exit_code } => ServerMessage::TerminalClosed {
+                    terminal_id: terminal_id.clone(),
+                    exit_code,
+                },
+            };
+            if outbound.send(OutboundMessage::Json(msg)).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+pub(crate) async fn terminal_input(ctx: &HandlerContext<'_>, terminal_id: String, data: String) {
+    let result = ctx
+        .state
+        .terminal_service()
+        .write(&terminal_id, ctx.conn_id, data.as_bytes());
+    send_terminal_error(ctx.client_tx, &terminal_id, result).await;
+}
+
+pub(crate) async fn resize_terminal(
+    ctx: &HandlerContext<'_>,
+    terminal_id: String,
+    cols: u16,
+    rows: u16,
+) {
+    let result =
+        ctx.state
+            .terminal_service()
+            .resize(&terminal_id, ctx.conn_id, TerminalSize { cols, rows });
+    send_terminal_error(ctx.client_tx, &terminal_id, result).await;
+}
+
+pub(crate) async fn close_terminal(ctx: &HandlerContext<'_>, terminal_id: String) {
+    info!(
+        component = "terminal",
+        event = "terminal.close.requested",
+        connection_id = ctx.conn_id,
+        terminal_id = %terminal_id,
+        "Terminal close requested"
+    );
+    let result = ctx
+        .state
+        .terminal_service()
+        .close(&terminal_id, ctx.conn_id);
+    send_terminal_error(ctx.client_tx, &terminal_id, result).await;
+}
+
+async fn send_terminal_error(
+    client_tx: &mpsc::Sender<OutboundMessage>,
+    terminal_id: &str,
+    result: Result<(), TerminalError>,
+) {
+    let Err(error) = result else {
+        return;
+    };
+    let code = match error {
+        TerminalError::NotFound => "terminal_not_found",
+        TerminalError::Spawn(_) | TerminalError::Io(_) => "terminal_io_failed",
+    };
+    send_json(
+        client_tx,
+        ServerMessage::Error {
+            code: code.to_string(),
+            message: format!("Terminal {terminal_id}: {error}"),
+            session_id: None,
+            error: None,
+        },
+    )
+    .await;
+}
@@ -0,0 +1,39 @@
+use orbitdock_protocol::{ClientMessage, ServerMessage};
+
+use crate::persistence::list_turns;
+use crate::websocket::send_json;
+use crate::ws_handlers::HandlerContext;
+
+pub(crate) async fn handle(msg: ClientMessage, ctx: &HandlerContext<'_>) {
+    match msg {
+        ClientMessage::GetTurns { session_id } => get_turns(ctx, session_id).await,
+
+        _ => {
+            tracing::warn!(?msg, "turns::handle called with unexpected variant");
+        }
+    }
+}
+
+pub(crate) async fn get_turns(ctx: &HandlerContext<'_>, session_id: String) {
+    match list_turns(session_id.clone()).await {
+        Ok(turns) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::TurnsList { session_id, turns },
+            )
+            .await;
+        }
+        Err(e) => {
+            send_json(
+                ctx.client_tx,
+                ServerMessage::Error {
+                    code: "db_error".to_string(),
+                    message: e.to_string(),
+                    session_id: Some(session_id),
+                    error: None,
+                },
+            )
+            .await;
+        }
+    }
+}